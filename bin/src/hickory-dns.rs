@@ -20,6 +20,7 @@
 //!    -z DIR, --zonedir=DIR   Path to the root directory for all zone files, see also config toml
 //!    -p PORT, --port=PORT    Override the listening port
 //!    --tls-port=PORT         Override the listening port for TLS connections
+//!    -t, --check-config      Validate the configuration, zone files and certificates, then exit
 //! ```
 
 // BINARY WARNINGS
@@ -36,15 +37,20 @@
 #![recursion_limit = "128"]
 #![allow(clippy::redundant_clone)]
 
+#[cfg(unix)]
+use std::fs;
 use std::{
     env, fmt,
     net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs},
     path::{Path, PathBuf},
+    process,
     sync::Arc,
 };
 
 use clap::Parser;
 use time::OffsetDateTime;
+#[cfg(unix)]
+use tokio::signal::unix::{signal, SignalKind};
 use tokio::{
     net::{TcpListener, UdpSocket},
     runtime,
@@ -75,6 +81,8 @@ use hickory_server::{
         StoreConfig,
     },
 };
+#[cfg(unix)]
+use hickory_server::{proto::udp::UdpSendCounters, server::ResponseBufferCounters, support};
 
 #[cfg(feature = "dnssec")]
 use {hickory_client::rr::rdata::key::KeyUsage, hickory_server::authority::DnssecAuthority};
@@ -122,12 +130,58 @@ where
             }
         }
 
+        authority
+            .set_signature_timing(zone_config.signature_timing())
+            .await
+            .expect("failed to set signature timing");
+
         info!("signing zone: {}", zone_config.get_zone()?);
         authority.secure_zone().await.expect("failed to sign zone");
     }
     Ok(())
 }
 
+/// How often signed zones are checked for expiring signatures
+#[cfg(feature = "dnssec")]
+const RESIGN_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// Signs again the RRsets of a signed zone whose signatures are expiring, every `RESIGN_INTERVAL`
+#[cfg(feature = "dnssec")]
+fn spawn_resigner<A, L>(authority: &Arc<A>, zone_config: &ZoneConfig)
+where
+    A: DnssecAuthority<Lookup = L> + 'static,
+    L: Send + Sync + Sized + 'static,
+{
+    if !zone_config.is_dnssec_enabled() {
+        return;
+    }
+
+    let authority = Arc::clone(authority);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(RESIGN_INTERVAL);
+        // the zone was just signed, the first tick completes immediately
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            match authority.resign_expiring().await {
+                Ok(0) => debug!("no expiring signatures in zone: {}", authority.origin()),
+                Ok(count) => info!(
+                    "signed {count} RRsets with expiring signatures in zone: {}",
+                    authority.origin()
+                ),
+                Err(e) => error!(
+                    "failed to sign expiring RRsets in zone {}: {e}",
+                    authority.origin()
+                ),
+            }
+        }
+    });
+}
+
+#[cfg(not(feature = "dnssec"))]
+fn spawn_resigner<T>(_authority: &Arc<T>, _zone_config: &ZoneConfig) {}
+
 #[cfg(not(feature = "dnssec"))]
 #[allow(clippy::unnecessary_wraps)]
 async fn load_keys<T>(
@@ -178,7 +232,9 @@ async fn load_zone(
 
             // load any keys for the Zone, if it is a dynamic update zone, then keys are required
             load_keys(&mut authority, zone_name_for_signer, zone_config).await?;
-            Box::new(Arc::new(authority)) as Box<dyn AuthorityObject>
+            let authority = Arc::new(authority);
+            spawn_resigner(&authority, zone_config);
+            Box::new(authority) as Box<dyn AuthorityObject>
         }
         Some(StoreConfig::File(ref config)) => {
             if zone_path.is_some() {
@@ -195,7 +251,9 @@ async fn load_zone(
 
             // load any keys for the Zone, if it is a dynamic update zone, then keys are required
             load_keys(&mut authority, zone_name_for_signer, zone_config).await?;
-            Box::new(Arc::new(authority)) as Box<dyn AuthorityObject>
+            let authority = Arc::new(authority);
+            spawn_resigner(&authority, zone_config);
+            Box::new(authority) as Box<dyn AuthorityObject>
         }
         #[cfg(feature = "resolver")]
         Some(StoreConfig::Forward(ref config)) => {
@@ -241,7 +299,9 @@ async fn load_zone(
 
             // load any keys for the Zone, if it is a dynamic update zone, then keys are required
             load_keys(&mut authority, zone_name_for_signer, zone_config).await?;
-            Box::new(Arc::new(authority)) as Box<dyn AuthorityObject>
+            let authority = Arc::new(authority);
+            spawn_resigner(&authority, zone_config);
+            Box::new(authority) as Box<dyn AuthorityObject>
         }
         None => {
             let config = FileConfig {
@@ -258,7 +318,9 @@ async fn load_zone(
 
             // load any keys for the Zone, if it is a dynamic update zone, then keys are required
             load_keys(&mut authority, zone_name_for_signer, zone_config).await?;
-            Box::new(Arc::new(authority)) as Box<dyn AuthorityObject>
+            let authority = Arc::new(authority);
+            spawn_resigner(&authority, zone_config);
+            Box::new(authority) as Box<dyn AuthorityObject>
         }
         Some(_) => {
             panic!("unrecognized authority type, check enabled features");
@@ -316,6 +378,79 @@ struct Cli {
     /// overrides any value in config file
     #[clap(long = "quic-port", value_name = "QUIC-PORT")]
     pub(crate) quic_port: Option<u16>,
+
+    /// Validate the configuration file, all zone files and any certificate files, then exit
+    /// without starting the server, exits non-zero if any check fails
+    #[clap(short = 't', long = "check-config")]
+    pub(crate) check_config: bool,
+}
+
+/// Loads the configuration, every zone, and the TLS certificate, without binding any sockets
+fn check_config(args: &Cli) -> Result<(), String> {
+    let config_path = args.config.as_path();
+    info!("checking configuration from: {:?}", config_path);
+    let config = Config::read_config(config_path)
+        .map_err(|e| format!("could not read config {}: {:?}", config_path.display(), e))?;
+    let zone_dir: PathBuf = args
+        .zonedir
+        .clone()
+        .unwrap_or_else(|| config.get_directory().to_path_buf());
+
+    let runtime = runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("failed to initialize Tokio Runtime: {e}"))?;
+    for zone in config.get_zones() {
+        let zone_name = zone
+            .get_zone()
+            .map_err(|e| format!("bad zone name in {config_path:?}: {e}"))?;
+
+        runtime
+            .block_on(load_zone(&zone_dir, zone))
+            .map_err(|e| format!("could not load zone {zone_name}: {e}"))?;
+    }
+
+    config
+        .get_rewrite()
+        .to_rules()
+        .map_err(|e| format!("bad rewrite rule in {config_path:?}: {e}"))?;
+    config
+        .get_listen_addrs_ipv4()
+        .map_err(|e| format!("error parsing configured Ipv4 addresses: {e}"))?;
+    config
+        .get_listen_addrs_ipv6()
+        .map_err(|e| format!("error parsing configured Ipv6 addresses: {e}"))?;
+
+    if let Some(tls_cert_config) = config.get_tls_cert() {
+        check_tls_cert(&zone_dir, tls_cert_config)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "dns-over-tls")]
+fn check_tls_cert(zone_dir: &Path, tls_cert_config: &TlsCertConfig) -> Result<(), String> {
+    dnssec::load_cert(zone_dir, tls_cert_config)
+        .map(drop)
+        .map_err(|e| format!("error loading tls certificate file: {e}"))
+}
+
+#[cfg(not(feature = "dns-over-tls"))]
+fn check_tls_cert(
+    zone_dir: &Path,
+    tls_cert_config: &hickory_server::config::dnssec::TlsCertConfig,
+) -> Result<(), String> {
+    // without TLS support the certificate can't be parsed, but it should at least be readable
+    let paths = Some(tls_cert_config.get_path())
+        .into_iter()
+        .chain(tls_cert_config.get_private_key());
+    for path in paths {
+        let path = zone_dir.join(path);
+        std::fs::File::open(&path)
+            .map_err(|e| format!("could not read {}: {e}", path.display()))?;
+    }
+
+    Ok(())
 }
 
 /// Main method for running the named server.
@@ -333,6 +468,19 @@ fn main() {
         default();
     }
 
+    if args.check_config {
+        match check_config(&args) {
+            Ok(()) => {
+                info!("configuration is valid: {}", args.config.display());
+                process::exit(0);
+            }
+            Err(e) => {
+                error!("configuration is invalid: {e}");
+                process::exit(1);
+            }
+        }
+    }
+
     info!("Hickory DNS {} starting", hickory_client::version());
     // start up the server for listening
 
@@ -356,6 +504,8 @@ fn main() {
         .build()
         .expect("failed to initialize Tokio Runtime");
     let mut catalog: Catalog = Catalog::new();
+    #[cfg_attr(not(unix), allow(unused_mut, unused_variables))]
+    let mut authorities: Vec<Box<dyn AuthorityObject>> = Vec::new();
     // configure our server based on the config_path
     for zone in config.get_zones() {
         let zone_name = zone
@@ -363,11 +513,23 @@ fn main() {
             .unwrap_or_else(|_| panic!("bad zone name in {:?}", config_path));
 
         match runtime.block_on(load_zone(&zone_dir, zone)) {
-            Ok(authority) => catalog.upsert(zone_name.into(), authority),
+            Ok(authority) => {
+                authorities.push(authority.box_clone());
+                if let Some(factor) = zone.get_ttl_scale_factor() {
+                    catalog.set_ttl_scale_factor(zone_name.clone().into(), factor);
+                }
+                catalog.upsert(zone_name.into(), authority);
+            }
             Err(error) => panic!("could not load zone {}: {}", zone_name, error),
         }
     }
 
+    let rewrite_rules = config
+        .get_rewrite()
+        .to_rules()
+        .unwrap_or_else(|e| panic!("bad rewrite rule in {:?}: {}", config_path, e));
+    catalog.set_rewrite_rules(rewrite_rules);
+
     // TODO: support all the IPs asked to listen on...
     // TODO:, there should be the option to listen on any port, IP and protocol option...
     let v4addr = config
@@ -476,6 +638,19 @@ fn main() {
         );
     }
 
+    // write a support bundle whenever we receive a SIGUSR1
+    #[cfg(unix)]
+    runtime.spawn(write_support_bundle_on_signal(
+        args.config.clone(),
+        authorities.iter().map(|a| a.box_clone()).collect(),
+        server.response_buffer_counters(),
+        server.udp_send_counters(),
+    ));
+
+    // reload the zones from disk whenever we receive a SIGHUP
+    #[cfg(unix)]
+    runtime.spawn(reload_zones_on_hangup(authorities));
+
     // config complete, starting!
     banner();
     info!("awaiting connections...");
@@ -502,6 +677,101 @@ fn main() {
     };
 }
 
+/// Waits for SIGHUP signals, reloading all zones each time one is received
+#[cfg(unix)]
+async fn reload_zones_on_hangup(authorities: Vec<Box<dyn AuthorityObject>>) {
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            error!("could not register SIGHUP handler, zones will not be reloaded: {e}");
+            return;
+        }
+    };
+
+    while hangup.recv().await.is_some() {
+        info!("received SIGHUP, reloading zones");
+        reload_zones(&authorities).await;
+    }
+}
+
+/// Reloads all zones, any zone that fails to load keeps serving its previous data
+#[cfg(unix)]
+async fn reload_zones(authorities: &[Box<dyn AuthorityObject>]) {
+    for authority in authorities {
+        match authority.reload_zone().await {
+            Ok(true) => info!("reloaded zone: {}", authority.origin()),
+            Ok(false) => debug!("zone can not be reloaded: {}", authority.origin()),
+            Err(e) => error!(
+                "failed to reload zone {}, keeping existing data: {e}",
+                authority.origin()
+            ),
+        }
+    }
+}
+
+/// Waits for SIGUSR1 signals, writing a support bundle to the temporary directory each time one
+///  is received
+#[cfg(unix)]
+async fn write_support_bundle_on_signal(
+    config_path: PathBuf,
+    authorities: Vec<Box<dyn AuthorityObject>>,
+    response_buffer_counters: Arc<ResponseBufferCounters>,
+    udp_send_counters: Arc<UdpSendCounters>,
+) {
+    let mut user_defined = match signal(SignalKind::user_defined1()) {
+        Ok(user_defined) => user_defined,
+        Err(e) => {
+            error!("could not register SIGUSR1 handler, support bundles are disabled: {e}");
+            return;
+        }
+    };
+
+    while user_defined.recv().await.is_some() {
+        info!("received SIGUSR1, writing support bundle");
+
+        let config = fs::read_to_string(&config_path)
+            .map_err(|e| warn!("could not read config {}: {e}", config_path.display()))
+            .ok();
+        let counters = [
+            (
+                "response_buffer_throttled",
+                response_buffer_counters.throttled(),
+            ),
+            (
+                "response_buffer_drain_timeouts",
+                response_buffer_counters.drain_timeouts(),
+            ),
+            ("response_buffer_shed", response_buffer_counters.shed()),
+            ("udp_truncated", udp_send_counters.truncated()),
+            ("udp_dropped", udp_send_counters.dropped()),
+        ];
+        let authorities = authorities.iter().map(|a| &**a).collect::<Vec<_>>();
+
+        let mut bundle = Vec::new();
+        if let Err(e) =
+            support::write_support_bundle(&mut bundle, config.as_deref(), &counters, &authorities)
+                .await
+        {
+            error!("failed to write support bundle: {e}");
+            continue;
+        }
+
+        let path = env::temp_dir().join(format!(
+            "hickory-dns-support-{}.txt",
+            OffsetDateTime::now_utc().unix_timestamp()
+        ));
+        let written = {
+            let path = path.clone();
+            tokio::task::spawn_blocking(move || fs::write(path, bundle)).await
+        };
+        match written {
+            Ok(Ok(())) => info!("wrote support bundle to {}", path.display()),
+            Ok(Err(e)) => error!("failed to write support bundle to {}: {e}", path.display()),
+            Err(e) => error!("failed to write support bundle: {e}"),
+        }
+    }
+}
+
 #[cfg(feature = "dns-over-tls")]
 fn config_tls(
     args: &Cli,
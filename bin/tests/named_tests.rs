@@ -23,7 +23,7 @@ use hickory_client::udp::UdpClientStream;
 use hickory_server::server::Protocol;
 
 use hickory_proto::iocompat::AsyncIoTokioAsStd;
-use server_harness::{named_test_harness, query_a, query_a_refused};
+use server_harness::{named_check_config, named_test_harness, query_a, query_a_refused};
 
 #[test]
 fn test_example_toml_startup() {
@@ -57,6 +57,21 @@ fn test_example_toml_startup() {
     })
 }
 
+#[test]
+fn test_example_toml_check_config() {
+    assert!(named_check_config("example.toml"));
+}
+
+#[test]
+fn test_check_config_fails_on_missing_zone_file() {
+    assert!(!named_check_config("example_missing_zone.toml"));
+}
+
+#[test]
+fn test_check_config_fails_on_missing_config() {
+    assert!(!named_check_config("does_not_exist.toml"));
+}
+
 #[test]
 fn test_ipv4_only_toml_startup() {
     named_test_harness("ipv4_only.toml", |socket_ports| {
@@ -74,6 +74,24 @@ fn collect_and_print<R: BufRead>(read: &mut R, output: &mut String) {
     }
 }
 
+/// Runs the Server with `--check-config`, returning true if the configuration was valid
+#[allow(dead_code)]
+pub fn named_check_config(toml: &str) -> bool {
+    let server_path = env::var("TDNS_WORKSPACE_ROOT").unwrap_or_else(|_| "..".to_owned());
+
+    Command::new(env!("CARGO_BIN_EXE_hickory-dns"))
+        .arg("--check-config")
+        .arg(format!(
+            "--config={server_path}/tests/test-data/test_configs/{toml}"
+        ))
+        .arg(format!(
+            "--zonedir={server_path}/tests/test-data/test_configs"
+        ))
+        .status()
+        .expect("failed to run named")
+        .success()
+}
+
 /// Spins up a Server and handles shutting it down after running the test
 #[allow(dead_code)]
 pub fn named_test_harness<F, R>(toml: &str, test: F)
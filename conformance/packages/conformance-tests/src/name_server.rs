@@ -1,3 +1,4 @@
+mod delegation;
 mod rfc4035;
 mod rfc5155;
 mod scenarios;
@@ -0,0 +1,88 @@
+//! delegation consistency checks with the `delegation-check` utility
+//!
+//! `delegation-check` is part of Hickory so the tests are skipped for other implementations. the
+//! name servers under test are the peer's
+
+use std::net::Ipv4Addr;
+
+use dns_test::delegation_check::DelegationCheck;
+use dns_test::name_server::NameServer;
+use dns_test::record::Record;
+use dns_test::{Implementation, Network, Result, FQDN};
+
+const GLUE_IPV4_ADDR: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 99);
+
+#[test]
+fn consistent_delegation() -> Result<()> {
+    let Implementation::Hickory(repository) = &*dns_test::SUBJECT else {
+        return Ok(());
+    };
+
+    let network = Network::new()?;
+    let child_ns = NameServer::new(&dns_test::PEER, FQDN::NAMESERVERS, &network)?;
+    let mut com_ns = NameServer::new(&dns_test::PEER, FQDN::COM, &network)?;
+    com_ns.referral_nameserver(&child_ns);
+
+    let child_ns = child_ns.start()?;
+    let com_ns = com_ns.start()?;
+
+    let checker = DelegationCheck::new(repository, &network)?;
+    let output = checker.run(&FQDN::NAMESERVERS, com_ns.ipv4_addr())?;
+
+    assert!(output.is_consistent(), "{:?}", output.findings);
+    assert_eq!(vec![child_ns.fqdn().clone()], output.parent_ns);
+    assert_eq!(vec![child_ns.fqdn().clone()], output.child_ns);
+
+    Ok(())
+}
+
+#[test]
+fn inconsistent_delegation() -> Result<()> {
+    let Implementation::Hickory(repository) = &*dns_test::SUBJECT else {
+        return Ok(());
+    };
+
+    let network = Network::new()?;
+    let mut child_ns = NameServer::new(&dns_test::PEER, FQDN::NAMESERVERS, &network)?;
+    // never started, so it's lame and its zone can't list it
+    let lame_ns = NameServer::new(&dns_test::PEER, FQDN::NAMESERVERS, &network)?;
+    let extra_ns = FQDN("extra.nameservers.com.")?;
+    child_ns
+        .add(Record::ns(FQDN::NAMESERVERS, extra_ns.clone()))
+        .add(Record::a(extra_ns.clone(), child_ns.ipv4_addr()));
+
+    let mut com_ns = NameServer::new(&dns_test::PEER, FQDN::COM, &network)?;
+    com_ns
+        .referral_nameserver(&child_ns)
+        .referral_nameserver(&lame_ns)
+        .add(Record::a(child_ns.fqdn().clone(), GLUE_IPV4_ADDR));
+
+    let child_ns = child_ns.start()?;
+    let com_ns = com_ns.start()?;
+
+    let checker = DelegationCheck::new(repository, &network)?;
+    let output = checker.run(&FQDN::NAMESERVERS, com_ns.ipv4_addr())?;
+
+    assert!(!output.is_consistent());
+    let findings = &output.findings;
+    let contains = |expected: &str| findings.iter().any(|finding| finding.starts_with(expected));
+
+    assert!(
+        contains(&format!("missing at parent: {extra_ns}")),
+        "{findings:?}"
+    );
+    assert!(
+        contains(&format!("missing at child: {}", lame_ns.fqdn())),
+        "{findings:?}"
+    );
+    assert!(
+        contains(&format!("lame server: {}", lame_ns.fqdn())),
+        "{findings:?}"
+    );
+    assert!(
+        contains(&format!("glue mismatch: {}", child_ns.fqdn())),
+        "{findings:?}"
+    );
+
+    Ok(())
+}
@@ -1,3 +1,4 @@
 //! plain DNS functionality
 
+mod deleg;
 mod scenarios;
@@ -0,0 +1,153 @@
+//! experimental DELEG delegations (draft-ietf-dnsop-deleg)
+//!
+//! only Hickory supports DELEG, built with the `unstable-deleg` feature, so the tests are skipped
+//! for other implementations. the `com.` zone, which carries the DELEG records, is served by the
+//! subject as well because other name servers answer queries at a delegation point with a referral
+
+use std::net::Ipv4Addr;
+
+use dns_test::client::{Client, DigOutput, DigSettings};
+use dns_test::name_server::{NameServer, Running, Stopped};
+use dns_test::record::{Record, RecordType};
+use dns_test::tshark::{Capture, Direction};
+use dns_test::{Network, Resolver, Result, FQDN};
+
+const NS_IPV4_ADDR: Ipv4Addr = Ipv4Addr::new(1, 1, 1, 1);
+const DELEG_IPV4_ADDR: Ipv4Addr = Ipv4Addr::new(2, 2, 2, 2);
+
+#[test]
+fn follows_deleg_over_ns() -> Result<()> {
+    if !dns_test::SUBJECT.is_hickory() {
+        return Ok(());
+    }
+
+    let network = Network::new()?;
+    let needle_fqdn = FQDN("example.nameservers.com.")?;
+
+    let mut ns_ns = NameServer::new(&dns_test::PEER, FQDN::NAMESERVERS, &network)?;
+    ns_ns.add(Record::a(needle_fqdn.clone(), NS_IPV4_ADDR));
+    let mut deleg_ns = NameServer::new(&dns_test::PEER, FQDN::NAMESERVERS, &network)?;
+    deleg_ns.add(Record::a(needle_fqdn.clone(), DELEG_IPV4_ADDR));
+
+    let deleg_addr = deleg_ns.ipv4_addr();
+    let fixture = Fixture::start(&network, ns_ns, deleg_ns.fqdn().clone(), deleg_addr)?;
+    let _deleg_ns = deleg_ns.start()?;
+
+    let mut tshark = fixture.resolver.eavesdrop()?;
+    let output = fixture.dig(&network, &needle_fqdn)?;
+    tshark.wait_for_capture()?;
+    let captures = tshark.terminate()?;
+
+    // the answer comes from the name server of the DELEG record
+    assert!(output.status.is_noerror());
+    let [answer] = output.answer.try_into().unwrap();
+    let a = answer.try_into_a().unwrap();
+    assert_eq!(DELEG_IPV4_ADDR, a.ipv4_addr);
+
+    assert_ne!(0, count_needle_queries(&captures, deleg_addr));
+    assert_eq!(0, count_needle_queries(&captures, fixture.ns_addr));
+
+    Ok(())
+}
+
+#[test]
+fn falls_back_to_ns_when_deleg_target_is_unreachable() -> Result<()> {
+    if !dns_test::SUBJECT.is_hickory() {
+        return Ok(());
+    }
+
+    let network = Network::new()?;
+    let needle_fqdn = FQDN("example.nameservers.com.")?;
+
+    let mut ns_ns = NameServer::new(&dns_test::PEER, FQDN::NAMESERVERS, &network)?;
+    ns_ns.add(Record::a(needle_fqdn.clone(), NS_IPV4_ADDR));
+    // never started, so nothing answers on its address
+    let deleg_ns = NameServer::new(&dns_test::PEER, FQDN::NAMESERVERS, &network)?;
+
+    let deleg_addr = deleg_ns.ipv4_addr();
+    let fixture = Fixture::start(&network, ns_ns, deleg_ns.fqdn().clone(), deleg_addr)?;
+
+    let mut tshark = fixture.resolver.eavesdrop()?;
+    let output = fixture.dig(&network, &needle_fqdn)?;
+    tshark.wait_for_capture()?;
+    let captures = tshark.terminate()?;
+
+    assert!(output.status.is_noerror());
+    let [answer] = output.answer.try_into().unwrap();
+    let a = answer.try_into_a().unwrap();
+    assert_eq!(NS_IPV4_ADDR, a.ipv4_addr);
+
+    assert_ne!(0, count_needle_queries(&captures, deleg_addr));
+    assert_ne!(0, count_needle_queries(&captures, fixture.ns_addr));
+
+    Ok(())
+}
+
+struct Fixture {
+    resolver: Resolver,
+    ns_addr: Ipv4Addr,
+    _nameservers: Vec<NameServer<Running>>,
+}
+
+impl Fixture {
+    /// starts the `nameservers.com.` name server of the NS records, a `com.` name server that
+    /// delegates `nameservers.com.` with both NS and DELEG records, a root name server and the
+    /// resolver
+    fn start(
+        network: &Network,
+        ns_ns: NameServer<Stopped>,
+        deleg_target: FQDN,
+        deleg_addr: Ipv4Addr,
+    ) -> Result<Self> {
+        let mut com_ns = NameServer::new(&dns_test::SUBJECT, FQDN::COM, network)?;
+        com_ns.referral_nameserver(&ns_ns).add(Record::deleg(
+            FQDN::NAMESERVERS,
+            deleg_target,
+            vec![deleg_addr],
+        ));
+
+        let mut root_ns = NameServer::new(&dns_test::PEER, FQDN::ROOT, network)?;
+        root_ns.referral_nameserver(&com_ns);
+        let root = root_ns.root_hint();
+
+        let ns_addr = ns_ns.ipv4_addr();
+        let nameservers = vec![ns_ns.start()?, com_ns.start()?, root_ns.start()?];
+
+        let resolver = Resolver::new(network, root).start(&dns_test::SUBJECT)?;
+
+        Ok(Self {
+            resolver,
+            ns_addr,
+            _nameservers: nameservers,
+        })
+    }
+
+    fn dig(&self, network: &Network, needle_fqdn: &FQDN) -> Result<DigOutput> {
+        let client = Client::new(network)?;
+        let settings = *DigSettings::default().recurse();
+        client.dig(
+            settings,
+            self.resolver.ipv4_addr(),
+            RecordType::A,
+            needle_fqdn,
+        )
+    }
+}
+
+fn count_needle_queries(captures: &[Capture], destination: Ipv4Addr) -> usize {
+    captures
+        .iter()
+        .filter(|capture| {
+            matches!(capture.direction, Direction::Outgoing { destination: to } if to == destination)
+        })
+        .filter(|capture| {
+            capture.message.as_value()["Queries"]
+                .as_object()
+                .map_or(false, |queries| {
+                    queries
+                        .keys()
+                        .any(|query| query.contains("example.nameservers.com: type A"))
+                })
+        })
+        .count()
+}
@@ -0,0 +1,106 @@
+//! the `delegation-check` utility of `hickory-util`
+
+use std::net::Ipv4Addr;
+
+use crate::container::{Container, Image, Network};
+use crate::{Repository, Result, FQDN};
+
+/// runs `delegation-check` in a container of the hickory image, the only image that includes it
+pub struct DelegationCheck {
+    inner: Container,
+}
+
+impl DelegationCheck {
+    pub fn new(repository: &Repository<'static>, network: &Network) -> Result<Self> {
+        Ok(Self {
+            inner: Container::run(&Image::Hickory(repository.clone()), network)?,
+        })
+    }
+
+    /// checks the delegation of `zone` in the zone served by the `parent` name server
+    pub fn run(&self, zone: &FQDN, parent: Ipv4Addr) -> Result<DelegationCheckOutput> {
+        let parent = parent.to_string();
+        // `CLICOLOR=0` keeps the output free of escape codes
+        let output = self.inner.output(&[
+            "env",
+            "CLICOLOR=0",
+            "delegation-check",
+            "--timeout",
+            "2",
+            "--parent",
+            &parent,
+            zone.as_str(),
+        ])?;
+
+        if !output.status.success() && output.stdout.is_empty() {
+            return Err(format!("`delegation-check` failed: {}", output.stderr).into());
+        }
+
+        output.stdout.parse()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DelegationCheckOutput {
+    pub parent_ns: Vec<FQDN>,
+    pub child_ns: Vec<FQDN>,
+    /// one line per finding, e.g. `missing at parent: ns.example.com.`
+    pub findings: Vec<String>,
+}
+
+impl DelegationCheckOutput {
+    pub fn is_consistent(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+impl core::str::FromStr for DelegationCheckOutput {
+    type Err = crate::Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let mut output = Self::default();
+        for line in input.lines().map(str::trim) {
+            if let Some(name) = line.strip_prefix("parent NS ") {
+                output.parent_ns.push(name.parse()?);
+            } else if let Some(name) = line.strip_prefix("child NS ") {
+                output.child_ns.push(name.parse()?);
+            } else if !(line.is_empty() || line.starts_with("zone ") || line == "consistent") {
+                output.findings.push(line.to_string());
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() -> Result<()> {
+        let input = "zone nameservers.com.\r
+parent NS primary1.nameservers.com.\r
+parent NS primary2.nameservers.com.\r
+child NS primary1.nameservers.com.\r
+missing at child: primary2.nameservers.com.\r
+";
+        let output: DelegationCheckOutput = input.parse()?;
+
+        assert_eq!(
+            vec![
+                FQDN("primary1.nameservers.com.")?,
+                FQDN("primary2.nameservers.com.")?
+            ],
+            output.parent_ns
+        );
+        assert_eq!(vec![FQDN("primary1.nameservers.com.")?], output.child_ns);
+        assert_eq!(
+            vec!["missing at child: primary2.nameservers.com.".to_string()],
+            output.findings
+        );
+        assert!(!output.is_consistent());
+
+        Ok(())
+    }
+}
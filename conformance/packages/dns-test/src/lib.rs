@@ -12,6 +12,7 @@ pub use crate::trust_anchor::TrustAnchor;
 
 pub mod client;
 mod container;
+pub mod delegation_check;
 mod fqdn;
 mod implementation;
 pub mod name_server;
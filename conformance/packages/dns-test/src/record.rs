@@ -53,6 +53,7 @@ record_types!(A, AAAA, DNSKEY, DS, MX, NS, NSEC3, NSEC3PARAM, RRSIG, SOA, TXT);
 #[allow(clippy::upper_case_acronyms)]
 pub enum Record {
     A(A),
+    DELEG(DELEG),
     DNSKEY(DNSKEY),
     DS(DS),
     NS(NS),
@@ -68,6 +69,12 @@ impl From<NSEC3> for Record {
     }
 }
 
+impl From<DELEG> for Record {
+    fn from(v: DELEG) -> Self {
+        Self::DELEG(v)
+    }
+}
+
 impl From<DNSKEY> for Record {
     fn from(v: DNSKEY) -> Self {
         Self::DNSKEY(v)
@@ -143,6 +150,17 @@ impl Record {
         .into()
     }
 
+    pub fn deleg(zone: FQDN, target: FQDN, ipv4_hint: Vec<Ipv4Addr>) -> Self {
+        DELEG {
+            zone,
+            ttl: DEFAULT_TTL,
+            priority: 1,
+            target,
+            ipv4_hint,
+        }
+        .into()
+    }
+
     pub fn try_into_ds(self) -> CoreResult<DS, Self> {
         if let Self::DS(v) = self {
             Ok(v)
@@ -171,6 +189,7 @@ impl FromStr for Record {
 
         let record = match record_type {
             "A" => Record::A(input.parse()?),
+            "DELEG" => Record::DELEG(input.parse()?),
             "DNSKEY" => Record::DNSKEY(input.parse()?),
             "DS" => Record::DS(input.parse()?),
             "NS" => Record::NS(input.parse()?),
@@ -189,6 +208,7 @@ impl fmt::Display for Record {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Record::A(a) => write!(f, "{a}"),
+            Record::DELEG(deleg) => write!(f, "{deleg}"),
             Record::DS(ds) => write!(f, "{ds}"),
             Record::DNSKEY(dnskey) => write!(f, "{dnskey}"),
             Record::NS(ns) => write!(f, "{ns}"),
@@ -243,6 +263,80 @@ impl fmt::Display for A {
     }
 }
 
+/// experimental extensible delegation record (draft-ietf-dnsop-deleg)
+///
+/// the record type has no assigned number so only name servers that know it under this name, i.e.
+/// Hickory built with the `unstable-deleg` feature, can load it from a zone file
+#[derive(Debug, Clone)]
+pub struct DELEG {
+    pub zone: FQDN,
+    pub ttl: u32,
+    pub priority: u16,
+    pub target: FQDN,
+    pub ipv4_hint: Vec<Ipv4Addr>,
+}
+
+impl FromStr for DELEG {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let mut columns = input.split_whitespace();
+
+        let [Some(zone), Some(ttl), Some(class), Some(record_type), Some(priority), Some(target)] =
+            array::from_fn(|_| columns.next())
+        else {
+            return Err("expected at least 6 columns".into());
+        };
+
+        check_record_type::<Self>(record_type)?;
+        check_class(class)?;
+
+        let mut ipv4_hint = vec![];
+        for param in columns {
+            let Some(addrs) = param.strip_prefix("ipv4hint=") else {
+                return Err(format!("unsupported DELEG parameter: {param}").into());
+            };
+
+            for addr in addrs.split(',') {
+                ipv4_hint.push(addr.parse()?);
+            }
+        }
+
+        Ok(Self {
+            zone: zone.parse()?,
+            ttl: ttl.parse()?,
+            priority: priority.parse()?,
+            target: target.parse()?,
+            ipv4_hint,
+        })
+    }
+}
+
+impl fmt::Display for DELEG {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            zone,
+            ttl,
+            priority,
+            target,
+            ipv4_hint,
+        } = self;
+
+        let record_type = unqualified_type_name::<Self>();
+        write!(
+            f,
+            "{zone}\t{ttl}\t{CLASS}\t{record_type}\t{priority} {target}"
+        )?;
+
+        for (index, addr) in ipv4_hint.iter().enumerate() {
+            let separator = if index == 0 { " ipv4hint=" } else { "," };
+            write!(f, "{separator}{addr}")?;
+        }
+
+        Ok(())
+    }
+}
+
 // integer types chosen based on bit sizes in section 2.1 of RFC4034
 #[derive(Clone, Debug)]
 pub struct DNSKEY {
@@ -810,6 +904,34 @@ mod tests {
         Ok(())
     }
 
+    const DELEG_INPUT: &str =
+        "example.com.	86400	IN	DELEG	1 ns1.example.com. ipv4hint=192.0.2.1,192.0.2.2";
+
+    #[test]
+    fn deleg() -> Result<()> {
+        let deleg @ DELEG {
+            zone,
+            ttl,
+            priority,
+            target,
+            ipv4_hint,
+        } = &DELEG_INPUT.parse()?;
+
+        assert_eq!("example.com.", zone.as_str());
+        assert_eq!(86400, *ttl);
+        assert_eq!(1, *priority);
+        assert_eq!("ns1.example.com.", target.as_str());
+        assert_eq!(
+            &[Ipv4Addr::new(192, 0, 2, 1), Ipv4Addr::new(192, 0, 2, 2)],
+            ipv4_hint.as_slice()
+        );
+
+        let output = deleg.to_string();
+        assert_eq!(DELEG_INPUT, output);
+
+        Ok(())
+    }
+
     // dig DNSKEY .
     const DNSKEY_INPUT: &str = ".	1116	IN	DNSKEY	257 3 8 AwEAAaz/tAm8yTn4Mfeh5eyI96WSVexTBAvkMgJzkKTOiW1vkIbzxeF3 +/4RgWOq7HrxRixHlFlExOLAJr5emLvN7SWXgnLh4+B5xQlNVz8Og8kv ArMtNROxVQuCaSnIDdD5LKyWbRd2n9WGe2R8PzgCmr3EgVLrjyBxWezF 0jLHwVN8efS3rCj/EWgvIWgb9tarpVUDK/b58Da+sqqls3eNbuv7pr+e oZG+SrDK6nWeL3c6H5Apxz7LjVc1uTIdsIXxuOLYA4/ilBmSVIzuDWfd RUfhHdY6+cn8HFRm+2hM8AnXGXws9555KrUB5qihylGa8subX2Nn6UwN R1AkUTV74bU=";
 
@@ -129,4 +129,8 @@ impl ConnectionProvider for AsyncStdConnectionProvider {
     ) -> Self::FutureConn {
         self.connection_provider.new_connection(config, options)
     }
+
+    fn spawn_bg(&self, future: Pin<Box<dyn Future<Output = ()> + Send>>) -> bool {
+        self.connection_provider.spawn_bg(future)
+    }
 }
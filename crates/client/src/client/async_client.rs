@@ -18,10 +18,14 @@ use futures_util::{
     stream::{Stream, StreamExt},
 };
 use rand;
+use tokio::time::Sleep;
 use tracing::debug;
 
 use crate::{
-    client::Signer,
+    client::{
+        zone_transfer_limits::{Throttle, ZoneTransferLimits},
+        Signer,
+    },
     error::*,
     op::{Message, MessageType, OpCode, Query},
     proto::{
@@ -34,7 +38,10 @@ use crate::{
         },
         TokioTime,
     },
-    rr::{rdata::SOA, DNSClass, Name, Record, RecordSet, RecordType},
+    rr::{
+        rdata::{opt::EdnsOption, SOA},
+        DNSClass, Name, Record, RecordSet, RecordType,
+    },
 };
 
 /// A DNS Client implemented over futures-rs.
@@ -596,11 +603,55 @@ pub trait ClientHandle: 'static + Clone + DnsHandle + Send {
         &mut self,
         zone_origin: Name,
         last_soa: Option<SOA>,
+    ) -> ClientStreamXfr<<Self as DnsHandle>::Response> {
+        self.zone_transfer_with_limits(zone_origin, last_soa, ZoneTransferLimits::default())
+    }
+
+    /// Same as [`Self::zone_transfer`], but aborts the transfer with an error once it exceeds the
+    /// `limits`, and throttles reading the messages to the configured rate.
+    ///
+    /// # Arguments
+    /// * `zone_origin` - the zone name to update, i.e. SOA name
+    /// * `last_soa` - the last SOA known, if any. If provided, name must match `zone_origin`
+    /// * `limits` - the size, rate and duration limits of the transfer
+    fn zone_transfer_with_limits(
+        &mut self,
+        zone_origin: Name,
+        last_soa: Option<SOA>,
+        limits: ZoneTransferLimits,
     ) -> ClientStreamXfr<<Self as DnsHandle>::Response> {
         let ixfr = last_soa.is_some();
         let message = update_message::zone_transfer(zone_origin, last_soa);
 
-        ClientStreamXfr::new(self.send(message), ixfr)
+        ClientStreamXfr::with_limits(self.send(message), ixfr, limits)
+    }
+
+    /// Queries the SOA of a zone with the EXPIRE EDNS option, as a secondary does to check whether
+    ///  its copy of the zone is current, [RFC 7314](https://tools.ietf.org/html/rfc7314)
+    ///
+    /// The remaining expire time of the zone is read from the response with
+    ///  [`crate::client::ZoneExpiry::expire_option`].
+    ///
+    /// # Arguments
+    /// * `zone_origin` - the zone name, i.e. SOA name
+    fn query_soa_with_expire(
+        &mut self,
+        zone_origin: Name,
+    ) -> ClientResponse<<Self as DnsHandle>::Response> {
+        let mut message = Message::new();
+        message
+            .set_id(rand::random())
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(false)
+            .add_query(Query::query(zone_origin, RecordType::SOA));
+
+        let edns = message.extensions_mut().get_or_insert_with(Edns::new);
+        edns.set_max_payload(update_message::MAX_PAYLOAD_LEN)
+            .set_version(0);
+        edns.options_mut().insert(EdnsOption::Expire(None));
+
+        ClientResponse(self.send(message))
     }
 }
 
@@ -653,17 +704,61 @@ where
     R: Stream<Item = Result<DnsResponse, ProtoError>> + Send + Unpin + 'static,
 {
     state: ClientStreamXfrState<R>,
+    limits: ZoneTransferLimits,
+    records: usize,
+    bytes: usize,
+    throttle: Option<Throttle>,
+    pause: Option<Pin<Box<Sleep>>>,
+    deadline: Option<Pin<Box<Sleep>>>,
 }
 
 impl<R> ClientStreamXfr<R>
 where
     R: Stream<Item = Result<DnsResponse, ProtoError>> + Send + Unpin + 'static,
 {
+    #[cfg(test)]
     fn new(inner: R, maybe_incr: bool) -> Self {
+        Self::with_limits(inner, maybe_incr, ZoneTransferLimits::default())
+    }
+
+    fn with_limits(inner: R, maybe_incr: bool, limits: ZoneTransferLimits) -> Self {
         Self {
             state: ClientStreamXfrState::Start { inner, maybe_incr },
+            limits,
+            records: 0,
+            bytes: 0,
+            throttle: None,
+            pause: None,
+            deadline: None,
         }
     }
+
+    /// Counts the response against the limits, and pauses reading if it exceeds the rate
+    fn count(&mut self, response: &DnsResponse) -> Result<(), ClientError> {
+        let bytes = response.as_buffer().len();
+        self.records += response.answers().len();
+        self.bytes += bytes;
+
+        if self.limits.exceeded(self.records, self.bytes) {
+            self.state = ClientStreamXfrState::Ended;
+            return Err(ClientErrorKind::ZoneTransferTooLarge {
+                records: self.records,
+                bytes: self.bytes,
+            }
+            .into());
+        }
+
+        if let Some(bytes_per_second) = self.limits.bytes_per_second {
+            let throttle = self
+                .throttle
+                .get_or_insert_with(|| Throttle::new(bytes_per_second));
+            if let Some(until) = throttle.take(bytes) {
+                self.pause = Some(Box::pin(tokio::time::sleep_until(until)));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// State machine for ClientStreamXfr, implementing almost all logic
@@ -789,9 +884,14 @@ impl<R> ClientStreamXfrState<R> {
                     }
                     1 => {
                         *self = Ended;
-                        match answers.last().map(|r| r.record_type()) {
-                            Some(RecordType::SOA) => Ok(()),
-                            _ => Err(ClientErrorKind::Message(
+                        match answers.last().and_then(get_serial) {
+                            Some(serial) if serial == expected_serial => Ok(()),
+                            Some(found) => Err(ClientErrorKind::ZoneTransferSerialMismatch {
+                                expected: expected_serial,
+                                found,
+                            }
+                            .into()),
+                            None => Err(ClientErrorKind::Message(
                                 "invalid zone transfer, contains trailing records",
                             )
                             .into()),
@@ -845,13 +945,34 @@ where
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         use ClientStreamXfrState::*;
 
-        if matches!(self.state, Ended) {
+        let this = &mut *self;
+        if matches!(this.state, Ended) {
             return Poll::Ready(None);
         }
 
-        let message = ready!(self.state.inner().poll_next_unpin(cx)).map(|response| {
+        if let Some(max_duration) = this.limits.max_duration {
+            // started on the first poll, as the stream may be created outside of the runtime
+            let deadline = this
+                .deadline
+                .get_or_insert_with(|| Box::pin(tokio::time::sleep(max_duration)));
+            if deadline.as_mut().poll(cx).is_ready() {
+                this.state = Ended;
+                return Poll::Ready(Some(Err(ClientErrorKind::ZoneTransferTimeout(
+                    max_duration,
+                )
+                .into())));
+            }
+        }
+
+        if let Some(pause) = &mut this.pause {
+            ready!(pause.as_mut().poll(cx));
+            this.pause = None;
+        }
+
+        let message = ready!(this.state.inner().poll_next_unpin(cx)).map(|response| {
             let ok = response?;
-            self.state.process(ok.answers())?;
+            this.count(&ok)?;
+            this.state.process(ok.answers())?;
             Ok(ok)
         });
         Poll::Ready(message)
@@ -1080,6 +1201,128 @@ mod tests {
         assert!(stream.next().await.is_none());
     }
 
+    #[tokio::test]
+    async fn test_stream_xfr_axfr_serial_mismatch() {
+        let stream = get_stream_testcase(vec![vec![soa_record(3), a_record(1), soa_record(4)]]);
+        let mut stream = ClientStreamXfr::new(stream, false);
+
+        let error = stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ClientErrorKind::ZoneTransferSerialMismatch {
+                expected: 3,
+                found: 4
+            }
+        ));
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_xfr_endless_axfr_record_limit() {
+        let mut ip = 0;
+        let stream = get_stream_testcase(vec![vec![soa_record(3)]]).chain(
+            futures_util::stream::repeat_with(move || {
+                ip += 1;
+                let mut message = Message::new();
+                message.add_answer(a_record(ip));
+                Ok(DnsResponse::from_message(message).unwrap())
+            }),
+        );
+        let limits = ZoneTransferLimits {
+            max_records: Some(10),
+            ..ZoneTransferLimits::default()
+        };
+        let mut stream = ClientStreamXfr::with_limits(stream, false, limits);
+
+        for _ in 0..10 {
+            stream.next().await.unwrap().unwrap();
+        }
+
+        let error = stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ClientErrorKind::ZoneTransferTooLarge { records: 11, .. }
+        ));
+        assert!(matches!(stream.state, Ended));
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_xfr_byte_limit() {
+        let stream = get_stream_testcase(vec![
+            vec![soa_record(3)],
+            vec![a_record(1)],
+            vec![soa_record(3)],
+        ]);
+        let limits = ZoneTransferLimits {
+            max_bytes: Some(100),
+            ..ZoneTransferLimits::default()
+        };
+        let mut stream = ClientStreamXfr::with_limits(stream, false, limits);
+
+        stream.next().await.unwrap().unwrap();
+        let error = stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ClientErrorKind::ZoneTransferTooLarge { records: 2, .. }
+        ));
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_xfr_throttled() {
+        const BYTES_PER_SECOND: u64 = 50;
+
+        let stream = get_stream_testcase(vec![
+            vec![soa_record(3)],
+            vec![a_record(1)],
+            vec![a_record(2)],
+            vec![soa_record(3)],
+        ]);
+        let limits = ZoneTransferLimits {
+            bytes_per_second: Some(BYTES_PER_SECOND),
+            ..ZoneTransferLimits::default()
+        };
+        let mut stream = ClientStreamXfr::with_limits(stream, false, limits);
+
+        let start = tokio::time::Instant::now();
+        let mut bytes = 0_u64;
+        while let Some(response) = stream.next().await {
+            // the last message is read once the bytes before it were allowed by the rate
+            let elapsed = start.elapsed().as_secs_f64();
+            let expected = bytes.saturating_sub(BYTES_PER_SECOND) as f64 / BYTES_PER_SECOND as f64;
+            assert!(
+                elapsed >= expected && elapsed < expected + 0.01,
+                "elapsed {elapsed}s, expected {expected}s"
+            );
+
+            bytes += response.unwrap().as_buffer().len() as u64;
+        }
+
+        assert!(bytes > 3 * BYTES_PER_SECOND);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_xfr_max_duration() {
+        let stream =
+            get_stream_testcase(vec![vec![soa_record(3)]]).chain(futures_util::stream::pending());
+        let limits = ZoneTransferLimits {
+            max_duration: Some(Duration::from_secs(5)),
+            ..ZoneTransferLimits::default()
+        };
+        let mut stream = ClientStreamXfr::with_limits(stream, false, limits);
+
+        let start = tokio::time::Instant::now();
+        stream.next().await.unwrap().unwrap();
+        let error = stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ClientErrorKind::ZoneTransferTimeout(_)
+        ));
+        assert_eq!(start.elapsed(), Duration::from_secs(5));
+        assert!(stream.next().await.is_none());
+    }
+
     #[tokio::test]
     async fn async_client() {
         use crate::client::{AsyncClient, ClientHandle};
@@ -20,7 +20,7 @@ use futures_util::stream::{Stream, StreamExt};
 use tokio::runtime::{self, Runtime};
 
 use crate::client::async_client::ClientStreamXfr;
-use crate::client::{AsyncClient, ClientConnection, ClientHandle, Signer};
+use crate::client::{AsyncClient, ClientConnection, ClientHandle, Signer, ZoneTransferLimits};
 use crate::error::*;
 use crate::proto::{
     error::ProtoError,
@@ -430,6 +430,22 @@ pub trait Client {
             runtime,
         })
     }
+
+    /// Same as [`Self::zone_transfer`], but aborts the transfer with an error once it exceeds the
+    /// `limits`, see [`ClientHandle::zone_transfer_with_limits`].
+    fn zone_transfer_with_limits(
+        &self,
+        name: &Name,
+        last_soa: Option<SOA>,
+        limits: ZoneTransferLimits,
+    ) -> ClientResult<BlockingStream<ClientStreamXfr<<Self as Client>::Response>>> {
+        let (mut client, runtime) = self.spawn_client()?;
+
+        Ok(BlockingStream {
+            inner: client.zone_transfer_with_limits(name.clone(), last_soa, limits),
+            runtime,
+        })
+    }
 }
 
 /// The Client is abstracted over either hickory_client::tcp::TcpClientConnection or
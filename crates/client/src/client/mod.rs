@@ -25,6 +25,8 @@ mod client;
 pub mod client_connection;
 mod memoize_client_handle;
 mod rc_stream;
+mod zone_expiry;
+mod zone_transfer_limits;
 
 #[allow(deprecated)]
 pub use self::async_client::{AsyncClient, ClientFuture, ClientHandle, ClientStreamingResponse};
@@ -39,3 +41,5 @@ pub use self::client::{BlockingStream, Client, SyncClient};
 pub use self::client_connection::ClientConnection;
 pub use self::client_connection::Signer;
 pub use self::memoize_client_handle::MemoizeClientHandle;
+pub use self::zone_expiry::ZoneExpiry;
+pub use self::zone_transfer_limits::ZoneTransferLimits;
@@ -0,0 +1,174 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The expire timer of a secondary zone
+
+use std::time::{Duration, Instant};
+
+use crate::proto::op::Message;
+use crate::proto::rr::rdata::opt::{EdnsCode, EdnsOption};
+use crate::rr::rdata::SOA;
+
+/// The time after which a secondary stops answering for a zone it failed to refresh
+///
+/// Without the EXPIRE EDNS option, [RFC 7314](https://tools.ietf.org/html/rfc7314), the timer is
+///  reset to the full expire time of the SOA on each refresh, even when the primary it was
+///  transferred from is itself a secondary that hasn't reached its own primary for a while. With
+///  the option the remaining time reported by the server is used instead.
+///
+/// The current time is passed to every method, so that the timer doesn't depend on a clock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ZoneExpiry {
+    expires_at: Instant,
+}
+
+impl ZoneExpiry {
+    /// Starts the timer for a zone transferred at `now`
+    ///
+    /// # Arguments
+    ///
+    /// * `soa` - the SOA of the transferred zone
+    /// * `expire_option` - the value of the EXPIRE option of the response, see [`Self::expire_option`]
+    /// * `now` - the time the zone was transferred
+    pub fn new(soa: &SOA, expire_option: Option<u32>, now: Instant) -> Self {
+        Self {
+            expires_at: now + expire_time(soa, expire_option),
+        }
+    }
+
+    /// Restarts the timer after the zone was successfully refreshed at `now`
+    ///
+    /// A remaining time from the EXPIRE option only extends the timer, RFC 7314 section 4.
+    ///
+    /// # Arguments
+    ///
+    /// * `soa` - the SOA of the refreshed zone
+    /// * `expire_option` - the value of the EXPIRE option of the response, see [`Self::expire_option`]
+    /// * `now` - the time of the refresh
+    pub fn refreshed(&mut self, soa: &SOA, expire_option: Option<u32>, now: Instant) {
+        let expires_at = now + expire_time(soa, expire_option);
+        self.expires_at = match expire_option {
+            Some(_) => self.expires_at.max(expires_at),
+            None => expires_at,
+        };
+    }
+
+    /// The time remaining until the zone expires, zero once it has expired
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.expires_at.saturating_duration_since(now)
+    }
+
+    /// Returns true once the zone has expired
+    pub fn is_expired(&self, now: Instant) -> bool {
+        now >= self.expires_at
+    }
+
+    /// Returns the remaining expire time from the EXPIRE option of a response, in seconds
+    pub fn expire_option(response: &Message) -> Option<u32> {
+        match response.extensions().as_ref()?.option(EdnsCode::Expire)? {
+            EdnsOption::Expire(expire) => *expire,
+            _ => None,
+        }
+    }
+}
+
+fn expire_time(soa: &SOA, expire_option: Option<u32>) -> Duration {
+    let expire = expire_option.unwrap_or_else(|| soa.expire().unsigned_abs());
+    Duration::from_secs(expire.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::op::Edns;
+    use crate::rr::Name;
+
+    fn soa(expire: i32) -> SOA {
+        SOA::new(
+            Name::from_ascii("ns.example.com.").unwrap(),
+            Name::from_ascii("hostmaster.example.com.").unwrap(),
+            1,
+            3600,
+            600,
+            expire,
+            300,
+        )
+    }
+
+    fn response(expire: Option<u32>) -> Message {
+        let mut message = Message::new();
+        let edns = message.extensions_mut().get_or_insert_with(Edns::new);
+        if let Some(expire) = expire {
+            edns.options_mut().insert(EdnsOption::Expire(Some(expire)));
+        }
+        message
+    }
+
+    #[test]
+    fn test_expire_option() {
+        assert_eq!(ZoneExpiry::expire_option(&response(Some(3600))), Some(3600));
+        assert_eq!(ZoneExpiry::expire_option(&response(None)), None);
+        assert_eq!(ZoneExpiry::expire_option(&Message::new()), None);
+    }
+
+    #[test]
+    fn test_expiry_from_primary() {
+        let now = Instant::now();
+
+        // a primary reports the expire time of its SOA
+        let expiry = ZoneExpiry::new(&soa(86400), Some(86400), now);
+        assert_eq!(expiry.remaining(now), Duration::from_secs(86400));
+
+        // without the option the SOA is used
+        let expiry = ZoneExpiry::new(&soa(86400), None, now);
+        assert_eq!(expiry.remaining(now), Duration::from_secs(86400));
+        assert!(!expiry.is_expired(now + Duration::from_secs(86399)));
+        assert!(expiry.is_expired(now + Duration::from_secs(86400)));
+    }
+
+    #[test]
+    fn test_expiry_from_secondary() {
+        let now = Instant::now();
+
+        // an intermediate secondary which last refreshed an hour ago
+        let expiry = ZoneExpiry::new(&soa(86400), Some(82800), now);
+        assert_eq!(expiry.remaining(now), Duration::from_secs(82800));
+
+        // which propagates its remaining time down the chain
+        let later = now + Duration::from_secs(600);
+        let chained = ZoneExpiry::new(
+            &soa(86400),
+            Some(expiry.remaining(later).as_secs() as u32),
+            later,
+        );
+        assert_eq!(chained.remaining(later), Duration::from_secs(82200));
+        assert_eq!(chained, expiry);
+    }
+
+    #[test]
+    fn test_refresh_adjusts_timer() {
+        let start = Instant::now();
+        let mut expiry = ZoneExpiry::new(&soa(86400), Some(86400), start);
+
+        // the upstream secondary lost contact with its primary, the remaining time is smaller than
+        //  ours, so the timer is kept
+        let now = start + Duration::from_secs(3600);
+        expiry.refreshed(&soa(86400), Some(1800), now);
+        assert_eq!(expiry.remaining(now), Duration::from_secs(82800));
+
+        // the upstream was refreshed, the timer is extended to its remaining time
+        let now = start + Duration::from_secs(7200);
+        expiry.refreshed(&soa(86400), Some(86000), now);
+        assert_eq!(expiry.remaining(now), Duration::from_secs(86000));
+
+        // without the option the timer is reset to the SOA expire
+        let now = start + Duration::from_secs(10800);
+        expiry.refreshed(&soa(3600), None, now);
+        assert_eq!(expiry.remaining(now), Duration::from_secs(3600));
+        assert!(expiry.is_expired(now + Duration::from_secs(3600)));
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Safety limits for zone transfers
+
+use std::time::Duration;
+
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+/// Limits applied to a zone transfer, see [`crate::client::ClientHandle::zone_transfer_with_limits`]
+///
+/// A misconfigured or malicious primary can send an endless AXFR stream or an exploding IXFR, the
+///  transfer is aborted with an error once it exceeds any of these. All limits are disabled by
+///  default.
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde-config", serde(default))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ZoneTransferLimits {
+    /// The maximum number of records accepted, counting all answers of all messages
+    pub max_records: Option<usize>,
+    /// The maximum number of bytes accepted, counting the encoded messages
+    pub max_bytes: Option<usize>,
+    /// The rate in bytes per second to which reading the messages is throttled
+    ///
+    /// Up to one second worth of bytes can be read at once.
+    pub bytes_per_second: Option<u64>,
+    /// The maximum time from starting the transfer until it ends
+    pub max_duration: Option<Duration>,
+}
+
+impl ZoneTransferLimits {
+    /// Returns true if the counts exceed the `max_records` or `max_bytes` limits
+    pub(crate) fn exceeded(&self, records: usize, bytes: usize) -> bool {
+        self.max_records.map_or(false, |max| records > max)
+            || self.max_bytes.map_or(false, |max| bytes > max)
+    }
+}
+
+/// A token bucket of bytes, refilled at a constant rate
+pub(crate) struct Throttle {
+    bytes_per_second: f64,
+    tokens: f64,
+    last_update: Instant,
+}
+
+impl Throttle {
+    pub(crate) fn new(bytes_per_second: u64) -> Self {
+        // a rate of zero would never allow any message
+        let bytes_per_second = bytes_per_second.max(1) as f64;
+
+        Self {
+            bytes_per_second,
+            tokens: bytes_per_second,
+            last_update: Instant::now(),
+        }
+    }
+
+    /// Takes `bytes` from the bucket, returns until when reading must pause to stay within the rate
+    pub(crate) fn take(&mut self, bytes: usize) -> Option<Instant> {
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(self.last_update);
+        self.last_update = now;
+
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.bytes_per_second)
+            .min(self.bytes_per_second);
+        self.tokens -= bytes as f64;
+
+        if self.tokens >= 0.0 {
+            return None;
+        }
+
+        Some(now + Duration::from_secs_f64(-self.tokens / self.bytes_per_second))
+    }
+}
@@ -0,0 +1,473 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Probes for the EDNS compliance of a name server
+//!
+//! The probes are modeled on the [ISC EDNS compliance tests](https://gitlab.isc.org/isc-projects/DNS-Compliance-Testing),
+//!  each one sends a single query for the SOA of a zone the server is authoritative for, and checks
+//!  the response against [RFC 6891](https://tools.ietf.org/html/rfc6891) and the RFCs of the
+//!  probed options.
+//!
+//! ```rust,no_run
+//! use std::net::SocketAddr;
+//! use std::str::FromStr;
+//!
+//! use hickory_client::client::AsyncClient;
+//! use hickory_client::edns_compliance::{self, ProbeOptions};
+//! use hickory_client::rr::Name;
+//! use hickory_client::udp::UdpClientStream;
+//! use tokio::net::UdpSocket;
+//!
+//! # async fn probe() {
+//! let address = SocketAddr::from(([8, 8, 8, 8], 53));
+//! let stream = UdpClientStream::<UdpSocket>::new(address);
+//! let (client, bg) = AsyncClient::connect(stream).await.unwrap();
+//! tokio::spawn(bg);
+//!
+//! let name = Name::from_str("example.com.").unwrap();
+//! let report = edns_compliance::run_probes(client, name, &ProbeOptions::default()).await;
+//! println!("{report}");
+//! # }
+//! ```
+
+use std::fmt::{self, Display};
+use std::time::Duration;
+
+use futures_util::stream::{self, StreamExt};
+#[cfg(feature = "serde-config")]
+use serde::Serialize;
+use tokio::time::{self, Instant};
+
+use crate::proto::error::{ProtoError, ProtoErrorKind};
+use crate::proto::op::{Edns, Message, MessageType, OpCode, Query, ResponseCode};
+use crate::proto::rr::rdata::opt::{EdnsCode, EdnsOption};
+use crate::proto::rr::{Name, RecordType};
+use crate::proto::xfer::{DnsHandle, DnsResponse, FirstAnswer};
+
+/// An option code from the range reserved for local and experimental use, RFC 6891
+const UNKNOWN_OPTION: u16 = 65001;
+
+/// A Z flag without meaning, which servers must ignore and not echo
+const UNKNOWN_FLAG: u16 = 0x0080;
+
+/// The client cookie sent in the cookie probe, RFC 7873
+const CLIENT_COOKIE: [u8; 8] = *b"hickory!";
+
+/// A single probe of the EDNS compliance of a name server
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+#[cfg_attr(feature = "serde-config", serde(rename_all = "lowercase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Probe {
+    /// A plain DNS query, the response must not contain an OPT record
+    Dns,
+    /// An EDNS version 0 query, the response must contain an OPT record of version 0
+    Edns,
+    /// An EDNS version 1 query, the response must be BADVERS with an OPT record of version 0
+    Edns1,
+    /// An EDNS query with an unknown option, which must not be echoed in the response
+    EdnsOpt,
+    /// An EDNS query with an unknown flag, which must not be echoed in the response
+    EdnsFlags,
+    /// An EDNS query with the DO bit set, which must be copied to the response
+    Do,
+    /// An EDNS query with a client cookie, servers supporting cookies echo it with their own
+    Cookie,
+    /// An EDNS query with the TCP keepalive option, servers supporting it return their timeout
+    ///
+    /// Servers ignore the option over UDP, so this probe is only meaningful over TCP.
+    Keepalive,
+}
+
+impl Probe {
+    /// All probes, in the order they are run
+    pub const ALL: [Self; 8] = [
+        Self::Dns,
+        Self::Edns,
+        Self::Edns1,
+        Self::EdnsOpt,
+        Self::EdnsFlags,
+        Self::Do,
+        Self::Cookie,
+        Self::Keepalive,
+    ];
+
+    /// The name of the probe, as used by the ISC compliance tests
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Dns => "dns",
+            Self::Edns => "edns",
+            Self::Edns1 => "edns1",
+            Self::EdnsOpt => "ednsopt",
+            Self::EdnsFlags => "ednsflags",
+            Self::Do => "do",
+            Self::Cookie => "cookie",
+            Self::Keepalive => "keepalive",
+        }
+    }
+
+    /// The query sent for this probe
+    fn message(self, name: &Name, max_payload: u16) -> Message {
+        let mut message = Message::new();
+        message
+            .add_query(Query::query(name.clone(), RecordType::SOA))
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(false);
+
+        if self == Self::Dns {
+            return message;
+        }
+
+        let mut edns = Edns::new();
+        edns.set_max_payload(max_payload).set_version(0);
+        match self {
+            Self::Dns | Self::Edns => (),
+            Self::Edns1 => {
+                edns.set_version(1);
+            }
+            Self::EdnsOpt => edns
+                .options_mut()
+                .insert(EdnsOption::Unknown(UNKNOWN_OPTION, vec![])),
+            Self::EdnsFlags => {
+                edns.set_z_flags(UNKNOWN_FLAG);
+            }
+            Self::Do => {
+                edns.set_dnssec_ok(true);
+            }
+            Self::Cookie => edns
+                .options_mut()
+                .insert(EdnsOption::Cookie(CLIENT_COOKIE.to_vec())),
+            Self::Keepalive => edns.options_mut().insert(EdnsOption::Keepalive(None)),
+        }
+        message.set_edns(edns);
+
+        message
+    }
+
+    /// Checks the response to this probe
+    fn check(self, response: &Message) -> ProbeStatus {
+        let expected_code = match self {
+            Self::Edns1 => u16::from(ResponseCode::BADVERS),
+            _ => u16::from(ResponseCode::NoError),
+        };
+        if u16::from(response.response_code()) != expected_code {
+            return ProbeStatus::Broken;
+        }
+
+        let edns = match (self, response.extensions().as_ref()) {
+            (Self::Dns, None) => return ProbeStatus::Supported,
+            (Self::Dns, Some(_)) | (_, None) => return ProbeStatus::Broken,
+            (_, Some(edns)) => edns,
+        };
+        if edns.version() != 0 {
+            return ProbeStatus::Broken;
+        }
+
+        let compliant = match self {
+            Self::Dns | Self::Edns => true,
+            Self::Edns1 => response.answers().is_empty(),
+            Self::EdnsOpt => edns.option(EdnsCode::Unknown(UNKNOWN_OPTION)).is_none(),
+            Self::EdnsFlags => edns.z_flags() & UNKNOWN_FLAG == 0,
+            Self::Do => edns.dnssec_ok(),
+            Self::Cookie => match edns.option(EdnsCode::Cookie) {
+                None => return ProbeStatus::Unsupported,
+                // the client cookie followed by a server cookie of 8 to 32 bytes, RFC 7873
+                Some(EdnsOption::Cookie(cookie)) => {
+                    cookie.starts_with(&CLIENT_COOKIE) && (16..=40).contains(&cookie.len())
+                }
+                Some(_) => false,
+            },
+            Self::Keepalive => match edns.option(EdnsCode::Keepalive) {
+                None => return ProbeStatus::Unsupported,
+                Some(_) => true,
+            },
+        };
+
+        if compliant {
+            ProbeStatus::Supported
+        } else {
+            ProbeStatus::Broken
+        }
+    }
+}
+
+impl Display for Probe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The outcome of a probe
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+#[cfg_attr(feature = "serde-config", serde(rename_all = "lowercase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ProbeStatus {
+    /// The server handled the probe as required
+    Supported,
+    /// The server correctly responded, but doesn't support the optional feature probed
+    Unsupported,
+    /// The response violates the specification
+    Broken,
+    /// The server did not respond in time
+    Timeout,
+    /// The query failed for another reason, e.g. the connection was refused
+    Error,
+}
+
+impl ProbeStatus {
+    /// Returns true if this does not indicate a compliance problem
+    pub fn is_compliant(self) -> bool {
+        matches!(self, Self::Supported | Self::Unsupported)
+    }
+}
+
+impl Display for ProbeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Supported => "ok",
+            Self::Unsupported => "unsupported",
+            Self::Broken => "broken",
+            Self::Timeout => "timeout",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// A summary of the OPT record of a response
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EdnsSummary {
+    /// The EDNS version
+    pub version: u8,
+    /// The DO bit
+    pub dnssec_ok: bool,
+    /// The Z flags, without the DO bit
+    pub z_flags: u16,
+    /// The payload size advertised by the server
+    pub max_payload: u16,
+    /// The codes of the options, in the order of the response
+    pub options: Vec<u16>,
+}
+
+impl From<&Edns> for EdnsSummary {
+    fn from(edns: &Edns) -> Self {
+        Self {
+            version: edns.version(),
+            dnssec_ok: edns.dnssec_ok(),
+            z_flags: edns.z_flags(),
+            max_payload: edns.max_payload(),
+            options: edns
+                .options()
+                .as_ref()
+                .iter()
+                .map(|(code, _)| u16::from(*code))
+                .collect(),
+        }
+    }
+}
+
+/// A summary of the response to a probe
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResponseSummary {
+    /// The response code, including the high bits from the OPT record
+    pub response_code: u16,
+    /// True if the truncation flag was set
+    pub truncated: bool,
+    /// The number of records in the answer section
+    pub answer_count: usize,
+    /// The OPT record, if the response had one
+    pub edns: Option<EdnsSummary>,
+}
+
+impl From<&Message> for ResponseSummary {
+    fn from(message: &Message) -> Self {
+        Self {
+            response_code: message.response_code().into(),
+            truncated: message.truncated(),
+            answer_count: message.answers().len(),
+            edns: message.extensions().as_ref().map(EdnsSummary::from),
+        }
+    }
+}
+
+impl Display for ResponseSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let response_code: ResponseCode = self.response_code.into();
+        write!(f, "{response_code}")?;
+        if self.truncated {
+            write!(f, ", truncated")?;
+        }
+        write!(f, ", answers: {}", self.answer_count)?;
+
+        match &self.edns {
+            None => write!(f, ", no edns"),
+            Some(edns) => {
+                write!(
+                    f,
+                    ", edns version: {}, do: {}, z: {:#06x}, payload: {}",
+                    edns.version, edns.dnssec_ok, edns.z_flags, edns.max_payload,
+                )?;
+                if !edns.options.is_empty() {
+                    write!(f, ", options: {:?}", edns.options)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The result of a single probe
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProbeResult {
+    /// The probe that was run
+    pub probe: Probe,
+    /// The outcome of the probe
+    pub status: ProbeStatus,
+    /// A summary of the response, if any was received
+    pub response: Option<ResponseSummary>,
+    /// The error of the query, if it failed
+    pub error: Option<String>,
+}
+
+impl ProbeResult {
+    fn new(probe: Probe, response: Result<DnsResponse, ProtoError>) -> Self {
+        match response {
+            Ok(response) => Self {
+                probe,
+                status: probe.check(&response),
+                response: Some(ResponseSummary::from(&*response)),
+                error: None,
+            },
+            Err(e) => Self {
+                probe,
+                status: match e.kind() {
+                    ProtoErrorKind::Timeout => ProbeStatus::Timeout,
+                    _ => ProbeStatus::Error,
+                },
+                response: None,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+impl Display for ProbeResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.probe, self.status)?;
+        if let Some(response) = &self.response {
+            write!(f, " ({response})")?;
+        }
+        if let Some(error) = &self.error {
+            write!(f, " ({error})")?;
+        }
+        Ok(())
+    }
+}
+
+/// The results of all probes against a name server
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComplianceReport {
+    /// The zone that was queried
+    pub name: Name,
+    /// The payload size advertised by the server in response to an EDNS query
+    pub max_payload: Option<u16>,
+    /// The results, in the order of `Probe::ALL`
+    pub results: Vec<ProbeResult>,
+}
+
+impl ComplianceReport {
+    /// Returns the result of the `probe`
+    pub fn result(&self, probe: Probe) -> Option<&ProbeResult> {
+        self.results.iter().find(|result| result.probe == probe)
+    }
+
+    /// Returns true if no probe revealed a compliance problem
+    pub fn is_compliant(&self) -> bool {
+        self.results
+            .iter()
+            .all(|result| result.status.is_compliant())
+    }
+}
+
+impl Display for ComplianceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "; edns compliance of {}", self.name)?;
+        if let Some(max_payload) = self.max_payload {
+            writeln!(f, "; advertised payload size: {max_payload}")?;
+        }
+        for result in &self.results {
+            writeln!(f, "{result}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Options for running the probes
+#[derive(Clone, Copy, Debug)]
+pub struct ProbeOptions {
+    /// The maximum number of probes in flight at once
+    pub concurrency: usize,
+    /// The minimum time between starting two probes
+    pub interval: Duration,
+    /// The payload size advertised in the EDNS probes
+    pub max_payload: u16,
+}
+
+impl Default for ProbeOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            interval: Duration::from_millis(50),
+            max_payload: 1232,
+        }
+    }
+}
+
+/// Runs all probes against the name server behind `handle`
+///
+/// # Arguments
+///
+/// * `handle` - the connection to the name server, its timeout applies to every probe
+/// * `name` - a zone the name server is authoritative for
+/// * `options` - the concurrency and rate limit of the probes
+pub async fn run_probes<H: DnsHandle>(
+    handle: H,
+    name: Name,
+    options: &ProbeOptions,
+) -> ComplianceReport {
+    let start = Instant::now();
+    let results = stream::iter(Probe::ALL.into_iter().enumerate())
+        .map(|(i, probe)| {
+            let handle = handle.clone();
+            let message = probe.message(&name, options.max_payload);
+            let not_before = start + options.interval * i as u32;
+
+            async move {
+                time::sleep_until(not_before).await;
+                let response = handle.send(message).first_answer().await;
+                ProbeResult::new(probe, response)
+            }
+        })
+        .buffered(options.concurrency.max(1))
+        .collect::<Vec<_>>()
+        .await;
+
+    let max_payload = results
+        .iter()
+        .find(|result| result.probe == Probe::Edns && result.status == ProbeStatus::Supported)
+        .and_then(|result| result.response.as_ref()?.edns.as_ref())
+        .map(|edns| edns.max_payload);
+
+    ComplianceReport {
+        name,
+        max_payload,
+        results,
+    }
+}
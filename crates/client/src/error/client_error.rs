@@ -7,7 +7,7 @@
 
 //! Error types for the crate
 
-use std::{fmt, io};
+use std::{fmt, io, time::Duration};
 
 use futures_channel::mpsc;
 use thiserror::Error;
@@ -51,6 +51,28 @@ pub enum ErrorKind {
     /// A request timed out
     #[error("request timed out")]
     Timeout,
+
+    /// A zone transfer exceeded the record or byte limit and was aborted
+    #[error("zone transfer too large, aborted after {records} records and {bytes} bytes")]
+    ZoneTransferTooLarge {
+        /// The number of records received
+        records: usize,
+        /// The number of bytes received
+        bytes: usize,
+    },
+
+    /// A zone transfer did not end within its maximum duration
+    #[error("zone transfer did not end within {0:?}")]
+    ZoneTransferTimeout(Duration),
+
+    /// The SOA concluding a zone transfer has a different serial than the opening one
+    #[error("zone transfer ended with serial {found}, expected {expected}")]
+    ZoneTransferSerialMismatch {
+        /// The serial of the opening SOA
+        expected: u32,
+        /// The serial of the concluding SOA
+        found: u32,
+    },
 }
 
 impl Clone for ErrorKind {
@@ -65,6 +87,15 @@ impl Clone for ErrorKind {
             Proto(proto) => Proto(proto.clone()),
             SendError(e) => SendError(e.clone()),
             Timeout => Timeout,
+            ZoneTransferTooLarge { records, bytes } => ZoneTransferTooLarge {
+                records: *records,
+                bytes: *bytes,
+            },
+            ZoneTransferTimeout(duration) => ZoneTransferTimeout(*duration),
+            ZoneTransferSerialMismatch { expected, found } => ZoneTransferSerialMismatch {
+                expected: *expected,
+                found: *found,
+            },
         }
     }
 }
@@ -159,7 +190,9 @@ impl From<ProtoError> for Error {
 impl From<Error> for io::Error {
     fn from(e: Error) -> Self {
         match *e.kind() {
-            ErrorKind::Timeout => Self::new(io::ErrorKind::TimedOut, e),
+            ErrorKind::Timeout | ErrorKind::ZoneTransferTimeout(_) => {
+                Self::new(io::ErrorKind::TimedOut, e)
+            }
             _ => Self::new(io::ErrorKind::Other, e),
         }
     }
@@ -270,6 +270,7 @@
 //! ```
 
 pub mod client;
+pub mod edns_compliance;
 pub mod error;
 #[cfg(feature = "mdns")]
 #[cfg_attr(docsrs, doc(cfg(feature = "mdns")))]
@@ -279,6 +280,7 @@ pub mod rr;
 pub mod serialize;
 pub mod tcp;
 pub mod udp;
+pub mod zone_diff;
 
 // TODO: consider removing tcp/udp/https modules...
 #[cfg(feature = "dns-over-https")]
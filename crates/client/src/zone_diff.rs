@@ -0,0 +1,832 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Compares the copies of a zone served by two name servers, e.g. before moving the zone to another
+//!  provider
+//!
+//! The records of each copy are read with a zone transfer when the server allows it, else by
+//!  walking the NSEC chain of a signed zone, else by querying a list of names. The RRsets are
+//!  compared in canonical form, [RFC 4034](https://tools.ietf.org/html/rfc4034#section-6.2), only
+//!  a digest of each RRset is kept so that large zones are compared without buffering them.
+//!
+//! ```rust,no_run
+//! use std::net::SocketAddr;
+//! use std::str::FromStr;
+//!
+//! use hickory_client::client::AsyncClient;
+//! use hickory_client::rr::Name;
+//! use hickory_client::tcp::TcpClientStream;
+//! use hickory_client::zone_diff::{self, DiffOptions, ZoneSource};
+//! use hickory_proto::iocompat::AsyncIoTokioAsStd;
+//! use tokio::net::TcpStream;
+//!
+//! # async fn diff() {
+//! async fn client(address: SocketAddr) -> AsyncClient {
+//!     let (stream, sender) = TcpClientStream::<AsyncIoTokioAsStd<TcpStream>>::new(address);
+//!     let (client, bg) = AsyncClient::new(stream, sender, None).await.unwrap();
+//!     tokio::spawn(bg);
+//!     client
+//! }
+//!
+//! let old = client(SocketAddr::from(([192, 0, 2, 1], 53))).await;
+//! let new = client(SocketAddr::from(([192, 0, 2, 2], 53))).await;
+//! let zone = Name::from_str("example.com.").unwrap();
+//! let source = ZoneSource::Auto(vec![]);
+//! let report = zone_diff::diff_zones(old, new, zone, &source, &DiffOptions::default())
+//!     .await
+//!     .unwrap();
+//! print!("{report}");
+//! # }
+//! ```
+
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::hash::{Hash, Hasher};
+
+use futures_util::stream::StreamExt;
+#[cfg(feature = "serde-config")]
+use serde::Serialize;
+use tracing::debug;
+
+use crate::client::ClientHandle;
+use crate::error::{ClientError, ClientResult};
+use crate::proto::op::ResponseCode;
+use crate::proto::rr::rdata::SOA;
+use crate::proto::serialize::binary::{BinEncodable, BinEncoder, EncodeMode};
+use crate::rr::{DNSClass, Name, RData, Record, RecordType};
+
+/// How much a difference matters for the migration, ordered from the least to the most important
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+#[cfg_attr(feature = "serde-config", serde(rename_all = "lowercase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Expected to differ, e.g. the serial of the SOA
+    Info,
+    /// The answers are the same, but are cached differently
+    Warning,
+    /// The answers differ
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// One of the two compared servers
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+#[cfg_attr(feature = "serde-config", serde(rename_all = "lowercase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    /// The first server, usually the current one
+    First,
+    /// The second server, usually the one the zone is moved to
+    Second,
+}
+
+impl Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::First => "first",
+            Self::Second => "second",
+        })
+    }
+}
+
+/// The way an RRset differs between the servers
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DifferenceKind {
+    /// The RRset is only served by one of the servers
+    MissingRrset {
+        /// The server which serves the RRset
+        present_on: Side,
+    },
+    /// The record data of the RRset differs
+    RdataMismatch {
+        /// The number of records on the first server
+        first: usize,
+        /// The number of records on the second server
+        second: usize,
+    },
+    /// The record data is the same, but the TTL differs
+    TtlDrift {
+        /// The TTL on the first server
+        first: u32,
+        /// The TTL on the second server
+        second: u32,
+    },
+    /// Only the serial of the SOA differs, reported if [`DiffOptions::ignore_soa_serial`] is false
+    SoaSerial {
+        /// The serial on the first server
+        first: u32,
+        /// The serial on the second server
+        second: u32,
+    },
+}
+
+impl DifferenceKind {
+    /// The severity of this kind of difference
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::MissingRrset { .. } | Self::RdataMismatch { .. } => Severity::Error,
+            Self::TtlDrift { .. } => Severity::Warning,
+            Self::SoaSerial { .. } => Severity::Info,
+        }
+    }
+}
+
+impl Display for DifferenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingRrset { present_on } => write!(f, "missing rrset, only on {present_on}"),
+            Self::RdataMismatch { first, second } => {
+                write!(f, "rdata mismatch, {first} and {second} records")
+            }
+            Self::TtlDrift { first, second } => write!(f, "ttl drift, {first} and {second}"),
+            Self::SoaSerial { first, second } => write!(f, "soa serial, {first} and {second}"),
+        }
+    }
+}
+
+/// A difference of an RRset between the servers
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Difference {
+    /// The owner of the RRset
+    pub name: Name,
+    /// The type of the RRset
+    pub record_type: RecordType,
+    /// How the RRset differs
+    pub kind: DifferenceKind,
+}
+
+impl Difference {
+    /// The severity of the difference
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{severity}: {name} {record_type}: {kind}",
+            severity = self.severity(),
+            name = self.name,
+            record_type = self.record_type,
+            kind = self.kind,
+        )
+    }
+}
+
+/// How the records of a copy of the zone are read
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ZoneSource {
+    /// A zone transfer, else the NSEC chain, else the names if any are given
+    Auto(Vec<Name>),
+    /// A zone transfer, AXFR
+    Transfer,
+    /// The NSEC chain of a signed zone, the NSEC3 chain can't be walked
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    Walk,
+    /// Queries for the [`DiffOptions::query_types`] of each name
+    Names(Vec<Name>),
+}
+
+/// The way the records of a copy of the zone were read
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+#[cfg_attr(feature = "serde-config", serde(rename_all = "lowercase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SourceKind {
+    /// A zone transfer
+    Transfer,
+    /// The NSEC chain
+    Walk,
+    /// A list of names
+    Names,
+}
+
+impl Display for SourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Transfer => "zone transfer",
+            Self::Walk => "nsec walk",
+            Self::Names => "name list",
+        })
+    }
+}
+
+/// Options of the comparison
+#[derive(Clone, Debug)]
+pub struct DiffOptions {
+    /// The types of the RRsets which are not compared, RRSIG by default as the signatures differ
+    ///  between signers
+    pub ignored_types: Vec<RecordType>,
+    /// Don't report RRsets which only differ by the SOA serial, true by default
+    pub ignore_soa_serial: bool,
+    /// The types queried for each name of a [`ZoneSource::Names`] list
+    pub query_types: Vec<RecordType>,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            ignored_types: vec![RecordType::RRSIG],
+            ignore_soa_serial: true,
+            query_types: vec![
+                RecordType::SOA,
+                RecordType::NS,
+                RecordType::A,
+                RecordType::AAAA,
+                RecordType::CNAME,
+                RecordType::MX,
+                RecordType::TXT,
+                RecordType::SRV,
+                RecordType::CAA,
+                RecordType::PTR,
+                RecordType::DS,
+                RecordType::DNSKEY,
+            ],
+        }
+    }
+}
+
+/// The result of the comparison
+#[cfg_attr(feature = "serde-config", derive(Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffReport {
+    /// The compared zone
+    pub zone: Name,
+    /// The way the records were read from each server
+    pub sources: [SourceKind; 2],
+    /// The number of RRsets read from each server
+    pub rrsets: [usize; 2],
+    /// The differences, in the canonical order of the names
+    pub differences: Vec<Difference>,
+}
+
+impl DiffReport {
+    /// The severity of the most important difference, None if the copies are identical
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.differences.iter().map(Difference::severity).max()
+    }
+
+    /// Returns true if any difference is at least as important as `severity`
+    pub fn has_differences(&self, severity: Severity) -> bool {
+        self.max_severity().map_or(false, |max| max >= severity)
+    }
+}
+
+impl Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "; zone diff of {}", self.zone)?;
+        writeln!(
+            f,
+            "; first: {} rrsets by {}",
+            self.rrsets[0], self.sources[0]
+        )?;
+        writeln!(
+            f,
+            "; second: {} rrsets by {}",
+            self.rrsets[1], self.sources[1]
+        )?;
+        for difference in &self.differences {
+            writeln!(f, "{difference}")?;
+        }
+        if self.differences.is_empty() {
+            writeln!(f, "; no differences")?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the zone from both servers and compares the RRsets
+///
+/// # Arguments
+///
+/// * `first` - the connection to the first name server, a zone transfer requires TCP
+/// * `second` - the connection to the second name server
+/// * `zone` - the zone to compare
+/// * `source` - the way the records are read, the same for both servers
+/// * `options` - the RRsets and differences which are compared
+pub async fn diff_zones<A: ClientHandle, B: ClientHandle>(
+    first: A,
+    second: B,
+    zone: Name,
+    source: &ZoneSource,
+    options: &DiffOptions,
+) -> ClientResult<DiffReport> {
+    let (first_source, first_digests) = read_zone(first, &zone, source, options).await?;
+    let (second_source, second_digests) = read_zone(second, &zone, source, options).await?;
+
+    Ok(DiffReport {
+        sources: [first_source, second_source],
+        rrsets: [first_digests.len(), second_digests.len()],
+        differences: compare(first_digests, second_digests, options),
+        zone,
+    })
+}
+
+/// The digest of an RRset, independent of the order of the records
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct RrsetDigest {
+    ttl: u32,
+    records: usize,
+    hash: u64,
+    soa_serial: Option<u32>,
+}
+
+type Digests = HashMap<(Name, RecordType), RrsetDigest>;
+
+/// Accumulates the digests of the RRsets of a zone, from records in any order
+struct ZoneDigests<'a> {
+    zone: &'a Name,
+    options: &'a DiffOptions,
+    digests: Digests,
+}
+
+impl<'a> ZoneDigests<'a> {
+    fn new(zone: &'a Name, options: &'a DiffOptions) -> Self {
+        Self {
+            zone,
+            options,
+            digests: HashMap::new(),
+        }
+    }
+
+    fn add(&mut self, record: &Record) -> ClientResult<()> {
+        let record_type = record.record_type();
+        if !self.zone.zone_of(record.name()) || self.options.ignored_types.contains(&record_type) {
+            return Ok(());
+        }
+
+        // the serial is compared separately, so that it can be ignored
+        let mut soa_serial = None;
+        let rdata = match record.data() {
+            RData::SOA(soa) => {
+                soa_serial = Some(soa.serial());
+                RData::SOA(SOA::new(
+                    soa.mname().clone(),
+                    soa.rname().clone(),
+                    0,
+                    soa.refresh(),
+                    soa.retry(),
+                    soa.expire(),
+                    soa.minimum(),
+                ))
+            }
+            rdata => rdata.clone(),
+        };
+        let hash = canonical_hash(&rdata)?;
+
+        let key = (record.name().to_lowercase(), record_type);
+        match self.digests.entry(key) {
+            Entry::Vacant(entry) => {
+                entry.insert(RrsetDigest {
+                    ttl: record.ttl(),
+                    records: 1,
+                    hash,
+                    soa_serial,
+                });
+            }
+            Entry::Occupied(mut entry) => {
+                let digest = entry.get_mut();
+                digest.ttl = digest.ttl.min(record.ttl());
+                digest.records += 1;
+                digest.hash = digest.hash.wrapping_add(hash);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Hashes the record data in canonical form, the names in it are lowercased
+fn canonical_hash(rdata: &RData) -> ClientResult<u64> {
+    let mut bytes = Vec::new();
+    let mut encoder = BinEncoder::with_mode(&mut bytes, EncodeMode::Signing);
+    rdata.emit(&mut encoder)?;
+
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    Ok(hasher.finish())
+}
+
+fn compare(first: Digests, mut second: Digests, options: &DiffOptions) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    let mut push = |(name, record_type): (Name, RecordType), kind| {
+        differences.push(Difference {
+            name,
+            record_type,
+            kind,
+        })
+    };
+
+    for (key, first) in first {
+        let Some(second) = second.remove(&key) else {
+            push(
+                key,
+                DifferenceKind::MissingRrset {
+                    present_on: Side::First,
+                },
+            );
+            continue;
+        };
+
+        if first.hash != second.hash || first.records != second.records {
+            push(
+                key,
+                DifferenceKind::RdataMismatch {
+                    first: first.records,
+                    second: second.records,
+                },
+            );
+        } else if first.ttl != second.ttl {
+            push(
+                key,
+                DifferenceKind::TtlDrift {
+                    first: first.ttl,
+                    second: second.ttl,
+                },
+            );
+        } else if let (Some(first), Some(second)) = (first.soa_serial, second.soa_serial) {
+            if first != second && !options.ignore_soa_serial {
+                push(key, DifferenceKind::SoaSerial { first, second });
+            }
+        }
+    }
+
+    for key in second.into_keys() {
+        push(
+            key,
+            DifferenceKind::MissingRrset {
+                present_on: Side::Second,
+            },
+        );
+    }
+
+    differences.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then_with(|| u16::from(a.record_type).cmp(&u16::from(b.record_type)))
+    });
+    differences
+}
+
+async fn read_zone<C: ClientHandle>(
+    mut client: C,
+    zone: &Name,
+    source: &ZoneSource,
+    options: &DiffOptions,
+) -> ClientResult<(SourceKind, Digests)> {
+    let mut digests = ZoneDigests::new(zone, options);
+    let kind = match source {
+        ZoneSource::Transfer => {
+            transfer(&mut client, zone, &mut digests).await?;
+            SourceKind::Transfer
+        }
+        #[cfg(feature = "dnssec")]
+        ZoneSource::Walk => {
+            walk(&mut client, zone, &mut digests).await?;
+            SourceKind::Walk
+        }
+        ZoneSource::Names(names) => {
+            query_names(&mut client, names, &mut digests).await?;
+            SourceKind::Names
+        }
+        ZoneSource::Auto(names) => match transfer(&mut client, zone, &mut digests).await {
+            Ok(()) => SourceKind::Transfer,
+            Err(e) => {
+                debug!("zone transfer of {zone} failed, {e}");
+                digests = ZoneDigests::new(zone, options);
+                auto_fallback(&mut client, zone, names, &mut digests, e).await?
+            }
+        },
+    };
+
+    Ok((kind, digests.digests))
+}
+
+#[cfg(feature = "dnssec")]
+async fn auto_fallback<C: ClientHandle>(
+    client: &mut C,
+    zone: &Name,
+    names: &[Name],
+    digests: &mut ZoneDigests<'_>,
+    transfer_error: ClientError,
+) -> ClientResult<SourceKind> {
+    match walk(client, zone, digests).await {
+        Ok(()) => Ok(SourceKind::Walk),
+        Err(e) if !names.is_empty() => {
+            debug!("nsec walk of {zone} failed, {e}");
+            digests.digests.clear();
+            query_names(client, names, digests).await?;
+            Ok(SourceKind::Names)
+        }
+        Err(e) => {
+            tracing::warn!("nsec walk of {zone} failed, {e}");
+            Err(transfer_error)
+        }
+    }
+}
+
+#[cfg(not(feature = "dnssec"))]
+async fn auto_fallback<C: ClientHandle>(
+    client: &mut C,
+    _zone: &Name,
+    names: &[Name],
+    digests: &mut ZoneDigests<'_>,
+    transfer_error: ClientError,
+) -> ClientResult<SourceKind> {
+    if names.is_empty() {
+        return Err(transfer_error);
+    }
+
+    query_names(client, names, digests).await?;
+    Ok(SourceKind::Names)
+}
+
+/// Reads the zone with AXFR, the SOA closing the transfer is skipped
+async fn transfer<C: ClientHandle>(
+    client: &mut C,
+    zone: &Name,
+    digests: &mut ZoneDigests<'_>,
+) -> ClientResult<()> {
+    let mut responses = client.zone_transfer(zone.clone(), None);
+    let mut soa_seen = false;
+    while let Some(response) = responses.next().await {
+        let response = response?;
+        if response.response_code() != ResponseCode::NoError {
+            return Err(ClientError::from(format!(
+                "zone transfer refused: {}",
+                response.response_code()
+            )));
+        }
+
+        for record in response.answers() {
+            if record.record_type() == RecordType::SOA {
+                if soa_seen {
+                    continue;
+                }
+                soa_seen = true;
+            }
+            digests.add(record)?;
+        }
+    }
+
+    if !soa_seen {
+        return Err(ClientError::from("zone transfer without SOA"));
+    }
+    Ok(())
+}
+
+/// Reads the zone by following the NSEC chain from the apex, and querying the types of each name
+#[cfg(feature = "dnssec")]
+async fn walk<C: ClientHandle>(
+    client: &mut C,
+    zone: &Name,
+    digests: &mut ZoneDigests<'_>,
+) -> ClientResult<()> {
+    use std::collections::HashSet;
+
+    use crate::proto::rr::dnssec::rdata::NSEC;
+    use crate::proto::rr::RecordData;
+
+    let mut visited = HashSet::new();
+    let mut name = zone.clone();
+    loop {
+        if !visited.insert(name.to_lowercase()) {
+            return Err(ClientError::from(format!("nsec chain loops at {name}")));
+        }
+
+        let records = query_rrset(client, &name, RecordType::NSEC).await?;
+        let next = records.iter().find_map(|record| {
+            NSEC::try_borrow(record.data()).map(|nsec| {
+                (
+                    nsec.next_domain_name().clone(),
+                    nsec.type_bit_maps().to_vec(),
+                )
+            })
+        });
+        let Some((next, types)) = next else {
+            return Err(ClientError::from(format!("no nsec record at {name}")));
+        };
+        for record in &records {
+            digests.add(record)?;
+        }
+
+        for record_type in types {
+            if record_type == RecordType::NSEC
+                || digests.options.ignored_types.contains(&record_type)
+            {
+                continue;
+            }
+            for record in query_rrset(client, &name, record_type).await? {
+                digests.add(&record)?;
+            }
+        }
+
+        // the last NSEC points back to the apex
+        if next == *zone || !zone.zone_of(&next) {
+            return Ok(());
+        }
+        name = next;
+    }
+}
+
+async fn query_names<C: ClientHandle>(
+    client: &mut C,
+    names: &[Name],
+    digests: &mut ZoneDigests<'_>,
+) -> ClientResult<()> {
+    let query_types = digests.options.query_types.clone();
+    for name in names {
+        for record_type in &query_types {
+            for record in query_rrset(client, name, *record_type).await? {
+                digests.add(&record)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Queries an RRset, the records of delegations are in the authority section
+async fn query_rrset<C: ClientHandle>(
+    client: &mut C,
+    name: &Name,
+    record_type: RecordType,
+) -> ClientResult<Vec<Record>> {
+    let response = client
+        .query(name.clone(), DNSClass::IN, record_type)
+        .await?;
+
+    let records = response
+        .answers()
+        .iter()
+        .chain(response.name_servers())
+        .filter(|record| record.record_type() == record_type && record.name() == name)
+        .cloned()
+        .collect();
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::rr::rdata::{A, NS};
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).unwrap()
+    }
+
+    fn digests<'a>(zone: &'a Name, options: &'a DiffOptions, records: &[Record]) -> Digests {
+        let mut digests = ZoneDigests::new(zone, options);
+        for record in records {
+            digests.add(record).unwrap();
+        }
+        digests.digests
+    }
+
+    fn soa(serial: u32) -> Record {
+        Record::from_rdata(
+            name("example.com."),
+            3600,
+            RData::SOA(SOA::new(
+                name("ns.example.com."),
+                name("hostmaster.example.com."),
+                serial,
+                3600,
+                600,
+                86400,
+                300,
+            )),
+        )
+    }
+
+    fn a(owner: &str, ttl: u32, last: u8) -> Record {
+        Record::from_rdata(name(owner), ttl, RData::A(A::new(192, 0, 2, last)))
+    }
+
+    #[test]
+    fn test_identical_in_any_order() {
+        let zone = name("example.com.");
+        let options = DiffOptions::default();
+        let ns = Record::from_rdata(zone.clone(), 3600, RData::NS(NS(name("NS.example.com."))));
+        let first = digests(
+            &zone,
+            &options,
+            &[
+                soa(1),
+                ns,
+                a("www.example.com.", 300, 1),
+                a("www.example.com.", 300, 2),
+            ],
+        );
+
+        // names in the rdata are compared in canonical form, the serial is ignored
+        let ns = Record::from_rdata(zone.clone(), 3600, RData::NS(NS(name("ns.example.com."))));
+        let second = digests(
+            &zone,
+            &options,
+            &[
+                a("WWW.example.com.", 300, 2),
+                soa(2),
+                a("www.example.com.", 300, 1),
+                ns,
+            ],
+        );
+
+        assert_eq!(compare(first, second, &options), vec![]);
+    }
+
+    #[test]
+    fn test_differences() {
+        let zone = name("example.com.");
+        let options = DiffOptions {
+            ignore_soa_serial: false,
+            ..DiffOptions::default()
+        };
+        let first = digests(
+            &zone,
+            &options,
+            &[
+                soa(1),
+                a("www.example.com.", 300, 1),
+                a("ftp.example.com.", 300, 1),
+                a("mail.example.com.", 300, 1),
+                // out of the zone
+                a("www.example.net.", 300, 1),
+            ],
+        );
+        let second = digests(
+            &zone,
+            &options,
+            &[
+                soa(2),
+                a("www.example.com.", 600, 1),
+                a("mail.example.com.", 300, 2),
+                a("new.example.com.", 300, 1),
+            ],
+        );
+
+        let differences = compare(first, second, &options);
+        let differences = differences
+            .iter()
+            .map(|d| (d.name.to_string(), d.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            differences,
+            vec![
+                (
+                    "example.com.".to_string(),
+                    DifferenceKind::SoaSerial {
+                        first: 1,
+                        second: 2
+                    }
+                ),
+                (
+                    "ftp.example.com.".to_string(),
+                    DifferenceKind::MissingRrset {
+                        present_on: Side::First
+                    }
+                ),
+                (
+                    "mail.example.com.".to_string(),
+                    DifferenceKind::RdataMismatch {
+                        first: 1,
+                        second: 1
+                    }
+                ),
+                (
+                    "new.example.com.".to_string(),
+                    DifferenceKind::MissingRrset {
+                        present_on: Side::Second
+                    }
+                ),
+                (
+                    "www.example.com.".to_string(),
+                    DifferenceKind::TtlDrift {
+                        first: 300,
+                        second: 600
+                    }
+                ),
+            ]
+        );
+    }
+}
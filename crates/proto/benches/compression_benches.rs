@@ -0,0 +1,48 @@
+#![cfg(nightly)]
+#![feature(test)]
+
+extern crate test;
+
+use std::str::FromStr;
+
+use test::Bencher;
+
+use hickory_proto::rr::rdata::A;
+use hickory_proto::rr::{Name, RData, Record};
+use hickory_proto::serialize::binary::{BinEncodable, BinEncoder, MessageCompressor};
+
+fn records() -> Vec<Record> {
+    (0..100)
+        .map(|i| {
+            let name = Name::from_str(&format!("host{i}.zone{}.example.com.", i % 10)).unwrap();
+            Record::from_rdata(name, 300, RData::A(A::new(192, 0, 2, i)))
+        })
+        .collect()
+}
+
+#[bench]
+fn encode_records_greedy(b: &mut Bencher) {
+    let records = records();
+
+    b.iter(|| {
+        let mut buf = Vec::with_capacity(4096);
+        let mut encoder = BinEncoder::new(&mut buf);
+        for record in &records {
+            record.emit(&mut encoder).unwrap();
+        }
+        buf
+    });
+}
+
+#[bench]
+fn encode_records_message_compressor(b: &mut Bencher) {
+    let records = records();
+
+    b.iter(|| {
+        let mut buf = Vec::with_capacity(4096);
+        MessageCompressor::new(&records)
+            .encode(&records, &mut buf)
+            .unwrap();
+        buf
+    });
+}
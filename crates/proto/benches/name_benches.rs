@@ -8,6 +8,7 @@ use std::cmp::Ordering;
 use test::Bencher;
 
 use hickory_proto::rr::*;
+use hickory_proto::serialize::binary::{BinDecodable, BinEncodable};
 
 #[bench]
 fn name_cmp_short(b: &mut Bencher) {
@@ -158,3 +159,43 @@ fn name_no_lower_long(b: &mut Bencher) {
         assert_eq!(lower.num_labels(), 3);
     });
 }
+
+#[bench]
+fn name_from_ascii_medium(b: &mut Bencher) {
+    b.iter(|| {
+        let name = Name::from_ascii("www.example.com.").unwrap();
+        assert_eq!(name.num_labels(), 3);
+    });
+}
+
+#[bench]
+fn name_from_ascii_five_labels(b: &mut Bencher) {
+    b.iter(|| {
+        let name = Name::from_ascii("mail.eu-west.example.co.uk.").unwrap();
+        assert_eq!(name.num_labels(), 5);
+    });
+}
+
+#[bench]
+fn name_from_bytes_medium(b: &mut Bencher) {
+    let bytes = Name::from_ascii("www.example.com.")
+        .unwrap()
+        .to_bytes()
+        .unwrap();
+
+    b.iter(|| {
+        let name = Name::from_bytes(&bytes).unwrap();
+        assert_eq!(name.num_labels(), 3);
+    });
+}
+
+#[bench]
+fn name_append_domain(b: &mut Bencher) {
+    let host = Name::from_ascii("www").unwrap();
+    let zone = Name::from_ascii("example.com.").unwrap();
+
+    b.iter(|| {
+        let name = host.clone().append_domain(&zone).unwrap();
+        assert_eq!(name.num_labels(), 3);
+    });
+}
@@ -22,7 +22,7 @@ use once_cell::sync::Lazy;
 use thiserror::Error;
 use tracing::debug;
 
-use crate::op::{Header, Query, ResponseCode};
+use crate::op::{Edns, Header, Query, ResponseCode};
 
 #[cfg(feature = "dnssec")]
 use crate::rr::dnssec::{rdata::tsig::TsigAlgorithm, Proof};
@@ -68,6 +68,18 @@ pub enum ProtoErrorKind {
     #[error("there should only be one query per request, got: {0}")]
     BadQueryCount(usize),
 
+    /// The server answered with BADVERS, it doesn't implement the EDNS version of the request
+    ///
+    /// See [RFC 6891 section 6.1.3](https://tools.ietf.org/html/rfc6891#section-6.1.3), the
+    ///  version of the OPT record of the response is the highest version the server implements.
+    #[error("EDNS version {client_version} not supported by the server, which implements up to version {server_version}")]
+    BadEdnsVersion {
+        /// The highest EDNS version implemented by the server
+        server_version: u8,
+        /// The EDNS version of the request
+        client_version: u8,
+    },
+
     /// The underlying resource is too busy
     ///
     /// This is a signal that an internal resource is too busy. The intended action should be tried
@@ -89,6 +101,19 @@ pub enum ProtoErrorKind {
         len: usize,
     },
 
+    /// A CNAME chain loops back to one of its names
+    #[error("cname loop detected at: {0}")]
+    CnameLoop(crate::rr::Name),
+
+    /// A CNAME chain has more links than are followed
+    #[error("cname chain exceeds {limit} links at: {name}")]
+    CnameTooDeep {
+        /// The name at which following the chain stopped
+        name: crate::rr::Name,
+        /// The maximum number of links that are followed
+        limit: usize,
+    },
+
     /// Overlapping labels
     #[error("overlapping labels name {label} other {other}")]
     LabelOverlapsWithOther {
@@ -316,6 +341,11 @@ pub enum ProtoErrorKind {
     #[error("an unknown quic stream was used")]
     QuinnUnknownStreamError,
 
+    /// A DNS over HTTPS response id should always be 0, the response is matched by its HTTP stream
+    #[cfg(any(feature = "dns-over-https", feature = "dns-over-h3"))]
+    #[error("https messages should always be 0, got: {0}")]
+    HttpsMessageIdNot0(u16),
+
     /// A quic message id should always be 0
     #[cfg(feature = "quinn")]
     #[error("quic messages should always be 0, got: {0}")]
@@ -387,6 +417,13 @@ impl ProtoError {
         matches!(*self.kind, ProtoErrorKind::Io(..))
     }
 
+    /// Returns true if this is a std::io::Error of a datagram too big for the socket, see
+    ///  [`crate::udp::is_message_too_big`]
+    #[inline]
+    pub fn is_message_too_big(&self) -> bool {
+        matches!(&*self.kind, ProtoErrorKind::Io(e) if crate::udp::is_message_too_big(e))
+    }
+
     pub(crate) fn as_dyn(&self) -> &(dyn std::error::Error + 'static) {
         self
     }
@@ -397,6 +434,16 @@ impl ProtoError {
         debug!("Response:{}", *response);
 
         match response.response_code() {
+                BADVERS => {
+                    let server_version = response.extensions().as_ref().map_or(0, Edns::version);
+                    let error_kind = ProtoErrorKind::BadEdnsVersion {
+                        server_version,
+                        // the only version sent by this implementation
+                        client_version: Edns::new().version(),
+                    };
+
+                    Err(Self::from(error_kind))
+                }
                 code @ ServFail
                 | code @ Refused
                 | code @ FormErr
@@ -406,7 +453,6 @@ impl ProtoError {
                 | code @ NXRRSet
                 | code @ NotAuth
                 | code @ NotZone
-                | code @ BADVERS
                 | code @ BADSIG
                 | code @ BADKEY
                 | code @ BADTIME
@@ -600,9 +646,21 @@ impl Clone for ProtoErrorKind {
         use self::ProtoErrorKind::*;
         match *self {
             BadQueryCount(count) => BadQueryCount(count),
+            BadEdnsVersion {
+                server_version,
+                client_version,
+            } => BadEdnsVersion {
+                server_version,
+                client_version,
+            },
             Busy => Busy,
             Canceled(ref c) => Canceled(*c),
             CharacterDataTooLong { max, len } => CharacterDataTooLong { max, len },
+            CnameLoop(ref name) => CnameLoop(name.clone()),
+            CnameTooDeep { ref name, limit } => CnameTooDeep {
+                name: name.clone(),
+                limit,
+            },
             LabelOverlapsWithOther { label, other } => LabelOverlapsWithOther { label, other },
             DnsKeyProtocolNot3(protocol) => DnsKeyProtocolNot3(protocol),
             DomainNameTooLong(len) => DomainNameTooLong(len),
@@ -667,6 +725,8 @@ impl Clone for ProtoErrorKind {
             QuinnConnection(ref e) => QuinnConnection(e.clone()),
             #[cfg(feature = "quinn")]
             QuinnWriteError(ref e) => QuinnWriteError(e.clone()),
+            #[cfg(any(feature = "dns-over-https", feature = "dns-over-h3"))]
+            HttpsMessageIdNot0(val) => HttpsMessageIdNot0(val),
             #[cfg(feature = "quinn")]
             QuicMessageIdNot0(val) => QuicMessageIdNot0(val),
             #[cfg(feature = "quinn")]
@@ -27,7 +27,7 @@ use tokio_rustls::{
 };
 use tracing::{debug, warn};
 
-use crate::error::ProtoError;
+use crate::error::{ProtoError, ProtoErrorKind, ProtoResult};
 use crate::http::Version;
 use crate::iocompat::AsyncIoStdAsTokio;
 use crate::op::Message;
@@ -61,8 +61,9 @@ impl HttpsClientStream {
     async fn inner_send(
         h2: SendRequest<Bytes>,
         message: Bytes,
+        query_id: u16,
         name_server_name: Arc<str>,
-    ) -> Result<DnsResponse, ProtoError> {
+    ) -> ProtoResult<DnsResponse> {
         let mut h2 = match h2.ready().await {
             Ok(h2) => h2,
             Err(err) => {
@@ -176,7 +177,15 @@ impl HttpsClientStream {
 
         // and finally convert the bytes into a DNS message
         let message = Message::from_vec(&response_bytes)?;
-        Ok(DnsResponse::new(message, response_bytes.to_vec()))
+
+        // the response is matched by its HTTP stream, the id must be the 0 that was sent
+        if message.id() != 0 {
+            return Err(ProtoErrorKind::HttpsMessageIdNot0(message.id()).into());
+        }
+
+        let mut response = DnsResponse::new(message, response_bytes.to_vec());
+        response.restore_id(query_id);
+        Ok(response)
     }
 }
 
@@ -234,6 +243,7 @@ impl DnsRequestSender for HttpsClientStream {
         }
 
         // per the RFC, a zero id allows for the HTTP packet to be cached better
+        let query_id = message.id();
         message.set_id(0);
 
         let bytes = match message.to_vec() {
@@ -244,6 +254,7 @@ impl DnsRequestSender for HttpsClientStream {
         Box::pin(Self::inner_send(
             self.h2.clone(),
             Bytes::from(bytes),
+            query_id,
             Arc::clone(&self.name_server_name),
         ))
         .into()
@@ -730,6 +741,115 @@ mod tests {
         );
     }
 
+    /// A DoH server over an in memory connection, answering `count` pipelined requests in reverse
+    ///  order with `response_id`; returns the ids of the received queries
+    async fn mock_https_stream(
+        count: usize,
+        response_id: u16,
+    ) -> (HttpsClientStream, tokio::task::JoinHandle<Vec<u16>>) {
+        let (client_io, server_io) = tokio::io::duplex(4096);
+
+        let server = tokio::spawn(async move {
+            let mut connection = h2::server::handshake(server_io).await.unwrap();
+            let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+
+            // the connection must be polled for the request bodies to be received
+            tokio::spawn(async move {
+                while let Some(request) = connection.accept().await {
+                    let (request, respond) = request.unwrap();
+                    let sender = sender.clone();
+                    tokio::spawn(async move {
+                        let mut body = request.into_body();
+                        let mut bytes = BytesMut::new();
+                        while let Some(data) = body.data().await {
+                            let data = data.unwrap();
+                            body.flow_control().release_capacity(data.len()).unwrap();
+                            bytes.extend(data);
+                        }
+                        sender
+                            .send((Message::from_vec(&bytes).unwrap(), respond))
+                            .unwrap();
+                    });
+                }
+            });
+
+            let mut requests = Vec::new();
+            while requests.len() < count {
+                requests.push(receiver.recv().await.unwrap());
+            }
+
+            let ids = requests.iter().map(|(query, _)| query.id()).collect();
+            for (mut message, mut respond) in requests.into_iter().rev() {
+                message.set_id(response_id);
+                let bytes = Bytes::from(message.to_vec().unwrap());
+                let response = http::Response::builder()
+                    .header(header::CONTENT_TYPE, crate::http::MIME_APPLICATION_DNS)
+                    .header(CONTENT_LENGTH, bytes.len())
+                    .body(())
+                    .unwrap();
+                let mut send_stream = respond.send_response(response, false).unwrap();
+                send_stream.send_data(bytes, true).unwrap();
+            }
+
+            ids
+        });
+
+        let (h2, connection) = h2::client::handshake(client_io).await.unwrap();
+        tokio::spawn(connection);
+
+        let stream = HttpsClientStream {
+            name_server_name: Arc::from("dns.example.com"),
+            name_server: SocketAddr::from(([127, 0, 0, 1], 443)),
+            h2,
+            is_shutdown: false,
+        };
+
+        (stream, server)
+    }
+
+    fn query(name: &str, id: u16) -> DnsRequest {
+        let mut message = Message::new();
+        message
+            .set_id(id)
+            .add_query(Query::query(Name::from_str(name).unwrap(), RecordType::A));
+        DnsRequest::new(message, DnsRequestOptions::default())
+    }
+
+    #[tokio::test]
+    async fn test_https_pipelined_id_0() {
+        let (mut https, server) = mock_https_stream(3, 0).await;
+
+        let names = ["a.example.com.", "b.example.com.", "c.example.com."];
+        let responses = names
+            .iter()
+            .zip(1..)
+            .map(|(name, id)| https.send_message(query(name, id)).first_answer())
+            .collect::<Vec<_>>();
+        let responses = futures_util::future::try_join_all(responses).await.unwrap();
+
+        assert_eq!(server.await.unwrap(), vec![0, 0, 0]);
+        for ((name, id), response) in names.iter().zip(1..).zip(responses) {
+            assert_eq!(response.id(), id);
+            assert_eq!(response.queries()[0].name().to_ascii(), *name);
+            assert_eq!(Message::from_vec(response.as_buffer()).unwrap().id(), id);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_https_response_id_not_0() {
+        let (mut https, _server) = mock_https_stream(1, 7).await;
+
+        let error = https
+            .send_message(query("www.example.com.", 1))
+            .first_answer()
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ProtoErrorKind::HttpsMessageIdNot0(7)
+        ));
+    }
+
     fn client_config_tls12() -> ClientConfig {
         use rustls::RootCertStore;
         #[cfg_attr(
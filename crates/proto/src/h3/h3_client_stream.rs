@@ -24,7 +24,7 @@ use rustls::ClientConfig as TlsClientConfig;
 use tokio::sync::mpsc;
 use tracing::{debug, warn};
 
-use crate::error::ProtoError;
+use crate::error::{ProtoError, ProtoErrorKind, ProtoResult};
 use crate::http::Version;
 use crate::op::Message;
 use crate::quic::quic_socket::QuinnAsyncUdpSocketAdapter;
@@ -65,8 +65,9 @@ impl H3ClientStream {
     async fn inner_send(
         mut h3: SendRequest<OpenStreams, Bytes>,
         message: Bytes,
+        query_id: u16,
         name_server_name: Arc<str>,
-    ) -> Result<DnsResponse, ProtoError> {
+    ) -> ProtoResult<DnsResponse> {
         // build up the http request
         let request =
             crate::http::request::new(Version::Http3, &name_server_name, message.remaining());
@@ -181,7 +182,15 @@ impl H3ClientStream {
 
         // and finally convert the bytes into a DNS message
         let message = Message::from_vec(&response_bytes)?;
-        Ok(DnsResponse::new(message, response_bytes.to_vec()))
+
+        // the response is matched by its HTTP stream, the id must be the 0 that was sent
+        if message.id() != 0 {
+            return Err(ProtoErrorKind::HttpsMessageIdNot0(message.id()).into());
+        }
+
+        let mut response = DnsResponse::new(message, response_bytes.to_vec());
+        response.restore_id(query_id);
+        Ok(response)
     }
 }
 
@@ -239,6 +248,7 @@ impl DnsRequestSender for H3ClientStream {
         }
 
         // per the RFC, a zero id allows for the HTTP packet to be cached better
+        let query_id = message.id();
         message.set_id(0);
 
         let bytes = match message.to_vec() {
@@ -249,6 +259,7 @@ impl DnsRequestSender for H3ClientStream {
         Box::pin(Self::inner_send(
             self.send_request.clone(),
             Bytes::from(bytes),
+            query_id,
             Arc::clone(&self.name_server_name),
         ))
         .into()
@@ -331,7 +342,7 @@ impl H3ClientStreamBuilder {
         future: F,
         name_server: SocketAddr,
         dns_name: String,
-    ) -> Result<H3ClientStream, ProtoError>
+    ) -> ProtoResult<H3ClientStream>
     where
         S: DnsUdpSocket + QuicLocalAddr + 'static,
         F: Future<Output = std::io::Result<S>> + Send,
@@ -351,7 +362,7 @@ impl H3ClientStreamBuilder {
         self,
         name_server: SocketAddr,
         dns_name: String,
-    ) -> Result<H3ClientStream, ProtoError> {
+    ) -> ProtoResult<H3ClientStream> {
         let connect = if let Some(bind_addr) = self.bind_addr {
             <tokio::net::UdpSocket as UdpSocket>::connect_with_bind(name_server, bind_addr)
         } else {
@@ -374,7 +385,7 @@ impl H3ClientStreamBuilder {
         mut endpoint: Endpoint,
         name_server: SocketAddr,
         dns_name: String,
-    ) -> Result<H3ClientStream, ProtoError> {
+    ) -> ProtoResult<H3ClientStream> {
         let mut crypto_config = self.crypto_config;
         // ensure the ALPN protocol is set correctly
         if crypto_config.alpn_protocols.is_empty() {
@@ -16,7 +16,10 @@ use http::Request;
 use quinn::{EndpointConfig, ServerConfig};
 use rustls::{server::ServerConfig as TlsServerConfig, version::TLS13, Certificate, PrivateKey};
 
-use crate::{error::ProtoError, udp::UdpSocket};
+use crate::{
+    error::{ProtoError, ProtoResult},
+    udp::UdpSocket,
+};
 
 use super::ALPN_H3;
 
@@ -31,7 +34,7 @@ impl H3Server {
         name_server: SocketAddr,
         cert: Vec<Certificate>,
         key: PrivateKey,
-    ) -> Result<Self, ProtoError> {
+    ) -> ProtoResult<Self> {
         // setup a new socket for the server to use
         let socket = <tokio::net::UdpSocket as UdpSocket>::bind(name_server).await?;
         Self::with_socket(socket, cert, key)
@@ -42,7 +45,7 @@ impl H3Server {
         socket: tokio::net::UdpSocket,
         cert: Vec<Certificate>,
         key: PrivateKey,
-    ) -> Result<Self, ProtoError> {
+    ) -> ProtoResult<Self> {
         let mut config = TlsServerConfig::builder()
             .with_safe_default_cipher_suites()
             .with_safe_default_kx_groups()
@@ -73,7 +76,7 @@ impl H3Server {
     /// # Returns
     ///
     /// A remote connection that could accept many potential requests and the remote socket address
-    pub async fn accept(&mut self) -> Result<Option<(H3Connection, SocketAddr)>, ProtoError> {
+    pub async fn accept(&mut self) -> ProtoResult<Option<(H3Connection, SocketAddr)>> {
         let connecting = match self.endpoint.accept().await {
             Some(conn) => conn,
             None => return Ok(None),
@@ -109,7 +112,7 @@ impl H3Connection {
     /// Accept the next request from the client
     pub async fn accept(
         &mut self,
-    ) -> Option<Result<(Request<()>, RequestStream<BidiStream<Bytes>, Bytes>), ProtoError>> {
+    ) -> Option<ProtoResult<(Request<()>, RequestStream<BidiStream<Bytes>, Bytes>)>> {
         match self.connection.accept().await {
             Ok(Some((request, stream))) => Some(Ok((request, stream))),
             Ok(None) => None,
@@ -118,7 +121,7 @@ impl H3Connection {
     }
 
     /// Shutdown the connection.
-    pub async fn shutdown(&mut self) -> Result<(), ProtoError> {
+    pub async fn shutdown(&mut self) -> ProtoResult<()> {
         self.connection
             .shutdown(0)
             .await
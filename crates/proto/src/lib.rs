@@ -20,6 +20,7 @@
     rust_2018_idioms,
     unreachable_pub
 )]
+#![deny(clippy::result_large_err)]
 #![allow(
     clippy::single_component_path_imports,
     clippy::upper_case_acronyms, // can be removed on a major release boundary
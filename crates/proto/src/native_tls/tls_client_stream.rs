@@ -15,7 +15,7 @@ use futures_util::TryFutureExt;
 use native_tls::Certificate;
 use tokio_native_tls::TlsStream as TokioTlsStream;
 
-use crate::error::ProtoError;
+use crate::error::{ProtoError, ProtoResult};
 use crate::iocompat::AsyncIoStdAsTokio;
 use crate::iocompat::AsyncIoTokioAsStd;
 use crate::native_tls::TlsStreamBuilder;
@@ -63,7 +63,7 @@ impl<S: DnsTcpStream> TlsClientStreamBuilder<S> {
         name_server: SocketAddr,
         dns_name: String,
     ) -> (
-        Pin<Box<dyn Future<Output = Result<TlsClientStream<S>, ProtoError>> + Send>>,
+        Pin<Box<dyn Future<Output = ProtoResult<TlsClientStream<S>>> + Send>>,
         BufDnsStreamHandle,
     )
     where
@@ -94,7 +94,7 @@ impl<S: Connect> TlsClientStreamBuilder<S> {
         name_server: SocketAddr,
         dns_name: String,
     ) -> (
-        Pin<Box<dyn Future<Output = Result<TlsClientStream<S>, ProtoError>> + Send>>,
+        Pin<Box<dyn Future<Output = ProtoResult<TlsClientStream<S>>> + Send>>,
         BufDnsStreamHandle,
     ) {
         let (stream_future, sender) = self.0.build(name_server, dns_name);
@@ -0,0 +1,290 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DNS Stateful Operations messages, used for DNS Push Notifications
+
+use crate::{
+    error::{ProtoError, ProtoErrorKind, ProtoResult},
+    op::{Header, MessageType, OpCode, Query},
+    rr::Record,
+    serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder},
+};
+
+/// DSO-TYPE of the SUBSCRIBE TLV
+const SUBSCRIBE: u16 = 0x0040;
+/// DSO-TYPE of the PUSH TLV
+const PUSH: u16 = 0x0041;
+/// DSO-TYPE of the UNSUBSCRIBE TLV
+const UNSUBSCRIBE: u16 = 0x0042;
+
+/// A DSO message, [RFC 8490, DNS Stateful Operations](https://tools.ietf.org/html/rfc8490#section-5.4)
+///
+/// ```text
+/// 5.4.  DSO Message Format
+///
+///    A DSO message begins with the standard twelve-byte DNS message header
+///    [RFC1035] with the OPCODE field set to the DSO OPCODE (6).  However,
+///    unlike standard DNS messages, the question section, answer section,
+///    authority records section, and additional records sections are not
+///    present.  The corresponding count fields (QDCOUNT, ANCOUNT, NSCOUNT,
+///    ARCOUNT) MUST be set to zero on transmission.
+///
+///    If a DSO message is received where any of the count fields are not
+///    zero, then a FORMERR MUST be returned.
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DsoMessage {
+    header: Header,
+    tlvs: Vec<DsoTlv>,
+}
+
+impl DsoMessage {
+    /// Constructs a new DSO message with the given id and TLVs
+    ///
+    /// Unidirectional messages, such as PUSH and UNSUBSCRIBE, must use an id of 0.
+    pub fn new(id: u16, message_type: MessageType, tlvs: Vec<DsoTlv>) -> Self {
+        let mut header = Header::new();
+        header
+            .set_id(id)
+            .set_message_type(message_type)
+            .set_op_code(OpCode::Dso);
+
+        Self { header, tlvs }
+    }
+
+    /// Constructs the response to a DSO request, without any TLVs
+    pub fn response_from_request(request: &Header) -> Self {
+        Self {
+            header: Header::response_from_request(request),
+            tlvs: Vec::new(),
+        }
+    }
+
+    /// The header of the message
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Mutable access to the header of the message
+    pub fn header_mut(&mut self) -> &mut Header {
+        &mut self.header
+    }
+
+    /// The TLVs of the message, the first is the primary TLV
+    pub fn tlvs(&self) -> &[DsoTlv] {
+        &self.tlvs
+    }
+
+    /// Decodes a DSO message from the buffer
+    pub fn from_vec(buffer: &[u8]) -> ProtoResult<Self> {
+        let mut decoder = BinDecoder::new(buffer);
+        Self::read(&mut decoder)
+    }
+
+    /// Encodes the DSO message into a buffer
+    pub fn to_vec(&self) -> ProtoResult<Vec<u8>> {
+        let mut buffer = Vec::with_capacity(512);
+        {
+            let mut encoder = BinEncoder::new(&mut buffer);
+            self.emit(&mut encoder)?;
+        }
+
+        Ok(buffer)
+    }
+}
+
+impl BinEncodable for DsoMessage {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        let mut header = self.header;
+        header
+            .set_query_count(0)
+            .set_answer_count(0)
+            .set_name_server_count(0)
+            .set_additional_count(0);
+        header.emit(encoder)?;
+
+        for tlv in &self.tlvs {
+            tlv.emit(encoder)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'r> BinDecodable<'r> for DsoMessage {
+    fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
+        let header = Header::read(decoder)?;
+        if header.op_code() != OpCode::Dso {
+            return Err(format!("not a DSO message: {}", header.op_code()).into());
+        }
+
+        if header.query_count() != 0
+            || header.answer_count() != 0
+            || header.name_server_count() != 0
+            || header.additional_count() != 0
+        {
+            return Err(ProtoErrorKind::FormError {
+                header,
+                error: Box::new("DSO message with non-zero record counts".into()),
+            }
+            .into());
+        }
+
+        let mut tlvs = Vec::new();
+        while !decoder.is_empty() {
+            tlvs.push(DsoTlv::read(decoder)?);
+        }
+
+        Ok(Self { header, tlvs })
+    }
+}
+
+/// A DSO TLV, only those of [RFC 8765, DNS Push Notifications](https://tools.ietf.org/html/rfc8765)
+///  are decoded
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DsoTlv {
+    /// Requests notifications for changes to the records matching the query
+    Subscribe(Query),
+    /// Records which were added to or removed from a subscribed RRset
+    Push(Vec<Record>),
+    /// Cancels the subscription created by the SUBSCRIBE message with this id
+    Unsubscribe(u16),
+    /// Any other TLV, with its DSO-TYPE and DSO-DATA
+    Unknown(u16, Vec<u8>),
+}
+
+impl DsoTlv {
+    fn dso_type(&self) -> u16 {
+        match self {
+            Self::Subscribe(_) => SUBSCRIBE,
+            Self::Push(_) => PUSH,
+            Self::Unsubscribe(_) => UNSUBSCRIBE,
+            Self::Unknown(dso_type, _) => *dso_type,
+        }
+    }
+}
+
+impl BinEncodable for DsoTlv {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        encoder.emit_u16(self.dso_type())?;
+        let place = encoder.place::<u16>()?;
+
+        // names in DSO TLVs must not be compressed
+        encoder.with_canonical_names(|encoder| match self {
+            Self::Subscribe(query) => query.emit(encoder),
+            Self::Push(records) => {
+                for record in records {
+                    record.emit(encoder)?;
+                }
+                Ok(())
+            }
+            Self::Unsubscribe(id) => encoder.emit_u16(*id),
+            Self::Unknown(_, data) => encoder.emit_vec(data),
+        })?;
+
+        let len = encoder.len_since_place(&place);
+        let len = u16::try_from(len).map_err(|_| ProtoError::from("DSO TLV is too long"))?;
+        place.replace(encoder, len)
+    }
+}
+
+impl<'r> BinDecodable<'r> for DsoTlv {
+    fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
+        let dso_type = decoder.read_u16()?.unverified(/*any type is valid*/);
+        let length = decoder.read_u16()?.unverified(/*bounded by read_slice*/) as usize;
+        let data = decoder.read_slice(length)?.unverified(/*parsed below*/);
+
+        let mut data_decoder = BinDecoder::new(data);
+        let tlv = match dso_type {
+            SUBSCRIBE => Self::Subscribe(Query::read(&mut data_decoder)?),
+            PUSH => {
+                let mut records = Vec::new();
+                while !data_decoder.is_empty() {
+                    records.push(Record::read(&mut data_decoder)?);
+                }
+                Self::Push(records)
+            }
+            UNSUBSCRIBE => Self::Unsubscribe(data_decoder.read_u16()?.unverified()),
+            _ => return Ok(Self::Unknown(dso_type, data.to_vec())),
+        };
+
+        if !data_decoder.is_empty() {
+            return Err(format!("unexpected data at the end of DSO TLV: {dso_type}").into());
+        }
+
+        Ok(tlv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::rr::{rdata::A, Name, RData, RecordType};
+
+    #[test]
+    fn test_subscribe() {
+        let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+        let message = DsoMessage::new(
+            0x1234,
+            MessageType::Query,
+            vec![DsoTlv::Subscribe(query.clone())],
+        );
+
+        let bytes = message.to_vec().unwrap();
+        assert_eq!(&bytes[2..4], &[0x30, 0x00]);
+        assert_eq!(&bytes[12..16], &[0x00, 0x40, 0x00, 21]);
+        assert_eq!(DsoMessage::from_vec(&bytes).unwrap(), message);
+    }
+
+    #[test]
+    fn test_push_is_not_compressed() {
+        let name = Name::from_str("www.example.com.").unwrap();
+        let records = vec![
+            Record::from_rdata(name.clone(), 300, RData::A(A::new(127, 0, 0, 1))),
+            Record::from_rdata(name, 300, RData::A(A::new(127, 0, 0, 2))),
+        ];
+        let message = DsoMessage::new(
+            0,
+            MessageType::Query,
+            vec![DsoTlv::Push(records), DsoTlv::Unsubscribe(7)],
+        );
+
+        let bytes = message.to_vec().unwrap();
+        // two records, each with a 17 byte name, type, class, ttl, rdlength and an A record
+        assert_eq!(&bytes[12..16], &[0x00, 0x41, 0x00, 2 * (17 + 10 + 4)]);
+        assert_eq!(DsoMessage::from_vec(&bytes).unwrap(), message);
+    }
+
+    #[test]
+    fn test_unknown_tlv() {
+        let message = DsoMessage::new(
+            1,
+            MessageType::Query,
+            vec![DsoTlv::Unknown(0x0001, vec![0, 0, 0, 15])],
+        );
+
+        let bytes = message.to_vec().unwrap();
+        assert_eq!(DsoMessage::from_vec(&bytes).unwrap(), message);
+    }
+
+    #[test]
+    fn test_invalid_messages() {
+        let mut bytes = DsoMessage::new(1, MessageType::Query, vec![DsoTlv::Unsubscribe(1)])
+            .to_vec()
+            .unwrap();
+
+        // truncated TLV
+        assert!(DsoMessage::from_vec(&bytes[..bytes.len() - 1]).is_err());
+
+        // non-zero counts
+        bytes[5] = 1;
+        assert!(DsoMessage::from_vec(&bytes).is_err());
+    }
+}
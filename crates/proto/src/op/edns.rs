@@ -222,6 +222,11 @@ impl BinEncodable for Edns {
         place.replace(encoder, len as u16)?;
         Ok(())
     }
+
+    fn encoded_size(&self) -> usize {
+        // the root name, type, class, ttl and rdata length are 11 bytes
+        11 + self.options.encoded_size()
+    }
 }
 
 impl fmt::Display for Edns {
@@ -543,6 +543,10 @@ impl BinEncodable for Header {
 
         Ok(())
     }
+
+    fn encoded_size(&self) -> usize {
+        Self::len()
+    }
 }
 
 impl<'r> BinDecodable<'r> for Header {
@@ -14,7 +14,7 @@ use crate::rr::{DNSClass, RecordType};
 use crate::serialize::binary::*;
 
 /// Identical to [crate::op::Query], except that the Name is guaranteed to be in lower case form
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct LowerQuery {
     name: LowerName,
     original: Query,
@@ -75,6 +75,10 @@ impl BinEncodable for LowerQuery {
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
         self.original.emit(encoder)
     }
+
+    fn encoded_size(&self) -> usize {
+        self.original.encoded_size()
+    }
 }
 
 impl<'r> BinDecodable<'r> for LowerQuery {
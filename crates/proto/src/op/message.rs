@@ -7,7 +7,11 @@
 
 //! Basic protocol message for DNS
 
-use std::{fmt, iter, mem, ops::Deref, sync::Arc};
+use std::{
+    fmt, iter, mem,
+    ops::{Deref, Range},
+    sync::Arc,
+};
 
 use tracing::{debug, warn};
 
@@ -653,6 +657,45 @@ impl Message {
         mem::take(&mut self.signature)
     }
 
+    /// The number of records in the answer section, unlike `answer_count` of the header
+    pub fn answer_count_actual(&self) -> u16 {
+        u16::try_from(self.answers.len()).unwrap_or(u16::MAX)
+    }
+
+    /// Verifies that the counts of the header match the records of each section
+    ///
+    /// The EDNS OPT record and the SIG(0) or TSIG records are part of the additional count.
+    ///
+    /// # Errors
+    ///
+    /// If any count differs, e.g. for a message built without updating the header.
+    pub fn verify_counts(&self) -> ProtoResult<()> {
+        let sections = [
+            ("query", self.query_count(), self.queries.len()),
+            ("answer", self.answer_count(), self.answers.len()),
+            (
+                "name server",
+                self.name_server_count(),
+                self.name_servers.len(),
+            ),
+            (
+                "additional",
+                self.additional_count(),
+                self.additionals.len() + usize::from(self.edns.is_some()) + self.signature.len(),
+            ),
+        ];
+
+        for (section, header_count, actual) in sections {
+            if usize::from(header_count) != actual {
+                return Err(ProtoError::from(format!(
+                    "{section} count of the header is {header_count}, found {actual} records"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
     // TODO: only necessary in tests, should it be removed?
     /// this is necessary to match the counts in the header from the record sections
     ///  this happens implicitly on write_to, so no need to call before write_to
@@ -685,11 +728,21 @@ impl Message {
     /// # Returns
     ///
     /// This returns a tuple of first standard Records, then a possibly associated Edns, and then finally any optionally associated SIG0 and TSIG records.
-    #[cfg_attr(not(feature = "dnssec"), allow(unused_mut))]
     pub fn read_records(
         decoder: &mut BinDecoder<'_>,
         count: usize,
         is_additional: bool,
+    ) -> ProtoResult<(Vec<Record>, Option<Edns>, Vec<Record>)> {
+        Self::read_records_with_spans(decoder, count, is_additional, None)
+    }
+
+    /// Reads the records, the byte ranges of the standard Records are pushed to `spans` if present
+    #[cfg_attr(not(feature = "dnssec"), allow(unused_mut))]
+    fn read_records_with_spans(
+        decoder: &mut BinDecoder<'_>,
+        count: usize,
+        is_additional: bool,
+        mut spans: Option<&mut Vec<Range<usize>>>,
     ) -> ProtoResult<(Vec<Record>, Option<Edns>, Vec<Record>)> {
         let mut records: Vec<Record> = Vec::with_capacity(count);
         let mut edns: Option<Edns> = None;
@@ -700,7 +753,9 @@ impl Message {
         // tsig must be last, once this is set, disable.
         let mut saw_tsig = false;
         for _ in 0..count {
+            let start = decoder.index();
             let record = Record::read(decoder)?;
+            let span = start..decoder.index();
             if saw_tsig {
                 return Err("tsig must be final resource record".into());
             } // TSIG must be last and multiple TSIG records are not allowed
@@ -708,6 +763,12 @@ impl Message {
                 if saw_sig0 {
                     return Err("sig0 must be final resource record".into());
                 } // SIG0 must be last
+                if record.record_type() == RecordType::OPT {
+                    return Err("edns record present outside of the additional section".into());
+                } // RFC 6891, the OPT RR is only valid in the additional section
+                if let Some(spans) = spans.as_deref_mut() {
+                    spans.push(span);
+                }
                 records.push(record)
             } else {
                 match record.record_type() {
@@ -731,12 +792,18 @@ impl Message {
                         if edns.is_some() {
                             return Err("more than one edns record present".into());
                         }
+                        if !record.name().is_root() {
+                            return Err("edns record owner is not the root".into());
+                        }
                         edns = Some((&record).into());
                     }
                     _ => {
                         if saw_sig0 {
                             return Err("sig0 must be final resource record".into());
                         } // SIG0 must be last
+                        if let Some(spans) = spans.as_deref_mut() {
+                            spans.push(span);
+                        }
                         records.push(record);
                     }
                 }
@@ -752,14 +819,118 @@ impl Message {
         Self::read(&mut decoder)
     }
 
+    /// Decodes a message from the buffer, rejecting messages whose counts don't match the records
+    ///
+    /// Data after the records counted in the header is an error, [`Self::from_vec`] ignores it.
+    ///  See also [`Self::verify_counts`].
+    pub fn from_vec_strict(buffer: &[u8]) -> ProtoResult<Self> {
+        let mut decoder = BinDecoder::new(buffer);
+        let message = Self::read(&mut decoder)?;
+        message.verify_counts()?;
+
+        if !decoder.is_empty() {
+            return Err(ProtoError::from(format!(
+                "{} bytes after the records counted in the header",
+                decoder.len()
+            )));
+        }
+
+        Ok(message)
+    }
+
+    /// Decodes a message, additionally recording where each record was found in the buffer.
+    ///
+    /// This is more costly than [`Message::read`], only use it when the spans are needed.
+    pub fn read_with_spans(decoder: &mut BinDecoder<'_>) -> ProtoResult<(Self, RecordSpans)> {
+        let mut spans = RecordSpans::default();
+        let message = Self::read_message(decoder, Some(&mut spans))?;
+        Ok((message, spans))
+    }
+
+    fn read_message(
+        decoder: &mut BinDecoder<'_>,
+        spans: Option<&mut RecordSpans>,
+    ) -> ProtoResult<Self> {
+        let mut header = Header::read(decoder)?;
+
+        // TODO: return just header, and in the case of the rest of message getting an error.
+        //  this could improve error detection while decoding.
+
+        // get the questions
+        let count = header.query_count() as usize;
+        let mut queries = Vec::with_capacity(count);
+        for _ in 0..count {
+            queries.push(Query::read(decoder)?);
+        }
+
+        // get all counts before header moves
+        let answer_count = header.answer_count() as usize;
+        let name_server_count = header.name_server_count() as usize;
+        let additional_count = header.additional_count() as usize;
+
+        let (answer_spans, name_server_spans, additional_spans) = match spans {
+            Some(spans) => (
+                Some(&mut spans.answers),
+                Some(&mut spans.name_servers),
+                Some(&mut spans.additionals),
+            ),
+            None => (None, None, None),
+        };
+
+        let (answers, _, _) =
+            Self::read_records_with_spans(decoder, answer_count, false, answer_spans)?;
+        let (name_servers, _, _) =
+            Self::read_records_with_spans(decoder, name_server_count, false, name_server_spans)?;
+        let (additionals, edns, signature) =
+            Self::read_records_with_spans(decoder, additional_count, true, additional_spans)?;
+
+        // need to grab error code from EDNS (which might have a higher value)
+        if let Some(edns) = &edns {
+            let high_response_code = edns.rcode_high();
+            header.merge_response_code(high_response_code);
+
+            // 16 is BADSIG in the error of a TSIG record, as the RCODE of a message with EDNS it is
+            //  BADVERS, RFC 6891
+            if header.response_code() == ResponseCode::BADSIG {
+                header.set_response_code(ResponseCode::BADVERS);
+            }
+        }
+
+        Ok(Self {
+            header,
+            queries,
+            answers,
+            name_servers,
+            additionals,
+            signature,
+            edns,
+        })
+    }
+
     /// Encodes the Message into a buffer
-    pub fn to_vec(&self) -> Result<Vec<u8>, ProtoError> {
+    pub fn to_vec(&self) -> ProtoResult<Vec<u8>> {
         // TODO: this feels like the right place to verify the max packet size of the message,
         //  will need to update the header for truncation and the lengths if we send less than the
         //  full response. This needs to conform with the EDNS settings of the server...
-        let mut buffer = Vec::with_capacity(512);
+        let mut buffer = Vec::with_capacity(self.encoded_size());
+        {
+            let mut encoder = BinEncoder::new(&mut buffer);
+            self.emit(&mut encoder)?;
+        }
+
+        Ok(buffer)
+    }
+
+    /// Encodes the Message with uncompressed and lowercase names
+    ///
+    /// All names are lowercase, including those in the RDATA of every type, unlike the canonical
+    ///  form of single records, see [`Record::canonical_form`].
+    pub fn canonical_form(&self) -> ProtoResult<Vec<u8>> {
+        let mut buffer = Vec::with_capacity(self.encoded_size());
         {
             let mut encoder = BinEncoder::new(&mut buffer);
+            encoder.set_canonical_names(true);
+            encoder.set_lowercase_names(true);
             self.emit(&mut encoder)?;
         }
 
@@ -882,6 +1053,41 @@ impl Deref for Message {
     }
 }
 
+/// The sections of a Message which contain records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageSection {
+    /// The answer section, see [`Message::answers`]
+    Answers,
+    /// The authority section, see [`Message::name_servers`]
+    NameServers,
+    /// The additional section, without the EDNS and signature records, see [`Message::additionals`]
+    Additionals,
+}
+
+/// The byte ranges of the records of a decoded Message, see [`Message::read_with_spans`]
+///
+/// The ranges are offsets into the buffer the Message was decoded from, indexed the same way as the
+///  records in the Message.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecordSpans {
+    answers: Vec<Range<usize>>,
+    name_servers: Vec<Range<usize>>,
+    additionals: Vec<Range<usize>>,
+}
+
+impl RecordSpans {
+    /// Returns the byte range of the record at `index` in the `section`
+    pub fn get(&self, index: usize, section: MessageSection) -> Option<Range<usize>> {
+        let spans = match section {
+            MessageSection::Answers => &self.answers,
+            MessageSection::NameServers => &self.name_servers,
+            MessageSection::Additionals => &self.additionals,
+        };
+
+        spans.get(index).cloned()
+    }
+}
+
 /// Alias for a function verifying if a message is properly signed
 pub type MessageVerifier = Box<dyn FnMut(&[u8]) -> ProtoResult<DnsResponse> + Send>;
 
@@ -968,13 +1174,82 @@ impl<'e, I: Iterator<Item = &'e E>, E: 'e + BinEncodable> EmitAndCount for I {
     }
 }
 
+/// Emits the records of a section, keeping each RRset together with the RRSIGs covering it
+///
+/// Consecutive records with the same name, class and type form a unit, where RRSIGs count as the
+///  type they cover. A unit is either emitted completely or not at all, with `atomic` the whole
+///  section is a single unit. Emitting stops at the first unit which doesn't fit.
+///
+/// # Return
+///
+/// The count of records emitted, and the type of the unit which didn't fit, if any
+fn emit_record_units<'r, I: Iterator<Item = &'r Record>>(
+    records: &mut I,
+    atomic: bool,
+    encoder: &mut BinEncoder<'_>,
+) -> ProtoResult<(usize, Option<RecordType>)> {
+    let mut count = 0;
+    let mut unit_count = 0;
+    let mut unit = None;
+    let mut rollback = encoder.set_rollback();
+
+    for record in records {
+        let key = (record.name(), record.dns_class(), covered_type(record));
+        if unit != Some(key) {
+            if !atomic {
+                count += unit_count;
+                unit_count = 0;
+                rollback = encoder.set_rollback();
+            }
+            unit = Some(key);
+        }
+
+        match record.emit(encoder) {
+            Ok(()) => unit_count += 1,
+            Err(e) if matches!(e.kind(), ProtoErrorKind::MaxBufferSizeExceeded(_)) => {
+                rollback.rollback(encoder);
+                return Ok((count, Some(key.2)));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok((count + unit_count, None))
+}
+
+/// The type of the RRset the record belongs to, for RRSIGs this is the type they cover
+fn covered_type(record: &Record) -> RecordType {
+    #[cfg(feature = "dnssec")]
+    {
+        use crate::rr::{dnssec::rdata::RRSIG, RecordData};
+
+        if let Some(rrsig) = RRSIG::try_borrow(record.data()) {
+            return rrsig.type_covered();
+        }
+    }
+
+    record.record_type()
+}
+
 /// Emits the different sections of a message properly
 ///
+/// The records are emitted in units of an RRset with its RRSIGs, a unit is never split. When the
+///  message doesn't fit the maximum size of the encoder, sections are elided in this order:
+///
+/// * the additional section is cut at the last unit which fits, this doesn't set TC
+/// * the authority section is cut at the last unit which fits, unless it holds data which is
+///   required for the response: if the answer section is empty, e.g. for the SOA and proof of a
+///   negative response, the authority section is emitted completely or not at all, and NSEC and
+///   NSEC3 records are never elided
+/// * if required data doesn't fit, the remaining records are omitted and TC is set
+///
+/// Space for the EDNS record and the signature is reserved, so that these are always emitted.
+///
 /// # Return
 ///
 /// In the case of a successful emit, the final header (updated counts, etc) is returned for help with logging, etc.
 #[allow(clippy::too_many_arguments)]
-pub fn emit_message_parts<Q, A, N, D>(
+pub fn emit_message_parts<'r, Q, A, N, D>(
     header: &Header,
     queries: &mut Q,
     answers: &mut A,
@@ -986,27 +1261,36 @@ pub fn emit_message_parts<Q, A, N, D>(
 ) -> ProtoResult<Header>
 where
     Q: EmitAndCount,
-    A: EmitAndCount,
-    N: EmitAndCount,
-    D: EmitAndCount,
+    A: Iterator<Item = &'r Record>,
+    N: Iterator<Item = &'r Record>,
+    D: Iterator<Item = &'r Record>,
 {
     let include_signature: bool = encoder.mode() != EncodeMode::Signing;
     let place = encoder.place::<Header>()?;
 
     let query_count = queries.emit(encoder)?;
-    // TODO: need to do something on max records
-    //  return offset of last emitted record.
-    let answer_count = count_was_truncated(answers.emit(encoder))?;
-    let nameserver_count = count_was_truncated(name_servers.emit(encoder))?;
-    let mut additional_count = count_was_truncated(additionals.emit(encoder))?;
+
+    // reserve the space of the records which are emitted after the sections
+    let reserved = edns.map_or(0, Edns::encoded_size)
+        + signature
+            .iter()
+            .filter(|_| include_signature)
+            .map(BinEncodable::encoded_size)
+            .sum::<usize>();
+    let max_size = encoder.max_size();
+    encoder.set_max_size(max_size.saturating_sub(u16::try_from(reserved).unwrap_or(u16::MAX)));
+
+    let sections = emit_record_sections(answers, name_servers, additionals, encoder);
+    encoder.set_max_size(max_size);
+    let (answer_count, nameserver_count, mut additional_count, mut was_truncated) = sections?;
 
     if let Some(mut edns) = edns.cloned() {
         // need to commit the error code
         edns.set_rcode_high(header.response_code().high());
 
         let count = count_was_truncated(encoder.emit_all(iter::once(&Record::from(&edns))))?;
-        additional_count.0 += count.0;
-        additional_count.1 |= count.1;
+        additional_count += count.0;
+        was_truncated |= count.1;
     } else if header.response_code().high() > 0 {
         warn!(
             "response code: {} for request: {} requires EDNS but none available",
@@ -1020,24 +1304,60 @@ where
     //  part of the additionals section.
     if include_signature {
         let count = count_was_truncated(encoder.emit_all(signature.iter()))?;
-        additional_count.0 += count.0;
-        additional_count.1 |= count.1;
+        additional_count += count.0;
+        was_truncated |= count.1;
     }
 
     let counts = HeaderCounts {
         query_count,
-        answer_count: answer_count.0,
-        nameserver_count: nameserver_count.0,
-        additional_count: additional_count.0,
+        answer_count,
+        nameserver_count,
+        additional_count,
     };
-    let was_truncated =
-        header.truncated() || answer_count.1 || nameserver_count.1 || additional_count.1;
+    let was_truncated = header.truncated() || was_truncated;
 
     let final_header = update_header_counts(header, was_truncated, counts);
     place.replace(encoder, final_header)?;
     Ok(final_header)
 }
 
+/// Emits the answer, authority and additional sections, see `emit_message_parts`
+///
+/// # Return
+///
+/// The counts of the records emitted in each section, and if TC needs to be set
+fn emit_record_sections<'r>(
+    answers: &mut impl Iterator<Item = &'r Record>,
+    name_servers: &mut impl Iterator<Item = &'r Record>,
+    additionals: &mut impl Iterator<Item = &'r Record>,
+    encoder: &mut BinEncoder<'_>,
+) -> ProtoResult<(usize, usize, usize, bool)> {
+    let (answer_count, elided) = emit_record_units(answers, false, encoder)?;
+    if let Some(rtype) = elided {
+        debug!("answer {rtype} does not fit, truncating");
+        return Ok((answer_count, 0, 0, true));
+    }
+
+    let (nameserver_count, elided) = emit_record_units(name_servers, answer_count == 0, encoder)?;
+    match elided {
+        Some(rtype)
+            if answer_count == 0 || rtype == RecordType::NSEC || rtype == RecordType::NSEC3 =>
+        {
+            debug!("authority {rtype} does not fit, truncating");
+            return Ok((answer_count, nameserver_count, 0, true));
+        }
+        Some(rtype) => debug!("authority {rtype} does not fit, eliding"),
+        None => (),
+    }
+
+    let (additional_count, elided) = emit_record_units(additionals, false, encoder)?;
+    if let Some(rtype) = elided {
+        debug!("additional {rtype} does not fit, eliding");
+    }
+
+    Ok((answer_count, nameserver_count, additional_count, false))
+}
+
 impl BinEncodable for Message {
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
         emit_message_parts(
@@ -1053,46 +1373,26 @@ impl BinEncodable for Message {
 
         Ok(())
     }
-}
 
-impl<'r> BinDecodable<'r> for Message {
-    fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
-        let mut header = Header::read(decoder)?;
-
-        // TODO: return just header, and in the case of the rest of message getting an error.
-        //  this could improve error detection while decoding.
-
-        // get the questions
-        let count = header.query_count() as usize;
-        let mut queries = Vec::with_capacity(count);
-        for _ in 0..count {
-            queries.push(Query::read(decoder)?);
+    /// The sum of the sizes of all sections, names are counted without compression
+    fn encoded_size(&self) -> usize {
+        fn sum<T: BinEncodable>(section: &[T]) -> usize {
+            section.iter().map(BinEncodable::encoded_size).sum()
         }
 
-        // get all counts before header moves
-        let answer_count = header.answer_count() as usize;
-        let name_server_count = header.name_server_count() as usize;
-        let additional_count = header.additional_count() as usize;
-
-        let (answers, _, _) = Self::read_records(decoder, answer_count, false)?;
-        let (name_servers, _, _) = Self::read_records(decoder, name_server_count, false)?;
-        let (additionals, edns, signature) = Self::read_records(decoder, additional_count, true)?;
-
-        // need to grab error code from EDNS (which might have a higher value)
-        if let Some(edns) = &edns {
-            let high_response_code = edns.rcode_high();
-            header.merge_response_code(high_response_code);
-        }
+        self.header.encoded_size()
+            + sum(&self.queries)
+            + sum(&self.answers)
+            + sum(&self.name_servers)
+            + sum(&self.additionals)
+            + self.edns.as_ref().map_or(0, Edns::encoded_size)
+            + sum(&self.signature)
+    }
+}
 
-        Ok(Self {
-            header,
-            queries,
-            answers,
-            name_servers,
-            additionals,
-            signature,
-            edns,
-        })
+impl<'r> BinDecodable<'r> for Message {
+    fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
+        Self::read_message(decoder, None)
     }
 }
 
@@ -1141,6 +1441,8 @@ impl fmt::Display for Message {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rr::{rdata::A, Name, RData};
+    use crate::xfer::DnsResponse;
 
     #[test]
     fn test_emit_and_read_header() {
@@ -1200,6 +1502,42 @@ mod tests {
     }
 
     #[cfg(test)]
+    #[test]
+    fn test_canonical_form() {
+        use crate::rr::rdata::CNAME;
+
+        let name = Name::from_ascii("WWW.Example.COM.").unwrap();
+        let mut message = Message::new();
+        message
+            .set_id(0x1234)
+            .add_query(Query::query(name.clone(), RecordType::CNAME))
+            .add_answer(Record::from_rdata(
+                name,
+                300,
+                RData::CNAME(CNAME(Name::from_ascii("Host.Example.COM.").unwrap())),
+            ));
+
+        let owner = b"\x03www\x07example\x03com\x00";
+        let mut expected = vec![0x12, 0x34, 0, 0, 0, 1, 0, 1, 0, 0, 0, 0];
+        // query
+        expected.extend_from_slice(owner);
+        expected.extend_from_slice(&[0, 5, 0, 1]);
+        // answer, the owner is not compressed
+        expected.extend_from_slice(owner);
+        expected.extend_from_slice(&[0, 5, 0, 1, 0, 0, 1, 0x2c, 0, 18]);
+        expected.extend_from_slice(b"\x04host\x07example\x03com\x00");
+
+        let canonical = message.canonical_form().unwrap();
+        assert_eq!(canonical, expected);
+        assert_eq!(
+            canonical[12 + 17 + 4..],
+            message.answers()[0].canonical_form().unwrap()[..]
+        );
+
+        // the regular encoding compresses the owner
+        assert!(message.to_vec().unwrap().len() < canonical.len());
+    }
+
     fn test_emit_and_read(message: Message) {
         let mut byte_vec: Vec<u8> = Vec::with_capacity(512);
         {
@@ -1301,6 +1639,148 @@ mod tests {
         assert_eq!(message.id(), 4096);
     }
 
+    fn edns_message(edit: impl FnOnce(&mut Message)) -> Vec<u8> {
+        let mut message = Message::new();
+        message
+            .add_query(Query::query(
+                Name::from_ascii("www.example.com.").unwrap(),
+                RecordType::A,
+            ))
+            .set_edns(Edns::new());
+        edit(&mut message);
+        message.to_vec().unwrap()
+    }
+
+    #[test]
+    fn test_multiple_edns() {
+        let buf = edns_message(|message| {
+            message.add_additional(Record::from(&Edns::new()));
+        });
+
+        assert!(Message::from_vec(&buf).is_err());
+    }
+
+    #[test]
+    fn test_verify_counts() {
+        let buf = edns_message(|message| {
+            message
+                .add_answer(Record::stub())
+                .add_additional(Record::stub());
+        });
+
+        let message = Message::from_vec_strict(&buf).unwrap();
+        message.verify_counts().unwrap();
+        assert_eq!(message.answer_count_actual(), 1);
+        // the OPT record is counted
+        assert_eq!(message.additional_count(), 2);
+
+        // a message built without updating the counts
+        let mut built = message.clone();
+        built.add_answer(Record::stub());
+        assert!(built.verify_counts().is_err());
+        assert_eq!(built.answer_count(), 1);
+        assert_eq!(built.answer_count_actual(), 2);
+
+        // the header counts one record less than the message holds, the OPT record is not read
+        let mut buf = buf;
+        buf[11] -= 1;
+        let message = Message::from_vec(&buf).unwrap();
+        assert!(message.extensions().is_none());
+        assert!(Message::from_vec_strict(&buf).is_err());
+    }
+
+    #[test]
+    fn test_edns_outside_additionals() {
+        let buf = edns_message(|message| {
+            message.add_answer(Record::from(&Edns::new()));
+        });
+
+        assert!(Message::from_vec(&buf).is_err());
+    }
+
+    #[test]
+    fn test_edns_not_root() {
+        let buf = edns_message(|message| {
+            let mut opt = Record::from(&Edns::new());
+            opt.set_name(Name::from_ascii("example.com.").unwrap());
+            message.extensions_mut().take();
+            message.add_additional(opt);
+        });
+
+        assert!(Message::from_vec(&buf).is_err());
+    }
+
+    #[test]
+    fn test_edns_version() {
+        let buf = edns_message(|message| {
+            message
+                .extensions_mut()
+                .get_or_insert_with(Edns::new)
+                .set_version(1);
+        });
+
+        let message = Message::from_vec(&buf).expect("failed to parse message");
+        assert_eq!(message.extensions().as_ref().map(Edns::version), Some(1));
+    }
+
+    #[test]
+    fn test_badvers() {
+        let buf = edns_message(|message| {
+            message.set_response_code(ResponseCode::BADVERS);
+        });
+
+        let message = Message::from_vec(&buf).expect("failed to parse message");
+        assert_eq!(message.response_code(), ResponseCode::BADVERS);
+    }
+
+    #[test]
+    fn test_badvers_error() {
+        let buf = edns_message(|message| {
+            message.set_response_code(ResponseCode::BADVERS);
+            message
+                .extensions_mut()
+                .get_or_insert_with(Edns::new)
+                .set_version(1);
+        });
+
+        let message = Message::from_vec(&buf).expect("failed to parse message");
+        let response = DnsResponse::from_message(message).unwrap();
+        let error = ProtoError::from_response(response, false).expect_err("BADVERS is an error");
+        assert!(matches!(
+            error.kind(),
+            ProtoErrorKind::BadEdnsVersion {
+                server_version: 1,
+                client_version: 0,
+            }
+        ));
+    }
+
+    #[test]
+    fn test_encoded_size() {
+        let name = Name::from_ascii("www.example.com.").unwrap();
+        let mut message = Message::new();
+        message
+            .add_query(Query::query(name.clone(), RecordType::A))
+            .set_edns(Edns::new());
+        for i in 0..64 {
+            message.add_answer(Record::from_rdata(
+                name.clone(),
+                300,
+                RData::A(A::new(192, 0, 2, i)),
+            ));
+        }
+
+        let size = message.encoded_size();
+        assert!(size > 512);
+
+        let mut buffer = Vec::with_capacity(size);
+        let capacity = buffer.capacity();
+        message.emit(&mut BinEncoder::new(&mut buffer)).unwrap();
+
+        assert_eq!(buffer.capacity(), capacity);
+        assert!(buffer.len() <= size);
+    }
+
     #[test]
     fn rdata_zero_roundtrip() {
         let buf = &[
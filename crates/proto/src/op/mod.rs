@@ -17,6 +17,7 @@
 //! Operations to send with a `Client` or server, e.g. `Query`, `Message`, or `UpdateMessage` can
 //! be used together to either query or update resource records sets.
 
+pub mod dso;
 mod edns;
 pub mod header;
 mod lower_query;
@@ -26,11 +27,13 @@ pub mod query;
 pub mod response_code;
 pub mod update_message;
 
+pub use self::dso::{DsoMessage, DsoTlv};
 pub use self::edns::Edns;
 pub use self::header::Header;
 pub use self::header::MessageType;
 pub use self::message::{
-    Message, MessageFinalizer, MessageParts, MessageVerifier, NoopMessageFinalizer,
+    Message, MessageFinalizer, MessageParts, MessageSection, MessageVerifier, NoopMessageFinalizer,
+    RecordSpans,
 };
 pub use self::op_code::OpCode;
 pub use self::query::Query;
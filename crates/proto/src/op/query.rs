@@ -233,6 +233,10 @@ impl BinEncodable for Query {
 
         Ok(())
     }
+
+    fn encoded_size(&self) -> usize {
+        self.name.encoded_size() + 4
+    }
 }
 
 impl<'r> BinDecodable<'r> for Query {
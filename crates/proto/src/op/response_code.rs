@@ -61,6 +61,7 @@ use std::fmt::{Display, Formatter};
 ///  ```
 #[derive(Debug, Eq, PartialEq, PartialOrd, Copy, Clone, Hash)]
 #[allow(dead_code)]
+#[non_exhaustive]
 pub enum ResponseCode {
     /// No Error [RFC 1035](https://tools.ietf.org/html/rfc1035)
     NoError,
@@ -11,7 +11,10 @@ use std::fmt::Debug;
 
 use crate::{
     op::{Edns, Message, MessageType, OpCode, Query},
-    rr::{rdata::SOA, DNSClass, Name, RData, Record, RecordSet, RecordType},
+    rr::{
+        rdata::{opt::EdnsOption, SOA},
+        DNSClass, Name, RData, Record, RecordSet, RecordType,
+    },
 };
 
 /// To reduce errors in using the Message struct as an Update, this will do the call throughs
@@ -608,13 +611,11 @@ pub fn zone_transfer(zone_origin: Name, last_soa: Option<SOA>) -> Message {
         message.add_name_server(record);
     }
 
-    // Extended dns
+    // Extended dns, the EXPIRE option asks for the remaining expire time of the zone, RFC 7314
     {
-        message
-            .extensions_mut()
-            .get_or_insert_with(Edns::new)
-            .set_max_payload(MAX_PAYLOAD_LEN)
-            .set_version(0);
+        let edns = message.extensions_mut().get_or_insert_with(Edns::new);
+        edns.set_max_payload(MAX_PAYLOAD_LEN).set_version(0);
+        edns.options_mut().insert(EdnsOption::Expire(None));
     }
 
     message
@@ -14,7 +14,7 @@ use futures_util::TryFutureExt;
 use openssl::x509::X509;
 use tokio_openssl::SslStream as TokioTlsStream;
 
-use crate::error::ProtoError;
+use crate::error::{ProtoError, ProtoResult};
 use crate::iocompat::AsyncIoStdAsTokio;
 use crate::iocompat::AsyncIoTokioAsStd;
 use crate::tcp::{Connect, DnsTcpStream, TcpClientStream};
@@ -71,7 +71,7 @@ impl<S: DnsTcpStream> TlsClientStreamBuilder<S> {
         name_server: SocketAddr,
         dns_name: String,
     ) -> (
-        Pin<Box<dyn Future<Output = Result<TlsClientStream<S>, ProtoError>> + Send>>,
+        Pin<Box<dyn Future<Output = ProtoResult<TlsClientStream<S>>> + Send>>,
         BufDnsStreamHandle,
     )
     where
@@ -109,7 +109,7 @@ impl<S: Connect> TlsClientStreamBuilder<S> {
         name_server: SocketAddr,
         dns_name: String,
     ) -> (
-        Pin<Box<dyn Future<Output = Result<TlsClientStream<S>, ProtoError>> + Send>>,
+        Pin<Box<dyn Future<Output = ProtoResult<TlsClientStream<S>>> + Send>>,
         BufDnsStreamHandle,
     ) {
         let (stream_future, sender) = self.0.build(name_server, dns_name);
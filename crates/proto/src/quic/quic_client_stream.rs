@@ -20,7 +20,7 @@ use rustls::{version::TLS13, ClientConfig as TlsClientConfig};
 
 use crate::udp::{DnsUdpSocket, QuicLocalAddr};
 use crate::{
-    error::ProtoError,
+    error::{ProtoError, ProtoResult},
     quic::quic_socket::QuinnAsyncUdpSocketAdapter,
     quic::quic_stream::{DoqErrorCode, QuicStream},
     udp::UdpSocket,
@@ -55,23 +55,24 @@ impl QuicClientStream {
         QuicClientStreamBuilder::default()
     }
 
-    async fn inner_send(
-        connection: Connection,
-        message: DnsRequest,
-    ) -> Result<DnsResponse, ProtoError> {
+    async fn inner_send(connection: Connection, message: DnsRequest) -> ProtoResult<DnsResponse> {
         let (send_stream, recv_stream) = connection.open_bi().await?;
 
         // RFC: The mapping specified here requires that the client selects a separate
         //  QUIC stream for each query. The server then uses the same stream to provide all the response messages for that query.
         let mut stream = QuicStream::new(send_stream, recv_stream);
 
+        // the id is sent as 0, the response is handed back with the id of the request
+        let query_id = message.id();
         stream.send(message.into_parts().0).await?;
 
         // The client MUST send the DNS query over the selected stream,
         // and MUST indicate through the STREAM FIN mechanism that no further data will be sent on that stream.
         stream.finish().await?;
 
-        stream.receive().await
+        let mut response = stream.receive().await?;
+        response.restore_id(query_id);
+        Ok(response)
     }
 }
 
@@ -196,7 +197,7 @@ impl QuicClientStreamBuilder {
         future: F,
         name_server: SocketAddr,
         dns_name: String,
-    ) -> Result<QuicClientStream, ProtoError>
+    ) -> ProtoResult<QuicClientStream>
     where
         S: DnsUdpSocket + QuicLocalAddr + 'static,
         F: Future<Output = std::io::Result<S>> + Send,
@@ -217,7 +218,7 @@ impl QuicClientStreamBuilder {
         self,
         name_server: SocketAddr,
         dns_name: String,
-    ) -> Result<QuicClientStream, ProtoError> {
+    ) -> ProtoResult<QuicClientStream> {
         let connect = if let Some(bind_addr) = self.bind_addr {
             <tokio::net::UdpSocket as UdpSocket>::connect_with_bind(name_server, bind_addr)
         } else {
@@ -236,7 +237,7 @@ impl QuicClientStreamBuilder {
         mut endpoint: Endpoint,
         name_server: SocketAddr,
         dns_name: String,
-    ) -> Result<QuicClientStream, ProtoError> {
+    ) -> ProtoResult<QuicClientStream> {
         // ensure the ALPN protocol is set correctly
         let mut crypto_config = if let Some(crypto_config) = self.crypto_config {
             crypto_config
@@ -275,7 +276,7 @@ impl QuicClientStreamBuilder {
 }
 
 /// Default crypto options for quic
-pub fn client_config_tls13() -> Result<TlsClientConfig, ProtoError> {
+pub fn client_config_tls13() -> ProtoResult<TlsClientConfig> {
     use rustls::RootCertStore;
     #[cfg_attr(
         not(any(feature = "native-certs", feature = "webpki-roots")),
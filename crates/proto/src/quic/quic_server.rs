@@ -10,7 +10,7 @@ use std::{io, net::SocketAddr, sync::Arc};
 use quinn::{Connection, Endpoint, ServerConfig};
 use rustls::{server::ServerConfig as TlsServerConfig, version::TLS13, Certificate, PrivateKey};
 
-use crate::{error::ProtoError, udp::UdpSocket};
+use crate::{error::ProtoResult, udp::UdpSocket};
 
 use super::{
     quic_config,
@@ -28,7 +28,7 @@ impl QuicServer {
         name_server: SocketAddr,
         cert: Vec<Certificate>,
         key: PrivateKey,
-    ) -> Result<Self, ProtoError> {
+    ) -> ProtoResult<Self> {
         // setup a new socket for the server to use
         let socket = <tokio::net::UdpSocket as UdpSocket>::bind(name_server).await?;
         Self::with_socket(socket, cert, key)
@@ -39,7 +39,7 @@ impl QuicServer {
         socket: tokio::net::UdpSocket,
         cert: Vec<Certificate>,
         key: PrivateKey,
-    ) -> Result<Self, ProtoError> {
+    ) -> ProtoResult<Self> {
         let mut config = TlsServerConfig::builder()
             .with_safe_default_cipher_suites()
             .with_safe_default_kx_groups()
@@ -71,7 +71,7 @@ impl QuicServer {
     /// # Returns
     ///
     /// A remote connection that could have many potential bi-directional streams and the remote socket address
-    pub async fn next(&mut self) -> Result<Option<(QuicStreams, SocketAddr)>, ProtoError> {
+    pub async fn next(&mut self) -> ProtoResult<Option<(QuicStreams, SocketAddr)>> {
         let connecting = match self.endpoint.accept().await {
             Some(conn) => conn,
             None => return Ok(None),
@@ -98,7 +98,7 @@ pub struct QuicStreams {
 
 impl QuicStreams {
     /// Get the next bi directional stream from the client
-    pub async fn next(&mut self) -> Option<Result<QuicStream, ProtoError>> {
+    pub async fn next(&mut self) -> Option<ProtoResult<QuicStream>> {
         match self.connection.accept_bi().await {
             Ok((send_stream, receive_stream)) => {
                 Some(Ok(QuicStream::new(send_stream, receive_stream)))
@@ -10,7 +10,7 @@ use quinn::{RecvStream, SendStream, VarInt};
 use tracing::debug;
 
 use crate::{
-    error::{ProtoError, ProtoErrorKind},
+    error::{ProtoError, ProtoErrorKind, ProtoResult},
     op::Message,
     xfer::DnsResponse,
 };
@@ -48,6 +48,7 @@ pub(crate) const DOQ_ALPN: &[u8] = b"doq";
 ///     Alternative error code used for tests.
 /// ```
 #[derive(Clone, Copy)]
+#[non_exhaustive]
 pub enum DoqErrorCode {
     /// No error. This is used when the connection or stream needs to be closed, but there is no error to signal.
     NoError,
@@ -124,7 +125,7 @@ impl QuicStream {
     }
 
     /// Send the DNS message to the other side
-    pub async fn send(&mut self, mut message: Message) -> Result<(), ProtoError> {
+    pub async fn send(&mut self, mut message: Message) -> ProtoResult<()> {
         // RFC: When sending queries over a QUIC connection, the DNS Message ID MUST be set to zero. The stream mapping for DoQ allows for
         // unambiguous correlation of queries and responses and so the Message ID field is not required.
         message.set_id(0);
@@ -135,7 +136,7 @@ impl QuicStream {
     }
 
     /// Send pre-encoded bytes, warning, QUIC requires the message id to be 0.
-    pub async fn send_bytes(&mut self, bytes: Bytes) -> Result<(), ProtoError> {
+    pub async fn send_bytes(&mut self, bytes: Bytes) -> ProtoResult<()> {
         // In order that multiple responses can be parsed, a 2-octet length field is used in exactly the same way as the 2-octet length
         // field defined for DNS over TCP [RFC1035]. The practical result of this is that the content of each QUIC stream is exactly
         // the same as the content of a TCP connection that would manage exactly one query.All DNS messages (queries and responses)
@@ -151,13 +152,13 @@ impl QuicStream {
     }
 
     /// finishes the send stream, i.e. there will be no more data sent to the remote
-    pub async fn finish(&mut self) -> Result<(), ProtoError> {
+    pub async fn finish(&mut self) -> ProtoResult<()> {
         self.send_stream.finish().await?;
         Ok(())
     }
 
     /// Receive a single packet
-    pub async fn receive(&mut self) -> Result<DnsResponse, ProtoError> {
+    pub async fn receive(&mut self) -> ProtoResult<DnsResponse> {
         let bytes = self.receive_bytes().await?;
         let message = Message::from_vec(&bytes)?;
 
@@ -174,7 +175,7 @@ impl QuicStream {
 
     // TODO: we should change the protocol handlers to work with Messages since some require things like 0 for the Message ID.
     /// Receive a single packet as raw bytes
-    pub async fn receive_bytes(&mut self) -> Result<BytesMut, ProtoError> {
+    pub async fn receive_bytes(&mut self) -> ProtoResult<BytesMut> {
         // following above, the data should be first the length, followed by the message(s)
         let mut len = [0u8; 2];
         self.receive_stream.read_exact(&mut len).await?;
@@ -200,14 +201,14 @@ impl QuicStream {
     }
 
     /// Reset the sending stream due to some error
-    pub fn reset(&mut self, code: DoqErrorCode) -> Result<(), ProtoError> {
+    pub fn reset(&mut self, code: DoqErrorCode) -> ProtoResult<()> {
         self.send_stream
             .reset(code.into())
             .map_err(|_| ProtoError::from(ProtoErrorKind::QuinnUnknownStreamError))
     }
 
     /// Stop the receiving stream due to some error
-    pub fn stop(&mut self, code: DoqErrorCode) -> Result<(), ProtoError> {
+    pub fn stop(&mut self, code: DoqErrorCode) -> ProtoResult<()> {
         self.receive_stream
             .stop(code.into())
             .map_err(|_| ProtoError::from(ProtoErrorKind::QuinnUnknownStreamError))
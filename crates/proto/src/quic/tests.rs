@@ -9,12 +9,15 @@
 
 use std::{env, net::SocketAddr, path::Path, str::FromStr, sync::Arc};
 
+use bytes::Bytes;
 use futures_util::StreamExt;
 use rustls::{ClientConfig, KeyLogFile};
+use tokio::task::JoinHandle;
 
 use crate::{
+    error::ProtoErrorKind,
     op::{Message, Query},
-    quic::QuicClientStreamBuilder,
+    quic::{QuicClientStream, QuicClientStreamBuilder},
     rr::{Name, RecordType},
     rustls::tls_server,
     xfer::DnsRequestSender,
@@ -22,7 +25,7 @@ use crate::{
 
 use super::quic_server::QuicServer;
 
-async fn server_responder(mut server: QuicServer) {
+async fn server_responder(mut server: QuicServer, response_id: u16) {
     while let Some((mut conn, addr)) = server
         .next()
         .await
@@ -33,19 +36,22 @@ async fn server_responder(mut server: QuicServer) {
         while let Some(stream) = conn.next().await {
             let mut stream = stream.expect("new client stream failed");
 
+            // receive() rejects any query with an id other than 0
             let client_message = stream.receive().await.expect("failed to receive");
 
             // just response with the same message.
+            let mut message = client_message.into_message();
+            message.set_id(response_id);
             stream
-                .send(client_message.into_message())
+                .send_bytes(Bytes::from(message.to_vec().unwrap()))
                 .await
                 .expect("failed to send response")
         }
     }
 }
 
-#[tokio::test]
-async fn test_quic_stream() {
+/// Starts a server answering with `response_id`, and connects a client to it
+async fn quic_client_and_server(response_id: u16) -> (QuicClientStream, JoinHandle<()>) {
     let dns_name = "ns.example.com";
 
     let server_path = env::var("TDNS_WORKSPACE_ROOT").unwrap_or_else(|_| "../..".to_owned());
@@ -72,7 +78,7 @@ async fn test_quic_stream() {
     // kick off the server
     let server_addr = quic_ns.local_addr().expect("no address");
     println!("testing quic on: {server_addr}");
-    let server_join = tokio::spawn(server_responder(quic_ns));
+    let server_join = tokio::spawn(server_responder(quic_ns, response_id));
 
     // now construct the client
     let mut roots = rustls::RootCertStore::empty();
@@ -90,16 +96,23 @@ async fn test_quic_stream() {
     builder.crypto_config(client_config);
 
     println!("starting quic connect");
-    let mut client_stream = builder
+    let client_stream = builder
         .build(server_addr, dns_name.to_string())
         .await
         .expect("failed to connect");
 
     println!("connected client to server");
 
+    (client_stream, server_join)
+}
+
+#[tokio::test]
+async fn test_quic_stream() {
+    let (mut client_stream, server_join) = quic_client_and_server(0).await;
+
     // create a test message, send and then receive...
     let mut message = Message::default();
-    message.add_query(Query::query(
+    message.set_id(1234).add_query(Query::query(
         Name::from_str("www.example.test.").unwrap(),
         RecordType::AAAA,
     ));
@@ -115,8 +128,32 @@ async fn test_quic_stream() {
         .expect("no response received")
         .expect("failed to read response");
 
+    // the id was sent as 0, and restored on the response
     assert_eq!(*response, message);
+    assert_eq!(Message::from_vec(response.as_buffer()).unwrap().id(), 1234);
 
     // and finally kill the server
     server_join.abort();
 }
+
+#[tokio::test]
+async fn test_quic_response_id_not_0() {
+    let (mut client_stream, server_join) = quic_client_and_server(7).await;
+
+    let mut message = Message::default();
+    message.set_id(1234).add_query(Query::query(
+        Name::from_str("www.example.test.").unwrap(),
+        RecordType::AAAA,
+    ));
+
+    let error = client_stream
+        .send_message(message.into())
+        .next()
+        .await
+        .expect("no response received")
+        .expect_err("response id should be rejected");
+
+    assert!(matches!(error.kind(), ProtoErrorKind::QuicMessageIdNot0(7)));
+
+    server_join.abort();
+}
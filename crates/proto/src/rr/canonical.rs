@@ -0,0 +1,590 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Canonical forms of names, records and RRsets
+//!
+//! The canonical forms are the input of DNSSEC signatures and digests,
+//!  [RFC 4034 section 6](https://tools.ietf.org/html/rfc4034#section-6), and of zone digests,
+//!  [RFC 8976](https://tools.ietf.org/html/rfc8976). The `write_*` functions encode one record at a
+//!  time into the writer, which can be a `Vec<u8>` or an adapter feeding a digest.
+
+use std::borrow::Borrow;
+use std::io::Write;
+
+use crate::{
+    error::ProtoResult,
+    rr::{DNSClass, Name, RData, Record, RecordType},
+    serialize::binary::{BinEncodable, BinEncoder},
+};
+
+#[cfg(feature = "dnssec")]
+use crate::rr::dnssec::{
+    rdata::{sig, SIG},
+    tbs,
+};
+
+/// The type code of ZONEMD records, RFC 8976
+const ZONEMD: u16 = 63;
+
+/// Returns true if the names in the RDATA of the `record_type` are lowercase in canonical form
+///
+/// [RFC 4034 section 6.2](https://tools.ietf.org/html/rfc4034#section-6.2), item 3, as updated by
+///  [RFC 6840 section 5.1](https://tools.ietf.org/html/rfc6840#section-5.1) which removes NSEC.
+///  The names in the RDATA of all other types, e.g. SVCB, keep their case.
+pub fn lowercases_rdata_names(record_type: RecordType) -> bool {
+    matches!(
+        record_type,
+        RecordType::NS
+            | RecordType::CNAME
+            | RecordType::SOA
+            | RecordType::PTR
+            | RecordType::MX
+            | RecordType::NAPTR
+            | RecordType::SRV
+            | RecordType::SIG
+            | RecordType::RRSIG
+    )
+}
+
+/// Emits the name in canonical form, i.e. uncompressed and lowercase
+pub fn emit_name(encoder: &mut BinEncoder<'_>, name: &Name) -> ProtoResult<()> {
+    let was_lowercase = encoder.is_lowercase_names();
+    encoder.set_lowercase_names(true);
+
+    let result = name.emit_as_canonical(encoder, true);
+    encoder.set_lowercase_names(was_lowercase);
+
+    result
+}
+
+/// Emits the RDATA in canonical form, without its length
+pub fn emit_rdata(encoder: &mut BinEncoder<'_>, rdata: &RData) -> ProtoResult<()> {
+    let was_canonical = encoder.is_canonical_names();
+    let was_lowercase = encoder.is_lowercase_names();
+    encoder.set_canonical_names(true);
+    encoder.set_lowercase_names(lowercases_rdata_names(rdata.record_type()));
+
+    let result = rdata.emit(encoder);
+    encoder.set_canonical_names(was_canonical);
+    encoder.set_lowercase_names(was_lowercase);
+
+    result
+}
+
+/// Returns the RDATA in canonical form, without its length
+pub fn rdata_to_bytes(rdata: &RData) -> ProtoResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    emit_rdata(&mut BinEncoder::new(&mut bytes), rdata)?;
+
+    Ok(bytes)
+}
+
+/// Writes a record in canonical form, with the given TTL
+///
+/// ```text
+/// RR = owner | type | class | TTL | RDATA length | RDATA
+/// ```
+pub fn write_record<W: Write>(
+    writer: &mut W,
+    name: &Name,
+    dns_class: DNSClass,
+    ttl: u32,
+    rdata: &RData,
+) -> ProtoResult<()> {
+    let mut owner = Vec::new();
+    emit_name(&mut BinEncoder::new(&mut owner), name)?;
+
+    write_canonical_rdata(
+        writer,
+        &owner,
+        rdata.record_type(),
+        dns_class,
+        ttl,
+        &rdata_to_bytes(rdata)?,
+    )
+}
+
+/// Returns the record in canonical form
+pub fn record_to_bytes(record: &Record) -> ProtoResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    write_record(
+        &mut bytes,
+        record.name(),
+        record.dns_class(),
+        record.ttl(),
+        record.data(),
+    )?;
+
+    Ok(bytes)
+}
+
+/// Writes the RRset in canonical form and order, all records with the owner `name` and `ttl`
+///
+/// The records are ordered by their canonical RDATA,
+///  [RFC 4034 section 6.3](https://tools.ietf.org/html/rfc4034#section-6.3), duplicates are
+///  written once.
+pub fn write_rrset<'r, W, I>(
+    writer: &mut W,
+    name: &Name,
+    dns_class: DNSClass,
+    ttl: u32,
+    rdatas: I,
+) -> ProtoResult<()>
+where
+    W: Write,
+    I: IntoIterator<Item = &'r RData>,
+{
+    let mut owner = Vec::new();
+    emit_name(&mut BinEncoder::new(&mut owner), name)?;
+
+    let mut rdatas = rdatas
+        .into_iter()
+        .map(|rdata| Ok((rdata.record_type(), rdata_to_bytes(rdata)?)))
+        .collect::<ProtoResult<Vec<_>>>()?;
+    rdatas.sort_by(|(l_type, l), (r_type, r)| {
+        u16::from(*l_type).cmp(&(*r_type).into()).then(l.cmp(r))
+    });
+    rdatas.dedup();
+
+    for (record_type, rdata) in &rdatas {
+        write_canonical_rdata(writer, &owner, *record_type, dns_class, ttl, rdata)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the input of the signature of an RRSIG over the RRset, RFC 4034 section 3.1.8.1
+///
+/// ```text
+/// signature = sign(RRSIG_RDATA | RR(1) | RR(2)... )
+/// ```
+///
+/// Only the `records` with the `name`, `dns_class` and the type covered by the RRSIG are part of
+///  the RRset. The owner name of a wildcard expansion is replaced by the wildcard, see
+///  [`tbs::determine_name`], and the TTL by the original TTL of the RRSIG.
+#[cfg(feature = "dnssec")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+pub fn write_rrsig_input<W: Write, B: Borrow<Record>>(
+    writer: &mut W,
+    name: &Name,
+    dns_class: DNSClass,
+    sig: &SIG,
+    records: &[B],
+) -> ProtoResult<()> {
+    let owner = tbs::determine_name(name, sig.num_labels())?;
+
+    let mut rrsig_rdata = Vec::new();
+    sig::emit_pre_sig(
+        &mut BinEncoder::new(&mut rrsig_rdata),
+        sig.type_covered(),
+        sig.algorithm(),
+        owner.num_labels(),
+        sig.original_ttl(),
+        sig.sig_expiration(),
+        sig.sig_inception(),
+        sig.key_tag(),
+        sig.signer_name(),
+    )?;
+    writer.write_all(&rrsig_rdata)?;
+
+    let rrset = records.iter().map(|record| -> &Record { record.borrow() });
+    let rrset = rrset.filter(|record| {
+        record.dns_class() == dns_class
+            && record.record_type() == sig.type_covered()
+            && record.name() == name
+    });
+
+    write_rrset(
+        writer,
+        &owner,
+        dns_class,
+        sig.original_ttl(),
+        rrset.map(Record::data),
+    )
+}
+
+/// Writes the input of the digest of a zone, RFC 8976 section 3.3.1
+///
+/// The records of the zone at or below the `origin` are written in canonical form and order, by
+///  owner name, type and RDATA, duplicates once. The ZONEMD RRset of the apex and the RRSIGs
+///  covering it are excluded. Records with owner names outside of the zone are skipped, occluded
+///  records below delegations are not detected and must not be passed.
+pub fn write_zonemd_input<W: Write, B: Borrow<Record>>(
+    writer: &mut W,
+    origin: &Name,
+    records: &[B],
+) -> ProtoResult<()> {
+    let mut zone = Vec::with_capacity(records.len());
+    for record in records {
+        let record = record.borrow();
+        if !origin.zone_of(record.name()) {
+            continue;
+        }
+
+        let rdata = rdata_to_bytes(record.data())?;
+        if record.name() == origin && is_zonemd(record.record_type(), &rdata) {
+            continue;
+        }
+
+        zone.push((record, rdata));
+    }
+
+    zone.sort_by(|(l, l_rdata), (r, r_rdata)| {
+        l.name()
+            .cmp(r.name())
+            .then_with(|| u16::from(l.record_type()).cmp(&r.record_type().into()))
+            .then_with(|| l.dns_class().cmp(&r.dns_class()))
+            .then_with(|| l_rdata.cmp(r_rdata))
+    });
+    zone.dedup_by(|(l, l_rdata), (r, r_rdata)| {
+        l.name() == r.name()
+            && l.record_type() == r.record_type()
+            && l.dns_class() == r.dns_class()
+            && l_rdata == r_rdata
+    });
+
+    let mut owner = Vec::new();
+    let mut last_name: Option<&Name> = None;
+    for (record, rdata) in &zone {
+        if last_name != Some(record.name()) {
+            owner.clear();
+            emit_name(&mut BinEncoder::new(&mut owner), record.name())?;
+            last_name = Some(record.name());
+        }
+
+        write_canonical_rdata(
+            writer,
+            &owner,
+            record.record_type(),
+            record.dns_class(),
+            record.ttl(),
+            rdata,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Returns true for ZONEMD records and the RRSIGs covering them, the type covered is the first
+///  field of the RRSIG RDATA
+fn is_zonemd(record_type: RecordType, rdata: &[u8]) -> bool {
+    match record_type {
+        RecordType::RRSIG => rdata.get(..2) == Some(&ZONEMD.to_be_bytes()[..]),
+        record_type => u16::from(record_type) == ZONEMD,
+    }
+}
+
+/// Writes a record from its canonical owner name and RDATA
+fn write_canonical_rdata<W: Write>(
+    writer: &mut W,
+    owner: &[u8],
+    record_type: RecordType,
+    dns_class: DNSClass,
+    ttl: u32,
+    rdata: &[u8],
+) -> ProtoResult<()> {
+    let rdata_length = u16::try_from(rdata.len()).map_err(|_| "RDATA exceeds u16::MAX")?;
+
+    writer.write_all(owner)?;
+    writer.write_all(&u16::from(record_type).to_be_bytes())?;
+    writer.write_all(&u16::from(dns_class).to_be_bytes())?;
+    writer.write_all(&ttl.to_be_bytes())?;
+    writer.write_all(&rdata_length.to_be_bytes())?;
+    writer.write_all(rdata)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::str::FromStr;
+
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::rr::rdata::{A, AAAA, CNAME, MX, NS, NULL, SOA, SVCB};
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).unwrap()
+    }
+
+    fn hex(hex: &str) -> Vec<u8> {
+        data_encoding::HEXLOWER_PERMISSIVE
+            .decode(hex.replace(char::is_whitespace, "").as_bytes())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_rdata_names() {
+        let cname = RData::CNAME(CNAME(name("WWW.Example.COM.")));
+        assert_eq!(
+            rdata_to_bytes(&cname).unwrap(),
+            b"\x03www\x07example\x03com\x00"
+        );
+
+        // the names in the RDATA of newer types keep their case
+        let svcb = RData::SVCB(SVCB::new(
+            0,
+            Name::from_ascii("Svc.Example.COM.").unwrap(),
+            vec![],
+        ));
+        assert_eq!(
+            rdata_to_bytes(&svcb).unwrap(),
+            b"\x00\x00\x03Svc\x07Example\x03COM\x00"
+        );
+
+        // the names are not compressed
+        let soa = RData::SOA(SOA::new(
+            name("ns1.example.com."),
+            name("Admin.Example.com."),
+            1,
+            2,
+            3,
+            4,
+            5,
+        ));
+        let mut bytes = Vec::new();
+        write_record(&mut bytes, &name("Example.COM."), DNSClass::IN, 3600, &soa).unwrap();
+        assert_eq!(
+            bytes,
+            hex("076578616d706c6503636f6d00 0006 0001 00000e10 0038
+                 036e7331076578616d706c6503636f6d00 0561646d696e076578616d706c6503636f6d00
+                 00000001 00000002 00000003 00000004 00000005")
+        );
+    }
+
+    /// RFC 8976 appendix A.1, the simple ZONEMD example zone
+    #[test]
+    fn test_zonemd_rfc8976_simple() {
+        let origin = name("example.");
+        let zonemd = Record::from_rdata(
+            origin.clone(),
+            86400,
+            RData::Unknown {
+                code: RecordType::Unknown(ZONEMD),
+                rdata: NULL::with(hex(
+                    "7848b91c 01 01 c68090d90a7aed716bc459f9340e3d7c1370d4d24b7e2fc3
+                     a1ddc0b9a87153b9a9713b3c9ae5cc27777f98b8e730044c",
+                )),
+            },
+        );
+
+        let records = vec![
+            zonemd,
+            Record::from_rdata(
+                name("ns2.example."),
+                3600,
+                RData::AAAA(AAAA(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x63))),
+            ),
+            Record::from_rdata(
+                origin.clone(),
+                86400,
+                RData::SOA(SOA::new(
+                    name("ns1.example."),
+                    name("admin.example."),
+                    2018031900,
+                    1800,
+                    900,
+                    604800,
+                    86400,
+                )),
+            ),
+            Record::from_rdata(origin.clone(), 86400, RData::NS(NS(name("NS2.example.")))),
+            Record::from_rdata(
+                name("ns1.example."),
+                3600,
+                RData::A(A(Ipv4Addr::new(203, 0, 113, 63))),
+            ),
+            Record::from_rdata(origin.clone(), 86400, RData::NS(NS(name("ns1.example.")))),
+            // out of zone
+            Record::from_rdata(
+                name("example.com."),
+                3600,
+                RData::A(A(Ipv4Addr::new(192, 0, 2, 1))),
+            ),
+        ];
+
+        let mut bytes = Vec::new();
+        write_zonemd_input(&mut bytes, &origin, &records).unwrap();
+
+        let expected = hex(
+            "076578616d706c65000002000100015180000d036e7331076578616d706c6500
+             076578616d706c65000002000100015180000d036e7332076578616d706c6500
+             076578616d706c650000060001000151800030036e7331076578616d706c6500
+             0561646d696e076578616d706c65007848b91c000007080000038400093a80
+             00015180036e7331076578616d706c65000001000100000e100004cb00713f03
+             6e7332076578616d706c6500001c000100000e10001020010db8000000000000
+             000000000063",
+        );
+        assert_eq!(bytes, expected);
+
+        #[cfg(feature = "ring")]
+        assert_eq!(
+            ring::digest::digest(&ring::digest::SHA384, &bytes).as_ref(),
+            hex("c68090d90a7aed716bc459f9340e3d7c1370d4d24b7e2fc3
+                 a1ddc0b9a87153b9a9713b3c9ae5cc27777f98b8e730044c")
+        );
+    }
+
+    /// RFC 4034 section 5.4, the digest of a DS is over the canonical owner name and DNSKEY RDATA
+    #[test]
+    fn test_ds_input_rfc4034() {
+        let mut key = hex("01000305");
+        key.extend_from_slice(include_bytes!("../../tests/test-data/rfc4034-5.4.key"));
+
+        let mut bytes = Vec::new();
+        emit_name(
+            &mut BinEncoder::new(&mut bytes),
+            &name("DSKEY.Example.com."),
+        )
+        .unwrap();
+        assert_eq!(bytes, b"\x05dskey\x07example\x03com\x00");
+        bytes.extend_from_slice(&key);
+
+        #[cfg(feature = "ring")]
+        assert_eq!(
+            ring::digest::digest(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY, &bytes).as_ref(),
+            hex("2BB183AF5F22588179A53B0A98631FAD1A292118")
+        );
+    }
+
+    #[cfg(feature = "dnssec")]
+    #[test]
+    fn test_rrsig_input() {
+        use crate::rr::dnssec::Algorithm;
+
+        let records = [
+            Record::from_rdata(
+                name("a.Example.com."),
+                300,
+                RData::MX(MX::new(10, name("Mail.Example.com."))),
+            ),
+            Record::from_rdata(
+                name("A.example.com."),
+                300,
+                RData::MX(MX::new(5, name("mx.example.com."))),
+            ),
+            // not part of the RRset
+            Record::from_rdata(
+                name("b.example.com."),
+                300,
+                RData::MX(MX::new(1, name("mx.example.com."))),
+            ),
+        ];
+
+        let sig = SIG::new(
+            RecordType::MX,
+            Algorithm::RSASHA256,
+            3,
+            3600,
+            0x6592_0080,
+            0x6569_2200,
+            12345,
+            name("Example.COM."),
+            vec![],
+        );
+
+        let mut bytes = Vec::new();
+        write_rrsig_input(
+            &mut bytes,
+            &name("a.example.com."),
+            DNSClass::IN,
+            &sig,
+            &records,
+        )
+        .unwrap();
+
+        let expected = hex(
+            "000f 08 03 00000e10 65920080 65692200 3039 076578616d706c6503636f6d00
+             0161076578616d706c6503636f6d00 000f 0001 00000e10 0012
+               0005 026d78076578616d706c6503636f6d00
+             0161076578616d706c6503636f6d00 000f 0001 00000e10 0014
+               000a 046d61696c076578616d706c6503636f6d00",
+        );
+        assert_eq!(bytes, expected);
+
+        // a wildcard expansion is signed with the wildcard as owner name
+        let sig = SIG::new(
+            RecordType::MX,
+            Algorithm::RSASHA256,
+            2,
+            3600,
+            0x6592_0080,
+            0x6569_2200,
+            12345,
+            name("example.com."),
+            vec![],
+        );
+
+        let mut bytes = Vec::new();
+        write_rrsig_input(
+            &mut bytes,
+            &name("a.example.com."),
+            DNSClass::IN,
+            &sig,
+            &records,
+        )
+        .unwrap();
+        assert_eq!(&bytes[..3], &[0x00, 0x0f, 0x08]);
+        assert_eq!(bytes[3], 2);
+        let owner = b"\x01*\x07example\x03com\x00";
+        assert_eq!(&bytes[31..31 + owner.len()], owner);
+    }
+
+    fn rrset() -> Vec<RData> {
+        vec![
+            RData::MX(MX::new(10, name("mail.example.com."))),
+            RData::MX(MX::new(10, name("Mail2.example.com."))),
+            RData::MX(MX::new(5, name("MX.example.com."))),
+            RData::MX(MX::new(20, name("backup.example.net."))),
+            RData::MX(MX::new(20, name("b.example.net."))),
+            // a duplicate, only differing in case
+            RData::MX(MX::new(10, name("MAIL.example.com."))),
+        ]
+    }
+
+    /// The records of the RRset, split between two owner names of different case
+    fn zone() -> Vec<Record> {
+        rrset()
+            .into_iter()
+            .enumerate()
+            .map(|(i, rdata)| {
+                let owner = match i % 3 {
+                    0 => name("example.com."),
+                    1 => name("WWW.example.com."),
+                    _ => name("www.Example.com."),
+                };
+                Record::from_rdata(owner, 3600, rdata)
+            })
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn test_rrset_order_insensitive(shuffled in Just(rrset()).prop_shuffle()) {
+            let mut expected = Vec::new();
+            write_rrset(&mut expected, &name("example.com."), DNSClass::IN, 3600, &rrset()).unwrap();
+
+            let mut bytes = Vec::new();
+            write_rrset(&mut bytes, &name("Example.com."), DNSClass::IN, 3600, &shuffled).unwrap();
+            prop_assert_eq!(bytes, expected);
+        }
+
+        #[test]
+        fn test_zonemd_order_insensitive(shuffled in Just(zone()).prop_shuffle()) {
+            let origin = name("example.com.");
+
+            let mut expected = Vec::new();
+            write_zonemd_input(&mut expected, &origin, &zone()).unwrap();
+
+            let mut bytes = Vec::new();
+            write_zonemd_input(&mut bytes, &origin, &shuffled).unwrap();
+            prop_assert_eq!(bytes, expected);
+        }
+    }
+}
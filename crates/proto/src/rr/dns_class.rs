@@ -22,6 +22,7 @@ use crate::serialize::binary::*;
 #[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
 #[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 #[allow(dead_code)]
+#[non_exhaustive]
 pub enum DNSClass {
     /// Internet
     IN,
@@ -98,6 +99,10 @@ impl BinEncodable for DNSClass {
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
         encoder.emit_u16((*self).into())
     }
+
+    fn encoded_size(&self) -> usize {
+        2
+    }
 }
 
 impl<'r> BinDecodable<'r> for DNSClass {
@@ -167,6 +167,38 @@ impl Algorithm {
         }
     }
 
+    /// The implementation status of the algorithm for signing, see [`AlgorithmSecurityLevel`]
+    pub fn security_level(self) -> AlgorithmSecurityLevel {
+        match self {
+            Self::RSASHA256 | Self::ECDSAP256SHA256 => AlgorithmSecurityLevel::Must,
+            Self::ED25519 => AlgorithmSecurityLevel::Recommended,
+            // ED448 is not implemented here
+            Self::ECDSAP384SHA384 | Self::Unknown(16) => AlgorithmSecurityLevel::Optional,
+            Self::RSASHA1 | Self::RSASHA1NSEC3SHA1 | Self::RSASHA512 => {
+                AlgorithmSecurityLevel::NotRecommended
+            }
+            // DSA-NSEC3-SHA1 and ECC-GOST
+            Self::RSAMD5 | Self::DSA | Self::Unknown(6) | Self::Unknown(12) => {
+                AlgorithmSecurityLevel::MustNot
+            }
+            Self::Unknown(_) => AlgorithmSecurityLevel::Unknown,
+        }
+    }
+
+    /// Returns true if RFC 8624 forbids signing with this algorithm
+    ///
+    /// These are RSAMD5, DSA, DSA-NSEC3-SHA1 and ECC-GOST.
+    pub fn is_deprecated(self) -> bool {
+        self.security_level() == AlgorithmSecurityLevel::MustNot
+    }
+
+    /// Returns true if RFC 8624 recommends this algorithm for new keys
+    ///
+    /// These are ECDSAP256SHA256 and ED25519, which are also small and fast to validate.
+    pub fn is_recommended(self) -> bool {
+        matches!(self, Self::ECDSAP256SHA256 | Self::ED25519)
+    }
+
     /// Convert to string form
     #[deprecated(note = "use as_str instead")]
     pub fn to_str(self) -> &'static str {
@@ -190,6 +222,45 @@ impl Algorithm {
     }
 }
 
+/// The implementation status of DNSSEC algorithms for signing
+///
+/// [RFC 8624](https://tools.ietf.org/html/rfc8624#section-3.1), Algorithm Implementation
+///  Requirements and Usage Guidance for DNSSEC, June 2019
+///
+/// ```text
+///  +--------+--------------------+-----------------+-------------------+
+///  | Number | Mnemonics          | DNSSEC Signing  | DNSSEC Validation |
+///  +--------+--------------------+-----------------+-------------------+
+///  | 1      | RSAMD5             | MUST NOT        | MUST NOT          |
+///  | 3      | DSA                | MUST NOT        | MUST NOT          |
+///  | 5      | RSASHA1            | NOT RECOMMENDED | MUST              |
+///  | 6      | DSA-NSEC3-SHA1     | MUST NOT        | MUST NOT          |
+///  | 7      | RSASHA1-NSEC3-SHA1 | NOT RECOMMENDED | MUST              |
+///  | 8      | RSASHA256          | MUST            | MUST              |
+///  | 10     | RSASHA512          | NOT RECOMMENDED | MUST              |
+///  | 12     | ECC-GOST           | MUST NOT        | MAY               |
+///  | 13     | ECDSAP256SHA256    | MUST            | MUST              |
+///  | 14     | ECDSAP384SHA384    | MAY             | RECOMMENDED       |
+///  | 15     | ED25519            | RECOMMENDED     | RECOMMENDED       |
+///  | 16     | ED448              | MAY             | RECOMMENDED       |
+///  +--------+--------------------+-----------------+-------------------+
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum AlgorithmSecurityLevel {
+    /// MUST be implemented
+    Must,
+    /// RECOMMENDED to be implemented
+    Recommended,
+    /// MAY be implemented, i.e. OPTIONAL
+    Optional,
+    /// NOT RECOMMENDED to be used
+    NotRecommended,
+    /// MUST NOT be used
+    MustNot,
+    /// Not listed in RFC 8624, e.g. private algorithms
+    Unknown,
+}
+
 impl BinEncodable for Algorithm {
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
         encoder.emit(u8::from(*self))
@@ -284,3 +355,30 @@ fn test_order() {
         assert_eq!(got, expect);
     }
 }
+
+#[test]
+fn test_security_level() {
+    assert!(Algorithm::RSAMD5.is_deprecated());
+    assert!(Algorithm::DSA.is_deprecated());
+    assert!(Algorithm::from_u8(6).is_deprecated());
+    assert!(Algorithm::from_u8(12).is_deprecated());
+    assert!(!Algorithm::RSASHA1.is_deprecated());
+    assert!(!Algorithm::from_u8(253).is_deprecated());
+
+    assert!(Algorithm::ED25519.is_recommended());
+    assert!(Algorithm::ECDSAP256SHA256.is_recommended());
+    assert!(!Algorithm::RSASHA256.is_recommended());
+
+    assert_eq!(
+        Algorithm::RSASHA256.security_level(),
+        AlgorithmSecurityLevel::Must
+    );
+    assert_eq!(
+        Algorithm::RSASHA512.security_level(),
+        AlgorithmSecurityLevel::NotRecommended
+    );
+    assert_eq!(
+        Algorithm::from_u8(253).security_level(),
+        AlgorithmSecurityLevel::Unknown
+    );
+}
@@ -17,7 +17,6 @@ mod nsec3;
 pub mod proof;
 pub mod public_key;
 pub mod rdata;
-#[cfg(any(feature = "openssl", feature = "ring"))]
 mod rsa_public_key;
 mod signer;
 mod supported_algorithm;
@@ -26,10 +25,11 @@ mod trust_anchor;
 pub mod tsig;
 mod verifier;
 
-pub use self::algorithm::Algorithm;
+pub use self::algorithm::{Algorithm, AlgorithmSecurityLevel};
 pub use self::digest_type::DigestType;
 pub use self::nsec3::Nsec3HashAlgorithm;
 pub use self::proof::{Proof, ProofError, ProofErrorKind, ProofFlags, Proven};
+pub use self::public_key::KeySize;
 pub use self::public_key::PublicKey;
 pub use self::public_key::PublicKeyBuf;
 pub use self::public_key::PublicKeyEnum;
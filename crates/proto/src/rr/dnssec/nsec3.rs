@@ -104,6 +104,7 @@ use crate::serialize::binary::{BinEncodable, BinEncoder};
 /// ```
 #[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[non_exhaustive]
 pub enum Nsec3HashAlgorithm {
     /// Hash for the Nsec3 records
     SHA1,
@@ -160,7 +161,9 @@ impl Nsec3HashAlgorithm {
                 {
                     let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut buf);
                     encoder.set_canonical_names(true);
-                    name.emit(&mut encoder).expect("could not encode Name");
+                    name.to_lowercase()
+                        .emit(&mut encoder)
+                        .expect("could not encode Name");
                 }
 
                 Self::sha1_recursive_hash(salt, buf, iterations)
@@ -292,6 +295,12 @@ fn test_known_hashes() {
         hash_with_base32("xx.example"),
         "t644ebqk9bibcna874givr6joj62mlhv"
     );
+
+    // the owner name is hashed in canonical form, i.e. lowercase
+    assert_eq!(
+        hash_with_base32("X.Y.W.EXAMPLE"),
+        "2vptu5timamqttgl4luu9kg21e0aor3s"
+    );
 }
 
 #[cfg(test)]
@@ -526,6 +526,83 @@ impl PublicKey for PublicKeyBuf {
     }
 }
 
+/// The size of a public key, the modulus for RSA and the curve for ECDSA and EdDSA keys
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum KeySize {
+    /// RSA key with a modulus of the number of bits
+    Rsa(u32),
+    /// ECDSA key on the P-256 curve
+    P256,
+    /// ECDSA key on the P-384 curve
+    P384,
+    /// EdDSA key on Curve25519
+    Ed25519,
+}
+
+impl KeySize {
+    /// The size of the key in bits, e.g. 2048 for a 2048-bit RSA key and 256 for P-256
+    pub fn bits(self) -> u32 {
+        match self {
+            Self::Rsa(bits) => bits,
+            Self::P256 | Self::Ed25519 => 256,
+            Self::P384 => 384,
+        }
+    }
+
+    /// Returns true if this is an RSA key
+    pub fn is_rsa(self) -> bool {
+        matches!(self, Self::Rsa(..))
+    }
+}
+
+impl std::fmt::Display for KeySize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rsa(bits) => write!(f, "RSA-{bits}"),
+            Self::P256 => f.write_str("P-256"),
+            Self::P384 => f.write_str("P-384"),
+            Self::Ed25519 => f.write_str("Ed25519"),
+        }
+    }
+}
+
+/// Returns the size of the public key, in DNS format, of the algorithm
+///
+/// This only parses the key, it doesn't require a crypto library. RSA keys are in the format of
+/// [RFC 3110](https://tools.ietf.org/html/rfc3110#section-2), ECDSA keys are the uncompressed
+/// points of [RFC 6605](https://tools.ietf.org/html/rfc6605#section-4) and Ed25519 keys are the
+/// 32 bytes of [RFC 8080](https://tools.ietf.org/html/rfc8080#section-3).
+pub fn key_size(algorithm: Algorithm, public_key: &[u8]) -> ProtoResult<KeySize> {
+    use crate::rr::dnssec::rsa_public_key;
+
+    let expect_len = |len: usize, size: KeySize| {
+        if public_key.len() == len {
+            Ok(size)
+        } else {
+            Err(ProtoError::from(format!(
+                "bad {algorithm} public key length: {}",
+                public_key.len()
+            )))
+        }
+    };
+
+    #[allow(deprecated)]
+    match algorithm {
+        Algorithm::RSAMD5
+        | Algorithm::RSASHA1
+        | Algorithm::RSASHA1NSEC3SHA1
+        | Algorithm::RSASHA256
+        | Algorithm::RSASHA512 => Ok(KeySize::Rsa(
+            rsa_public_key::RSAPublicKey::try_from(public_key)?.modulus_bits(),
+        )),
+        Algorithm::ECDSAP256SHA256 => expect_len(64, KeySize::P256),
+        Algorithm::ECDSAP384SHA384 => expect_len(96, KeySize::P384),
+        Algorithm::ED25519 => expect_len(32, KeySize::Ed25519),
+        _ => Err(ProtoErrorKind::UnknownAlgorithmTypeValue(u8::from(algorithm)).into()),
+    }
+}
+
 #[cfg(all(not(feature = "ring"), feature = "openssl"))]
 #[cfg(test)]
 mod tests {
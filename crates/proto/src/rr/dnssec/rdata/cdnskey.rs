@@ -33,6 +33,12 @@ impl Deref for CDNSKEY {
     }
 }
 
+impl From<DNSKEY> for CDNSKEY {
+    fn from(dnskey: DNSKEY) -> Self {
+        Self(dnskey)
+    }
+}
+
 impl BinEncodable for CDNSKEY {
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
         self.0.emit(encoder)
@@ -33,6 +33,12 @@ impl Deref for CDS {
     }
 }
 
+impl From<DS> for CDS {
+    fn from(ds: DS) -> Self {
+        Self(ds)
+    }
+}
+
 impl BinEncodable for CDS {
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
         self.0.emit(encoder)
@@ -15,7 +15,8 @@ use serde::{Deserialize, Serialize};
 use crate::{
     error::{ProtoError, ProtoErrorKind, ProtoResult},
     rr::{
-        dnssec::{Algorithm, Digest, DigestType},
+        canonical,
+        dnssec::{public_key, Algorithm, Digest, DigestType, KeySize},
         record_data::RData,
         Name, RecordData, RecordDataDecodable, RecordType,
     },
@@ -192,6 +193,11 @@ impl DNSKEY {
         &self.public_key
     }
 
+    /// The size of the public key, i.e. the RSA modulus or the curve of an ECDSA or EdDSA key
+    pub fn key_size(&self) -> ProtoResult<KeySize> {
+        public_key::key_size(self.algorithm, &self.public_key)
+    }
+
     /// Output the encoded form of the flags
     pub fn flags(&self) -> u16 {
         let mut flags: u16 = 0;
@@ -242,9 +248,9 @@ impl DNSKEY {
         {
             let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut buf);
             encoder.set_canonical_names(true);
-            if let Err(e) = name
-                .emit(&mut encoder)
-                .and_then(|_| self.emit(&mut encoder))
+            // the owner name is in canonical form, i.e. lowercase, RFC 4034 section 5.1.4
+            if let Err(e) =
+                canonical::emit_name(&mut encoder, name).and_then(|_| self.emit(&mut encoder))
             {
                 tracing::warn!("error serializing dnskey: {e}");
                 return Err(format!("error serializing dnskey: {e}").into());
@@ -337,12 +343,6 @@ impl DNSKEY {
     }
 }
 
-impl From<DNSKEY> for RData {
-    fn from(key: DNSKEY) -> Self {
-        Self::DNSSEC(super::DNSSECRData::DNSKEY(key))
-    }
-}
-
 impl BinEncodable for DNSKEY {
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
         encoder.emit_u16(self.flags())?;
@@ -486,6 +486,33 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_key_size() {
+        // the 2048-bit root KSK, tag 20326, in the format of RFC 3110
+        let root_ksk = include_bytes!("../roots/20326.rsa");
+        let rsa = DNSKEY::new(true, true, false, Algorithm::RSASHA256, root_ksk.to_vec());
+        assert_eq!(rsa.key_size().unwrap(), KeySize::Rsa(2048));
+        assert_eq!(rsa.key_size().unwrap().bits(), 2048);
+
+        // leading zeros of the modulus aren't counted, the 3 byte exponent length is supported
+        let mut long_exponent = vec![0, 0, 3, 1, 0, 1, 0];
+        long_exponent.extend_from_slice(&[0x7f; 128]);
+        let rsa = DNSKEY::new(true, true, false, Algorithm::RSASHA512, long_exponent);
+        assert_eq!(rsa.key_size().unwrap(), KeySize::Rsa(1023));
+
+        let ecdsa = DNSKEY::new(true, false, false, Algorithm::ECDSAP256SHA256, vec![4; 64]);
+        assert_eq!(ecdsa.key_size().unwrap(), KeySize::P256);
+        let ecdsa = DNSKEY::new(true, false, false, Algorithm::ECDSAP384SHA384, vec![4; 96]);
+        assert_eq!(ecdsa.key_size().unwrap().bits(), 384);
+        let ed25519 = DNSKEY::new(true, false, false, Algorithm::ED25519, vec![1; 32]);
+        assert_eq!(ed25519.key_size().unwrap(), KeySize::Ed25519);
+
+        let short = DNSKEY::new(true, false, false, Algorithm::ECDSAP256SHA256, vec![4; 63]);
+        assert!(short.key_size().is_err());
+        let empty = DNSKEY::new(true, false, false, Algorithm::RSASHA256, vec![]);
+        assert!(empty.key_size().is_err());
+    }
+
     #[test]
     #[cfg(any(feature = "openssl", feature = "ring"))]
     fn test() {
@@ -515,6 +542,16 @@ mod tests {
                 DigestType::SHA256
             )
             .is_ok());
+
+        // the owner name is digested in canonical form, i.e. lowercase
+        let digest = |name| {
+            rdata
+                .to_digest(&Name::parse(name, None).unwrap(), DigestType::SHA256)
+                .unwrap()
+                .as_ref()
+                .to_vec()
+        };
+        assert_eq!(digest("WWW.Example.com."), digest("www.example.com."));
     }
 
     #[test]
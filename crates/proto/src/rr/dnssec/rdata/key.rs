@@ -562,6 +562,7 @@ fn test_update_scope() {
 /// ```
 #[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[non_exhaustive]
 pub enum Protocol {
     /// Not in use
     #[deprecated = "Deprecated by RFC3445"]
@@ -755,12 +756,6 @@ impl KEY {
     // }
 }
 
-impl From<KEY> for RData {
-    fn from(key: KEY) -> Self {
-        Self::DNSSEC(super::DNSSECRData::KEY(key))
-    }
-}
-
 impl BinEncodable for KEY {
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
         encoder.emit_u16(self.flags())?;
@@ -55,7 +55,7 @@ pub type DNSSECRecordType = RecordType;
 
 /// Record data enum variants for DNSSEC-specific records.
 #[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
-#[derive(Debug, EnumAsInner, PartialEq, Clone, Eq)]
+#[derive(Debug, EnumAsInner, PartialEq, Clone, Eq, Hash)]
 #[non_exhaustive]
 pub enum DNSSECRData {
     /// ```text
@@ -701,3 +701,14 @@ impl From<DNSSECRData> for RData {
         Self::DNSSEC(rdata)
     }
 }
+
+impl TryFrom<RData> for DNSSECRData {
+    type Error = RData;
+
+    fn try_from(rdata: RData) -> Result<Self, Self::Error> {
+        match rdata {
+            RData::DNSSEC(rdata) => Ok(rdata),
+            _ => Err(rdata),
+        }
+    }
+}
@@ -14,7 +14,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     error::ProtoResult,
-    rr::{dnssec::Algorithm, Name, RData, RecordData, RecordDataDecodable, RecordType},
+    rr::{dnssec::Algorithm, Name, RData, RecordData, RecordDataDecodable, RecordSet, RecordType},
     serialize::binary::{BinDecoder, BinEncodable, BinEncoder, Restrict},
 };
 
@@ -69,6 +69,45 @@ impl RRSIG {
             sig,
         ))
     }
+
+    /// Returns true if this signature could have been made over the `rrset` by `zone_name`
+    ///
+    /// The checks are those of [RFC 4035 section 5.3.1](https://tools.ietf.org/html/rfc4035#section-5.3.1)
+    ///  that don't need the DNSKEY or the current time:
+    ///
+    /// ```text
+    ///   o  The RRSIG RR and the RRset MUST have the same owner name and the
+    ///      same class.
+    ///
+    ///   o  The RRSIG RR's Signer's Name field MUST be the name of the zone
+    ///      that contains the RRset.
+    ///
+    ///   o  The RRSIG RR's Type Covered field MUST equal the RRset's type.
+    ///
+    ///   o  The number of labels in the RRset owner name MUST be greater than
+    ///      or equal to the value in the RRSIG RR's Labels field.
+    /// ```
+    ///
+    /// A Labels field that is less than the labels of the owner name means the RRset was
+    ///  expanded from a wildcard.
+    pub fn covers_rrset(&self, rrset: &RecordSet, zone_name: &Name) -> bool {
+        self.type_covered() == rrset.record_type()
+            && self.signer_name() == zone_name
+            && zone_name.zone_of(rrset.name())
+            && self.num_labels() <= rrset.name().num_labels()
+    }
+
+    /// Returns false if the algorithm of this signature must not be used for validation
+    ///
+    /// [RFC 8624 section 3.1](https://tools.ietf.org/html/rfc8624#section-3.1) lists RSAMD5 and
+    ///  DSA as MUST NOT for DNSSEC validation, unknown algorithms can't be validated either.
+    pub fn algorithm_supported(&self) -> bool {
+        #[allow(deprecated)]
+        !matches!(
+            self.algorithm(),
+            Algorithm::RSAMD5 | Algorithm::DSA | Algorithm::Unknown(_)
+        )
+    }
 }
 
 impl Deref for RRSIG {
@@ -138,3 +177,58 @@ impl fmt::Display for RRSIG {
         write!(f, "{}", self.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::rr::rdata::A;
+
+    fn rrsig(type_covered: RecordType, algorithm: Algorithm, num_labels: u8) -> RRSIG {
+        RRSIG::new(
+            type_covered,
+            algorithm,
+            num_labels,
+            3600,
+            0,
+            0,
+            0,
+            Name::from_str("example.com.").unwrap(),
+            vec![],
+        )
+    }
+
+    fn rrset(name: &str) -> RecordSet {
+        let mut rrset = RecordSet::new(&Name::from_str(name).unwrap(), RecordType::A, 0);
+        rrset.add_rdata(RData::A(A::new(192, 0, 2, 1)));
+        rrset
+    }
+
+    #[test]
+    fn test_covers_rrset() {
+        let zone = Name::from_str("example.com.").unwrap();
+        let rrset = rrset("www.example.com.");
+
+        assert!(rrsig(RecordType::A, Algorithm::ED25519, 3).covers_rrset(&rrset, &zone));
+        // expanded from *.example.com.
+        assert!(rrsig(RecordType::A, Algorithm::ED25519, 2).covers_rrset(&rrset, &zone));
+
+        assert!(!rrsig(RecordType::AAAA, Algorithm::ED25519, 3).covers_rrset(&rrset, &zone));
+        assert!(!rrsig(RecordType::A, Algorithm::ED25519, 4).covers_rrset(&rrset, &zone));
+        assert!(!rrsig(RecordType::A, Algorithm::ED25519, 3)
+            .covers_rrset(&rrset, &Name::from_str("example.net.").unwrap()));
+        assert!(!rrsig(RecordType::A, Algorithm::ED25519, 3)
+            .covers_rrset(&self::rrset("www.example.net."), &zone));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_algorithm_supported() {
+        assert!(rrsig(RecordType::A, Algorithm::RSASHA256, 3).algorithm_supported());
+        assert!(rrsig(RecordType::A, Algorithm::RSASHA1, 3).algorithm_supported());
+        assert!(!rrsig(RecordType::A, Algorithm::RSAMD5, 3).algorithm_supported());
+        assert!(!rrsig(RecordType::A, Algorithm::DSA, 3).algorithm_supported());
+        assert!(!rrsig(RecordType::A, Algorithm::Unknown(200), 3).algorithm_supported());
+    }
+}
@@ -13,7 +13,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     error::*,
-    rr::{dnssec::Algorithm, Name, RData, RecordData, RecordDataDecodable, RecordType},
+    rr::{canonical, dnssec::Algorithm, Name, RData, RecordData, RecordDataDecodable, RecordType},
     serialize::binary::*,
 };
 
@@ -566,7 +566,8 @@ pub fn emit_pre_sig(
     encoder.emit_u32(sig_expiration)?;
     encoder.emit_u32(sig_inception)?;
     encoder.emit_u16(key_tag)?;
-    signer_name.emit_as_canonical(encoder, true)?;
+    // the signer's name is in canonical form, i.e. lowercase, RFC 4034 section 6.2
+    canonical::emit_name(encoder, signer_name)?;
     Ok(())
 }
 
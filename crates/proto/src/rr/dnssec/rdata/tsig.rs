@@ -177,6 +177,7 @@ pub struct TSIG {
 /// ```
 #[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[non_exhaustive]
 pub enum TsigAlgorithm {
     /// HMAC-MD5.SIG-ALG.REG.INT (not supported for cryptographic operations)
     HmacMd5,
@@ -507,8 +508,9 @@ impl TsigAlgorithm {
     /// Convert a DNS name to an Algorithm
     pub fn from_name(name: Name) -> Self {
         use TsigAlgorithm::*;
-        match name.to_ascii().as_str() {
-            "HMAC-MD5.SIG-ALG.REG.INT" => HmacMd5,
+        // algorithm names are domain names, and compared ignoring case
+        match name.to_lowercase().to_ascii().as_str() {
+            "hmac-md5.sig-alg.reg.int" => HmacMd5,
             "gss-tsig" => Gss,
             "hmac-sha1" => HmacSha1,
             "hmac-sha224" => HmacSha224,
@@ -745,11 +747,11 @@ pub fn signed_bitmessage_to_buf(
         Query::read(&mut decoder)?;
     }
 
-    // read all records except for the last one (tsig)
-    let record_count = header.answer_count() as usize
-        + header.name_server_count() as usize
-        + header.additional_count() as usize;
+    // read all records except for the last one (tsig), the OPT record is only valid in the
+    // additional section
+    let record_count = header.answer_count() as usize + header.name_server_count() as usize;
     Message::read_records(&mut decoder, record_count, false)?;
+    Message::read_records(&mut decoder, header.additional_count() as usize, true)?;
 
     // keep position of data end
     let end_data = message.len() - decoder.len();
@@ -830,6 +832,22 @@ mod tests {
         assert_eq!(rdata, read_rdata);
     }
 
+    #[test]
+    fn test_algorithm_from_name() {
+        for (name, algorithm) in [
+            ("hmac-sha256", TsigAlgorithm::HmacSha256),
+            ("HMAC-SHA256", TsigAlgorithm::HmacSha256),
+            ("HMAC-MD5.SIG-ALG.REG.INT", TsigAlgorithm::HmacMd5),
+            ("hmac-md5.sig-alg.reg.int", TsigAlgorithm::HmacMd5),
+        ] {
+            assert_eq!(
+                TsigAlgorithm::from_name(Name::from_ascii(name).unwrap()),
+                algorithm,
+                "{name}"
+            );
+        }
+    }
+
     #[test]
     fn test_encode_decode_tsig() {
         test_encode_decode(TSIG::new(
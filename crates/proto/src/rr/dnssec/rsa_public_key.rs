@@ -9,6 +9,7 @@ use crate::error::*;
 
 pub(crate) struct RSAPublicKey<'a> {
     n: &'a [u8],
+    #[cfg_attr(not(any(feature = "openssl", feature = "ring")), allow(dead_code))]
     e: &'a [u8],
 }
 
@@ -36,7 +37,16 @@ impl<'a> RSAPublicKey<'a> {
     pub(crate) fn n(&self) -> &[u8] {
         self.n
     }
+    #[cfg(any(feature = "openssl", feature = "ring"))]
     pub(crate) fn e(&self) -> &[u8] {
         self.e
     }
+
+    /// The size of the modulus in bits, not counting leading zeros
+    pub(crate) fn modulus_bits(&self) -> u32 {
+        match self.n.iter().position(|b| *b != 0) {
+            Some(first) => (self.n.len() - first) as u32 * 8 - self.n[first].leading_zeros(),
+            None => 0,
+        }
+    }
 }
@@ -11,7 +11,7 @@ use std::borrow::Borrow;
 
 use crate::{
     error::*,
-    rr::{dnssec::Algorithm, DNSClass, Name, Record, RecordType},
+    rr::{canonical, dnssec::Algorithm, DNSClass, Name, Record, RecordType},
     serialize::binary::{BinEncodable, BinEncoder, EncodeMode},
 };
 
@@ -84,7 +84,6 @@ pub fn message_tbs<M: BinEncodable>(message: &M, pre_sig0: &SIG) -> ProtoResult<
 /// # Returns
 ///
 /// the binary hash of the specified RRSet and associated information
-// FIXME: OMG, there are a ton of asserts in here...
 #[allow(clippy::too_many_arguments)]
 pub fn rrset_tbs<B: Borrow<Record>>(
     name: &Name,
@@ -99,87 +98,19 @@ pub fn rrset_tbs<B: Borrow<Record>>(
     signer_name: &Name,
     records: &[B],
 ) -> ProtoResult<TBS> {
-    // TODO: change this to a BTreeSet so that it's preordered, no sort necessary
-    let mut rrset: Vec<&Record> = Vec::new();
-
-    // collect only the records for this rrset
-    for record in records {
-        let record = record.borrow();
-        if dns_class == record.dns_class()
-            && type_covered == record.record_type()
-            && name == record.name()
-        {
-            rrset.push(record);
-        }
-    }
-
-    // put records in canonical order
-    rrset.sort();
-
-    let name = determine_name(name, num_labels)?;
-
-    // TODO: rather than buffering here, use the Signer/Verifier? might mean fewer allocations...
-    let mut buf: Vec<u8> = Vec::new();
-
-    {
-        let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut buf);
-        encoder.set_canonical_names(true);
-
-        //          signed_data = RRSIG_RDATA | RR(1) | RR(2)...  where
-        //
-        //             "|" denotes concatenation
-        //
-        //             RRSIG_RDATA is the wire format of the RRSIG RDATA fields
-        //                with the Signature field excluded and the Signer's Name
-        //                in canonical form.
-        assert!(sig::emit_pre_sig(
-            &mut encoder,
-            type_covered,
-            algorithm,
-            name.num_labels(),
-            original_ttl,
-            sig_expiration,
-            sig_inception,
-            key_tag,
-            signer_name,
-        )
-        .is_ok());
-
-        // construct the rrset signing data
-        for record in rrset {
-            //             RR(i) = name | type | class | OrigTTL | RDATA length | RDATA
-            //
-            //                name is calculated according to the function in the RFC 4035
-            assert!(name
-                .to_lowercase()
-                .emit_as_canonical(&mut encoder, true)
-                .is_ok());
-            //
-            //                type is the RRset type and all RRs in the class
-            assert!(type_covered.emit(&mut encoder).is_ok());
-            //
-            //                class is the RRset's class
-            assert!(dns_class.emit(&mut encoder).is_ok());
-            //
-            //                OrigTTL is the value from the RRSIG Original TTL field
-            assert!(encoder.emit_u32(original_ttl).is_ok());
-            //
-            //                RDATA length
-            // TODO: add support to the encoder to set a marker to go back and write the length
-            let mut rdata_buf = Vec::new();
-            {
-                let mut rdata_encoder = BinEncoder::new(&mut rdata_buf);
-                rdata_encoder.set_canonical_names(true);
-                assert!(record.data().emit(&mut rdata_encoder).is_ok());
-            }
-            assert!(encoder.emit_u16(rdata_buf.len() as u16).is_ok());
-            //
-            //                All names in the RDATA field are in canonical form (set above)
-            assert!(encoder.emit_vec(&rdata_buf).is_ok());
-        }
-    }
-
-    Ok(TBS(buf))
+    let sig = SIG::new(
+        type_covered,
+        algorithm,
+        num_labels,
+        original_ttl,
+        sig_expiration,
+        sig_inception,
+        key_tag,
+        signer_name.clone(),
+        Vec::new(),
+    );
+
+    rrset_tbs_with_sig(name, dns_class, &sig, records)
 }
 
 /// Returns the to-be-signed serialization of the given record set using the information
@@ -219,19 +150,13 @@ pub fn rrset_tbs_with_sig<B: Borrow<Record>>(
     sig: &SIG,
     records: &[B],
 ) -> ProtoResult<TBS> {
-    rrset_tbs(
-        name,
-        dns_class,
-        sig.num_labels(),
-        sig.type_covered(),
-        sig.algorithm(),
-        sig.original_ttl(),
-        sig.sig_expiration(),
-        sig.sig_inception(),
-        sig.key_tag(),
-        sig.signer_name(),
-        records,
-    )
+    //          signed_data = RRSIG_RDATA | RR(1) | RR(2)...
+    //
+    //  see the canonical module for the canonical form of the RRset
+    let mut buf: Vec<u8> = Vec::new();
+    canonical::write_rrsig_input(&mut buf, name, dns_class, sig, records)?;
+
+    Ok(TBS(buf))
 }
 
 /// [RFC 4035](https://tools.ietf.org/html/rfc4035), DNSSEC Protocol Modifications, March 2005
@@ -262,7 +187,7 @@ pub fn rrset_tbs_with_sig<B: Borrow<Record>>(
 ///
 ///    The canonical forms for names and RRsets are defined in [RFC4034].
 /// ```
-pub fn determine_name(name: &Name, num_labels: u8) -> Result<Name, ProtoError> {
+pub fn determine_name(name: &Name, num_labels: u8) -> ProtoResult<Name> {
     //             To calculate the name:
     //                let rrsig_labels = the value of the RRSIG Labels field
     //
@@ -148,17 +148,7 @@ impl Label {
 
     /// compares with the other label, ignoring case
     pub fn cmp_with_f<F: LabelCmp>(&self, other: &Self) -> Ordering {
-        let s = self.0.iter();
-        let o = other.0.iter();
-
-        for (s, o) in s.zip(o) {
-            match F::cmp_u8(*s, *o) {
-                Ordering::Equal => continue,
-                not_eq => return not_eq,
-            }
-        }
-
-        self.0.len().cmp(&other.0.len())
+        F::cmp_bytes(&self.0, &other.0)
     }
 
     /// Performs the conversion to utf8 from IDNA as necessary, see `fmt` for more details
@@ -178,6 +168,9 @@ impl Label {
     }
 
     /// Writes this label to safe ascii, escaping characters as necessary
+    ///
+    /// Printable ASCII characters with a special meaning are escaped as `\X`, all other octets as
+    ///  `\DDD` with the decimal value of the octet, see RFC 1035 section 5.1.
     pub fn write_ascii<W: Write>(&self, f: &mut W) -> Result<(), fmt::Error> {
         // We can't guarantee that the same input will always translate to the same output
         fn escape_non_ascii<W: Write>(
@@ -185,7 +178,7 @@ impl Label {
             f: &mut W,
             is_first: bool,
         ) -> Result<(), fmt::Error> {
-            let to_triple_escape = |ch: u8| format!("\\{ch:03o}");
+            let to_triple_escape = |ch: u8| format!("\\{ch:03}");
             let to_single_escape = |ch: char| format!("\\{ch}");
 
             match char::from(byte) {
@@ -267,9 +260,9 @@ impl Display for Label {
 }
 
 impl Debug for Label {
+    /// Writes the label as ascii, control characters are escaped and can't forge log lines
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
-        let label = String::from_utf8_lossy(self.borrow());
-        f.write_str(&label)
+        self.write_ascii(f)
     }
 }
 
@@ -306,6 +299,18 @@ impl Hash for Label {
 pub trait LabelCmp {
     /// this should mimic the cmp method from [`PartialOrd`]
     fn cmp_u8(l: u8, r: u8) -> Ordering;
+
+    /// compares the raw bytes of two labels, shorter labels order before longer ones they prefix
+    fn cmp_bytes(l: &[u8], r: &[u8]) -> Ordering {
+        for (l, r) in l.iter().zip(r) {
+            match Self::cmp_u8(*l, *r) {
+                Ordering::Equal => continue,
+                not_eq => return not_eq,
+            }
+        }
+
+        l.len().cmp(&r.len())
+    }
 }
 
 /// For case sensitive comparisons
@@ -568,19 +573,23 @@ mod tests {
 
     #[test]
     fn test_ascii_escape() {
-        assert_eq!(
-            Label::from_raw_bytes(&[0o200]).unwrap().to_string(),
-            "\\200"
-        );
-        assert_eq!(
-            Label::from_raw_bytes(&[0o001]).unwrap().to_string(),
-            "\\001"
-        );
+        assert_eq!(Label::from_raw_bytes(&[200]).unwrap().to_string(), "\\200");
+        assert_eq!(Label::from_raw_bytes(&[1]).unwrap().to_string(), "\\001");
         assert_eq!(Label::from_ascii(".").unwrap().to_ascii(), "\\.");
         assert_eq!(
             Label::from_ascii("ben.fry").unwrap().to_string(),
             "ben\\.fry"
         );
-        assert_eq!(Label::from_raw_bytes(&[0o200]).unwrap().to_ascii(), "\\200");
+        assert_eq!(Label::from_raw_bytes(&[128]).unwrap().to_ascii(), "\\128");
+        assert_eq!(
+            Label::from_raw_bytes(b"a\nb \"c\"").unwrap().to_ascii(),
+            "a\\010b\\032\\\"c\\\""
+        );
+    }
+
+    #[test]
+    fn test_debug_escape() {
+        let label = Label::from_raw_bytes(b"x\n[ERROR] forged").unwrap();
+        assert_eq!(format!("{label:?}"), "x\\010\\[ERROR\\]\\032forged");
     }
 }
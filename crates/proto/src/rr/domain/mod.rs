@@ -13,5 +13,5 @@ mod try_parse_ip;
 pub mod usage;
 
 pub use self::label::{IntoLabel, Label};
-pub use self::name::{IntoName, LabelIter, Name};
+pub use self::name::{compress_name, decompress_name, IntoName, LabelIter, Name};
 pub use self::try_parse_ip::TryParseIp;
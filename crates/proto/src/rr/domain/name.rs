@@ -33,6 +33,9 @@ pub struct Name {
     label_ends: TinyVec<[u8; 24]>,
 }
 
+/// The maximum length of a name in the wire format, RFC 1035 section 3.1
+const MAX_WIRE_LEN: usize = 255;
+
 impl Name {
     /// Create a new domain::Name, i.e. label
     pub fn new() -> Self {
@@ -47,10 +50,11 @@ impl Name {
     }
 
     /// Extend the name with the offered label, and ensure maximum name length is not exceeded.
-    fn extend_name(&mut self, label: &[u8]) -> Result<(), ProtoError> {
+    fn extend_name(&mut self, label: &[u8]) -> ProtoResult<()> {
         self.label_data.extend_from_slice(label);
         self.label_ends.push(self.label_data.len() as u8);
-        if self.len() > 255 {
+        // in the wire format, each label has a length octet, and the name ends with the root label
+        if self.len() + 1 > MAX_WIRE_LEN {
             return Err(ProtoErrorKind::DomainNameTooLong(self.len()).into());
         };
         Ok(())
@@ -131,11 +135,17 @@ impl Name {
         Ok(self)
     }
 
-    /// Creates a new Name from the specified labels
+    /// Creates a new fully qualified Name from the specified labels
     ///
     /// # Arguments
     ///
-    /// * `labels` - vector of items which will be stored as Strings.
+    /// * `labels` - the labels of the name, strings are converted with IDNA, byte slices are
+    ///   used as they are.
+    ///
+    /// # Errors
+    ///
+    /// If a label is empty or longer than 63 bytes, or the name is longer than 255 bytes in the
+    ///  wire format, i.e. 253 bytes without the trailing dot in the presentation format.
     ///
     /// # Examples
     ///
@@ -159,26 +169,9 @@ impl Name {
         I: IntoIterator<Item = L>,
         L: IntoLabel,
     {
-        let (labels, errors): (Vec<_>, Vec<_>) = labels
-            .into_iter()
-            .map(IntoLabel::into_label)
-            .partition(Result::is_ok);
-        let labels: Vec<_> = labels.into_iter().map(Result::unwrap).collect();
-        let errors: Vec<_> = errors.into_iter().map(Result::unwrap_err).collect();
-
-        if labels.len() > 255 {
-            return Err(ProtoErrorKind::DomainNameTooLong(labels.len()).into());
-        };
-        if !errors.is_empty() {
-            return Err(format!("error converting some labels: {errors:?}").into());
-        };
-
-        let mut name = Self {
-            is_fqdn: true,
-            ..Self::default()
-        };
+        let mut name = Self::root();
         for label in labels {
-            name = name.append_label(label)?;
+            name.extend_name(label.into_label()?.as_bytes())?;
         }
 
         Ok(name)
@@ -209,7 +202,7 @@ impl Name {
     /// assert_eq!(name, Name::from_str("www.example.com.").unwrap());
     /// assert!(name.is_fqdn());
     /// ```
-    pub fn append_name(mut self, other: &Self) -> Result<Self, ProtoError> {
+    pub fn append_name(mut self, other: &Self) -> ProtoResult<Self> {
         for label in other.iter() {
             self.extend_name(label)?;
         }
@@ -235,7 +228,7 @@ impl Name {
     /// assert_eq!(name, Name::from_str("www.example.com").unwrap());
     /// assert!(name.is_fqdn())
     /// ```
-    pub fn append_domain(self, domain: &Self) -> Result<Self, ProtoError> {
+    pub fn append_domain(self, domain: &Self) -> ProtoResult<Self> {
         let mut this = self.append_name(domain)?;
         this.set_fqdn(true);
         Ok(this)
@@ -313,6 +306,11 @@ impl Name {
 
     /// same as `zone_of` allows for case sensitive call
     pub fn zone_of_case(&self, name: &Self) -> bool {
+        self.zone_of_with_f::<CaseSensitive>(name)
+    }
+
+    /// returns true if all labels of self are equal, with `F`, to the labels at the end of name
+    fn zone_of_with_f<F: LabelCmp>(&self, name: &Self) -> bool {
         let self_len = self.label_ends.len();
         let name_len = name.label_ends.len();
         if self_len == 0 {
@@ -332,7 +330,7 @@ impl Name {
         let zip_iter = self_iter.zip(name_iter);
 
         for (self_label, name_label) in zip_iter {
-            if self_label != name_label {
+            if F::cmp_bytes(self_label, name_label) != Ordering::Equal {
                 return false;
             }
         }
@@ -357,10 +355,7 @@ impl Name {
     /// assert!(!another.zone_of(&name));
     /// ```
     pub fn zone_of(&self, name: &Self) -> bool {
-        let self_lower = self.to_lowercase();
-        let name_lower = name.to_lowercase();
-
-        self_lower.zone_of_case(&name_lower)
+        self.zone_of_with_f::<CaseInsensitive>(name)
     }
 
     /// Returns the number of labels in the name, discounting `*`.
@@ -460,7 +455,7 @@ impl Name {
     /// assert_eq!(bytes_name, name);
     ///
     /// let bytes_name = Name::from_labels(vec!["bad.char".as_bytes(), "example".as_bytes(), "com".as_bytes()]).unwrap();
-    /// let name = Name::from_ascii("bad\\056char.example.com.").unwrap();
+    /// let name = Name::from_ascii("bad\\046char.example.com.").unwrap();
     ///
     /// assert_eq!(bytes_name, name);
     /// ```
@@ -492,6 +487,16 @@ impl Name {
         Self::from_encoded_str::<LabelEncUtf8>(name.as_ref(), None)
     }
 
+    /// A label with escapes is the escaped octets as is, RFC 1035 section 5.1, other labels are
+    ///  encoded with `E`
+    fn to_label<E: LabelEnc>(label: &str, raw: &[u8], escaped: bool) -> ProtoResult<Label> {
+        if escaped {
+            Label::from_raw_bytes(raw)
+        } else {
+            E::to_label(label)
+        }
+    }
+
     /// First attempts to decode via `from_utf8`, if that fails IDNA checks, then falls back to
     /// ascii decoding.
     ///
@@ -518,6 +523,9 @@ impl Name {
     fn from_encoded_str<E: LabelEnc>(local: &str, origin: Option<&Self>) -> ProtoResult<Self> {
         let mut name = Self::new();
         let mut label = String::new();
+        // the octets of the label, only used if it has escapes
+        let mut raw = Vec::new();
+        let mut escaped = false;
 
         let mut state = ParseState::Label;
 
@@ -533,56 +541,57 @@ impl Name {
             match state {
                 ParseState::Label => match ch {
                     '.' => {
-                        name = name.append_label(E::to_label(&label)?)?;
+                        name = name.append_label(Self::to_label::<E>(&label, &raw, escaped)?)?;
                         label.clear();
+                        raw.clear();
+                        escaped = false;
                     }
                     '\\' => state = ParseState::Escape1,
-                    ch if !ch.is_control() && !ch.is_whitespace() => label.push(ch),
+                    ch if !ch.is_control() && !ch.is_whitespace() => {
+                        label.push(ch);
+                        raw.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+                    }
                     _ => return Err(format!("unrecognized char: {ch}").into()),
                 },
                 ParseState::Escape1 => {
-                    if ch.is_numeric() {
-                        state = ParseState::Escape2(
-                            ch.to_digit(8)
-                                .ok_or_else(|| ProtoError::from(format!("illegal char: {ch}")))?,
-                        );
+                    escaped = true;
+                    if let Some(digit) = ch.to_digit(10) {
+                        state = ParseState::Escape2(digit);
+                    } else if ch.is_numeric() {
+                        return Err(ProtoError::from(format!("illegal char: {ch}")));
                     } else {
                         // it's a single escaped char
                         label.push(ch);
+                        raw.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
                         state = ParseState::Label;
                     }
                 }
                 ParseState::Escape2(i) => {
-                    if ch.is_numeric() {
-                        state = ParseState::Escape3(
-                            i,
-                            ch.to_digit(8)
-                                .ok_or_else(|| ProtoError::from(format!("illegal char: {ch}")))?,
-                        );
-                    } else {
-                        return Err(ProtoError::from(format!("unrecognized char: {ch}")));
-                    }
+                    let ii = ch
+                        .to_digit(10)
+                        .ok_or_else(|| ProtoError::from(format!("unrecognized char: {ch}")))?;
+                    state = ParseState::Escape3(i, ii);
                 }
                 ParseState::Escape3(i, ii) => {
-                    if ch.is_numeric() {
-                        // octal conversion
-                        let val: u32 = (i * 8 * 8)
-                            + (ii * 8)
-                            + ch.to_digit(8)
-                                .ok_or_else(|| ProtoError::from(format!("illegal char: {ch}")))?;
-                        let new: char = char::from_u32(val)
-                            .ok_or_else(|| ProtoError::from(format!("illegal char: {ch}")))?;
-                        label.push(new);
-                        state = ParseState::Label;
-                    } else {
-                        return Err(format!("unrecognized char: {ch}").into());
-                    }
+                    let iii = ch
+                        .to_digit(10)
+                        .ok_or_else(|| ProtoError::from(format!("unrecognized char: {ch}")))?;
+                    // \DDD is the decimal value of an octet, RFC 1035 section 5.1
+                    let val = u8::try_from(i * 100 + ii * 10 + iii)
+                        .map_err(|_| ProtoError::from(format!("illegal octet: \\{i}{ii}{iii}")))?;
+                    label.push(char::from(val));
+                    raw.push(val);
+                    state = ParseState::Label;
                 }
             }
         }
 
+        if !matches!(state, ParseState::Label) {
+            return Err("name ends with an incomplete escape".into());
+        }
+
         if !label.is_empty() {
-            name = name.append_label(E::to_label(&label)?)?;
+            name = name.append_label(Self::to_label::<E>(&label, &raw, escaped)?)?;
         }
 
         if local.ends_with('.') {
@@ -617,7 +626,11 @@ impl Name {
             }
 
             labels_written.push(encoder.offset());
-            encoder.emit_character_data(label)?;
+            if encoder.is_lowercase_names() {
+                encoder.emit_character_data(label.to_ascii_lowercase())?;
+            } else {
+                encoder.emit_character_data(label)?;
+            }
         }
         let last_index = encoder.offset();
         // now search for other labels already stored matching from the beginning label, strip then to the end
@@ -678,20 +691,14 @@ impl Name {
         }
     }
 
-    /// compares with the other label, ignoring case
+    /// compares the raw bytes of the labels with `F`, from the root to the leftmost label
     fn cmp_with_f<F: LabelCmp>(&self, other: &Self) -> Ordering {
-        if self.label_ends.is_empty() && other.label_ends.is_empty() {
-            return Ordering::Equal;
-        }
-
         // we reverse the iters so that we are comparing from the root/domain to the local...
         let self_labels = self.iter().rev();
         let other_labels = other.iter().rev();
 
         for (l, r) in self_labels.zip(other_labels) {
-            let l = Label::from_raw_bytes(l).unwrap();
-            let r = Label::from_raw_bytes(r).unwrap();
-            match l.cmp_with_f::<F>(&r) {
+            match F::cmp_bytes(l, r) {
                 Ordering::Equal => continue,
                 not_eq => return not_eq,
             }
@@ -710,6 +717,47 @@ impl Name {
         self.cmp_with_f::<CaseSensitive>(other) == Ordering::Equal
     }
 
+    /// Compares the Names in the canonical order of RFC 4034, ignoring the case of ASCII letters
+    ///
+    /// The raw bytes of the labels are compared, so the result does not depend on how the names
+    ///  were created, e.g. decoded from a message or parsed from a zone file. This is the `Ord`
+    ///  implementation of `Name`.
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use std::str::FromStr;
+    /// use hickory_proto::rr::Name;
+    ///
+    /// let name = Name::from_str("WWW.Example.COM.").unwrap();
+    /// let lower = Name::from_str("www.example.com.").unwrap();
+    /// assert_eq!(name.cmp_case_insensitive(&lower), Ordering::Equal);
+    /// assert_eq!(name.cmp_case(&lower), Ordering::Less);
+    /// ```
+    pub fn cmp_case_insensitive(&self, other: &Self) -> Ordering {
+        self.cmp_with_f::<CaseInsensitive>(other)
+    }
+
+    /// Compares the Names for equality, ignoring the case of ASCII letters, RFC 4343
+    ///
+    /// Like [`Self::cmp_case_insensitive`], this compares the raw bytes of the labels. This is the
+    ///  `PartialEq` implementation of `Name`, which does not consider whether the names are FQDNs.
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use hickory_proto::rr::Name;
+    ///
+    /// let name = Name::from_str("WWW.Example.COM.").unwrap();
+    /// assert!(name.eq_case_insensitive(&Name::from_str("www.example.com.").unwrap()));
+    /// assert!(!name.eq_case(&Name::from_str("www.example.com.").unwrap()));
+    /// ```
+    pub fn eq_case_insensitive(&self, other: &Self) -> bool {
+        self.label_ends.len() == other.label_ends.len()
+            && self
+                .iter()
+                .zip(other.iter())
+                .all(|(l, r)| l.eq_ignore_ascii_case(r))
+    }
+
     /// Converts this name into an ascii safe string.
     ///
     /// If the name is an IDNA name, then the name labels will be returned with the `xn--` prefix.
@@ -731,7 +779,7 @@ impl Name {
     }
 
     /// Converts a *.arpa Name in a PTR record back into an IpNet if possible.
-    pub fn parse_arpa_name(&self) -> Result<IpNet, ProtoError> {
+    pub fn parse_arpa_name(&self) -> ProtoResult<IpNet> {
         if !self.is_fqdn() {
             return Err("PQDN cannot be valid arpa name".into());
         }
@@ -1066,20 +1114,19 @@ impl From<Ipv6Addr> for Name {
 
 impl PartialEq<Self> for Name {
     fn eq(&self, other: &Self) -> bool {
-        self.cmp_with_f::<CaseInsensitive>(other) == Ordering::Equal
+        self.eq_case_insensitive(other)
     }
 }
 
 impl Hash for Name {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.is_fqdn.hash(state);
-
-        // this needs to be CaseInsensitive like PartialEq
-        for l in self
-            .iter()
-            .map(|l| Label::from_raw_bytes(l).unwrap().to_lowercase())
-        {
-            l.hash(state);
+        // this needs to be CaseInsensitive like PartialEq, which also ignores is_fqdn
+        state.write_usize(self.label_ends.len());
+        for label in self.iter() {
+            state.write_usize(label.len());
+            for byte in label {
+                state.write_u8(byte.to_ascii_lowercase());
+            }
         }
     }
 }
@@ -1096,6 +1143,11 @@ impl BinEncodable for Name {
         let is_canonical_names = encoder.is_canonical_names();
         self.emit_as_canonical(encoder, is_canonical_names)
     }
+
+    /// The length of the labels, each with their length octet, and the root label
+    fn encoded_size(&self) -> usize {
+        self.label_ends.len() + self.label_data.len() + 1
+    }
 }
 
 impl<'r> BinDecodable<'r> for Name {
@@ -1222,6 +1274,71 @@ fn read_inner(
     Ok(())
 }
 
+/// Writes `name` to the encoder, replacing any suffix already written with a compression pointer
+///
+/// This compresses the name regardless of whether the encoder is in canonical mode, and stores the
+/// offsets of the labels written in the encoder's pointer table for use by later names
+/// (RFC 1035 §4.1.4).
+pub fn compress_name(name: &Name, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+    name.emit_as_canonical(encoder, false)
+}
+
+/// Reads a possibly compressed name from the decoder
+///
+/// Compression pointers are resolved as offsets into `msg_start`, which must be the entire
+/// message starting at the first octet of the header. The decoder is left positioned after the
+/// first pointer or the root label of the name. Pointers that lead back to an offset which was
+/// already followed are rejected as a loop.
+///
+/// # Arguments
+///
+/// * `decoder` - decoder positioned at the start of the name
+/// * `msg_start` - the full message buffer against which pointers are resolved
+pub fn decompress_name<'a>(decoder: &mut BinDecoder<'a>, msg_start: &'a [u8]) -> ProtoResult<Name> {
+    let mut name = Name::root();
+    let mut followed = Vec::<u16>::new();
+    // labels are read from the decoder up to the first pointer, then from the message
+    let mut pointed: Option<BinDecoder<'a>> = None;
+
+    loop {
+        let current = match pointed.as_mut() {
+            Some(pointed) => pointed,
+            None => &mut *decoder,
+        };
+
+        match current.peek().map(Restrict::unverified) {
+            Some(0) | None => {
+                current.pop()?;
+                break;
+            }
+            Some(byte) if byte & 0b1100_0000 == 0b1100_0000 => {
+                let idx = current.index();
+                let ptr = current.read_u16()?.unverified(/*checked below*/) & 0x3FFF;
+                if followed.contains(&ptr) {
+                    return Err(DecodeError::PointerLoop { idx, ptr }.into());
+                }
+                if ptr as usize >= msg_start.len() {
+                    return Err(DecodeError::InsufficientBytes.into());
+                }
+
+                followed.push(ptr);
+                pointed = Some(BinDecoder::new(msg_start).clone(ptr));
+            }
+            Some(byte) if byte & 0b1100_0000 == 0b0000_0000 => {
+                let label = current
+                    .read_character_data()?
+                    .verify_unwrap(|l| l.len() <= 63)
+                    .map_err(|l| DecodeError::LabelBytesTooLong(l.len()))?;
+
+                name.extend_name(label)?;
+            }
+            Some(byte) => return Err(DecodeError::UnrecognizedLabelCode(byte).into()),
+        }
+    }
+
+    Ok(name)
+}
+
 impl fmt::Display for Name {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.write_labels::<fmt::Formatter<'_>, LabelEncUtf8>(f)
@@ -1271,7 +1388,7 @@ impl Ord for Name {
     ///            \200.z.example
     /// ```
     fn cmp(&self, other: &Self) -> Ordering {
-        self.cmp_with_f::<CaseInsensitive>(other)
+        self.cmp_case_insensitive(other)
     }
 }
 
@@ -1477,6 +1594,53 @@ mod tests {
         assert_eq!(third, r_test);
     }
 
+    #[test]
+    fn test_compress_decompress_name() {
+        let mut bytes: Vec<u8> = Vec::with_capacity(512);
+
+        let first = Name::from_str("ra.rb.rc.").unwrap();
+        let second = Name::from_str("z.rb.rc.").unwrap();
+
+        {
+            let mut e = BinEncoder::new(&mut bytes);
+            e.set_canonical_names(true);
+
+            compress_name(&first, &mut e).unwrap();
+            assert_eq!(e.len(), 10);
+
+            // "z" and a pointer to "rb.rc", even though the encoder is canonical
+            compress_name(&second, &mut e).unwrap();
+            assert_eq!(e.len(), 14);
+        }
+
+        let mut d = BinDecoder::new(&bytes);
+        assert_eq!(decompress_name(&mut d, &bytes).unwrap(), first);
+        assert_eq!(decompress_name(&mut d, &bytes).unwrap(), second);
+        assert!(d.is_empty());
+
+        // pointers are resolved against the message, not the decoder's buffer
+        let mut d = BinDecoder::new(&bytes[10..]);
+        assert_eq!(decompress_name(&mut d, &bytes).unwrap(), second);
+    }
+
+    #[test]
+    fn test_decompress_name_pointer_loop() {
+        // a label followed by a pointer back to itself
+        let bytes = vec![0x01, 0x41, 0xC0, 0x00];
+        let mut d = BinDecoder::new(&bytes);
+        assert!(decompress_name(&mut d, &bytes).is_err());
+
+        // two pointers directing to each other
+        let bytes = vec![0xC0, 0x02, 0xC0, 0x00];
+        let mut d = BinDecoder::new(&bytes);
+        assert!(decompress_name(&mut d, &bytes).is_err());
+
+        // pointer past the end of the message
+        let bytes = vec![0xC0, 0x10];
+        let mut d = BinDecoder::new(&bytes);
+        assert!(decompress_name(&mut d, &bytes).is_err());
+    }
+
     #[test]
     fn test_recursive_pointer() {
         // points to an invalid beginning label marker
@@ -1650,6 +1814,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_case_insensitive() {
+        use std::collections::hash_map::DefaultHasher;
+
+        fn hash(name: &Name) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            name.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        // decoded from the wire, the case of the labels is preserved as is
+        let wire = Name::from_bytes(b"\x03WwW\x07ExAmPlE\x03CoM\x00").unwrap();
+        let lower = Name::from_str("www.example.com.").unwrap();
+        let relative = Name::from_str("www.example.com").unwrap();
+
+        for other in [&lower, &relative] {
+            assert!(wire.eq_case_insensitive(other));
+            assert_eq!(wire.cmp_case_insensitive(other), Ordering::Equal);
+            assert_eq!(&wire, other);
+            assert_eq!(hash(&wire), hash(other));
+            assert!(!wire.eq_case(other));
+        }
+        assert!(Name::from_str("example.com.").unwrap().zone_of(&wire));
+        assert!(!Name::from_str("example.com.").unwrap().zone_of_case(&wire));
+
+        // only ASCII letters are compared ignoring case
+        let upper = Name::from_labels(vec![&[0xC4u8] as &[u8], b"example"]).unwrap();
+        let lower = Name::from_labels(vec![&[0xE4u8] as &[u8], b"example"]).unwrap();
+        assert!(!upper.eq_case_insensitive(&lower));
+        assert_eq!(upper.cmp_case_insensitive(&lower), Ordering::Less);
+
+        // labels are compared as a whole, not the concatenated label data
+        let split = Name::from_str("ab.c.").unwrap();
+        let joined = Name::from_str("a.bc.").unwrap();
+        assert!(!split.eq_case_insensitive(&joined));
+        assert_ne!(split.cmp_case_insensitive(&joined), Ordering::Equal);
+    }
+
     #[test]
     fn test_from_ipv4() {
         let ip = IpAddr::V4(Ipv4Addr::new(26, 3, 0, 103));
@@ -1899,6 +2101,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_from_labels() {
+        let name = Name::from_labels([b"www".as_slice(), b"example", b"com"]).unwrap();
+        assert_eq!(name, Name::from_ascii("www.example.com.").unwrap());
+        assert!(name.is_fqdn());
+
+        // an empty iterator is the root
+        let root = Name::from_labels(Vec::<&[u8]>::new()).unwrap();
+        assert!(root.is_root());
+        assert_eq!(root, Name::root());
+    }
+
+    #[test]
+    fn test_from_labels_max_length() {
+        // 3 labels of 63 bytes and one of 61, each with a length octet, plus the root label
+        let long = [b'a'; 63];
+        let labels = [&long[..], &long[..], &long[..], &long[..61]];
+        let name = Name::from_labels(labels).unwrap();
+        assert_eq!(name.to_string().len(), 253 + 1);
+
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        name.emit(&mut encoder).unwrap();
+        assert_eq!(bytes.len(), 255);
+
+        let mut decoder = BinDecoder::new(&bytes);
+        assert_eq!(Name::read(&mut decoder).unwrap(), name);
+
+        // one more byte exceeds the maximum
+        let labels = [&long[..], &long[..], &long[..], &long[..62]];
+        let error = Name::from_labels(labels).unwrap_err();
+        assert!(matches!(error.kind(), ProtoErrorKind::DomainNameTooLong(_)));
+
+        let labels = vec![&long[..1]; 128];
+        let error = Name::from_labels(labels).unwrap_err();
+        assert!(matches!(error.kind(), ProtoErrorKind::DomainNameTooLong(_)));
+        assert!(Name::from_labels(vec![&long[..1]; 127]).is_ok());
+    }
+
+    #[test]
+    fn test_from_labels_invalid_label() {
+        let error = Name::from_labels([&[b'a'; 64][..]]).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ProtoErrorKind::LabelBytesTooLong(64)
+        ));
+
+        assert!(Name::from_labels([&b"www"[..], b"", b"com"]).is_err());
+        assert!(Name::from_labels([&[b'a'; 63][..]]).is_ok());
+    }
+
     #[test]
     fn test_double_ended_iterator() {
         let name = Name::from_ascii("www.example.com").unwrap();
@@ -1928,4 +2181,80 @@ mod tests {
         assert!(iter.next().is_none());
         assert_eq!(iter.size_hint().0, 0);
     }
+
+    #[test]
+    fn test_common_names_are_inline() {
+        for ascii in [
+            "www.example.com.",
+            "_sip._tcp.example.com.",
+            "mail.eu-west.example.co.uk.",
+            "1.2.0.192.in-addr.arpa.",
+        ] {
+            let name = Name::from_ascii(ascii).unwrap();
+            assert!(name.label_data.is_inline(), "{name}");
+            assert!(name.label_ends.is_inline(), "{name}");
+
+            let lower = name.to_lowercase();
+            assert!(lower.label_data.is_inline(), "{lower}");
+
+            let bytes = name.to_bytes().unwrap();
+            let decoded = Name::from_bytes(&bytes).unwrap();
+            assert!(decoded.label_data.is_inline(), "{decoded}");
+            assert_eq!(decoded, name);
+        }
+
+        // longer names spill to the heap and behave the same
+        let long =
+            Name::from_ascii("a-rather-long-host-name.in-a-deeply.nested.example.com.").unwrap();
+        assert!(!long.label_data.is_inline());
+        assert_eq!(long.num_labels(), 5);
+        assert_eq!(Name::from_bytes(&long.to_bytes().unwrap()).unwrap(), long);
+    }
+
+    #[test]
+    fn test_escaped_round_trip() {
+        let labels: [&[u8]; 5] = [
+            b"line\nbreak",
+            b"nul\0",
+            b"\"quoted\"",
+            b"high\xc8\xff",
+            b"a.b\\c",
+        ];
+        for label in labels {
+            let name = Name::from_labels(vec![label, b"example", b"com"]).unwrap();
+            let text = name.to_string();
+            assert!(text.is_ascii(), "{text}");
+            assert!(!text.chars().any(|c| c.is_ascii_control()), "{text}");
+
+            for parsed in [Name::from_ascii(&text), Name::from_utf8(&text)] {
+                let parsed = parsed.unwrap_or_else(|e| panic!("{text}: {e}"));
+                assert!(parsed.eq_case(&name), "{text}");
+                assert_eq!(parsed.to_string(), text);
+            }
+        }
+
+        assert_eq!(
+            Name::from_ascii("a\\200\\010b.").unwrap().iter().next(),
+            Some(&b"a\xc8\nb"[..])
+        );
+        assert!(Name::from_ascii("a\\256.").is_err());
+        assert!(Name::from_ascii("a\\1x0.").is_err());
+        assert!(Name::from_ascii("a\\12").is_err());
+    }
+
+    #[test]
+    fn test_debug_escapes_log_injection() {
+        let name = Name::from_labels(vec![
+            &b"www\n2024-01-01T00:00:00Z ERROR forged"[..],
+            b"example",
+            b"com",
+        ])
+        .unwrap();
+
+        let debug = format!("{name:?}");
+        assert!(!debug.contains('\n'), "{debug}");
+        assert!(debug.starts_with("Name(\"www\\010"), "{debug}");
+        assert!(debug.contains("\\032ERROR\\032forged."), "{debug}");
+        assert!(!name.to_string().contains('\n'));
+    }
 }
@@ -187,6 +187,10 @@ impl BinEncodable for LowerName {
         let is_canonical_names = encoder.is_canonical_names();
         self.emit_as_canonical(encoder, is_canonical_names)
     }
+
+    fn encoded_size(&self) -> usize {
+        self.0.encoded_size()
+    }
 }
 
 impl fmt::Display for LowerName {
@@ -7,6 +7,7 @@
 
 //! Resource record related components, e.g. `Name` aka label, `Record`, `RData`, ...
 
+pub mod canonical;
 pub mod dns_class;
 // TODO: rename to sec
 #[cfg(feature = "dnssec")]
@@ -14,6 +15,7 @@ pub mod dns_class;
 pub mod dnssec;
 pub mod domain;
 mod lower_name;
+pub(crate) mod presentation;
 pub mod rdata;
 pub mod record_data;
 pub mod record_type;
@@ -21,6 +23,7 @@ pub mod resource;
 mod rr_key;
 mod rr_set;
 pub mod type_bit_map;
+mod typed_records;
 
 use std::fmt::{Debug, Display};
 
@@ -39,7 +42,9 @@ pub use self::rr_set::IntoRecordSet;
 pub use self::rr_set::RecordSet;
 pub use self::rr_set::RrsetRecords;
 pub use lower_name::LowerName;
+pub use presentation::LogSafe;
 pub use rr_key::RrKey;
+pub use typed_records::{canonical_name, AddressIter, RecordDataIter, TxtStringIter};
 
 /// RecordData that is stored in a DNS Record.
 ///
@@ -62,6 +67,14 @@ pub trait RecordData: Clone + Sized + PartialEq + Eq + Display + Debug + BinEnco
     fn is_update(&self) -> bool {
         false
     }
+
+    /// Checks that the record data is valid for its record type
+    ///
+    /// The typed record data is consistent by construction, only the untyped data of
+    ///  `RData::Unknown` may not be valid for its type code, e.g. 3 bytes for an A record.
+    fn validate(&self) -> ProtoResult<()> {
+        Ok(())
+    }
 }
 
 trait RecordDataDecodable<'r>: Sized {
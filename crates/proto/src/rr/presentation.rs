@@ -0,0 +1,176 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Escaping of character-strings in presentation format, and of any output in logs
+
+use std::{
+    fmt::{self, Write},
+    str,
+};
+
+/// Writes the octets as a quoted character-string in presentation format
+///
+/// [RFC 1035, DOMAIN NAMES - IMPLEMENTATION AND SPECIFICATION, November 1987](https://tools.ietf.org/html/rfc1035#section-5.1)
+///
+/// ```text
+/// \X              where X is any character other than a digit (0-9), is
+///                 used to quote that character so that its special meaning
+///                 does not apply.  For example, "\." can be used to place
+///                 a dot character in a label.
+///
+/// \DDD            where each D is a digit is the octet corresponding to
+///                 the decimal number described by DDD.  The resulting
+///                 octet is assumed to be text and is not checked for
+///                 special meaning.
+/// ```
+///
+/// `"` and `\` are escaped with `\`. Printable UTF-8 is written as is, control characters and
+///  octets which are not valid UTF-8 as `\DDD`, so that the output parses back to the same octets.
+pub(crate) fn write_character_string<W: Write>(f: &mut W, bytes: &[u8]) -> fmt::Result {
+    f.write_char('"')?;
+    write_escaped(f, bytes)?;
+    f.write_char('"')
+}
+
+/// Writes the octets escaped like [`write_character_string`], without the quotes
+pub(crate) fn write_escaped<W: Write>(f: &mut W, mut bytes: &[u8]) -> fmt::Result {
+    loop {
+        match str::from_utf8(bytes) {
+            Ok(text) => return write_escaped_text(f, text),
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                write_escaped_text(f, str::from_utf8(valid).expect("checked by from_utf8"))?;
+
+                let invalid_len = e.error_len().unwrap_or(rest.len());
+                for byte in &rest[..invalid_len] {
+                    write!(f, "\\{byte:03}")?;
+                }
+                bytes = &rest[invalid_len..];
+            }
+        }
+    }
+}
+
+fn write_escaped_text<W: Write>(f: &mut W, text: &str) -> fmt::Result {
+    for ch in text.chars() {
+        match ch {
+            '"' | '\\' => {
+                f.write_char('\\')?;
+                f.write_char(ch)?;
+            }
+            ch if is_unsafe(ch) => {
+                for byte in ch.encode_utf8(&mut [0; 4]).bytes() {
+                    write!(f, "\\{byte:03}")?;
+                }
+            }
+            ch => f.write_char(ch)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Control characters, and the characters which change how the text around them is displayed
+fn is_unsafe(ch: char) -> bool {
+    ch.is_control()
+        || matches!(
+            ch,
+            '\u{200e}' | '\u{200f}' | '\u{2028}'..='\u{202e}' | '\u{2066}'..='\u{2069}'
+        )
+}
+
+/// Displays a value with all control characters escaped, for log lines
+///
+/// Names and record data can contain any octets. Written to logs as is, line breaks forge log
+///  entries and terminal escape sequences or bidi controls hide text. ASCII control characters
+///  are written as `\DDD`, other ones as `\u{XXXX}`. Unlike the presentation format, the output
+///  is not meant to be parsed again.
+///
+/// ```
+/// use hickory_proto::rr::LogSafe;
+///
+/// let text = "forged\n[ERROR] entry";
+/// assert_eq!(LogSafe(text).to_string(), "forged\\010[ERROR] entry");
+/// assert_eq!(LogSafe("\u{202e}txt.exe").to_string(), "\\u{202e}txt.exe");
+/// ```
+#[derive(Clone, Copy)]
+pub struct LogSafe<T>(pub T);
+
+impl<T: fmt::Display> fmt::Display for LogSafe<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(EscapeUnsafe(f), "{}", self.0)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for LogSafe<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(EscapeUnsafe(f), "{:?}", self.0)
+    }
+}
+
+/// Escapes the unsafe characters written through it
+struct EscapeUnsafe<'a, 'f>(&'a mut fmt::Formatter<'f>);
+
+impl Write for EscapeUnsafe<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for ch in s.chars() {
+            match ch {
+                ch if !is_unsafe(ch) => self.0.write_char(ch)?,
+                ch if ch.is_ascii() => write!(self.0, "\\{:03}", ch as u8)?,
+                ch => write!(self.0, "\\u{{{:04x}}}", ch as u32)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn character_string(bytes: &[u8]) -> String {
+        let mut text = String::new();
+        write_character_string(&mut text, bytes).unwrap();
+        text
+    }
+
+    #[test]
+    fn test_character_string() {
+        assert_eq!(character_string(b""), r#""""#);
+        assert_eq!(character_string(b"plain text"), r#""plain text""#);
+        assert_eq!(
+            character_string(b"a \"quoted\" \\ b"),
+            r#""a \"quoted\" \\ b""#
+        );
+        assert_eq!(
+            character_string(b"line\nbreak\0\x7f"),
+            r#""line\010break\000\127""#
+        );
+        assert_eq!(character_string("h\u{e9}".as_bytes()), "\"h\u{e9}\"");
+        assert_eq!(
+            character_string(b"invalid \xf0\x90\x80 utf8 \xff"),
+            r#""invalid \240\144\128 utf8 \255""#
+        );
+        assert_eq!(
+            character_string("\u{85}\u{202e}".as_bytes()),
+            r#""\194\133\226\128\174""#
+        );
+    }
+
+    #[test]
+    fn test_log_safe() {
+        assert_eq!(
+            LogSafe("a\r\nb\x1b[31m").to_string(),
+            "a\\013\\010b\\027[31m"
+        );
+        assert_eq!(LogSafe("tab\there").to_string(), "tab\\009here");
+        assert_eq!(LogSafe("h\u{e9} \\ \"").to_string(), "h\u{e9} \\ \"");
+        assert_eq!(LogSafe("\u{2028}").to_string(), "\\u{2028}");
+        assert_eq!(format!("{:?}", LogSafe("\u{85}")), "\"\\u{85}\"");
+    }
+}
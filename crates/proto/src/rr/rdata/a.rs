@@ -89,6 +89,10 @@ impl BinEncodable for A {
         encoder.emit(segments[3])?;
         Ok(())
     }
+
+    fn encoded_size(&self) -> usize {
+        4
+    }
 }
 
 impl<'r> BinDecodable<'r> for A {
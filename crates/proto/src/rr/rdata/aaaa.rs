@@ -87,6 +87,10 @@ impl BinEncodable for AAAA {
         encoder.emit_u16(segments[7])?;
         Ok(())
     }
+
+    fn encoded_size(&self) -> usize {
+        16
+    }
 }
 
 impl<'r> BinDecodable<'r> for AAAA {
@@ -0,0 +1,352 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! APL records for lists of address prefixes
+#![allow(clippy::use_self)]
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{ProtoError, ProtoResult},
+    rr::{RData, RecordData, RecordDataDecodable, RecordType},
+    serialize::binary::{BinDecoder, BinEncodable, BinEncoder, Restrict, RestrictedMath},
+};
+
+/// The IANA address family number of IPv4
+const FAMILY_IPV4: u16 = 1;
+/// The IANA address family number of IPv6
+const FAMILY_IPV6: u16 = 2;
+
+/// [RFC 3123](https://tools.ietf.org/html/rfc3123#section-4)
+///
+/// ```text
+/// 4. APL RDATA format
+///
+///    The RDATA section consists of zero or more items (<apitem>) of the
+///    form
+///
+///       +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///       |                          ADDRESSFAMILY                |
+///       +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///       |             PREFIX    | N|         AFDLENGTH  |
+///       +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///       /                          AFDPART                      /
+///       |                                                       |
+///       +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///
+///       ADDRESSFAMILY     16 bit unsigned value as assigned by IANA
+///                         (see IANA Considerations)
+///       PREFIX            8 bit unsigned binary coded prefix length.
+///                         Upper and lower bounds and interpretation of
+///                         this value are address family specific.
+///       N                 negation flag, indicates the presence of the
+///                         "!" character in the textual format.  It has
+///                         the value "1" if the "!" was given, "0" else.
+///       AFDLENGTH         length in octets of the following address
+///                         family dependent part (7 bit unsigned).
+///       AFDPART           address family dependent part.  See below.
+/// ```
+///
+/// Only the address families of IPv4 and IPv6 are supported, as they are the only ones with a
+///  presentation format.
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct APL(Vec<AddressPrefix>);
+
+impl APL {
+    /// Creates a new APL record data from a list of address prefixes
+    pub fn new(items: Vec<AddressPrefix>) -> Self {
+        Self(items)
+    }
+
+    /// The address prefixes of the list, in order
+    pub fn items(&self) -> &[AddressPrefix] {
+        &self.0
+    }
+}
+
+/// An item of an APL record, an address prefix which may be negated
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct AddressPrefix {
+    negation: bool,
+    address: IpAddr,
+    prefix_len: u8,
+}
+
+impl AddressPrefix {
+    /// Creates a new address prefix
+    ///
+    /// # Arguments
+    ///
+    /// * `negation` - true if the prefix is excluded from the list, `!` in the presentation format.
+    /// * `address` - the address of the prefix.
+    /// * `prefix_len` - the number of significant bits of the address.
+    ///
+    /// # Returns
+    ///
+    /// An error if the prefix length is longer than the address
+    pub fn new(negation: bool, address: IpAddr, prefix_len: u8) -> ProtoResult<Self> {
+        let max = match address {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        if prefix_len > max {
+            return Err(format!("APL prefix length too long: {address}/{prefix_len}").into());
+        }
+
+        Ok(Self {
+            negation,
+            address,
+            prefix_len,
+        })
+    }
+
+    /// Returns true if the prefix is excluded from the list
+    pub fn negation(&self) -> bool {
+        self.negation
+    }
+
+    /// The address of the prefix
+    pub fn address(&self) -> IpAddr {
+        self.address
+    }
+
+    /// The number of significant bits of the address
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// The address family number, 1 for IPv4 and 2 for IPv6
+    pub fn family(&self) -> u16 {
+        match self.address {
+            IpAddr::V4(_) => FAMILY_IPV4,
+            IpAddr::V6(_) => FAMILY_IPV6,
+        }
+    }
+
+    /// The address without its trailing zero octets, which are omitted from the wire format
+    fn afdpart(&self) -> Vec<u8> {
+        let mut octets = match self.address {
+            IpAddr::V4(address) => address.octets().to_vec(),
+            IpAddr::V6(address) => address.octets().to_vec(),
+        };
+        while octets.last() == Some(&0) {
+            octets.pop();
+        }
+        octets
+    }
+}
+
+impl BinEncodable for APL {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        for item in &self.0 {
+            let afdpart = item.afdpart();
+            encoder.emit_u16(item.family())?;
+            encoder.emit_u8(item.prefix_len)?;
+            encoder.emit_u8(u8::from(item.negation) << 7 | afdpart.len() as u8)?;
+            encoder.emit_vec(&afdpart)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'r> RecordDataDecodable<'r> for APL {
+    fn read_data(decoder: &mut BinDecoder<'r>, length: Restrict<u16>) -> ProtoResult<Self> {
+        let mut remaining = length.map(|l| l as usize);
+        let mut items = Vec::new();
+
+        while remaining.unverified() > 0 {
+            let family = decoder.read_u16()?.unverified();
+            let prefix_len = decoder.read_u8()?.unverified();
+            let flags = decoder.read_u8()?.unverified();
+            let negation = flags & 0b1000_0000 != 0;
+            let afd_len = usize::from(flags & 0b0111_1111);
+
+            remaining = remaining
+                .checked_sub(4 + afd_len)
+                .map_err(|_| ProtoError::from("invalid rdata length in APL"))?;
+            let afdpart = decoder.read_slice(afd_len)?.unverified();
+
+            let address = match family {
+                FAMILY_IPV4 if afd_len <= 4 => {
+                    let mut octets = [0; 4];
+                    octets[..afd_len].copy_from_slice(afdpart);
+                    IpAddr::V4(Ipv4Addr::from(octets))
+                }
+                FAMILY_IPV6 if afd_len <= 16 => {
+                    let mut octets = [0; 16];
+                    octets[..afd_len].copy_from_slice(afdpart);
+                    IpAddr::V6(Ipv6Addr::from(octets))
+                }
+                FAMILY_IPV4 | FAMILY_IPV6 => {
+                    return Err(format!("APL address too long: {afd_len} octets").into())
+                }
+                _ => return Err(format!("unsupported APL address family: {family}").into()),
+            };
+
+            items.push(AddressPrefix::new(negation, address, prefix_len)?);
+        }
+
+        Ok(APL::new(items))
+    }
+}
+
+impl RecordData for APL {
+    fn try_from_rdata(data: RData) -> Result<Self, RData> {
+        match data {
+            RData::APL(data) => Ok(data),
+            _ => Err(data),
+        }
+    }
+
+    fn try_borrow(data: &RData) -> Option<&Self> {
+        match data {
+            RData::APL(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    fn record_type(&self) -> RecordType {
+        RecordType::APL
+    }
+
+    fn into_rdata(self) -> RData {
+        RData::APL(self)
+    }
+}
+
+/// [RFC 3123](https://tools.ietf.org/html/rfc3123#section-5)
+///
+/// ```text
+/// 5. Zone File Syntax
+///
+///    The textual representation of an APL RR in a DNS zone file is as
+///    follows:
+///
+///    <owner>   IN   <TTL>   APL   {[!]afi:address/prefix}*
+///
+///    The data consists of zero or more strings of the address family
+///    indicator <afi>, immediately followed by a colon ":", an address,
+///    immediately followed by the "/" character, immediately followed by a
+///    decimal numeric value for the prefix length.  Any such string may be
+///    preceded by a "!" character.  The strings are separated by
+///    whitespace.
+/// ```
+impl fmt::Display for APL {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let mut first = true;
+        for item in &self.0 {
+            if !first {
+                f.write_str(" ")?;
+            }
+            first = false;
+            write!(f, "{item}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for AddressPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{negation}{family}:{address}/{prefix_len}",
+            negation = if self.negation { "!" } else { "" },
+            family = self.family(),
+            address = self.address,
+            prefix_len = self.prefix_len,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn emit(rdata: &APL) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        rdata.emit(&mut encoder).expect("failed to emit APL");
+        bytes
+    }
+
+    fn read(bytes: &[u8]) -> ProtoResult<APL> {
+        let mut decoder = BinDecoder::new(bytes);
+        APL::read_data(&mut decoder, Restrict::new(bytes.len() as u16))
+    }
+
+    fn prefix(negation: bool, address: &str, prefix_len: u8) -> AddressPrefix {
+        AddressPrefix::new(negation, address.parse().unwrap(), prefix_len).unwrap()
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        // foo.example. IN APL 1:192.168.32.0/21 !1:192.168.38.0/28
+        let rdata = APL::new(vec![
+            prefix(false, "192.168.32.0", 21),
+            prefix(true, "192.168.38.0", 28),
+        ]);
+        let bytes = [
+            0, 1, 21, 3, 192, 168, 32, //
+            0, 1, 28, 0x83, 192, 168, 38,
+        ];
+        assert_eq!(emit(&rdata), bytes);
+        assert_eq!(read(&bytes).unwrap(), rdata);
+        assert_eq!(rdata.to_string(), "1:192.168.32.0/21 !1:192.168.38.0/28");
+
+        // baz.example. IN APL 1:224.0.0.0/4 2:FF00:0:0:0:0:0:0:0/8
+        let rdata = APL::new(vec![
+            prefix(false, "224.0.0.0", 4),
+            prefix(false, "ff00::", 8),
+        ]);
+        let bytes = [0, 1, 4, 1, 224, 0, 2, 8, 1, 0xff];
+        assert_eq!(emit(&rdata), bytes);
+        assert_eq!(read(&bytes).unwrap(), rdata);
+
+        // an empty list, and the default route with an empty afdpart
+        let rdata = APL::new(vec![]);
+        assert!(emit(&rdata).is_empty());
+        assert_eq!(read(&[]).unwrap(), rdata);
+        let rdata = APL::new(vec![prefix(true, "0.0.0.0", 0)]);
+        assert_eq!(emit(&rdata), [0, 1, 0, 0x80]);
+        assert_eq!(rdata.to_string(), "!1:0.0.0.0/0");
+    }
+
+    #[test]
+    fn test_read_trailing_zeros() {
+        // trailing zero octets may be present on the wire
+        let rdata = read(&[0, 1, 16, 4, 10, 1, 0, 0]).unwrap();
+        assert_eq!(rdata.items(), [prefix(false, "10.1.0.0", 16)]);
+        assert_eq!(emit(&rdata), [0, 1, 16, 2, 10, 1]);
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        // unknown address family
+        assert!(read(&[0, 3, 8, 1, 10]).is_err());
+        // prefix longer than the address
+        assert!(read(&[0, 1, 33, 1, 10]).is_err());
+        // afdpart longer than the address
+        assert!(read(&[0, 1, 8, 5, 10, 0, 0, 0, 1]).is_err());
+        // truncated item
+        assert!(read(&[0, 1, 8, 2, 10]).is_err());
+        assert!(read(&[0, 1, 8]).is_err());
+    }
+
+    #[test]
+    fn test_new_invalid() {
+        assert!(AddressPrefix::new(false, "10.0.0.0".parse().unwrap(), 33).is_err());
+        assert!(AddressPrefix::new(false, "::".parse().unwrap(), 129).is_err());
+    }
+}
@@ -29,7 +29,10 @@ use url::Url;
 
 use crate::{
     error::{ProtoError, ProtoResult},
-    rr::{domain::Name, RData, RecordData, RecordDataDecodable, RecordType},
+    rr::{
+        domain::Name, presentation::write_escaped, RData, RecordData, RecordDataDecodable,
+        RecordType,
+    },
     serialize::binary::*,
 };
 
@@ -222,6 +225,7 @@ impl CAA {
 /// Specifies in what contexts this key may be trusted for use
 #[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[non_exhaustive]
 pub enum Property {
     /// The issue property
     ///    entry authorizes the holder of the domain name `Issuer Domain
@@ -879,10 +883,7 @@ impl fmt::Display for Value {
                 }
             }
             Value::Url(url) => write!(f, "{url}")?,
-            Value::Unknown(v) => match str::from_utf8(v) {
-                Ok(text) => write!(f, "{text}")?,
-                Err(_) => return Err(fmt::Error),
-            },
+            Value::Unknown(v) => write_escaped(f, v)?,
         }
 
         f.write_str("\"")
@@ -0,0 +1,230 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DELEG type and related implementations, experimental
+//!
+//! [draft-ietf-dnsop-deleg](https://datatracker.ietf.org/doc/draft-ietf-dnsop-deleg/) defines an
+//!  extensible delegation record, served by the parent at the delegation point next to the NS
+//!  records. Unlike NS records it is authoritative data of the parent, and signed by it. The
+//!  RDATA has the format of SVCB, the target is a name server of the child zone, and the
+//!  parameters carry its addresses and transports.
+//!
+//! No record type number has been assigned by IANA yet, so it is configurable with
+//!  [`set_deleg_type_code`].
+
+use std::{
+    fmt,
+    net::IpAddr,
+    ops::Deref,
+    sync::atomic::{AtomicU16, Ordering},
+};
+
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::ProtoResult,
+    rr::{RData, RecordData, RecordDataDecodable, RecordType},
+    serialize::binary::{BinDecoder, BinEncodable, BinEncoder, Restrict},
+};
+
+use super::svcb::{SvcParamValue, SVCB};
+
+/// The record type number used for DELEG until one is assigned, from the private use range
+pub const DEFAULT_DELEG_TYPE_CODE: u16 = 65432;
+
+static DELEG_TYPE_CODE: AtomicU16 = AtomicU16::new(DEFAULT_DELEG_TYPE_CODE);
+
+/// Returns the record type number of DELEG records
+pub fn deleg_type_code() -> u16 {
+    DELEG_TYPE_CODE.load(Ordering::Relaxed)
+}
+
+/// Sets the record type number of DELEG records, for all records read and written from now on
+///
+/// The number must not be one of the record types known to this crate, otherwise it's read as
+///  DELEG instead. This only exists until a number is assigned to DELEG.
+pub fn set_deleg_type_code(code: u16) {
+    DELEG_TYPE_CODE.store(code, Ordering::Relaxed);
+}
+
+/// DELEG is a delegation with the RDATA of SVCB, see SVCB for the documentation of the format
+///
+/// ```text
+/// Name TTL IN DELEG Priority TargetName Params
+/// ```
+///
+/// A priority of 0 is the alias form, the target then names a set of DELEG records to use
+///  instead. Otherwise the target is a name server of the delegated zone.
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct DELEG(pub SVCB);
+
+impl DELEG {
+    /// The addresses of the `ipv4hint` and `ipv6hint` parameters
+    pub fn ip_hints(&self) -> impl Iterator<Item = IpAddr> + '_ {
+        self.0
+            .svc_params()
+            .iter()
+            .flat_map(|(_, value)| match value {
+                SvcParamValue::Ipv4Hint(hint) => {
+                    hint.0.iter().map(|a| IpAddr::V4(a.0)).collect::<Vec<_>>()
+                }
+                SvcParamValue::Ipv6Hint(hint) => {
+                    hint.0.iter().map(|aaaa| IpAddr::V6(aaaa.0)).collect()
+                }
+                _ => Vec::new(),
+            })
+    }
+
+    /// The port of the `port` parameter
+    pub fn port(&self) -> Option<u16> {
+        self.0
+            .svc_params()
+            .iter()
+            .find_map(|(_, value)| match value {
+                SvcParamValue::Port(port) => Some(*port),
+                _ => None,
+            })
+    }
+
+    /// The transports of the `alpn` parameter, e.g. `dot` or `doq`
+    pub fn alpn(&self) -> &[String] {
+        self.0
+            .svc_params()
+            .iter()
+            .find_map(|(_, value)| match value {
+                SvcParamValue::Alpn(alpn) => Some(alpn.0.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Deref for DELEG {
+    type Target = SVCB;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl BinEncodable for DELEG {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        self.0.emit(encoder)
+    }
+}
+
+impl<'r> RecordDataDecodable<'r> for DELEG {
+    fn read_data(decoder: &mut BinDecoder<'r>, length: Restrict<u16>) -> ProtoResult<Self> {
+        SVCB::read_data(decoder, length).map(Self)
+    }
+}
+
+impl RecordData for DELEG {
+    fn try_from_rdata(data: RData) -> Result<Self, RData> {
+        match data {
+            RData::DELEG(deleg) => Ok(deleg),
+            _ => Err(data),
+        }
+    }
+
+    fn try_borrow(data: &RData) -> Option<&Self> {
+        match data {
+            RData::DELEG(deleg) => Some(deleg),
+            _ => None,
+        }
+    }
+
+    fn record_type(&self) -> RecordType {
+        RecordType::DELEG
+    }
+
+    fn into_rdata(self) -> RData {
+        RData::DELEG(self)
+    }
+}
+
+impl fmt::Display for DELEG {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+    use crate::rr::{
+        rdata::{
+            svcb::{Alpn, IpHint, SvcParamKey},
+            A, AAAA,
+        },
+        Name,
+    };
+
+    fn deleg() -> DELEG {
+        DELEG(SVCB::new(
+            1,
+            Name::from_ascii("ns1.example.net.").unwrap(),
+            vec![
+                (
+                    SvcParamKey::Alpn,
+                    SvcParamValue::Alpn(Alpn(vec!["dot".to_string()])),
+                ),
+                (SvcParamKey::Port, SvcParamValue::Port(853)),
+                (
+                    SvcParamKey::Ipv4Hint,
+                    SvcParamValue::Ipv4Hint(IpHint(vec![A::new(192, 0, 2, 1)])),
+                ),
+                (
+                    SvcParamKey::Ipv6Hint,
+                    SvcParamValue::Ipv6Hint(IpHint(vec![AAAA::new(
+                        0x2001, 0xdb8, 0, 0, 0, 0, 0, 1,
+                    )])),
+                ),
+            ],
+        ))
+    }
+
+    #[test]
+    fn test_params() {
+        let deleg = deleg();
+
+        assert_eq!(
+            deleg.ip_hints().collect::<Vec<_>>(),
+            vec![
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+            ]
+        );
+        assert_eq!(deleg.port(), Some(853));
+        assert_eq!(deleg.alpn(), &["dot".to_string()]);
+    }
+
+    #[test]
+    fn test_read_write() {
+        let rdata = RData::DELEG(deleg());
+
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        rdata.emit(&mut encoder).unwrap();
+        let length = bytes.len() as u16;
+
+        let mut decoder = BinDecoder::new(&bytes);
+        let read = RData::read(&mut decoder, RecordType::DELEG, Restrict::new(length)).unwrap();
+        assert_eq!(read, rdata);
+    }
+
+    #[test]
+    fn test_type_code() {
+        assert_eq!(u16::from(RecordType::DELEG), deleg_type_code());
+        assert_eq!(RecordType::from(deleg_type_code()), RecordType::DELEG);
+        assert_eq!("DELEG".parse::<RecordType>().unwrap(), RecordType::DELEG);
+    }
+}
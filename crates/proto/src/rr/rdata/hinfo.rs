@@ -7,14 +7,14 @@
 
 //! HINFO record for storing host information
 
-use std::fmt;
+use std::fmt::{self, Write};
 
 #[cfg(feature = "serde-config")]
 use serde::{Deserialize, Serialize};
 
 use crate::{
     error::*,
-    rr::{RData, RecordData, RecordType},
+    rr::{presentation::write_character_string, RData, RecordData, RecordType},
     serialize::binary::*,
 };
 
@@ -167,12 +167,9 @@ impl RecordData for HINFO {
 /// ```
 impl fmt::Display for HINFO {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        write!(
-            f,
-            "{cpu} {os}",
-            cpu = &String::from_utf8_lossy(&self.cpu),
-            os = &String::from_utf8_lossy(&self.os)
-        )?;
+        write_character_string(f, &self.cpu)?;
+        f.write_char(' ')?;
+        write_character_string(f, &self.os)?;
         Ok(())
     }
 }
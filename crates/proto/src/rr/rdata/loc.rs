@@ -0,0 +1,597 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! LOC records for the geographical location of a host, network or subnet
+#![allow(clippy::use_self)]
+
+use std::fmt;
+
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{ProtoError, ProtoResult},
+    rr::{RData, RecordData, RecordDataDecodable, RecordType},
+    serialize::binary::{BinDecoder, BinEncodable, BinEncoder, Restrict},
+};
+
+/// The encoded value of the equator and of the prime meridian, in thousandths of an arc second
+const EQUATOR: i64 = 1 << 31;
+
+/// The encoded value of the altitude of the WGS 84 reference spheroid, in centimeters
+const REFERENCE_ALTITUDE: i64 = 10_000_000;
+
+/// Thousandths of an arc second per degree
+const THOUSANDTHS_PER_DEGREE: i64 = 3_600_000;
+
+/// The mean radius of the earth in meters, used for the distance between two locations
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// The default size of the sphere, 1m, as `mantissa << 4 | exponent` in centimeters
+#[cfg(feature = "text-parsing")]
+pub(crate) const DEFAULT_SIZE: u8 = 0x12;
+/// The default horizontal precision, 10000m
+#[cfg(feature = "text-parsing")]
+pub(crate) const DEFAULT_HORIZ_PRE: u8 = 0x16;
+/// The default vertical precision, 10m
+#[cfg(feature = "text-parsing")]
+pub(crate) const DEFAULT_VERT_PRE: u8 = 0x13;
+
+/// [RFC 1876](https://tools.ietf.org/html/rfc1876#section-2)
+///
+/// ```text
+/// 2. RDATA Format
+///
+///        MSB                                           LSB
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///       0|        VERSION        |         SIZE          |
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///       2|       HORIZ PRE       |       VERT PRE        |
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///       4|                   LATITUDE                    |
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///       6|                   LATITUDE                    |
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///       8|                   LONGITUDE                   |
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///      10|                   LONGITUDE                   |
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///      12|                   ALTITUDE                    |
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///      14|                   ALTITUDE                    |
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    (octet)
+///
+/// VERSION      Version number of the representation.  This must be zero.
+///
+/// SIZE         The diameter of a sphere enclosing the described entity, in
+///              centimeters, expressed as a pair of four-bit unsigned
+///              integers, each ranging from zero to nine, with the most
+///              significant four bits representing the base and the second
+///              number representing the power of ten by which to multiply
+///              the base.
+///
+/// HORIZ PRE    The horizontal precision of the data, in centimeters,
+///              expressed using the same representation as SIZE.
+///
+/// VERT PRE     The vertical precision of the data, in centimeters,
+///              expressed using the same representation as for SIZE.
+///
+/// LATITUDE     The latitude of the center of the sphere described by the
+///              SIZE field, expressed as a 32-bit integer, most significant
+///              octet first (network standard byte order), in thousandths
+///              of a second of arc.  2^31 represents the equator; numbers
+///              above that are north latitude.
+///
+/// LONGITUDE    The longitude of the center of the sphere described by the
+///              SIZE field, expressed as a 32-bit integer, most significant
+///              octet first (network standard byte order), in thousandths
+///              of a second of arc, rounded away from the prime meridian.
+///              2^31 represents the prime meridian; numbers above that are
+///              east longitude.
+///
+/// ALTITUDE     The altitude of the center of the sphere described by the
+///              SIZE field, expressed as a 32-bit integer, most significant
+///              octet first (network standard byte order), in centimeters,
+///              from a base of 100,000m below the [WGS 84] reference
+///              spheroid used by GPS (semimajor axis a=6378137.0,
+///              reciprocal flattening rf=298.257223563).
+/// ```
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct LOC {
+    size: u8,
+    horiz_pre: u8,
+    vert_pre: u8,
+    latitude: u32,
+    longitude: u32,
+    altitude: u32,
+}
+
+impl LOC {
+    /// Creates a new LOC record data from the values of its wire format
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - the diameter of the location, as a mantissa and exponent of centimeters.
+    /// * `horiz_pre` - the horizontal precision, as a mantissa and exponent of centimeters.
+    /// * `vert_pre` - the vertical precision, as a mantissa and exponent of centimeters.
+    /// * `latitude` - thousandths of an arc second, 2^31 is the equator.
+    /// * `longitude` - thousandths of an arc second, 2^31 is the prime meridian.
+    /// * `altitude` - centimeters from 100,000m below the WGS 84 reference spheroid.
+    pub fn new(
+        size: u8,
+        horiz_pre: u8,
+        vert_pre: u8,
+        latitude: u32,
+        longitude: u32,
+        altitude: u32,
+    ) -> Self {
+        Self {
+            size,
+            horiz_pre,
+            vert_pre,
+            latitude,
+            longitude,
+            altitude,
+        }
+    }
+
+    /// Creates a new LOC record data from decimal degrees and meters
+    ///
+    /// Each value is encoded as the nearest value representable in the wire format, the accessors
+    ///  of the result return those values. Creating a LOC from the values returned by the
+    ///  accessors yields the same record.
+    ///
+    /// # Arguments
+    ///
+    /// * `latitude` - degrees north of the equator, negative to the south.
+    /// * `longitude` - degrees east of the prime meridian, negative to the west.
+    /// * `altitude` - meters above the WGS 84 reference spheroid.
+    /// * `size` - the diameter of the location in meters.
+    /// * `horizontal_precision` - the horizontal precision in meters.
+    /// * `vertical_precision` - the vertical precision in meters.
+    ///
+    /// # Returns
+    ///
+    /// An error if any value is out of range for the wire format
+    pub fn from_degrees(
+        latitude: f64,
+        longitude: f64,
+        altitude: f64,
+        size: f64,
+        horizontal_precision: f64,
+        vertical_precision: f64,
+    ) -> ProtoResult<Self> {
+        let encode_angle = |degrees: f64, max: f64, field: &str| {
+            if !(-max..=max).contains(&degrees) {
+                return Err(ProtoError::from(format!(
+                    "LOC {field} out of range: {degrees}"
+                )));
+            }
+
+            let thousandths = (degrees * THOUSANDTHS_PER_DEGREE as f64).round() as i64;
+            Ok((EQUATOR + thousandths) as u32)
+        };
+
+        let centimeters = (altitude * 100.0).round();
+        if !(-(REFERENCE_ALTITUDE as f64)..=(u32::MAX as i64 - REFERENCE_ALTITUDE) as f64)
+            .contains(&centimeters)
+        {
+            return Err(format!("LOC altitude out of range: {altitude}").into());
+        }
+
+        Ok(Self {
+            size: encode_precision(size)?,
+            horiz_pre: encode_precision(horizontal_precision)?,
+            vert_pre: encode_precision(vertical_precision)?,
+            latitude: encode_angle(latitude, 90.0, "latitude")?,
+            longitude: encode_angle(longitude, 180.0, "longitude")?,
+            altitude: (REFERENCE_ALTITUDE + centimeters as i64) as u32,
+        })
+    }
+
+    /// The latitude in degrees, north of the equator is positive
+    pub fn latitude(&self) -> f64 {
+        decode_angle(self.latitude)
+    }
+
+    /// The longitude in degrees, east of the prime meridian is positive
+    pub fn longitude(&self) -> f64 {
+        decode_angle(self.longitude)
+    }
+
+    /// The altitude in meters above the WGS 84 reference spheroid
+    pub fn altitude(&self) -> f64 {
+        self.altitude_centimeters() as f64 / 100.0
+    }
+
+    /// The diameter in meters of a sphere enclosing the location
+    pub fn size(&self) -> f64 {
+        decode_precision(self.size) as f64 / 100.0
+    }
+
+    /// The horizontal precision in meters
+    pub fn horizontal_precision(&self) -> f64 {
+        decode_precision(self.horiz_pre) as f64 / 100.0
+    }
+
+    /// The vertical precision in meters
+    pub fn vertical_precision(&self) -> f64 {
+        decode_precision(self.vert_pre) as f64 / 100.0
+    }
+
+    /// The great-circle distance in meters to another location
+    ///
+    /// This uses the haversine formula on a sphere of the mean radius of the earth, the altitudes
+    ///  are ignored.
+    pub fn distance_to(&self, other: &LOC) -> f64 {
+        let (lat1, lat2) = (self.latitude().to_radians(), other.latitude().to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (other.longitude() - self.longitude()).to_radians();
+
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS * a.sqrt().atan2((1.0 - a).sqrt())
+    }
+
+    fn altitude_centimeters(&self) -> i64 {
+        i64::from(self.altitude) - REFERENCE_ALTITUDE
+    }
+}
+
+fn decode_angle(angle: u32) -> f64 {
+    (i64::from(angle) - EQUATOR) as f64 / THOUSANDTHS_PER_DEGREE as f64
+}
+
+/// Decodes a size or precision to centimeters
+pub(crate) fn decode_precision(value: u8) -> u64 {
+    u64::from(value >> 4) * 10_u64.pow(u32::from(value & 0x0F))
+}
+
+/// Encodes meters as the nearest size or precision, `mantissa << 4 | exponent` in centimeters
+pub(crate) fn encode_precision(meters: f64) -> ProtoResult<u8> {
+    let centimeters = meters * 100.0;
+    if !(0.0..=9e9).contains(&centimeters) {
+        return Err(format!("LOC size or precision out of range: {meters}").into());
+    }
+
+    let mut nearest = (0, centimeters);
+    for exponent in 0..=9 {
+        for mantissa in 1..=9 {
+            let value = (mantissa << 4) | exponent;
+            let distance = (decode_precision(value) as f64 - centimeters).abs();
+            if distance < nearest.1 {
+                nearest = (value, distance);
+            }
+        }
+    }
+
+    Ok(nearest.0)
+}
+
+fn is_valid_precision(value: u8) -> bool {
+    value >> 4 <= 9 && value & 0x0F <= 9
+}
+
+impl BinEncodable for LOC {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        encoder.emit_u8(0)?;
+        encoder.emit_u8(self.size)?;
+        encoder.emit_u8(self.horiz_pre)?;
+        encoder.emit_u8(self.vert_pre)?;
+        encoder.emit_u32(self.latitude)?;
+        encoder.emit_u32(self.longitude)?;
+        encoder.emit_u32(self.altitude)
+    }
+}
+
+impl<'r> RecordDataDecodable<'r> for LOC {
+    fn read_data(decoder: &mut BinDecoder<'r>, length: Restrict<u16>) -> ProtoResult<Self> {
+        if length.map(|l| l as usize).unverified() != 16 {
+            return Err("invalid rdata length in LOC".into());
+        }
+
+        let version = decoder.read_u8()?.unverified();
+        if version != 0 {
+            return Err(format!("unsupported LOC version: {version}").into());
+        }
+
+        let size = decoder
+            .read_u8()?
+            .verify_unwrap(|v| is_valid_precision(*v))
+            .map_err(|_| ProtoError::from("invalid size in LOC"))?;
+        let horiz_pre = decoder
+            .read_u8()?
+            .verify_unwrap(|v| is_valid_precision(*v))
+            .map_err(|_| ProtoError::from("invalid horizontal precision in LOC"))?;
+        let vert_pre = decoder
+            .read_u8()?
+            .verify_unwrap(|v| is_valid_precision(*v))
+            .map_err(|_| ProtoError::from("invalid vertical precision in LOC"))?;
+        let latitude = decoder
+            .read_u32()?
+            .verify_unwrap(|v| (i64::from(*v) - EQUATOR).abs() <= 90 * THOUSANDTHS_PER_DEGREE)
+            .map_err(|_| ProtoError::from("invalid latitude in LOC"))?;
+        let longitude = decoder
+            .read_u32()?
+            .verify_unwrap(|v| (i64::from(*v) - EQUATOR).abs() <= 180 * THOUSANDTHS_PER_DEGREE)
+            .map_err(|_| ProtoError::from("invalid longitude in LOC"))?;
+        let altitude = decoder.read_u32()?.unverified();
+
+        Ok(LOC::new(
+            size, horiz_pre, vert_pre, latitude, longitude, altitude,
+        ))
+    }
+}
+
+impl RecordData for LOC {
+    fn try_from_rdata(data: RData) -> Result<Self, RData> {
+        match data {
+            RData::LOC(data) => Ok(data),
+            _ => Err(data),
+        }
+    }
+
+    fn try_borrow(data: &RData) -> Option<&Self> {
+        match data {
+            RData::LOC(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    fn record_type(&self) -> RecordType {
+        RecordType::LOC
+    }
+
+    fn into_rdata(self) -> RData {
+        RData::LOC(self)
+    }
+}
+
+struct Angle(u32, [char; 2]);
+
+impl fmt::Display for Angle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let Self(angle, [positive, negative]) = *self;
+        let thousandths = i64::from(angle) - EQUATOR;
+        let hemisphere = if thousandths < 0 { negative } else { positive };
+        let thousandths = thousandths.abs();
+
+        write!(
+            f,
+            "{degrees} {minutes} {seconds}.{fraction:03} {hemisphere}",
+            degrees = thousandths / THOUSANDTHS_PER_DEGREE,
+            minutes = thousandths / 60_000 % 60,
+            seconds = thousandths / 1000 % 60,
+            fraction = thousandths % 1000,
+        )
+    }
+}
+
+struct Meters(i64);
+
+impl fmt::Display for Meters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let centimeters = self.0.abs();
+        match centimeters % 100 {
+            0 => write!(f, "{sign}{}m", centimeters / 100),
+            fraction => write!(f, "{sign}{}.{fraction:02}m", centimeters / 100),
+        }
+    }
+}
+
+/// [RFC 1876](https://tools.ietf.org/html/rfc1876#section-3)
+///
+/// ```text
+/// 3. Master File Format
+///
+///    The LOC record is expressed in a master file in the following format:
+///
+///    <owner> <TTL> <class> LOC ( d1 [m1 [s1]] {"N"|"S"} d2 [m2 [s2]]
+///                                {"E"|"W"} alt["m"] [siz["m"] [hp["m"]
+///                                [vp["m"]]]] )
+///
+///    (The parentheses are used for multi-line data as specified in [RFC
+///    1035] section 5.1.)
+///
+///    where:
+///
+///        d1:     [0 .. 90]            (degrees latitude)
+///        d2:     [0 .. 180]           (degrees longitude)
+///        m1, m2: [0 .. 59]            (minutes latitude/longitude)
+///        s1, s2: [0 .. 59.999]        (seconds latitude/longitude)
+///        alt:    [-100000.00 .. 42849672.95] BY .01 (altitude in meters)
+///        siz, hp, vp: [0 .. 90000000.00] (size/precision in meters)
+/// ```
+impl fmt::Display for LOC {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{latitude} {longitude} {altitude} {size} {horiz_pre} {vert_pre}",
+            latitude = Angle(self.latitude, ['N', 'S']),
+            longitude = Angle(self.longitude, ['E', 'W']),
+            altitude = Meters(self.altitude_centimeters()),
+            size = Meters(decode_precision(self.size) as i64),
+            horiz_pre = Meters(decode_precision(self.horiz_pre) as i64),
+            vert_pre = Meters(decode_precision(self.vert_pre) as i64),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::float_cmp)]
+
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn emit(rdata: &LOC) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        rdata.emit(&mut encoder).expect("failed to emit LOC");
+        bytes
+    }
+
+    fn read(bytes: &[u8]) -> ProtoResult<LOC> {
+        let mut decoder = BinDecoder::new(bytes);
+        LOC::read_data(&mut decoder, Restrict::new(bytes.len() as u16))
+    }
+
+    /// `cambridge-net.kei.com. LOC 42 21 54 N 71 06 18 W -24m 30m` from RFC 1876 section 4
+    const CAMBRIDGE_NET: &[u8] = &[
+        0, 0x33, 0x16, 0x13, 137, 23, 45, 208, 112, 190, 21, 240, 0, 152, 141, 32,
+    ];
+
+    #[test]
+    fn test_encode_decode() {
+        let rdata = read(CAMBRIDGE_NET).expect("failed to read LOC");
+        assert_eq!(emit(&rdata), CAMBRIDGE_NET);
+
+        assert!((rdata.latitude() - (42.0 + 21.0 / 60.0 + 54.0 / 3600.0)).abs() < 1e-9);
+        assert!((rdata.longitude() + (71.0 + 6.0 / 60.0 + 18.0 / 3600.0)).abs() < 1e-9);
+        assert_eq!(rdata.altitude(), -24.0);
+        assert_eq!(rdata.size(), 30.0);
+        assert_eq!(rdata.horizontal_precision(), 10_000.0);
+        assert_eq!(rdata.vertical_precision(), 10.0);
+        assert_eq!(
+            rdata.to_string(),
+            "42 21 54.000 N 71 6 18.000 W -24m 30m 10000m 10m"
+        );
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        // unknown version
+        let mut bytes = CAMBRIDGE_NET.to_vec();
+        bytes[0] = 1;
+        assert!(read(&bytes).is_err());
+
+        // a mantissa above nine
+        let mut bytes = CAMBRIDGE_NET.to_vec();
+        bytes[1] = 0xA2;
+        assert!(read(&bytes).is_err());
+
+        // a latitude beyond the north pole
+        let mut bytes = CAMBRIDGE_NET.to_vec();
+        bytes[4..8].copy_from_slice(&(EQUATOR as u32 + 324_000_001).to_be_bytes());
+        assert!(read(&bytes).is_err());
+
+        assert!(read(&CAMBRIDGE_NET[..15]).is_err());
+    }
+
+    #[test]
+    fn test_from_degrees() {
+        let rdata = LOC::from_degrees(
+            -(32.0 + 7.0 / 60.0 + 19.0 / 3600.0),
+            116.0 + 2.0 / 60.0 + 25.0 / 3600.0,
+            10.0,
+            1.0,
+            10_000.0,
+            10.0,
+        )
+        .unwrap();
+        assert_eq!(
+            rdata.to_string(),
+            "32 7 19.000 S 116 2 25.000 E 10m 1m 10000m 10m"
+        );
+
+        // precisions are rounded to the nearest representable value
+        let rdata = LOC::from_degrees(0.0, 0.0, 0.0, 0.0, 1234.0, 0.014).unwrap();
+        assert_eq!(rdata.size(), 0.0);
+        assert_eq!(rdata.horizontal_precision(), 1000.0);
+        assert_eq!(rdata.vertical_precision(), 0.01);
+
+        assert!(LOC::from_degrees(90.5, 0.0, 0.0, 1.0, 1.0, 1.0).is_err());
+        assert!(LOC::from_degrees(0.0, -180.5, 0.0, 1.0, 1.0, 1.0).is_err());
+        assert!(LOC::from_degrees(0.0, 0.0, -100_000.01, 1.0, 1.0, 1.0).is_err());
+        assert!(LOC::from_degrees(0.0, 0.0, 0.0, -1.0, 1.0, 1.0).is_err());
+        assert!(LOC::from_degrees(0.0, 0.0, 0.0, 1.0, f64::NAN, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_distance_to() {
+        let cambridge = read(CAMBRIDGE_NET).unwrap();
+        assert_eq!(cambridge.distance_to(&cambridge), 0.0);
+
+        // pipex.net. LOC 52 14 05 N 00 08 50 E 10m
+        let pipex = LOC::from_degrees(
+            52.0 + 14.0 / 60.0 + 5.0 / 3600.0,
+            8.0 / 60.0 + 50.0 / 3600.0,
+            10.0,
+            1.0,
+            10_000.0,
+            10.0,
+        )
+        .unwrap();
+        let distance = cambridge.distance_to(&pipex);
+        assert!((5_255_000.0..5_265_000.0).contains(&distance), "{distance}");
+        assert_eq!(distance, pipex.distance_to(&cambridge));
+
+        // antipodal points are half the circumference apart
+        let north = LOC::from_degrees(90.0, 0.0, 0.0, 1.0, 1.0, 1.0).unwrap();
+        let south = LOC::from_degrees(-90.0, 0.0, 0.0, 1.0, 1.0, 1.0).unwrap();
+        assert!((north.distance_to(&south) - std::f64::consts::PI * EARTH_RADIUS).abs() < 1e-6);
+    }
+
+    proptest! {
+        #[test]
+        fn test_precision_round_trip(mantissa in 1_u8..=9, exponent in 0_u8..=9) {
+            let value = mantissa << 4 | exponent;
+            let meters = decode_precision(value) as f64 / 100.0;
+            prop_assert_eq!(encode_precision(meters).unwrap(), value);
+        }
+
+        #[test]
+        fn test_precision_nearest(meters in 0.0..90_000_000.0) {
+            let encoded = encode_precision(meters).unwrap();
+            let centimeters = meters * 100.0;
+            let distance = (decode_precision(encoded) as f64 - centimeters).abs();
+            for mantissa in 0..=9_u8 {
+                for exponent in 0..=9_u8 {
+                    let other = decode_precision(mantissa << 4 | exponent) as f64;
+                    prop_assert!(distance <= (other - centimeters).abs());
+                }
+            }
+        }
+
+        #[test]
+        fn test_from_degrees_round_trip(
+            latitude in -90.0..=90.0,
+            longitude in -180.0..=180.0,
+            altitude in -100_000.0..42_849_672.0,
+            size in 0.0..90_000_000.0,
+            horizontal_precision in 0.0..90_000_000.0,
+            vertical_precision in 0.0..90_000_000.0,
+        ) {
+            let rdata = LOC::from_degrees(
+                latitude,
+                longitude,
+                altitude,
+                size,
+                horizontal_precision,
+                vertical_precision,
+            )
+            .unwrap();
+
+            let again = LOC::from_degrees(
+                rdata.latitude(),
+                rdata.longitude(),
+                rdata.altitude(),
+                rdata.size(),
+                rdata.horizontal_precision(),
+                rdata.vertical_precision(),
+            )
+            .unwrap();
+            prop_assert_eq!(&again, &rdata);
+
+            let bytes = emit(&rdata);
+            prop_assert_eq!(read(&bytes).unwrap(), rdata);
+        }
+    }
+}
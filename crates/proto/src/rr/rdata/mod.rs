@@ -12,10 +12,15 @@
 // each of these module's has the parser for that rdata embedded, to keep the file sizes down...
 pub mod a;
 pub mod aaaa;
+pub mod apl;
 pub mod caa;
 pub mod csync;
+#[cfg(feature = "unstable-deleg")]
+#[cfg_attr(docsrs, doc(cfg(feature = "unstable-deleg")))]
+pub mod deleg;
 pub mod hinfo;
 pub mod https;
+pub mod loc;
 pub mod mx;
 pub mod name;
 pub mod naptr;
@@ -31,17 +36,21 @@ pub mod txt;
 
 pub use self::a::A;
 pub use self::aaaa::AAAA;
+pub use self::apl::APL;
 pub use self::caa::CAA;
 pub use self::csync::CSYNC;
+#[cfg(feature = "unstable-deleg")]
+pub use self::deleg::DELEG;
 pub use self::hinfo::HINFO;
 pub use self::https::HTTPS;
+pub use self::loc::LOC;
 pub use self::mx::MX;
 pub use self::name::{ANAME, CNAME, NS, PTR};
 pub use self::naptr::NAPTR;
 pub use self::null::NULL;
 pub use self::openpgpkey::OPENPGPKEY;
 pub use self::opt::OPT;
-pub use self::soa::SOA;
+pub use self::soa::{Serial, SOA};
 pub use self::srv::SRV;
 pub use self::sshfp::SSHFP;
 pub use self::svcb::SVCB;
@@ -7,14 +7,14 @@
 
 //! Dynamic Delegation Discovery System
 
-use std::fmt;
+use std::fmt::{self, Write};
 
 #[cfg(feature = "serde-config")]
 use serde::{Deserialize, Serialize};
 
 use crate::{
     error::{ProtoError, ProtoResult},
-    rr::{domain::Name, RData, RecordData, RecordType},
+    rr::{domain::Name, presentation::write_character_string, RData, RecordData, RecordType},
     serialize::binary::*,
 };
 
@@ -283,14 +283,16 @@ impl fmt::Display for NAPTR {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(
             f,
-            "{order} {pref} \"{flags}\" \"{service}\" \"{regexp}\" {replace}",
+            "{order} {pref} ",
             order = self.order,
-            pref = self.preference,
-            flags = &String::from_utf8_lossy(&self.flags),
-            service = &String::from_utf8_lossy(&self.services),
-            regexp = &String::from_utf8_lossy(&self.regexp),
-            replace = self.replacement
-        )
+            pref = self.preference
+        )?;
+        write_character_string(f, &self.flags)?;
+        f.write_char(' ')?;
+        write_character_string(f, &self.services)?;
+        f.write_char(' ')?;
+        write_character_string(f, &self.regexp)?;
+        write!(f, " {replace}", replace = self.replacement)
     }
 }
 
@@ -9,6 +9,7 @@
 #![allow(clippy::use_self)]
 
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::str::FromStr;
 
@@ -19,7 +20,7 @@ use tracing::warn;
 
 use crate::{
     error::{ProtoError, ProtoErrorKind, ProtoResult},
-    rr::{RData, RecordData, RecordDataDecodable, RecordType},
+    rr::{Name, RData, RecordData, RecordDataDecodable, RecordType},
     serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder, Restrict},
 };
 
@@ -224,6 +225,13 @@ impl PartialEq for OPT {
 
 impl Eq for OPT {}
 
+impl Hash for OPT {
+    /// Equality ignores the order of the options, so only the number of options is hashed
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.options.len().hash(state);
+    }
+}
+
 impl AsMut<Vec<(EdnsCode, EdnsOption)>> for OPT {
     fn as_mut(&mut self) -> &mut Vec<(EdnsCode, EdnsOption)> {
         &mut self.options
@@ -245,6 +253,14 @@ impl BinEncodable for OPT {
         }
         Ok(())
     }
+
+    fn encoded_size(&self) -> usize {
+        // each option is prefixed with its code and length
+        self.as_ref()
+            .iter()
+            .map(|(_, edns_option)| 4 + usize::from(edns_option.len()))
+            .sum()
+    }
 }
 
 impl<'r> RecordDataDecodable<'r> for OPT {
@@ -407,6 +423,18 @@ pub enum EdnsCode {
     /// [RFC 7901, CHAIN Query Requests in DNS, Optional](https://tools.ietf.org/html/rfc7901)
     Chain,
 
+    /// [RFC 8145, edns-key-tag](https://tools.ietf.org/html/rfc8145)
+    KeyTag,
+
+    /// [RFC 8914, Extended DNS Errors](https://tools.ietf.org/html/rfc8914)
+    EDE,
+
+    /// [draft-bellis-dnsop-edns-tags, EDNS Client Tag](https://datatracker.ietf.org/doc/draft-bellis-dnsop-edns-tags/)
+    ClientTag,
+
+    /// [draft-bellis-dnsop-edns-tags, EDNS Server Tag](https://datatracker.ietf.org/doc/draft-bellis-dnsop-edns-tags/)
+    ServerTag,
+
     /// Unknown, used to deal with unknown or unsupported codes
     Unknown(u16),
 }
@@ -429,6 +457,10 @@ impl From<u16> for EdnsCode {
             11 => Self::Keepalive,
             12 => Self::Padding,
             13 => Self::Chain,
+            14 => Self::KeyTag,
+            15 => Self::EDE,
+            16 => Self::ClientTag,
+            17 => Self::ServerTag,
             _ => Self::Unknown(value),
         }
     }
@@ -451,6 +483,10 @@ impl From<EdnsCode> for u16 {
             EdnsCode::Keepalive => 11,
             EdnsCode::Padding => 12,
             EdnsCode::Chain => 13,
+            EdnsCode::KeyTag => 14,
+            EdnsCode::EDE => 15,
+            EdnsCode::ClientTag => 16,
+            EdnsCode::ServerTag => 17,
             EdnsCode::Unknown(value) => value,
         }
     }
@@ -458,13 +494,28 @@ impl From<EdnsCode> for u16 {
 
 /// options used to pass information about capabilities between client and server
 ///
-/// `note: Not all EdnsOptions are supported at this time.`
+/// The options of all the registered codes are decoded, an option which is malformed is kept as
+///  [`EdnsOption::Unknown`] rather than failing the whole message, except for the Client Subnet
+///  and EXPIRE options.
 ///
 /// <https://www.iana.org/assignments/dns-parameters/dns-parameters.xhtml#dns-parameters-13>
 #[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
 #[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Hash)]
 #[non_exhaustive]
 pub enum EdnsOption {
+    /// [RFC 8764, Apple's Long-Lived Queries, Optional](https://tools.ietf.org/html/rfc8764)
+    LLQ(LlqOption),
+
+    /// [UL On-hold](https://files.dns-sd.org/draft-sekar-dns-ul.txt)
+    ///
+    /// The lease of the update and the optional lease of the KEY records, in seconds
+    UL(u32, Option<u32>),
+
+    /// [RFC 5001, NSID](https://tools.ietf.org/html/rfc5001)
+    ///
+    /// Empty in queries, responses carry the identifier of the name server
+    NSID(Vec<u8>),
+
     /// [RFC 6975, DNSSEC Algorithm Understood](https://tools.ietf.org/html/rfc6975)
     #[cfg(feature = "dnssec")]
     #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
@@ -483,6 +534,45 @@ pub enum EdnsOption {
     /// [RFC 7871, Client Subnet, Optional](https://tools.ietf.org/html/rfc7871)
     Subnet(ClientSubnet),
 
+    /// [RFC 7314, EDNS EXPIRE Option](https://tools.ietf.org/html/rfc7314)
+    ///
+    /// Empty in queries, responses carry the remaining time until the zone expires in seconds
+    Expire(Option<u32>),
+
+    /// [RFC 7873, DNS Cookies](https://tools.ietf.org/html/rfc7873)
+    ///
+    /// The client cookie of 8 bytes, followed by the server cookie of 8 to 32 bytes if known
+    Cookie(Vec<u8>),
+
+    /// [RFC 7828, edns-tcp-keepalive](https://tools.ietf.org/html/rfc7828)
+    ///
+    /// Empty in queries, responses carry the idle timeout in units of 100 milliseconds
+    Keepalive(Option<u16>),
+
+    /// [RFC 7830, The EDNS(0) Padding](https://tools.ietf.org/html/rfc7830)
+    ///
+    /// The number of padding bytes, which are emitted as zeros
+    Padding(u16),
+
+    /// [RFC 7901, CHAIN Query Requests in DNS, Optional](https://tools.ietf.org/html/rfc7901)
+    ///
+    /// The closest trust point known to the client
+    Chain(Name),
+
+    /// [RFC 8145, edns-key-tag](https://tools.ietf.org/html/rfc8145)
+    ///
+    /// The key tags of the trust anchors of the client
+    KeyTag(Vec<u16>),
+
+    /// [RFC 8914, Extended DNS Errors](https://tools.ietf.org/html/rfc8914)
+    EDE(ExtendedError),
+
+    /// [draft-bellis-dnsop-edns-tags, EDNS Client Tag](https://datatracker.ietf.org/doc/draft-bellis-dnsop-edns-tags/)
+    ClientTag(u16),
+
+    /// [draft-bellis-dnsop-edns-tags, EDNS Server Tag](https://datatracker.ietf.org/doc/draft-bellis-dnsop-edns-tags/)
+    ServerTag(u16),
+
     /// Unknown, used to deal with unknown or unsupported codes
     Unknown(u16, Vec<u8>),
 }
@@ -495,7 +585,17 @@ impl EdnsOption {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.len(),
+            EdnsOption::LLQ(ref llq) => llq.len(),
             EdnsOption::Subnet(ref subnet) => subnet.len(),
+            EdnsOption::Expire(expire) => expire.map_or(0, |_| 4),
+            EdnsOption::UL(_, key_lease) => key_lease.map_or(4, |_| 8),
+            EdnsOption::NSID(ref data) | EdnsOption::Cookie(ref data) => data.len() as u16,
+            EdnsOption::Keepalive(timeout) => timeout.map_or(0, |_| 2),
+            EdnsOption::Padding(len) => len,
+            EdnsOption::Chain(ref name) => name.iter().map(|l| l.len() as u16 + 1).sum::<u16>() + 1,
+            EdnsOption::KeyTag(ref tags) => tags.len() as u16 * 2,
+            EdnsOption::EDE(ref ede) => ede.len(),
+            EdnsOption::ClientTag(_) | EdnsOption::ServerTag(_) => 2,
             EdnsOption::Unknown(_, ref data) => data.len() as u16, // TODO: should we verify?
         }
     }
@@ -507,8 +607,11 @@ impl EdnsOption {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.is_empty(),
+            EdnsOption::LLQ(ref llq) => llq.is_empty(),
             EdnsOption::Subnet(ref subnet) => subnet.is_empty(),
+            EdnsOption::Expire(expire) => expire.is_none(),
             EdnsOption::Unknown(_, ref data) => data.is_empty(),
+            _ => self.len() == 0,
         }
     }
 }
@@ -520,7 +623,23 @@ impl BinEncodable for EdnsOption {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.emit(encoder),
+            EdnsOption::LLQ(ref llq) => llq.emit(encoder),
             EdnsOption::Subnet(ref subnet) => subnet.emit(encoder),
+            EdnsOption::Expire(Some(expire)) => encoder.emit_u32(expire),
+            EdnsOption::Expire(None) => Ok(()),
+            EdnsOption::UL(lease, key_lease) => {
+                encoder.emit_u32(lease)?;
+                key_lease.map_or(Ok(()), |key_lease| encoder.emit_u32(key_lease))
+            }
+            EdnsOption::NSID(ref data) | EdnsOption::Cookie(ref data) => encoder.emit_vec(data),
+            EdnsOption::Keepalive(Some(timeout)) => encoder.emit_u16(timeout),
+            EdnsOption::Keepalive(None) => Ok(()),
+            EdnsOption::Padding(len) => encoder.emit_vec(&vec![0; usize::from(len)]),
+            // names in options are never compressed
+            EdnsOption::Chain(ref name) => name.emit_as_canonical(encoder, true),
+            EdnsOption::KeyTag(ref tags) => tags.iter().try_for_each(|tag| encoder.emit_u16(*tag)),
+            EdnsOption::EDE(ref ede) => ede.emit(encoder),
+            EdnsOption::ClientTag(tag) | EdnsOption::ServerTag(tag) => encoder.emit_u16(tag),
             EdnsOption::Unknown(_, ref data) => encoder.emit_vec(data), // gah, clone needed or make a crazy api.
         }
     }
@@ -539,12 +658,84 @@ impl<'a> TryFrom<(EdnsCode, &'a [u8])> for EdnsOption {
             EdnsCode::DHU => Self::DHU(value.1.into()),
             #[cfg(feature = "dnssec")]
             EdnsCode::N3U => Self::N3U(value.1.into()),
+            // LLQ is not implemented, so a malformed option is kept as is rather than failing the
+            // whole message
+            EdnsCode::LLQ => value
+                .1
+                .try_into()
+                .map(Self::LLQ)
+                .unwrap_or_else(|_| Self::Unknown(value.0.into(), value.1.to_vec())),
             EdnsCode::Subnet => Self::Subnet(value.1.try_into()?),
+            EdnsCode::Expire => match *value.1 {
+                [] => Self::Expire(None),
+                [a, b, c, d] => Self::Expire(Some(u32::from_be_bytes([a, b, c, d]))),
+                _ => {
+                    return Err(ProtoErrorKind::Msg(format!(
+                        "invalid EXPIRE option length: {}",
+                        value.1.len()
+                    ))
+                    .into())
+                }
+            },
+            EdnsCode::UL => or_unknown(value, |data| match *data {
+                [a, b, c, d] => Some(Self::UL(u32::from_be_bytes([a, b, c, d]), None)),
+                [a, b, c, d, e, f, g, h] => Some(Self::UL(
+                    u32::from_be_bytes([a, b, c, d]),
+                    Some(u32::from_be_bytes([e, f, g, h])),
+                )),
+                _ => None,
+            }),
+            EdnsCode::NSID => Self::NSID(value.1.to_vec()),
+            EdnsCode::Cookie => or_unknown(value, |data| {
+                (data.len() == 8 || (16..=40).contains(&data.len()))
+                    .then(|| Self::Cookie(data.to_vec()))
+            }),
+            EdnsCode::Keepalive => or_unknown(value, |data| match *data {
+                [] => Some(Self::Keepalive(None)),
+                [a, b] => Some(Self::Keepalive(Some(u16::from_be_bytes([a, b])))),
+                _ => None,
+            }),
+            // the content of the padding is ignored, RFC 7830 section 3
+            EdnsCode::Padding => Self::Padding(value.1.len() as u16),
+            EdnsCode::Chain => or_unknown(value, |data| {
+                let mut decoder = BinDecoder::new(data);
+                let name = Name::read(&mut decoder).ok()?;
+                decoder.is_empty().then_some(Self::Chain(name))
+            }),
+            EdnsCode::KeyTag => or_unknown(value, |data| {
+                (data.len() % 2 == 0).then(|| {
+                    Self::KeyTag(
+                        data.chunks_exact(2)
+                            .map(|tag| u16::from_be_bytes([tag[0], tag[1]]))
+                            .collect(),
+                    )
+                })
+            }),
+            EdnsCode::EDE => or_unknown(value, |data| data.try_into().ok().map(Self::EDE)),
+            EdnsCode::ClientTag => or_unknown(value, |data| match *data {
+                [a, b] => Some(Self::ClientTag(u16::from_be_bytes([a, b]))),
+                _ => None,
+            }),
+            EdnsCode::ServerTag => or_unknown(value, |data| match *data {
+                [a, b] => Some(Self::ServerTag(u16::from_be_bytes([a, b]))),
+                _ => None,
+            }),
             _ => Self::Unknown(value.0.into(), value.1.to_vec()),
         })
     }
 }
 
+/// Parses the data of an option with `parse`, keeping it as [`EdnsOption::Unknown`] if malformed
+fn or_unknown(
+    (code, data): (EdnsCode, &[u8]),
+    parse: impl FnOnce(&[u8]) -> Option<EdnsOption>,
+) -> EdnsOption {
+    parse(data).unwrap_or_else(|| {
+        warn!("malformed EDNS option {:?}, keeping it unparsed", code);
+        EdnsOption::Unknown(code.into(), data.to_vec())
+    })
+}
+
 impl<'a> TryFrom<&'a EdnsOption> for Vec<u8> {
     type Error = ProtoError;
 
@@ -554,8 +745,18 @@ impl<'a> TryFrom<&'a EdnsOption> for Vec<u8> {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.into(),
+            EdnsOption::LLQ(ref llq) => llq.into(),
             EdnsOption::Subnet(ref subnet) => subnet.try_into()?,
+            EdnsOption::Expire(expire) => {
+                expire.map_or_else(Vec::new, |e| e.to_be_bytes().to_vec())
+            }
             EdnsOption::Unknown(_, ref data) => data.clone(), // gah, clone needed or make a crazy api.
+            _ => {
+                let mut bytes = Self::with_capacity(value.len() as usize);
+                let mut encoder = BinEncoder::new(&mut bytes);
+                value.emit(&mut encoder)?;
+                bytes
+            }
         })
     }
 }
@@ -569,12 +770,242 @@ impl<'a> From<&'a EdnsOption> for EdnsCode {
             EdnsOption::DHU(..) => Self::DHU,
             #[cfg(feature = "dnssec")]
             EdnsOption::N3U(..) => Self::N3U,
+            EdnsOption::LLQ(..) => Self::LLQ,
             EdnsOption::Subnet(..) => Self::Subnet,
+            EdnsOption::Expire(..) => Self::Expire,
+            EdnsOption::UL(..) => Self::UL,
+            EdnsOption::NSID(..) => Self::NSID,
+            EdnsOption::Cookie(..) => Self::Cookie,
+            EdnsOption::Keepalive(..) => Self::Keepalive,
+            EdnsOption::Padding(..) => Self::Padding,
+            EdnsOption::Chain(..) => Self::Chain,
+            EdnsOption::KeyTag(..) => Self::KeyTag,
+            EdnsOption::EDE(..) => Self::EDE,
+            EdnsOption::ClientTag(..) => Self::ClientTag,
+            EdnsOption::ServerTag(..) => Self::ServerTag,
             EdnsOption::Unknown(code, _) => code.into(),
         }
     }
 }
 
+/// [RFC 8764, Apple's Long-Lived Queries, Optional](https://tools.ietf.org/html/rfc8764#section-3.2)
+///
+/// ```text
+/// Field Name        Field Type     Description
+/// ---------------------------------------------------------------------
+/// OPTION-CODE       u_int16_t      LLQ (1)
+/// OPTION-LENGTH     u_int16_t      Length of following fields (18)
+/// LLQ-VERSION       u_int16_t      Version of LLQ protocol implemented
+/// LLQ-OPCODE        u_int16_t      Identifies LLQ operation
+/// LLQ-ERROR         u_int16_t      Identifies LLQ errors
+/// LLQ-ID            u_int64_t      Identifier for an LLQ
+/// LLQ-LEASE         u_int32_t      Requested or granted life of LLQ, in seconds
+/// ```
+///
+/// Only the encoding of the option is supported, LLQ itself is not implemented.
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct LlqOption {
+    version: u16,
+    opcode: u16,
+    error: u16,
+    id: u64,
+    lease_life: u32,
+}
+
+impl LlqOption {
+    /// Construct a new LlqOption
+    pub fn new(version: u16, opcode: u16, error: u16, id: u64, lease_life: u32) -> Self {
+        Self {
+            version,
+            opcode,
+            error,
+            id,
+            lease_life,
+        }
+    }
+
+    /// Returns the length in bytes of the EdnsOption
+    #[inline]
+    pub fn len(&self) -> u16 {
+        // LLQ-VERSION, LLQ-OPCODE, LLQ-ERROR: 2 octets each
+        // LLQ-ID: 8 octets
+        // LLQ-LEASE: 4 octets
+        2 + 2 + 2 + 8 + 4
+    }
+
+    /// Returns `true` if the length in bytes of the LlqOption is 0
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// returns the version of the LLQ protocol
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// returns the LLQ operation
+    pub fn opcode(&self) -> u16 {
+        self.opcode
+    }
+
+    /// returns the LLQ error
+    pub fn error(&self) -> u16 {
+        self.error
+    }
+
+    /// returns the identifier of the LLQ
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// returns the requested or granted life of the LLQ, in seconds
+    pub fn lease_life(&self) -> u32 {
+        self.lease_life
+    }
+}
+
+impl BinEncodable for LlqOption {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        encoder.emit_u16(self.version)?;
+        encoder.emit_u16(self.opcode)?;
+        encoder.emit_u16(self.error)?;
+        encoder.emit_vec(&self.id.to_be_bytes())?;
+        encoder.emit_u32(self.lease_life)
+    }
+}
+
+impl<'a> BinDecodable<'a> for LlqOption {
+    fn read(decoder: &mut BinDecoder<'a>) -> ProtoResult<Self> {
+        let version = decoder.read_u16()?.unverified();
+        let opcode = decoder.read_u16()?.unverified();
+        let error = decoder.read_u16()?.unverified();
+        let mut id = [0u8; 8];
+        id.copy_from_slice(decoder.read_slice(8)?.unverified());
+        let lease_life = decoder.read_u32()?.unverified();
+
+        Ok(Self {
+            version,
+            opcode,
+            error,
+            id: u64::from_be_bytes(id),
+            lease_life,
+        })
+    }
+}
+
+impl<'a> From<&'a LlqOption> for Vec<u8> {
+    fn from(value: &'a LlqOption) -> Self {
+        let mut bytes = Self::with_capacity(value.len() as usize);
+        bytes.extend_from_slice(&value.version.to_be_bytes());
+        bytes.extend_from_slice(&value.opcode.to_be_bytes());
+        bytes.extend_from_slice(&value.error.to_be_bytes());
+        bytes.extend_from_slice(&value.id.to_be_bytes());
+        bytes.extend_from_slice(&value.lease_life.to_be_bytes());
+        bytes
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for LlqOption {
+    type Error = ProtoError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        if value.len() != 18 {
+            return Err(ProtoErrorKind::Message("Invalid LLQ option length").into());
+        }
+
+        let mut decoder = BinDecoder::new(value);
+        Self::read(&mut decoder)
+    }
+}
+
+/// [RFC 8914, Extended DNS Errors](https://tools.ietf.org/html/rfc8914#section-2)
+///
+/// ```text
+///                                              1   1   1   1   1   1
+///      0   1   2   3   4   5   6   7   8   9   0   1   2   3   4   5
+///    +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+/// 0: |                            OPTION-CODE                        |
+///    +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+/// 2: |                           OPTION-LENGTH                       |
+///    +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+/// 4: | INFO-CODE                                                     |
+///    +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+/// 6: / EXTRA-TEXT ...                                                /
+///    +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+/// ```
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Hash)]
+pub struct ExtendedError {
+    info_code: u16,
+    extra_text: String,
+}
+
+impl ExtendedError {
+    /// Construct a new ExtendedError
+    pub fn new(info_code: u16, extra_text: impl Into<String>) -> Self {
+        Self {
+            info_code,
+            extra_text: extra_text.into(),
+        }
+    }
+
+    /// Returns the length in bytes of the EdnsOption
+    #[inline]
+    pub fn len(&self) -> u16 {
+        2 + self.extra_text.len() as u16
+    }
+
+    /// Returns `true` if the length in bytes of the ExtendedError is 0
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// returns the code of the error, see the
+    ///  [registry](https://www.iana.org/assignments/dns-parameters/dns-parameters.xhtml#extended-dns-error-codes)
+    pub fn info_code(&self) -> u16 {
+        self.info_code
+    }
+
+    /// returns the text describing the error for humans, may be empty
+    pub fn extra_text(&self) -> &str {
+        &self.extra_text
+    }
+}
+
+impl BinEncodable for ExtendedError {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        encoder.emit_u16(self.info_code)?;
+        encoder.emit_vec(self.extra_text.as_bytes())
+    }
+}
+
+impl<'a> BinDecodable<'a> for ExtendedError {
+    fn read(decoder: &mut BinDecoder<'a>) -> ProtoResult<Self> {
+        let info_code = decoder.read_u16()?.unverified();
+        let extra_text = decoder
+            .read_slice(decoder.len())?
+            .unverified(/*checked as UTF-8 below*/);
+        let extra_text = std::str::from_utf8(extra_text)
+            .map_err(|_| ProtoError::from("EDE extra text is not UTF-8"))?
+            // some implementations terminate the text with a NUL, RFC 8914 section 2
+            .trim_end_matches('\0');
+
+        Ok(Self::new(info_code, extra_text))
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ExtendedError {
+    type Error = ProtoError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let mut decoder = BinDecoder::new(value);
+        Self::read(&mut decoder)
+    }
+}
+
 /// [RFC 7871, Client Subnet, Optional](https://tools.ietf.org/html/rfc7871)
 ///
 /// ```text
@@ -667,6 +1098,32 @@ impl ClientSubnet {
         self.scope_prefix = scope_prefix;
     }
 
+    /// Returns the address with the bits beyond the source prefix set to 0
+    pub fn mask_address(&self) -> IpAddr {
+        let max_prefix = match self.address {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+
+        ipnet::IpNet::new(self.address, self.source_prefix.min(max_prefix))
+            .map(|net| net.network())
+            .unwrap_or(self.address)
+    }
+
+    /// Returns the option with the source prefix truncated to `scope_prefix_len`
+    ///
+    /// A resolver forwarding the client subnet should not reveal more of the address than the
+    ///  scope the authoritative server answered for, see
+    ///  [RFC 7871 section 11.1](https://tools.ietf.org/html/rfc7871#section-11.1).
+    pub fn truncate_to_scope(&self, scope_prefix_len: u8) -> Self {
+        let mut truncated = Self {
+            source_prefix: self.source_prefix.min(scope_prefix_len),
+            ..*self
+        };
+        truncated.address = truncated.mask_address();
+        truncated
+    }
+
     fn addr_len(&self) -> u16 {
         let source_prefix = self.source_prefix as u16;
         source_prefix / 8 + if source_prefix % 8 > 0 { 1 } else { 0 }
@@ -853,9 +1310,9 @@ mod tests {
             ),
             (
                 EdnsCode::Cookie,
-                EdnsOption::Unknown(10, vec![0x0b, 0x64, 0xb4, 0xdc, 0xd7, 0xb0, 0xcc, 0x8f]),
+                EdnsOption::Cookie(vec![0x0b, 0x64, 0xb4, 0xdc, 0xd7, 0xb0, 0xcc, 0x8f]),
             ),
-            (EdnsCode::Keepalive, EdnsOption::Unknown(11, vec![])),
+            (EdnsCode::Keepalive, EdnsOption::Keepalive(None)),
         ];
         let options = OPT::new(options);
         assert_eq!(opt, options);
@@ -878,19 +1335,10 @@ mod tests {
 
         let opt = read_rdata.unwrap();
         let options = vec![
+            (EdnsCode::EDE, EdnsOption::EDE(ExtendedError::new(6, ""))),
             (
-                EdnsCode::Unknown(15u16),
-                EdnsOption::Unknown(15u16, vec![0x00, 0x06]),
-            ),
-            (
-                EdnsCode::Unknown(15u16),
-                EdnsOption::Unknown(
-                    15u16,
-                    vec![
-                        0x00, 0x09, 0x55, 0x6E, 0x6B, 0x6E, 0x6F, 0x77, 0x6E, 0x20, 0x65, 0x72,
-                        0x72, 0x6F, 0x72,
-                    ],
-                ),
+                EdnsCode::EDE,
+                EdnsOption::EDE(ExtendedError::new(9, "Unknown error")),
             ),
         ];
         let options = OPT::new(options);
@@ -906,6 +1354,190 @@ mod tests {
         assert_eq!(bytes, expected_bytes);
     }
 
+    #[test]
+    fn test_llq() {
+        let llq = LlqOption::new(1, 1, 0, 0x0102_0304_0506_0708, 3600);
+        let expected_bytes: Vec<u8> = vec![
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08,
+            0x00, 0x00, 0x0e, 0x10,
+        ];
+        assert_eq!(Vec::<u8>::from(&llq), expected_bytes);
+        assert_eq!(LlqOption::try_from(expected_bytes.as_slice()).unwrap(), llq);
+        assert!(LlqOption::try_from(&expected_bytes[..17]).is_err());
+
+        let mut rdata = OPT::default();
+        rdata.insert(EdnsOption::LLQ(llq));
+
+        let mut bytes = Vec::new();
+        let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut bytes);
+        rdata.emit(&mut encoder).unwrap();
+        let bytes = encoder.into_bytes();
+        assert_eq!(&bytes[..4], &[0x00, 0x01, 0x00, 0x12]);
+
+        let mut decoder: BinDecoder<'_> = BinDecoder::new(bytes);
+        let restrict = Restrict::new(bytes.len() as u16);
+        let read_rdata = OPT::read_data(&mut decoder, restrict).expect("Decoding error");
+        assert_eq!(read_rdata.get(EdnsCode::LLQ), Some(&EdnsOption::LLQ(llq)));
+
+        let malformed = EdnsOption::try_from((EdnsCode::LLQ, &expected_bytes[..17])).unwrap();
+        assert_eq!(
+            malformed,
+            EdnsOption::Unknown(1, expected_bytes[..17].to_vec())
+        );
+    }
+
+    #[test]
+    fn test_expire() {
+        let mut rdata = OPT::default();
+        rdata.insert(EdnsOption::Expire(Some(604_800)));
+
+        let mut bytes = Vec::new();
+        let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut bytes);
+        rdata.emit(&mut encoder).unwrap();
+        let bytes = encoder.into_bytes();
+        assert_eq!(bytes, &[0x00, 0x09, 0x00, 0x04, 0x00, 0x09, 0x3a, 0x80]);
+
+        let mut decoder: BinDecoder<'_> = BinDecoder::new(bytes);
+        let restrict = Restrict::new(bytes.len() as u16);
+        let read_rdata = OPT::read_data(&mut decoder, restrict).expect("Decoding error");
+        assert_eq!(
+            read_rdata.get(EdnsCode::Expire),
+            Some(&EdnsOption::Expire(Some(604_800)))
+        );
+
+        // queries carry an empty option
+        let query = EdnsOption::Expire(None);
+        assert!(query.is_empty());
+        assert_eq!(Vec::<u8>::try_from(&query).unwrap(), Vec::<u8>::new());
+        assert_eq!(
+            EdnsOption::try_from((EdnsCode::Expire, &[][..])).unwrap(),
+            query
+        );
+
+        assert!(EdnsOption::try_from((EdnsCode::Expire, &[0, 0, 1][..])).is_err());
+    }
+
+    #[test]
+    fn test_registered_options() {
+        let options = vec![
+            (EdnsOption::UL(3600, None), vec![0, 0, 0x0e, 0x10]),
+            (
+                EdnsOption::UL(3600, Some(7200)),
+                vec![0, 0, 0x0e, 0x10, 0, 0, 0x1c, 0x20],
+            ),
+            (EdnsOption::NSID(vec![]), vec![]),
+            (EdnsOption::NSID(b"ns1".to_vec()), b"ns1".to_vec()),
+            (EdnsOption::Cookie(vec![1; 24]), vec![1; 24]),
+            (EdnsOption::Keepalive(Some(1200)), vec![0x04, 0xb0]),
+            (EdnsOption::Padding(3), vec![0, 0, 0]),
+            (
+                EdnsOption::Chain(Name::from_ascii("example.").unwrap()),
+                b"\x07example\x00".to_vec(),
+            ),
+            (
+                EdnsOption::KeyTag(vec![20326, 38696]),
+                vec![0x4f, 0x66, 0x97, 0x28],
+            ),
+            (
+                EdnsOption::EDE(ExtendedError::new(18, "denied")),
+                b"\x00\x12denied".to_vec(),
+            ),
+            (EdnsOption::ClientTag(0x1234), vec![0x12, 0x34]),
+            (EdnsOption::ServerTag(0x5678), vec![0x56, 0x78]),
+        ];
+
+        for (option, data) in options {
+            let code = EdnsCode::from(&option);
+            assert_eq!(usize::from(option.len()), data.len(), "{option:?}");
+            assert_eq!(Vec::<u8>::try_from(&option).unwrap(), data, "{option:?}");
+
+            let mut rdata = OPT::default();
+            rdata.insert(option.clone());
+            let mut bytes = Vec::new();
+            let mut encoder = BinEncoder::new(&mut bytes);
+            rdata.emit(&mut encoder).unwrap();
+
+            let mut expected = u16::from(code).to_be_bytes().to_vec();
+            expected.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            expected.extend_from_slice(&data);
+            assert_eq!(bytes, expected, "{option:?}");
+
+            let mut decoder = BinDecoder::new(&bytes);
+            let restrict = Restrict::new(bytes.len() as u16);
+            let read_rdata = OPT::read_data(&mut decoder, restrict).expect("Decoding error");
+            assert_eq!(read_rdata.get(code), Some(&option));
+        }
+    }
+
+    #[test]
+    fn test_malformed_options_are_unknown() {
+        let malformed = [
+            (EdnsCode::UL, &[0, 0, 1][..]),
+            (EdnsCode::Cookie, &[1; 12][..]),
+            (EdnsCode::Keepalive, &[1][..]),
+            (EdnsCode::Chain, &[0xc0, 0x0c][..]),
+            (EdnsCode::Chain, &[0, 0][..]),
+            (EdnsCode::KeyTag, &[1, 2, 3][..]),
+            (EdnsCode::EDE, &[0][..]),
+            (EdnsCode::EDE, &[0, 1, 0xff][..]),
+            (EdnsCode::ClientTag, &[][..]),
+            (EdnsCode::ServerTag, &[1, 2, 3][..]),
+        ];
+
+        for (code, data) in malformed {
+            assert_eq!(
+                EdnsOption::try_from((code, data)).unwrap(),
+                EdnsOption::Unknown(code.into(), data.to_vec())
+            );
+        }
+
+        // padding is decoded whatever its content
+        assert_eq!(
+            EdnsOption::try_from((EdnsCode::Padding, &[1, 2][..])).unwrap(),
+            EdnsOption::Padding(2)
+        );
+        // a NUL terminating the extra text is dropped
+        assert_eq!(
+            EdnsOption::try_from((EdnsCode::EDE, &b"\x00\x03stale\x00"[..])).unwrap(),
+            EdnsOption::EDE(ExtendedError::new(3, "stale"))
+        );
+    }
+
+    #[test]
+    fn test_client_subnet_mask_address() {
+        let ecs = ClientSubnet::new("192.0.2.77".parse().unwrap(), 24, 0);
+        assert_eq!(ecs.mask_address(), "192.0.2.0".parse::<IpAddr>().unwrap());
+
+        let ecs = ClientSubnet::new("2001:db8:1234:5678::1".parse().unwrap(), 48, 0);
+        assert_eq!(
+            ecs.mask_address(),
+            "2001:db8:1234::".parse::<IpAddr>().unwrap()
+        );
+
+        // prefixes beyond the address length keep the whole address
+        let ecs = ClientSubnet::new("192.0.2.77".parse().unwrap(), 40, 0);
+        assert_eq!(ecs.mask_address(), "192.0.2.77".parse::<IpAddr>().unwrap());
+    }
+
+    #[test]
+    fn test_client_subnet_truncate_to_scope() {
+        let ecs = ClientSubnet::new("192.0.2.77".parse().unwrap(), 32, 0);
+        let truncated = ecs.truncate_to_scope(24);
+        assert_eq!(truncated.addr(), "192.0.2.0".parse::<IpAddr>().unwrap());
+        assert_eq!(truncated.source_prefix(), 24);
+        assert_eq!(
+            Vec::<u8>::try_from(&truncated).unwrap(),
+            [0x00, 0x01, 0x18, 0x00, 0xc0, 0x00, 0x02]
+        );
+
+        // a wider scope never extends the source prefix
+        assert_eq!(truncated.truncate_to_scope(32), truncated);
+
+        let ecs = ClientSubnet::new("2001:db8:1234:5678::1".parse().unwrap(), 56, 0);
+        let truncated = ecs.truncate_to_scope(48);
+        assert_eq!(truncated, "2001:db8:1234::/48".parse().unwrap());
+    }
+
     #[test]
     fn test_read_client_subnet() {
         let bytes: Vec<u8> = vec![0x00, 0x01, 0x18, 0x00, 0xac, 0x01, 0x01];
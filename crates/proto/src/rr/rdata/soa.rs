@@ -7,7 +7,12 @@
 
 //! start of authority record defining ownership and defaults for the zone
 
-use std::fmt;
+use std::{
+    cmp::Ordering,
+    fmt,
+    ops::{Add, Sub},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 #[cfg(feature = "serde-config")]
 use serde::{Deserialize, Serialize};
@@ -67,7 +72,7 @@ use crate::{
 pub struct SOA {
     mname: Name,
     rname: Name,
-    serial: u32,
+    serial: Serial,
     refresh: i32,
     retry: i32,
     expire: i32,
@@ -102,7 +107,7 @@ impl SOA {
         Self {
             mname,
             rname,
-            serial,
+            serial: Serial(serial),
             refresh,
             retry,
             expire,
@@ -110,9 +115,28 @@ impl SOA {
         }
     }
 
-    /// Increments the serial number by one
+    /// Increments the serial number by one, wrapping around to 0 after `u32::MAX`
+    ///
+    /// The serial addition of [RFC 1982](https://tools.ietf.org/html/rfc1982), the new serial is
+    ///  always greater than the previous one.
     pub fn increment_serial(&mut self) {
-        self.serial += 1; // TODO: what to do on overflow?
+        self.serial = self.serial + 1;
+    }
+
+    /// Advances the serial to the `YYYYMMDDNN` format of the UTC date of `now`
+    ///
+    /// If the serial is already of the date of `now`, the sequence number `NN` is incremented,
+    ///  otherwise the serial starts at `YYYYMMDD00`. A serial which is not less than
+    ///  `YYYYMMDD00`, e.g. after 99 changes in a day or with serials of another format, is
+    ///  incremented instead, so that the new serial is always greater than the previous one.
+    pub fn advance_serial_to_timestamp(&mut self, now: SystemTime) {
+        let date_serial = Serial(date_serial(now));
+
+        if self.serial < date_serial {
+            self.serial = date_serial;
+        } else {
+            self.increment_serial();
+        }
     }
 
     /// ```text
@@ -153,6 +177,11 @@ impl SOA {
     /// The unsigned 32 bit version number of the original copy of the zone. Zone transfers
     /// preserve this value. This value wraps and should be compared using sequence space arithmetic.
     pub fn serial(&self) -> u32 {
+        self.serial.0
+    }
+
+    /// The serial number of the zone, for comparisons with sequence space arithmetic
+    pub fn serial_number(&self) -> Serial {
         self.serial
     }
 
@@ -235,7 +264,7 @@ impl BinEncodable for SOA {
             .emit_with_lowercase(encoder, is_canonical_names)?;
         self.rname
             .emit_with_lowercase(encoder, is_canonical_names)?;
-        encoder.emit_u32(self.serial)?;
+        encoder.emit_u32(self.serial.0)?;
         encoder.emit_i32(self.refresh)?;
         encoder.emit_i32(self.retry)?;
         encoder.emit_i32(self.expire)?;
@@ -249,7 +278,7 @@ impl<'r> BinDecodable<'r> for SOA {
         Ok(Self {
             mname: Name::read(decoder)?,
             rname: Name::read(decoder)?,
-            serial: decoder.read_u32()?.unverified(/*any u32 is valid*/),
+            serial: Serial(decoder.read_u32()?.unverified(/*any u32 is valid*/)),
             refresh: decoder.read_i32()?.unverified(/*any i32 is valid*/),
             retry: decoder.read_i32()?.unverified(/*any i32 is valid*/),
             expire: decoder.read_i32()?.unverified(/*any i32 is valid*/),
@@ -351,6 +380,114 @@ impl fmt::Display for SOA {
     }
 }
 
+/// A zone serial number, compared and added to with the sequence space arithmetic of
+///  [RFC 1982](https://tools.ietf.org/html/rfc1982)
+///
+/// ```text
+/// 3.2. Comparison
+///
+///    Any two sequence numbers, s1 and s2, can be compared.  s1 is said
+///    to be less than s2 if, and only if, s1 is not equal to s2, and
+///
+///         (i1 < i2 and i2 - i1 < 2^(SERIAL_BITS - 1)) or
+///         (i1 > i2 and i1 - i2 > 2^(SERIAL_BITS - 1))
+/// ```
+///
+/// Serials which are exactly 2^31 apart are not comparable, and the comparison is not transitive,
+///  so `Serial` is only `PartialOrd`.
+#[cfg_attr(
+    feature = "serde-config",
+    derive(Deserialize, Serialize),
+    serde(transparent)
+)]
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Serial(pub u32);
+
+/// Returns the `YYYYMMDD00` serial of the UTC date of `now`
+fn date_serial(now: SystemTime) -> u32 {
+    // times before the epoch are clamped to it
+    let days = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / 86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    // the format overflows u32 in the year 42950
+    (year * 1_000_000 + month * 10_000 + day * 100) as u32
+}
+
+/// Converts days since 1970-01-01 into a (year, month, day) date of the proleptic Gregorian calendar
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    // shift the epoch to 0000-03-01, the start of a 400 year era
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // months starting in March
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    (year, month, day)
+}
+
+/// Half of the serial number space, 2^(SERIAL_BITS - 1)
+const SERIAL_HALF: u32 = 1 << 31;
+
+impl PartialOrd for Serial {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match other.0.wrapping_sub(self.0) {
+            0 => Some(Ordering::Equal),
+            distance if distance < SERIAL_HALF => Some(Ordering::Less),
+            distance if distance > SERIAL_HALF => Some(Ordering::Greater),
+            _ => None,
+        }
+    }
+}
+
+/// Adds to the serial, wrapping around at 2^32
+///
+/// RFC 1982 only defines the addition of values up to 2^31 - 1, larger values are added all
+///  the same, but the result is not greater than the original serial.
+impl Add<u32> for Serial {
+    type Output = Self;
+
+    fn add(self, rhs: u32) -> Self {
+        Self(self.0.wrapping_add(rhs))
+    }
+}
+
+/// Subtracts from the serial, wrapping around at 0
+impl Sub<u32> for Serial {
+    type Output = Self;
+
+    fn sub(self, rhs: u32) -> Self {
+        Self(self.0.wrapping_sub(rhs))
+    }
+}
+
+impl From<u32> for Serial {
+    fn from(serial: u32) -> Self {
+        Self(serial)
+    }
+}
+
+impl From<Serial> for u32 {
+    fn from(serial: Serial) -> Self {
+        serial.0
+    }
+}
+
+impl fmt::Display for Serial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::dbg_macro, clippy::print_stdout)]
@@ -385,4 +522,137 @@ mod tests {
         let read_rdata = SOA::read_data(&mut decoder, Restrict::new(len)).expect("Decoding error");
         assert_eq!(rdata, read_rdata);
     }
+
+    #[test]
+    fn test_serial_comparison() {
+        // RFC 1982, section 5.2, with SERIAL_BITS = 32
+        assert!(Serial(0) < Serial(1));
+        assert!(Serial(0) < Serial(SERIAL_HALF - 1));
+        assert!(Serial(1) < Serial(SERIAL_HALF));
+        assert!(Serial(SERIAL_HALF) < Serial(u32::MAX));
+        assert!(Serial(u32::MAX) < Serial(0));
+        assert!(Serial(SERIAL_HALF + 1) < Serial(0));
+        assert!(Serial(u32::MAX - 10) < Serial(10));
+        assert!(Serial(10) > Serial(u32::MAX - 10));
+        assert!(Serial(42) <= Serial(42));
+        assert!(Serial(42) >= Serial(42));
+
+        // serials 2^31 apart are not comparable
+        for (s1, s2) in [(0, SERIAL_HALF), (SERIAL_HALF, 0), (1, SERIAL_HALF + 1)] {
+            assert_eq!(Serial(s1).partial_cmp(&Serial(s2)), None);
+            assert!(!Serial(s1).lt(&Serial(s2)));
+            assert!(!Serial(s1).gt(&Serial(s2)));
+        }
+    }
+
+    #[test]
+    fn test_serial_arithmetic() {
+        // RFC 1982, section 5.1, adding 1 to the largest value wraps around to 0, which is greater
+        let max = Serial(u32::MAX);
+        assert_eq!(max + 1, Serial(0));
+        assert!(max + 1 > max);
+        assert_eq!(Serial(0) - 1, max);
+        assert_eq!(Serial(100) + 100, Serial(200));
+        assert_eq!(Serial(200) - 100, Serial(100));
+
+        // the largest addition which keeps the result greater
+        assert!(Serial(5) + (SERIAL_HALF - 1) > Serial(5));
+        assert_eq!(Serial(5).partial_cmp(&(Serial(5) + SERIAL_HALF)), None);
+        assert!(Serial(5) + (SERIAL_HALF + 1) < Serial(5));
+    }
+
+    #[test]
+    fn test_increment_serial() {
+        use std::str::FromStr;
+
+        let mut rdata = SOA::new(
+            Name::from_str("m.example.com").unwrap(),
+            Name::from_str("r.example.com").unwrap(),
+            u32::MAX,
+            2,
+            3,
+            4,
+            5,
+        );
+        let serial = rdata.serial_number();
+
+        rdata.increment_serial();
+        assert_eq!(rdata.serial(), 0);
+        assert!(rdata.serial_number() > serial);
+    }
+
+    fn soa(serial: u32) -> SOA {
+        use std::str::FromStr;
+
+        SOA::new(
+            Name::from_str("m.example.com").unwrap(),
+            Name::from_str("r.example.com").unwrap(),
+            serial,
+            2,
+            3,
+            4,
+            5,
+        )
+    }
+
+    /// Returns the time of the date, at noon
+    fn date(year: u64, month: u64, day: u64) -> SystemTime {
+        use std::time::Duration;
+
+        let days = (0..)
+            .find(|days| civil_from_days(*days) == (year, month, day))
+            .unwrap();
+        UNIX_EPOCH + Duration::from_secs(days * 86_400 + 43_200)
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(59), (1970, 3, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(11_017), (2000, 3, 1));
+        assert_eq!(civil_from_days(19_722), (2023, 12, 31));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+    }
+
+    #[test]
+    fn test_advance_serial_to_timestamp() {
+        let now = date(2024, 3, 15);
+
+        // from a serial of another format
+        let mut rdata = soa(1);
+        rdata.advance_serial_to_timestamp(now);
+        assert_eq!(rdata.serial(), 2024031500);
+
+        // on the same day
+        rdata.advance_serial_to_timestamp(now);
+        assert_eq!(rdata.serial(), 2024031501);
+
+        // from a previous day
+        let mut rdata = soa(2024031499);
+        rdata.advance_serial_to_timestamp(now);
+        assert_eq!(rdata.serial(), 2024031500);
+
+        // from a previous year, at the end of a month
+        let mut rdata = soa(2023123142);
+        rdata.advance_serial_to_timestamp(date(2024, 2, 29));
+        assert_eq!(rdata.serial(), 2024022900);
+
+        // after 99 changes on a day, the serial runs into the next day
+        let mut rdata = soa(2024031599);
+        rdata.advance_serial_to_timestamp(now);
+        assert_eq!(rdata.serial(), 2024031600);
+        rdata.advance_serial_to_timestamp(date(2024, 3, 16));
+        assert_eq!(rdata.serial(), 2024031601);
+
+        // a serial ahead of the date is incremented
+        let mut rdata = soa(2025010100);
+        rdata.advance_serial_to_timestamp(now);
+        assert_eq!(rdata.serial(), 2025010101);
+
+        // times before the epoch are clamped to it
+        let mut rdata = soa(0);
+        rdata.advance_serial_to_timestamp(UNIX_EPOCH - std::time::Duration::from_secs(1));
+        assert_eq!(rdata.serial(), 1970010100);
+    }
 }
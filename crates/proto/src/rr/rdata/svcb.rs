@@ -14,14 +14,22 @@ use std::{
     fmt,
 };
 
+use rand::{seq::SliceRandom, Rng};
 #[cfg(feature = "serde-config")]
 use serde::{Deserialize, Serialize};
 
 use enum_as_inner::EnumAsInner;
 
+#[cfg(feature = "ech")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ech")))]
+pub mod ech;
+
+#[cfg(feature = "text-parsing")]
+use crate::serialize::txt::{ParseError, RDataParser};
 use crate::{
     error::{ProtoError, ProtoErrorKind, ProtoResult},
     rr::{
+        presentation::write_character_string,
         rdata::{A, AAAA},
         Name, RData, RecordData, RecordDataDecodable, RecordType,
     },
@@ -70,7 +78,14 @@ use crate::{
 ///   If any RRs are malformed, the client MUST reject the entire RRSet and
 ///   fall back to non-SVCB connection establishment.
 /// ```
+///
+/// With the `text-parsing` feature, the record data is serialized with serde as a string in
+///  presentation format, e.g. `"1 . alpn=h2,h3"`, see [`SVCB::from_str`].
 #[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[cfg_attr(
+    all(feature = "serde-config", feature = "text-parsing"),
+    serde(try_from = "String", into = "String")
+)]
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct SVCB {
     svc_priority: u16,
@@ -79,9 +94,27 @@ pub struct SVCB {
 }
 
 impl SVCB {
+    /// Returns a builder for a record of the priority and target name, see [`SvcbBuilder`]
+    ///
+    /// ```
+    /// use hickory_proto::rr::{rdata::SVCB, Name};
+    ///
+    /// let target = Name::from_ascii("dot.example.com.").unwrap();
+    /// let svcb = SVCB::builder(1, target).alpn(["h2", "h3"]).port(443).build().unwrap();
+    /// assert_eq!(svcb.to_string(), "1 dot.example.com. alpn=h2,h3 port=443");
+    /// ```
+    pub fn builder(svc_priority: u16, target_name: Name) -> SvcbBuilder {
+        SvcbBuilder {
+            svc_priority,
+            target_name,
+            svc_params: Vec::new(),
+        }
+    }
+
     /// Create a new SVCB record from parts
     ///
-    /// It is up to the caller to validate the data going into the record
+    /// It is up to the caller to validate the data going into the record, see [`SVCB::builder`]
+    ///  for a validated record
     pub fn new(
         svc_priority: u16,
         target_name: Name,
@@ -156,6 +189,128 @@ impl SVCB {
     pub fn svc_params(&self) -> &[(SvcParamKey, SvcParamValue)] {
         &self.svc_params
     }
+
+    /// The ECHConfigList of the "ech" parameter, if present
+    pub fn ech_config_list(&self) -> Option<&EchConfigList> {
+        self.svc_params.iter().find_map(|(_, value)| match value {
+            SvcParamValue::EchConfigList(ech) => Some(ech),
+            _ => None,
+        })
+    }
+}
+
+/// A builder of [`SVCB`] records, which sorts and validates the parameters
+///
+/// [`SvcbBuilder::build`] sorts the parameters by key, as required for the wire format, and
+///  returns an error if the record is inconsistent:
+///
+/// * a key is set more than once
+/// * the key of a parameter doesn't match its value
+/// * a key listed in `mandatory` is not present in the record, or is `mandatory` itself
+/// * `no-default-alpn` is present without `alpn`
+#[derive(Debug, Clone)]
+pub struct SvcbBuilder {
+    svc_priority: u16,
+    target_name: Name,
+    svc_params: Vec<(SvcParamKey, SvcParamValue)>,
+}
+
+impl SvcbBuilder {
+    /// Sets the "alpn" parameter, the supported protocols
+    pub fn alpn(self, alpn: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let alpn = alpn.into_iter().map(Into::into).collect();
+        self.param(SvcParamKey::Alpn, SvcParamValue::Alpn(Alpn(alpn)))
+    }
+
+    /// Sets the "no-default-alpn" parameter, the default protocol is not supported
+    pub fn no_default_alpn(self) -> Self {
+        self.param(SvcParamKey::NoDefaultAlpn, SvcParamValue::NoDefaultAlpn)
+    }
+
+    /// Sets the "port" parameter
+    pub fn port(self, port: u16) -> Self {
+        self.param(SvcParamKey::Port, SvcParamValue::Port(port))
+    }
+
+    /// Sets the "ipv4hint" parameter
+    pub fn ipv4hint(self, hints: Vec<A>) -> Self {
+        self.param(
+            SvcParamKey::Ipv4Hint,
+            SvcParamValue::Ipv4Hint(IpHint(hints)),
+        )
+    }
+
+    /// Sets the "ipv6hint" parameter
+    pub fn ipv6hint(self, hints: Vec<AAAA>) -> Self {
+        self.param(
+            SvcParamKey::Ipv6Hint,
+            SvcParamValue::Ipv6Hint(IpHint(hints)),
+        )
+    }
+
+    /// Sets the "ech" parameter
+    pub fn ech(self, ech: EchConfigList) -> Self {
+        self.param(
+            SvcParamKey::EchConfigList,
+            SvcParamValue::EchConfigList(ech),
+        )
+    }
+
+    /// Sets the "mandatory" parameter, the keys which clients must support to use the record
+    pub fn mandatory(self, keys: Vec<SvcParamKey>) -> Self {
+        self.param(
+            SvcParamKey::Mandatory,
+            SvcParamValue::Mandatory(Mandatory(keys)),
+        )
+    }
+
+    /// Sets the parameter of the key, e.g. of a private use key with an [`Unknown`] value
+    pub fn param(mut self, key: SvcParamKey, value: SvcParamValue) -> Self {
+        self.svc_params.push((key, value));
+        self
+    }
+
+    /// Returns the record, with the parameters sorted by key
+    pub fn build(self) -> ProtoResult<SVCB> {
+        let Self {
+            svc_priority,
+            target_name,
+            mut svc_params,
+        } = self;
+
+        for (key, value) in &svc_params {
+            if !value.is_value_of(*key) {
+                return Err(
+                    format!("value of SvcParam {key} does not match the key: {value}").into(),
+                );
+            }
+        }
+
+        svc_params.sort_by_key(|(key, _)| *key);
+        if let Some(pair) = svc_params.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(format!("SvcParam {} is set more than once", pair[0].0).into());
+        }
+
+        let has_key = |key: SvcParamKey| svc_params.iter().any(|(k, _)| *k == key);
+
+        if let Some((_, SvcParamValue::Mandatory(Mandatory(keys)))) = svc_params.first() {
+            for key in keys {
+                if *key == SvcParamKey::Mandatory {
+                    return Err("mandatory SvcParam lists itself".into());
+                }
+
+                if !has_key(*key) {
+                    return Err(format!("mandatory SvcParam {key} is not present").into());
+                }
+            }
+        }
+
+        if has_key(SvcParamKey::NoDefaultAlpn) && !has_key(SvcParamKey::Alpn) {
+            return Err("SvcParam no-default-alpn is present without alpn".into());
+        }
+
+        Ok(SVCB::new(svc_priority, target_name, svc_params))
+    }
 }
 
 ///  [RFC 9460 SVCB and HTTPS Resource Records, Nov 2023](https://datatracker.ietf.org/doc/html/rfc9460#section-14.3.2)
@@ -203,6 +358,7 @@ impl SVCB {
 /// ```
 #[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[non_exhaustive]
 pub enum SvcParamKey {
     /// Mandatory keys in this RR
     Mandatory,
@@ -288,7 +444,7 @@ impl fmt::Display for SvcParamKey {
             Self::Ipv6Hint => f.write_str("ipv6hint")?,
             Self::Key(val) => write!(f, "key{val}")?,
             Self::Key65535 => f.write_str("key65535")?,
-            Self::Unknown(val) => write!(f, "unknown{val}")?,
+            Self::Unknown(val) => write!(f, "key{val}")?,
         }
 
         Ok(())
@@ -300,7 +456,7 @@ impl std::str::FromStr for SvcParamKey {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         /// keys are in the format of key#, e.g. key12344, with a max value of u16
-        fn parse_unknown_key(key: &str) -> Result<SvcParamKey, ProtoError> {
+        fn parse_unknown_key(key: &str) -> ProtoResult<SvcParamKey> {
             let key_value = key.strip_prefix("key").ok_or_else(|| {
                 ProtoError::from(ProtoErrorKind::Msg(format!(
                     "bad formatted key ({key}), expected key1234"
@@ -349,6 +505,7 @@ impl PartialOrd for SvcParamKey {
 /// ```
 #[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
 #[derive(Debug, PartialEq, Eq, Hash, Clone, EnumAsInner)]
+#[non_exhaustive]
 pub enum SvcParamValue {
     ///    In a ServiceMode RR, a SvcParamKey is considered "mandatory" if the
     ///    RR will not function correctly for clients that ignore this
@@ -432,6 +589,25 @@ pub enum SvcParamValue {
 }
 
 impl SvcParamValue {
+    /// Returns true if this is a value of the key, unknown values are values of any key without
+    ///  a known format
+    fn is_value_of(&self, key: SvcParamKey) -> bool {
+        matches!(
+            (key, self),
+            (SvcParamKey::Mandatory, Self::Mandatory(_))
+                | (SvcParamKey::Alpn, Self::Alpn(_))
+                | (SvcParamKey::NoDefaultAlpn, Self::NoDefaultAlpn)
+                | (SvcParamKey::Port, Self::Port(_))
+                | (SvcParamKey::Ipv4Hint, Self::Ipv4Hint(_))
+                | (SvcParamKey::EchConfigList, Self::EchConfigList(_))
+                | (SvcParamKey::Ipv6Hint, Self::Ipv6Hint(_))
+                | (
+                    SvcParamKey::Key(_) | SvcParamKey::Key65535 | SvcParamKey::Unknown(_),
+                    Self::Unknown(_)
+                )
+        )
+    }
+
     // a 2 octet field containing the length of the SvcParamValue as an
     //      integer between 0 and 65535 in network byte order (but constrained
     //      by the RDATA and DNS message sizes).
@@ -632,9 +808,11 @@ impl fmt::Display for Mandatory {
     ///
     ///    ipv6hint=... key65333=ex1 key65444=ex2 mandatory=key65444,ipv6hint
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        for key in self.0.iter() {
-            // TODO: confirm in the RFC that trailing commas are ok
-            write!(f, "{key},")?;
+        for (i, key) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{key}")?;
         }
 
         Ok(())
@@ -796,16 +974,84 @@ impl BinEncodable for Alpn {
 impl fmt::Display for Alpn {
     ///   The presentation value SHALL be a comma-separated list
     ///   (Appendix A.1) of one or more "alpn-id"s.
+    ///
+    /// Commas and backslashes in the alpn-ids are escaped as `\,` and `\\`.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        for alpn in self.0.iter() {
-            // TODO: confirm in the RFC that trailing commas are ok
-            write!(f, "{alpn},")?;
+        for (i, alpn) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            for c in alpn.chars() {
+                if c == ',' || c == '\\' {
+                    f.write_str("\\")?;
+                }
+                write!(f, "{c}")?;
+            }
         }
 
         Ok(())
     }
 }
 
+impl std::str::FromStr for Alpn {
+    type Err = ProtoError;
+
+    /// Parses the presentation format, a comma-separated list of alpn-ids, e.g. `h2,h3`
+    ///
+    /// [RFC 9460 SVCB and HTTPS Resource Records, Nov 2023](https://datatracker.ietf.org/doc/html/rfc9460#appendix-A.1)
+    ///
+    /// ```text
+    ///   In order to represent lists of items in zone files, this
+    ///   specification uses comma-separated lists.  When the allowed items in
+    ///   the list cannot contain "," or "\", this is trivial.
+    /// ```
+    ///
+    /// Commas and backslashes in an alpn-id are escaped as `\,` and `\\`, other escape sequences
+    ///  are rejected. The escapes of a zone file character-string, e.g. `\065`, must already be
+    ///  decoded. Every alpn-id must have 1 to 255 octets, i.e. empty items are not allowed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn push(alpns: &mut Vec<String>, alpn: &mut String) -> ProtoResult<()> {
+            if alpn.is_empty() || alpn.len() > 255 {
+                return Err(ProtoErrorKind::Msg(format!(
+                    "alpn-id must have 1 to 255 octets, found {}",
+                    alpn.len()
+                ))
+                .into());
+            }
+
+            alpns.push(std::mem::take(alpn));
+            Ok(())
+        }
+
+        if s.is_empty() {
+            return Err(ProtoError::from("Alpn expects at least one value"));
+        }
+
+        let mut alpns = Vec::new();
+        let mut alpn = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                ',' => push(&mut alpns, &mut alpn)?,
+                '\\' => match chars.next() {
+                    Some(escaped @ (',' | '\\')) => alpn.push(escaped),
+                    Some(other) => {
+                        return Err(ProtoErrorKind::Msg(format!(
+                            "unsupported escape sequence in alpn: \\{other}"
+                        ))
+                        .into())
+                    }
+                    None => return Err(ProtoError::from("alpn ends with an escape character")),
+                },
+                c => alpn.push(c),
+            }
+        }
+        push(&mut alpns, &mut alpn)?;
+
+        Ok(Self(alpns))
+    }
+}
+
 /// [draft-ietf-tls-svcb-ech-01 Bootstrapping TLS Encrypted ClientHello with DNS Service Bindings, Sep 2024](https://datatracker.ietf.org/doc/html/draft-ietf-tls-svcb-ech-01)
 ///
 /// ```text
@@ -827,6 +1073,48 @@ impl fmt::Display for Alpn {
 #[repr(transparent)]
 pub struct EchConfigList(pub Vec<u8>);
 
+impl EchConfigList {
+    /// The length of the ECHConfigList in bytes, including the length prefix
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if there are no bytes, not even the length prefix
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The number of ECHConfig structures in the list
+    ///
+    /// Only the length prefixes are read, the ECHConfig contents are not parsed. Empty data is
+    ///  counted as no configs. Returns an error if the lengths don't match the data.
+    pub fn config_count(&self) -> ProtoResult<usize> {
+        if self.0.is_empty() {
+            return Ok(0);
+        }
+
+        let mut decoder = BinDecoder::new(&self.0);
+        let list_len = decoder.read_u16()?.unverified(/*checked against the remaining data*/);
+        if usize::from(list_len) != decoder.len() {
+            return Err(ProtoError::from(format!(
+                "ECHConfigList length {list_len} does not match the remaining {} bytes",
+                decoder.len()
+            )));
+        }
+
+        let mut count = 0;
+        while !decoder.is_empty() {
+            // ECHConfig: version followed by the length of the contents
+            decoder.read_u16()?;
+            let len = decoder.read_u16()?.unverified(/*the contents are skipped*/);
+            decoder.read_slice(usize::from(len))?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
+
 impl<'r> BinDecodable<'r> for EchConfigList {
     /// In wire format, the value of the parameter is an ECHConfigList (Section 4 of draft-ietf-tls-esni-18),
     /// including the redundant length prefix. In presentation format, the value is the
@@ -929,6 +1217,33 @@ impl fmt::Debug for EchConfigList {
 #[repr(transparent)]
 pub struct IpHint<T>(pub Vec<T>);
 
+impl<T> IpHint<T> {
+    /// Shuffles the addresses in place
+    ///
+    /// ```text
+    ///   Like an A or AAAA RRSet, the list of addresses represents an unordered
+    ///   collection, and clients SHOULD pick addresses to use in a random order.
+    /// ```
+    pub fn shuffle<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.0.shuffle(rng);
+    }
+
+    /// Returns a copy of the hint with the addresses shuffled, see [`Self::shuffle`]
+    pub fn shuffled<R: Rng + ?Sized>(&self, rng: &mut R) -> Self
+    where
+        T: Clone,
+    {
+        let mut hint = self.clone();
+        hint.shuffle(rng);
+        hint
+    }
+
+    /// Picks one of the addresses at random, `None` if the hint is empty
+    pub fn random_pick<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<&T> {
+        self.0.choose(rng)
+    }
+}
+
 impl<'r, T> BinDecodable<'r> for IpHint<T>
 where
     T: BinDecodable<'r>,
@@ -976,8 +1291,11 @@ where
     ///   in standard textual format [RFC 5952](https://tools.ietf.org/html/rfc5952).  To enable simpler parsing,
     ///   this SvcParamValue MUST NOT contain escape sequences.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        for ip in self.0.iter() {
-            write!(f, "{ip},")?;
+        for (i, ip) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{ip}")?;
         }
 
         Ok(())
@@ -1019,10 +1337,8 @@ impl<'r> BinDecodable<'r> for Unknown {
 
 impl BinEncodable for Unknown {
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
-        // draft-ietf-dnsop-svcb-https-11#appendix-A: The algorithm is the same as used by
-        // <character-string> in RFC 1035, although the output length in this
-        // document is not limited to 255 octets.
-        encoder.emit_character_data_unrestricted(&self.0)?;
+        // the value is the decoded presentation value, without a length prefix, as read
+        encoder.emit_vec(&self.0)?;
 
         Ok(())
     }
@@ -1030,10 +1346,8 @@ impl BinEncodable for Unknown {
 
 impl fmt::Display for Unknown {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        // TODO: this needs to be properly encoded
-        write!(f, "\"{}\",", String::from_utf8_lossy(&self.0))?;
-
-        Ok(())
+        // the generic format of RFC 9460, section 2.1, octets escaped as \DDD
+        write_character_string(f, &self.0)
     }
 }
 
@@ -1168,15 +1482,49 @@ impl fmt::Display for SVCB {
         )?;
 
         for (key, param) in self.svc_params.iter() {
-            write!(f, " {key}={param}")?
+            match param {
+                // no-default-alpn has no value, and the key must not be followed by "="
+                SvcParamValue::NoDefaultAlpn => write!(f, " {key}")?,
+                _ => write!(f, " {key}={param}")?,
+            }
         }
 
         Ok(())
     }
 }
 
+#[cfg(feature = "text-parsing")]
+impl std::str::FromStr for SVCB {
+    type Err = ParseError;
+
+    /// Parses the record data in presentation format, e.g. `1 . alpn=h2,h3`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rdata = RData::try_from_str(RecordType::SVCB, s)?;
+        Self::try_from_rdata(rdata).map_err(|_| ParseError::from("not SVCB record data"))
+    }
+}
+
+#[cfg(feature = "text-parsing")]
+impl TryFrom<String> for SVCB {
+    type Error = ParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<SVCB> for String {
+    fn from(svcb: SVCB) -> Self {
+        svcb.to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
+    use proptest::{collection::vec, prelude::*};
+
     use super::*;
 
     #[test]
@@ -1296,4 +1644,223 @@ mod tests {
         let mut encoder = BinEncoder::new(&mut buf);
         svcb.emit(&mut encoder).unwrap();
     }
+
+    #[test]
+    fn test_alpn_from_str() {
+        let alpn = Alpn::from_str("h2,h3").unwrap();
+        assert_eq!(alpn.0, ["h2", "h3"]);
+        assert_eq!(alpn.to_string(), "h2,h3");
+
+        // Figure 10 of RFC 9460, after the character-string is decoded
+        let alpn = Alpn::from_str(r"f\\oo\,bar,h2").unwrap();
+        assert_eq!(alpn.0, [r"f\oo,bar", "h2"]);
+        assert_eq!(alpn.to_string(), r"f\\oo\,bar,h2");
+
+        assert!(Alpn::from_str("").is_err());
+        assert!(Alpn::from_str("h2,").is_err());
+        assert!(Alpn::from_str(",h2").is_err());
+        assert!(Alpn::from_str("h2,,h3").is_err());
+        assert!(Alpn::from_str(r"h2\").is_err());
+        assert!(Alpn::from_str(r"h\2").is_err());
+        assert!(Alpn::from_str(&"a".repeat(255)).is_ok());
+        assert!(Alpn::from_str(&"a".repeat(256)).is_err());
+    }
+
+    #[test]
+    fn test_ip_hint_random_pick() {
+        let hint = IpHint(vec![
+            A::new(192, 0, 2, 1),
+            A::new(192, 0, 2, 2),
+            A::new(192, 0, 2, 3),
+        ]);
+        let mut rng = rand::thread_rng();
+
+        let picks = (0..100)
+            .map(|_| *hint.random_pick(&mut rng).unwrap())
+            .collect::<Vec<_>>();
+        assert!(picks.iter().all(|pick| hint.0.contains(pick)));
+        assert!(picks.iter().any(|pick| *pick != hint.0[0]));
+
+        assert_eq!(IpHint::<A>(vec![]).random_pick(&mut rng), None);
+    }
+
+    #[test]
+    fn test_ip_hint_shuffle() {
+        let hint = IpHint((0..=255).map(|i| A::new(192, 0, 2, i)).collect::<Vec<_>>());
+        let mut rng = rand::thread_rng();
+
+        let mut shuffled = hint.shuffled(&mut rng);
+        assert_ne!(shuffled, hint);
+        shuffled.0.sort_by_key(|a| a.0);
+        assert_eq!(shuffled, hint);
+
+        let mut shuffled = hint.clone();
+        shuffled.shuffle(&mut rng);
+        assert_ne!(shuffled, hint);
+    }
+
+    /// An ECHConfigList with the length prefix, of ECHConfigs with the given contents
+    fn ech_config_list(contents: &[&[u8]]) -> EchConfigList {
+        let mut configs = Vec::new();
+        for content in contents {
+            configs.extend_from_slice(&0xfe0d_u16.to_be_bytes());
+            configs.extend_from_slice(&(content.len() as u16).to_be_bytes());
+            configs.extend_from_slice(content);
+        }
+
+        let mut list = (configs.len() as u16).to_be_bytes().to_vec();
+        list.extend(configs);
+        EchConfigList(list)
+    }
+
+    #[test]
+    fn test_ech_config_count() {
+        let empty = ech_config_list(&[]);
+        assert_eq!(empty.len(), 2);
+        assert!(!empty.is_empty());
+        assert_eq!(empty.config_count().unwrap(), 0);
+
+        let one = ech_config_list(&[b"config"]);
+        assert_eq!(one.len(), 2 + 4 + 6);
+        assert_eq!(one.config_count().unwrap(), 1);
+
+        let two = ech_config_list(&[b"first", b""]);
+        assert_eq!(two.config_count().unwrap(), 2);
+
+        assert!(EchConfigList(vec![]).is_empty());
+        assert_eq!(EchConfigList(vec![]).config_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_ech_config_count_invalid() {
+        // the config is longer than the list
+        let mut truncated = ech_config_list(&[b"config"]);
+        truncated.0.pop();
+        truncated.0[1] -= 1;
+        assert!(truncated.config_count().is_err());
+
+        // the list length doesn't match the data
+        let mut trailing = ech_config_list(&[b"config"]);
+        trailing.0.push(0);
+        assert!(trailing.config_count().is_err());
+
+        assert!(EchConfigList(vec![0]).config_count().is_err());
+    }
+
+    #[test]
+    fn test_builder_sorts_params() {
+        let target = Name::from_ascii("dot.example.com.").unwrap();
+        let svcb = SVCB::builder(1, target.clone())
+            .port(443)
+            .ipv4hint(vec![A::new(192, 0, 2, 1)])
+            .mandatory(vec![SvcParamKey::Port])
+            .alpn(["dot"])
+            .build()
+            .unwrap();
+
+        assert_eq!(svcb.svc_priority(), 1);
+        assert_eq!(svcb.target_name(), &target);
+        assert_eq!(
+            svcb.svc_params(),
+            [
+                (
+                    SvcParamKey::Mandatory,
+                    SvcParamValue::Mandatory(Mandatory(vec![SvcParamKey::Port]))
+                ),
+                (
+                    SvcParamKey::Alpn,
+                    SvcParamValue::Alpn(Alpn(vec!["dot".to_string()]))
+                ),
+                (SvcParamKey::Port, SvcParamValue::Port(443)),
+                (
+                    SvcParamKey::Ipv4Hint,
+                    SvcParamValue::Ipv4Hint(IpHint(vec![A::new(192, 0, 2, 1)]))
+                ),
+            ]
+        );
+
+        // the record can be emitted and read back
+        let mut bytes = Vec::new();
+        svcb.emit(&mut BinEncoder::new(&mut bytes)).unwrap();
+        let mut decoder = BinDecoder::new(&bytes);
+        let read = SVCB::read_data(&mut decoder, Restrict::new(bytes.len() as u16)).unwrap();
+        assert_eq!(read, svcb);
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        let builder = SVCB::builder(1, Name::root());
+
+        assert!(builder.clone().port(853).port(443).build().is_err());
+        assert!(builder
+            .clone()
+            .mandatory(vec![SvcParamKey::Port])
+            .build()
+            .is_err());
+        assert!(builder
+            .clone()
+            .port(443)
+            .mandatory(vec![SvcParamKey::Mandatory, SvcParamKey::Port])
+            .build()
+            .is_err());
+        assert!(builder.clone().no_default_alpn().build().is_err());
+        assert!(builder
+            .clone()
+            .param(SvcParamKey::Port, SvcParamValue::NoDefaultAlpn)
+            .build()
+            .is_err());
+
+        assert!(builder
+            .clone()
+            .alpn(["h3"])
+            .no_default_alpn()
+            .build()
+            .is_ok());
+        assert!(builder
+            .param(
+                SvcParamKey::Key(65280),
+                SvcParamValue::Unknown(Unknown(b"private".to_vec()))
+            )
+            .build()
+            .is_ok());
+    }
+
+    #[cfg(all(feature = "serde-config", feature = "text-parsing"))]
+    #[test]
+    fn test_serde_presentation_format() {
+        let svcb = SVCB::new(
+            1,
+            Name::root(),
+            vec![
+                (
+                    SvcParamKey::Alpn,
+                    SvcParamValue::Alpn(Alpn(vec!["h2".to_string(), "h3".to_string()])),
+                ),
+                (SvcParamKey::Port, SvcParamValue::Port(8443)),
+            ],
+        );
+
+        let json = serde_json::to_string(&svcb).unwrap();
+        assert_eq!(json, r#""1 . alpn=h2,h3 port=8443""#);
+        assert_eq!(serde_json::from_str::<SVCB>(&json).unwrap(), svcb);
+
+        // as written by hand in a configuration file
+        let svcb = serde_json::from_str::<SVCB>(r#""1 . alpn=\"h2,h3\" port=8443""#).unwrap();
+        assert_eq!(svcb.to_string(), "1 . alpn=h2,h3 port=8443");
+
+        let https = crate::rr::rdata::HTTPS(svcb);
+        let json = serde_json::to_string(&https).unwrap();
+        assert_eq!(serde_json::from_str(&json).ok(), Some(https));
+
+        assert!(serde_json::from_str::<SVCB>(r#""1 . alpn""#).is_err());
+        assert!(serde_json::from_str::<SVCB>(r#""not a priority .""#).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn test_alpn_display_round_trip(alpns in vec("[ -~]{1,32}", 1..5)) {
+            let alpn = Alpn(alpns);
+            prop_assert_eq!(Alpn::from_str(&alpn.to_string()).unwrap(), alpn);
+        }
+    }
 }
@@ -0,0 +1,560 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Structure of the ECHConfigList carried in the "ech" SvcParam
+//!
+//! [draft-ietf-tls-esni-22 TLS Encrypted Client Hello, Sep 2024](https://datatracker.ietf.org/doc/html/draft-ietf-tls-esni-22#section-4)
+//!
+//! ```text
+//!     opaque HpkePublicKey<1..2^16-1>;
+//!     uint16 HpkeKemId;              // Defined in RFC9180
+//!     uint16 HpkeKdfId;              // Defined in RFC9180
+//!     uint16 HpkeAeadId;             // Defined in RFC9180
+//!     uint16 ECHConfigExtensionType; // Defined in Section 11.3
+//!
+//!     struct {
+//!         HpkeKdfId kdf_id;
+//!         HpkeAeadId aead_id;
+//!     } HpkeSymmetricCipherSuite;
+//!
+//!     struct {
+//!         uint8 config_id;
+//!         HpkeKemId kem_id;
+//!         HpkePublicKey public_key;
+//!         HpkeSymmetricCipherSuite cipher_suites<4..2^16-4>;
+//!     } HpkeKeyConfig;
+//!
+//!     struct {
+//!         ECHConfigExtensionType type;
+//!         opaque data<0..2^16-1>;
+//!     } ECHConfigExtension;
+//!
+//!     struct {
+//!         HpkeKeyConfig key_config;
+//!         uint8 maximum_name_length;
+//!         opaque public_name<1..255>;
+//!         ECHConfigExtension extensions<0..2^16-1>;
+//!     } ECHConfigContents;
+//!
+//!     struct {
+//!         uint16 version;
+//!         uint16 length;
+//!         select (ECHConfig.version) {
+//!           case 0xfe0d: ECHConfigContents contents;
+//!         }
+//!     } ECHConfig;
+//!
+//!     ECHConfig ECHConfigList<4..2^16-1>;
+//! ```
+//!
+//! This only parses and validates the structure, the HPKE operations are left to the TLS
+//!  implementation. The retry configs sent by a server in its `encrypted_client_hello` extension
+//!  use the same encoding, and can be parsed by wrapping them in an [`EchConfigList`].
+
+use crate::{
+    error::{ProtoError, ProtoResult},
+    serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder},
+};
+
+use super::EchConfigList;
+
+/// The version of ECHConfig with a known structure, the one of draft-ietf-tls-esni-13 and later
+pub const ECH_VERSION: u16 = 0xfe0d;
+
+impl EchConfigList {
+    /// Parses the list of ECHConfig entries
+    ///
+    /// Entries with an unknown version are kept as [`EchConfig::Unknown`], the client should skip
+    ///  them when selecting a config.
+    pub fn configs(&self) -> ProtoResult<Vec<EchConfig>> {
+        let mut decoder = BinDecoder::new(&self.0);
+
+        let len =
+            decoder.read_u16()?.unverified(/*validated against the remaining bytes*/) as usize;
+        if len != decoder.len() {
+            return Err(ProtoError::from(format!(
+                "ECHConfigList length {len} does not match the {} remaining bytes",
+                decoder.len()
+            )));
+        }
+
+        let mut configs = Vec::new();
+        while !decoder.is_empty() {
+            configs.push(EchConfig::read(&mut decoder)?);
+        }
+
+        if configs.is_empty() {
+            return Err(ProtoError::from(
+                "ECHConfigList expects at least one ECHConfig",
+            ));
+        }
+
+        Ok(configs)
+    }
+
+    /// Parses the list of ECHConfig entries, failing on entries of a version other than
+    ///  [`ECH_VERSION`]
+    ///
+    /// For consumers which only support the current version and must not silently fall back to
+    ///  fewer configs, e.g. when validating the records of a zone. A TLS client should use
+    ///  [`Self::configs`] instead, and skip the unknown versions.
+    pub fn parse_configs(&self) -> ProtoResult<Vec<EchConfig>> {
+        let configs = self.configs()?;
+        if let Some(config) = configs.iter().find(|config| config.contents().is_none()) {
+            return Err(ProtoError::from(format!(
+                "ECHConfig version {:#06x} is not supported",
+                config.version()
+            )));
+        }
+
+        Ok(configs)
+    }
+
+    /// Encodes the configs as an ECHConfigList, including the length prefix
+    pub fn from_configs(configs: &[EchConfig]) -> ProtoResult<Self> {
+        if configs.is_empty() {
+            return Err(ProtoError::from(
+                "ECHConfigList expects at least one ECHConfig",
+            ));
+        }
+
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        emit_u16_prefixed(&mut encoder, |encoder| {
+            for config in configs {
+                config.emit(encoder)?;
+            }
+
+            Ok(())
+        })?;
+
+        Ok(Self(bytes))
+    }
+}
+
+/// An ECHConfig entry of the list
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum EchConfig {
+    /// A config of version [`ECH_VERSION`]
+    Current(EchConfigContents),
+    /// A config of another version, its contents are opaque
+    Unknown {
+        /// The version of the config
+        version: u16,
+        /// The encoded contents of the config
+        contents: Vec<u8>,
+    },
+}
+
+impl EchConfig {
+    /// The version of the config
+    pub fn version(&self) -> u16 {
+        match self {
+            Self::Current(_) => ECH_VERSION,
+            Self::Unknown { version, .. } => *version,
+        }
+    }
+
+    /// The contents of the config, if the version is known
+    pub fn contents(&self) -> Option<&EchConfigContents> {
+        match self {
+            Self::Current(contents) => Some(contents),
+            Self::Unknown { .. } => None,
+        }
+    }
+}
+
+impl<'r> BinDecodable<'r> for EchConfig {
+    fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
+        let version = decoder.read_u16()?.unverified(/*any version is valid*/);
+        let len = decoder.read_u16()?.unverified(/*bounded by read_slice*/) as usize;
+        let contents = decoder.read_slice(len)?.unverified(/*parsed below*/);
+
+        if version != ECH_VERSION {
+            return Ok(Self::Unknown {
+                version,
+                contents: contents.to_vec(),
+            });
+        }
+
+        let mut decoder = BinDecoder::new(contents);
+        let contents = EchConfigContents::read(&mut decoder)?;
+        if !decoder.is_empty() {
+            return Err(ProtoError::from(format!(
+                "ECHConfig has {} trailing bytes",
+                decoder.len()
+            )));
+        }
+
+        Ok(Self::Current(contents))
+    }
+}
+
+impl BinEncodable for EchConfig {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        encoder.emit_u16(self.version())?;
+        emit_u16_prefixed(encoder, |encoder| match self {
+            Self::Current(contents) => contents.emit(encoder),
+            Self::Unknown { contents, .. } => encoder.emit_vec(contents),
+        })
+    }
+}
+
+/// The contents of an ECHConfig of version [`ECH_VERSION`]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct EchConfigContents {
+    config_id: u8,
+    kem_id: u16,
+    public_key: Vec<u8>,
+    cipher_suites: Vec<HpkeSymmetricCipherSuite>,
+    maximum_name_length: u8,
+    public_name: String,
+    extensions: Vec<EchConfigExtension>,
+}
+
+impl EchConfigContents {
+    /// The identifier of the config, sent by the client to select the key of the server
+    pub fn config_id(&self) -> u8 {
+        self.config_id
+    }
+
+    /// The HPKE KEM identifier of the public key, [RFC 9180](https://datatracker.ietf.org/doc/html/rfc9180#section-7.1)
+    pub fn kem_id(&self) -> u16 {
+        self.kem_id
+    }
+
+    /// The HPKE public key of the server
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    /// The HPKE cipher suites supported by the server
+    pub fn cipher_suites(&self) -> &[HpkeSymmetricCipherSuite] {
+        &self.cipher_suites
+    }
+
+    /// The longest name the server expects in the inner ClientHello, 0 if it has no expectation
+    pub fn maximum_name_length(&self) -> u8 {
+        self.maximum_name_length
+    }
+
+    /// The name sent in the outer ClientHello, and used to authenticate retry configs
+    pub fn public_name(&self) -> &str {
+        &self.public_name
+    }
+
+    /// The extensions of the config
+    pub fn extensions(&self) -> &[EchConfigExtension] {
+        &self.extensions
+    }
+
+    /// Returns true if the config has an extension that the client must understand to use it
+    pub fn has_mandatory_extensions(&self) -> bool {
+        self.extensions.iter().any(EchConfigExtension::is_mandatory)
+    }
+}
+
+impl<'r> BinDecodable<'r> for EchConfigContents {
+    fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
+        let config_id = decoder.read_u8()?.unverified(/*any id is valid*/);
+        let kem_id = decoder.read_u16()?.unverified(/*any KEM is valid*/);
+
+        let public_key = read_u16_prefixed(decoder)?;
+        if public_key.is_empty() {
+            return Err(ProtoError::from("ECHConfig public_key is empty"));
+        }
+        let public_key = public_key.to_vec();
+
+        let suites = read_u16_prefixed(decoder)?;
+        if suites.is_empty() || suites.len() % 4 != 0 {
+            return Err(ProtoError::from(format!(
+                "ECHConfig cipher_suites length {} is not a non-zero multiple of 4",
+                suites.len()
+            )));
+        }
+        let mut suites = BinDecoder::new(suites);
+        let mut cipher_suites = Vec::new();
+        while !suites.is_empty() {
+            cipher_suites.push(HpkeSymmetricCipherSuite::read(&mut suites)?);
+        }
+
+        let maximum_name_length = decoder.read_u8()?.unverified(/*any length is valid*/);
+
+        let len = decoder.read_u8()?.unverified(/*bounded by read_slice*/) as usize;
+        let public_name = decoder.read_slice(len)?.unverified(/*validated below*/);
+        if public_name.is_empty() || !public_name.is_ascii() {
+            return Err(ProtoError::from(
+                "ECHConfig public_name is not a non-empty ASCII name",
+            ));
+        }
+        let public_name = String::from_utf8_lossy(public_name).into_owned();
+
+        let mut extensions_decoder = BinDecoder::new(read_u16_prefixed(decoder)?);
+        let mut extensions = Vec::new();
+        while !extensions_decoder.is_empty() {
+            extensions.push(EchConfigExtension::read(&mut extensions_decoder)?);
+        }
+
+        Ok(Self {
+            config_id,
+            kem_id,
+            public_key,
+            cipher_suites,
+            maximum_name_length,
+            public_name,
+            extensions,
+        })
+    }
+}
+
+impl BinEncodable for EchConfigContents {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        encoder.emit_u8(self.config_id)?;
+        encoder.emit_u16(self.kem_id)?;
+        emit_u16_prefixed(encoder, |encoder| encoder.emit_vec(&self.public_key))?;
+        emit_u16_prefixed(encoder, |encoder| {
+            for suite in &self.cipher_suites {
+                suite.emit(encoder)?;
+            }
+
+            Ok(())
+        })?;
+        encoder.emit_u8(self.maximum_name_length)?;
+
+        let public_name = u8::try_from(self.public_name.len())
+            .map_err(|_| ProtoError::from("ECHConfig public_name exceeds 255 bytes"))?;
+        encoder.emit_u8(public_name)?;
+        encoder.emit_vec(self.public_name.as_bytes())?;
+
+        emit_u16_prefixed(encoder, |encoder| {
+            for extension in &self.extensions {
+                extension.emit(encoder)?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// An HPKE KDF and AEAD pair, [RFC 9180](https://datatracker.ietf.org/doc/html/rfc9180#section-7.2)
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct HpkeSymmetricCipherSuite {
+    /// The HPKE KDF identifier
+    pub kdf_id: u16,
+    /// The HPKE AEAD identifier
+    pub aead_id: u16,
+}
+
+impl<'r> BinDecodable<'r> for HpkeSymmetricCipherSuite {
+    fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
+        Ok(Self {
+            kdf_id: decoder.read_u16()?.unverified(/*any KDF is valid*/),
+            aead_id: decoder.read_u16()?.unverified(/*any AEAD is valid*/),
+        })
+    }
+}
+
+impl BinEncodable for HpkeSymmetricCipherSuite {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        encoder.emit_u16(self.kdf_id)?;
+        encoder.emit_u16(self.aead_id)
+    }
+}
+
+/// An extension of an ECHConfig
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct EchConfigExtension {
+    /// The type of the extension
+    pub ext_type: u16,
+    /// The opaque data of the extension
+    pub data: Vec<u8>,
+}
+
+impl EchConfigExtension {
+    /// Returns true if the high bit of the type is set, a client that doesn't understand the
+    ///  extension must not use the config
+    pub fn is_mandatory(&self) -> bool {
+        self.ext_type & 0x8000 != 0
+    }
+}
+
+impl<'r> BinDecodable<'r> for EchConfigExtension {
+    fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
+        let ext_type = decoder.read_u16()?.unverified(/*any type is valid*/);
+        let data = read_u16_prefixed(decoder)?.to_vec();
+
+        Ok(Self { ext_type, data })
+    }
+}
+
+impl BinEncodable for EchConfigExtension {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        encoder.emit_u16(self.ext_type)?;
+        emit_u16_prefixed(encoder, |encoder| encoder.emit_vec(&self.data))
+    }
+}
+
+/// Reads an opaque field prefixed with its u16 length
+fn read_u16_prefixed<'r>(decoder: &mut BinDecoder<'r>) -> ProtoResult<&'r [u8]> {
+    let len = decoder.read_u16()?.unverified(/*bounded by read_slice*/) as usize;
+    Ok(decoder.read_slice(len)?.unverified(/*opaque*/))
+}
+
+/// Emits the output of `emit` prefixed with its u16 length
+fn emit_u16_prefixed(
+    encoder: &mut BinEncoder<'_>,
+    emit: impl FnOnce(&mut BinEncoder<'_>) -> ProtoResult<()>,
+) -> ProtoResult<()> {
+    let place = encoder.place::<u16>()?;
+    emit(encoder)?;
+
+    let len = u16::try_from(encoder.len_since_place(&place))
+        .map_err(|_| ProtoError::from("ECHConfig field exceeds 65535 bytes"))?;
+    place.replace(encoder, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The ECHConfigList of crypto.cloudflare.com
+    const CLOUDFLARE: &str = "AEX+DQBBtgAgACBMmGJQR02doup+5VPMjYpe5HQQ/bpntFCxDa8LT2PLAgAEAAEAAQASY2xvdWRmbGFyZS1lY2guY29tAAA=";
+
+    fn cloudflare() -> EchConfigList {
+        EchConfigList(data_encoding::BASE64.decode(CLOUDFLARE.as_bytes()).unwrap())
+    }
+
+    /// A list with a config of an older draft, and a config with extensions
+    fn multiple() -> EchConfigList {
+        let current = EchConfig::Current(EchConfigContents {
+            config_id: 7,
+            kem_id: 0x0010,
+            public_key: vec![0x04; 65],
+            cipher_suites: vec![
+                HpkeSymmetricCipherSuite {
+                    kdf_id: 0x0001,
+                    aead_id: 0x0001,
+                },
+                HpkeSymmetricCipherSuite {
+                    kdf_id: 0x0001,
+                    aead_id: 0x0003,
+                },
+            ],
+            maximum_name_length: 64,
+            public_name: "public.example.com".to_string(),
+            extensions: vec![
+                EchConfigExtension {
+                    ext_type: 0x0a0a,
+                    data: vec![],
+                },
+                EchConfigExtension {
+                    ext_type: 0x8a8a,
+                    data: vec![1, 2, 3],
+                },
+            ],
+        });
+        let old = EchConfig::Unknown {
+            version: 0xfe0a,
+            contents: vec![0xff; 12],
+        };
+
+        EchConfigList::from_configs(&[old, current]).unwrap()
+    }
+
+    #[test]
+    fn test_cloudflare() {
+        let list = cloudflare();
+        let configs = list.configs().unwrap();
+        assert_eq!(configs.len(), 1);
+
+        let contents = configs[0].contents().unwrap();
+        assert_eq!(configs[0].version(), ECH_VERSION);
+        assert_eq!(contents.config_id(), 0xb6);
+        assert_eq!(contents.kem_id(), 0x0020);
+        assert_eq!(contents.public_key().len(), 32);
+        assert_eq!(
+            contents.cipher_suites(),
+            &[HpkeSymmetricCipherSuite {
+                kdf_id: 0x0001,
+                aead_id: 0x0001,
+            }]
+        );
+        assert_eq!(contents.maximum_name_length(), 0);
+        assert_eq!(contents.public_name(), "cloudflare-ech.com");
+        assert!(contents.extensions().is_empty());
+
+        assert_eq!(EchConfigList::from_configs(&configs).unwrap(), list);
+    }
+
+    #[test]
+    fn test_unknown_versions_are_kept() {
+        let list = multiple();
+        let configs = list.configs().unwrap();
+        assert_eq!(configs.len(), 2);
+
+        assert_eq!(configs[0].version(), 0xfe0a);
+        assert!(configs[0].contents().is_none());
+
+        let contents = configs[1].contents().unwrap();
+        assert_eq!(contents.config_id(), 7);
+        assert_eq!(contents.cipher_suites().len(), 2);
+        assert_eq!(contents.extensions().len(), 2);
+        assert!(contents.has_mandatory_extensions());
+
+        assert_eq!(EchConfigList::from_configs(&configs).unwrap(), list);
+    }
+
+    #[test]
+    fn test_parse_configs() {
+        let configs = cloudflare().parse_configs().unwrap();
+        assert_eq!(configs, cloudflare().configs().unwrap());
+
+        let error = multiple().parse_configs().unwrap_err();
+        assert!(error.to_string().contains("0xfe0a"), "{error}");
+
+        EchConfigList(vec![]).parse_configs().unwrap_err();
+        EchConfigList(vec![0, 0]).parse_configs().unwrap_err();
+    }
+
+    #[test]
+    fn test_truncated() {
+        for list in [cloudflare(), multiple()] {
+            for len in 0..list.0.len() {
+                let truncated = EchConfigList(list.0[..len].to_vec());
+                assert!(truncated.configs().is_err(), "accepted {len} bytes");
+            }
+        }
+    }
+
+    #[test]
+    fn test_corrupted() {
+        let corrupt = |index: usize, value: u8| {
+            let mut list = cloudflare();
+            list.0[index] = value;
+            list.configs()
+        };
+
+        // the list length
+        corrupt(1, 0x44).unwrap_err();
+        // the config length, longer and shorter than the contents
+        corrupt(5, 0x42).unwrap_err();
+        corrupt(5, 0x40).unwrap_err();
+        // the cipher suites length is not a multiple of 4
+        corrupt(44, 0x03).unwrap_err();
+        // the public name length
+        corrupt(50, 0x13).unwrap_err();
+        corrupt(50, 0x00).unwrap_err();
+
+        // trailing bytes after the list
+        let mut list = cloudflare();
+        list.0.push(0);
+        list.configs().unwrap_err();
+
+        // an empty list
+        EchConfigList(vec![0, 0]).configs().unwrap_err();
+    }
+}
@@ -77,6 +77,7 @@ pub struct TLSA {
 /// ```
 #[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[non_exhaustive]
 pub enum CertUsage {
     /// ```text
     ///       0 -- Certificate usage 0 is used to specify a CA certificate, or
@@ -188,6 +189,7 @@ impl From<CertUsage> for u8 {
 /// ```
 #[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[non_exhaustive]
 pub enum Selector {
     /// Full certificate: the Certificate binary structure as defined in [RFC5280](https://tools.ietf.org/html/rfc5280)
     Full,
@@ -247,6 +249,7 @@ impl From<Selector> for u8 {
 /// ```
 #[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[non_exhaustive]
 pub enum Matching {
     /// Exact match on selected content
     Raw,
@@ -13,8 +13,10 @@ use std::slice::Iter;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    error::ProtoResult,
-    rr::{RData, RecordData, RecordDataDecodable, RecordType},
+    error::{ProtoErrorKind, ProtoResult},
+    rr::{
+        presentation::write_character_string, RData, RecordData, RecordDataDecodable, RecordType,
+    },
     serialize::binary::*,
 };
 
@@ -31,6 +33,9 @@ use crate::{
 /// TXT RRs are used to hold descriptive text.  The semantics of the text
 /// depends on the domain where it is found.
 /// ```
+///
+/// The character-strings are kept as they were created or received, emitting the record or
+///  writing it to a zone file preserves their segmentation.
 #[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct TXT {
@@ -46,7 +51,8 @@ impl TXT {
     ///
     /// # Return value
     ///
-    /// The new TXT record data.
+    /// The new TXT record data, strings longer than 255 bytes will fail to emit, see
+    ///  [`Self::try_new`] to check the limits on creation.
     pub fn new(txt_data: Vec<String>) -> Self {
         Self {
             txt_data: txt_data
@@ -66,7 +72,8 @@ impl TXT {
     ///
     /// # Return value
     ///
-    /// The new TXT record data.
+    /// The new TXT record data, strings longer than 255 bytes will fail to emit, see
+    ///  [`Self::try_from_bytes`] to check the limits on creation.
     pub fn from_bytes(txt_data: Vec<&[u8]>) -> Self {
         Self {
             txt_data: txt_data
@@ -77,6 +84,54 @@ impl TXT {
         }
     }
 
+    /// Creates a new TXT record data, each string becoming one character-string
+    ///
+    /// # Errors
+    ///
+    /// If a string is longer than 255 bytes, or the record data would exceed 65535 bytes.
+    pub fn try_new(txt_data: Vec<String>) -> ProtoResult<Self> {
+        let txt = Self::new(txt_data);
+        txt.check_limits()?;
+        Ok(txt)
+    }
+
+    /// Creates a new TXT record data from bytes, each slice becoming one character-string
+    ///
+    /// # Errors
+    ///
+    /// If a slice is longer than 255 bytes, or the record data would exceed 65535 bytes.
+    pub fn try_from_bytes(txt_data: Vec<&[u8]>) -> ProtoResult<Self> {
+        let txt = Self::from_bytes(txt_data);
+        txt.check_limits()?;
+        Ok(txt)
+    }
+
+    /// Checks the length of the character-strings and of the whole record data
+    fn check_limits(&self) -> ProtoResult<()> {
+        let mut rdata_len = 0_usize;
+        for string in self.txt_data.iter() {
+            if string.len() > MAX_CHARACTER_STRING_LEN {
+                return Err(ProtoErrorKind::CharacterDataTooLong {
+                    max: MAX_CHARACTER_STRING_LEN,
+                    len: string.len(),
+                }
+                .into());
+            }
+
+            // the length octet and the string
+            rdata_len += 1 + string.len();
+        }
+
+        if rdata_len > MAX_RDATA_LEN {
+            return Err(ProtoErrorKind::Msg(format!(
+                "TXT record data of {rdata_len} bytes exceeds the maximum of {MAX_RDATA_LEN}"
+            ))
+            .into());
+        }
+
+        Ok(())
+    }
+
     /// ```text
     /// TXT-DATA        One or more <character-string>s.
     /// ```
@@ -88,8 +143,19 @@ impl TXT {
     pub fn iter(&self) -> Iter<'_, Box<[u8]>> {
         self.txt_data.iter()
     }
+
+    /// Returns the character-strings joined without separator, e.g. for DKIM keys split across strings
+    pub fn concatenated(&self) -> Vec<u8> {
+        self.txt_data.concat()
+    }
 }
 
+/// The maximum length of a character-string
+const MAX_CHARACTER_STRING_LEN: usize = 255;
+
+/// The maximum length of the record data
+const MAX_RDATA_LEN: usize = u16::MAX as usize;
+
 impl BinEncodable for TXT {
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
         for s in self.txt_data() {
@@ -112,6 +178,17 @@ impl<'r> RecordDataDecodable<'r> for TXT {
             let string = decoder.read_character_data()?.unverified(/*any data should be validate in TXT usage*/);
             strings.push(string.to_vec().into_boxed_slice());
         }
+
+        // the last character-string must not extend past the record data
+        let read = data_len - decoder.len();
+        if read != rdata_length {
+            return Err(ProtoErrorKind::IncorrectRDataLengthRead {
+                read,
+                len: rdata_length,
+            }
+            .into());
+        }
+
         Ok(Self {
             txt_data: strings.into_boxed_slice(),
         })
@@ -143,14 +220,16 @@ impl RecordData for TXT {
 }
 
 impl fmt::Display for TXT {
-    /// Format a [TXT] with lossy conversion of invalid utf8.
+    /// Format a [TXT] in the presentation format of zone files, each character-string quoted.
     ///
-    /// ## Case of invalid utf8
+    /// The segmentation of the strings is preserved, parsing the output in a zone file results in
+    ///  the same character-strings. `"` and `\` are escaped with `\`, control characters
+    ///  as `\DDD`.
     ///
-    /// Invalid utf8 will be converted to:
-    /// `U+FFFD REPLACEMENT CHARACTER`, which looks like this: �
+    /// ## Case of invalid utf8
     ///
-    /// Same behaviour as `alloc::string::String::from_utf8_lossy`.
+    /// The octets which are not valid utf8 are written as `\DDD` like the control characters, the
+    ///  record data is displayed without loss.
     /// ```rust
     /// # use hickory_proto::rr::rdata::TXT;
     /// let first_bytes = b"Invalid utf8 <\xF0\x90\x80>.";
@@ -160,14 +239,18 @@ impl fmt::Display for TXT {
     ///
     /// let tested = format!("{}", txt);
     /// assert_eq!(
-    ///     tested.as_bytes(),
-    ///     b"Invalid utf8 <\xEF\xBF\xBD>. Valid utf8 <\xF0\x9F\xA4\xA3>",
-    ///     "Utf8 lossy conversion error! Mismatch between input and expected"
+    ///     tested,
+    ///     r#""Invalid utf8 <\240\144\128>." " Valid utf8 <🤣>""#,
+    ///     "Mismatch between input and expected"
     /// );
     /// ```
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        for txt in self.txt_data.iter() {
-            f.write_str(&String::from_utf8_lossy(txt))?;
+        for (i, txt) in self.txt_data.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+
+            write_character_string(f, txt)?;
         }
 
         Ok(())
@@ -178,7 +261,41 @@ impl fmt::Display for TXT {
 mod tests {
     #![allow(clippy::dbg_macro, clippy::print_stdout)]
 
+    use proptest::{collection::vec, prelude::*};
+
     use super::*;
+    #[cfg(feature = "text-parsing")]
+    use crate::{
+        rr::{Name, Record},
+        serialize::txt::Parser,
+    };
+
+    fn emit(rdata: &TXT) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        rdata.emit(&mut encoder).expect("failed to emit");
+        bytes
+    }
+
+    fn read(bytes: &[u8]) -> ProtoResult<TXT> {
+        let mut decoder = BinDecoder::new(bytes);
+        TXT::read_data(&mut decoder, Restrict::new(bytes.len() as u16))
+    }
+
+    /// Writes the TXT record as a zone file and parses it back
+    #[cfg(feature = "text-parsing")]
+    fn zone_round_trip(rdata: TXT) -> TXT {
+        let origin = Name::from_ascii("example.com.").unwrap();
+        let record = Record::from_rdata(origin.clone(), 3600, rdata);
+        let zone = format!("{record}\n");
+
+        let (_, records) = Parser::new(zone.as_str(), None, Some(origin))
+            .parse()
+            .unwrap_or_else(|e| panic!("failed to parse {zone:?}: {e}"));
+        let record_set = records.into_values().next().expect("no records parsed");
+        let record = record_set.records_without_rrsigs().next().unwrap();
+        TXT::try_borrow(record.data()).unwrap().clone()
+    }
 
     #[test]
     fn test() {
@@ -214,4 +331,151 @@ mod tests {
         let read_rdata = TXT::read_data(&mut decoder, restrict).expect("Decoding error");
         assert_eq!(rdata, read_rdata);
     }
+
+    #[test]
+    fn test_limits() {
+        assert!(TXT::try_new(vec!["a".repeat(255)]).is_ok());
+        assert!(matches!(
+            TXT::try_new(vec!["a".repeat(256)]).unwrap_err().kind(),
+            ProtoErrorKind::CharacterDataTooLong { max: 255, len: 256 }
+        ));
+
+        // 256 strings of 255 bytes, each with a length octet, are 65536 bytes
+        let long = [0_u8; 255];
+        assert!(TXT::try_from_bytes(vec![&long[..]; 255]).is_ok());
+        assert!(TXT::try_from_bytes(vec![&long[..]; 256]).is_err());
+        assert!(TXT::try_from_bytes(vec![&long[..254]; 256]).is_ok());
+    }
+
+    #[test]
+    fn test_read_past_rdata() {
+        // the string claims 5 bytes, the record data only has 3
+        let bytes = [5, b'a', b'b', b'c', b'd', b'e'];
+        let mut decoder = BinDecoder::new(&bytes);
+        assert!(TXT::read_data(&mut decoder, Restrict::new(3)).is_err());
+    }
+
+    #[test]
+    fn test_concatenated() {
+        let rdata = TXT::from_bytes(vec![b"v=DKIM1; k=rsa; ", b"", b"p=MIGf"]);
+        assert_eq!(rdata.concatenated(), b"v=DKIM1; k=rsa; p=MIGf");
+        assert_eq!(rdata.txt_data().len(), 3);
+    }
+
+    #[test]
+    fn test_display() {
+        let rdata = TXT::from_bytes(vec![b"a \"quoted\" \\ b", b"", b"line\nbreak"]);
+        assert_eq!(
+            rdata.to_string(),
+            r#""a \"quoted\" \\ b" "" "line\010break""#
+        );
+
+        #[cfg(feature = "text-parsing")]
+        assert_eq!(zone_round_trip(rdata.clone()), rdata);
+    }
+
+    #[test]
+    fn test_display_binary() {
+        let rdata = TXT::from_bytes(vec![b"nul\0 \"q\" \\", b"\xff\xfe high", b"a\r\nb"]);
+        let text = r#""nul\000 \"q\" \\" "\255\254 high" "a\013\010b""#;
+        assert_eq!(rdata.to_string(), text);
+
+        #[cfg(feature = "text-parsing")]
+        {
+            let parsed = zone_round_trip(rdata.clone());
+            assert_eq!(parsed, rdata);
+            assert_eq!(parsed.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn test_zero_length_strings() {
+        // the largest number of strings fitting in a record, each only a length octet
+        let rdata = TXT::from_bytes(vec![b"".as_slice(); u16::MAX as usize]);
+
+        let bytes = emit(&rdata);
+        assert_eq!(bytes, vec![0; u16::MAX as usize]);
+        assert_eq!(read(&bytes).unwrap(), rdata);
+
+        #[cfg(feature = "text-parsing")]
+        assert_eq!(zone_round_trip(rdata.clone()), rdata);
+    }
+
+    #[cfg(feature = "text-parsing")]
+    #[test]
+    fn test_zone_list() {
+        let origin = Name::from_ascii("example.com.").unwrap();
+        let zone = "@ 3600 IN TXT ( \"v=DKIM1; k=rsa; \" ; the key\n    \"p=MIGf\" )\n";
+
+        let (_, records) = Parser::new(zone, None, Some(origin)).parse().unwrap();
+        let record_set = records.into_values().next().unwrap();
+        let record = record_set.records_without_rrsigs().next().unwrap();
+        let rdata = TXT::try_borrow(record.data()).unwrap();
+
+        assert_eq!(
+            rdata,
+            &TXT::new(vec!["v=DKIM1; k=rsa; ".to_string(), "p=MIGf".to_string()])
+        );
+    }
+
+    #[cfg(feature = "text-parsing")]
+    #[test]
+    fn test_zone_string_too_long() {
+        let origin = Name::from_ascii("example.com.").unwrap();
+        let zone = format!("@ 3600 IN TXT \"{}\"\n", "a".repeat(256));
+
+        assert!(Parser::new(zone, None, Some(origin)).parse().is_err());
+    }
+
+    /// Random bytes split into random character-strings
+    fn segments() -> impl Strategy<Value = Vec<Vec<u8>>> {
+        vec(vec(any::<u8>(), 0..=255), 0..64)
+    }
+
+    /// Random printable text split into random character-strings
+    #[cfg(feature = "text-parsing")]
+    fn text_segments() -> impl Strategy<Value = Vec<String>> {
+        vec("[ -~\t\n\u{a0}-\u{ff}\u{1F600}-\u{1F64F}]{0,60}", 0..64)
+    }
+
+    proptest! {
+        #[test]
+        fn test_wire_round_trip(segments in segments()) {
+            let rdata = TXT::try_from_bytes(segments.iter().map(Vec::as_slice).collect()).unwrap();
+
+            let bytes = emit(&rdata);
+            let expected = segments.iter().fold(Vec::new(), |mut bytes, segment| {
+                bytes.push(segment.len() as u8);
+                bytes.extend_from_slice(segment);
+                bytes
+            });
+            prop_assert_eq!(&bytes, &expected);
+
+            let read_rdata = read(&bytes).unwrap();
+            prop_assert_eq!(&read_rdata, &rdata);
+            prop_assert_eq!(emit(&read_rdata), bytes);
+            prop_assert_eq!(read_rdata.concatenated(), segments.concat());
+        }
+
+        #[cfg(feature = "text-parsing")]
+        #[test]
+        fn test_zone_round_trip(segments in text_segments()) {
+            // at least one string, a TXT record without data can not be written to a zone file
+            prop_assume!(!segments.is_empty());
+            let rdata = TXT::try_new(segments).unwrap();
+
+            prop_assert_eq!(zone_round_trip(rdata.clone()), rdata);
+        }
+
+        #[cfg(feature = "text-parsing")]
+        #[test]
+        fn test_zone_round_trip_binary(segments in segments()) {
+            prop_assume!(!segments.is_empty());
+            let rdata = TXT::try_from_bytes(segments.iter().map(Vec::as_slice).collect()).unwrap();
+
+            let parsed = zone_round_trip(rdata.clone());
+            prop_assert_eq!(parsed.to_string(), rdata.to_string());
+            prop_assert_eq!(parsed, rdata);
+        }
+    }
 }
@@ -22,8 +22,8 @@ use crate::{
     error::{ProtoError, ProtoErrorKind, ProtoResult},
     rr::{
         rdata::{
-            A, AAAA, ANAME, CAA, CNAME, CSYNC, HINFO, HTTPS, MX, NAPTR, NS, NULL, OPENPGPKEY, OPT,
-            PTR, SOA, SRV, SSHFP, SVCB, TLSA, TXT,
+            A, AAAA, ANAME, APL, CAA, CNAME, CSYNC, HINFO, HTTPS, LOC, MX, NAPTR, NS, NULL,
+            OPENPGPKEY, OPT, PTR, SOA, SRV, SSHFP, SVCB, TLSA, TXT,
         },
         record_type::RecordType,
         RecordData, RecordDataDecodable,
@@ -32,7 +32,9 @@ use crate::{
 };
 
 #[cfg(feature = "dnssec")]
-use super::dnssec::rdata::DNSSECRData;
+use super::dnssec::{self, rdata::DNSSECRData};
+#[cfg(feature = "unstable-deleg")]
+use super::rdata::DELEG;
 
 /// Record data enum variants for all valid DNS data types.
 ///
@@ -56,7 +58,7 @@ use super::dnssec::rdata::DNSSECRData;
 /// length (including the length octet).
 /// ```
 #[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
-#[derive(Debug, EnumAsInner, PartialEq, Clone, Eq)]
+#[derive(Debug, EnumAsInner, PartialEq, Clone, Eq, Hash)]
 #[non_exhaustive]
 pub enum RData {
     /// ```text
@@ -109,6 +111,25 @@ pub enum RData {
     /// ```
     ANAME(ANAME),
 
+    /// ```text
+    /// -- RFC 3123 -- A DNS RR Type for Lists of Address Prefixes   October 2001
+    ///
+    /// 4. APL RDATA format
+    ///
+    ///    The RDATA section consists of zero or more items (<apitem>) of the
+    ///    form
+    ///
+    ///       +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///       |                          ADDRESSFAMILY                |
+    ///       +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///       |             PREFIX    | N|         AFDLENGTH  |
+    ///       +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///       /                          AFDPART                      /
+    ///       |                                                       |
+    ///       +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    /// ```
+    APL(APL),
+
     /// ```text
     /// -- RFC 6844          Certification Authority Authorization     January 2013
     ///
@@ -240,6 +261,45 @@ pub enum RData {
     /// ```
     HTTPS(HTTPS),
 
+    /// [draft-ietf-dnsop-deleg](https://datatracker.ietf.org/doc/draft-ietf-dnsop-deleg/), experimental
+    ///
+    /// An extensible delegation, served by the parent zone at the delegation point. The wire and
+    ///  presentation format are those of SVCB.
+    ///
+    /// ```text
+    /// Name TTL IN DELEG Priority TargetName Params
+    /// ```
+    #[cfg(feature = "unstable-deleg")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "unstable-deleg")))]
+    DELEG(DELEG),
+
+    /// ```text
+    /// -- RFC 1876 -- Location Information in the DNS             January 1996
+    ///
+    /// 2. RDATA Format
+    ///
+    ///        MSB                                           LSB
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///       0|        VERSION        |         SIZE          |
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///       2|       HORIZ PRE       |       VERT PRE        |
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///       4|                   LATITUDE                    |
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///       6|                   LATITUDE                    |
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///       8|                   LONGITUDE                   |
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///      10|                   LONGITUDE                   |
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///      12|                   ALTITUDE                    |
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///      14|                   ALTITUDE                    |
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///    (octet)
+    /// ```
+    LOC(LOC),
+
     /// ```text
     /// 3.3.9. MX RDATA format
     ///
@@ -717,11 +777,15 @@ impl RData {
             Self::A(..) => RecordType::A,
             Self::AAAA(..) => RecordType::AAAA,
             Self::ANAME(..) => RecordType::ANAME,
+            Self::APL(..) => RecordType::APL,
             Self::CAA(..) => RecordType::CAA,
             Self::CNAME(..) => RecordType::CNAME,
             Self::CSYNC(..) => RecordType::CSYNC,
             Self::HINFO(..) => RecordType::HINFO,
             Self::HTTPS(..) => RecordType::HTTPS,
+            #[cfg(feature = "unstable-deleg")]
+            Self::DELEG(..) => RecordType::DELEG,
+            Self::LOC(..) => RecordType::LOC,
             Self::MX(..) => RecordType::MX,
             Self::NAPTR(..) => RecordType::NAPTR,
             Self::NS(..) => RecordType::NS,
@@ -773,6 +837,10 @@ impl RData {
                 trace!("reading ANAME");
                 ANAME::read(decoder).map(Self::ANAME)
             }
+            RecordType::APL => {
+                trace!("reading APL");
+                APL::read_data(decoder, length).map(Self::APL)
+            }
             rt @ RecordType::ANY | rt @ RecordType::AXFR | rt @ RecordType::IXFR => {
                 return Err(ProtoErrorKind::UnknownRecordTypeValue(rt.into()).into());
             }
@@ -796,6 +864,11 @@ impl RData {
                 trace!("reading HTTPS");
                 HTTPS::read_data(decoder, length).map(Self::HTTPS)
             }
+            #[cfg(feature = "unstable-deleg")]
+            RecordType::DELEG => {
+                trace!("reading DELEG");
+                DELEG::read_data(decoder, length).map(Self::DELEG)
+            }
             RecordType::ZERO => {
                 trace!("reading EMPTY");
                 // we should never get here, since ZERO should be 0 length, and None in the Record.
@@ -803,6 +876,10 @@ impl RData {
                 #[allow(deprecated)]
                 Ok(Self::ZERO)
             }
+            RecordType::LOC => {
+                trace!("reading LOC");
+                LOC::read_data(decoder, length).map(Self::LOC)
+            }
             RecordType::MX => {
                 trace!("reading MX");
                 MX::read_data(decoder, length).map(Self::MX)
@@ -955,6 +1032,7 @@ impl BinEncodable for RData {
             Self::A(ref address) => address.emit(encoder),
             Self::AAAA(ref address) => address.emit(encoder),
             Self::ANAME(ref name) => encoder.with_canonical_names(|encoder| name.emit(encoder)),
+            Self::APL(ref apl) => apl.emit(encoder),
             Self::CAA(ref caa) => encoder.with_canonical_names(|encoder| caa.emit(encoder)),
             Self::CNAME(ref cname) => cname.emit(encoder),
             Self::NS(ref ns) => ns.emit(encoder),
@@ -962,6 +1040,9 @@ impl BinEncodable for RData {
             Self::CSYNC(ref csync) => csync.emit(encoder),
             Self::HINFO(ref hinfo) => hinfo.emit(encoder),
             Self::HTTPS(ref https) => https.emit(encoder),
+            #[cfg(feature = "unstable-deleg")]
+            Self::DELEG(ref deleg) => deleg.emit(encoder),
+            Self::LOC(ref loc) => loc.emit(encoder),
             Self::ZERO => Ok(()),
             Self::MX(ref mx) => mx.emit(encoder),
             Self::NAPTR(ref naptr) => encoder.with_canonical_names(|encoder| naptr.emit(encoder)),
@@ -982,6 +1063,15 @@ impl BinEncodable for RData {
             Self::Update0(_) => Ok(()),
         }
     }
+
+    fn encoded_size(&self) -> usize {
+        match *self {
+            Self::A(ref a) => a.encoded_size(),
+            Self::AAAA(ref aaaa) => aaaa.encoded_size(),
+            Self::ZERO | Self::Update0(_) => 0,
+            _ => BinEncodable::to_bytes(self).map_or(0, |bytes| bytes.len()),
+        }
+    }
 }
 
 impl RecordData for RData {
@@ -1004,8 +1094,74 @@ impl RecordData for RData {
     fn is_update(&self) -> bool {
         matches!(self, RData::Update0(_))
     }
+
+    /// The data of `RData::Unknown` must decode as its type code, e.g. as a known type which
+    ///  the caller didn't construct with its typed data
+    fn validate(&self) -> ProtoResult<()> {
+        let (record_type, rdata) = match self {
+            Self::Unknown { code, rdata } => (*code, rdata),
+            #[cfg(feature = "dnssec")]
+            Self::DNSSEC(DNSSECRData::Unknown { code, rdata }) => (RecordType::from(*code), rdata),
+            _ => return Ok(()),
+        };
+
+        let bytes = rdata.anything();
+        let length = u16::try_from(bytes.len())
+            .map_err(|_| ProtoError::from(format!("{record_type} data is too long")))?;
+        let mut decoder = BinDecoder::new(bytes);
+        Self::read(&mut decoder, record_type, Restrict::new(length))
+            .map(drop)
+            .map_err(|e| ProtoErrorKind::Msg(format!("invalid {record_type} data: {e}")).into())
+    }
 }
 
+/// Implements the conversions between `RData` and the record data types it contains
+///
+/// `TryFrom<RData>` returns the `RData` as the error if it is of another type, see
+/// `RecordData::try_from_rdata`.
+macro_rules! rdata_conversions {
+    ($($rdata:ty),+ $(,)?) => {
+        $(
+            impl From<$rdata> for RData {
+                fn from(rdata: $rdata) -> Self {
+                    rdata.into_rdata()
+                }
+            }
+
+            impl TryFrom<RData> for $rdata {
+                type Error = RData;
+
+                fn try_from(rdata: RData) -> Result<Self, Self::Error> {
+                    Self::try_from_rdata(rdata)
+                }
+            }
+        )+
+    };
+}
+
+rdata_conversions!(
+    A, AAAA, ANAME, APL, CAA, CNAME, CSYNC, HINFO, HTTPS, LOC, MX, NAPTR, NS, NULL, OPENPGPKEY,
+    OPT, PTR, SOA, SRV, SSHFP, SVCB, TLSA, TXT,
+);
+
+#[cfg(feature = "unstable-deleg")]
+rdata_conversions!(DELEG);
+
+#[cfg(feature = "dnssec")]
+rdata_conversions!(
+    dnssec::rdata::CDNSKEY,
+    dnssec::rdata::CDS,
+    dnssec::rdata::DNSKEY,
+    dnssec::rdata::DS,
+    dnssec::rdata::KEY,
+    dnssec::rdata::NSEC,
+    dnssec::rdata::NSEC3,
+    dnssec::rdata::NSEC3PARAM,
+    dnssec::rdata::RRSIG,
+    dnssec::rdata::SIG,
+    dnssec::rdata::TSIG,
+);
+
 impl fmt::Display for RData {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         fn w<D: fmt::Display>(f: &mut fmt::Formatter<'_>, rdata: D) -> Result<(), fmt::Error> {
@@ -1016,6 +1172,7 @@ impl fmt::Display for RData {
             Self::A(address) => w(f, address),
             Self::AAAA(ref address) => w(f, address),
             Self::ANAME(ref name) => w(f, name),
+            Self::APL(ref apl) => w(f, apl),
             Self::CAA(ref caa) => w(f, caa),
             // to_lowercase for rfc4034 and rfc6840
             Self::CNAME(ref cname) => w(f, cname),
@@ -1024,6 +1181,9 @@ impl fmt::Display for RData {
             Self::CSYNC(ref csync) => w(f, csync),
             Self::HINFO(ref hinfo) => w(f, hinfo),
             Self::HTTPS(ref https) => w(f, https),
+            #[cfg(feature = "unstable-deleg")]
+            Self::DELEG(ref deleg) => w(f, deleg),
+            Self::LOC(ref loc) => w(f, loc),
             Self::ZERO => Ok(()),
             // to_lowercase for rfc4034 and rfc6840
             Self::MX(ref mx) => w(f, mx),
@@ -1263,11 +1423,15 @@ mod tests {
             RData::A(..) => RecordType::A,
             RData::AAAA(..) => RecordType::AAAA,
             RData::ANAME(..) => RecordType::ANAME,
+            RData::APL(..) => RecordType::APL,
             RData::CAA(..) => RecordType::CAA,
             RData::CNAME(..) => RecordType::CNAME,
             RData::CSYNC(..) => RecordType::CSYNC,
             RData::HINFO(..) => RecordType::HINFO,
             RData::HTTPS(..) => RecordType::HTTPS,
+            #[cfg(feature = "unstable-deleg")]
+            RData::DELEG(..) => RecordType::DELEG,
+            RData::LOC(..) => RecordType::LOC,
             RData::MX(..) => RecordType::MX,
             RData::NAPTR(..) => RecordType::NAPTR,
             RData::NS(..) => RecordType::NS,
@@ -1293,4 +1457,23 @@ mod tests {
     fn test_write_to() {
         test_emit_data_set(get_data(), |e, d| d.emit(e));
     }
+
+    #[test]
+    fn test_encoded_size() {
+        for (rdata, bytes) in get_data() {
+            assert_eq!(rdata.encoded_size(), bytes.len(), "{rdata:?}");
+        }
+    }
+
+    #[test]
+    fn test_try_from() {
+        let rdata = RData::from(A::new(127, 0, 0, 1));
+        assert_eq!(rdata, RData::A(A::new(127, 0, 0, 1)));
+
+        let a: A = rdata.clone().try_into().unwrap();
+        assert_eq!(a, A::new(127, 0, 0, 1));
+
+        let err = AAAA::try_from(rdata.clone()).unwrap_err();
+        assert_eq!(err, rdata);
+    }
 }
@@ -16,6 +16,8 @@ use std::str::FromStr;
 use serde::{Deserialize, Serialize};
 
 use crate::error::*;
+#[cfg(feature = "unstable-deleg")]
+use crate::rr::rdata::deleg;
 use crate::serialize::binary::*;
 
 // TODO: adopt proper restrictions on usage: https://tools.ietf.org/html/rfc6895 section 3.1
@@ -39,7 +41,8 @@ pub enum RecordType {
     //  AFSDB,      //	18	RFC 1183	AFS database record
     /// [RFC 1035](https://tools.ietf.org/html/rfc1035) All cached records, aka ANY
     ANY,
-    //  APL,        //	42	RFC 3123	Address Prefix List
+    /// [RFC 3123](https://tools.ietf.org/html/rfc3123) Address Prefix List
+    APL,
     /// [RFC 1035](https://tools.ietf.org/html/rfc1035) Authoritative Zone Transfer
     AXFR,
     /// [RFC 6844](https://tools.ietf.org/html/rfc6844) Certification Authority Authorization
@@ -56,6 +59,10 @@ pub enum RecordType {
     //  DNAME,      // 39 RFC 2672 Delegation Name
     /// [RFC 7477](https://tools.ietf.org/html/rfc4034) Child-to-parent synchronization record
     CSYNC,
+    /// [draft-ietf-dnsop-deleg](https://datatracker.ietf.org/doc/draft-ietf-dnsop-deleg/) Extensible delegation record, experimental
+    #[cfg(feature = "unstable-deleg")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "unstable-deleg")))]
+    DELEG,
     /// [RFC 4034](https://tools.ietf.org/html/rfc4034) DNS Key record: RSASHA256 and RSASHA512, RFC5702
     DNSKEY,
     /// [RFC 4034](https://tools.ietf.org/html/rfc4034) Delegation signer: RSASHA256 and RSASHA512, RFC5702
@@ -71,7 +78,8 @@ pub enum RecordType {
     //  KX,         // 36 RFC 2230 Key eXchanger record
     /// [RFC 2535](https://tools.ietf.org/html/rfc2535) and [RFC 2930](https://tools.ietf.org/html/rfc2930) Key record
     KEY,
-    //  LOC,        // 29 RFC 1876 Location record
+    /// [RFC 1876](https://tools.ietf.org/html/rfc1876) Location record
+    LOC,
     /// [RFC 1035](https://tools.ietf.org/html/rfc1035) Mail exchange record
     MX,
     /// [RFC 3403](https://tools.ietf.org/html/rfc3403) Naming Authority Pointer
@@ -202,17 +210,21 @@ impl FromStr for RecordType {
             "A" => Ok(Self::A),
             "AAAA" => Ok(Self::AAAA),
             "ANAME" => Ok(Self::ANAME),
+            "APL" => Ok(Self::APL),
             "AXFR" => Ok(Self::AXFR),
             "CAA" => Ok(Self::CAA),
             "CDNSKEY" => Ok(Self::CDNSKEY),
             "CDS" => Ok(Self::CDS),
             "CNAME" => Ok(Self::CNAME),
             "CSYNC" => Ok(Self::CSYNC),
+            #[cfg(feature = "unstable-deleg")]
+            "DELEG" => Ok(Self::DELEG),
             "DNSKEY" => Ok(Self::DNSKEY),
             "DS" => Ok(Self::DS),
             "HINFO" => Ok(Self::HINFO),
             "HTTPS" => Ok(Self::HTTPS),
             "KEY" => Ok(Self::KEY),
+            "LOC" => Ok(Self::LOC),
             "MX" => Ok(Self::MX),
             "NAPTR" => Ok(Self::NAPTR),
             "NSEC" => Ok(Self::NSEC),
@@ -248,11 +260,15 @@ impl From<u16> for RecordType {
     /// ```
     fn from(value: u16) -> Self {
         match value {
+            // checked first, the number is configurable until one is assigned
+            #[cfg(feature = "unstable-deleg")]
+            value if value == deleg::deleg_type_code() => Self::DELEG,
             1 => Self::A,
             28 => Self::AAAA,
             // TODO: wrong value here, see https://github.com/hickory-dns/hickory-dns/issues/723
             65305 => Self::ANAME,
             255 => Self::ANY,
+            42 => Self::APL,
             251 => Self::IXFR,
             252 => Self::AXFR,
             257 => Self::CAA,
@@ -265,6 +281,7 @@ impl From<u16> for RecordType {
             13 => Self::HINFO,
             65 => Self::HTTPS,
             25 => Self::KEY,
+            29 => Self::LOC,
             15 => Self::MX,
             35 => Self::NAPTR,
             2 => Self::NS,
@@ -295,6 +312,10 @@ impl BinEncodable for RecordType {
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
         encoder.emit_u16((*self).into())
     }
+
+    fn encoded_size(&self) -> usize {
+        2
+    }
 }
 
 impl<'r> BinDecodable<'r> for RecordType {
@@ -328,18 +349,22 @@ impl From<RecordType> for &'static str {
             RecordType::AAAA => "AAAA",
             RecordType::ANAME => "ANAME",
             RecordType::ANY => "ANY",
+            RecordType::APL => "APL",
             RecordType::AXFR => "AXFR",
             RecordType::CAA => "CAA",
             RecordType::CDNSKEY => "CDNSKEY",
             RecordType::CDS => "CDS",
             RecordType::CNAME => "CNAME",
             RecordType::CSYNC => "CSYNC",
+            #[cfg(feature = "unstable-deleg")]
+            RecordType::DELEG => "DELEG",
             RecordType::DNSKEY => "DNSKEY",
             RecordType::DS => "DS",
             RecordType::HINFO => "HINFO",
             RecordType::HTTPS => "HTTPS",
             RecordType::KEY => "KEY",
             RecordType::IXFR => "IXFR",
+            RecordType::LOC => "LOC",
             RecordType::MX => "MX",
             RecordType::NAPTR => "NAPTR",
             RecordType::NS => "NS",
@@ -381,18 +406,22 @@ impl From<RecordType> for u16 {
             // TODO: wrong value here, see https://github.com/hickory-dns/hickory-dns/issues/723
             RecordType::ANAME => 65305,
             RecordType::ANY => 255,
+            RecordType::APL => 42,
             RecordType::AXFR => 252,
             RecordType::CAA => 257,
             RecordType::CDNSKEY => 60,
             RecordType::CDS => 59,
             RecordType::CNAME => 5,
             RecordType::CSYNC => 62,
+            #[cfg(feature = "unstable-deleg")]
+            RecordType::DELEG => deleg::deleg_type_code(),
             RecordType::DNSKEY => 48,
             RecordType::DS => 43,
             RecordType::HINFO => 13,
             RecordType::HTTPS => 65,
             RecordType::KEY => 25,
             RecordType::IXFR => 251,
+            RecordType::LOC => 29,
             RecordType::MX => 15,
             RecordType::NAPTR => 35,
             RecordType::NS => 2,
@@ -497,10 +526,12 @@ mod tests {
             "A",
             "AAAA",
             "ANAME",
+            "APL",
             "CAA",
             "CNAME",
             "CSYNC",
             "HINFO",
+            "LOC",
             "NULL",
             "MX",
             "NAPTR",
@@ -7,7 +7,13 @@
 
 //! resource record implementation
 
-use std::{cmp::Ordering, convert::TryFrom, fmt};
+use std::{
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt,
+    hash::{Hash, Hasher},
+    time::{Duration, Instant},
+};
 
 #[cfg(feature = "serde-config")]
 use serde::{Deserialize, Serialize};
@@ -82,6 +88,8 @@ pub struct Record<R: RecordData = RData> {
     mdns_cache_flush: bool,
     #[cfg(feature = "dnssec")]
     proof: Proof,
+    #[cfg_attr(feature = "serde-config", serde(skip))]
+    expires_at: Option<Instant>,
 }
 
 impl Record {
@@ -96,6 +104,7 @@ impl Record {
             mdns_cache_flush: false,
             #[cfg(feature = "dnssec")]
             proof: Proof::default(),
+            expires_at: None,
         }
     }
 }
@@ -112,6 +121,7 @@ impl Record {
             mdns_cache_flush: false,
             #[cfg(feature = "dnssec")]
             proof: Proof::default(),
+            expires_at: None,
         }
     }
 
@@ -122,6 +132,16 @@ impl Record {
     {
         RecordRef::try_from(self).ok()
     }
+
+    /// Encodes the record in canonical form, the input of DNSSEC signatures and digests
+    ///
+    /// The names are uncompressed, the owner name is lowercase and so are the names in the RDATA
+    ///  of the types listed in [RFC 4034 section 6.2](https://tools.ietf.org/html/rfc4034#section-6.2),
+    ///  see [`canonical::lowercases_rdata_names`](crate::rr::canonical::lowercases_rdata_names).
+    ///  The TTL is the TTL of the record.
+    pub fn canonical_form(&self) -> ProtoResult<Vec<u8>> {
+        crate::rr::canonical::record_to_bytes(self)
+    }
 }
 
 impl<R: RecordData> Record<R> {
@@ -132,7 +152,18 @@ impl<R: RecordData> Record<R> {
     /// * `name` - name of the resource records
     /// * `ttl` - time-to-live is the amount of time this record should be cached before refreshing
     /// * `rdata` - record data to associate with the Record
+    ///
+    /// The record type is always the one of `rdata`, there is no way to set it separately. The
+    ///  typed form only accepts record data of its type:
+    ///
+    /// ```compile_fail
+    /// use hickory_proto::rr::{rdata::{A, TXT}, Name, Record};
+    ///
+    /// let record: Record<A> = Record::from_rdata(Name::root(), 3600, TXT::new(vec![]));
+    /// ```
+    #[track_caller]
     pub fn from_rdata(name: Name, ttl: u32, rdata: R) -> Self {
+        debug_assert_valid(&rdata);
         Self {
             name_labels: name,
             dns_class: DNSClass::IN,
@@ -142,6 +173,7 @@ impl<R: RecordData> Record<R> {
             mdns_cache_flush: false,
             #[cfg(feature = "dnssec")]
             proof: Proof::default(),
+            expires_at: None,
         }
     }
 
@@ -157,6 +189,7 @@ impl<R: RecordData> Record<R> {
             mdns_cache_flush,
             #[cfg(feature = "dnssec")]
             proof,
+            expires_at,
         } = record;
 
         match R::try_from_rdata(rdata) {
@@ -169,6 +202,7 @@ impl<R: RecordData> Record<R> {
                 mdns_cache_flush,
                 #[cfg(feature = "dnssec")]
                 proof,
+                expires_at,
             }),
             Err(rdata) => Err(Record {
                 name_labels,
@@ -179,6 +213,7 @@ impl<R: RecordData> Record<R> {
                 mdns_cache_flush,
                 #[cfg(feature = "dnssec")]
                 proof,
+                expires_at,
             }),
         }
     }
@@ -194,6 +229,7 @@ impl<R: RecordData> Record<R> {
             mdns_cache_flush,
             #[cfg(feature = "dnssec")]
             proof,
+            expires_at,
         } = self;
 
         let rdata: RData = RecordData::into_rdata(rdata);
@@ -207,6 +243,7 @@ impl<R: RecordData> Record<R> {
             mdns_cache_flush,
             #[cfg(feature = "dnssec")]
             proof,
+            expires_at,
         }
     }
 
@@ -246,8 +283,11 @@ impl<R: RecordData> Record<R> {
     ///                 For example, the if the TYPE is A and the CLASS is IN,
     ///                 the RDATA field is a 4 octet ARPA Internet address.
     /// ```
+    ///
+    /// The record type changes with the data.
     #[track_caller]
     pub fn set_data(&mut self, rdata: R) -> &mut Self {
+        debug_assert_valid(&rdata);
         self.rdata = rdata;
         self
     }
@@ -269,6 +309,41 @@ impl<R: RecordData> Record<R> {
         self
     }
 
+    /// Sets the time at which the record expires, e.g. once it is cached for its TTL
+    pub fn set_expires_at(&mut self, expires_at: Instant) -> &mut Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Returns the time at which the record expires, if one was set
+    #[inline]
+    pub fn expires_at(&self) -> Option<Instant> {
+        self.expires_at
+    }
+
+    /// Returns true if the record has an expiry time, and `now` is after it
+    pub fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.map_or(false, |expires_at| now > expires_at)
+    }
+
+    /// Returns the time remaining until the record expires, `None` if it is expired
+    ///
+    /// A record without an expiry time doesn't expire, the remaining time is its TTL.
+    pub fn remaining_ttl(&self, now: Instant) -> Option<Duration> {
+        match self.expires_at {
+            Some(_) if self.is_expired(now) => None,
+            Some(expires_at) => Some(expires_at - now),
+            None => Some(Duration::from_secs(u64::from(self.ttl))),
+        }
+    }
+
+    /// Checks that the record can be encoded, i.e. that its data is valid for its record type
+    ///
+    /// This is cheap for all typed record data, only `RData::Unknown` is decoded as its type code.
+    pub fn validate(&self) -> ProtoResult<()> {
+        self.rdata.validate()
+    }
+
     /// Returns the name of the record
     #[inline]
     pub fn name(&self) -> &Name {
@@ -368,6 +443,7 @@ impl<R: RecordData> From<Record<R>> for RecordParts<R> {
             mdns_cache_flush,
             #[cfg(feature = "dnssec")]
             proof,
+            expires_at: _,
         } = record;
 
         Self {
@@ -390,8 +466,20 @@ impl IntoRecordSet for Record {
     }
 }
 
+#[track_caller]
+fn debug_assert_valid<R: RecordData>(rdata: &R) {
+    if cfg!(debug_assertions) {
+        if let Err(e) = rdata.validate() {
+            panic!("inconsistent record data: {e}");
+        }
+    }
+}
+
 impl<R: RecordData> BinEncodable for Record<R> {
+    /// Fails if the record data isn't valid for the record type, see [`Record::validate`]
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        self.validate()?;
+
         self.name_labels.emit(encoder)?;
         self.record_type().emit(encoder)?;
 
@@ -427,6 +515,17 @@ impl<R: RecordData> BinEncodable for Record<R> {
         place.replace(encoder, len as u16)?;
         Ok(())
     }
+
+    fn encoded_size(&self) -> usize {
+        // the type, class, ttl and rdata length are 10 bytes
+        let rdata = if self.rdata.is_update() {
+            0
+        } else {
+            self.rdata.encoded_size()
+        };
+
+        self.name_labels.encoded_size() + 10 + rdata
+    }
 }
 
 impl<'r> BinDecodable<'r> for Record<RData> {
@@ -519,6 +618,7 @@ impl<'r> BinDecodable<'r> for Record<RData> {
             mdns_cache_flush,
             #[cfg(feature = "dnssec")]
             proof: Proof::default(),
+            expires_at: None,
         })
     }
 }
@@ -601,6 +701,15 @@ impl<R: RecordData> PartialEq for Record<R> {
     }
 }
 
+impl<R: RecordData + Hash> Hash for Record<R> {
+    /// Consistent with equality, the TTL is not hashed
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name_labels.hash(state);
+        self.dns_class.hash(state);
+        self.rdata.hash(state);
+    }
+}
+
 /// returns the value of the compare if the items are greater or lesser, but continues on equal
 macro_rules! compare_or_equal {
     ($x:ident, $y:ident, $z:ident) => {
@@ -720,6 +829,7 @@ pub struct RecordRef<'a, R: RecordData> {
     mdns_cache_flush: bool,
     #[cfg(feature = "dnssec")]
     proof: Proof,
+    expires_at: Option<Instant>,
 }
 
 impl<'a, R: RecordData> Clone for RecordRef<'a, R> {
@@ -742,9 +852,17 @@ impl<'a, R: RecordData> RecordRef<'a, R> {
             mdns_cache_flush: self.mdns_cache_flush,
             #[cfg(feature = "dnssec")]
             proof: self.proof,
+            expires_at: self.expires_at,
         }
     }
 
+    /// Checks that the record can be encoded, i.e. that its data is valid for its record type
+    ///
+    /// This is cheap for all typed record data, only `RData::Unknown` is decoded as its type code.
+    pub fn validate(&self) -> ProtoResult<()> {
+        self.rdata.validate()
+    }
+
     /// Returns the name of the record
     #[inline]
     pub fn name(&self) -> &Name {
@@ -806,6 +924,7 @@ impl<'a, R: RecordData> TryFrom<&'a Record> for RecordRef<'a, R> {
             mdns_cache_flush,
             #[cfg(feature = "dnssec")]
             proof,
+            expires_at,
         } = record;
 
         match R::try_borrow(rdata) {
@@ -819,6 +938,7 @@ impl<'a, R: RecordData> TryFrom<&'a Record> for RecordRef<'a, R> {
                 mdns_cache_flush: *mdns_cache_flush,
                 #[cfg(feature = "dnssec")]
                 proof: *proof,
+                expires_at: *expires_at,
             }),
         }
     }
@@ -833,7 +953,7 @@ mod tests {
 
     use super::*;
     use crate::rr::dns_class::DNSClass;
-    use crate::rr::rdata::{A, AAAA};
+    use crate::rr::rdata::{A, AAAA, MX, NULL, SVCB};
     use crate::rr::record_data::RData;
     use crate::rr::Name;
     #[allow(clippy::useless_attribute)]
@@ -861,6 +981,54 @@ mod tests {
         assert_eq!(got, record);
     }
 
+    #[test]
+    fn test_validate() {
+        let name = Name::from_str("www.example.com").unwrap();
+        let mut record = Record::from_rdata(name, 5, RData::A(A::new(192, 168, 0, 1)));
+        assert!(record.validate().is_ok());
+
+        // the data of an unknown type code may be anything
+        *record.data_mut() = RData::Unknown {
+            code: RecordType::Unknown(65_400),
+            rdata: NULL::with(vec![1, 2, 3]),
+        };
+        assert!(record.validate().is_ok());
+        assert!(record.to_bytes().is_ok());
+
+        // the untyped data of a known type must be valid for it
+        *record.data_mut() = RData::Unknown {
+            code: RecordType::A,
+            rdata: NULL::with(vec![192, 168, 0, 1]),
+        };
+        assert!(record.validate().is_ok());
+        *record.data_mut() = RData::Unknown {
+            code: RecordType::A,
+            rdata: NULL::with(vec![1, 2, 3]),
+        };
+        assert!(record.validate().is_err());
+        assert!(record.to_bytes().is_err());
+
+        *record.data_mut() = RData::Unknown {
+            code: RecordType::ANY,
+            rdata: NULL::with(vec![1]),
+        };
+        assert!(record.validate().is_err());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "inconsistent record data")]
+    fn test_from_inconsistent_rdata() {
+        Record::from_rdata(
+            Name::root(),
+            5,
+            RData::Unknown {
+                code: RecordType::AAAA,
+                rdata: NULL::with(vec![0; 4]),
+            },
+        );
+    }
+
     #[test]
     fn test_order() {
         let mut record = Record::from_rdata(
@@ -896,6 +1064,91 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_hash_set_dedup() {
+        use std::collections::HashSet;
+
+        let record = Record::from_rdata(
+            Name::from_str("www.example.com.").unwrap(),
+            5,
+            RData::A(A::new(192, 168, 0, 1)),
+        );
+
+        // the TTL and the case of the name are ignored, as for equality
+        let mut same = record.clone();
+        same.set_ttl(3600)
+            .set_name(Name::from_str("WWW.Example.com.").unwrap());
+
+        let mut other = record.clone();
+        other.set_data(RData::A(A::new(192, 168, 0, 2)));
+
+        let set: HashSet<Record> = [record, same, other].into_iter().collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        // RFC 4034 section 6.2, the owner name and the names in the RDATA of MX are lowercase,
+        //  and not compressed
+        let record = Record::from_rdata(
+            Name::from_ascii("Example.COM.").unwrap(),
+            3600,
+            RData::MX(MX::new(10, Name::from_ascii("Mail.Example.COM.").unwrap())),
+        );
+
+        let mut expected = b"\x07example\x03com\x00".to_vec();
+        expected.extend_from_slice(&[0, 15, 0, 1, 0, 0, 0x0e, 0x10, 0, 20, 0, 10]);
+        expected.extend_from_slice(b"\x04mail\x07example\x03com\x00");
+        assert_eq!(record.canonical_form().unwrap(), expected);
+
+        // the names in the RDATA of other types keep their case
+        let record = Record::from_rdata(
+            Name::from_ascii("_443._tcp.Example.COM.").unwrap(),
+            300,
+            RData::SVCB(SVCB::new(
+                1,
+                Name::from_ascii("Svc.Example.COM.").unwrap(),
+                vec![],
+            )),
+        );
+        let canonical = record.canonical_form().unwrap();
+        assert!(canonical.starts_with(b"\x04_443\x04_tcp\x07example\x03com\x00"));
+        assert!(canonical.ends_with(b"\x03Svc\x07Example\x03COM\x00"));
+    }
+
+    #[test]
+    fn test_expiry() {
+        use std::time::{Duration, Instant};
+
+        let now = Instant::now();
+        let mut record = Record::from_rdata(
+            Name::from_str("www.example.com.").unwrap(),
+            300,
+            RData::A(A::new(192, 0, 2, 1)),
+        );
+
+        // without an expiry time the record doesn't expire
+        assert!(!record.is_expired(now + Duration::from_secs(3600)));
+        assert_eq!(record.remaining_ttl(now), Some(Duration::from_secs(300)));
+
+        let expires_at = now + Duration::from_secs(60);
+        record.set_expires_at(expires_at);
+        assert_eq!(record.expires_at(), Some(expires_at));
+
+        assert!(!record.is_expired(now));
+        assert_eq!(record.remaining_ttl(now), Some(Duration::from_secs(60)));
+        assert!(!record.is_expired(expires_at));
+        assert_eq!(record.remaining_ttl(expires_at), Some(Duration::ZERO));
+
+        let after = expires_at + Duration::from_millis(1);
+        assert!(record.is_expired(after));
+        assert_eq!(record.remaining_ttl(after), None);
+
+        // the expiry time is kept through conversions
+        let record = record.into_record_of_rdata();
+        assert!(record.is_expired(after));
+    }
+
     #[cfg(feature = "mdns")]
     #[test]
     fn test_mdns_cache_flush_bit_handling() {
@@ -5,7 +5,7 @@
 // https://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use std::{iter::Chain, slice::Iter, vec};
+use std::{cmp::Ordering, iter::Chain, slice::Iter, vec};
 
 use tracing::{info, warn};
 
@@ -14,6 +14,11 @@ use crate::rr::{DNSClass, Name, RData, Record, RecordType};
 #[cfg(feature = "dnssec")]
 #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
 use crate::rr::dnssec::SupportedAlgorithms;
+#[cfg(feature = "dnssec")]
+use crate::rr::{
+    dnssec::{rdata::RRSIG, Algorithm},
+    RecordData,
+};
 
 /// Set of resource records associated to a name and type
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -154,7 +159,9 @@ impl RecordSet {
     /// # Arguments
     ///
     /// * `supported_algorithms` - the RRSIGs will be filtered by the set of supported_algorithms,
-    ///                            and then only the maximal RRSIG algorithm will be returned.
+    ///                            and then only the RRSIGs of the maximal algorithm will be
+    ///                            returned, there can be more than one when the zone is signed
+    ///                            by multiple keys, e.g. by multiple providers.
     #[cfg(feature = "dnssec")]
     #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
     pub fn records_with_rrsigs(
@@ -164,10 +171,7 @@ impl RecordSet {
         if self.records.is_empty() {
             RrsetRecords::Empty
         } else {
-            let rrsigs = RrsigsByAlgorithms {
-                rrsigs: self.rrsigs.iter(),
-                supported_algorithms,
-            };
+            let rrsigs = RrsigsByAlgorithms::new(self.rrsigs.iter(), supported_algorithms);
             RrsetRecords::RecordsAndRrsigs(RecordsAndRrsigsIter(self.records.iter().chain(rrsigs)))
         }
     }
@@ -296,7 +300,12 @@ impl RecordSet {
                     match soa_record.data() {
                         RData::SOA(ref existing_soa) => {
                             if let RData::SOA(ref new_soa) = record.data() {
-                                if new_soa.serial() <= existing_soa.serial() {
+                                // serials are compared with sequence space arithmetic, RFC 1982
+                                if new_soa
+                                    .serial_number()
+                                    .partial_cmp(&existing_soa.serial_number())
+                                    != Some(Ordering::Greater)
+                                {
                                     info!(
                                         "update ignored serial out of data: {:?} <= {:?}",
                                         new_soa, existing_soa
@@ -539,7 +548,29 @@ impl<'r> Iterator for RecordsAndRrsigsIter<'r> {
 #[derive(Debug)]
 pub(crate) struct RrsigsByAlgorithms<'r> {
     rrsigs: Iter<'r, Record>,
-    supported_algorithms: SupportedAlgorithms,
+    /// the maximal supported algorithm of the RRSIGs, None when no algorithms are specified
+    algorithm: Option<Algorithm>,
+}
+
+#[cfg(feature = "dnssec")]
+impl<'r> RrsigsByAlgorithms<'r> {
+    fn new(rrsigs: Iter<'r, Record>, supported_algorithms: SupportedAlgorithms) -> Self {
+        // disable rfc 6975 when no supported_algorithms specified
+        let algorithm = if supported_algorithms.is_empty() {
+            None
+        } else {
+            rrsigs
+                .clone()
+                .filter_map(|record| RRSIG::try_borrow(record.data()))
+                .map(|rrsig| rrsig.algorithm())
+                .filter(|algorithm| supported_algorithms.has(*algorithm))
+                .max()
+                // none of the RRSIGs are supported, return none of them
+                .or(Some(Algorithm::Unknown(0)))
+        };
+
+        Self { rrsigs, algorithm }
+    }
 }
 
 #[cfg(feature = "dnssec")]
@@ -548,33 +579,13 @@ impl<'r> Iterator for RrsigsByAlgorithms<'r> {
     type Item = &'r Record;
 
     fn next(&mut self) -> Option<Self::Item> {
-        use crate::rr::dnssec::rdata::DNSSECRData;
-        use crate::rr::dnssec::Algorithm;
+        let Some(algorithm) = self.algorithm else {
+            return self.rrsigs.next();
+        };
 
-        let supported_algorithms = self.supported_algorithms;
-
-        // disable rfc 6975 when no supported_algorithms specified
-        if supported_algorithms.is_empty() {
-            self.rrsigs.next()
-        } else {
-            self.rrsigs
-                .by_ref()
-                .filter(|record| {
-                    if let RData::DNSSEC(DNSSECRData::RRSIG(ref rrsig)) = record.data() {
-                        supported_algorithms.has(rrsig.algorithm())
-                    } else {
-                        false
-                    }
-                })
-                .max_by_key(|record| {
-                    if let RData::DNSSEC(DNSSECRData::RRSIG(ref rrsig)) = record.data() {
-                        rrsig.algorithm()
-                    } else {
-                        #[allow(deprecated)]
-                        Algorithm::RSASHA1
-                    }
-                })
-        }
+        self.rrsigs.by_ref().find(|record| {
+            RRSIG::try_borrow(record.data()).map_or(false, |rrsig| rrsig.algorithm() == algorithm)
+        })
     }
 }
 
@@ -974,4 +985,61 @@ mod test {
             }
         }));
     }
+
+    #[test]
+    #[cfg(feature = "dnssec")]
+    fn test_get_filter_multiple_signers() {
+        use crate::rr::dnssec::rdata::{DNSSECRData, RRSIG};
+        use crate::rr::dnssec::{Algorithm, SupportedAlgorithms};
+        use crate::rr::RecordData;
+
+        let name = Name::root();
+        let rrsig = |algorithm, key_tag| {
+            let rrsig = RRSIG::new(
+                RecordType::A,
+                algorithm,
+                0,
+                0,
+                0,
+                0,
+                key_tag,
+                Name::root(),
+                vec![],
+            );
+            Record::from_rdata(name.clone(), 3600, RData::DNSSEC(DNSSECRData::RRSIG(rrsig)))
+        };
+
+        let a = Record::from_rdata(
+            name.clone(),
+            3600,
+            RData::A(Ipv4Addr::new(93, 184, 216, 24).into()),
+        );
+
+        // two signers with the same algorithm, e.g. in a multi-signer setup
+        let mut rrset = RecordSet::from(a);
+        rrset.insert_rrsig(rrsig(Algorithm::RSASHA256, 1));
+        rrset.insert_rrsig(rrsig(Algorithm::ED25519, 2));
+        rrset.insert_rrsig(rrsig(Algorithm::ED25519, 3));
+
+        let key_tags = |supported_algorithms| {
+            rrset
+                .records_with_rrsigs(supported_algorithms)
+                .filter_map(|r| RRSIG::try_borrow(r.data()))
+                .map(|rrsig| rrsig.key_tag())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(key_tags(SupportedAlgorithms::all()), vec![2, 3]);
+
+        let mut supported_algorithms = SupportedAlgorithms::new();
+        supported_algorithms.set(Algorithm::RSASHA256);
+        assert_eq!(key_tags(supported_algorithms), vec![1]);
+
+        let mut supported_algorithms = SupportedAlgorithms::new();
+        supported_algorithms.set(Algorithm::ECDSAP256SHA256);
+        assert!(key_tags(supported_algorithms).is_empty());
+
+        // no supported algorithms disables the filtering
+        assert_eq!(key_tags(SupportedAlgorithms::new()), vec![1, 2, 3]);
+    }
 }
@@ -0,0 +1,239 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Typed views of the record data in a list of records, e.g. the answers of a response
+
+use std::borrow::Cow;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::net::IpAddr;
+use std::slice::Iter;
+
+use crate::rr::rdata::TXT;
+use crate::rr::{Name, RData, Record, RecordData};
+
+/// Iterator over the record data of type `T` in a list of records, all other records are skipped
+pub struct RecordDataIter<'a, T> {
+    records: Iter<'a, Record>,
+    record_data: PhantomData<fn() -> T>,
+}
+
+impl<'a, T: RecordData + 'a> RecordDataIter<'a, T> {
+    /// Iterates over the record data of type `T` in `records`
+    pub fn new(records: &'a [Record]) -> Self {
+        Self {
+            records: records.iter(),
+            record_data: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: RecordData + 'a> Iterator for RecordDataIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.records
+            .by_ref()
+            .find_map(|record| T::try_borrow(record.data()))
+    }
+}
+
+impl<'a, T: RecordData + 'a> FusedIterator for RecordDataIter<'a, T> {}
+
+/// Iterator over the addresses of the A and AAAA records in a list of records
+pub struct AddressIter<'a>(Iter<'a, Record>);
+
+impl<'a> AddressIter<'a> {
+    /// Iterates over the addresses in `records`
+    pub fn new(records: &'a [Record]) -> Self {
+        Self(records.iter())
+    }
+}
+
+impl Iterator for AddressIter<'_> {
+    type Item = IpAddr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.by_ref().find_map(|record| match record.data() {
+            RData::A(a) => Some(IpAddr::V4(a.0)),
+            RData::AAAA(aaaa) => Some(IpAddr::V6(aaaa.0)),
+            _ => None,
+        })
+    }
+}
+
+impl FusedIterator for AddressIter<'_> {}
+
+/// Iterator over the TXT records in a list of records, each joined into a single string
+///
+/// The character-strings of a TXT record are concatenated without separator, as required for
+///  e.g. SPF, [RFC 7208](https://tools.ietf.org/html/rfc7208#section-3.3). Invalid UTF-8 is
+///  replaced with the replacement character. Records with a single character-string are borrowed.
+pub struct TxtStringIter<'a>(RecordDataIter<'a, TXT>);
+
+impl<'a> TxtStringIter<'a> {
+    /// Iterates over the TXT strings in `records`
+    pub fn new(records: &'a [Record]) -> Self {
+        Self(RecordDataIter::new(records))
+    }
+}
+
+impl<'a> Iterator for TxtStringIter<'a> {
+    type Item = Cow<'a, str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let txt = self.0.next()?;
+        Some(match txt.txt_data() {
+            [] => Cow::Borrowed(""),
+            [string] => String::from_utf8_lossy(string),
+            strings => Cow::Owned(String::from_utf8_lossy(&strings.concat()).into_owned()),
+        })
+    }
+}
+
+impl FusedIterator for TxtStringIter<'_> {}
+
+/// Follows the CNAME records in `records`, starting at `name`, and returns the final owner name
+///
+/// If there is no CNAME for `name`, `name` is returned. Every CNAME is followed at most once, so
+///  a CNAME loop ends at the name that would repeat.
+pub fn canonical_name<'a>(records: &'a [Record], name: &'a Name) -> &'a Name {
+    let mut name = name;
+    for _ in 0..records.len() {
+        let next = records.iter().find_map(|record| match record.data() {
+            RData::CNAME(cname) if record.name() == name => Some(&cname.0),
+            _ => None,
+        });
+
+        match next {
+            Some(next) => name = next,
+            None => break,
+        }
+    }
+
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::rr::rdata::{A, AAAA, CNAME};
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).unwrap()
+    }
+
+    /// A CNAME chain from `www.example.com.` to `c.example.net.`, followed by its addresses
+    fn cname_chain() -> Vec<Record> {
+        vec![
+            Record::from_rdata(
+                name("www.example.com."),
+                300,
+                RData::CNAME(CNAME(name("b.example.org."))),
+            ),
+            Record::from_rdata(
+                name("b.example.org."),
+                300,
+                RData::CNAME(CNAME(name("c.example.net."))),
+            ),
+            Record::from_rdata(name("c.example.net."), 300, RData::A(A::new(192, 0, 2, 1))),
+            Record::from_rdata(
+                name("c.example.net."),
+                300,
+                RData::AAAA(AAAA::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_cname_chain() {
+        let records = cname_chain();
+
+        let a = RecordDataIter::<A>::new(&records).collect::<Vec<_>>();
+        assert_eq!(a, [&A::new(192, 0, 2, 1)]);
+
+        let cnames = RecordDataIter::<CNAME>::new(&records).collect::<Vec<_>>();
+        assert_eq!(cnames.len(), 2);
+
+        let addresses = AddressIter::new(&records).collect::<Vec<_>>();
+        assert_eq!(
+            addresses,
+            [
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+            ]
+        );
+
+        let query = name("WWW.example.com.");
+        assert_eq!(canonical_name(&records, &query), &name("c.example.net."));
+
+        // names in the middle of the chain are followed as well
+        let middle = name("b.example.org.");
+        assert_eq!(canonical_name(&records, &middle), &name("c.example.net."));
+    }
+
+    #[test]
+    fn test_cname_loop() {
+        let records = vec![
+            Record::from_rdata(
+                name("a.example.com."),
+                300,
+                RData::CNAME(CNAME(name("b.example.com."))),
+            ),
+            Record::from_rdata(
+                name("b.example.com."),
+                300,
+                RData::CNAME(CNAME(name("a.example.com."))),
+            ),
+        ];
+
+        let query = name("a.example.com.");
+        assert_eq!(canonical_name(&records, &query), &name("a.example.com."));
+    }
+
+    #[test]
+    fn test_txt_strings() {
+        let records = vec![
+            Record::from_rdata(
+                name("example.com."),
+                300,
+                RData::TXT(TXT::new(vec![
+                    "v=spf1 ip4:192.0.2.0/24 ".to_string(),
+                    "ip6:2001:db8::/32 -all".to_string(),
+                ])),
+            ),
+            Record::from_rdata(
+                name("example.com."),
+                300,
+                RData::TXT(TXT::new(vec!["single".to_string()])),
+            ),
+        ];
+
+        let strings = TxtStringIter::new(&records).collect::<Vec<_>>();
+        assert_eq!(
+            strings,
+            ["v=spf1 ip4:192.0.2.0/24 ip6:2001:db8::/32 -all", "single"]
+        );
+        assert!(matches!(strings[0], Cow::Owned(_)));
+        assert!(matches!(strings[1], Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_empty() {
+        let records = Vec::new();
+
+        assert_eq!(RecordDataIter::<A>::new(&records).next(), None);
+        assert_eq!(AddressIter::new(&records).next(), None);
+        assert_eq!(TxtStringIter::new(&records).next(), None);
+
+        let query = name("www.example.com.");
+        assert_eq!(canonical_name(&records, &query), &query);
+    }
+}
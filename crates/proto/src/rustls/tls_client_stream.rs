@@ -16,7 +16,7 @@ use std::sync::Arc;
 use futures_util::TryFutureExt;
 use rustls::ClientConfig;
 
-use crate::error::ProtoError;
+use crate::error::{ProtoError, ProtoResult};
 use crate::iocompat::AsyncIoStdAsTokio;
 use crate::iocompat::AsyncIoTokioAsStd;
 use crate::rustls::tls_stream::{tls_connect_with_bind_addr, tls_connect_with_future};
@@ -40,7 +40,7 @@ pub fn tls_client_connect<S: Connect>(
     dns_name: String,
     client_config: Arc<ClientConfig>,
 ) -> (
-    Pin<Box<dyn Future<Output = Result<TlsClientStream<S>, ProtoError>> + Send + Unpin>>,
+    Pin<Box<dyn Future<Output = ProtoResult<TlsClientStream<S>>> + Send + Unpin>>,
     BufDnsStreamHandle,
 ) {
     tls_client_connect_with_bind_addr(name_server, None, dns_name, client_config)
@@ -60,7 +60,7 @@ pub fn tls_client_connect_with_bind_addr<S: Connect>(
     dns_name: String,
     client_config: Arc<ClientConfig>,
 ) -> (
-    Pin<Box<dyn Future<Output = Result<TlsClientStream<S>, ProtoError>> + Send + Unpin>>,
+    Pin<Box<dyn Future<Output = ProtoResult<TlsClientStream<S>>> + Send + Unpin>>,
     BufDnsStreamHandle,
 ) {
     let (stream_future, sender) =
@@ -88,7 +88,7 @@ pub fn tls_client_connect_with_future<S, F>(
     dns_name: String,
     client_config: Arc<ClientConfig>,
 ) -> (
-    Pin<Box<dyn Future<Output = Result<TlsClientStream<S>, ProtoError>> + Send + Unpin>>,
+    Pin<Box<dyn Future<Output = ProtoResult<TlsClientStream<S>>> + Send + Unpin>>,
     BufDnsStreamHandle,
 )
 where
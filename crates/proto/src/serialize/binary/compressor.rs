@@ -0,0 +1,198 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Name compression table built ahead of encoding a set of records
+
+use std::collections::{HashMap, HashSet};
+
+use crate::error::ProtoResult;
+use crate::rr::rdata::{CNAME, NS, PTR};
+use crate::rr::{Name, RData, Record};
+use crate::serialize::binary::{BinEncodable, BinEncoder, EncodeMode};
+
+/// Encodes records with a compression table built from all of their names up front
+///
+/// The `BinEncoder` stores a label pointer for every suffix of every name it writes, and searches
+///  all of them for each name. Only the suffixes which occur in more than one name can ever be
+///  pointed to, so the `MessageCompressor` collects those from the records first, and the encoder
+///  only stores pointers to them. This produces the same compression
+///  ([RFC 1035 section 4.1.4](https://tools.ietf.org/html/rfc1035#section-4.1.4)) with a smaller
+///  table to search.
+///
+/// ```
+/// use std::str::FromStr;
+///
+/// use hickory_proto::rr::{rdata::A, Name, RData, Record};
+/// use hickory_proto::serialize::binary::MessageCompressor;
+///
+/// let records = (1..=3)
+///     .map(|i| {
+///         let name = Name::from_str(&format!("host{i}.example.com.")).unwrap();
+///         Record::from_rdata(name, 300, RData::A(A::new(192, 0, 2, i)))
+///     })
+///     .collect::<Vec<_>>();
+///
+/// let compressor = MessageCompressor::new(&records);
+/// assert_eq!(compressor.len(), 2); // "example.com" and "com"
+///
+/// let mut buf = Vec::new();
+/// compressor.encode(&records, &mut buf).unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MessageCompressor {
+    suffixes: HashSet<Vec<u8>>,
+}
+
+impl MessageCompressor {
+    /// Builds the compression table from the owner names of the records and the names in their
+    ///  RDATA
+    pub fn new(records: &[Record]) -> Self {
+        let mut counts = HashMap::<Vec<u8>, usize>::new();
+        for name in records.iter().flat_map(names) {
+            for suffix in wire_suffixes(name) {
+                *counts.entry(suffix).or_default() += 1;
+            }
+        }
+
+        Self {
+            suffixes: counts
+                .into_iter()
+                .filter(|(_, count)| *count > 1)
+                .map(|(suffix, _)| suffix)
+                .collect(),
+        }
+    }
+
+    /// Returns the number of suffixes which will be stored as compression targets
+    pub fn len(&self) -> usize {
+        self.suffixes.len()
+    }
+
+    /// Returns true if no names share a suffix, i.e. nothing can be compressed
+    pub fn is_empty(&self) -> bool {
+        self.suffixes.is_empty()
+    }
+
+    /// Appends the records to the buffer, compressing names with the table
+    ///
+    /// The offsets of the compression pointers are relative to the start of `buf`, so it should
+    ///  be empty or hold the preceding part of the message, e.g. the header and queries.
+    pub fn encode(&self, records: &[Record], buf: &mut Vec<u8>) -> ProtoResult<()> {
+        let offset = buf.len() as u32;
+        let mut encoder = BinEncoder::with_offset(buf, offset, EncodeMode::Normal);
+        encoder.set_name_pointer_suffixes(&self.suffixes);
+
+        for record in records {
+            record.emit(&mut encoder)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The owner name of the record, and the names in the RDATA which may be compressed
+fn names(record: &Record) -> impl Iterator<Item = &Name> {
+    let (first, second) = match record.data() {
+        RData::CNAME(CNAME(name)) | RData::NS(NS(name)) | RData::PTR(PTR(name)) => {
+            (Some(name), None)
+        }
+        RData::MX(mx) => (Some(mx.exchange()), None),
+        RData::SOA(soa) => (Some(soa.mname()), Some(soa.rname())),
+        RData::SRV(srv) => (Some(srv.target()), None),
+        _ => (None, None),
+    };
+
+    std::iter::once(record.name()).chain(first).chain(second)
+}
+
+/// The suffixes of the name in uncompressed wire form, without the root label, as compared by
+///  `BinEncoder::get_label_pointer`
+fn wire_suffixes(name: &Name) -> impl Iterator<Item = Vec<u8>> + '_ {
+    let labels = name.iter().collect::<Vec<_>>();
+    (0..labels.len()).map(move |start| {
+        let mut suffix = Vec::new();
+        for label in &labels[start..] {
+            suffix.push(label.len() as u8);
+            suffix.extend_from_slice(label);
+        }
+        suffix
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::rr::rdata::{A, MX};
+    use crate::serialize::binary::{BinDecodable, BinDecoder};
+
+    fn records() -> Vec<Record> {
+        let mut records = (1..=10)
+            .map(|i| {
+                let name = Name::from_str(&format!("host{i}.sub.example.com.")).unwrap();
+                Record::from_rdata(name, 300, RData::A(A::new(192, 0, 2, i)))
+            })
+            .collect::<Vec<_>>();
+        records.push(Record::from_rdata(
+            Name::from_str("example.com.").unwrap(),
+            300,
+            RData::MX(MX::new(10, Name::from_str("mail.unique.net.").unwrap())),
+        ));
+        records
+    }
+
+    #[test]
+    fn test_suffix_table() {
+        let compressor = MessageCompressor::new(&records());
+
+        // "sub.example.com", "example.com" and "com", but not the unique host names
+        assert_eq!(compressor.len(), 3);
+    }
+
+    #[test]
+    fn test_smaller_than_uncompressed() {
+        let records = records();
+
+        let mut uncompressed = Vec::new();
+        let mut encoder = BinEncoder::new(&mut uncompressed);
+        encoder.set_canonical_names(true);
+        for record in &records {
+            record.emit(&mut encoder).unwrap();
+        }
+
+        let mut compressed = Vec::new();
+        MessageCompressor::new(&records)
+            .encode(&records, &mut compressed)
+            .unwrap();
+        assert!(compressed.len() < uncompressed.len());
+
+        // the same size as with the full table of the encoder
+        let mut greedy = Vec::new();
+        let mut encoder = BinEncoder::new(&mut greedy);
+        for record in &records {
+            record.emit(&mut encoder).unwrap();
+        }
+        assert_eq!(compressed, greedy);
+    }
+
+    #[test]
+    fn test_encode_after_header() {
+        let records = records();
+
+        let mut buf = vec![0; 12];
+        MessageCompressor::new(&records)
+            .encode(&records, &mut buf)
+            .unwrap();
+
+        let mut decoder = BinDecoder::new(&buf);
+        decoder.read_slice(12).unwrap();
+        for record in &records {
+            assert_eq!(&Record::read(&mut decoder).unwrap(), record);
+        }
+    }
+}
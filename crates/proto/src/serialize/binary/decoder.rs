@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+use crate::error::ProtoResult;
 use crate::serialize::binary::Restrict;
 use thiserror::Error;
 
@@ -75,6 +76,15 @@ pub enum DecodeError {
         /// Start of the other label
         other: usize,
     },
+
+    /// A compression pointer leads back to an offset which was already followed
+    #[error("pointer loop at idx: {idx} ptr: {ptr}")]
+    PointerLoop {
+        /// index of the pointer closing the loop
+        idx: usize,
+        /// location to which the pointer is directing
+        ptr: u16,
+    },
 }
 
 impl<'a> BinDecoder<'a> {
@@ -186,6 +196,30 @@ impl<'a> BinDecoder<'a> {
         Ok(Restrict::new(read))
     }
 
+    /// Returns a decoder over the next `len` bytes and advances this decoder past them
+    ///
+    /// Reads from the returned decoder fail with an error once `len` bytes have been read, which
+    ///  bounds the decoding of nested structures. The returned decoder still sees all data up to
+    ///  its end, so its indexes match this decoder's and name pointers to earlier data resolve.
+    ///
+    /// ```
+    /// use hickory_proto::serialize::binary::BinDecoder;
+    ///
+    /// let deadbeef = b"deadbeef";
+    /// let mut decoder = BinDecoder::new(deadbeef);
+    /// let mut dead = decoder.sub_decoder(4).unwrap();
+    /// assert_eq!(dead.len(), 4);
+    /// assert_eq!(decoder.len(), 4);
+    /// assert!(dead.read_slice(5).is_err());
+    /// ```
+    pub fn sub_decoder(&mut self, len: usize) -> ProtoResult<Self> {
+        let remaining = self.read_slice(len)?.unverified(/*bounded by the returned decoder*/);
+        Ok(Self {
+            buffer: &self.buffer[..self.index()],
+            remaining,
+        })
+    }
+
     /// Reads a slice from a previous index to the current
     pub fn slice_from(&self, index: usize) -> DecodeResult<&'a [u8]> {
         if index > self.index() {
@@ -287,4 +321,41 @@ mod tests {
         // this should fail
         assert!(decoder.slice_from(10).is_err());
     }
+
+    #[test]
+    fn test_sub_decoder() {
+        let deadbeef = b"deadbeef";
+        let mut decoder = BinDecoder::new(deadbeef);
+        decoder.read_slice(2).expect("failed to read de");
+
+        let mut sub_decoder = decoder.sub_decoder(4).expect("failed to get sub decoder");
+        assert_eq!(decoder.index(), 6);
+        assert_eq!(decoder.len(), 2);
+        assert_eq!(sub_decoder.index(), 2);
+        assert_eq!(sub_decoder.len(), 4);
+
+        let read = sub_decoder.read_slice(3).expect("failed to read adb");
+        assert_eq!(&read.unverified(), b"adb");
+        let read = sub_decoder.slice_from(0).expect("failed to get slice");
+        assert_eq!(&read, b"deadb");
+
+        // reads are bounded by the sub decoder
+        assert!(sub_decoder.read_slice(2).is_err());
+        assert_eq!(
+            sub_decoder
+                .read_u8()
+                .expect("failed to read e")
+                .unverified(),
+            b'e'
+        );
+        assert!(sub_decoder.read_u8().is_err());
+        assert!(sub_decoder.is_empty());
+
+        // the parent continues after the sub decoder
+        let read = decoder.read_slice(2).expect("failed to read ef");
+        assert_eq!(&read.unverified(), b"ef");
+
+        // this should fail
+        assert!(decoder.sub_decoder(1).is_err());
+    }
 }
@@ -5,6 +5,8 @@
 // https://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use std::collections::HashSet;
+use std::io::{self, IoSlice, Write};
 use std::marker::PhantomData;
 
 use crate::{
@@ -22,6 +24,8 @@ mod private {
     pub(super) struct MaximalBuf<'a> {
         max_size: usize,
         buffer: &'a mut Vec<u8>,
+        /// bytes which are part of the message, but not of the buffer
+        external: usize,
     }
 
     impl<'a> MaximalBuf<'a> {
@@ -29,6 +33,7 @@ mod private {
             MaximalBuf {
                 max_size: max_size as usize,
                 buffer,
+                external: 0,
             }
         }
 
@@ -37,9 +42,14 @@ mod private {
             self.max_size = max as usize;
         }
 
+        /// The maximum size which is enforced
+        pub(super) fn max_size(&self) -> usize {
+            self.max_size
+        }
+
         pub(super) fn write(&mut self, offset: usize, data: &[u8]) -> ProtoResult<()> {
             debug_assert!(offset <= self.buffer.len());
-            if offset + data.len() > self.max_size {
+            if offset + data.len() + self.external > self.max_size {
                 return Err(ProtoErrorKind::MaxBufferSizeExceeded(self.max_size).into());
             }
 
@@ -59,7 +69,7 @@ mod private {
 
         pub(super) fn reserve(&mut self, offset: usize, len: usize) -> ProtoResult<()> {
             let end = offset + len;
-            if end > self.max_size {
+            if end + self.external > self.max_size {
                 return Err(ProtoErrorKind::MaxBufferSizeExceeded(self.max_size).into());
             }
 
@@ -67,6 +77,21 @@ mod private {
             Ok(())
         }
 
+        /// Accounts for `len` bytes appended to the message outside of the buffer
+        pub(super) fn reserve_external(&mut self, len: usize) -> ProtoResult<()> {
+            if self.buffer.len() + self.external + len > self.max_size {
+                return Err(ProtoErrorKind::MaxBufferSizeExceeded(self.max_size).into());
+            }
+
+            self.external += len;
+            Ok(())
+        }
+
+        /// Sets the bytes outside of the buffer, after some were discarded
+        pub(super) fn set_external(&mut self, len: usize) {
+            self.external = len;
+        }
+
         /// truncates are always safe
         pub(super) fn truncate(&mut self, len: usize) {
             self.buffer.truncate(len)
@@ -95,8 +120,13 @@ pub struct BinEncoder<'a> {
     buffer: private::MaximalBuf<'a>,
     /// start of label pointers with their labels in fully decompressed form for easy comparison, smallvec here?
     name_pointers: Vec<(usize, Vec<u8>)>,
+    /// if set, only label pointers to these suffixes are stored, see `MessageCompressor`
+    name_pointer_suffixes: Option<&'a HashSet<Vec<u8>>>,
     mode: EncodeMode,
     canonical_names: bool,
+    lowercase_names: bool,
+    /// the slices emitted without copying, with the buffer index they precede, if gathering
+    gathered: Option<Vec<(usize, &'a [u8])>>,
 }
 
 impl<'a> BinEncoder<'a> {
@@ -134,11 +164,30 @@ impl<'a> BinEncoder<'a> {
             // TODO: add max_size to signature
             buffer: private::MaximalBuf::new(u16::MAX, buf),
             name_pointers: Vec::new(),
+            name_pointer_suffixes: None,
             mode,
             canonical_names: false,
+            lowercase_names: false,
+            gathered: None,
         }
     }
 
+    /// Create a new encoder in gather mode, which keeps references to the byte slices emitted with
+    ///  [`Self::emit_bytes_zerocopy`] instead of copying them
+    ///
+    /// The encoded message is then the buffer interleaved with those slices, see
+    ///  [`Self::into_vectored`].
+    pub fn vectored(buf: &'a mut Vec<u8>) -> Self {
+        let mut encoder = Self::new(buf);
+        encoder.gathered = Some(Vec::new());
+        encoder
+    }
+
+    /// Returns true if the encoder is in gather mode, see [`Self::vectored`]
+    pub fn is_vectored(&self) -> bool {
+        self.gathered.is_some()
+    }
+
     // TODO: move to constructor (kept for backward compatibility)
     /// Sets the maximum size of the buffer
     ///
@@ -149,11 +198,28 @@ impl<'a> BinEncoder<'a> {
         self.buffer.set_max_size(max);
     }
 
+    /// Returns the maximum size of the buffer, see [`Self::set_max_size`]
+    pub fn max_size(&self) -> u16 {
+        u16::try_from(self.buffer.max_size()).unwrap_or(u16::MAX)
+    }
+
     /// Returns a reference to the internal buffer
+    ///
+    /// In gather mode the buffer lacks the slices emitted with [`Self::emit_bytes_zerocopy`], use
+    ///  [`Self::into_vectored`].
     pub fn into_bytes(self) -> &'a Vec<u8> {
         self.buffer.into_bytes()
     }
 
+    /// Returns the encoded message as the internal buffer interleaved with the gathered slices
+    pub fn into_vectored(mut self) -> VectoredBytes<'a> {
+        let gathered = self.gathered.take().unwrap_or_default();
+        VectoredBytes {
+            buffer: self.buffer.into_bytes(),
+            gathered,
+        }
+    }
+
     /// Returns the length of the buffer
     pub fn len(&self) -> usize {
         self.buffer.len()
@@ -189,6 +255,16 @@ impl<'a> BinEncoder<'a> {
         self.canonical_names
     }
 
+    /// If set to true, then the ASCII letters of names will be written as lowercase
+    pub fn set_lowercase_names(&mut self, lowercase_names: bool) {
+        self.lowercase_names = lowercase_names;
+    }
+
+    /// Returns true if the encoder is writing names as lowercase
+    pub fn is_lowercase_names(&self) -> bool {
+        self.lowercase_names
+    }
+
     /// Emit all names in canonical form, useful for <https://tools.ietf.org/html/rfc3597>
     pub fn with_canonical_names<F: FnOnce(&mut Self) -> ProtoResult<()>>(
         &mut self,
@@ -214,6 +290,25 @@ impl<'a> BinEncoder<'a> {
         let offset = self.offset;
         self.buffer.truncate(offset);
         self.name_pointers.retain(|&(start, _)| start < offset);
+        if let Some(gathered) = &mut self.gathered {
+            gathered.retain(|&(index, _)| index <= offset);
+        }
+        self.update_external();
+    }
+
+    /// The position in the message of the buffer index, i.e. including the gathered slices before it
+    fn message_offset(&self, index: usize) -> usize {
+        let gathered = self.gathered.iter().flatten();
+        index
+            + gathered
+                .filter(|(at, _)| *at <= index)
+                .map(|(_, bytes)| bytes.len())
+                .sum::<usize>()
+    }
+
+    fn update_external(&mut self) {
+        let external = self.gathered.iter().flatten().map(|(_, b)| b.len()).sum();
+        self.buffer.set_external(external);
     }
 
     // /// returns an error if the maximum buffer size would be exceeded with the addition number of elements
@@ -243,20 +338,37 @@ impl<'a> BinEncoder<'a> {
         assert!(start <= (u16::MAX as usize));
         assert!(end <= (u16::MAX as usize));
         assert!(start <= end);
-        if self.offset < 0x3FFF_usize {
-            self.name_pointers
-                .push((start, self.slice_of(start, end).to_vec())); // the next char will be at the len() location
+        if self.message_offset(self.offset) < 0x3FFF_usize {
+            let suffix = self.slice_of(start, end);
+            if self
+                .name_pointer_suffixes
+                .map_or(false, |suffixes| !suffixes.contains(suffix))
+            {
+                return;
+            }
+
+            self.name_pointers.push((start, suffix.to_vec())); // the next char will be at the len() location
         }
     }
 
+    /// Restricts the label pointers stored by `store_label_pointer` to the given suffixes
+    ///
+    /// The suffixes are the labels of names in their uncompressed wire form, without the root label.
+    ///  Pointers to suffixes which are not written again are never used, leaving them out of the
+    ///  table keeps the lookups of `get_label_pointer` short.
+    pub(crate) fn set_name_pointer_suffixes(&mut self, suffixes: &'a HashSet<Vec<u8>>) {
+        self.name_pointer_suffixes = Some(suffixes);
+    }
+
     /// Looks up the index of an already written label
     pub fn get_label_pointer(&self, start: usize, end: usize) -> Option<u16> {
         let search = self.slice_of(start, end);
 
         for (match_start, matcher) in &self.name_pointers {
             if matcher.as_slice() == search {
-                assert!(match_start <= &(u16::MAX as usize));
-                return Some(*match_start as u16);
+                let match_start = self.message_offset(*match_start);
+                assert!(match_start <= (u16::MAX as usize));
+                return Some(match_start as u16);
             }
         }
 
@@ -337,6 +449,27 @@ impl<'a> BinEncoder<'a> {
         self.write_slice(data)
     }
 
+    /// Writes the byte slice to the stream, without copying it in gather mode
+    ///
+    /// In gather mode, see [`Self::vectored`], the encoder keeps a reference to `data`, which is
+    ///  only part of [`Self::into_vectored`]. This saves copying large payloads, e.g. the data of
+    ///  OPENPGPKEY records, which the caller owns for the lifetime of the encoder. Otherwise, or
+    ///  when not appending to the end of the buffer, `data` is copied like with [`Self::emit_vec`].
+    pub fn emit_bytes_zerocopy(&mut self, data: &'a [u8]) -> ProtoResult<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        if self.gathered.is_none() || self.offset != self.buffer.len() {
+            return self.write_slice(data);
+        }
+
+        self.buffer.reserve_external(data.len())?;
+        if let Some(gathered) = &mut self.gathered {
+            gathered.push((self.offset, data));
+        }
+        Ok(())
+    }
+
     /// Emits all the elements of an Iterator to the encoder
     pub fn emit_all<'e, I: Iterator<Item = &'e E>, E: 'e + BinEncodable>(
         &mut self,
@@ -398,7 +531,8 @@ impl<'a> BinEncoder<'a> {
 
     /// calculates the length of data written since the place was creating
     pub fn len_since_place<T: EncodedSize>(&self, place: &Place<T>) -> usize {
-        (self.offset - place.start_index) - place.size_of()
+        (self.message_offset(self.offset) - self.message_offset(place.start_index))
+            - place.size_of()
     }
 
     /// write back to a previously captured location
@@ -424,9 +558,11 @@ impl<'a> BinEncoder<'a> {
         emit_result
     }
 
-    fn set_rollback(&self) -> Rollback {
+    pub(crate) fn set_rollback(&self) -> Rollback {
         Rollback {
             rollback_index: self.offset(),
+            name_pointers: self.name_pointers.len(),
+            gathered: self.gathered.as_ref().map_or(0, Vec::len),
         }
     }
 }
@@ -471,11 +607,97 @@ impl<T: EncodedSize> Place<T> {
 /// A type representing a rollback point in a stream
 pub(crate) struct Rollback {
     rollback_index: usize,
+    name_pointers: usize,
+    gathered: usize,
 }
 
 impl Rollback {
+    /// Discards everything written since the rollback point, including the labels which later
+    ///  names could otherwise point to
     pub(crate) fn rollback(self, encoder: &mut BinEncoder<'_>) {
-        encoder.set_offset(self.rollback_index)
+        encoder.set_offset(self.rollback_index);
+        encoder.buffer.truncate(self.rollback_index);
+        encoder.name_pointers.truncate(self.name_pointers);
+        if let Some(gathered) = &mut encoder.gathered {
+            gathered.truncate(self.gathered);
+        }
+        encoder.update_external();
+    }
+}
+
+/// A message encoded in gather mode, the buffer of the encoder interleaved with the byte slices
+///  emitted without copying, see [`BinEncoder::vectored`]
+#[derive(Debug)]
+pub struct VectoredBytes<'a> {
+    buffer: &'a [u8],
+    gathered: Vec<(usize, &'a [u8])>,
+}
+
+impl<'a> VectoredBytes<'a> {
+    /// The length of the message
+    pub fn len(&self) -> usize {
+        self.buffer.len() + self.gathered.iter().map(|(_, b)| b.len()).sum::<usize>()
+    }
+
+    /// Returns true if the message is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The parts of the message in order, for a vectored write
+    pub fn io_slices(&self) -> Vec<IoSlice<'a>> {
+        let mut slices = Vec::with_capacity(self.gathered.len() * 2 + 1);
+        let mut start = 0;
+        for &(index, bytes) in &self.gathered {
+            if start < index {
+                slices.push(IoSlice::new(&self.buffer[start..index]));
+            }
+            slices.push(IoSlice::new(bytes));
+            start = index;
+        }
+        if start < self.buffer.len() {
+            slices.push(IoSlice::new(&self.buffer[start..]));
+        }
+
+        slices
+    }
+
+    /// Writes the whole message with `write_vectored`
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let slices = self.io_slices();
+        let (mut index, mut skip) = (0, 0);
+
+        while index < slices.len() {
+            let mut pending = Vec::with_capacity(slices.len() - index);
+            pending.push(IoSlice::new(&slices[index][skip..]));
+            pending.extend_from_slice(&slices[index + 1..]);
+
+            let mut written = match writer.write_vectored(&pending) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => written,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            // advance past the written bytes
+            while index < slices.len() && written >= slices[index].len() - skip {
+                written -= slices[index].len() - skip;
+                index += 1;
+                skip = 0;
+            }
+            skip += written;
+        }
+
+        Ok(())
+    }
+
+    /// Copies the message into a contiguous buffer
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.len());
+        for slice in self.io_slices() {
+            bytes.extend_from_slice(&slice);
+        }
+        bytes
     }
 }
 
@@ -652,6 +874,98 @@ mod tests {
         assert!(Message::from_vec(&bytes).is_ok());
     }
 
+    /// Emits a name, a length prefixed payload, and a name compressed to the first one
+    fn emit_payload<'a>(encoder: &mut BinEncoder<'a>, payload: &'a [u8], zerocopy: bool) {
+        let name = Name::from_str("www.example.com.").unwrap();
+        name.emit(encoder).unwrap();
+
+        let place = encoder.place::<u16>().unwrap();
+        if zerocopy {
+            encoder.emit_bytes_zerocopy(payload).unwrap();
+        } else {
+            encoder.emit_vec(payload).unwrap();
+        }
+        let len = encoder.len_since_place(&place);
+        place.replace(encoder, len as u16).unwrap();
+
+        name.emit(encoder).unwrap();
+    }
+
+    #[test]
+    fn test_vectored() {
+        let payload = vec![0xAB; 1000];
+
+        let mut copied = Vec::new();
+        emit_payload(&mut BinEncoder::new(&mut copied), &payload, false);
+
+        let mut buffer = Vec::new();
+        let mut encoder = BinEncoder::vectored(&mut buffer);
+        emit_payload(&mut encoder, &payload, true);
+        let vectored = encoder.into_vectored();
+
+        assert_eq!(vectored.len(), copied.len());
+        assert_eq!(vectored.io_slices().len(), 3);
+        assert_eq!(vectored.to_vec(), copied);
+
+        /// Writes at most 7 bytes per call
+        struct Trickle(Vec<u8>);
+
+        impl Write for Trickle {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                let len = buf.len().min(7);
+                self.0.extend_from_slice(&buf[..len]);
+                Ok(len)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut writer = Trickle(Vec::new());
+        vectored.write_to(&mut writer).unwrap();
+        assert_eq!(writer.0, copied);
+
+        let mut writer = Vec::new();
+        vectored.write_to(&mut writer).unwrap();
+        assert_eq!(writer, copied);
+    }
+
+    #[test]
+    fn test_vectored_max_size_and_rollback() {
+        let payload = [1u8; 100];
+        let mut buffer = Vec::new();
+        let mut encoder = BinEncoder::vectored(&mut buffer);
+        encoder.set_max_size(150);
+
+        encoder.emit_bytes_zerocopy(&payload).unwrap();
+        assert_eq!(encoder.len(), 0);
+        assert!(encoder.emit_vec(&[2; 51]).is_err());
+
+        let rollback = encoder.set_rollback();
+        encoder.emit_vec(&[2; 10]).unwrap();
+        encoder.emit_bytes_zerocopy(&payload[..40]).unwrap();
+        assert!(encoder.emit_u8(3).is_err());
+        rollback.rollback(&mut encoder);
+
+        encoder.emit_vec(&[2; 50]).unwrap();
+        let mut expected = payload.to_vec();
+        expected.extend_from_slice(&[2; 50]);
+        assert_eq!(encoder.into_vectored().to_vec(), expected);
+    }
+
+    #[test]
+    fn test_zerocopy_copies_without_gathering() {
+        let payload = [1u8, 2, 3];
+        let mut buffer = Vec::new();
+        let mut encoder = BinEncoder::new(&mut buffer);
+        encoder.emit_bytes_zerocopy(&payload).unwrap();
+
+        assert!(!encoder.is_vectored());
+        assert_eq!(encoder.into_vectored().to_vec(), payload);
+        assert_eq!(buffer, payload);
+    }
+
     #[test]
     fn test_fuzzed() {
         const MESSAGE: &[u8] = include_bytes!("../../../tests/test-data/fuzz-long.rdata");
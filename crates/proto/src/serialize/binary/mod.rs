@@ -7,13 +7,16 @@
 
 //! Binary serialization types
 
+mod compressor;
 mod decoder;
 mod encoder;
 mod restrict;
 
+pub use self::compressor::MessageCompressor;
 pub use self::decoder::{BinDecoder, DecodeError};
 pub use self::encoder::BinEncoder;
 pub use self::encoder::EncodeMode;
+pub use self::encoder::VectoredBytes;
 pub use self::restrict::{Restrict, RestrictedMath, Verified};
 
 #[cfg(test)]
@@ -36,6 +39,14 @@ pub trait BinEncodable {
 
         Ok(bytes)
     }
+
+    /// Returns the number of bytes needed to encode the type, e.g. to allocate a buffer upfront
+    ///
+    /// The default implementation encodes into a scratch buffer, types with a known size override
+    /// it. Names are counted without compression, so the encoded form may be smaller.
+    fn encoded_size(&self) -> usize {
+        self.to_bytes().map_or(0, |bytes| bytes.len())
+    }
 }
 
 /// A trait for types which are serializable to and from DNS binary formats
@@ -54,6 +65,10 @@ impl BinEncodable for u16 {
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
         encoder.emit_u16(*self)
     }
+
+    fn encoded_size(&self) -> usize {
+        2
+    }
 }
 
 impl<'r> BinDecodable<'r> for u16 {
@@ -69,6 +84,10 @@ impl BinEncodable for i32 {
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
         encoder.emit_i32(*self)
     }
+
+    fn encoded_size(&self) -> usize {
+        4
+    }
 }
 
 impl<'r> BinDecodable<'r> for i32 {
@@ -84,6 +103,10 @@ impl BinEncodable for u32 {
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
         encoder.emit_u32(*self)
     }
+
+    fn encoded_size(&self) -> usize {
+        4
+    }
 }
 
 impl<'r> BinDecodable<'r> for u32 {
@@ -99,4 +122,8 @@ impl BinEncodable for Vec<u8> {
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
         encoder.emit_vec(self)
     }
+
+    fn encoded_size(&self) -> usize {
+        self.len()
+    }
 }
@@ -66,6 +66,7 @@ impl RDataParser for RData {
             RecordType::A => Self::A(a::parse(tokens)?),
             RecordType::AAAA => Self::AAAA(aaaa::parse(tokens)?),
             RecordType::ANAME => Self::ANAME(ANAME(name::parse(tokens, origin)?)),
+            RecordType::APL => Self::APL(apl::parse(tokens)?),
             RecordType::ANY => return Err(ParseError::from("parsing ANY doesn't make sense")),
             RecordType::AXFR => return Err(ParseError::from("parsing AXFR doesn't make sense")),
             RecordType::CAA => caa::parse(tokens).map(Self::CAA)?,
@@ -73,7 +74,12 @@ impl RDataParser for RData {
             RecordType::CSYNC => csync::parse(tokens).map(Self::CSYNC)?,
             RecordType::HINFO => Self::HINFO(hinfo::parse(tokens)?),
             RecordType::HTTPS => svcb::parse(tokens).map(HTTPS).map(Self::HTTPS)?,
+            #[cfg(feature = "unstable-deleg")]
+            RecordType::DELEG => svcb::parse(tokens)
+                .map(crate::rr::rdata::DELEG)
+                .map(Self::DELEG)?,
             RecordType::IXFR => return Err(ParseError::from("parsing IXFR doesn't make sense")),
+            RecordType::LOC => Self::LOC(loc::parse(tokens)?),
             RecordType::MX => Self::MX(mx::parse(tokens, origin)?),
             RecordType::NAPTR => Self::NAPTR(naptr::parse(tokens, origin)?),
             RecordType::NULL => Self::NULL(null::parse(tokens)?),
@@ -88,6 +94,9 @@ impl RDataParser for RData {
             RecordType::TLSA => Self::TLSA(tlsa::parse(tokens)?),
             RecordType::TXT => Self::TXT(txt::parse(tokens)?),
             RecordType::SIG => return Err(ParseError::from("parsing SIG doesn't make sense")),
+            #[cfg(feature = "dnssec")]
+            RecordType::DNSKEY => Self::DNSSEC(DNSSECRData::DNSKEY(dnskey::parse(tokens)?)),
+            #[cfg(not(feature = "dnssec"))]
             RecordType::DNSKEY => {
                 return Err(ParseError::from("DNSKEY should be dynamically generated"))
             }
@@ -189,6 +198,25 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg(feature = "unstable-deleg")]
+    fn test_deleg_parse() {
+        let data = "1 ns1.example.net. port=5353 ipv4hint=192.0.2.1";
+        let record = RData::try_from_str(RecordType::DELEG, data).unwrap();
+        let deleg = record.as_deleg().unwrap();
+
+        assert_eq!(deleg.svc_priority(), 1);
+        assert_eq!(
+            deleg.target_name(),
+            &Name::from_str("ns1.example.net.").unwrap()
+        );
+        assert_eq!(deleg.port(), Some(5353));
+        assert_eq!(
+            deleg.ip_hints().collect::<Vec<_>>(),
+            vec!["192.0.2.1".parse::<std::net::IpAddr>().unwrap()]
+        );
+    }
+
     #[test]
     fn test_csync() {
         let tokens = ["123", "1", "A", "NS"];
@@ -227,6 +255,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_loc_parse() {
+        let data = "42 21 54 N 71 06 18 W -24m ( 30m\n 10000m 10m )";
+        let record = RData::try_from_str(RecordType::LOC, data).unwrap();
+
+        assert_eq!(record.record_type(), RecordType::LOC);
+        assert_eq!(
+            record.to_string(),
+            "42 21 54.000 N 71 6 18.000 W -24m 30m 10000m 10m"
+        );
+    }
+
+    #[test]
+    fn test_apl_parse() {
+        let data = "1:192.168.0.0/16 !2:fd00::/8";
+        let record = RData::try_from_str(RecordType::APL, data).unwrap();
+
+        assert_eq!(record.record_type(), RecordType::APL);
+        assert_eq!(record.to_string(), data);
+    }
+
     #[cfg(feature = "dnssec")]
     #[test]
     #[allow(deprecated)]
@@ -0,0 +1,107 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! APL records for lists of address prefixes
+
+use std::net::IpAddr;
+
+use crate::rr::rdata::apl::{AddressPrefix, APL};
+use crate::serialize::txt::errors::{ParseError, ParseErrorKind, ParseResult};
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 3123](https://tools.ietf.org/html/rfc3123#section-5)
+///
+/// ```text
+/// 5. Zone File Syntax
+///
+///    <owner>   IN   <TTL>   APL   {[!]afi:address/prefix}*
+///
+///    foo.example.  IN APL 1:192.168.32.0/21 !1:192.168.38.0/28
+///    bar.example.  IN APL 1:224.0.0.0/4 2:FF00:0:0:0:0:0:0:0/8
+/// ```
+pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(tokens: I) -> ParseResult<APL> {
+    tokens
+        .map(parse_item)
+        .collect::<ParseResult<_>>()
+        .map(APL::new)
+}
+
+fn parse_item(token: &str) -> ParseResult<AddressPrefix> {
+    let invalid = || ParseError::from(ParseErrorKind::Msg(format!("invalid APL item: {token}")));
+
+    let (negation, item) = match token.strip_prefix('!') {
+        Some(item) => (true, item),
+        None => (false, token),
+    };
+    let (family, prefix) = item.split_once(':').ok_or_else(invalid)?;
+    let (address, prefix_len) = prefix.rsplit_once('/').ok_or_else(invalid)?;
+    let prefix_len = prefix_len.parse::<u8>()?;
+
+    let address = match family {
+        "1" => IpAddr::V4(address.parse()?),
+        "2" => IpAddr::V6(address.parse()?),
+        _ => {
+            return Err(
+                ParseErrorKind::Msg(format!("unsupported APL address family: {family}")).into(),
+            )
+        }
+    };
+
+    Ok(AddressPrefix::new(negation, address, prefix_len)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(s: &str) -> ParseResult<APL> {
+        parse(s.split_whitespace())
+    }
+
+    #[test]
+    fn test_parsing() {
+        // examples from RFC 3123 section 8
+        for (presentation, display) in [
+            (
+                "1:192.168.32.0/21 !1:192.168.38.0/28",
+                "1:192.168.32.0/21 !1:192.168.38.0/28",
+            ),
+            (
+                "1:224.0.0.0/4 2:FF00:0:0:0:0:0:0:0/8",
+                "1:224.0.0.0/4 2:ff00::/8",
+            ),
+            ("", ""),
+            (
+                "1:192.168.0.0/16 !2:fd00::/8",
+                "1:192.168.0.0/16 !2:fd00::/8",
+            ),
+        ] {
+            let rdata = parse_str(presentation).unwrap();
+            assert_eq!(rdata.to_string(), display);
+            assert_eq!(parse_str(display).unwrap(), rdata);
+        }
+
+        let rdata = parse_str("!2:fd00::/8").unwrap();
+        let item = rdata.items()[0];
+        assert!(item.negation());
+        assert_eq!(item.family(), 2);
+        assert_eq!(item.address(), "fd00::".parse::<IpAddr>().unwrap());
+        assert_eq!(item.prefix_len(), 8);
+    }
+
+    #[test]
+    fn test_parsing_fails() {
+        assert!(parse_str("192.168.0.0/16").is_err());
+        assert!(parse_str("1:192.168.0.0").is_err());
+        assert!(parse_str("1:192.168.0.0/33").is_err());
+        assert!(parse_str("1:fd00::/8").is_err());
+        assert!(parse_str("2:192.168.0.0/16").is_err());
+        assert!(parse_str("3:192.168.0.0/16").is_err());
+        assert!(parse_str("!!1:192.168.0.0/16").is_err());
+    }
+}
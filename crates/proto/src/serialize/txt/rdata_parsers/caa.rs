@@ -23,6 +23,8 @@ use crate::rr::rdata::caa::{Property, Value};
 use crate::rr::rdata::CAA;
 use crate::serialize::txt::errors::{ParseError, ParseErrorKind, ParseResult};
 
+use super::character_string;
+
 /// Parse the RData from a set of Tokens
 ///
 /// [RFC 6844, DNS Certification Authority Authorization, January 2013](https://tools.ietf.org/html/rfc6844#section-5.1)
@@ -87,7 +89,7 @@ pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResu
                 let url = caa::read_iodef(value_str.as_bytes())?;
                 Value::Url(url)
             }
-            Property::Unknown(_) => Value::Unknown(value_str.as_bytes().to_vec()),
+            Property::Unknown(_) => Value::Unknown(character_string(value_str)?),
         }
     };
 
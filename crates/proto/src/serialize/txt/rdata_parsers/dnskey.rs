@@ -0,0 +1,120 @@
+//! Parser for DNSKEY text form
+
+use crate::rr::dnssec::rdata::DNSKEY;
+use crate::rr::dnssec::Algorithm;
+use crate::serialize::txt::errors::{ParseError, ParseErrorKind, ParseResult};
+
+/// Parse the RData from a set of Tokens
+///
+/// This allows public only keys, e.g. the ZSK of another provider in a multi-signer setup, see
+/// [RFC 8901](https://datatracker.ietf.org/doc/html/rfc8901), to be published in a zone.
+///
+/// [RFC 4034, Resource Records for the DNS Security Extensions](https://datatracker.ietf.org/doc/html/rfc4034#section-2.2)
+/// ```text
+/// 2.2.  The DNSKEY RR Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    The Flag field MUST be represented as an unsigned decimal integer.
+///    Given the currently defined flags, the possible values are: 0, 256,
+///    and 257.
+///
+///    The Protocol Field MUST be represented as an unsigned decimal integer
+///    with a value of 3.
+///
+///    The Algorithm field MUST be represented either as an unsigned decimal
+///    integer or as an algorithm mnemonic as specified in Appendix A.1.
+///
+///    The Public Key field MUST be represented as a Base64 encoding of the
+///    Public Key.  Whitespace is allowed within the Base64 text.  For a
+///    definition of Base64 encoding, see [RFC3548].
+/// ```
+pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<DNSKEY> {
+    let flags_str: &str = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::Message("flags not present")))?;
+    let protocol_str: &str = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::Message("protocol not present")))?;
+    let algorithm_str: &str = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::Message("algorithm not present")))?;
+
+    let flags: u16 = flags_str.parse()?;
+    let zone_key = flags & 0b0000_0001_0000_0000 != 0;
+    let secure_entry_point = flags & 0b0000_0000_0000_0001 != 0;
+    let revoke = flags & 0b0000_0000_1000_0000 != 0;
+
+    let protocol: u8 = protocol_str.parse()?;
+    if protocol != 3 {
+        return Err(ParseError::from(ParseErrorKind::Message(
+            "protocol must be 3",
+        )));
+    }
+
+    #[allow(deprecated)]
+    let algorithm = match algorithm_str {
+        // Mnemonics from Appendix A.1.
+        "RSAMD5" => Algorithm::RSAMD5,
+        "DSA" => Algorithm::DSA,
+        "RSASHA1" => Algorithm::RSASHA1,
+        "RSASHA256" => Algorithm::RSASHA256,
+        "RSASHA512" => Algorithm::RSASHA512,
+        "ECDSAP256SHA256" => Algorithm::ECDSAP256SHA256,
+        "ECDSAP384SHA384" => Algorithm::ECDSAP384SHA384,
+        "ED25519" => Algorithm::ED25519,
+        _ => Algorithm::from_u8(algorithm_str.parse()?),
+    };
+
+    let public_key_str: String = tokens.collect();
+    if public_key_str.is_empty() {
+        return Err(ParseError::from(ParseErrorKind::Message(
+            "public key not present",
+        )));
+    }
+    let public_key = data_encoding::BASE64.decode(public_key_str.as_bytes())?;
+
+    Ok(DNSKEY::new(
+        zone_key,
+        secure_entry_point,
+        revoke,
+        algorithm,
+        public_key,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsing() {
+        let dnskey =
+            parse("257 3 15 l02Woi0iS8Aa25FQkUd9RMzZHJpBoRQwAQEX1SxZJA4=".split(' ')).unwrap();
+        assert!(dnskey.zone_key());
+        assert!(dnskey.secure_entry_point());
+        assert!(!dnskey.revoke());
+        assert_eq!(dnskey.algorithm(), Algorithm::ED25519);
+        assert_eq!(dnskey.flags(), 257);
+        assert_eq!(dnskey.public_key().len(), 32);
+
+        // whitespace is allowed in the public key, and the algorithm can be a mnemonic
+        let split = parse("256 3 ED25519 l02Woi0iS8Aa25FQkUd9 RMzZHJpBoRQwAQEX1SxZJA4=".split(' '))
+            .unwrap();
+        assert!(split.zone_key());
+        assert!(!split.secure_entry_point());
+        assert_eq!(split.public_key(), dnskey.public_key());
+
+        // round trips through the display format
+        let displayed = dnskey.to_string();
+        assert_eq!(parse(displayed.split(' ')).unwrap(), dnskey);
+    }
+
+    #[test]
+    fn test_parsing_errors() {
+        assert!(parse("257 3 15".split(' ')).is_err());
+        assert!(parse("257 2 15 l02Woi0iS8Aa25FQkUd9RMzZHJpBoRQwAQEX1SxZJA4=".split(' ')).is_err());
+        assert!(parse("257 3 15 not-base64!".split(' ')).is_err());
+        assert!(parse("test".split(' ')).is_err());
+    }
+}
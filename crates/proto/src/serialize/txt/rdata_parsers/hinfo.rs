@@ -10,6 +10,8 @@
 use crate::rr::rdata::HINFO;
 use crate::serialize::txt::errors::{ParseError, ParseErrorKind, ParseResult};
 
+use super::character_string;
+
 /// Parse the RData from a set of Tokens
 ///
 /// ```text
@@ -20,12 +22,15 @@ pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResu
     let cpu = tokens
         .next()
         .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("cpu".to_string())))
-        .map(ToString::to_string)?;
+        .and_then(character_string)?;
     let os = tokens
         .next()
         .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("os".to_string())))
-        .map(ToString::to_string)?;
-    Ok(HINFO::new(cpu, os))
+        .and_then(character_string)?;
+    Ok(HINFO::from_bytes(
+        cpu.into_boxed_slice(),
+        os.into_boxed_slice(),
+    ))
 }
 
 #[test]
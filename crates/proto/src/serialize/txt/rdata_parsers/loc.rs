@@ -0,0 +1,228 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! LOC records for the geographical location of a host, network or subnet
+
+use std::iter::Peekable;
+
+use crate::rr::rdata::loc::{
+    encode_precision, DEFAULT_HORIZ_PRE, DEFAULT_SIZE, DEFAULT_VERT_PRE, LOC,
+};
+use crate::serialize::txt::errors::{ParseError, ParseErrorKind, ParseResult};
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 1876](https://tools.ietf.org/html/rfc1876#section-3)
+///
+/// ```text
+/// 3. Master File Format
+///
+///    The LOC record is expressed in a master file in the following format:
+///
+///    <owner> <TTL> <class> LOC ( d1 [m1 [s1]] {"N"|"S"} d2 [m2 [s2]]
+///                                {"E"|"W"} alt["m"] [siz["m"] [hp["m"]
+///                                [vp["m"]]]] )
+///
+///    If omitted, minutes and seconds default to zero, size defaults to 1m,
+///    horizontal precision defaults to 10000m, and vertical precision
+///    defaults to 10m.
+/// ```
+pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(tokens: I) -> ParseResult<LOC> {
+    let mut tokens = tokens.peekable();
+
+    let latitude = parse_angle(&mut tokens, 90, ["N", "S"], "latitude")?;
+    let longitude = parse_angle(&mut tokens, 180, ["E", "W"], "longitude")?;
+
+    let altitude = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("altitude".to_string())))
+        .and_then(|t| parse_decimal(t.strip_suffix('m').unwrap_or(t), 2, "altitude"))?;
+    let altitude = u32::try_from(altitude + 10_000_000)
+        .map_err(|_| ParseErrorKind::Msg(format!("LOC altitude out of range: {altitude}")))?;
+
+    let mut parse_precision = |field: &str, default: u8| -> ParseResult<u8> {
+        let Some(token) = tokens.next() else {
+            return Ok(default);
+        };
+
+        let centimeters = parse_decimal(token.strip_suffix('m').unwrap_or(token), 2, field)?;
+        Ok(encode_precision(centimeters as f64 / 100.0)?)
+    };
+    let size = parse_precision("size", DEFAULT_SIZE)?;
+    let horiz_pre = parse_precision("horizontal precision", DEFAULT_HORIZ_PRE)?;
+    let vert_pre = parse_precision("vertical precision", DEFAULT_VERT_PRE)?;
+
+    if tokens.next().is_some() {
+        return Err(ParseErrorKind::Message("too many fields for LOC").into());
+    }
+
+    Ok(LOC::new(
+        size, horiz_pre, vert_pre, latitude, longitude, altitude,
+    ))
+}
+
+/// Parses `d [m [s]] hemisphere` to thousandths of an arc second, offset by 2^31
+fn parse_angle<'i, I: Iterator<Item = &'i str>>(
+    tokens: &mut Peekable<I>,
+    max_degrees: i64,
+    [positive, negative]: [&str; 2],
+    field: &str,
+) -> ParseResult<u32> {
+    let missing = || ParseError::from(ParseErrorKind::MissingToken(format!("LOC {field}")));
+    let is_hemisphere =
+        |token: &&str| token.eq_ignore_ascii_case(positive) || token.eq_ignore_ascii_case(negative);
+
+    let degrees = tokens.next().ok_or_else(missing)?;
+    let degrees = parse_decimal(degrees, 0, field)?;
+
+    let mut minutes = 0;
+    let mut seconds = 0;
+    if let Some(token) = tokens.next_if(|t| !is_hemisphere(t)) {
+        minutes = parse_decimal(token, 0, field)?;
+        if let Some(token) = tokens.next_if(|t| !is_hemisphere(t)) {
+            seconds = parse_decimal(token, 3, field)?;
+        }
+    }
+
+    let hemisphere = tokens.next().ok_or_else(missing)?;
+    if !is_hemisphere(&hemisphere) {
+        return Err(
+            ParseErrorKind::Msg(format!("invalid LOC {field} hemisphere: {hemisphere}")).into(),
+        );
+    }
+
+    if !(0..=max_degrees).contains(&degrees)
+        || !(0..60).contains(&minutes)
+        || !(0..60_000).contains(&seconds)
+    {
+        return Err(ParseErrorKind::Msg(format!("LOC {field} out of range")).into());
+    }
+
+    let thousandths = (degrees * 60 + minutes) * 60_000 + seconds;
+    if thousandths > max_degrees * 3_600_000 {
+        return Err(ParseErrorKind::Msg(format!("LOC {field} out of range")).into());
+    }
+
+    let thousandths = if hemisphere.eq_ignore_ascii_case(negative) {
+        -thousandths
+    } else {
+        thousandths
+    };
+    Ok(((1_i64 << 31) + thousandths) as u32)
+}
+
+/// Parses a decimal number with at most `digits` fractional digits, scaled by `10^digits`
+fn parse_decimal(token: &str, digits: usize, field: &str) -> ParseResult<i64> {
+    let invalid = || ParseError::from(ParseErrorKind::Msg(format!("invalid LOC {field}: {token}")));
+
+    let (negative, unsigned) = match token.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, token),
+    };
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    if integer.is_empty()
+        || fraction.len() > digits
+        || !integer
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+
+    let integer = integer.parse::<i64>().map_err(|_| invalid())?;
+    let fraction = format!("{fraction:0<digits$}");
+    let fraction = match fraction.is_empty() {
+        true => 0,
+        false => fraction.parse::<i64>().map_err(|_| invalid())?,
+    };
+
+    let value = integer
+        .checked_mul(10_i64.pow(digits as u32))
+        .and_then(|v| v.checked_add(fraction))
+        .ok_or_else(invalid)?;
+    Ok(if negative { -value } else { value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(s: &str) -> ParseResult<LOC> {
+        parse(s.split_whitespace())
+    }
+
+    #[test]
+    fn test_parsing() {
+        // examples from RFC 1876 section 4
+        let rdata = parse_str("42 21 54 N 71 06 18 W -24m 30m").unwrap();
+        assert_eq!(
+            rdata.to_string(),
+            "42 21 54.000 N 71 6 18.000 W -24m 30m 10000m 10m"
+        );
+
+        let rdata = parse_str("42 21 43.952 N 71 5 6.344 W -24m 1m 200m").unwrap();
+        assert_eq!(
+            rdata.to_string(),
+            "42 21 43.952 N 71 5 6.344 W -24m 1m 200m 10m"
+        );
+
+        let rdata = parse_str("52 14 05 N 00 08 50 E 10m").unwrap();
+        assert_eq!(
+            rdata.to_string(),
+            "52 14 5.000 N 0 8 50.000 E 10m 1m 10000m 10m"
+        );
+
+        let rdata = parse_str("32 7 19 S 116 2 25 E 10m").unwrap();
+        assert_eq!(
+            rdata.to_string(),
+            "32 7 19.000 S 116 2 25.000 E 10m 1m 10000m 10m"
+        );
+
+        let rdata = parse_str("42 21 28.764 N 71 00 51.617 W -44m 2000m").unwrap();
+        assert_eq!(
+            rdata.to_string(),
+            "42 21 28.764 N 71 0 51.617 W -44m 2000m 10000m 10m"
+        );
+    }
+
+    #[test]
+    fn test_parsing_optional_fields() {
+        // minutes and seconds may be omitted, the m suffix is optional
+        let rdata = parse_str("42 N 71 6 W 12.5 0.5m 100 0.01m").unwrap();
+        assert_eq!(rdata.latitude(), 42.0);
+        assert_eq!(rdata.longitude(), -(71.0 + 6.0 / 60.0));
+        assert_eq!(rdata.altitude(), 12.5);
+        assert_eq!(rdata.size(), 0.5);
+        assert_eq!(rdata.horizontal_precision(), 100.0);
+        assert_eq!(rdata.vertical_precision(), 0.01);
+        assert_eq!(
+            rdata.to_string(),
+            "42 0 0.000 N 71 6 0.000 W 12.50m 0.50m 100m 0.01m"
+        );
+
+        // the display format parses back to the same record
+        assert_eq!(parse_str(&rdata.to_string()).unwrap(), rdata);
+    }
+
+    #[test]
+    fn test_parsing_fails() {
+        assert!(parse_str("").is_err());
+        assert!(parse_str("42 21 54 N").is_err());
+        assert!(parse_str("42 21 54 N 71 06 18 W").is_err());
+        assert!(parse_str("91 N 71 W 0m").is_err());
+        assert!(parse_str("90 0 1 N 71 W 0m").is_err());
+        assert!(parse_str("42 60 N 71 W 0m").is_err());
+        assert!(parse_str("42 1 1.0001 N 71 W 0m").is_err());
+        assert!(parse_str("42 N 181 W 0m").is_err());
+        assert!(parse_str("42 X 71 W 0m").is_err());
+        assert!(parse_str("42 N 71 N 0m").is_err());
+        assert!(parse_str("42 N 71 W -100000.01m").is_err());
+        assert!(parse_str("42 N 71 W 0m 90000001m").is_err());
+        assert!(parse_str("42 N 71 W 0m 1m 1m 1m 1m").is_err());
+    }
+}
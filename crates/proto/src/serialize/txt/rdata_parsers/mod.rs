@@ -21,11 +21,15 @@
 // each of these module's has the parser for that rdata embedded, to keep the file sizes down...
 pub(crate) mod a;
 pub(crate) mod aaaa;
+pub(crate) mod apl;
 pub(crate) mod caa;
 pub(crate) mod csync;
 #[cfg(feature = "dnssec")]
+pub(crate) mod dnskey;
+#[cfg(feature = "dnssec")]
 pub(crate) mod ds;
 pub(crate) mod hinfo;
+pub(crate) mod loc;
 pub(crate) mod mx;
 pub(crate) mod name;
 pub(crate) mod naptr;
@@ -37,3 +41,66 @@ pub(crate) mod sshfp;
 pub(crate) mod svcb;
 pub(crate) mod tlsa;
 pub(crate) mod txt;
+
+use crate::serialize::txt::{ParseError, ParseResult};
+
+/// Decodes the escape sequences of a character-string, RFC 1035 section 5.1
+///
+/// `\X` is the character X, `\DDD` the octet with the decimal value DDD.
+pub(crate) fn character_string(token: &str) -> ParseResult<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(ch) = chars.next() {
+        let ch = match ch {
+            '\\' => chars
+                .next()
+                .ok_or_else(|| ParseError::from("character-string ends with an escape"))?,
+            ch => {
+                bytes.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+                continue;
+            }
+        };
+
+        if !ch.is_ascii_digit() {
+            bytes.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+            continue;
+        }
+
+        let mut val = 0_u32;
+        for digit in [Some(ch), chars.next(), chars.next()] {
+            let digit = digit
+                .and_then(|digit| digit.to_digit(10))
+                .ok_or_else(|| ParseError::from(format!("bad escape in: {token}")))?;
+            val = val * 10 + digit;
+        }
+        let octet = u8::try_from(val)
+            .map_err(|_| ParseError::from(format!("escaped octet out of range: \\{val}")))?;
+        bytes.push(octet);
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_character_string() {
+        assert_eq!(character_string("plain").unwrap(), b"plain");
+        assert_eq!(
+            character_string(r#"a \"b\" \\ \;"#).unwrap(),
+            br#"a "b" \ ;"#
+        );
+        assert_eq!(
+            character_string(r"\000\010\200\255").unwrap(),
+            [0, 10, 200, 255]
+        );
+        assert_eq!(character_string("h\u{e9}").unwrap(), "h\u{e9}".as_bytes());
+
+        assert!(character_string(r"\256").is_err());
+        assert!(character_string(r"\12").is_err());
+        assert!(character_string(r"\1a2").is_err());
+        assert!(character_string("a\\").is_err());
+    }
+}
@@ -13,6 +13,8 @@ use crate::rr::rdata::naptr::{verify_flags, NAPTR};
 use crate::rr::Name;
 use crate::serialize::txt::errors::{ParseError, ParseErrorKind, ParseResult};
 
+use super::character_string;
+
 /// Parse the RData from a set of Tokens
 ///
 /// ```text
@@ -38,8 +40,8 @@ pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(
     let flags = tokens
         .next()
         .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("flags".to_string())))
-        .map(ToString::to_string)
-        .map(|s| s.into_bytes().into_boxed_slice())?;
+        .and_then(character_string)
+        .map(Vec::into_boxed_slice)?;
     if !verify_flags(&flags) {
         return Err(ParseError::from("bad flags, must be in range [a-zA-Z0-9]"));
     }
@@ -47,14 +49,14 @@ pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(
     let service = tokens
         .next()
         .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("service".to_string())))
-        .map(ToString::to_string)
-        .map(|s| s.into_bytes().into_boxed_slice())?;
+        .and_then(character_string)
+        .map(Vec::into_boxed_slice)?;
 
     let regexp = tokens
         .next()
         .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("regexp".to_string())))
-        .map(ToString::to_string)
-        .map(|s| s.into_bytes().into_boxed_slice())?;
+        .and_then(character_string)
+        .map(Vec::into_boxed_slice)?;
 
     let replacement: Name = tokens
         .next()
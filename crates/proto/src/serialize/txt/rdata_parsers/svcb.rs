@@ -20,6 +20,8 @@ use crate::{
     },
 };
 
+use super::character_string;
+
 /// [RFC 9460 SVCB and HTTPS Resource Records, Nov 2023](https://datatracker.ietf.org/doc/html/rfc9460#section-2.1)
 ///
 /// ```text
@@ -311,7 +313,7 @@ fn parse_ech_config(value: Option<&str>) -> Result<SvcParamValue, ParseError> {
 /// ```
 fn parse_unknown(value: Option<&str>) -> Result<SvcParamValue, ParseError> {
     let unknown: Vec<u8> = if let Some(value) = value {
-        value.as_bytes().to_vec()
+        character_string(value)?
     } else {
         Vec::new()
     };
@@ -437,6 +439,26 @@ mod tests {
         assert_eq!(svcb, svcb_display);
     }
 
+    #[test]
+    fn test_parse_display_unknown() {
+        let svcb: SVCB =
+            parse_record(r#"example.com. 42 SVCB 1 foo.example.com. key667="a\"\\\010\210q""#);
+        let (_, value) = &svcb.svc_params()[0];
+        assert_eq!(
+            value,
+            &SvcParamValue::Unknown(Unknown(b"a\"\\\n\xd2q".to_vec()))
+        );
+
+        let svcb_display = svcb.to_string();
+        assert_eq!(
+            svcb_display,
+            r#"1 foo.example.com. key667="a\"\\\010\210q""#
+        );
+
+        let svcb_display = format!("example.com. 42 IN SVCB {svcb_display}");
+        assert_eq!(parse_record::<SVCB>(&svcb_display), svcb);
+    }
+
     /// sanity check for https
     #[test]
     fn test_parsing_https() {
@@ -517,7 +539,7 @@ mod tests {
                 priority: 1,
                 params: vec![(
                     SvcParamKey::Key(667),
-                    SvcParamValue::Unknown(Unknown(b"hello\\210qoo".into())),
+                    SvcParamValue::Unknown(Unknown(b"hello\xd2qoo".into())),
                 )],
             },
             // Figure 7: Two Quoted IPv6 Hints
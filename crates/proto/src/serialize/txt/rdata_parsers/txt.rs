@@ -19,9 +19,16 @@
 use crate::rr::rdata::TXT;
 use crate::serialize::txt::errors::ParseResult;
 
+use super::character_string;
+
 /// Parse the RData from a set of Tokens
-#[allow(clippy::unnecessary_wraps)]
+///
+/// Every token, quoted or not, is one character-string of the record, its escapes decoded.
 pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(tokens: I) -> ParseResult<TXT> {
-    let txt_data: Vec<String> = tokens.map(ToString::to_string).collect();
-    Ok(TXT::new(txt_data))
+    let txt_data = tokens
+        .map(character_string)
+        .collect::<ParseResult<Vec<_>>>()?;
+    Ok(TXT::try_from_bytes(
+        txt_data.iter().map(Vec::as_slice).collect(),
+    )?)
 }
@@ -10,6 +10,10 @@ use std::{char, iter::Peekable};
 
 use crate::serialize::txt::errors::{LexerError, LexerErrorKind, LexerResult};
 
+/// The maximum number of characters in a single token, large enough for a list of character-strings
+///  filling a 64KB TXT record, with every octet escaped as \DDD
+const MAX_TOKEN_CHARS: usize = 4 * u16::MAX as usize + 4096;
+
 /// A Lexer for Zone files
 pub(crate) struct Lexer<'a> {
     txt: Peekable<CowChars<'a>>,
@@ -34,9 +38,9 @@ impl<'a> Lexer<'a> {
         let mut char_data_vec: Option<Vec<String>> = None;
         let mut char_data: Option<String> = None;
 
-        for i in 0..4096 {
+        for i in 0..MAX_TOKEN_CHARS {
             // max chars in a single lex, helps with issues in the lexer...
-            assert!(i < 4095); // keeps the bounds of the loop defined (nothing lasts forever)
+            assert!(i < MAX_TOKEN_CHARS - 1); // keeps the bounds of the loop defined (nothing lasts forever)
 
             // This is to get around mutability rules such that we can peek at the iter without moving next...
             let ch: Option<char> = self.peek();
@@ -81,7 +85,7 @@ impl<'a> Lexer<'a> {
                         Some('"') => {
                             self.txt.next();
                             char_data = Some(String::new());
-                            self.state = State::Quote;
+                            self.state = State::Quote { is_list: false };
                         }
                         Some(';') => self.state = State::Comment { is_list: false },
                         Some(ch) if ch.is_whitespace() => {
@@ -116,9 +120,22 @@ impl<'a> Lexer<'a> {
                         }
                     }
                 }
-                State::Quote => {
+                State::Quote { is_list } => {
                     match ch {
                         // end and gobble the '"'
+                        Some('"') if is_list => {
+                            self.txt.next();
+                            let char_data = char_data.take().unwrap_or_default();
+                            char_data_vec
+                                .as_mut()
+                                .ok_or_else(|| {
+                                    LexerError::from(LexerErrorKind::IllegalState(
+                                        "char_data_vec is None",
+                                    ))
+                                })?
+                                .push(char_data);
+                            self.state = State::List;
+                        }
                         Some('"') => {
                             self.state = State::RestOfLine;
                             self.txt.next();
@@ -126,9 +143,7 @@ impl<'a> Lexer<'a> {
                                 char_data.take().unwrap_or_else(|| "".into()),
                             )));
                         }
-                        Some('\\') => {
-                            Self::push_to_str(&mut char_data, self.escape_seq()?)?;
-                        }
+                        Some('\\') => self.escape_seq(&mut char_data)?,
                         Some(ch) => {
                             self.txt.next();
                             Self::push_to_str(&mut char_data, ch)?;
@@ -185,6 +200,11 @@ impl<'a> Lexer<'a> {
                     Some(ch) if ch.is_whitespace() => {
                         self.txt.next();
                     }
+                    Some('"') => {
+                        self.txt.next();
+                        char_data = Some(String::new());
+                        self.state = State::Quote { is_list: true };
+                    }
                     Some(ch) if !ch.is_control() && !ch.is_whitespace() => {
                         char_data = Some(String::new());
                         self.state = State::CharData { is_list: true }
@@ -279,54 +299,46 @@ impl<'a> Lexer<'a> {
             })
     }
 
-    fn escape_seq(&mut self) -> LexerResult<char> {
-        // escaped character, let's decode it.
+    /// Checks an escape sequence in a quoted string, `\X` or `\DDD`, and keeps it as is
+    ///
+    /// The escapes are decoded by the parsers of the record data. Only they know whether the
+    ///  string is a name, or a character-string which may contain any octet.
+    fn escape_seq(&mut self, collect: &mut Option<String>) -> LexerResult<()> {
         self.txt.next(); // consume the escape
         let ch = self
             .peek()
             .ok_or_else(|| LexerError::from(LexerErrorKind::EOF))?;
+        if ch.is_control() {
+            return Err(LexerErrorKind::IllegalCharacter(ch).into());
+        }
 
-        if !ch.is_control() {
-            if ch.is_numeric() {
-                // in this case it's an escaped octal: \DDD
-                let d1: u32 = self
-                    .txt
-                    .next()
-                    .ok_or_else(|| LexerError::from(LexerErrorKind::EOF))
-                    .map(|c| {
-                        c.to_digit(10)
-                            .ok_or_else(|| LexerError::from(LexerErrorKind::IllegalCharacter(c)))
-                    })??; // gobble
-                let d2: u32 = self
-                    .txt
-                    .next()
-                    .ok_or_else(|| LexerError::from(LexerErrorKind::EOF))
-                    .map(|c| {
-                        c.to_digit(10)
-                            .ok_or_else(|| LexerError::from(LexerErrorKind::IllegalCharacter(c)))
-                    })??; // gobble
-                let d3: u32 = self
-                    .txt
-                    .next()
-                    .ok_or_else(|| LexerError::from(LexerErrorKind::EOF))
-                    .map(|c| {
-                        c.to_digit(10)
-                            .ok_or_else(|| LexerError::from(LexerErrorKind::IllegalCharacter(c)))
-                    })??; // gobble
-
-                let val: u32 = (d1 << 16) + (d2 << 8) + d3;
-                let ch: char = char::from_u32(val)
-                    .ok_or_else(|| LexerError::from(LexerErrorKind::UnrecognizedOctet(val)))?;
-
-                Ok(ch)
-            } else {
-                // this is an escaped char: \X
-                self.txt.next(); // gobble the char
-                Ok(ch)
-            }
-        } else {
-            Err(LexerErrorKind::IllegalCharacter(ch).into())
+        Self::push_to_str(collect, '\\')?;
+        if !ch.is_numeric() {
+            // this is an escaped char: \X
+            self.txt.next(); // gobble the char
+            return Self::push_to_str(collect, ch);
+        }
+
+        // in this case it's an escaped octet: \DDD
+        let mut val = 0;
+        for _ in 0..3 {
+            let c = self
+                .txt
+                .next()
+                .ok_or_else(|| LexerError::from(LexerErrorKind::EOF))?;
+            let digit = c
+                .to_digit(10)
+                .ok_or_else(|| LexerError::from(LexerErrorKind::IllegalCharacter(c)))?;
+            val = val * 10 + digit;
+            Self::push_to_str(collect, c)?;
         }
+
+        // \DDD is the decimal value of an octet, RFC 1035 section 5.1
+        if val > u32::from(u8::MAX) {
+            return Err(LexerErrorKind::UnrecognizedOctet(val).into());
+        }
+
+        Ok(())
     }
 
     fn peek(&mut self) -> Option<char> {
@@ -365,7 +377,7 @@ pub(crate) enum State {
     //  Name,              // CharData + '.' + CharData
     Comment { is_list: bool }, // ;.*
     At,                        // @
-    Quote,                     // ".*"
+    Quote { is_list: bool },   // ".*"
     Dollar,                    // $
     EOL,                       // \n or \r\n
     EOF,
@@ -379,6 +391,8 @@ pub enum Token {
     /// (..) TODO, this is probably wrong, List maybe should just skip line endings
     List(Vec<String>),
     /// [a-zA-Z, non-control utf8, ., -, 0-9]+, ".*"
+    ///
+    /// Escape sequences, `\X` and `\DDD`, are kept as is, also in quoted strings.
     CharData(String),
     /// @
     At,
@@ -493,19 +507,19 @@ mod lex_test {
         );
         assert_eq!(
             Lexer::new("\"some \\A\"").next_token().unwrap().unwrap(),
-            Token::CharData("some A".to_string())
+            Token::CharData("some \\A".to_string())
         );
         assert_eq!(
             Lexer::new("\"a\\Aa\"").next_token().unwrap().unwrap(),
-            Token::CharData("aAa".to_string())
+            Token::CharData("a\\Aa".to_string())
         );
         assert_eq!(
             Lexer::new("\"a\\$\"").next_token().unwrap().unwrap(),
-            Token::CharData("a$".to_string())
+            Token::CharData("a\\$".to_string())
         );
         assert_eq!(
             Lexer::new("\"a\\077\"").next_token().unwrap().unwrap(),
-            Token::CharData("a\u{707}".to_string())
+            Token::CharData("a\\077".to_string())
         );
 
         assert!(Lexer::new("\"a\\\"").next_token().is_err());
@@ -619,6 +633,36 @@ mod lex_test {
         assert_eq!(next_token(&mut lexer), None);
     }
 
+    #[test]
+    fn quoted_list() {
+        let mut lexer =
+            Lexer::new("( \"v=DKIM1; k=rsa; \"\n  \"p=MIGf\" ; key\n\"\" \"a) \\\"b\")");
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::List(vec![
+                "v=DKIM1; k=rsa; ".to_string(),
+                "p=MIGf".to_string(),
+                "".to_string(),
+                "a) \\\"b".to_string(),
+            ])
+        );
+        assert_eq!(next_token(&mut lexer), None);
+
+        assert!(Lexer::new("(\"abc)").next_token().is_err());
+    }
+
+    #[test]
+    fn escaped_octets() {
+        assert_eq!(
+            Lexer::new("\"\\000\\009\\255\"")
+                .next_token()
+                .unwrap()
+                .unwrap(),
+            Token::CharData("\\000\\009\\255".to_string())
+        );
+        assert!(Lexer::new("\"\\256\"").next_token().is_err());
+    }
+
     #[test]
     #[allow(clippy::cognitive_complexity)]
     fn soa() {
@@ -15,9 +15,11 @@
  */
 
 //! TCP protocol related components for DNS
+mod push_client_stream;
 mod tcp_client_stream;
 mod tcp_stream;
 
+pub use self::push_client_stream::PushClientStream;
 pub use self::tcp_client_stream::{TcpClientConnect, TcpClientStream};
 pub use self::tcp_stream::{Connect, DnsTcpStream, TcpStream};
 
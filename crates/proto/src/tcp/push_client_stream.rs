@@ -0,0 +1,128 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::fmt::{self, Display};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_channel::mpsc;
+use futures_util::stream::{Stream, StreamExt};
+use tracing::{debug, warn};
+
+use crate::error::ProtoError;
+use crate::op::{DsoMessage, Header, OpCode};
+use crate::serialize::binary::{BinDecodable, BinDecoder};
+use crate::tcp::{DnsTcpStream, TcpClientStream};
+use crate::xfer::{DnsClientStream, SerialMessage};
+
+/// A TCP client stream for a long-lived DNS Push Notifications connection, [RFC 8765](https://tools.ietf.org/html/rfc8765)
+///
+/// DSO messages, i.e. the responses to SUBSCRIBE requests and the PUSH messages of the server, are
+///  delivered to the receiver returned by [`PushClientStream::new`], all other messages are
+///  returned by the stream as usual. DSO requests are sent as raw messages through the
+///  `BufDnsStreamHandle` of the underlying `TcpClientStream`, see [`DsoMessage::to_vec`].
+#[must_use = "futures do nothing unless polled"]
+pub struct PushClientStream<S>
+where
+    S: DnsTcpStream,
+{
+    tcp_stream: TcpClientStream<S>,
+    push_sender: mpsc::UnboundedSender<DsoMessage>,
+}
+
+impl<S: DnsTcpStream> PushClientStream<S> {
+    /// Wraps the TcpClientStream, returning the receiver of the DSO messages from the server
+    pub fn new(tcp_stream: TcpClientStream<S>) -> (Self, mpsc::UnboundedReceiver<DsoMessage>) {
+        let (push_sender, push_receiver) = mpsc::unbounded();
+
+        (
+            Self {
+                tcp_stream,
+                push_sender,
+            },
+            push_receiver,
+        )
+    }
+}
+
+impl<S: DnsTcpStream> Display for PushClientStream<S> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(formatter, "Push({})", self.tcp_stream)
+    }
+}
+
+impl<S: DnsTcpStream> DnsClientStream for PushClientStream<S> {
+    type Time = S::Time;
+
+    fn name_server_addr(&self) -> SocketAddr {
+        self.tcp_stream.name_server_addr()
+    }
+}
+
+impl<S: DnsTcpStream> Stream for PushClientStream<S> {
+    type Item = Result<SerialMessage, ProtoError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let message = try_ready_stream!(self.tcp_stream.poll_next_unpin(cx));
+
+            match try_into_dso(message) {
+                Ok(Some(dso)) => {
+                    if self.push_sender.unbounded_send(dso).is_err() {
+                        debug!("push receiver is gone, dropping DSO message");
+                    }
+                }
+                Ok(None) => (),
+                Err(message) => return Poll::Ready(Some(Ok(message))),
+            }
+        }
+    }
+}
+
+/// Returns the DSO message if this is one, otherwise the message is given back
+fn try_into_dso(message: SerialMessage) -> Result<Option<DsoMessage>, SerialMessage> {
+    let mut decoder = BinDecoder::new(message.bytes());
+    match Header::read(&mut decoder) {
+        Ok(header) if header.op_code() == OpCode::Dso => {}
+        _ => return Err(message),
+    }
+
+    match DsoMessage::from_vec(message.bytes()) {
+        Ok(dso) => Ok(Some(dso)),
+        Err(e) => {
+            warn!("dropping malformed DSO message: {}", e);
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::op::{DsoTlv, Message, MessageType};
+    use crate::rr::{rdata::A, Name, RData, Record};
+
+    #[test]
+    fn test_try_into_dso() {
+        let addr = SocketAddr::from(([127, 0, 0, 1], 53));
+        let record = Record::from_rdata(Name::root(), 300, RData::A(A::new(127, 0, 0, 1)));
+        let push = DsoMessage::new(0, MessageType::Query, vec![DsoTlv::Push(vec![record])]);
+
+        let message = SerialMessage::new(push.to_vec().unwrap(), addr);
+        assert_eq!(try_into_dso(message).ok(), Some(Some(push.clone())));
+
+        let message = SerialMessage::new(Message::new().to_vec().unwrap(), addr);
+        let message = try_into_dso(message).unwrap_err();
+        assert!(Message::from_vec(message.bytes()).is_ok());
+
+        let mut malformed = push.to_vec().unwrap();
+        malformed.truncate(malformed.len() - 1);
+        let message = SerialMessage::new(malformed, addr);
+        assert_eq!(try_into_dso(message).ok(), Some(None));
+    }
+}
@@ -16,12 +16,57 @@
 
 //! UDP protocol related components for DNS
 
+use std::io;
+
 mod udp_client_stream;
 mod udp_stream;
 
 pub use self::udp_client_stream::{UdpClientConnect, UdpClientStream};
-pub use self::udp_stream::{DnsUdpSocket, QuicLocalAddr, UdpSocket, UdpStream};
+pub use self::udp_stream::{DnsUdpSocket, QuicLocalAddr, UdpSendCounters, UdpSocket, UdpStream};
 
 /// Max size for the UDP receive buffer as recommended by
 /// [RFC6891](https://datatracker.ietf.org/doc/html/rfc6891#section-6.2.5).
 pub const MAX_RECEIVE_BUFFER_SIZE: usize = 4096;
+
+/// The max size of the truncated response sent in place of a response the socket rejected as too
+///  big, every client accepts 512 bytes over UDP, RFC 1035 section 4.2.1
+pub const TRUNCATED_RESPONSE_SIZE: usize = 512;
+
+/// The OS error code of a datagram which is too big for the socket, the interface or the path MTU
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const EMSGSIZE: Option<i32> = Some(90);
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+const EMSGSIZE: Option<i32> = Some(40);
+#[cfg(any(target_os = "solaris", target_os = "illumos"))]
+const EMSGSIZE: Option<i32> = Some(97);
+#[cfg(windows)]
+const EMSGSIZE: Option<i32> = Some(10040); // WSAEMSGSIZE
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly",
+    target_os = "solaris",
+    target_os = "illumos",
+    windows
+)))]
+const EMSGSIZE: Option<i32> = None;
+
+/// Returns true if the send of a datagram failed because it is too big, i.e. EMSGSIZE
+///
+/// std maps this error to no stable `io::ErrorKind`, so the OS error code is checked. The runtimes
+///  pass the errors of their sockets through unchanged.
+pub fn is_message_too_big(error: &io::Error) -> bool {
+    EMSGSIZE.is_some() && error.raw_os_error() == EMSGSIZE
+}
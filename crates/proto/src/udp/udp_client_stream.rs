@@ -17,9 +17,10 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use futures_util::{future::Future, stream::Stream};
 use tracing::{debug, trace, warn};
 
-use crate::error::ProtoError;
+use crate::error::{ProtoError, ProtoResult};
 use crate::op::message::NoopMessageFinalizer;
-use crate::op::{Message, MessageFinalizer, MessageVerifier};
+use crate::op::{Header, Message, MessageFinalizer, MessageVerifier, Query};
+use crate::serialize::binary::{BinDecodable, BinDecoder};
 use crate::udp::udp_stream::{NextRandomUdpSocket, UdpCreator, UdpSocket};
 use crate::udp::{DnsUdpSocket, MAX_RECEIVE_BUFFER_SIZE};
 use crate::xfer::{DnsRequest, DnsRequestSender, DnsResponse, DnsResponseStream, SerialMessage};
@@ -223,6 +224,7 @@ impl<S: DnsUdpSocket + Send + 'static, MF: MessageFinalizer> DnsRequestSender
         };
 
         let message_id = message.id();
+        let strict = message.options().strict_message_validation;
         let message = SerialMessage::new(bytes, self.name_server);
 
         debug!(
@@ -238,8 +240,15 @@ impl<S: DnsUdpSocket + Send + 'static, MF: MessageFinalizer> DnsRequestSender
             self.timeout,
             Box::pin(async move {
                 let socket: S = NextRandomUdpSocket::new_with_closure(&addr, creator).await?;
-                send_serial_message_inner(message, message_id, verifier, socket, recv_buf_size)
-                    .await
+                send_serial_message_inner(
+                    message,
+                    message_id,
+                    verifier,
+                    socket,
+                    recv_buf_size,
+                    strict,
+                )
+                .await
             }),
         )
         .into()
@@ -303,7 +312,8 @@ async fn send_serial_message_inner<S: DnsUdpSocket + Send>(
     verifier: Option<MessageVerifier>,
     socket: S,
     recv_buf_size: usize,
-) -> Result<DnsResponse, ProtoError> {
+    strict: bool,
+) -> ProtoResult<DnsResponse> {
     let bytes = msg.bytes();
     let addr = msg.addr();
     let len_sent: usize = socket.send_to(bytes, addr).await?;
@@ -341,7 +351,12 @@ async fn send_serial_message_inner<S: DnsUdpSocket + Send>(
             continue;
         }
 
-        match Message::from_vec(&buffer) {
+        let message = if strict {
+            Message::from_vec_strict(&buffer)
+        } else {
+            Message::from_vec(&buffer)
+        };
+        match message {
             Ok(message) => {
                 // Validate the message id in the response matches the value chosen for the query.
                 if msg_id != message.id() {
@@ -380,8 +395,10 @@ async fn send_serial_message_inner<S: DnsUdpSocket + Send>(
                 //  - RR data in responses of dubious reliability.  When a resolver
                 // receives unsolicited responses or RR data other than that
                 // requested, it should discard it without caching it.
-                let request_message = Message::from_vec(msg.bytes())?;
-                let request_queries = request_message.queries();
+                //
+                // Only the header and the question section of the request are read back, the
+                // rest of it was written by us and need not pass the checks applied to responses.
+                let request_queries = read_request_queries(msg.bytes())?;
                 let response_queries = message.queries();
 
                 if !response_queries
@@ -412,14 +429,27 @@ async fn send_serial_message_inner<S: DnsUdpSocket + Send>(
     }
 }
 
+/// Reads the question section of a request we have sent
+fn read_request_queries(request: &[u8]) -> ProtoResult<Vec<Query>> {
+    let mut decoder = BinDecoder::new(request);
+    let header = Header::read(&mut decoder)?;
+    Message::read_queries(&mut decoder, header.query_count() as usize)
+}
+
 #[cfg(test)]
 #[cfg(feature = "tokio-runtime")]
 mod tests {
     #![allow(clippy::dbg_macro, clippy::print_stdout)]
     use crate::tests::udp_client_stream_test;
     use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use std::time::Duration;
     use tokio::{net::UdpSocket as TokioUdpSocket, runtime::Runtime};
 
+    use super::UdpClientStream;
+    use crate::op::{Message, MessageType, Query};
+    use crate::rr::{Name, RecordType};
+    use crate::xfer::{DnsRequest, DnsRequestOptions, DnsRequestSender, FirstAnswer};
+
     #[test]
     fn test_udp_client_stream_ipv4() {
         let io_loop = Runtime::new().expect("failed to create tokio runtime");
@@ -437,4 +467,48 @@ mod tests {
             io_loop,
         )
     }
+
+    #[test]
+    fn test_strict_message_validation() {
+        let io_loop = Runtime::new().expect("failed to create tokio runtime");
+        io_loop.block_on(async {
+            // answers with data after the counted records
+            let server = TokioUdpSocket::bind((Ipv4Addr::LOCALHOST, 0))
+                .await
+                .unwrap();
+            let server_addr = server.local_addr().unwrap();
+            tokio::spawn(async move {
+                let mut buffer = [0_u8; 512];
+                loop {
+                    let (len, addr) = server.recv_from(&mut buffer).await.unwrap();
+                    let mut response = Message::from_vec(&buffer[..len]).unwrap();
+                    response.set_message_type(MessageType::Response);
+                    let mut bytes = response.to_vec().unwrap();
+                    bytes.extend_from_slice(b"trailing");
+                    server.send_to(&bytes, addr).await.unwrap();
+                }
+            });
+
+            let stream = UdpClientStream::with_timeout(server_addr, Duration::from_millis(200));
+            let mut stream: UdpClientStream<TokioUdpSocket> = stream.await.unwrap();
+
+            let mut query = Message::new();
+            query.add_query(Query::query(
+                Name::from_ascii("www.example.com.").unwrap(),
+                RecordType::A,
+            ));
+
+            let options = DnsRequestOptions::default();
+            let request = DnsRequest::new(query.clone(), options);
+            assert!(stream.send_message(request).first_answer().await.is_ok());
+
+            // the response is dropped, the request times out
+            let options = DnsRequestOptions {
+                strict_message_validation: true,
+                ..options
+            };
+            let request = DnsRequest::new(query, options);
+            assert!(stream.send_message(request).first_answer().await.is_err());
+        });
+    }
 }
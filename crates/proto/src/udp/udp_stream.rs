@@ -9,6 +9,7 @@ use std::io;
 use std::marker::PhantomData;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
@@ -19,7 +20,9 @@ use rand;
 use rand::distributions::{uniform::Uniform, Distribution};
 use tracing::{debug, warn};
 
-use crate::udp::MAX_RECEIVE_BUFFER_SIZE;
+use crate::op::{Message, MessageType};
+use crate::rr::rdata::OPT;
+use crate::udp::{is_message_too_big, MAX_RECEIVE_BUFFER_SIZE, TRUNCATED_RESPONSE_SIZE};
 use crate::xfer::{BufDnsStreamHandle, SerialMessage, StreamReceiver};
 use crate::Time;
 
@@ -87,6 +90,28 @@ pub trait UdpSocket: DnsUdpSocket {
 pub struct UdpStream<S: Send> {
     socket: S,
     outbound_messages: StreamReceiver,
+    send_counters: Option<Arc<UdpSendCounters>>,
+}
+
+/// The counters of the messages a [`UdpStream`] could not send because the socket rejected them
+///  as too big, e.g. on links with a small MTU
+#[derive(Debug, Default)]
+pub struct UdpSendCounters {
+    truncated: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl UdpSendCounters {
+    /// The responses which were sent again as an empty truncated response
+    pub fn truncated(&self) -> u64 {
+        self.truncated.load(Ordering::Relaxed)
+    }
+
+    /// The messages which were dropped, as they weren't responses or the truncated response
+    ///  could not be sent either
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
 }
 
 /// To implement quinn::AsyncUdpSocket, we need our custom socket capable of getting local address.
@@ -140,6 +165,7 @@ impl<S: UdpSocket + Send + 'static> UdpStream<S> {
         let stream = Box::new(next_socket.map_ok(move |socket| Self {
             socket,
             outbound_messages,
+            send_counters: None,
         }));
 
         (stream, message_sender)
@@ -166,24 +192,36 @@ impl<S: DnsUdpSocket + Send + 'static> UdpStream<S> {
         let stream = Self {
             socket,
             outbound_messages,
+            send_counters: None,
         };
 
         (stream, message_sender)
     }
 
+    /// Counts the messages the socket rejects as too big in `counters`
+    pub fn with_send_counters(mut self, counters: Arc<UdpSendCounters>) -> Self {
+        self.send_counters = Some(counters);
+        self
+    }
+
     #[allow(unused)]
     pub(crate) fn from_parts(socket: S, outbound_messages: StreamReceiver) -> Self {
         Self {
             socket,
             outbound_messages,
+            send_counters: None,
         }
     }
 }
 
 impl<S: Send> UdpStream<S> {
     #[allow(clippy::type_complexity)]
-    fn pollable_split(&mut self) -> (&mut S, &mut StreamReceiver) {
-        (&mut self.socket, &mut self.outbound_messages)
+    fn pollable_split(&mut self) -> (&mut S, &mut StreamReceiver, Option<&UdpSendCounters>) {
+        (
+            &mut self.socket,
+            &mut self.outbound_messages,
+            self.send_counters.as_deref(),
+        )
     }
 }
 
@@ -191,7 +229,7 @@ impl<S: DnsUdpSocket + Send + 'static> Stream for UdpStream<S> {
     type Item = Result<SerialMessage, io::Error>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let (socket, outbound_messages) = self.pollable_split();
+        let (socket, outbound_messages, send_counters) = self.pollable_split();
         let socket = Pin::new(socket);
         let mut outbound_messages = Pin::new(outbound_messages);
 
@@ -205,12 +243,16 @@ impl<S: DnsUdpSocket + Send + 'static> Stream for UdpStream<S> {
             //   meaning that sending will be preferred over receiving...
 
             // TODO: shouldn't this return the error to send to the sender?
-            if let Err(e) = ready!(socket.poll_send_to(cx, message.bytes(), addr)) {
+            match ready!(socket.poll_send_to(cx, message.bytes(), addr)) {
+                Err(e) if is_message_too_big(&e) => {
+                    send_truncated(&socket, cx, message.bytes(), addr, send_counters)
+                }
                 // Drop the UDP packet and continue
-                warn!(
+                Err(e) => warn!(
                     "error sending message to {} on udp_socket, dropping response: {}",
                     addr, e
-                );
+                ),
+                Ok(_) => {}
             }
 
             // message sent, need to pop the message
@@ -229,6 +271,74 @@ impl<S: DnsUdpSocket + Send + 'static> Stream for UdpStream<S> {
     }
 }
 
+/// Sends an empty truncated response in place of a response the socket rejected as too big
+///
+/// The client retries over TCP, instead of waiting for a response that never arrives. The send is
+///  attempted once, the truncated response is dropped if the socket isn't ready.
+fn send_truncated<S: DnsUdpSocket>(
+    socket: &Pin<&mut S>,
+    cx: &mut Context<'_>,
+    bytes: &[u8],
+    addr: SocketAddr,
+    counters: Option<&UdpSendCounters>,
+) {
+    let counter = match truncated_response(bytes) {
+        Some(truncated) => match socket.poll_send_to(cx, &truncated, addr) {
+            Poll::Ready(Ok(_)) => {
+                debug!(
+                    "response of {} bytes to {} too big for the socket, sent truncated",
+                    bytes.len(),
+                    addr
+                );
+                counters.map(|c| &c.truncated)
+            }
+            Poll::Ready(Err(e)) => {
+                warn!("error sending truncated response to {addr}, dropping response: {e}");
+                counters.map(|c| &c.dropped)
+            }
+            Poll::Pending => {
+                warn!("socket not ready for truncated response to {addr}, dropping response");
+                counters.map(|c| &c.dropped)
+            }
+        },
+        None => {
+            warn!(
+                "message of {} bytes to {} too big for the socket, dropping message",
+                bytes.len(),
+                addr
+            );
+            counters.map(|c| &c.dropped)
+        }
+    };
+
+    if let Some(counter) = counter {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Re-encodes a response with its sections elided and the TC bit set, if it is a response
+///
+/// The question and the EDNS record without its options are kept, which always fits
+///  [`TRUNCATED_RESPONSE_SIZE`].
+fn truncated_response(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut message = Message::from_vec(bytes).ok()?;
+    if message.message_type() != MessageType::Response {
+        return None;
+    }
+
+    message.set_truncated(true);
+    message.take_answers();
+    message.take_name_servers();
+    message.take_additionals();
+    message.take_signature();
+    if let Some(edns) = message.extensions_mut() {
+        *edns.options_mut() = OPT::default();
+    }
+
+    let truncated = message.to_vec().ok()?;
+    (truncated.len() <= TRUNCATED_RESPONSE_SIZE).then_some(truncated)
+}
+
 #[must_use = "futures do nothing unless polled"]
 pub(crate) struct NextRandomUdpSocket<S> {
     name_server: SocketAddr,
@@ -396,8 +506,18 @@ impl DnsUdpSocket for tokio::net::UdpSocket {
 #[cfg(feature = "tokio-runtime")]
 mod tests {
     use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use std::sync::Mutex;
+
+    use futures_util::{FutureExt, StreamExt};
     use tokio::{net::UdpSocket as TokioUdpSocket, runtime::Runtime};
 
+    use super::*;
+    use crate::op::{Edns, Query};
+    use crate::rr::rdata::{opt::EdnsOption, A};
+    use crate::rr::{Name, RData, Record, RecordType};
+    use crate::udp::EMSGSIZE;
+    use crate::xfer::DnsStreamHandle;
+
     #[test]
     fn test_next_random_socket() {
         use crate::tests::next_random_socket_test;
@@ -422,4 +542,125 @@ mod tests {
             Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1),
         )));
     }
+
+    /// Rejects the datagrams above `max_size` bytes like a link with a small MTU
+    struct SmallMtuSocket {
+        max_size: usize,
+        sent: Arc<Mutex<Vec<Vec<u8>>>>,
+    }
+
+    impl DnsUdpSocket for SmallMtuSocket {
+        type Time = crate::TokioTime;
+
+        fn poll_recv_from(
+            &self,
+            _cx: &mut Context<'_>,
+            _buf: &mut [u8],
+        ) -> Poll<io::Result<(usize, SocketAddr)>> {
+            Poll::Pending
+        }
+
+        fn poll_send_to(
+            &self,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+            _target: SocketAddr,
+        ) -> Poll<io::Result<usize>> {
+            if buf.len() > self.max_size {
+                return Poll::Ready(Err(io::Error::from_raw_os_error(EMSGSIZE.unwrap())));
+            }
+
+            self.sent.lock().unwrap().push(buf.to_vec());
+            Poll::Ready(Ok(buf.len()))
+        }
+    }
+
+    fn large_message(message_type: MessageType) -> Vec<u8> {
+        let name = Name::from_ascii("www.example.com.").unwrap();
+        let mut edns = Edns::new();
+        edns.set_max_payload(4096);
+        edns.options_mut().insert(EdnsOption::Padding(64));
+
+        let mut message = Message::new();
+        message
+            .set_id(42)
+            .set_message_type(message_type)
+            .add_query(Query::query(name.clone(), RecordType::A))
+            .set_edns(edns);
+        for i in 0..100 {
+            message.add_answer(Record::from_rdata(
+                name.clone(),
+                300,
+                RData::A(A::new(192, 0, 2, i)),
+            ));
+        }
+
+        message.to_vec().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_too_big_response_is_truncated() {
+        let addr = SocketAddr::from(([192, 0, 2, 1], 53));
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let socket = SmallMtuSocket {
+            max_size: 1232,
+            sent: sent.clone(),
+        };
+        let counters = Arc::new(UdpSendCounters::default());
+        let (stream, mut handle) = UdpStream::with_bound(socket, addr);
+        let mut stream = stream.with_send_counters(counters.clone());
+
+        let response = large_message(MessageType::Response);
+        assert!(response.len() > 1232);
+        handle.send(SerialMessage::new(response, addr)).unwrap();
+        assert!(stream.next().now_or_never().is_none());
+
+        let sent = sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].len() <= TRUNCATED_RESPONSE_SIZE);
+
+        let truncated = Message::from_vec(&sent[0]).unwrap();
+        assert_eq!(truncated.id(), 42);
+        assert!(truncated.truncated());
+        assert_eq!(truncated.queries().len(), 1);
+        assert!(truncated.answers().is_empty());
+        let edns = truncated.extensions().as_ref().unwrap();
+        assert_eq!(edns.max_payload(), 4096);
+        assert!(edns.options().as_ref().is_empty());
+
+        assert_eq!(counters.truncated(), 1);
+        assert_eq!(counters.dropped(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_too_big_query_is_dropped() {
+        let addr = SocketAddr::from(([192, 0, 2, 1], 53));
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let socket = SmallMtuSocket {
+            max_size: 1232,
+            sent: sent.clone(),
+        };
+        let counters = Arc::new(UdpSendCounters::default());
+        let (stream, mut handle) = UdpStream::with_bound(socket, addr);
+        let mut stream = stream.with_send_counters(counters.clone());
+
+        handle
+            .send(SerialMessage::new(large_message(MessageType::Query), addr))
+            .unwrap();
+        assert!(stream.next().now_or_never().is_none());
+
+        assert!(sent.lock().unwrap().is_empty());
+        assert_eq!(counters.truncated(), 0);
+        assert_eq!(counters.dropped(), 1);
+    }
+
+    #[test]
+    fn test_is_message_too_big() {
+        assert!(is_message_too_big(&io::Error::from_raw_os_error(
+            EMSGSIZE.unwrap()
+        )));
+        assert!(!is_message_too_big(&io::Error::from(
+            io::ErrorKind::ConnectionRefused
+        )));
+    }
 }
@@ -23,7 +23,7 @@ const MAX_PAYLOAD_LEN: u16 = 1232;
 /// Implementations of Sinks for sending DNS messages
 pub trait DnsStreamHandle: 'static + Send {
     /// Sends a message to the Handle for delivery to the server.
-    fn send(&mut self, buffer: SerialMessage) -> Result<(), ProtoError>;
+    fn send(&mut self, buffer: SerialMessage) -> ProtoResult<()>;
 }
 
 /// A trait for implementing high level functions of DNS.
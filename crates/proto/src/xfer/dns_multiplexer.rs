@@ -32,8 +32,8 @@ use rand::{
 use tracing::debug;
 
 use crate::{
-    error::{ProtoError, ProtoErrorKind},
-    op::{MessageFinalizer, MessageVerifier},
+    error::{ProtoError, ProtoErrorKind, ProtoResult},
+    op::{Message, MessageFinalizer, MessageVerifier},
     xfer::{
         ignore_send, BufDnsStreamHandle, DnsClientStream, DnsRequest, DnsRequestSender,
         DnsResponse, DnsResponseStream, SerialMessage, CHANNEL_BUFFER_SIZE,
@@ -49,6 +49,7 @@ struct ActiveRequest {
     request_id: u16,
     timeout: Box<dyn Future<Output = ()> + Send + Unpin>,
     verifier: Option<MessageVerifier>,
+    strict: bool,
 }
 
 impl ActiveRequest {
@@ -57,6 +58,7 @@ impl ActiveRequest {
         request_id: u16,
         timeout: Box<dyn Future<Output = ()> + Send + Unpin>,
         verifier: Option<MessageVerifier>,
+        strict: bool,
     ) -> Self {
         Self {
             completion,
@@ -64,6 +66,7 @@ impl ActiveRequest {
             // request,
             timeout,
             verifier,
+            strict,
         }
     }
 
@@ -103,6 +106,7 @@ where
     timeout_duration: Duration,
     stream_handle: BufDnsStreamHandle,
     active_requests: HashMap<u16, ActiveRequest>,
+    max_outstanding: usize,
     signer: Option<Arc<MF>>,
     is_shutdown: bool,
 }
@@ -155,6 +159,7 @@ where
             stream,
             stream_handle: Some(stream_handle),
             timeout_duration,
+            max_outstanding: CHANNEL_BUFFER_SIZE,
             signer,
         }
     }
@@ -188,20 +193,14 @@ where
     }
 
     /// creates random query_id, validates against all active queries
-    fn next_random_query_id(&self) -> Result<u16, ProtoError> {
-        let mut rand = rand::thread_rng();
-
-        for _ in 0..100 {
-            let id: u16 = Standard.sample(&mut rand); // the range is [0 ... u16::max]
-
-            if !self.active_requests.contains_key(&id) {
-                return Ok(id);
-            }
-        }
+    ///
+    /// The ids only need to be unique on this connection. Starting from a random id, the first one
+    ///  not in use is taken, wrapping around at `u16::MAX`, so this only fails if all ids are in use.
+    fn next_random_query_id(&self) -> ProtoResult<u16> {
+        let start: u16 = Standard.sample(&mut rand::thread_rng()); // the range is [0 ... u16::max]
 
-        Err(ProtoError::from(
-            "id space exhausted, consider filing an issue",
-        ))
+        next_free_id(start, |id| self.active_requests.contains_key(&id))
+            .ok_or_else(|| ProtoError::from("id space exhausted, consider filing an issue"))
     }
 
     /// Closes all outstanding completes with a closed stream error
@@ -215,6 +214,13 @@ where
     }
 }
 
+/// Returns the first id from `start` for which `in_use` is false, wrapping around at `u16::MAX`
+fn next_free_id(start: u16, in_use: impl Fn(u16) -> bool) -> Option<u16> {
+    (0..=u16::MAX)
+        .map(|offset| start.wrapping_add(offset))
+        .find(|&id| !in_use(id))
+}
+
 /// A wrapper for a future DnsExchange connection
 #[must_use = "futures do nothing unless polled"]
 pub struct DnsMultiplexerConnect<F, S, MF>
@@ -226,9 +232,26 @@ where
     stream: F,
     stream_handle: Option<BufDnsStreamHandle>,
     timeout_duration: Duration,
+    max_outstanding: usize,
     signer: Option<Arc<MF>>,
 }
 
+impl<F, S, MF> DnsMultiplexerConnect<F, S, MF>
+where
+    F: Future<Output = Result<S, ProtoError>> + Send + Unpin + 'static,
+    S: Stream<Item = Result<SerialMessage, ProtoError>> + Unpin,
+    MF: MessageFinalizer + Send + Sync + 'static,
+{
+    /// Sets the maximum number of queries awaiting a response on the connection
+    ///
+    /// Further queries fail with [`ProtoErrorKind::Busy`] until responses arrive or requests time
+    ///  out. The default is 32, the value is clamped to the number of available message ids.
+    pub fn with_max_outstanding(mut self, max_outstanding: usize) -> Self {
+        self.max_outstanding = max_outstanding.clamp(1, usize::from(u16::MAX) + 1);
+        self
+    }
+}
+
 impl<F, S, MF> Future for DnsMultiplexerConnect<F, S, MF>
 where
     F: Future<Output = Result<S, ProtoError>> + Send + Unpin + 'static,
@@ -248,6 +271,7 @@ where
                 .take()
                 .expect("must not poll after complete"),
             active_requests: HashMap::new(),
+            max_outstanding: self.max_outstanding,
             signer: self.signer.clone(),
             is_shutdown: false,
         }))
@@ -274,7 +298,7 @@ where
             panic!("can not send messages after stream is shutdown")
         }
 
-        if self.active_requests.len() > CHANNEL_BUFFER_SIZE {
+        if self.active_requests.len() >= self.max_outstanding {
             return ProtoError::from(ProtoErrorKind::Busy).into();
         }
 
@@ -283,7 +307,8 @@ where
             Err(e) => return e.into(),
         };
 
-        let (mut request, _) = request.into_parts();
+        let correlation_id = request.correlation_id();
+        let (mut request, options) = request.into_parts();
         request.set_id(query_id);
 
         let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
@@ -313,12 +338,21 @@ where
         let (complete, receiver) = mpsc::channel(CHANNEL_BUFFER_SIZE);
 
         // send the message
-        let active_request =
-            ActiveRequest::new(complete, request.id(), Box::new(timeout), verifier);
+        let active_request = ActiveRequest::new(
+            complete,
+            request.id(),
+            Box::new(timeout),
+            verifier,
+            options.strict_message_validation,
+        );
 
         match request.to_vec() {
             Ok(buffer) => {
-                debug!(id = %active_request.request_id(), "sending message");
+                debug!(
+                    id = %active_request.request_id(),
+                    correlation_id = ?correlation_id,
+                    "sending message"
+                );
                 let serial_message = SerialMessage::new(buffer, self.stream.name_server_addr());
 
                 debug!(
@@ -383,7 +417,7 @@ where
         for i in 0..QOS_MAX_RECEIVE_MSGS {
             match self.stream.poll_next_unpin(cx) {
                 Poll::Ready(Some(Ok(buffer))) => {
-                    messages_received = i;
+                    messages_received = i + 1;
 
                     //   deserialize or log decode_error
                     match buffer.to_message() {
@@ -391,6 +425,13 @@ where
                             Entry::Occupied(mut request_entry) => {
                                 // send the response, complete the request...
                                 let active_request = request_entry.get_mut();
+                                if active_request.strict {
+                                    if let Err(error) = Message::from_vec_strict(buffer.bytes()) {
+                                        ignore_send(active_request.completion.try_send(Err(error)));
+                                        continue;
+                                    }
+                                }
+
                                 if let Some(ref mut verifier) = active_request.verifier {
                                     ignore_send(
                                         active_request
@@ -463,7 +504,7 @@ mod test {
         fn new(
             mut messages: Vec<Message>,
             addr: SocketAddr,
-        ) -> Pin<Box<dyn Future<Output = Result<Self, ProtoError>> + Send>> {
+        ) -> Pin<Box<dyn Future<Output = ProtoResult<Self>> + Send>> {
             messages.reverse(); // so we can pop() and get messages in order
             Box::pin(future::ok(Self {
                 messages,
@@ -714,4 +755,124 @@ mod test {
             axfr_response().len()
         );
     }
+
+    /// Answers all queued requests in reverse order, echoing the query
+    struct PipelinedClientStream {
+        addr: SocketAddr,
+        receiver: StreamReceiver,
+        requests: Vec<Message>,
+    }
+
+    impl fmt::Display for PipelinedClientStream {
+        fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+            write!(formatter, "PipelinedClientStream")
+        }
+    }
+
+    impl Stream for PipelinedClientStream {
+        type Item = Result<SerialMessage, ProtoError>;
+
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            while let Poll::Ready(Some(serial)) = self.receiver.poll_next_unpin(cx) {
+                let request = serial.to_message().unwrap();
+                self.requests.push(request);
+            }
+
+            match self.requests.pop() {
+                Some(mut response) => {
+                    response.set_message_type(MessageType::Response);
+                    Poll::Ready(Some(Ok(SerialMessage::new(
+                        response.to_bytes().unwrap(),
+                        self.addr,
+                    ))))
+                }
+                None => Poll::Pending,
+            }
+        }
+    }
+
+    impl DnsClientStream for PipelinedClientStream {
+        type Time = crate::TokioTime;
+
+        fn name_server_addr(&self) -> SocketAddr {
+            self.addr
+        }
+    }
+
+    fn query(name: &str) -> DnsRequest {
+        let mut msg = Message::new();
+        msg.add_query(Query::query(Name::from_ascii(name).unwrap(), RecordType::A))
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query);
+        DnsRequest::new(msg, DnsRequestOptions::default())
+    }
+
+    #[test]
+    fn test_next_free_id() {
+        assert_eq!(next_free_id(10, |_| false), Some(10));
+        assert_eq!(next_free_id(10, |id| id == 10 || id == 11), Some(12));
+        assert_eq!(next_free_id(u16::MAX, |id| id == u16::MAX), Some(0));
+        assert_eq!(next_free_id(u16::MAX, |id| id != 5), Some(5));
+        assert_eq!(next_free_id(0, |_| true), None);
+    }
+
+    #[tokio::test]
+    async fn test_multiplexer_max_outstanding() {
+        let (query, answer) = a_query_answer();
+        let mut multiplexer = get_mocked_multiplexer(answer).await;
+        multiplexer.max_outstanding = 2;
+
+        let _first = multiplexer.send_message(query.clone());
+        let _second = multiplexer.send_message(query.clone());
+        let ids = multiplexer.active_requests.keys().collect::<Vec<_>>();
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[0], ids[1]);
+
+        let error = multiplexer
+            .send_message(query)
+            .next()
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(error.kind(), ProtoErrorKind::Busy));
+    }
+
+    #[tokio::test]
+    async fn test_multiplexer_pipelined() {
+        let addr = SocketAddr::from(([127, 0, 0, 1], 1234));
+        let (handler, receiver) = BufDnsStreamHandle::new(addr);
+        let stream = Box::pin(future::ok(PipelinedClientStream {
+            addr,
+            receiver,
+            requests: Vec::new(),
+        }));
+        let mut multiplexer = DnsMultiplexer::<_, NoopMessageFinalizer>::with_timeout(
+            stream,
+            handler,
+            Duration::from_millis(100),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let names = ["a.example.com.", "b.example.com.", "c.example.com."];
+        let responses = names
+            .iter()
+            .map(|name| {
+                multiplexer
+                    .send_message(query(name))
+                    .try_collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let responses = tokio::select! {
+            _ = multiplexer.next() => panic!("should never end"),
+            r = future::try_join_all(responses) => r.unwrap(),
+        };
+
+        for (name, response) in names.iter().zip(responses) {
+            assert_eq!(response.len(), 1);
+            assert_eq!(response[0].queries()[0].name().to_ascii(), *name);
+        }
+    }
 }
@@ -31,6 +31,9 @@ pub struct DnsRequestOptions {
     pub max_request_depth: usize,
     /// set recursion desired (or not) for any requests
     pub recursion_desired: bool,
+    /// When true, responses whose header counts don't match their records are dropped, see
+    ///  [`Message::from_vec_strict`](crate::op::Message::from_vec_strict)
+    pub strict_message_validation: bool,
 }
 
 impl Default for DnsRequestOptions {
@@ -42,6 +45,7 @@ impl Default for DnsRequestOptions {
             use_edns: false,
             edns_set_dnssec_ok: false,
             recursion_desired: true,
+            strict_message_validation: false,
         }
     }
 }
@@ -53,12 +57,25 @@ impl Default for DnsRequestOptions {
 pub struct DnsRequest {
     message: Message,
     options: DnsRequestOptions,
+    correlation_id: Option<u64>,
 }
 
 impl DnsRequest {
     /// Returns a new DnsRequest object
     pub fn new(message: Message, options: DnsRequestOptions) -> Self {
-        Self { message, options }
+        Self {
+            message,
+            options,
+            correlation_id: None,
+        }
+    }
+
+    /// Associates an identifier with the request to correlate it in the logs of several services
+    ///
+    /// The identifier is never sent on the wire, it is only logged along with the request.
+    pub fn with_correlation_id(mut self, correlation_id: u64) -> Self {
+        self.correlation_id = Some(correlation_id);
+        self
     }
 
     /// Get the set of request options associated with this request
@@ -66,6 +83,11 @@ impl DnsRequest {
         &self.options
     }
 
+    /// The identifier correlating this request across services, see [`Self::with_correlation_id`]
+    pub fn correlation_id(&self) -> Option<u64> {
+        self.correlation_id
+    }
+
     /// Unwraps the raw message
     pub fn into_parts(self) -> (Message, DnsRequestOptions) {
         (self.message, self.options)
@@ -9,9 +9,11 @@
 
 use std::{
     convert::TryFrom,
+    fmt,
     future::Future,
     io,
-    ops::{Deref, DerefMut},
+    net::SocketAddr,
+    ops::{Deref, DerefMut, Range},
     pin::Pin,
     task::{Context, Poll},
 };
@@ -21,8 +23,12 @@ use futures_util::{ready, stream::Stream};
 
 use crate::{
     error::{ProtoError, ProtoErrorKind, ProtoResult},
-    op::{Message, ResponseCode},
-    rr::{rdata::SOA, resource::RecordRef, RecordType},
+    op::{Message, MessageSection, RecordSpans, ResponseCode},
+    rr::{
+        canonical_name, rdata::SOA, resource::RecordRef, AddressIter, Name, Record, RecordData,
+        RecordDataIter, RecordType, TxtStringIter,
+    },
+    serialize::binary::BinDecoder,
 };
 
 /// A stream returning DNS responses
@@ -134,23 +140,64 @@ type TimeoutFuture = Pin<
 pub struct DnsResponse {
     message: Message,
     buffer: Vec<u8>,
+    spans: Option<RecordSpans>,
+    upstream: Option<UpstreamMetadata>,
 }
 
 // TODO: when `impl Trait` lands in stable, remove this, and expose FlatMap over answers, et al.
 impl DnsResponse {
     /// Constructs a new DnsResponse
     pub fn new(message: Message, buffer: Vec<u8>) -> Self {
-        Self { message, buffer }
+        Self {
+            message,
+            buffer,
+            spans: None,
+            upstream: None,
+        }
     }
 
     /// Constructs a new DnsResponse with a buffer synthesized from the message
-    pub fn from_message(message: Message) -> Result<Self, ProtoError> {
+    pub fn from_message(message: Message) -> ProtoResult<Self> {
         Ok(Self {
             buffer: message.to_vec()?,
             message,
+            spans: None,
+            upstream: None,
         })
     }
 
+    /// Constructs a new DnsResponse by decoding the buffer
+    ///
+    /// If `record_spans` is set, the location of each record in the buffer is kept, see
+    ///  [`Self::record_span`]. This has a cost, so only request it when needed.
+    pub fn from_buffer(buffer: Vec<u8>, record_spans: bool) -> ProtoResult<Self> {
+        let (message, spans) = if record_spans {
+            let (message, spans) = Message::read_with_spans(&mut BinDecoder::new(&buffer))?;
+            (message, Some(spans))
+        } else {
+            (Message::from_vec(&buffer)?, None)
+        };
+
+        Ok(Self {
+            message,
+            buffer,
+            spans,
+            upstream: None,
+        })
+    }
+
+    /// Replaces the id of the message and of the encoded buffer
+    ///
+    /// Transports that send all queries with id 0, DoH and DoQ, use this to hand the response back
+    ///  with the id of the original request.
+    #[cfg(any(feature = "dns-over-https", feature = "dns-over-quic"))]
+    pub(crate) fn restore_id(&mut self, id: u16) {
+        self.message.set_id(id);
+        if let Some(header_id) = self.buffer.get_mut(..2) {
+            header_id.copy_from_slice(&id.to_be_bytes());
+        }
+    }
+
     /// Retrieves the SOA from the response. This will only exist if it was an authoritative response.
     pub fn soa(&self) -> Option<RecordRef<'_, SOA>> {
         self.name_servers()
@@ -222,6 +269,31 @@ impl DnsResponse {
             .map(|(ttl, soa)| (ttl).min(soa.minimum()))
     }
 
+    /// Returns an iterator over the record data of type `T` in the answers, all other records are skipped
+    pub fn iter_as<'a, T: RecordData + 'a>(&'a self) -> RecordDataIter<'a, T> {
+        RecordDataIter::new(self.answers())
+    }
+
+    /// Returns an iterator over the addresses of all A and AAAA records in the answers
+    pub fn addresses(&self) -> AddressIter<'_> {
+        AddressIter::new(self.answers())
+    }
+
+    /// Returns the name the first query resolved to by following the CNAME records in the answers
+    ///
+    /// If the answers contain no CNAME for the query name, the query name is returned, None if the
+    ///  response has no query.
+    pub fn canonical_name(&self) -> Option<&Name> {
+        self.queries()
+            .first()
+            .map(|query| canonical_name(self.answers(), query.name()))
+    }
+
+    /// Returns an iterator over the TXT records in the answers, with the strings of each record joined
+    pub fn txt_strings(&self) -> TxtStringIter<'_> {
+        TxtStringIter::new(self.answers())
+    }
+
     /// Does the response contain any records matching the query name and type?
     pub fn contains_answer(&self) -> bool {
         for q in self.queries() {
@@ -295,11 +367,131 @@ impl DnsResponse {
         }
     }
 
+    /// Classifies the response by the contents of its sections and the AA bit, see [`ResponseKind`]
+    ///
+    /// Unlike [`Self::negative_type`] this tells a referral apart from a NODATA response, which
+    ///  matters to clients talking to authoritative servers without recursion. The CNAME records
+    ///  of the answers are followed first, so a CNAME with a referral for its target is a referral
+    ///  rather than an answer.
+    pub fn response_kind(&self) -> ResponseKind {
+        let response_code = self.response_code();
+        if !matches!(
+            response_code,
+            ResponseCode::NoError | ResponseCode::NXDomain
+        ) {
+            return ResponseKind::Error { response_code };
+        }
+
+        let Some(query) = self.queries().first() else {
+            return ResponseKind::Error { response_code };
+        };
+
+        let name = canonical_name(self.answers(), query.name());
+        let answered = self.answers().iter().any(|record| {
+            let record_type = record.record_type();
+            match query.query_type() {
+                RecordType::ANY => record.name() == name,
+                RecordType::CNAME => record.name() == query.name() && record_type.is_cname(),
+                query_type => record.name() == name && record_type == query_type,
+            }
+        });
+        if answered {
+            return ResponseKind::Answer;
+        }
+
+        let soa = self.soa().as_ref().map(RecordRef::to_owned);
+        if response_code == ResponseCode::NXDomain {
+            return ResponseKind::NxDomain { soa };
+        }
+
+        if soa.is_some() || self.authoritative() {
+            return ResponseKind::NoData { soa };
+        }
+
+        // the deepest zone cut of the authority section, a server refers to a single zone
+        let zone_cut = self
+            .name_servers()
+            .iter()
+            .filter(|record| record.record_type().is_ns())
+            .map(Record::name)
+            .max_by_key(|cut| (cut.zone_of(name), cut.num_labels()));
+        let Some(zone_cut) = zone_cut.cloned() else {
+            return ResponseKind::NoData { soa };
+        };
+
+        let nameservers = self
+            .name_servers()
+            .iter()
+            .filter(|record| record.name() == &zone_cut)
+            .filter_map(|record| record.data().as_ns().map(|ns| ns.0.clone()))
+            .collect::<Vec<_>>();
+        let glue = self
+            .additionals()
+            .iter()
+            .filter(|record| record.record_type().is_ip_addr())
+            .filter(|record| nameservers.contains(record.name()))
+            .cloned()
+            .collect();
+
+        ResponseKind::Referral {
+            upward: zone_cut.is_root() || !zone_cut.zone_of(name),
+            zone_cut,
+            nameservers,
+            glue,
+        }
+    }
+
     /// Borrow the inner buffer from the response
     pub fn as_buffer(&self) -> &[u8] {
         &self.buffer
     }
 
+    /// Returns the response in the wire form it was received in
+    ///
+    /// Re-encoding the `Message` is not guaranteed to produce the same bytes, e.g. name compression
+    ///  may differ, so these are the bytes to use for digests, signature verification or logging.
+    ///  Modifications made to the `Message` through this response are not reflected here, and for a
+    ///  response constructed with [`Self::from_message`] these are the synthesized bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Returns the received bytes with only the message id replaced, for relaying the response
+    ///  unmodified to a client with a different id
+    pub fn bytes_with_id(&self, id: u16) -> Vec<u8> {
+        let mut bytes = self.buffer.clone();
+        if let Some(header_id) = bytes.get_mut(..2) {
+            header_id.copy_from_slice(&id.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Returns the byte range of the record at `index` in the `section` of [`Self::as_bytes`]
+    ///
+    /// This is only available for responses constructed with [`Self::from_buffer`] with
+    ///  `record_spans` set, otherwise `None` is returned.
+    pub fn record_span(&self, index: usize, section: MessageSection) -> Option<Range<usize>> {
+        self.spans.as_ref()?.get(index, section)
+    }
+
+    /// How the response was received from the upstream name server, if the handle records it
+    ///
+    /// This is set by the name server pool of the resolver.
+    pub fn upstream(&self) -> Option<&UpstreamMetadata> {
+        self.upstream.as_ref()
+    }
+
+    /// Mutable access to the metadata of the upstream exchange, see [`Self::upstream`]
+    pub fn upstream_mut(&mut self) -> Option<&mut UpstreamMetadata> {
+        self.upstream.as_mut()
+    }
+
+    /// Records from which name server and how the response was received
+    pub fn set_upstream(&mut self, upstream: UpstreamMetadata) -> &mut Self {
+        self.upstream = Some(upstream);
+        self
+    }
+
     /// Take the inner buffer from the response
     pub fn into_buffer(self) -> Vec<u8> {
         self.buffer
@@ -316,6 +508,50 @@ impl DnsResponse {
     }
 }
 
+/// How a response was received from an upstream name server, see [`DnsResponse::upstream`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct UpstreamMetadata {
+    /// The address of the name server which answered
+    pub server: SocketAddr,
+    /// The protocol the response was received over, e.g. `udp` or `tcp`
+    pub protocol: &'static str,
+    /// True if the request was sent again over TCP, after it failed or was truncated over UDP
+    pub tcp_fallback: bool,
+    /// The number of failed attempts before the response was received
+    pub retries: usize,
+}
+
+impl UpstreamMetadata {
+    /// A response received from `server` over `protocol` on the first attempt
+    pub fn new(server: SocketAddr, protocol: &'static str) -> Self {
+        Self {
+            server,
+            protocol,
+            tcp_fallback: false,
+            retries: 0,
+        }
+    }
+}
+
+impl fmt::Display for UpstreamMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{protocol}://{server} retries:{retries}",
+            protocol = self.protocol,
+            server = self.server,
+            retries = self.retries,
+        )?;
+
+        if self.tcp_fallback {
+            f.write_str(" tcp-fallback")?;
+        }
+
+        Ok(())
+    }
+}
+
 impl Deref for DnsResponse {
     type Target = Message;
 
@@ -645,12 +881,68 @@ impl NegativeType {
     }
 }
 
+/// The kind of a response, see [`DnsResponse::response_kind`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ResponseKind {
+    /// The answers contain records of the query type for the query name, or for the target of
+    ///  its CNAME records
+    Answer,
+    /// A referral to the name servers of a zone closer to the query name
+    ///
+    /// This is a NOERROR response without the AA bit, without answers and with the NS records of
+    ///  the zone cut in the authority section.
+    Referral {
+        /// The name of the zone the name servers are authoritative for
+        zone_cut: Name,
+        /// The names of the name servers of the zone
+        nameservers: Vec<Name>,
+        /// The A and AAAA records of the name servers from the additional section
+        glue: Vec<Record>,
+        /// The zone cut is the root or not an ancestor of the query name, which is a sign of a
+        ///  misconfigured or malicious server, no resolver should follow such a referral
+        upward: bool,
+    },
+    /// The query name exists but has no records of the query type
+    NoData {
+        /// The SOA record of the zone, from the authority section
+        soa: Option<Record<SOA>>,
+    },
+    /// The query name does not exist
+    NxDomain {
+        /// The SOA record of the zone, from the authority section
+        soa: Option<Record<SOA>>,
+    },
+    /// The response code is neither NOERROR nor NXDOMAIN, or the response has no query
+    Error {
+        /// The response code of the response
+        response_code: ResponseCode,
+    },
+}
+
+impl ResponseKind {
+    /// Returns true for a referral that should not be followed by a resolver asking the name
+    ///  servers of `zone`
+    ///
+    /// Besides upward referrals, a referral to `zone` itself or to one of its ancestors makes no
+    ///  progress towards the query name.
+    pub fn is_upward_referral_from(&self, zone: &Name) -> bool {
+        match self {
+            Self::Referral {
+                zone_cut, upward, ..
+            } => *upward || zone_cut.zone_of(zone),
+            _ => false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::op::{Message, Query, ResponseCode};
     use crate::rr::rdata::{A, CNAME, NS, SOA};
     use crate::rr::RData;
     use crate::rr::{Name, Record, RecordType};
+    use crate::serialize::binary::BinDecodable;
 
     use super::*;
 
@@ -904,4 +1196,274 @@ mod tests {
 
         assert!(response.contains_answer());
     }
+
+    #[test]
+    fn test_record_spans() {
+        #[rustfmt::skip]
+        let buffer: Vec<u8> = vec![
+            // header: id 0x1234, response, 1 query, 2 answers, 1 authority, 2 additional
+            0x12, 0x34, 0x81, 0x80, 0x00, 0x01, 0x00, 0x02, 0x00, 0x01, 0x00, 0x02,
+            // 12: query www.example.com. A IN
+            0x03, b'w', b'w', b'w', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c',
+            b'o', b'm', 0x00, 0x00, 0x01, 0x00, 0x01,
+            // 33: answer, pointer to www.example.com. A IN 300 127.0.0.1
+            0xc0, 0x0c, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x04, 127, 0, 0, 1,
+            // 49: answer, pointer to www.example.com. A IN 300 127.0.0.2
+            0xc0, 0x0c, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x04, 127, 0, 0, 2,
+            // 65: authority, pointer to example.com. NS IN 300 ns.example.com.
+            0xc0, 0x10, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x05, 0x02, b'n',
+            b's', 0xc0, 0x10,
+            // 82: additional, OPT with a payload of 1232
+            0x00, 0x00, 0x29, 0x04, 0xd0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // 93: additional, pointer to ns.example.com. A IN 300 127.0.0.3
+            0xc0, 0x4d, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x04, 127, 0, 0, 3,
+        ];
+
+        let response = DnsResponse::from_buffer(buffer.clone(), true).unwrap();
+        assert_eq!(response.as_bytes(), buffer.as_slice());
+        assert_eq!(response.answers().len(), 2);
+        assert!(response.extensions().is_some());
+
+        assert_eq!(
+            response.record_span(0, MessageSection::Answers),
+            Some(33..49)
+        );
+        assert_eq!(
+            response.record_span(1, MessageSection::Answers),
+            Some(49..65)
+        );
+        assert_eq!(response.record_span(2, MessageSection::Answers), None);
+        assert_eq!(
+            response.record_span(0, MessageSection::NameServers),
+            Some(65..82)
+        );
+        // the OPT record is not part of the additionals
+        assert_eq!(response.additionals().len(), 1);
+        assert_eq!(
+            response.record_span(0, MessageSection::Additionals),
+            Some(93..109)
+        );
+        assert_eq!(response.record_span(1, MessageSection::Additionals), None);
+
+        // each span decodes to the record at that index
+        let span = response.record_span(1, MessageSection::Answers).unwrap();
+        let mut decoder = BinDecoder::new(&buffer).clone(span.start as u16);
+        let record = Record::read(&mut decoder).unwrap();
+        assert_eq!(&record, &response.answers()[1]);
+        assert_eq!(decoder.index(), span.end);
+
+        // re-encoding places the OPT record last
+        assert_ne!(response.to_vec().unwrap(), buffer);
+
+        let relayed = response.bytes_with_id(0xabcd);
+        assert_eq!(&relayed[..2], &[0xab, 0xcd]);
+        assert_eq!(&relayed[2..], &buffer[2..]);
+
+        let response = DnsResponse::from_buffer(buffer, false).unwrap();
+        assert_eq!(response.record_span(0, MessageSection::Answers), None);
+    }
+
+    #[test]
+    fn test_typed_answers() {
+        let mut message = Message::new();
+        message.add_query(Query::query(xx(), RecordType::A));
+        message.add_answers(vec![
+            Record::from_rdata(xx(), 88640, RData::CNAME(CNAME(ns1()))),
+            Record::from_rdata(ns1(), 88640, RData::A(A::new(127, 0, 0, 2))),
+        ]);
+        let response = DnsResponse::from_message(message).unwrap();
+
+        assert_eq!(response.canonical_name(), Some(&ns1()));
+        assert_eq!(
+            response.iter_as::<A>().collect::<Vec<_>>(),
+            [&A::new(127, 0, 0, 2)]
+        );
+        assert_eq!(
+            response.addresses().collect::<Vec<_>>(),
+            [std::net::IpAddr::from([127, 0, 0, 2])]
+        );
+        assert_eq!(response.txt_strings().next(), None);
+
+        let empty = DnsResponse::from_message(Message::new()).unwrap();
+        assert_eq!(empty.canonical_name(), None);
+        assert_eq!(empty.addresses().next(), None);
+    }
+
+    fn ns_record(zone: Name, ns: Name) -> Record {
+        Record::from_rdata(zone, 88640, RData::NS(NS(ns)))
+    }
+
+    fn response_kind(message: Message) -> ResponseKind {
+        DnsResponse::from_message(message).unwrap().response_kind()
+    }
+
+    #[test]
+    fn test_response_kind_answer() {
+        let mut message = Message::default();
+        message.add_query(an_query());
+        message.add_answer(an_cname_record());
+        message.add_answer(Record::from_rdata(
+            tripple_xx(),
+            88640,
+            RData::A(A::new(127, 0, 0, 4)),
+        ));
+        assert_eq!(response_kind(message), ResponseKind::Answer);
+
+        // a CNAME query is answered by the CNAME record
+        let mut message = Message::default();
+        message.add_query(Query::query(an_example(), RecordType::CNAME));
+        message.add_answer(an_cname_record());
+        assert_eq!(response_kind(message), ResponseKind::Answer);
+    }
+
+    #[test]
+    fn test_response_kind_referral() {
+        let mut message = Message::default();
+        message.add_query(Query::query(tripple_xx(), RecordType::A));
+        message.add_name_server(ns1_record());
+        message.add_name_server(ns_record(xx(), Name::from_ascii("NS.ELSEWHERE.").unwrap()));
+        message.add_additional(Record::from_rdata(
+            ns1(),
+            88640,
+            RData::A(A::new(127, 0, 0, 2)),
+        ));
+        message.add_additional(Record::from_rdata(
+            Name::from_ascii("UNRELATED.").unwrap(),
+            88640,
+            RData::A(A::new(127, 0, 0, 3)),
+        ));
+
+        let kind = response_kind(message);
+        assert_eq!(
+            kind,
+            ResponseKind::Referral {
+                zone_cut: xx(),
+                nameservers: vec![ns1(), Name::from_ascii("NS.ELSEWHERE.").unwrap()],
+                glue: vec![Record::from_rdata(
+                    ns1(),
+                    88640,
+                    RData::A(A::new(127, 0, 0, 2))
+                )],
+                upward: false,
+            }
+        );
+        assert!(!kind.is_upward_referral_from(&Name::root()));
+        // the servers of XX. referring to XX. again make no progress
+        assert!(kind.is_upward_referral_from(&xx()));
+    }
+
+    #[test]
+    fn test_response_kind_cname_and_referral() {
+        // the answer is a CNAME, and the referral is for the zone of its target
+        let mut message = Message::default();
+        message.add_query(an_query());
+        message.add_answer(an_cname_record());
+        message.add_name_server(ns1_record());
+
+        match response_kind(message) {
+            ResponseKind::Referral {
+                zone_cut, upward, ..
+            } => {
+                assert_eq!(zone_cut, xx());
+                assert!(!upward);
+            }
+            kind => panic!("expected a referral: {kind:?}"),
+        }
+    }
+
+    #[test]
+    fn test_response_kind_upward_referral() {
+        // a referral to the root
+        let mut message = Message::default();
+        message.add_query(another_query());
+        message.add_name_server(ns_record(Name::root(), ns1()));
+
+        let kind = response_kind(message);
+        assert!(
+            matches!(kind, ResponseKind::Referral { upward: true, .. }),
+            "{kind:?}"
+        );
+        assert!(kind.is_upward_referral_from(&example()));
+
+        // a referral to a zone which doesn't contain the query name
+        let mut message = Message::default();
+        message.add_query(another_query());
+        message.add_name_server(ns1_record());
+
+        let kind = response_kind(message);
+        assert!(
+            matches!(kind, ResponseKind::Referral { upward: true, .. }),
+            "{kind:?}"
+        );
+    }
+
+    #[test]
+    fn test_response_kind_no_data() {
+        let mut message = Message::default();
+        message.add_query(another_query());
+        message.add_name_server(soa());
+        message.add_name_server(ns_record(example(), ns1()));
+
+        let soa = RecordRef::try_from(&soa()).unwrap().to_owned();
+        assert_eq!(
+            response_kind(message),
+            ResponseKind::NoData {
+                soa: Some(soa.clone())
+            }
+        );
+
+        // NS records in an authoritative response are not a referral
+        let mut message = Message::default();
+        message.set_authoritative(true);
+        message.add_query(another_query());
+        message.add_name_server(ns_record(example(), ns1()));
+        assert_eq!(response_kind(message), ResponseKind::NoData { soa: None });
+
+        let mut message = Message::default();
+        message.add_query(another_query());
+        assert_eq!(response_kind(message), ResponseKind::NoData { soa: None });
+    }
+
+    #[test]
+    fn test_response_kind_nx_domain() {
+        let mut message = Message::default();
+        message.set_response_code(ResponseCode::NXDomain);
+        message.add_query(an_query());
+        message.add_answer(an_cname_record());
+        message.add_name_server(soa());
+        message.add_name_server(ns1_record());
+
+        let soa = RecordRef::try_from(&soa()).unwrap().to_owned();
+        assert_eq!(
+            response_kind(message),
+            ResponseKind::NxDomain { soa: Some(soa) }
+        );
+
+        let mut message = Message::default();
+        message.set_response_code(ResponseCode::NXDomain);
+        message.add_query(another_query());
+        assert_eq!(response_kind(message), ResponseKind::NxDomain { soa: None });
+    }
+
+    #[test]
+    fn test_response_kind_error() {
+        let mut message = Message::default();
+        message.set_response_code(ResponseCode::ServFail);
+        message.add_query(another_query());
+        message.add_name_server(ns1_record());
+        assert_eq!(
+            response_kind(message),
+            ResponseKind::Error {
+                response_code: ResponseCode::ServFail
+            }
+        );
+
+        let message = Message::default();
+        assert_eq!(
+            response_kind(message),
+            ResponseKind::Error {
+                response_code: ResponseCode::NoError
+            }
+        );
+    }
 }
@@ -14,12 +14,11 @@ use std::{
     sync::Arc,
 };
 
-use async_recursion::async_recursion;
 use futures_util::{
-    future::{self, FutureExt, TryFutureExt},
+    future::{self, Future, FutureExt, TryFutureExt},
     stream::{self, Stream, TryStreamExt},
 };
-use tracing::{debug, trace};
+use tracing::{debug, trace, warn};
 
 use crate::{
     error::{ProtoError, ProtoErrorKind},
@@ -32,7 +31,10 @@ use crate::{
         rdata::opt::EdnsOption,
         Name, Record, RecordData, RecordType,
     },
-    xfer::{dns_handle::DnsHandle, DnsRequest, DnsRequestOptions, DnsResponse, FirstAnswer},
+    xfer::{
+        dns_handle::DnsHandle, DnsRequest, DnsRequestOptions, DnsResponse, DnssecObservations,
+        FirstAnswer,
+    },
 };
 
 #[cfg(feature = "dnssec")]
@@ -58,6 +60,31 @@ where
     request_depth: usize,
     minimum_key_len: usize,
     minimum_algorithm: Algorithm, // used to prevent down grade attacks...
+    unproven_ds_policy: UnprovenDsPolicy,
+    observations: Option<DnssecObservations>,
+}
+
+/// Decides the security status of a delegation, for which the parent zone neither returned DS
+///  records nor a validated proof of their absence.
+///
+/// The absence of the DS records is indeterminate in that case, it could be an insecure
+///  delegation or an attacker stripping the DS records of a secure one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnprovenDsPolicy {
+    /// Validation of the records below the delegation fails, they are bogus
+    #[default]
+    Bogus,
+    /// The delegation is treated as insecure
+    Insecure,
+}
+
+impl UnprovenDsPolicy {
+    fn proof(self) -> Proof {
+        match self {
+            Self::Bogus => Proof::Bogus,
+            Self::Insecure => Proof::Insecure,
+        }
+    }
 }
 
 impl<H> DnssecDnsHandle<H>
@@ -88,9 +115,27 @@ where
             request_depth: 0,
             minimum_key_len: 0,
             minimum_algorithm: Algorithm::RSASHA256,
+            unproven_ds_policy: UnprovenDsPolicy::default(),
+            observations: None,
         }
     }
 
+    /// Sets the policy for delegations, where the absence of the DS records can't be proven.
+    ///
+    /// Defaults to [`UnprovenDsPolicy::Bogus`].
+    pub fn set_unproven_ds_policy(&mut self, policy: UnprovenDsPolicy) -> &mut Self {
+        self.unproven_ds_policy = policy;
+        self
+    }
+
+    /// Records the DNSSEC parameters of the validated zones in `observations`.
+    ///
+    /// Only securely validated RRsets are recorded, by default nothing is recorded.
+    pub fn set_observations(&mut self, observations: DnssecObservations) -> &mut Self {
+        self.observations = Some(observations);
+        self
+    }
+
     /// An internal function used to clone the handle, but maintain some information back to the
     ///  original handle, such as the request_depth such that infinite recursion does
     ///  not occur.
@@ -101,6 +146,8 @@ where
             request_depth: self.request_depth + 1,
             minimum_key_len: self.minimum_key_len,
             minimum_algorithm: self.minimum_algorithm,
+            unproven_ds_policy: self.unproven_ds_policy,
+            observations: self.observations.clone(),
         }
     }
 }
@@ -230,27 +277,32 @@ where
 }
 
 /// Extracts the different sections of a message and verifies the RRSIGs
-async fn verify_response<H>(
+///
+/// The validation of DS records recurses into this function for the parent zone, the future is
+///  boxed to give it a known size.
+fn verify_response<H>(
     handle: DnssecDnsHandle<H>,
     mut message: DnsResponse,
     options: DnsRequestOptions,
-) -> DnsResponse
+) -> Pin<Box<dyn Future<Output = DnsResponse> + Send>>
 where
     H: DnsHandle + Sync + Unpin,
 {
-    let answers = message.take_answers();
-    let nameservers = message.take_name_servers();
-    let additionals = message.take_additionals();
+    Box::pin(async move {
+        let answers = message.take_answers();
+        let nameservers = message.take_name_servers();
+        let additionals = message.take_additionals();
 
-    let answers = verify_rrsets(handle.clone(), answers, options).await;
-    let nameservers = verify_rrsets(handle.clone(), nameservers, options).await;
-    let additionals = verify_rrsets(handle.clone(), additionals, options).await;
+        let answers = verify_rrsets(handle.clone(), answers, options).await;
+        let nameservers = verify_rrsets(handle.clone(), nameservers, options).await;
+        let additionals = verify_rrsets(handle.clone(), additionals, options).await;
 
-    message.insert_answers(answers);
-    message.insert_name_servers(nameservers);
-    message.insert_additionals(additionals);
+        message.insert_answers(answers);
+        message.insert_name_servers(nameservers);
+        message.insert_additionals(additionals);
 
-    message
+        message
+    })
 }
 
 /// This pulls all answers returned in a Message response and returns a future which will
@@ -272,12 +324,14 @@ where
         .filter(|rr| {
             !is_dnssec(rr, RecordType::RRSIG) &&
                              // if we are at a depth greater than 1, we are only interested in proving evaluation chains
-                             //   this means that only DNSKEY and DS are interesting at that point.
+                             //   this means that only DNSKEY and DS, and the NSEC proving the absence of DS,
+                             //   are interesting at that point.
                              //   this protects against looping over things like NS records and DNSKEYs in responses.
                              // TODO: is there a cleaner way to prevent cycles in the evaluations?
                                           (handle.request_depth <= 1 ||
                                            is_dnssec(rr, RecordType::DNSKEY) ||
-                                           is_dnssec(rr, RecordType::DS))
+                                           is_dnssec(rr, RecordType::DS) ||
+                                           is_dnssec(rr, RecordType::NSEC))
         })
         .map(|rr| (rr.name().clone(), rr.record_type()))
     {
@@ -322,6 +376,11 @@ where
         let proof = match proof {
             Ok(proof) => {
                 debug!("verified: {name} record_type: {record_type}",);
+                if let Some(observations) =
+                    handle.observations.as_ref().filter(|_| proof.is_secure())
+                {
+                    observations.observe(&name, record_type, &records);
+                }
                 proof
             }
             Err(ProofError { proof, kind }) => {
@@ -487,7 +546,18 @@ where
     }
 }
 
-#[async_recursion]
+/// Finds the DS records of `zone`
+///
+/// If there are none, the names above `zone` are searched for the closest enclosing secure zone,
+///  i.e. the one with validated DS records or a DNSKEY of the trust anchor. The absence of DS
+///  records always results in a `ProofError`, with the proof of the delegation:
+///
+/// * `Insecure`, the absence of the DS records was proven by a validated NSEC record,
+/// * `Bogus`, an enclosing secure zone exists, without anything proving an insecure delegation,
+/// * `Indeterminate`, there is no secure zone enclosing `zone`.
+///
+/// If the parent zone failed to prove the absence of DS records on the way up, the
+///  `UnprovenDsPolicy` of the handle decides between `Bogus` and `Insecure`.
 async fn find_ds_records<H>(
     handle: &DnssecDnsHandle<H>,
     zone: Name,
@@ -496,65 +566,214 @@ async fn find_ds_records<H>(
 where
     H: DnsHandle + Sync + Unpin,
 {
-    // need to get DS records for each DNSKEY
-    //   there will be a DS record for everything under the root keys
-    let ds_message = handle
-        .lookup(Query::query(zone.clone(), RecordType::DS), options)
-        .first_answer()
-        .await;
-
-    let error: ProtoError = match ds_message {
-        Ok(mut ds_message)
-            if ds_message
-                .answers()
-                .iter()
-                .filter(|r| r.record_type() == RecordType::DS)
-                .any(|r| r.proof().is_secure()) =>
-        {
-            // this is a secure DS record, perfect
-            let ds_records = ds_message
-                .take_answers()
-                .into_iter()
-                .filter_map(|r| Record::<DS>::try_from(r).ok())
-                .collect::<Vec<_>>();
+    // the first name, which could be a zone cut, but without a proof that it isn't
+    let mut unproven = None;
+    let mut name = zone.clone();
+
+    loop {
+        match lookup_ds(handle, &name, options).await {
+            DsLookup::Secure(ds_records) if name == zone => return Ok(ds_records),
+            // the enclosing secure zone
+            DsLookup::Secure(_) => break,
+            DsLookup::Bogus => {
+                return Err(ProofError::new(
+                    Proof::Bogus,
+                    ProofErrorKind::RrsigsUnverified {
+                        name,
+                        record_type: RecordType::DS,
+                    },
+                ))
+            }
+            DsLookup::Insecure => {
+                return Err(ProofError::new(
+                    Proof::Insecure,
+                    ProofErrorKind::DsResponseNsec { name },
+                ))
+            }
+            DsLookup::NoZoneCut => {
+                if is_trust_anchor(handle, &name, options).await {
+                    break;
+                }
+            }
+            DsLookup::Unproven => {
+                if is_trust_anchor(handle, &name, options).await {
+                    break;
+                }
 
-            return Ok(ds_records);
+                unproven.get_or_insert_with(|| name.clone());
+            }
         }
-        Ok(_) => ProtoError::from(ProtoErrorKind::NoError),
-        Err(error) => error,
-    };
 
-    // if the DS record was an NSEC then we have an insecure zone
-    if let Some((query, proof)) = error
-        .kind()
-        .as_nsec()
-        .filter(|(_query, proof)| proof.is_secure())
-    {
-        return Err(ProofError::new(
-            *proof,
-            ProofErrorKind::DsResponseNsec {
-                name: query.name().to_owned(),
-            },
-        ));
+        if name.is_root() {
+            return Err(ProofError::new(
+                Proof::Indeterminate,
+                ProofErrorKind::DsHasNoDnssecProof { name: zone },
+            ));
+        }
+        name = name.base_name();
     }
 
-    // otherwise we need to recursively discover the status of DS up the chain,
-    //   if we find a valid DS, then we're in a Bogus state,
-    //   if we find no records, then we are Indeterminate
-    //   if we get ProofError, our result is the same
-    match find_ds_records(handle, zone.base_name(), options).await {
-        Ok(ds_records) if !ds_records.is_empty() => Err(ProofError::new(
+    // there is a secure zone above, it should have proven the absence of the DS records
+    match unproven {
+        Some(name) => Err(ProofError::new(
+            handle.unproven_ds_policy.proof(),
+            ProofErrorKind::DsHasNoDnssecProof { name },
+        )),
+        None => Err(ProofError::new(
             Proof::Bogus,
             ProofErrorKind::DsRecordShouldExist { name: zone },
         )),
-        Ok(ds_records) if ds_records.is_empty() => Err(ProofError::new(
-            Proof::Indeterminate,
-            ProofErrorKind::DsHasNoDnssecProof { name: zone },
-        )),
-        err => err,
     }
 }
 
+/// What a DS query revealed about a name
+enum DsLookup {
+    /// Validated DS records, the name is a secure delegation
+    Secure(Vec<Record<DS>>),
+    /// DS records which failed validation
+    Bogus,
+    /// A validated NSEC record proves a delegation without DS records
+    Insecure,
+    /// The name is proven to not be a delegation
+    NoZoneCut,
+    /// Nothing that could be validated was returned
+    Unproven,
+}
+
+/// Queries the DS records of `name`, and determines what the response proves
+async fn lookup_ds<H>(
+    handle: &DnssecDnsHandle<H>,
+    name: &Name,
+    options: DnsRequestOptions,
+) -> DsLookup
+where
+    H: DnsHandle + Sync + Unpin,
+{
+    let query = Query::query(name.clone(), RecordType::DS);
+
+    // the query is sent with the wrapped handle, the proof of a negative response is needed here,
+    //  and not the error `send()` would turn it into.
+    let mut ds_options = options;
+    ds_options.use_edns = true;
+    ds_options.edns_set_dnssec_ok = true;
+    let message = match handle
+        .handle
+        .lookup(query.clone(), ds_options)
+        .first_answer()
+        .await
+    {
+        Ok(message) => message,
+        Err(error) => {
+            debug!("failed to lookup DS records of {name}: {error}");
+            return DsLookup::Unproven;
+        }
+    };
+    let mut message = verify_response(handle.clone_with_context(), message, options).await;
+
+    // the answer may contain a CNAME, and the DS records of its target
+    let answers = message.take_answers();
+    let ds_records = answers
+        .iter()
+        .filter(|r| r.name() == name)
+        .filter_map(|r| Record::<DS>::try_from(r.clone()).ok())
+        .collect::<Vec<_>>();
+
+    if ds_records.iter().any(|r| r.proof().is_secure()) {
+        return DsLookup::Secure(
+            ds_records
+                .into_iter()
+                .filter(|r| r.proof().is_secure())
+                .collect(),
+        );
+    } else if ds_records.iter().any(|r| r.proof().is_bogus()) {
+        return DsLookup::Bogus;
+    } else if !ds_records.is_empty() {
+        // e.g. DS records of an unsigned parent zone
+        return DsLookup::Unproven;
+    }
+
+    // a CNAME can't exist at a zone cut, where the child has an SOA record. The child zone
+    //  may still be misconfigured with a CNAME at its apex, in that case it's treated like any
+    //  other name without DS records.
+    if answers
+        .iter()
+        .any(|r| r.name() == name && r.record_type() == RecordType::CNAME && r.proof().is_secure())
+    {
+        debug!("{name} is a CNAME, not a zone cut");
+        return DsLookup::NoZoneCut;
+    }
+
+    let nsecs = message
+        .name_servers()
+        .iter()
+        .filter(|r| is_dnssec(r, RecordType::NSEC) && r.proof().is_secure())
+        .collect::<Vec<_>>();
+
+    if let Some(types) = nsecs
+        .iter()
+        .filter(|r| r.name() == name)
+        .find_map(|r| r.data().as_dnssec().and_then(DNSSECRData::as_nsec))
+        .map(|nsec| nsec.type_bit_maps())
+    {
+        return if types.contains(&RecordType::SOA) {
+            // the NSEC record of the child zone's apex, the child isn't authoritative for the
+            //  DS records of its delegation
+            debug!("DS records of {name} answered by the child zone");
+            DsLookup::Unproven
+        } else if types.contains(&RecordType::DS) {
+            DsLookup::Unproven
+        } else if types.contains(&RecordType::NS) {
+            DsLookup::Insecure
+        } else {
+            DsLookup::NoZoneCut
+        };
+    }
+
+    // otherwise the name must not exist
+    match message
+        .name_servers()
+        .iter()
+        .find(|r| r.record_type() == RecordType::SOA)
+    {
+        Some(soa) if verify_nsec(&query, soa.name(), &nsecs).is_secure() => DsLookup::NoZoneCut,
+        _ => DsLookup::Unproven,
+    }
+}
+
+/// Checks if the DNSKEYs of `name` contain a key of the trust anchor
+async fn is_trust_anchor<H>(
+    handle: &DnssecDnsHandle<H>,
+    name: &Name,
+    options: DnsRequestOptions,
+) -> bool
+where
+    H: DnsHandle + Sync + Unpin,
+{
+    if handle.trust_anchor.is_empty() {
+        return false;
+    }
+
+    // the trust anchor is the proof itself, there is nothing to validate
+    let query = Query::query(name.clone(), RecordType::DNSKEY);
+    handle
+        .handle
+        .lookup(query, options)
+        .first_answer()
+        .await
+        .map_or(false, |message| {
+            message
+                .answers()
+                .iter()
+                .filter(|r| r.name() == name)
+                .filter_map(|r| r.try_borrow::<DNSKEY>())
+                .any(|dnskey| {
+                    handle
+                        .trust_anchor
+                        .contains_dnskey_bytes(dnskey.data().public_key())
+                })
+        })
+}
+
 /// Verifies that a given RRSET is validly signed by any of the specified RRSIGs.
 ///
 /// Invalid RRSIGs will be ignored. RRSIGs will only be validated against DNSKEYs which can
@@ -595,6 +814,32 @@ where
         }
     }
 
+    // DS records are published by the parent zone, a signature made by the child zone is only the
+    //  child vouching for its own keys
+    let rrsigs = if rrset.record_type() == RecordType::DS {
+        let rrsigs = rrsigs
+            .into_iter()
+            .filter(|rrsig| {
+                let signer_name = rrsig.data().signer_name();
+                signer_name != rrset.name() && signer_name.zone_of(rrset.name())
+            })
+            .collect::<Vec<_>>();
+
+        if rrsigs.is_empty() {
+            return Err(ProofError::new(
+                Proof::Bogus,
+                ProofErrorKind::RrsigsUnverified {
+                    name: rrset.name().clone(),
+                    record_type: rrset.record_type(),
+                },
+            ));
+        }
+
+        rrsigs
+    } else {
+        rrsigs
+    };
+
     // the record set is going to be shared across a bunch of futures, Arc for that.
     trace!(
         "default validation {}, record_type: {:?}",
@@ -657,13 +902,38 @@ where
                 .map_err(|proto| {
                     ProofError::new(Proof::Indeterminate, ProofErrorKind::Proto { query, proto })
                 })
-                .map_ok(|message| {
-                    // DNSKEYs were already validated by the inner query in the above lookup
-                    message
+                .and_then(|message| {
+                    // DNSKEYs were already validated by the inner query in the above lookup,
+                    //  a key of an insecure zone can at most make the rrset insecure
+                    let dnskeys = message
                         .answers()
                         .iter()
-                        .filter_map(|r| r.try_borrow::<DNSKEY>())
+                        .filter_map(|r| r.try_borrow::<DNSKEY>());
+                    let proof = dnskeys
+                        .clone()
+                        .filter(|dnskey| dnskey.proof().is_secure())
                         .find_map(|dnskey| verify_rrset_with_dnskey(dnskey, *rrsig, &rrset).ok())
+                        .or_else(|| {
+                            dnskeys
+                                .filter(|dnskey| dnskey.proof().is_insecure())
+                                .find_map(|dnskey| {
+                                    verify_rrset_with_dnskey(dnskey, *rrsig, &rrset).ok()
+                                })
+                                .map(|_| Proof::Insecure)
+                        });
+
+                    // an RRSIG that can't be verified, e.g. made by another signer in a multi-signer
+                    //  setup (RFC 8901) with a key we can't build a chain to, must not stop the
+                    //  remaining RRSIGs from being tried.
+                    future::ready(proof.ok_or_else(|| {
+                        ProofError::new(
+                            Proof::Bogus,
+                            ProofErrorKind::RrsigsUnverified {
+                                name: rrset.name().clone(),
+                                record_type: rrset.record_type(),
+                            },
+                        )
+                    }))
                 })
         })
         .collect::<Vec<_>>();
@@ -682,18 +952,18 @@ where
     // as long as any of the verifications is good, then the RRSET is valid.
     let select = future::select_ok(verifications);
 
-    // this will return either a good result or the errors
+    // this will return either a good result or the last error,
+    //  if none verified we are in a bogus state, DS records were available (see beginning of function), but RRSIGs couldn't be verified
     let (proof, rest) = select.await?;
     drop(rest);
 
-    proof.ok_or_else(||
-        // we are in a bogus state, DS records were available (see beginning of function), but RRSIGs couldn't be verified
-        ProofError::new(Proof::Bogus, ProofErrorKind::RrsigsUnverified{name: rrset.name().clone(), record_type: rrset.record_type()})
-    )
+    Ok(proof)
 }
 
 /// Verifies the given SIG of the RRSET with the DNSKEY.
 #[cfg(feature = "dnssec")]
+// TODO: the kind of ProofError is a public field, it can only be boxed on a breaking release
+#[allow(clippy::result_large_err)]
 fn verify_rrset_with_dnskey(
     dnskey: RecordRef<'_, DNSKEY>,
     rrsig: RecordRef<'_, RRSIG>,
@@ -729,6 +999,13 @@ fn verify_rrset_with_dnskey(
             },
         ));
     }
+    if rrsig.data().algorithm().is_deprecated() {
+        warn!(
+            "{} is signed with the deprecated algorithm {}",
+            rrset.name(),
+            rrsig.data().algorithm()
+        );
+    }
 
     let current_time = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -0,0 +1,388 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The DNSSEC parameters of the validated zones, e.g. for reporting zones still signed with
+//!  weak algorithms or short keys
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use crate::rr::{
+    dnssec::{rdata::DNSSECRData, Algorithm, DigestType, KeySize, Nsec3HashAlgorithm},
+    Name, RData, Record, RecordType,
+};
+
+/// The DNSSEC parameters observed while validating the records of the zones
+///
+/// Only the parameters of securely validated RRsets are recorded. The observations are shared
+///  between all clones, so the same table can be read by e.g. a reporting task while the
+///  validating handle is in use.
+#[derive(Clone, Debug, Default)]
+pub struct DnssecObservations {
+    zones: Arc<Mutex<HashMap<Name, ZoneObservation>>>,
+}
+
+impl DnssecObservations {
+    /// Creates an empty table of observations
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the observation of the zone, if it was validated
+    pub fn get(&self, zone: &Name) -> Option<ZoneObservation> {
+        self.zones.lock().expect("poisoned").get(zone).cloned()
+    }
+
+    /// Returns the observations of all zones, ordered by zone name
+    pub fn snapshot(&self) -> Vec<ZoneObservation> {
+        let mut zones = self
+            .zones
+            .lock()
+            .expect("poisoned")
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        zones.sort_by(|a, b| a.zone.cmp(&b.zone));
+        zones
+    }
+
+    /// Removes all observations
+    pub fn reset(&self) {
+        self.zones.lock().expect("poisoned").clear();
+    }
+
+    /// The number of zones observed
+    pub fn len(&self) -> usize {
+        self.zones.lock().expect("poisoned").len()
+    }
+
+    /// Returns true if no zone was observed
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Records the DNSSEC parameters of a securely validated RRset
+    ///
+    /// `records` may contain other records, only the RRset of `name` and `record_type` and its
+    ///  RRSIGs are looked at.
+    pub(crate) fn observe(&self, name: &Name, record_type: RecordType, records: &[Record]) {
+        let now = SystemTime::now();
+        let mut zones = self.zones.lock().expect("poisoned");
+
+        let rrsigs = records
+            .iter()
+            .filter(|r| r.name() == name)
+            .filter_map(|r| match r.data() {
+                RData::DNSSEC(DNSSECRData::RRSIG(rrsig)) if rrsig.type_covered() == record_type => {
+                    Some(rrsig)
+                }
+                _ => None,
+            });
+
+        // the zone of NSEC and NSEC3 records is their signer
+        let mut signer = None;
+        for rrsig in rrsigs {
+            let zone = zone_entry(&mut zones, rrsig.signer_name(), now);
+            insert_sorted(&mut zone.signature_algorithms, rrsig.algorithm());
+
+            let validity = Duration::from_secs(u64::from(
+                rrsig.sig_expiration().wrapping_sub(rrsig.sig_inception()),
+            ));
+            zone.min_signature_validity = Some(
+                zone.min_signature_validity
+                    .map_or(validity, |min| min.min(validity)),
+            );
+            zone.max_signature_validity = Some(
+                zone.max_signature_validity
+                    .map_or(validity, |max| max.max(validity)),
+            );
+
+            signer.get_or_insert_with(|| rrsig.signer_name().clone());
+        }
+
+        let rrset = records
+            .iter()
+            .filter(|r| r.name() == name && r.record_type() == record_type);
+        for record in rrset {
+            match record.data() {
+                RData::DNSSEC(DNSSECRData::DNSKEY(dnskey)) => {
+                    let Ok(key_tag) = dnskey.calculate_key_tag() else {
+                        continue;
+                    };
+                    let zone = zone_entry(&mut zones, name, now);
+                    if zone.keys.iter().any(|k| k.key_tag == key_tag) {
+                        continue;
+                    }
+                    zone.keys.push(KeyObservation {
+                        key_tag,
+                        algorithm: dnskey.algorithm(),
+                        key_size: dnskey.key_size().ok(),
+                        secure_entry_point: dnskey.secure_entry_point(),
+                    });
+                    zone.keys.sort_by_key(|k| k.key_tag);
+                }
+                RData::DNSSEC(DNSSECRData::DS(ds)) => {
+                    let zone = zone_entry(&mut zones, name, now);
+                    insert_sorted(&mut zone.ds_digest_types, ds.digest_type());
+                }
+                RData::DNSSEC(DNSSECRData::NSEC(_)) => {
+                    if let Some(signer) = &signer {
+                        zone_entry(&mut zones, signer, now).denial = Some(Denial::Nsec);
+                    }
+                }
+                RData::DNSSEC(DNSSECRData::NSEC3(nsec3)) => {
+                    if let Some(signer) = &signer {
+                        zone_entry(&mut zones, signer, now).denial = Some(Denial::Nsec3 {
+                            hash_algorithm: nsec3.hash_algorithm(),
+                            iterations: nsec3.iterations(),
+                            salt_len: nsec3.salt().len(),
+                            opt_out: nsec3.opt_out(),
+                        });
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+fn zone_entry<'z>(
+    zones: &'z mut HashMap<Name, ZoneObservation>,
+    zone: &Name,
+    now: SystemTime,
+) -> &'z mut ZoneObservation {
+    let zone = zones
+        .entry(zone.clone())
+        .or_insert_with(|| ZoneObservation::new(zone.clone(), now));
+    zone.last_seen = now;
+    zone
+}
+
+fn insert_sorted<T: Ord>(values: &mut Vec<T>, value: T) {
+    if let Err(i) = values.binary_search(&value) {
+        values.insert(i, value);
+    }
+}
+
+/// The DNSSEC parameters of a zone
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ZoneObservation {
+    /// The name of the zone
+    pub zone: Name,
+    /// The validated DNSKEYs of the zone, ordered by key tag
+    pub keys: Vec<KeyObservation>,
+    /// The digest types of the validated DS records of the zone, in the parent zone
+    pub ds_digest_types: Vec<DigestType>,
+    /// The algorithms of the RRSIGs made by the zone
+    pub signature_algorithms: Vec<Algorithm>,
+    /// The mechanism of the last validated proof of nonexistence of the zone
+    pub denial: Option<Denial>,
+    /// The shortest validity period, expiration minus inception, of the RRSIGs of the zone
+    pub min_signature_validity: Option<Duration>,
+    /// The longest validity period of the RRSIGs of the zone
+    pub max_signature_validity: Option<Duration>,
+    /// The time this zone was first observed
+    pub first_seen: SystemTime,
+    /// The time this zone was last observed
+    pub last_seen: SystemTime,
+}
+
+impl ZoneObservation {
+    fn new(zone: Name, now: SystemTime) -> Self {
+        Self {
+            zone,
+            keys: Vec::new(),
+            ds_digest_types: Vec::new(),
+            signature_algorithms: Vec::new(),
+            denial: None,
+            min_signature_validity: None,
+            max_signature_validity: None,
+            first_seen: now,
+            last_seen: now,
+        }
+    }
+
+    /// The size of the shortest RSA key of the zone, if it has any
+    pub fn min_rsa_bits(&self) -> Option<u32> {
+        self.keys
+            .iter()
+            .filter_map(|k| k.key_size)
+            .filter(|s| s.is_rsa())
+            .map(KeySize::bits)
+            .min()
+    }
+
+    /// Returns true if the zone is signed with, or delegated by a digest of, SHA-1
+    #[allow(deprecated)]
+    pub fn uses_sha1(&self) -> bool {
+        let sha1_algorithm = |a: &Algorithm| {
+            matches!(
+                a,
+                Algorithm::RSASHA1 | Algorithm::RSASHA1NSEC3SHA1 | Algorithm::DSA
+            )
+        };
+
+        self.signature_algorithms.iter().any(sha1_algorithm)
+            || self.keys.iter().map(|k| &k.algorithm).any(sha1_algorithm)
+            || self.ds_digest_types.contains(&DigestType::SHA1)
+    }
+}
+
+/// A validated DNSKEY of a zone
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct KeyObservation {
+    /// The key tag of the DNSKEY
+    pub key_tag: u16,
+    /// The algorithm of the key
+    pub algorithm: Algorithm,
+    /// The size of the key, `None` if it's of an unsupported algorithm
+    pub key_size: Option<KeySize>,
+    /// The SEP flag, usually set on key signing keys
+    pub secure_entry_point: bool,
+}
+
+/// The mechanism used by a zone to prove the nonexistence of records
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Denial {
+    /// NSEC records, [RFC 4034](https://tools.ietf.org/html/rfc4034#section-4)
+    Nsec,
+    /// Hashed NSEC3 records, [RFC 5155](https://tools.ietf.org/html/rfc5155)
+    Nsec3 {
+        /// The hash algorithm of the owner names
+        hash_algorithm: Nsec3HashAlgorithm,
+        /// The number of additional iterations of the hash
+        iterations: u16,
+        /// The length of the salt in bytes
+        salt_len: usize,
+        /// The opt-out flag, insecure delegations may not be covered
+        opt_out: bool,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rr::dnssec::rdata::{DNSKEY, DS, NSEC3, RRSIG};
+
+    fn rrsig(name: &Name, zone: &Name, covered: RecordType, algorithm: Algorithm) -> Record {
+        let rrsig = RRSIG::new(
+            covered,
+            algorithm,
+            name.num_labels(),
+            3600,
+            1_000_000 + 86_400,
+            1_000_000,
+            1,
+            zone.clone(),
+            vec![],
+        );
+        Record::from_rdata(name.clone(), 3600, RData::DNSSEC(DNSSECRData::RRSIG(rrsig)))
+    }
+
+    #[test]
+    fn test_observe() {
+        let zone = Name::from_ascii("example.com.").unwrap();
+        let observations = DnssecObservations::new();
+        assert!(observations.is_empty());
+
+        let ksk = DNSKEY::new(
+            true,
+            true,
+            false,
+            Algorithm::RSASHA256,
+            include_bytes!("../rr/dnssec/roots/20326.rsa").to_vec(),
+        );
+        let zsk = DNSKEY::new(true, false, false, Algorithm::ECDSAP256SHA256, vec![4; 64]);
+        let records = vec![
+            Record::from_rdata(zone.clone(), 3600, RData::DNSSEC(DNSSECRData::DNSKEY(ksk))),
+            Record::from_rdata(zone.clone(), 3600, RData::DNSSEC(DNSSECRData::DNSKEY(zsk))),
+            rrsig(&zone, &zone, RecordType::DNSKEY, Algorithm::RSASHA256),
+        ];
+        observations.observe(&zone, RecordType::DNSKEY, &records);
+
+        let ds = DS::new(1, Algorithm::RSASHA256, DigestType::SHA1, vec![0; 20]);
+        let records = vec![Record::from_rdata(
+            zone.clone(),
+            3600,
+            RData::DNSSEC(DNSSECRData::DS(ds)),
+        )];
+        observations.observe(&zone, RecordType::DS, &records);
+
+        let hashed = Name::from_ascii("abcd.example.com.").unwrap();
+        let nsec3 = NSEC3::new(
+            Nsec3HashAlgorithm::SHA1,
+            true,
+            10,
+            vec![0xaa; 4],
+            vec![0; 20],
+            vec![RecordType::A],
+        );
+        let records = vec![
+            Record::from_rdata(
+                hashed.clone(),
+                3600,
+                RData::DNSSEC(DNSSECRData::NSEC3(nsec3)),
+            ),
+            rrsig(
+                &hashed,
+                &zone,
+                RecordType::NSEC3,
+                Algorithm::ECDSAP256SHA256,
+            ),
+        ];
+        observations.observe(&hashed, RecordType::NSEC3, &records);
+
+        assert_eq!(observations.len(), 1);
+        let observation = observations.get(&zone).unwrap();
+        assert_eq!(observation.keys.len(), 2);
+        let ksk = observation
+            .keys
+            .iter()
+            .find(|k| k.secure_entry_point)
+            .unwrap();
+        assert_eq!(ksk.algorithm, Algorithm::RSASHA256);
+        assert_eq!(ksk.key_size, Some(KeySize::Rsa(2048)));
+        let zsk = observation
+            .keys
+            .iter()
+            .find(|k| !k.secure_entry_point)
+            .unwrap();
+        assert_eq!(zsk.key_size, Some(KeySize::P256));
+
+        assert_eq!(observation.min_rsa_bits(), Some(2048));
+        assert_eq!(
+            observation.signature_algorithms,
+            [Algorithm::RSASHA256, Algorithm::ECDSAP256SHA256]
+        );
+        assert_eq!(observation.ds_digest_types, [DigestType::SHA1]);
+        assert!(observation.uses_sha1());
+        assert_eq!(
+            observation.denial,
+            Some(Denial::Nsec3 {
+                hash_algorithm: Nsec3HashAlgorithm::SHA1,
+                iterations: 10,
+                salt_len: 4,
+                opt_out: true,
+            })
+        );
+        assert_eq!(
+            observation.min_signature_validity,
+            Some(Duration::from_secs(86_400))
+        );
+        assert!(observation.first_seen <= observation.last_seen);
+
+        observations.reset();
+        assert!(observations.is_empty());
+        assert!(observations.snapshot().is_empty());
+    }
+}
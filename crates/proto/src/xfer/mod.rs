@@ -5,13 +5,16 @@
 //! TODO: this module needs some serious refactoring and normalization.
 
 use std::fmt::{Debug, Display};
+use std::mem;
 use std::net::SocketAddr;
 use std::pin::Pin;
-use std::task::{Context, Poll};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
 
 use futures_channel::mpsc;
 use futures_channel::oneshot;
-use futures_util::future::Future;
+use futures_util::future::{self, Future};
 use futures_util::ready;
 use futures_util::stream::{Fuse, Peekable, Stream, StreamExt};
 use tracing::{debug, warn};
@@ -27,6 +30,9 @@ pub mod dns_response;
 #[cfg(feature = "dnssec")]
 #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
 pub mod dnssec_dns_handle;
+#[cfg(feature = "dnssec")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+pub mod dnssec_observations;
 pub mod retry_dns_handle;
 mod serial_message;
 
@@ -36,10 +42,13 @@ pub use self::dns_exchange::{
 pub use self::dns_handle::{DnsHandle, DnsStreamHandle};
 pub use self::dns_multiplexer::{DnsMultiplexer, DnsMultiplexerConnect};
 pub use self::dns_request::{DnsRequest, DnsRequestOptions};
-pub use self::dns_response::{DnsResponse, DnsResponseStream};
+pub use self::dns_response::{DnsResponse, DnsResponseStream, ResponseKind, UpstreamMetadata};
+#[cfg(feature = "dnssec")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+pub use self::dnssec_dns_handle::{DnssecDnsHandle, UnprovenDsPolicy};
 #[cfg(feature = "dnssec")]
 #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
-pub use self::dnssec_dns_handle::DnssecDnsHandle;
+pub use self::dnssec_observations::DnssecObservations;
 pub use self::retry_dns_handle::RetryDnsHandle;
 pub use self::serial_message::SerialMessage;
 
@@ -66,18 +75,102 @@ pub trait DnsClientStream:
     fn name_server_addr(&self) -> SocketAddr;
 }
 
-/// Receiver handle for peekable fused SerialMessage channel
-pub type StreamReceiver = Peekable<Fuse<mpsc::Receiver<SerialMessage>>>;
-
 const CHANNEL_BUFFER_SIZE: usize = 32;
 
+/// Receiver for the messages sent through the associated [`BufDnsStreamHandle`]s
+///
+/// The stream yields `None`, a clean end of stream, once every handle has been dropped and all
+///  buffered messages have been received. Dropping the receiver, or calling [`Self::close`], is
+///  observable by the handles, all sends will fail and [`BufDnsStreamHandle::closed`] resolves.
+pub struct StreamReceiver {
+    receiver: Peekable<Fuse<mpsc::Receiver<SerialMessage>>>,
+    close: Arc<CloseNotify>,
+}
+
+impl StreamReceiver {
+    /// Peek at the next message without removing it from the stream
+    ///
+    /// The waker is registered with the channel if no message is available yet.
+    pub fn poll_peek(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<&SerialMessage>> {
+        Pin::new(&mut self.get_mut().receiver).poll_peek(cx)
+    }
+
+    /// Closes the receiving side, without dropping it
+    ///
+    /// Messages already buffered can still be received, but all sends on the handles will fail from
+    ///  this point and the handles are notified through [`BufDnsStreamHandle::closed`].
+    pub fn close(&mut self) {
+        self.receiver.get_mut().get_mut().close();
+        self.close.notify();
+    }
+}
+
+impl Stream for StreamReceiver {
+    type Item = SerialMessage;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_next_unpin(cx)
+    }
+}
+
+impl Drop for StreamReceiver {
+    fn drop(&mut self) {
+        self.close.notify();
+    }
+}
+
+/// Notifies all [`BufDnsStreamHandle`]s waiting in `closed` once the [`StreamReceiver`] is gone
+#[derive(Default)]
+struct CloseNotify {
+    closed: AtomicBool,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl CloseNotify {
+    fn notify(&self) {
+        self.closed.store(true, Ordering::Release);
+
+        let wakers = mem::take(&mut *self.wakers.lock().expect("CloseNotify lock poisoned"));
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    fn poll_closed(&self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.closed.load(Ordering::Acquire) {
+            return Poll::Ready(());
+        }
+
+        let mut wakers = self.wakers.lock().expect("CloseNotify lock poisoned");
+
+        // check again now that the lock is held, notify may have run in between
+        if self.closed.load(Ordering::Acquire) {
+            return Poll::Ready(());
+        }
+
+        if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+
+        Poll::Pending
+    }
+}
+
 /// A buffering stream bound to a `SocketAddr`
 ///
 /// This stream handle ensures that all messages sent via this handle have the remote_addr set as the destination for the packet
+///
+/// The channel to the [`StreamReceiver`] is bounded, it holds `32` messages plus one for each
+///  handle. [`DnsStreamHandle::send`] fails immediately when the channel is full, whereas
+///  [`Self::send_async`] waits for the receiver to make room.
+///
+/// Dropping the last handle (including clones) ends the `StreamReceiver` cleanly once it has
+///  received all buffered messages, this is not an error.
 #[derive(Clone)]
 pub struct BufDnsStreamHandle {
     remote_addr: SocketAddr,
     sender: mpsc::Sender<SerialMessage>,
+    close: Arc<CloseNotify>,
 }
 
 impl BufDnsStreamHandle {
@@ -89,11 +182,16 @@ impl BufDnsStreamHandle {
     /// * `sender` - the handle being used to send data to the server
     pub fn new(remote_addr: SocketAddr) -> (Self, StreamReceiver) {
         let (sender, receiver) = mpsc::channel(CHANNEL_BUFFER_SIZE);
-        let receiver = receiver.fuse().peekable();
+        let close = Arc::new(CloseNotify::default());
+        let receiver = StreamReceiver {
+            receiver: receiver.fuse().peekable(),
+            close: close.clone(),
+        };
 
         let this = Self {
             remote_addr,
             sender,
+            close,
         };
 
         (this, receiver)
@@ -106,12 +204,49 @@ impl BufDnsStreamHandle {
         Self {
             remote_addr,
             sender: self.sender.clone(),
+            close: self.close.clone(),
         }
     }
+
+    /// Polls for capacity to send a message
+    ///
+    /// Returns `Pending`, with the waker registered, while the channel is full, and an error if the
+    ///  receiver has been closed.
+    pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<ProtoResult<()>> {
+        self.sender
+            .poll_ready(cx)
+            .map_err(|e| ProtoError::from(format!("mpsc::SendError {e}")))
+    }
+
+    /// Sends a message to the receiver, waiting for capacity if the channel is full
+    ///
+    /// This applies backpressure to the sender, whereas [`DnsStreamHandle::send`] fails immediately.
+    ///  Returns an error if the receiver has been closed.
+    pub async fn send_async(&mut self, buffer: SerialMessage) -> ProtoResult<()> {
+        future::poll_fn(|cx| self.poll_ready(cx)).await?;
+
+        let remote_addr = self.remote_addr;
+        self.sender
+            .start_send(SerialMessage::new(buffer.into_parts().0, remote_addr))
+            .map_err(|e| ProtoError::from(format!("mpsc::SendError {e}")))
+    }
+
+    /// Returns true if the receiver has been closed or dropped
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    /// A future which resolves once the receiver has been closed or dropped
+    ///
+    /// This can be raced against sends to detect that the stream has gone away.
+    pub fn closed(&self) -> impl Future<Output = ()> + Send + 'static {
+        let close = self.close.clone();
+        future::poll_fn(move |cx| close.poll_closed(cx))
+    }
 }
 
 impl DnsStreamHandle for BufDnsStreamHandle {
-    fn send(&mut self, buffer: SerialMessage) -> Result<(), ProtoError> {
+    fn send(&mut self, buffer: SerialMessage) -> ProtoResult<()> {
         let remote_addr: SocketAddr = self.remote_addr;
         let sender: &mut _ = &mut self.sender;
         sender
@@ -296,3 +431,163 @@ where
         Poll::Ready(item)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use futures_util::task::{waker, ArcWake};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl CountingWaker {
+        fn wakes(&self) -> usize {
+            self.0.load(Ordering::SeqCst)
+        }
+    }
+
+    impl ArcWake for CountingWaker {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn message(byte: u8) -> SerialMessage {
+        SerialMessage::new(vec![byte], ([127, 0, 0, 1], 53).into())
+    }
+
+    #[test]
+    fn test_send_async_backpressure() {
+        let counter = Arc::new(CountingWaker::default());
+        let waker = waker(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let (mut handle, mut receiver) = BufDnsStreamHandle::new(([127, 0, 0, 1], 53).into());
+
+        // the buffer, plus the slot reserved for this handle
+        for i in 0..=CHANNEL_BUFFER_SIZE {
+            let mut send = Box::pin(handle.send_async(message(i as u8)));
+            assert!(send.as_mut().poll(&mut cx).is_ready());
+        }
+
+        // the consumer is slow, this send should wait for room
+        let mut send = Box::pin(handle.send_async(message(u8::MAX)));
+        assert!(send.as_mut().poll(&mut cx).is_pending());
+        assert_eq!(counter.wakes(), 0);
+
+        // once the consumer has caught up, the sender is woken and the message is sent
+        for i in 0..=CHANNEL_BUFFER_SIZE {
+            match Pin::new(&mut receiver).poll_next(&mut cx) {
+                Poll::Ready(Some(msg)) => assert_eq!(msg.bytes(), &[i as u8]),
+                _ => panic!("expected a message"),
+            }
+        }
+        assert!(counter.wakes() > 0);
+        assert!(matches!(send.as_mut().poll(&mut cx), Poll::Ready(Ok(()))));
+        drop(send);
+
+        match Pin::new(&mut receiver).poll_next(&mut cx) {
+            Poll::Ready(Some(msg)) => assert_eq!(msg.bytes(), &[u8::MAX]),
+            _ => panic!("expected a message"),
+        }
+    }
+
+    #[test]
+    fn test_receiver_drop_closes_handles() {
+        let counter = Arc::new(CountingWaker::default());
+        let waker = waker(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let (mut handle, receiver) = BufDnsStreamHandle::new(([127, 0, 0, 1], 53).into());
+        let other = handle.with_remote_addr(([127, 0, 0, 2], 53).into());
+
+        let mut closed = Box::pin(handle.closed());
+        let mut other_closed = Box::pin(other.closed());
+        assert!(closed.as_mut().poll(&mut cx).is_pending());
+        assert!(other_closed.as_mut().poll(&mut cx).is_pending());
+        assert!(!handle.is_closed());
+
+        drop(receiver);
+
+        assert!(counter.wakes() > 0);
+        assert!(closed.as_mut().poll(&mut cx).is_ready());
+        assert!(other_closed.as_mut().poll(&mut cx).is_ready());
+        assert!(handle.is_closed());
+        assert!(handle.send(message(1)).is_err());
+
+        let mut send = Box::pin(handle.send_async(message(1)));
+        assert!(matches!(send.as_mut().poll(&mut cx), Poll::Ready(Err(_))));
+    }
+
+    #[test]
+    fn test_receiver_close_keeps_buffered() {
+        let counter = Arc::new(CountingWaker::default());
+        let waker = waker(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let (mut handle, mut receiver) = BufDnsStreamHandle::new(([127, 0, 0, 1], 53).into());
+        handle.send(message(1)).expect("send failed");
+
+        receiver.close();
+        assert!(Box::pin(handle.closed()).as_mut().poll(&mut cx).is_ready());
+        assert!(handle.send(message(2)).is_err());
+
+        assert!(matches!(
+            Pin::new(&mut receiver).poll_next(&mut cx),
+            Poll::Ready(Some(_))
+        ));
+        assert!(matches!(
+            Pin::new(&mut receiver).poll_next(&mut cx),
+            Poll::Ready(None)
+        ));
+    }
+
+    #[test]
+    fn test_handle_drop_ends_stream() {
+        let counter = Arc::new(CountingWaker::default());
+        let waker = waker(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let (mut handle, mut receiver) = BufDnsStreamHandle::new(([127, 0, 0, 1], 53).into());
+        let other = handle.clone();
+        handle.send(message(1)).expect("send failed");
+
+        assert!(matches!(
+            Pin::new(&mut receiver).poll_next(&mut cx),
+            Poll::Ready(Some(_))
+        ));
+        assert!(Pin::new(&mut receiver).poll_next(&mut cx).is_pending());
+
+        drop(handle);
+        assert!(Pin::new(&mut receiver).poll_next(&mut cx).is_pending());
+
+        // dropping the last handle is a clean end of the stream
+        drop(other);
+        assert!(counter.wakes() > 0);
+        assert!(matches!(
+            Pin::new(&mut receiver).poll_next(&mut cx),
+            Poll::Ready(None)
+        ));
+    }
+
+    #[test]
+    fn test_no_busy_wakeups() {
+        let counter = Arc::new(CountingWaker::default());
+        let waker = waker(counter.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let (handle, mut receiver) = BufDnsStreamHandle::new(([127, 0, 0, 1], 53).into());
+        let mut closed = Box::pin(handle.closed());
+
+        // an executor only polls again after a wakeup, nothing has happened so there should be none
+        for _ in 0..10 {
+            assert!(Pin::new(&mut receiver).poll_next(&mut cx).is_pending());
+            assert!(Pin::new(&mut receiver).poll_peek(&mut cx).is_pending());
+            assert!(closed.as_mut().poll(&mut cx).is_pending());
+        }
+        assert_eq!(counter.wakes(), 0);
+    }
+}
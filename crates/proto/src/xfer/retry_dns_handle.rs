@@ -71,6 +71,7 @@ where
             handle: self.handle.clone(),
             stream,
             remaining_attempts: self.attempts,
+            retries: 0,
         })
     }
 }
@@ -84,6 +85,7 @@ where
     handle: H,
     stream: <H as DnsHandle>::Response,
     remaining_attempts: usize,
+    retries: usize,
 }
 
 impl<H: DnsHandle + Unpin> Stream for RetrySendStream<H> {
@@ -107,6 +109,13 @@ impl<H: DnsHandle + Unpin> Stream for RetrySendStream<H> {
                     //  then we can just reuse it... and no clone necessary
                     let request = self.request.clone();
                     self.stream = self.handle.send(request);
+                    self.retries += 1;
+                }
+                Poll::Ready(Some(Ok(mut response))) => {
+                    if let Some(upstream) = response.upstream_mut() {
+                        upstream.retries += self.retries;
+                    }
+                    return Poll::Ready(Some(Ok(response)));
                 }
                 poll => return poll,
             }
@@ -140,7 +149,7 @@ mod test {
     use super::*;
     use crate::error::*;
     use crate::op::*;
-    use crate::xfer::FirstAnswer;
+    use crate::xfer::{FirstAnswer, UpstreamMetadata};
     use futures_executor::block_on;
     use futures_util::future::*;
     use futures_util::stream::*;
@@ -166,7 +175,9 @@ mod test {
             if (i > self.retries || self.retries - i == 0) && self.last_succeed {
                 let mut message = Message::new();
                 message.set_id(i);
-                return Box::new(once(ok(DnsResponse::from_message(message).unwrap())));
+                let mut response = DnsResponse::from_message(message).unwrap();
+                response.set_upstream(UpstreamMetadata::new(([127, 0, 0, 1], 53).into(), "udp"));
+                return Box::new(once(ok(response)));
             }
 
             self.attempts.fetch_add(1, Ordering::SeqCst);
@@ -187,6 +198,7 @@ mod test {
         let test1 = Message::new();
         let result = block_on(handle.send(test1).first_answer()).expect("should have succeeded");
         assert_eq!(result.id(), 1); // this is checking the number of iterations the TestClient ran
+        assert_eq!(result.upstream().unwrap().retries, 1);
     }
 
     #[test]
@@ -0,0 +1,221 @@
+//! Golden file tests of the presentation and wire formats of all record types
+//!
+//! Every `*.golden` file in `tests/test-data/rdata` holds a single record:
+//!
+//! ```text
+//! ; comment lines start with ';'
+//! ; requires: dnssec
+//! ; parse: no, RRSIG records are generated when signing a zone
+//! www.example.com. 3600 IN A 192.0.2.1
+//! c0000201
+//! ```
+//!
+//! The first line that is not a comment is the record in presentation format, the remaining lines
+//!  are the RDATA in its canonical wire format as hex, whitespace is ignored. `requires: dnssec`
+//!  skips the fixture without the `dnssec` feature, `parse: no` skips parsing the presentation
+//!  format for records that can not be read from zone files.
+//!
+//! For every fixture the wire format is decoded, encoded again and compared to the fixture, then
+//!  the decoded record is displayed and compared to the presentation format. Finally the
+//!  presentation format is parsed and must match the decoded record. Adding a fixture is all that
+//!  is needed to cover a new record type, `scripts/rdata_fixture.sh` generates one from the
+//!  output of `dig`.
+
+#![cfg(feature = "text-parsing")]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use hickory_proto::rr::{DNSClass, Name, RData, Record, RecordType};
+use hickory_proto::serialize::binary::{BinDecoder, BinEncodable, BinEncoder, Restrict};
+use hickory_proto::serialize::txt::Parser;
+
+/// A record in presentation format and its RDATA in wire format
+struct Fixture {
+    presentation: String,
+    wire: Vec<u8>,
+    requires_dnssec: bool,
+    parse: bool,
+}
+
+impl Fixture {
+    fn read(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("failed to read: {e}"))?;
+
+        let mut presentation = None;
+        let mut hex = String::new();
+        let mut requires_dnssec = false;
+        let mut parse = true;
+        for line in text.lines() {
+            let line = line.trim();
+            if let Some(comment) = line.strip_prefix(';') {
+                let comment = comment.trim();
+                if let Some(feature) = comment.strip_prefix("requires:") {
+                    match feature.trim() {
+                        "dnssec" => requires_dnssec = true,
+                        feature => return Err(format!("unknown feature: {feature}")),
+                    }
+                } else if comment.starts_with("parse: no") {
+                    parse = false;
+                }
+            } else if line.is_empty() {
+                continue;
+            } else if presentation.is_none() {
+                presentation = Some(line.to_string());
+            } else {
+                hex.extend(line.split_whitespace());
+            }
+        }
+
+        let presentation = presentation.ok_or("no record in presentation format")?;
+        let wire = data_encoding::HEXLOWER_PERMISSIVE
+            .decode(hex.as_bytes())
+            .map_err(|e| format!("bad wire format hex: {e}"))?;
+
+        Ok(Self {
+            presentation,
+            wire,
+            requires_dnssec,
+            parse,
+        })
+    }
+
+    fn check(&self) -> Result<(), String> {
+        // the owner, TTL, class and type, followed by the RDATA
+        let fields = self.presentation.split_whitespace().collect::<Vec<_>>();
+        let [name, ttl, class, record_type, ..] = fields[..] else {
+            return Err("expected NAME TTL CLASS TYPE RDATA".to_string());
+        };
+        let name = Name::from_str(name).map_err(|e| format!("bad name: {e}"))?;
+        let ttl = u32::from_str(ttl).map_err(|e| format!("bad ttl: {e}"))?;
+        let class = DNSClass::from_str(class).map_err(|e| format!("bad class: {e}"))?;
+        let record_type =
+            RecordType::from_str(record_type).map_err(|e| format!("bad type: {e}"))?;
+
+        // wire format round trip
+        let length = u16::try_from(self.wire.len()).map_err(|_| "RDATA too long")?;
+        let mut decoder = BinDecoder::new(&self.wire);
+        let rdata = RData::read(&mut decoder, record_type, Restrict::new(length))
+            .map_err(|e| format!("failed to decode: {e}"))?;
+        if !decoder.is_empty() {
+            return Err(format!("{} bytes left after decoding", decoder.len()));
+        }
+
+        let wire = emit(&rdata)?;
+        if wire != self.wire {
+            return Err(format!(
+                "wire format differs after decoding\n  expected: {}\n  actual:   {}",
+                hex(&self.wire),
+                hex(&wire)
+            ));
+        }
+
+        // presentation format of the decoded record
+        let mut record = Record::from_rdata(name, ttl, rdata);
+        record.set_dns_class(class);
+        let displayed = record.to_string();
+        if normalize(&displayed) != normalize(&self.presentation) {
+            return Err(format!(
+                "presentation format differs\n  expected: {}\n  actual:   {displayed}",
+                self.presentation
+            ));
+        }
+
+        if !self.parse {
+            return Ok(());
+        }
+
+        // parsing the presentation format
+        let (_, records) = Parser::new(self.presentation.as_str(), None, Some(Name::root()))
+            .parse()
+            .map_err(|e| format!("failed to parse: {e}"))?;
+        let mut parsed = records.into_values().flat_map(|rrset| rrset.into_iter());
+        let (Some(parsed), None) = (parsed.next(), parsed.next()) else {
+            return Err("expected exactly one parsed record".to_string());
+        };
+
+        if parsed.data() != record.data() {
+            return Err(format!(
+                "parsed record differs from the decoded one\n  decoded: {record}\n  parsed:  {parsed}"
+            ));
+        }
+
+        let wire = emit(parsed.data())?;
+        if wire != self.wire {
+            return Err(format!(
+                "wire format of the parsed record differs\n  expected: {}\n  actual:   {}",
+                hex(&self.wire),
+                hex(&wire)
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Emits the RDATA in canonical form, i.e. without name compression
+fn emit(rdata: &RData) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let mut encoder = BinEncoder::new(&mut bytes);
+    encoder.set_canonical_names(true);
+    rdata
+        .emit(&mut encoder)
+        .map_err(|e| format!("failed to encode: {e}"))?;
+
+    Ok(bytes)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    data_encoding::HEXLOWER.encode(bytes)
+}
+
+/// Fields may be separated by any whitespace, e.g. the tabs in the output of dig
+fn normalize(presentation: &str) -> String {
+    presentation
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn fixtures() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/test-data/rdata");
+    let mut fixtures = fs::read_dir(&dir)
+        .expect("failed to read the fixture directory")
+        .map(|entry| entry.expect("failed to read fixture").path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "golden"))
+        .collect::<Vec<_>>();
+    fixtures.sort();
+
+    fixtures
+}
+
+#[test]
+fn test_rdata_golden_files() {
+    let fixtures = fixtures();
+    assert!(!fixtures.is_empty(), "no fixtures found");
+
+    let mut failures = Vec::new();
+    for path in &fixtures {
+        let file = path.file_name().unwrap().to_string_lossy();
+        let result = Fixture::read(path).and_then(|fixture| {
+            if fixture.requires_dnssec && !cfg!(feature = "dnssec") {
+                return Ok(());
+            }
+
+            fixture.check()
+        });
+
+        if let Err(e) = result {
+            failures.push(format!("{file}: {e}"));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} fixtures failed:\n{}",
+        failures.len(),
+        fixtures.len(),
+        failures.join("\n")
+    );
+}
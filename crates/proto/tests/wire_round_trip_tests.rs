@@ -0,0 +1,647 @@
+//! Property tests of the wire format: every generated value must decode to itself once encoded
+//!
+//! The strategies only generate values that are valid on the wire, e.g. labels of at most 63
+//!  bytes, SVCB parameters sorted by key or RRSIGs that expire after their inception. This
+//!  complements the fuzzer, which feeds arbitrary bytes to the decoder.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::strategy::Union;
+
+use hickory_proto::op::message::{update_header_counts, HeaderCounts};
+use hickory_proto::op::{Edns, Message, MessageType, OpCode, Query, ResponseCode};
+#[cfg(feature = "dnssec")]
+#[allow(deprecated)]
+use hickory_proto::rr::dnssec::{
+    rdata::{
+        key::{KeyTrust, KeyUsage, Protocol, UpdateScope},
+        tsig::TsigAlgorithm,
+        DNSSECRData, CDNSKEY, CDS, DNSKEY, DS, KEY, NSEC, NSEC3, NSEC3PARAM, RRSIG, SIG, TSIG,
+    },
+    Algorithm, DigestType, Nsec3HashAlgorithm,
+};
+#[cfg(feature = "unstable-deleg")]
+use hickory_proto::rr::rdata::DELEG;
+use hickory_proto::rr::rdata::{
+    apl::AddressPrefix,
+    caa::KeyValue,
+    opt::{ClientSubnet, EdnsOption},
+    sshfp::{self, FingerprintType},
+    svcb::{Alpn, EchConfigList, IpHint, Mandatory, SvcParamKey, SvcParamValue, Unknown},
+    tlsa::{CertUsage, Matching, Selector},
+    A, AAAA, ANAME, APL, CAA, CNAME, CSYNC, HINFO, HTTPS, LOC, MX, NAPTR, NS, NULL, OPENPGPKEY,
+    PTR, SOA, SRV, SSHFP, SVCB, TLSA, TXT,
+};
+use hickory_proto::rr::{DNSClass, Name, RData, Record, RecordType};
+use hickory_proto::serialize::binary::{BinDecodable, BinEncodable};
+
+fn label() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9_-]{1,63}"
+}
+
+fn name() -> impl Strategy<Value = Name> {
+    // at most 3 labels of 63 bytes keep the name below 255 bytes
+    vec(label(), 0..=3)
+        .prop_map(|labels| Name::from_labels(labels.iter().map(String::as_bytes)).unwrap())
+}
+
+fn bytes(len: std::ops::RangeInclusive<usize>) -> impl Strategy<Value = Vec<u8>> {
+    vec(any::<u8>(), len)
+}
+
+/// A character string, which is at most 255 bytes long
+fn character_string() -> impl Strategy<Value = String> {
+    "[ -~]{0,64}"
+}
+
+/// Types sorted and deduplicated, as they are in a type bit map
+fn type_bit_maps() -> impl Strategy<Value = Vec<RecordType>> {
+    let types = vec![
+        RecordType::A,
+        RecordType::NS,
+        RecordType::CNAME,
+        RecordType::SOA,
+        RecordType::MX,
+        RecordType::TXT,
+        RecordType::AAAA,
+        RecordType::SRV,
+        RecordType::DS,
+        RecordType::RRSIG,
+        RecordType::NSEC,
+        RecordType::DNSKEY,
+        RecordType::SVCB,
+        RecordType::HTTPS,
+        RecordType::CAA,
+    ];
+
+    proptest::sample::subsequence(types.clone(), 0..=types.len()).prop_map(|mut types| {
+        types.sort_by_key(|record_type| u16::from(*record_type));
+        types
+    })
+}
+
+fn svc_param_value(key: SvcParamKey) -> BoxedStrategy<SvcParamValue> {
+    match key {
+        SvcParamKey::Mandatory => vec(
+            prop_oneof![
+                Just(SvcParamKey::Alpn),
+                Just(SvcParamKey::Port),
+                Just(SvcParamKey::Ipv4Hint),
+                Just(SvcParamKey::Ipv6Hint),
+            ],
+            1..=4,
+        )
+        .prop_map(|keys| SvcParamValue::Mandatory(Mandatory(keys)))
+        .boxed(),
+        SvcParamKey::Alpn => vec("[a-z0-9/.]{1,16}", 1..=3)
+            .prop_map(|alpn| SvcParamValue::Alpn(Alpn(alpn)))
+            .boxed(),
+        SvcParamKey::NoDefaultAlpn => Just(SvcParamValue::NoDefaultAlpn).boxed(),
+        SvcParamKey::Port => any::<u16>().prop_map(SvcParamValue::Port).boxed(),
+        SvcParamKey::Ipv4Hint => vec(any::<[u8; 4]>(), 1..=3)
+            .prop_map(|addrs| {
+                let addrs = addrs.into_iter().map(|addr| A(Ipv4Addr::from(addr)));
+                SvcParamValue::Ipv4Hint(IpHint(addrs.collect()))
+            })
+            .boxed(),
+        SvcParamKey::EchConfigList => bytes(1..=32)
+            .prop_map(|ech| SvcParamValue::EchConfigList(EchConfigList(ech)))
+            .boxed(),
+        SvcParamKey::Ipv6Hint => vec(any::<[u8; 16]>(), 1..=3)
+            .prop_map(|addrs| {
+                let addrs = addrs.into_iter().map(|addr| AAAA(Ipv6Addr::from(addr)));
+                SvcParamValue::Ipv6Hint(IpHint(addrs.collect()))
+            })
+            .boxed(),
+        _ => bytes(1..=32)
+            .prop_map(|data| SvcParamValue::Unknown(Unknown(data)))
+            .boxed(),
+    }
+}
+
+/// SvcParams with unique keys, sorted by their key as required on the wire
+fn svc_params() -> impl Strategy<Value = Vec<(SvcParamKey, SvcParamValue)>> {
+    let keys = vec![
+        SvcParamKey::Mandatory,
+        SvcParamKey::Alpn,
+        SvcParamKey::NoDefaultAlpn,
+        SvcParamKey::Port,
+        SvcParamKey::Ipv4Hint,
+        SvcParamKey::EchConfigList,
+        SvcParamKey::Ipv6Hint,
+        SvcParamKey::Key(65280),
+    ];
+
+    proptest::sample::subsequence(keys.clone(), 0..=keys.len()).prop_flat_map(|keys| {
+        keys.into_iter()
+            .map(|key| svc_param_value(key).prop_map(move |value| (key, value)))
+            .collect::<Vec<_>>()
+    })
+}
+
+fn svcb() -> impl Strategy<Value = SVCB> {
+    (any::<u16>(), name(), svc_params()).prop_map(|(priority, target, params)| {
+        // the AliasMode has no parameters
+        let params = if priority == 0 { Vec::new() } else { params };
+        SVCB::new(priority, target, params)
+    })
+}
+
+fn apl() -> impl Strategy<Value = APL> {
+    let prefix = prop_oneof![
+        (any::<[u8; 4]>(), 0_u8..=32).prop_map(|(addr, len)| (IpAddr::from(addr), len)),
+        (any::<[u8; 16]>(), 0_u8..=128).prop_map(|(addr, len)| (IpAddr::from(addr), len)),
+    ];
+    // an empty list would be decoded as the RDATA of an update
+    vec((any::<bool>(), prefix), 1..4).prop_map(|items| {
+        APL::new(
+            items
+                .into_iter()
+                .map(|(negation, (address, len))| {
+                    AddressPrefix::new(negation, address, len).unwrap()
+                })
+                .collect(),
+        )
+    })
+}
+
+fn loc() -> impl Strategy<Value = LOC> {
+    (
+        -90.0..=90.0,
+        -180.0..=180.0,
+        -100_000.0..42_849_672.0,
+        0.0..90_000_000.0,
+        0.0..90_000_000.0,
+        0.0..90_000_000.0,
+    )
+        .prop_map(|(latitude, longitude, altitude, size, hp, vp)| {
+            LOC::from_degrees(latitude, longitude, altitude, size, hp, vp).unwrap()
+        })
+}
+
+fn caa() -> impl Strategy<Value = CAA> {
+    let issuer = (
+        any::<bool>(),
+        any::<bool>(),
+        proptest::option::of(vec("[a-z0-9]{1,16}", 1..=3)),
+        vec(("[a-zA-Z0-9]{1,8}", "[a-zA-Z0-9]{1,8}"), 0..=2),
+    )
+        .prop_map(|(critical, wildcard, issuer, options)| {
+            let issuer = issuer
+                .map(|labels| Name::from_labels(labels.iter().map(String::as_bytes)).unwrap());
+            let options = options
+                .into_iter()
+                .map(|(key, value)| KeyValue::new(key, value))
+                .collect();
+            if wildcard {
+                CAA::new_issuewild(critical, issuer, options)
+            } else {
+                CAA::new_issue(critical, issuer, options)
+            }
+        });
+    let iodef = (any::<bool>(), "[a-z0-9]{1,16}").prop_map(|(critical, host)| {
+        CAA::new_iodef(
+            critical,
+            format!("https://{host}.example/iodef").parse().unwrap(),
+        )
+    });
+
+    prop_oneof![issuer, iodef]
+}
+
+#[cfg(feature = "dnssec")]
+fn algorithm() -> impl Strategy<Value = Algorithm> {
+    prop_oneof![
+        Just(Algorithm::RSASHA256),
+        Just(Algorithm::RSASHA512),
+        Just(Algorithm::ECDSAP256SHA256),
+        Just(Algorithm::ECDSAP384SHA384),
+        Just(Algorithm::ED25519),
+    ]
+}
+
+#[cfg(feature = "dnssec")]
+fn dnskey() -> impl Strategy<Value = DNSKEY> {
+    (any::<bool>(), any::<bool>(), algorithm(), bytes(1..=64)).prop_map(
+        |(secure_entry_point, revoke, algorithm, public_key)| {
+            DNSKEY::new(true, secure_entry_point, revoke, algorithm, public_key)
+        },
+    )
+}
+
+#[cfg(feature = "dnssec")]
+fn ds() -> impl Strategy<Value = DS> {
+    let digest_type = prop_oneof![
+        Just(DigestType::SHA1),
+        Just(DigestType::SHA256),
+        Just(DigestType::SHA384),
+    ];
+
+    (any::<u16>(), algorithm(), digest_type, bytes(1..=48)).prop_map(
+        |(key_tag, algorithm, digest_type, digest)| {
+            DS::new(key_tag, algorithm, digest_type, digest)
+        },
+    )
+}
+
+/// The signature fields shared by RRSIG and SIG, the signature expires after its inception
+#[cfg(feature = "dnssec")]
+#[allow(clippy::type_complexity)]
+fn signature_fields(
+) -> impl Strategy<Value = (RecordType, Algorithm, u8, u32, u32, u32, u16, Name, Vec<u8>)> {
+    (
+        prop_oneof![
+            Just(RecordType::A),
+            Just(RecordType::NS),
+            Just(RecordType::SOA)
+        ],
+        algorithm(),
+        0..=3u8,
+        any::<u32>(),
+        any::<u32>(),
+        0..=u32::MAX / 2,
+        any::<u16>(),
+        name(),
+        bytes(1..=64),
+    )
+        .prop_map(
+            |(type_covered, algorithm, labels, ttl, inception, validity, key_tag, signer, sig)| {
+                let expiration = inception.wrapping_add(validity);
+                (
+                    type_covered,
+                    algorithm,
+                    labels,
+                    ttl,
+                    expiration,
+                    inception,
+                    key_tag,
+                    signer,
+                    sig,
+                )
+            },
+        )
+}
+
+/// The KEY record of SIG(0), its update scope is deprecated but still on the wire
+#[cfg(feature = "dnssec")]
+#[allow(deprecated)]
+fn key() -> impl Strategy<Value = KEY> {
+    (
+        prop_oneof![
+            Just(KeyTrust::NotAuth),
+            Just(KeyTrust::NotPrivate),
+            Just(KeyTrust::AuthOrPrivate),
+            Just(KeyTrust::DoNotTrust),
+        ],
+        prop_oneof![
+            Just(KeyUsage::Host),
+            Just(KeyUsage::Entity),
+            Just(KeyUsage::Reserved),
+        ],
+        any::<[bool; 4]>(),
+        algorithm(),
+        bytes(1..=64),
+    )
+        .prop_map(
+            |(trust, usage, [zone, strong, unique, general], algorithm, key)| {
+                let scope = UpdateScope {
+                    zone,
+                    strong,
+                    unique,
+                    general,
+                };
+                KEY::new(trust, usage, scope, Protocol::DNSSEC, algorithm, key)
+            },
+        )
+}
+
+#[cfg(feature = "dnssec")]
+fn dnssec_rdata() -> impl Strategy<Value = DNSSECRData> {
+    let nsec3_hash = Just(Nsec3HashAlgorithm::SHA1);
+    let tsig_algorithm = prop_oneof![
+        Just(TsigAlgorithm::HmacSha256),
+        Just(TsigAlgorithm::HmacSha384),
+        Just(TsigAlgorithm::HmacSha512),
+    ];
+
+    Union::new(vec![
+        dnskey().prop_map(DNSSECRData::DNSKEY).boxed(),
+        dnskey()
+            .prop_map(|dnskey| DNSSECRData::CDNSKEY(CDNSKEY::from(dnskey)))
+            .boxed(),
+        ds().prop_map(DNSSECRData::DS).boxed(),
+        ds().prop_map(|ds| DNSSECRData::CDS(CDS::from(ds))).boxed(),
+        key().prop_map(DNSSECRData::KEY).boxed(),
+        (name(), type_bit_maps())
+            .prop_map(|(next, types)| DNSSECRData::NSEC(NSEC::new(next, types)))
+            .boxed(),
+        (
+            nsec3_hash.clone(),
+            any::<bool>(),
+            any::<u16>(),
+            bytes(0..=16),
+            bytes(20..=20),
+            type_bit_maps(),
+        )
+            .prop_map(|(hash, opt_out, iterations, salt, next, types)| {
+                DNSSECRData::NSEC3(NSEC3::new(hash, opt_out, iterations, salt, next, types))
+            })
+            .boxed(),
+        (nsec3_hash, any::<bool>(), any::<u16>(), bytes(0..=16))
+            .prop_map(|(hash, opt_out, iterations, salt)| {
+                DNSSECRData::NSEC3PARAM(NSEC3PARAM::new(hash, opt_out, iterations, salt))
+            })
+            .boxed(),
+        signature_fields()
+            .prop_map(
+                |(covered, algorithm, labels, ttl, exp, inc, tag, signer, sig)| {
+                    DNSSECRData::RRSIG(RRSIG::new(
+                        covered, algorithm, labels, ttl, exp, inc, tag, signer, sig,
+                    ))
+                },
+            )
+            .boxed(),
+        signature_fields()
+            .prop_map(
+                |(covered, algorithm, labels, ttl, exp, inc, tag, signer, sig)| {
+                    DNSSECRData::SIG(SIG::new(
+                        covered, algorithm, labels, ttl, exp, inc, tag, signer, sig,
+                    ))
+                },
+            )
+            .boxed(),
+        (
+            tsig_algorithm,
+            0..(1u64 << 48),
+            any::<u16>(),
+            bytes(0..=64),
+            any::<u16>(),
+            any::<u16>(),
+            bytes(0..=6),
+        )
+            .prop_map(|(algorithm, time, fudge, mac, id, error, other)| {
+                DNSSECRData::TSIG(TSIG::new(algorithm, time, fudge, mac, id, error, other))
+            })
+            .boxed(),
+    ])
+}
+
+/// RDATA of every type which can be held by a record, except OPT which is only held by `Edns`
+fn rdata() -> impl Strategy<Value = RData> {
+    let strategies = vec![
+        any::<[u8; 4]>()
+            .prop_map(|addr| RData::A(A(Ipv4Addr::from(addr))))
+            .boxed(),
+        any::<[u8; 16]>()
+            .prop_map(|addr| RData::AAAA(AAAA(Ipv6Addr::from(addr))))
+            .boxed(),
+        name().prop_map(|name| RData::ANAME(ANAME(name))).boxed(),
+        apl().prop_map(RData::APL).boxed(),
+        caa().prop_map(RData::CAA).boxed(),
+        name().prop_map(|name| RData::CNAME(CNAME(name))).boxed(),
+        (any::<u32>(), any::<bool>(), any::<bool>(), type_bit_maps())
+            .prop_map(|(serial, immediate, soa_minimum, types)| {
+                RData::CSYNC(CSYNC::new(serial, immediate, soa_minimum, types))
+            })
+            .boxed(),
+        (character_string(), character_string())
+            .prop_map(|(cpu, os)| RData::HINFO(HINFO::new(cpu, os)))
+            .boxed(),
+        svcb().prop_map(|svcb| RData::HTTPS(HTTPS(svcb))).boxed(),
+        loc().prop_map(RData::LOC).boxed(),
+        (any::<u16>(), name())
+            .prop_map(|(preference, exchange)| RData::MX(MX::new(preference, exchange)))
+            .boxed(),
+        (
+            any::<u16>(),
+            any::<u16>(),
+            "[A-Za-z0-9]{0,4}",
+            character_string(),
+            character_string(),
+            name(),
+        )
+            .prop_map(
+                |(order, preference, flags, services, regexp, replacement)| {
+                    RData::NAPTR(NAPTR::new(
+                        order,
+                        preference,
+                        flags.into_bytes().into_boxed_slice(),
+                        services.into_bytes().into_boxed_slice(),
+                        regexp.into_bytes().into_boxed_slice(),
+                        replacement,
+                    ))
+                },
+            )
+            .boxed(),
+        bytes(1..=64)
+            .prop_map(|anything| RData::NULL(NULL::with(anything)))
+            .boxed(),
+        name().prop_map(|name| RData::NS(NS(name))).boxed(),
+        bytes(1..=64)
+            .prop_map(|key| RData::OPENPGPKEY(OPENPGPKEY::new(key)))
+            .boxed(),
+        name().prop_map(|name| RData::PTR(PTR(name))).boxed(),
+        (
+            name(),
+            name(),
+            any::<u32>(),
+            any::<i32>(),
+            any::<i32>(),
+            any::<i32>(),
+            any::<u32>(),
+        )
+            .prop_map(|(mname, rname, serial, refresh, retry, expire, minimum)| {
+                RData::SOA(SOA::new(
+                    mname, rname, serial, refresh, retry, expire, minimum,
+                ))
+            })
+            .boxed(),
+        (any::<u16>(), any::<u16>(), any::<u16>(), name())
+            .prop_map(|(priority, weight, port, target)| {
+                RData::SRV(SRV::new(priority, weight, port, target))
+            })
+            .boxed(),
+        (1..=4u8, 1..=2u8, bytes(1..=32))
+            .prop_map(|(algorithm, fingerprint_type, fingerprint)| {
+                RData::SSHFP(SSHFP::new(
+                    sshfp::Algorithm::from(algorithm),
+                    FingerprintType::from(fingerprint_type),
+                    fingerprint,
+                ))
+            })
+            .boxed(),
+        svcb().prop_map(RData::SVCB).boxed(),
+        (0..=3u8, 0..=1u8, 0..=2u8, bytes(1..=64))
+            .prop_map(|(usage, selector, matching, data)| {
+                RData::TLSA(TLSA::new(
+                    CertUsage::from(usage),
+                    Selector::from(selector),
+                    Matching::from(matching),
+                    data,
+                ))
+            })
+            .boxed(),
+        vec(character_string(), 1..=4)
+            .prop_map(|strings| RData::TXT(TXT::new(strings)))
+            .boxed(),
+        (65280..=65534u16, bytes(1..=64))
+            // the experimental DELEG type has a code in the private use range
+            .prop_filter("known type", |(code, _)| {
+                matches!(RecordType::from(*code), RecordType::Unknown(_))
+            })
+            .prop_map(|(code, data)| RData::Unknown {
+                code: RecordType::Unknown(code),
+                rdata: NULL::with(data),
+            })
+            .boxed(),
+        #[cfg(feature = "unstable-deleg")]
+        svcb().prop_map(|svcb| RData::DELEG(DELEG(svcb))).boxed(),
+        #[cfg(feature = "dnssec")]
+        dnssec_rdata().prop_map(RData::DNSSEC).boxed(),
+    ];
+
+    Union::new(strategies)
+}
+
+fn record() -> impl Strategy<Value = Record> {
+    let class = prop_oneof![Just(DNSClass::IN), Just(DNSClass::CH), Just(DNSClass::HS)];
+
+    (name(), class, any::<u32>(), rdata()).prop_map(|(name, class, ttl, rdata)| {
+        let mut record = Record::from_rdata(name, ttl, rdata);
+        record.set_dns_class(class);
+        record
+    })
+}
+
+/// A record of the answer, authority or additional section, SIG(0) and TSIG records are kept apart
+///  as the signature of a message
+fn section_record() -> impl Strategy<Value = Record> {
+    record().prop_filter("signature record", |record| {
+        !matches!(record.record_type(), RecordType::SIG | RecordType::TSIG)
+    })
+}
+
+fn edns() -> impl Strategy<Value = Edns> {
+    let option = prop_oneof![
+        (any::<u32>(), 0..=32u8).prop_map(|(addr, prefix)| {
+            // the address bits after the source prefix are not sent
+            let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+            let addr = Ipv4Addr::from(addr & mask).into();
+            EdnsOption::Subnet(ClientSubnet::new(addr, prefix, 0))
+        }),
+        (65001..=65534u16, bytes(0..=16)).prop_map(|(code, data)| EdnsOption::Unknown(code, data)),
+    ];
+
+    (512..=u16::MAX, any::<bool>(), proptest::option::of(option)).prop_map(
+        |(max_payload, dnssec_ok, option)| {
+            let mut edns = Edns::new();
+            edns.set_max_payload(max_payload);
+            edns.set_dnssec_ok(dnssec_ok);
+            if let Some(option) = option {
+                edns.options_mut().insert(option);
+            }
+            edns
+        },
+    )
+}
+
+fn message() -> impl Strategy<Value = Message> {
+    let message_type = prop_oneof![Just(MessageType::Query), Just(MessageType::Response)];
+    let op_code = prop_oneof![
+        Just(OpCode::Query),
+        Just(OpCode::Status),
+        Just(OpCode::Notify),
+        Just(OpCode::Update),
+    ];
+    let record_type = prop_oneof![
+        Just(RecordType::A),
+        Just(RecordType::AAAA),
+        Just(RecordType::MX),
+        Just(RecordType::TXT),
+        Just(RecordType::ANY),
+    ];
+    let query =
+        (name(), record_type).prop_map(|(name, record_type)| Query::query(name, record_type));
+    // without EDNS only the low four bits of the response code can be sent
+    let response_code = (0..=10u8).prop_map(ResponseCode::from_low);
+
+    (
+        (
+            any::<u16>(),
+            message_type,
+            op_code,
+            any::<[bool; 6]>(),
+            response_code,
+        ),
+        vec(query, 0..=2),
+        vec(section_record(), 0..=3),
+        vec(section_record(), 0..=3),
+        vec(section_record(), 0..=3),
+        proptest::option::of(edns()),
+    )
+        .prop_map(
+            |(header, queries, answers, name_servers, additionals, edns)| {
+                let (id, message_type, op_code, flags, response_code) = header;
+                let [aa, tc, rd, ra, ad, cd] = flags;
+
+                let mut message = Message::new();
+                message
+                    .set_id(id)
+                    .set_message_type(message_type)
+                    .set_op_code(op_code)
+                    .set_authoritative(aa)
+                    .set_truncated(tc)
+                    .set_recursion_desired(rd)
+                    .set_recursion_available(ra)
+                    .set_authentic_data(ad)
+                    .set_checking_disabled(cd)
+                    .set_response_code(response_code)
+                    .add_queries(queries)
+                    .add_answers(answers)
+                    .add_name_servers(name_servers)
+                    .add_additionals(additionals);
+                if let Some(edns) = edns {
+                    message.set_edns(edns);
+                }
+                // the counts are set when a message is encoded
+                let counts = HeaderCounts {
+                    query_count: message.queries().len(),
+                    answer_count: message.answers().len(),
+                    nameserver_count: message.name_servers().len(),
+                    additional_count: message.additionals().len()
+                        + usize::from(message.extensions().is_some()),
+                };
+                let header = update_header_counts(message.header(), tc, counts);
+                message.set_header(header);
+                message
+            },
+        )
+}
+
+proptest! {
+    #[test]
+    fn roundtrip_name(name in name()) {
+        let bytes = name.to_bytes().unwrap();
+        prop_assert_eq!(Name::from_bytes(&bytes).unwrap(), name);
+    }
+
+    #[test]
+    fn roundtrip_record(record in record()) {
+        prop_assert!(record.validate().is_ok());
+        let bytes = record.to_bytes().unwrap();
+        let decoded = Record::from_bytes(&bytes).unwrap();
+        prop_assert!(decoded.validate().is_ok());
+        prop_assert_eq!(&decoded, &record);
+        prop_assert_eq!(decoded.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn roundtrip_message(msg in message()) {
+        let bytes = msg.to_vec().unwrap();
+        let decoded = Message::from_vec(&bytes).unwrap();
+        prop_assert_eq!(&decoded, &msg);
+        prop_assert_eq!(decoded.to_vec().unwrap(), bytes);
+    }
+}
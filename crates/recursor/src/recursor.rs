@@ -24,8 +24,10 @@ use crate::{
     },
     recursor_pool::RecursorPool,
     resolver::{
-        config::{NameServerConfig, NameServerConfigGroup, Protocol, ResolverOpts},
-        dns_lru::{DnsLru, TtlConfig},
+        config::{
+            NameServerConfig, NameServerConfigGroup, Protocol, ResolverOpts, ServerFailurePolicy,
+        },
+        dns_lru::{DnsLru, TrustLevel, TtlConfig},
         error::ResolveError,
         lookup::Lookup,
         name_server::{GenericNameServerPool, TokioRuntimeProvider},
@@ -34,14 +36,16 @@ use crate::{
     Error, ErrorKind,
 };
 
-/// Set of nameservers by the zone name
-type NameServerCache<P> = LruCache<Name, RecursorPool<P>>;
+/// Set of nameservers by the zone name, with the expiry of the NS records they were built from
+type NameServerCache<P> = LruCache<Name, (RecursorPool<P>, Instant)>;
 
 /// A `Recursor` builder
 #[derive(Clone, Copy)]
 pub struct RecursorBuilder {
     ns_cache_size: usize,
     record_cache_size: usize,
+    ttl_config: TtlConfig,
+    server_failure_policy: ServerFailurePolicy,
     #[cfg(feature = "dnssec")]
     security_aware: bool,
 }
@@ -51,6 +55,8 @@ impl Default for RecursorBuilder {
         Self {
             ns_cache_size: 1024,
             record_cache_size: 1048576,
+            ttl_config: TtlConfig::default(),
+            server_failure_policy: ServerFailurePolicy::default(),
             #[cfg(feature = "dnssec")]
             security_aware: false,
         }
@@ -70,6 +76,25 @@ impl RecursorBuilder {
         self
     }
 
+    /// Sets the minimum and maximum TTLs of the cached records
+    ///
+    /// The TTLs of delegation data, NS records, DS records and glue, are capped and jittered
+    ///  independently from answer data, this applies to the cached name servers of zones as well.
+    pub fn ttl_config(&mut self, ttl_config: TtlConfig) -> &mut Self {
+        self.ttl_config = ttl_config;
+        self
+    }
+
+    /// Sets the handling of SERVFAIL and other error responses from the name servers of a zone
+    ///
+    /// By default the remaining name servers of the zone are tried, and the error is only
+    ///  returned once all of them failed. [`ServerFailurePolicy::FirstAnswer`] returns the first
+    ///  response instead, for latency critical deployments.
+    pub fn server_failure_policy(&mut self, policy: ServerFailurePolicy) -> &mut Self {
+        self.server_failure_policy = policy;
+        self
+    }
+
     /// Enables or disables (DNSSEC) security awareness
     #[cfg(feature = "dnssec")]
     pub fn security_aware(&mut self, security_aware: bool) -> &mut Self {
@@ -92,6 +117,8 @@ impl RecursorBuilder {
             roots,
             self.ns_cache_size,
             self.record_cache_size,
+            self.ttl_config,
+            self.server_failure_policy,
             security_aware,
         )
     }
@@ -104,6 +131,7 @@ pub struct Recursor {
     roots: RecursorPool<TokioRuntimeProvider>,
     name_server_cache: Mutex<NameServerCache<TokioRuntimeProvider>>,
     record_cache: DnsLru,
+    server_failure_policy: ServerFailurePolicy,
     security_aware: bool,
 }
 
@@ -113,10 +141,25 @@ impl Recursor {
         RecursorBuilder::default()
     }
 
+    /// Returns true if [`Self::resolve`] would answer the query from the record cache
+    ///
+    /// This is a cheap check, the query is not resolved.
+    pub fn is_cached(&self, query: &Query, now: Instant) -> bool {
+        self.record_cache.contains(query, now)
+    }
+
+    /// The cache of the records received from the name servers, e.g. to dump it, see
+    ///  [`DnsLru::dump`]
+    pub fn cache(&self) -> &DnsLru {
+        &self.record_cache
+    }
+
     fn build(
         roots: impl Into<NameServerConfigGroup>,
         ns_cache_size: usize,
         record_cache_size: usize,
+        ttl_config: TtlConfig,
+        server_failure_policy: ServerFailurePolicy,
         security_aware: bool,
     ) -> Result<Self, ResolveError> {
         // configure the hickory-resolver
@@ -125,17 +168,18 @@ impl Recursor {
         assert!(!roots.is_empty(), "roots must not be empty");
 
         debug!("Using cache sizes {}/{}", ns_cache_size, record_cache_size);
-        let opts = recursor_opts();
+        let opts = recursor_opts(server_failure_policy);
         let roots =
             GenericNameServerPool::from_config(roots, opts, TokioConnectionProvider::default());
         let roots = RecursorPool::from(Name::root(), roots);
         let name_server_cache = Mutex::new(NameServerCache::new(ns_cache_size));
-        let record_cache = DnsLru::new(record_cache_size, TtlConfig::default());
+        let record_cache = DnsLru::new(record_cache_size, ttl_config);
 
         Ok(Self {
             roots,
             name_server_cache,
             record_cache,
+            server_failure_policy,
             security_aware,
         })
     }
@@ -378,15 +422,39 @@ impl Recursor {
         // TODO: check if data is "authentic"
         match response.await {
             Ok(r) => {
+                // a referral to the zone being queried or one of its ancestors makes no progress,
+                //  and its records shouldn't replace the cached delegations
+                let upward_referral = r.response_kind().is_upward_referral_from(ns.zone());
+
                 let mut r = r.into_message();
                 info!("response: {}", r.header());
 
+                if upward_referral {
+                    warn!(
+                        "Dropping upward referral in response to {query} from zone {}",
+                        ns.zone()
+                    );
+                    r.take_name_servers();
+                    r.take_additionals();
+                }
+
+                // rank the records by the section they're from, so that e.g. the glue of a
+                //  referral doesn't replace an answer that is already cached
                 let records = r
                     .take_answers()
                     .into_iter()
-                    .chain(r.take_name_servers())
-                    .chain(r.take_additionals())
-                    .filter(|x| {
+                    .map(|x| (x, TrustLevel::Answer))
+                    .chain(
+                        r.take_name_servers()
+                            .into_iter()
+                            .map(|x| (x, TrustLevel::Authority)),
+                    )
+                    .chain(
+                        r.take_additionals()
+                            .into_iter()
+                            .map(|x| (x, TrustLevel::Additional)),
+                    )
+                    .filter(|(x, _)| {
                         if !is_subzone(ns.zone().clone(), x.name().clone()) {
                             warn!(
                                 "Dropping out of bailiwick record {x} for zone {}",
@@ -398,12 +466,14 @@ impl Recursor {
                         }
                     });
 
-                let lookup = self.record_cache.insert_records(query, records, now);
+                let lookup = self
+                    .record_cache
+                    .insert_records_with_trust_level(query, records, now);
 
                 lookup.ok_or_else(|| Error::from("no records found"))
             }
             Err(e) => {
-                warn!("lookup error: {e}");
+                warn!("lookup of {query} in {} failed: {e}", ns.zone());
                 Err(Error::from(e))
             }
         }
@@ -415,9 +485,10 @@ impl Recursor {
         zone: Name,
         request_time: Instant,
     ) -> Result<RecursorPool<TokioRuntimeProvider>, Error> {
-        // TODO: need to check TTLs here.
-        if let Some(ns) = self.name_server_cache.lock().get_mut(&zone) {
-            return Ok(ns.clone());
+        if let Some((ns, valid_until)) = self.name_server_cache.lock().get_mut(&zone) {
+            if request_time <= *valid_until {
+                return Ok(ns.clone());
+            }
         };
 
         let parent_zone = zone.base_name();
@@ -431,6 +502,11 @@ impl Recursor {
 
         // TODO: check for cached ns pool for this zone
 
+        #[cfg(feature = "unstable-deleg")]
+        let deleg_config = self
+            .deleg_config_for_zone(&zone, nameserver_pool.clone(), request_time)
+            .await;
+
         let lookup = Query::query(zone.clone(), RecordType::NS);
         let response = self
             .lookup(lookup.clone(), nameserver_pool.clone(), request_time)
@@ -439,7 +515,9 @@ impl Recursor {
         // let zone_nameservers = response.name_servers();
         // let glue = response.additionals();
 
-        // TODO: grab TTL and use for cache
+        // the pool is cached as long as the NS records, see `DnsLru` for the TTL of delegation data
+        let valid_until = response.valid_until();
+
         // get all the NS records and glue
         let mut config_group = NameServerConfigGroup::new();
         let mut need_ips_for_names = Vec::new();
@@ -544,16 +622,106 @@ impl Recursor {
         // now construct a namesever pool based off the NS and glue records
         let ns = GenericNameServerPool::from_config(
             config_group,
-            recursor_opts(),
+            recursor_opts(self.server_failure_policy),
             TokioConnectionProvider::default(),
         );
+        #[cfg(feature = "unstable-deleg")]
+        let ns = match deleg_config {
+            Some(deleg_config) => {
+                debug!("using DELEG nameservers for {zone}, NS nameservers as fallback");
+                let deleg = GenericNameServerPool::from_config(
+                    deleg_config,
+                    recursor_opts(self.server_failure_policy),
+                    TokioConnectionProvider::default(),
+                );
+                RecursorPool::from(zone.clone(), deleg).with_fallback(ns)
+            }
+            None => RecursorPool::from(zone.clone(), ns),
+        };
+        #[cfg(not(feature = "unstable-deleg"))]
         let ns = RecursorPool::from(zone.clone(), ns);
 
         // store in cache for future usage
         debug!("found nameservers for {}", zone);
-        self.name_server_cache.lock().insert(zone, ns.clone());
+        self.name_server_cache
+            .lock()
+            .insert(zone, (ns.clone(), valid_until));
         Ok(ns)
     }
+
+    /// Looks up the DELEG records of the zone in the parent zone, and returns their name servers
+    ///
+    /// DELEG records are authoritative data of the parent, so unlike the NS records of a referral
+    ///  they are signed by the parent and cached with their RRSIGs when DNSSEC is requested.
+    ///  `None` is returned if the parent has no DELEG records for the zone, or doesn't support them.
+    ///  The alias form isn't followed yet, and only plain DNS over UDP and TCP is used.
+    #[cfg(feature = "unstable-deleg")]
+    async fn deleg_config_for_zone(
+        &self,
+        zone: &Name,
+        parent_pool: RecursorPool<TokioRuntimeProvider>,
+        request_time: Instant,
+    ) -> Option<NameServerConfigGroup> {
+        let lookup = Query::query(zone.clone(), RecordType::DELEG);
+        let response = match self.lookup(lookup, parent_pool, request_time).await {
+            Ok(response) => response,
+            Err(e) => {
+                debug!("no DELEG records for {zone}: {e}");
+                return None;
+            }
+        };
+
+        let mut config_group = NameServerConfigGroup::new();
+        let delegs = response
+            .record_iter()
+            .filter(|r| r.name() == zone)
+            .filter_map(|r| r.data().as_deleg());
+
+        for deleg in delegs {
+            if deleg.svc_priority() == 0 {
+                debug!(
+                    "not following DELEG alias {} for {zone}",
+                    deleg.target_name()
+                );
+                continue;
+            }
+
+            let port = deleg.port().unwrap_or(53);
+            let mut ips = deleg.ip_hints().collect::<Vec<_>>();
+
+            // without hints the target needs to be resolved, which can't be done through the
+            //  zone that is being delegated
+            if ips.is_empty() && !zone.zone_of(deleg.target_name()) {
+                for record_type in [RecordType::A, RecordType::AAAA] {
+                    let query = Query::query(deleg.target_name().clone(), record_type);
+                    match self.resolve(query, request_time, false).await {
+                        Ok(lookup) => ips.extend(lookup.iter().filter_map(RData::ip_addr)),
+                        Err(e) => {
+                            debug!("resolving DELEG target {} failed: {e}", deleg.target_name())
+                        }
+                    }
+                }
+            }
+
+            for ip in ips {
+                let mut udp = NameServerConfig::new(SocketAddr::from((ip, port)), Protocol::Udp);
+                let mut tcp = NameServerConfig::new(SocketAddr::from((ip, port)), Protocol::Tcp);
+
+                udp.trust_negative_responses = true;
+                tcp.trust_negative_responses = true;
+
+                config_group.push(udp);
+                config_group.push(tcp);
+            }
+        }
+
+        if config_group.is_empty() {
+            debug!("no usable DELEG records for {zone}");
+            return None;
+        }
+
+        Some(config_group)
+    }
 }
 
 // as per section 3.2.1 of RFC4035
@@ -575,7 +743,7 @@ fn maybe_strip_dnssec_records(query_has_dnssec_ok: bool, lookup: Lookup, query:
     Lookup::new_with_deadline(query, records, lookup.valid_until())
 }
 
-fn recursor_opts() -> ResolverOpts {
+fn recursor_opts(server_failure_policy: ServerFailurePolicy) -> ResolverOpts {
     let mut options = ResolverOpts::default();
     options.ndots = 0;
     options.edns0 = true;
@@ -583,6 +751,7 @@ fn recursor_opts() -> ResolverOpts {
     options.preserve_intermediates = true;
     options.recursion_desired = false;
     options.num_concurrent_reqs = 1;
+    options.server_failure_policy = server_failure_policy;
 
     options
 }
@@ -14,6 +14,7 @@ use std::{
 
 use futures_util::{future::Shared, Future, FutureExt, StreamExt};
 use hickory_proto::{
+    error::ProtoErrorKind,
     op::Query,
     xfer::{DnsRequestOptions, DnsResponse},
     DnsHandle,
@@ -53,6 +54,9 @@ impl Future for SharedLookup {
 pub(crate) struct RecursorPool<P: RuntimeProvider + Send + 'static> {
     zone: Name,
     ns: GenericNameServerPool<P>,
+    /// Name servers that are queried when none of `ns` answered, e.g. those of the NS records of a
+    ///  zone that is delegated with DELEG
+    fallback: Option<GenericNameServerPool<P>>,
     active_requests: Arc<Mutex<ActiveRequests>>,
 }
 
@@ -63,6 +67,7 @@ impl RecursorPool<TokioRuntimeProvider> {
         Self {
             zone,
             ns,
+            fallback: None,
             active_requests,
         }
     }
@@ -76,12 +81,22 @@ where
         &self.zone
     }
 
+    /// Sets the name servers to query when the name servers of this pool didn't answer
+    ///
+    /// Negative responses are answers, they are not retried on the fallback.
+    #[cfg_attr(not(feature = "unstable-deleg"), allow(dead_code))]
+    pub(crate) fn with_fallback(mut self, fallback: GenericNameServerPool<P>) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
     pub(crate) async fn lookup(
         &self,
         query: Query,
         security_aware: bool,
     ) -> Result<DnsResponse, ResolveError> {
         let ns = self.ns.clone();
+        let fallback = self.fallback.clone();
 
         let query_cpy = query.clone();
 
@@ -97,13 +112,20 @@ where
                 options.use_edns = security_aware;
                 options.edns_set_dnssec_ok = security_aware;
 
+                let zone = self.zone.clone();
+                let lookup = async move {
+                    let result = first_response(&ns, query_cpy.clone(), options).await;
+                    let fallback = match fallback {
+                        Some(fallback) if !is_answer(&result) => fallback,
+                        _ => return result,
+                    };
+
+                    info!("falling back to the next name servers of {zone} for {query_cpy}");
+                    first_response(&fallback, query_cpy, options).await
+                };
+
                 // convert the lookup into a shared future
-                let lookup = ns
-                    .lookup(query_cpy, options)
-                    .into_future()
-                    .map(|(next, _)| next.map(|r| r.map_err(ResolveError::from)))
-                    .boxed()
-                    .shared();
+                let lookup = lookup.boxed().shared();
 
                 SharedLookup(lookup)
             })
@@ -117,3 +139,23 @@ where
         result
     }
 }
+
+async fn first_response<P: RuntimeProvider + Send + 'static>(
+    ns: &GenericNameServerPool<P>,
+    query: Query,
+    options: DnsRequestOptions,
+) -> Option<Result<DnsResponse, ResolveError>> {
+    let (next, _) = ns.lookup(query, options).into_future().await;
+    next.map(|r| r.map_err(ResolveError::from))
+}
+
+/// Returns true if the name servers answered, even if the answer is that there are no records
+fn is_answer(result: &Option<Result<DnsResponse, ResolveError>>) -> bool {
+    match result {
+        Some(Ok(_)) => true,
+        Some(Err(e)) => e.proto().map_or(false, |e| {
+            matches!(e.kind(), ProtoErrorKind::NoRecordsFound { .. })
+        }),
+        None => false,
+    }
+}
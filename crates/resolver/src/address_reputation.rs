@@ -0,0 +1,355 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Reputation of resolved addresses, reported by the application that connects to them
+//!
+//! The resolver doesn't connect to the addresses it returns, but the application does and learns
+//!  which of them are reachable and how fast. Reporting that with
+//!  [`crate::AsyncResolver::report_outcome`] lets the following lookups return the addresses that
+//!  worked first.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use lru_cache::LruCache;
+use parking_lot::Mutex;
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(test))]
+use std::time::{Duration, Instant};
+#[cfg(test)]
+use tokio::time::{Duration, Instant};
+
+use crate::lookup_ip::LookupIp;
+use crate::proto::rr::RData;
+
+/// The outcome of connecting to a resolved address
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The connection was established after `rtt`
+    Connected {
+        /// The time it took to connect, e.g. the TCP handshake
+        rtt: Duration,
+    },
+    /// The connection could not be established
+    Failed,
+}
+
+/// The reputation of one address, see [`AddressReputation::export`]
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReputationRecord {
+    /// The address the outcomes were reported for
+    pub ip: IpAddr,
+    /// The number of recent failures when the last outcome was reported, they decay with `age`
+    pub failures: f64,
+    /// The smoothed round-trip time of the connections, if any succeeded
+    pub srtt: Option<Duration>,
+    /// The time since the last outcome was reported
+    pub age: Duration,
+}
+
+/// A bounded table of the outcomes reported for resolved addresses
+///
+/// Clones share the same table. The table holds the `capacity` most recently reported
+///  addresses, and their reputation decays with `half_life` so that failing addresses are tried
+///  again eventually.
+#[derive(Clone)]
+pub struct AddressReputation(Arc<Mutex<Table>>);
+
+impl AddressReputation {
+    /// Creates an empty table for up to `capacity` addresses
+    pub fn new(capacity: usize, half_life: Duration) -> Self {
+        Self(Arc::new(Mutex::new(Table {
+            entries: LruCache::new(capacity),
+            half_life,
+        })))
+    }
+
+    /// Records the outcome of connecting to `ip`
+    pub fn report_outcome(&self, ip: IpAddr, outcome: Outcome) {
+        let now = Instant::now();
+        let mut table = self.0.lock();
+        let half_life = table.half_life;
+
+        let entry = match table.entries.get_mut(&ip) {
+            Some(entry) => entry,
+            None => {
+                table.entries.insert(ip, Entry::new(now));
+                table.entries.get_mut(&ip).expect("entry was just inserted")
+            }
+        };
+
+        entry.update(outcome, now, half_life);
+    }
+
+    /// Returns true if no outcomes were reported
+    pub fn is_empty(&self) -> bool {
+        self.0.lock().entries.is_empty()
+    }
+
+    /// Removes all reported outcomes
+    pub fn clear(&self) {
+        self.0.lock().entries.clear();
+    }
+
+    /// Returns the reputation of all addresses in the table, e.g. to persist it
+    pub fn export(&self) -> Vec<ReputationRecord> {
+        let now = Instant::now();
+        let table = self.0.lock();
+
+        table
+            .entries
+            .iter()
+            .map(|(ip, entry)| ReputationRecord {
+                ip: *ip,
+                failures: entry.failures,
+                srtt: entry.srtt,
+                age: now.saturating_duration_since(entry.last_update),
+            })
+            .collect()
+    }
+
+    /// Adds previously exported reputations to the table, replacing those of the same addresses
+    pub fn import(&self, records: impl IntoIterator<Item = ReputationRecord>) {
+        let now = Instant::now();
+        let mut table = self.0.lock();
+
+        for record in records {
+            let entry = Entry {
+                failures: record.failures,
+                srtt: record.srtt,
+                last_update: now.checked_sub(record.age).unwrap_or(now),
+            };
+
+            table.entries.insert(record.ip, entry);
+        }
+    }
+
+    /// Sorts the addresses by reachability, then by round-trip time
+    ///
+    /// The sort is stable, addresses without any recent outcome keep their order.
+    pub(crate) fn order(&self, lookup: LookupIp) -> LookupIp {
+        if self.is_empty() {
+            return lookup;
+        }
+
+        let now = Instant::now();
+        let mut table = self.0.lock();
+        let half_life = table.half_life;
+
+        lookup.sorted_by_key(|record| {
+            let ip = match record.data() {
+                RData::A(a) => IpAddr::V4(a.0),
+                RData::AAAA(aaaa) => IpAddr::V6(aaaa.0),
+                // keep e.g. CNAMEs ahead of the addresses
+                _ => return None,
+            };
+
+            let rank = table
+                .entries
+                .get_mut(&ip)
+                .map_or(Rank::NEUTRAL, |entry| entry.rank(now, half_life));
+            Some(rank)
+        })
+    }
+}
+
+impl Default for AddressReputation {
+    /// A table for 1024 addresses, with a half life of 5 minutes
+    fn default() -> Self {
+        Self::new(1024, Duration::from_secs(5 * 60))
+    }
+}
+
+struct Table {
+    entries: LruCache<IpAddr, Entry>,
+    half_life: Duration,
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    failures: f64,
+    srtt: Option<Duration>,
+    last_update: Instant,
+}
+
+impl Entry {
+    /// Failures are counted until the decayed count drops below this
+    const FAILING: f64 = 0.5;
+    /// The round-trip time is ignored once the decay drops below this, i.e. after 3 half lives
+    const FORGOTTEN: f64 = 0.125;
+    /// The weight of the previous round-trip times in the smoothed round-trip time
+    const SRTT_WEIGHT: f64 = 0.7;
+
+    fn new(now: Instant) -> Self {
+        Self {
+            failures: 0.0,
+            srtt: None,
+            last_update: now,
+        }
+    }
+
+    fn update(&mut self, outcome: Outcome, now: Instant, half_life: Duration) {
+        let decay = self.decay(now, half_life);
+
+        match outcome {
+            Outcome::Connected { rtt } => {
+                self.failures = 0.0;
+                self.srtt = Some(match self.srtt {
+                    Some(srtt) if decay >= Self::FORGOTTEN => {
+                        srtt.mul_f64(Self::SRTT_WEIGHT) + rtt.mul_f64(1.0 - Self::SRTT_WEIGHT)
+                    }
+                    _ => rtt,
+                });
+            }
+            Outcome::Failed => self.failures = self.failures * decay + 1.0,
+        }
+
+        self.last_update = now;
+    }
+
+    /// Returns the factor by which the reported outcomes have decayed
+    fn decay(&self, now: Instant, half_life: Duration) -> f64 {
+        let elapsed = now.saturating_duration_since(self.last_update);
+        if half_life.is_zero() {
+            return 0.0;
+        }
+
+        0.5_f64.powf(elapsed.as_secs_f64() / half_life.as_secs_f64())
+    }
+
+    fn rank(&self, now: Instant, half_life: Duration) -> Rank {
+        let decay = self.decay(now, half_life);
+
+        Rank {
+            failing: self.failures * decay >= Self::FAILING,
+            srtt: self
+                .srtt
+                .filter(|_| decay >= Self::FORGOTTEN)
+                .unwrap_or(Duration::MAX),
+        }
+    }
+}
+
+/// Addresses are ordered by this, reachable addresses first, then the fastest
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Rank {
+    failing: bool,
+    srtt: Duration,
+}
+
+impl Rank {
+    const NEUTRAL: Self = Self {
+        failing: false,
+        srtt: Duration::MAX,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::lookup::Lookup;
+    use crate::proto::op::Query;
+    use crate::proto::rr::{Name, Record, RecordType};
+
+    const FIRST: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+    const SECOND: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 2);
+
+    fn lookup() -> LookupIp {
+        let name = Name::from_str("www.example.com.").unwrap();
+        let records = [FIRST, SECOND]
+            .into_iter()
+            .map(|ip| Record::from_rdata(name.clone(), 300, RData::A(ip.into())))
+            .collect::<Vec<_>>();
+
+        Lookup::new_with_max_ttl(Query::query(name, RecordType::A), Arc::from(records)).into()
+    }
+
+    fn ordered(reputation: &AddressReputation) -> Vec<IpAddr> {
+        reputation.order(lookup()).iter().collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failures_order_last() {
+        let reputation = AddressReputation::default();
+        assert_eq!(ordered(&reputation), [FIRST, SECOND]);
+
+        reputation.report_outcome(FIRST.into(), Outcome::Failed);
+        reputation.report_outcome(FIRST.into(), Outcome::Failed);
+        assert_eq!(ordered(&reputation), [SECOND, FIRST]);
+
+        // the failures decay, after which the order of the lookup is kept again
+        tokio::time::advance(Duration::from_secs(5 * 60)).await;
+        assert_eq!(ordered(&reputation), [SECOND, FIRST]);
+        tokio::time::advance(Duration::from_secs(10 * 60)).await;
+        assert_eq!(ordered(&reputation), [FIRST, SECOND]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_faster_orders_first() {
+        let reputation = AddressReputation::default();
+
+        reputation.report_outcome(
+            FIRST.into(),
+            Outcome::Connected {
+                rtt: Duration::from_millis(200),
+            },
+        );
+        reputation.report_outcome(
+            SECOND.into(),
+            Outcome::Connected {
+                rtt: Duration::from_millis(20),
+            },
+        );
+        assert_eq!(ordered(&reputation), [SECOND, FIRST]);
+
+        tokio::time::advance(Duration::from_secs(20 * 60)).await;
+        assert_eq!(ordered(&reputation), [FIRST, SECOND]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shared_and_bounded() {
+        let reputation = AddressReputation::new(1, Duration::from_secs(60));
+        let clone = reputation.clone();
+
+        clone.report_outcome(FIRST.into(), Outcome::Failed);
+        assert_eq!(ordered(&reputation), [SECOND, FIRST]);
+
+        // only the most recent address is kept
+        clone.report_outcome(
+            SECOND.into(),
+            Outcome::Connected {
+                rtt: Duration::from_millis(20),
+            },
+        );
+        assert_eq!(reputation.export().len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_export_import() {
+        let reputation = AddressReputation::default();
+        reputation.report_outcome(FIRST.into(), Outcome::Failed);
+        tokio::time::advance(Duration::from_secs(60)).await;
+
+        let records = reputation.export();
+        let [record] = records.as_slice() else {
+            panic!("expected one record: {records:?}");
+        };
+        assert_eq!(record.ip, IpAddr::from(FIRST));
+        assert_eq!(record.age, Duration::from_secs(60));
+
+        let imported = AddressReputation::default();
+        imported.import(records);
+        assert_eq!(ordered(&imported), [SECOND, FIRST]);
+        assert_eq!(imported.export()[0].age, Duration::from_secs(60));
+    }
+}
@@ -10,23 +10,27 @@ use std::fmt;
 use std::net::IpAddr;
 use std::sync::Arc;
 
+use futures_util::future;
 use proto::error::ProtoResult;
 use proto::op::Query;
 use proto::rr::domain::usage::ONION;
 use proto::rr::domain::TryParseIp;
 use proto::rr::{IntoName, Name, Record, RecordType};
+#[cfg(feature = "dnssec")]
+use proto::xfer::DnssecObservations;
 use proto::xfer::{DnsRequestOptions, RetryDnsHandle};
 use tracing::{debug, trace};
 
+use crate::address_reputation::{AddressReputation, Outcome};
 use crate::caching_client::CachingClient;
-use crate::config::{ResolverConfig, ResolverOpts};
+use crate::config::{ResolverConfig, ResolverOpts, UpstreamAddressFamily};
 use crate::dns_lru::{self, DnsLru};
 use crate::error::*;
 use crate::lookup::{self, Lookup, LookupEither, LookupFuture};
 use crate::lookup_ip::{LookupIp, LookupIpFuture};
 #[cfg(feature = "tokio-runtime")]
 use crate::name_server::TokioConnectionProvider;
-use crate::name_server::{ConnectionProvider, NameServerPool};
+use crate::name_server::{ConnectionProvider, NameServerPool, ShadowLog, SharedAddressFamily};
 
 use crate::Hosts;
 
@@ -62,6 +66,11 @@ pub struct AsyncResolver<P: ConnectionProvider> {
     options: ResolverOpts,
     client_cache: CachingClient<LookupEither<P>>,
     hosts: Option<Arc<Hosts>>,
+    upstream_address_family: SharedAddressFamily,
+    shadow_log: Option<ShadowLog>,
+    address_reputation: AddressReputation,
+    #[cfg(feature = "dnssec")]
+    dnssec_observations: Option<DnssecObservations>,
 }
 
 /// An AsyncResolver used with Tokio
@@ -171,11 +180,34 @@ impl<R: ConnectionProvider> AsyncResolver<R> {
         Self::from_system_conf_with_provider(runtime)
     }
 
+    /// Constructs a new Resolver with the system configuration, or the fallback `policy` if it
+    ///  has no usable name servers.
+    ///
+    /// This will use `/etc/resolv.conf` on Unix OSes and the registry on Windows.
+    #[cfg(any(unix, target_os = "windows"))]
+    #[cfg(feature = "system-config")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(all(feature = "system-config", any(unix, target_os = "windows"))))
+    )]
+    pub fn from_system_conf_with_fallback(
+        runtime: R,
+        policy: &crate::system_conf::FallbackPolicy,
+    ) -> Result<Self, ResolveError> {
+        let (config, options) = super::system_conf::read_system_conf_with_fallback(policy)?;
+        Ok(Self::new_with_conn(config, options, runtime))
+    }
+
     /// Flushes/Removes all entries from the cache
     pub fn clear_cache(&self) {
         self.client_cache.clear_cache();
     }
 
+    /// The cache of the resolver, e.g. to pin or prepopulate the entries of critical names
+    pub fn cache(&self) -> &DnsLru {
+        self.client_cache.lru()
+    }
+
     /// Read the config for this resolver.
     pub fn config(&self) -> &ResolverConfig {
         &self.config
@@ -185,6 +217,57 @@ impl<R: ConnectionProvider> AsyncResolver<R> {
     pub fn options(&self) -> &ResolverOpts {
         &self.options
     }
+
+    /// Sets the address family used to reach the name servers, e.g. to stop using IPv6 after the
+    ///  network changed.
+    ///
+    /// This applies to all clones of this resolver, starting with the next request sent upstream.
+    ///  Cached responses are not affected.
+    pub fn set_upstream_address_family(&self, family: UpstreamAddressFamily) {
+        self.upstream_address_family.store(family);
+    }
+
+    /// The address family currently used to reach the name servers
+    pub fn upstream_address_family(&self) -> UpstreamAddressFamily {
+        self.upstream_address_family.load()
+    }
+
+    /// The outcomes of the queries duplicated to the shadow upstream, if one is configured in
+    ///  [`ResolverOpts::shadow`]
+    ///
+    /// The log is shared by all clones of this resolver.
+    pub fn shadow_log(&self) -> Option<&ShadowLog> {
+        self.shadow_log.as_ref()
+    }
+
+    /// The DNSSEC parameters of the validated zones, if enabled with
+    ///  [`ResolverOpts::dnssec_observations`], e.g. to report zones still signed with SHA-1
+    ///
+    /// The observations are shared by all clones of this resolver, and can be cleared with
+    ///  [`DnssecObservations::reset`].
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub fn dnssec_observations(&self) -> Option<&DnssecObservations> {
+        self.dnssec_observations.as_ref()
+    }
+
+    /// Reports the outcome of connecting to an address returned by [`Self::lookup_ip`]
+    ///
+    /// Following `lookup_ip` results return the reachable and fastest addresses first, until the
+    ///  reported outcomes decay. This applies to all clones of this resolver.
+    pub fn report_outcome(&self, ip: IpAddr, outcome: Outcome) {
+        self.address_reputation.report_outcome(ip, outcome);
+    }
+
+    /// The outcomes reported for resolved addresses, e.g. to export and persist them
+    pub fn address_reputation(&self) -> &AddressReputation {
+        &self.address_reputation
+    }
+
+    /// Replaces the table of reported outcomes, e.g. to share one between several resolvers
+    pub fn set_address_reputation(&mut self, address_reputation: AddressReputation) {
+        self.address_reputation = address_reputation;
+    }
 }
 
 impl<P: ConnectionProvider> AsyncResolver<P> {
@@ -204,13 +287,23 @@ impl<P: ConnectionProvider> AsyncResolver<P> {
     pub fn new_with_conn(config: ResolverConfig, options: ResolverOpts, conn_provider: P) -> Self {
         let pool =
             NameServerPool::from_config_with_provider(&config, options.clone(), conn_provider);
+        let upstream_address_family = pool.shared_address_family();
+        let shadow_log = pool.shadow_log().cloned();
         let either;
+        #[cfg(feature = "dnssec")]
+        let mut dnssec_observations = None;
         let client = RetryDnsHandle::new(pool, options.attempts);
         if options.validate {
             #[cfg(feature = "dnssec")]
             {
                 use proto::xfer::DnssecDnsHandle;
-                either = LookupEither::Secure(DnssecDnsHandle::new(client));
+                let mut handle = DnssecDnsHandle::new(client);
+                if options.dnssec_observations {
+                    let observations = DnssecObservations::new();
+                    handle.set_observations(observations.clone());
+                    dnssec_observations = Some(observations);
+                }
+                either = LookupEither::Secure(handle);
             }
 
             #[cfg(not(feature = "dnssec"))]
@@ -233,9 +326,15 @@ impl<P: ConnectionProvider> AsyncResolver<P> {
         let lru = DnsLru::new(options.cache_size, dns_lru::TtlConfig::from_opts(&options));
         Self {
             config,
-            client_cache: CachingClient::with_cache(lru, either, options.preserve_intermediates),
+            client_cache: CachingClient::with_cache(lru, either, options.preserve_intermediates)
+                .with_cname_chase_limit(options.cname_chase_limit),
             options,
             hosts,
+            upstream_address_family,
+            shadow_log,
+            address_reputation: AddressReputation::default(),
+            #[cfg(feature = "dnssec")]
+            dnssec_observations,
         }
     }
 
@@ -429,6 +528,7 @@ impl<P: ConnectionProvider> AsyncResolver<P> {
             finally_ip_addr.map(Record::into_data),
         )
         .await
+        .map(|lookup| self.address_reputation.order(lookup))
     }
 
     /// Customizes the static hosts used in this resolver.
@@ -436,6 +536,52 @@ impl<P: ConnectionProvider> AsyncResolver<P> {
         self.hosts = hosts.map(Arc::new);
     }
 
+    /// Returns the static hosts used in this resolver, e.g. to look up the scope of an address
+    pub fn hosts(&self) -> Option<&Hosts> {
+        self.hosts.as_deref()
+    }
+
+    /// Looks up the MX records of the domain, and the addresses of each mail exchanger
+    ///
+    /// The exchangers are resolved concurrently, an exchanger whose addresses can't be resolved
+    ///  is returned without addresses. The results are sorted by preference, the exchangers of the
+    ///  same preference keep the order of the MX lookup. The exchanger of a null MX record,
+    ///  [RFC 7505](https://tools.ietf.org/html/rfc7505), is not resolved.
+    ///
+    /// # Arguments
+    /// * `domain` - the domain receiving the mail
+    pub async fn lookup_mx_resolved<N: IntoName>(
+        &self,
+        domain: N,
+    ) -> Result<Vec<lookup::MxResolved>, ResolveError> {
+        let mx_lookup = self.mx_lookup(domain).await?;
+
+        let resolved = mx_lookup.iter().map(|mx| async move {
+            let exchange = mx.exchange().clone();
+            let addresses = if exchange.is_root() {
+                Vec::new()
+            } else {
+                match self.lookup_ip(exchange.clone()).await {
+                    Ok(lookup) => lookup.iter().collect(),
+                    Err(e) => {
+                        debug!("failed to resolve mail exchanger {exchange}: {e}");
+                        Vec::new()
+                    }
+                }
+            };
+
+            lookup::MxResolved {
+                preference: mx.preference(),
+                exchange,
+                addresses,
+            }
+        });
+
+        let mut resolved = future::join_all(resolved).await;
+        resolved.sort_by_key(|mx| mx.preference);
+        Ok(resolved)
+    }
+
     lookup_fn!(
         reverse_lookup,
         lookup::ReverseLookup,
@@ -450,6 +596,8 @@ impl<P: ConnectionProvider> AsyncResolver<P> {
     lookup_fn!(srv_lookup, lookup::SrvLookup, RecordType::SRV);
     lookup_fn!(tlsa_lookup, lookup::TlsaLookup, RecordType::TLSA);
     lookup_fn!(txt_lookup, lookup::TxtLookup, RecordType::TXT);
+    lookup_fn!(https_lookup, lookup::HttpsLookup, RecordType::HTTPS);
+    lookup_fn!(svcb_lookup, lookup::SvcbLookup, RecordType::SVCB);
 }
 
 impl<P: ConnectionProvider> fmt::Debug for AsyncResolver<P> {
@@ -1275,4 +1423,94 @@ mod tests {
             assert_eq!(resolver.build_names(name.clone()).len(), 2);
         }
     }
+
+    #[tokio::test]
+    async fn test_report_outcome_orders_lookup_ip() {
+        let mut resolver =
+            TokioAsyncResolver::tokio(ResolverConfig::new(), ResolverOpts::default());
+        let hosts = Hosts::default()
+            .read_hosts_conf("192.0.2.1 a.example.com\n192.0.2.2 a.example.com\n".as_bytes())
+            .unwrap();
+        resolver.set_hosts(Some(hosts));
+
+        let first = IpAddr::from([192, 0, 2, 1]);
+        let second = IpAddr::from([192, 0, 2, 2]);
+        let lookup = resolver.lookup_ip("a.example.com.").await.unwrap();
+        assert_eq!(lookup.iter().collect::<Vec<_>>(), [first, second]);
+
+        // the table is shared with the clones
+        resolver.clone().report_outcome(first, Outcome::Failed);
+        let lookup = resolver.lookup_ip("a.example.com.").await.unwrap();
+        assert_eq!(lookup.iter().collect::<Vec<_>>(), [second, first]);
+    }
+
+    #[tokio::test]
+    async fn test_lookup_mx_resolved() {
+        use proto::rr::rdata::{A, AAAA, MX};
+        use proto::rr::RData;
+        use std::time::{Duration, Instant};
+
+        let resolver = TokioAsyncResolver::tokio(
+            ResolverConfig::new(),
+            ResolverOpts {
+                ip_strategy: crate::config::LookupIpStrategy::Ipv4AndIpv6,
+                ..ResolverOpts::default()
+            },
+        );
+
+        let name = |name: &str| Name::from_ascii(name).unwrap();
+        let record = |owner: &str, rdata: RData| Record::from_rdata(name(owner), 300, rdata);
+        resolver.cache().prepopulate(
+            [
+                record(
+                    "example.com.",
+                    RData::MX(MX::new(20, name("backup.example.com."))),
+                ),
+                record(
+                    "example.com.",
+                    RData::MX(MX::new(10, name("mx.example.com."))),
+                ),
+                record("mx.example.com.", RData::A(A::new(192, 0, 2, 1))),
+                record(
+                    "mx.example.com.",
+                    RData::AAAA(AAAA::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+                ),
+                record("backup.example.com.", RData::A(A::new(192, 0, 2, 2))),
+                record("backup.example.com.", RData::A(A::new(192, 0, 2, 3))),
+                record(
+                    "backup.example.com.",
+                    RData::AAAA(AAAA::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2)),
+                ),
+            ],
+            Duration::from_secs(300),
+            Instant::now(),
+        );
+
+        let resolved = resolver
+            .lookup_mx_resolved("example.com.")
+            .await
+            .expect("lookup failed");
+        let addresses = |addresses: &[&str]| {
+            addresses
+                .iter()
+                .map(|address| address.parse::<IpAddr>().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            resolved,
+            [
+                lookup::MxResolved {
+                    preference: 10,
+                    exchange: name("mx.example.com."),
+                    addresses: addresses(&["192.0.2.1", "2001:db8::1"]),
+                },
+                lookup::MxResolved {
+                    preference: 20,
+                    exchange: name("backup.example.com."),
+                    addresses: addresses(&["192.0.2.2", "192.0.2.3", "2001:db8::2"]),
+                },
+            ]
+        );
+    }
 }
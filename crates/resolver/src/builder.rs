@@ -0,0 +1,214 @@
+// Copyright 2015-2024 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Builder for resolvers, which checks the options for conflicts
+
+use std::time::Duration;
+
+use crate::config::{ResolverConfig, ResolverOpts};
+use crate::dns_lru::{DEFAULT_NEGATIVE_MAX_TTL, MAX_TTL};
+use crate::error::ConfigError;
+#[cfg(any(unix, target_os = "windows"))]
+#[cfg(feature = "system-config")]
+use crate::error::ResolveError;
+use crate::name_server::ConnectionProvider;
+use crate::AsyncResolver;
+#[cfg(feature = "tokio-runtime")]
+use crate::{name_server::TokioConnectionProvider, Resolver, TokioAsyncResolver};
+
+/// Builds a resolver from a [`ResolverConfig`] and [`ResolverOpts`]
+///
+/// Unlike the constructors of the resolvers, the combination of the configuration and the
+///  options is checked when building, a conflict is reported as [`ConfigError`].
+///
+/// ```rust
+/// use hickory_resolver::config::{ResolverConfig, ResolverOpts};
+/// use hickory_resolver::ResolverBuilder;
+///
+/// let mut options = ResolverOpts::default();
+/// options.ndots = 2;
+///
+/// let builder = ResolverBuilder::new(ResolverConfig::cloudflare()).with_options(options);
+/// assert!(builder.validate().is_ok());
+/// ```
+#[derive(Clone, Debug)]
+#[must_use = "a resolver is only created by building it"]
+pub struct ResolverBuilder {
+    config: ResolverConfig,
+    options: ResolverOpts,
+}
+
+impl ResolverBuilder {
+    /// Creates a builder for the name servers of `config`, with the default [`ResolverOpts`]
+    pub fn new(config: ResolverConfig) -> Self {
+        Self {
+            config,
+            options: ResolverOpts::default(),
+        }
+    }
+
+    /// Creates a builder with the system configuration
+    ///
+    /// This will use `/etc/resolv.conf` on Unix OSes, including macOS, and the registry on Windows.
+    #[cfg(any(unix, target_os = "windows"))]
+    #[cfg(feature = "system-config")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(all(feature = "system-config", any(unix, target_os = "windows"))))
+    )]
+    pub fn from_system_config() -> Result<Self, ResolveError> {
+        let (config, options) = crate::system_conf::read_system_conf()?;
+        Ok(Self { config, options })
+    }
+
+    /// Creates a builder with the system configuration, or the fallback `policy` if it has no
+    ///  usable name servers
+    ///
+    /// This will use `/etc/resolv.conf` on Unix OSes, including macOS, and the registry on Windows.
+    #[cfg(any(unix, target_os = "windows"))]
+    #[cfg(feature = "system-config")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(all(feature = "system-config", any(unix, target_os = "windows"))))
+    )]
+    pub fn from_system_config_with_fallback(
+        policy: &crate::system_conf::FallbackPolicy,
+    ) -> Result<Self, ResolveError> {
+        let (config, options) = crate::system_conf::read_system_conf_with_fallback(policy)?;
+        Ok(Self { config, options })
+    }
+
+    /// Replaces the options of the resolver
+    pub fn with_options(mut self, options: ResolverOpts) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The configuration of the resolver
+    pub fn config(&self) -> &ResolverConfig {
+        &self.config
+    }
+
+    /// Mutable access to the configuration of the resolver
+    pub fn config_mut(&mut self) -> &mut ResolverConfig {
+        &mut self.config
+    }
+
+    /// The options of the resolver
+    pub fn options(&self) -> &ResolverOpts {
+        &self.options
+    }
+
+    /// Mutable access to the options of the resolver
+    pub fn options_mut(&mut self) -> &mut ResolverOpts {
+        &mut self.options
+    }
+
+    /// Checks the configuration and the options for conflicts
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.config.name_servers().is_empty() {
+            return Err(ConfigError::NoNameServers);
+        }
+
+        if self.options.validate && !cfg!(feature = "dnssec") {
+            return Err(ConfigError::DnssecUnavailable);
+        }
+
+        let max_ttl = Duration::from_secs(u64::from(MAX_TTL));
+        if let Some(min) = self.options.positive_min_ttl {
+            let max = self.options.positive_max_ttl.unwrap_or(max_ttl);
+            if min > max {
+                return Err(ConfigError::PositiveTtl { min, max });
+            }
+        }
+        if let Some(min) = self.options.negative_min_ttl {
+            let max = self
+                .options
+                .negative_max_ttl
+                .unwrap_or(Duration::from_secs(u64::from(DEFAULT_NEGATIVE_MAX_TTL)));
+            if min > max {
+                return Err(ConfigError::NegativeTtl { min, max });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validates the options and builds a [`Resolver`]
+    #[cfg(feature = "tokio-runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio-runtime")))]
+    pub fn build(self) -> Result<Resolver, ConfigError> {
+        self.validate()?;
+        Ok(Resolver::new(self.config, self.options)?)
+    }
+
+    /// Validates the options and builds a [`TokioAsyncResolver`]
+    #[cfg(feature = "tokio-runtime")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio-runtime")))]
+    pub fn build_async(self) -> Result<TokioAsyncResolver, ConfigError> {
+        self.build_with_provider(TokioConnectionProvider::default())
+    }
+
+    /// Validates the options and builds an [`AsyncResolver`], which uses `provider` for its
+    ///  connections
+    pub fn build_with_provider<P: ConnectionProvider>(
+        self,
+        provider: P,
+    ) -> Result<AsyncResolver<P>, ConfigError> {
+        self.validate()?;
+        Ok(AsyncResolver::new(self.config, self.options, provider))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let builder = ResolverBuilder::new(ResolverConfig::default());
+        assert!(builder.validate().is_ok());
+
+        let builder = ResolverBuilder::new(ResolverConfig::new());
+        assert!(matches!(
+            builder.validate(),
+            Err(ConfigError::NoNameServers)
+        ));
+    }
+
+    #[test]
+    fn test_validate_ttl() {
+        let mut builder = ResolverBuilder::new(ResolverConfig::default());
+        builder.options_mut().positive_min_ttl = Some(Duration::from_secs(60));
+        builder.options_mut().positive_max_ttl = Some(Duration::from_secs(30));
+        assert!(matches!(
+            builder.validate(),
+            Err(ConfigError::PositiveTtl { .. })
+        ));
+
+        let mut builder = ResolverBuilder::new(ResolverConfig::default());
+        builder.options_mut().negative_min_ttl = Some(Duration::from_secs(60));
+        builder.options_mut().negative_max_ttl = Some(Duration::from_secs(60));
+        assert!(builder.validate().is_ok());
+
+        // the maximum defaults to DEFAULT_NEGATIVE_MAX_TTL
+        builder.options_mut().negative_max_ttl = None;
+        builder.options_mut().negative_min_ttl =
+            Some(Duration::from_secs(u64::from(DEFAULT_NEGATIVE_MAX_TTL) + 1));
+        assert!(matches!(
+            builder.validate(),
+            Err(ConfigError::NegativeTtl { .. })
+        ));
+    }
+
+    #[test]
+    fn test_validate_dnssec() {
+        let mut builder = ResolverBuilder::new(ResolverConfig::default());
+        builder.options_mut().validate = true;
+        assert_eq!(builder.validate().is_ok(), cfg!(feature = "dnssec"));
+    }
+}
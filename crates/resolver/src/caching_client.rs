@@ -7,15 +7,7 @@
 
 //! Caching related functionality for the Resolver.
 
-use std::{
-    borrow::Cow,
-    pin::Pin,
-    sync::{
-        atomic::{AtomicU8, Ordering},
-        Arc,
-    },
-    time::Instant,
-};
+use std::{borrow::Cow, collections::HashSet, pin::Pin, time::Instant};
 
 use futures_util::future::{Future, TryFutureExt};
 use hickory_proto::error::ProtoErrorKind;
@@ -35,33 +27,68 @@ use crate::{
             },
             rdata::{A, AAAA, CNAME, PTR, SOA},
             resource::RecordRef,
-            DNSClass, Name, RData, Record, RecordType,
+            DNSClass, Name, RData, Record, RecordData, RecordType,
         },
         xfer::{DnsHandle, DnsRequestOptions, DnsResponse, FirstAnswer},
     },
 };
 
-const MAX_QUERY_DEPTH: u8 = 8; // arbitrarily chosen number...
+/// The default number of CNAME links followed, see [`crate::config::ResolverOpts::cname_chase_limit`]
+pub(crate) const DEFAULT_CNAME_CHASE_LIMIT: usize = 10;
 
 static LOCALHOST: Lazy<RData> =
     Lazy::new(|| RData::PTR(PTR(Name::from_ascii("localhost.").unwrap())));
 static LOCALHOST_V4: Lazy<RData> = Lazy::new(|| RData::A(A::new(127, 0, 0, 1)));
 static LOCALHOST_V6: Lazy<RData> = Lazy::new(|| RData::AAAA(AAAA::new(0, 0, 0, 0, 0, 0, 0, 1)));
 
-struct DepthTracker {
-    query_depth: Arc<AtomicU8>,
+/// The names visited while following the CNAME chain of a single lookup
+#[derive(Clone, Debug)]
+struct CnameChase {
+    visited: HashSet<Name>,
+    links: usize,
+    limit: usize,
 }
 
-impl DepthTracker {
-    fn track(query_depth: Arc<AtomicU8>) -> Self {
-        query_depth.fetch_add(1, Ordering::Release);
-        Self { query_depth }
+impl CnameChase {
+    fn new(limit: usize) -> Self {
+        Self {
+            visited: HashSet::new(),
+            links: 0,
+            limit,
+        }
     }
-}
 
-impl Drop for DepthTracker {
-    fn drop(&mut self) {
-        self.query_depth.fetch_sub(1, Ordering::Release);
+    /// Marks the name of a query as visited, this is not a link of the chain
+    fn visit(&mut self, name: &Name) {
+        self.visited.insert(name.clone());
+    }
+
+    /// Follows a CNAME link of the chain to `target`
+    ///
+    /// An error is returned if `target` was already visited, or if the chain now has more links
+    ///  than the limit.
+    fn follow(&mut self, target: &Name) -> Result<(), ProtoError> {
+        if !self.visited.insert(target.clone()) {
+            return Err(ProtoErrorKind::CnameLoop(target.clone()).into());
+        }
+
+        self.count_link(target)
+    }
+
+    /// Counts a link to `target` towards the limit, without checking it for loops
+    ///
+    /// SRV records can share targets, so these are only counted.
+    fn count_link(&mut self, target: &Name) -> Result<(), ProtoError> {
+        self.links += 1;
+        if self.links > self.limit {
+            return Err(ProtoErrorKind::CnameTooDeep {
+                name: target.clone(),
+                limit: self.limit,
+            }
+            .into());
+        }
+
+        Ok(())
     }
 }
 
@@ -75,8 +102,8 @@ where
 {
     lru: DnsLru,
     client: C,
-    query_depth: Arc<AtomicU8>,
     preserve_intermediates: bool,
+    cname_chase_limit: usize,
 }
 
 impl<C> CachingClient<C>
@@ -93,23 +120,30 @@ where
     }
 
     pub(crate) fn with_cache(lru: DnsLru, client: C, preserve_intermediates: bool) -> Self {
-        let query_depth = Arc::new(AtomicU8::new(0));
         Self {
             lru,
             client,
-            query_depth,
             preserve_intermediates,
+            cname_chase_limit: DEFAULT_CNAME_CHASE_LIMIT,
         }
     }
 
+    /// Sets the maximum number of CNAME links followed for a single lookup
+    pub(crate) fn with_cname_chase_limit(mut self, cname_chase_limit: usize) -> Self {
+        self.cname_chase_limit = cname_chase_limit;
+        self
+    }
+
     /// Perform a lookup against this caching client, looking first in the cache for a result
     pub fn lookup(
         &mut self,
         query: Query,
         options: DnsRequestOptions,
     ) -> Pin<Box<dyn Future<Output = Result<Lookup, ResolveError>> + Send>> {
+        let chase = CnameChase::new(self.cname_chase_limit);
         Box::pin(
-            Self::inner_lookup(query, options, self.clone(), vec![]).map_err(ResolveError::from),
+            Self::inner_lookup(query, options, self.clone(), vec![], chase)
+                .map_err(ResolveError::from),
         )
     }
 
@@ -118,6 +152,7 @@ where
         options: DnsRequestOptions,
         mut client: Self,
         preserved_records: Vec<(Record, u32)>,
+        mut chase: CnameChase,
     ) -> Result<Lookup, ProtoError> {
         // see https://tools.ietf.org/html/rfc6761
         //
@@ -176,7 +211,7 @@ where
             }
         }
 
-        let _tracker = DepthTracker::track(client.query_depth.clone());
+        chase.visit(query.name());
         let is_dnssec = client.client.is_verifying_dnssec();
 
         // first transition any polling that is needed (mutable refs...)
@@ -184,6 +219,19 @@ where
             return cached_lookup;
         };
 
+        // if the name is a known alias, only the query for the end of the chain needs to go upstream
+        let chain = client.cname_chain_from_cache(&query, Instant::now(), &mut chase)?;
+        if let Some(CNAME(target)) = chain.last().and_then(|(r, _)| CNAME::try_borrow(r.data())) {
+            let target = target.clone();
+            return match client
+                .lookup_cname_target(&query, target, options, chase)
+                .await
+            {
+                Ok(lookup) => Ok(client.cache_cname_chain(query, chain, lookup)),
+                Err(e) => client.cache(query, Err(e)),
+            };
+        }
+
         let response_message = client
             .client
             .lookup(query.clone(), options)
@@ -193,6 +241,10 @@ where
 
         // TODO: technically this might be duplicating work, as name_server already performs this evaluation.
         //  we may want to create a new type, if evaluated... but this is most generic to support any impl in LookupState...
+        let upstream = response_message
+            .as_ref()
+            .ok()
+            .and_then(|response| response.upstream().copied());
         let response_message = if let Ok(response) = response_message {
             ProtoError::from_response(response, false)
         } else {
@@ -234,6 +286,7 @@ where
                     &query,
                     response_message,
                     preserved_records,
+                    chase,
                 )?;
 
                 Ok(records)
@@ -246,10 +299,17 @@ where
                 next: future,
                 min_ttl: ttl,
             }) => match future.await {
-                Ok(lookup) => client.cname(lookup, query, ttl),
+                Ok(lookup) => {
+                    let upstream = lookup.upstream().copied();
+                    client
+                        .cname(lookup, query, ttl)
+                        .map(|lookup| lookup.with_upstream(upstream))
+                }
                 Err(e) => client.cache(query, Err(e)),
             },
-            Ok(Records::Exists(rdata)) => client.cache(query, Ok(rdata)),
+            Ok(Records::Exists(rdata)) => client
+                .cache(query, Ok(rdata))
+                .map(|lookup| lookup.with_upstream(upstream)),
             Err(e) => client.cache(query, Err(e)),
         }
     }
@@ -259,6 +319,123 @@ where
         self.lru.get(query, Instant::now())
     }
 
+    /// Follows the CNAME links for the query name that are already in the cache
+    ///
+    /// Each link is cached independently, with its own TTL, the chain ends at the first name without
+    ///  a current link. Returns the CNAME records of the chain, with their remaining TTLs, the last
+    ///  record points to the target name. The chain is empty if there is no cached link for the name.
+    ///
+    /// An error is returned if the cached links form a loop, or exceed the limit of `chase`.
+    fn cname_chain_from_cache(
+        &self,
+        query: &Query,
+        now: Instant,
+        chase: &mut CnameChase,
+    ) -> Result<Vec<(Record, u32)>, ProtoError> {
+        let mut chain = Vec::<(Record, u32)>::new();
+
+        // ANY and CNAME queries are answered with the link itself
+        if query.query_type().is_any() || query.query_type().is_cname() {
+            return Ok(chain);
+        }
+
+        let mut name = query.name().clone();
+        loop {
+            let mut link_query = Query::query(name.clone(), RecordType::CNAME);
+            link_query.set_query_class(query.query_class());
+
+            let Some(Ok(link)) = self.lru.get(&link_query, now) else {
+                break;
+            };
+            let Some((record, target)) = link.records().iter().find_map(|r| match r.data() {
+                RData::CNAME(CNAME(target)) if r.name() == &name => Some((r, target)),
+                _ => None,
+            }) else {
+                break;
+            };
+
+            chase.follow(target)?;
+            name = target.clone();
+            chain.push((record.clone(), record.ttl()));
+        }
+
+        Ok(chain)
+    }
+
+    /// Lookup the query type at the end of a cached CNAME chain
+    fn lookup_cname_target(
+        &self,
+        query: &Query,
+        target: Name,
+        options: DnsRequestOptions,
+        chase: CnameChase,
+    ) -> Pin<Box<dyn Future<Output = Result<Lookup, ProtoError>> + Send>> {
+        let mut next_query = Query::query(target, query.query_type());
+        next_query.set_query_class(query.query_class());
+
+        Box::pin(Self::inner_lookup(
+            next_query,
+            options,
+            self.clone(),
+            vec![],
+            chase,
+        ))
+    }
+
+    /// Caches each CNAME link of the chain starting at the query name in the answers
+    ///
+    /// This allows later queries for other record types at the same name to resolve through the
+    ///  cached links, see `cname_chain_from_cache`.
+    fn cache_cname_links(&self, query: &Query, answers: &[Record], now: Instant) {
+        let mut name = query.name();
+
+        // each record can be a link in the chain at most once, this also bounds any loops
+        for _ in 0..answers.len() {
+            let Some((record, target)) = answers.iter().find_map(|r| match r.data() {
+                RData::CNAME(CNAME(target))
+                    if r.name() == name && r.dns_class() == query.query_class() =>
+                {
+                    Some((r, target))
+                }
+                _ => None,
+            }) else {
+                break;
+            };
+
+            let mut link_query = Query::query(record.name().clone(), RecordType::CNAME);
+            link_query.set_query_class(record.dns_class());
+            self.lru
+                .insert(link_query, vec![(record.clone(), record.ttl())], now);
+
+            name = target;
+        }
+    }
+
+    /// Caches the lookup of the chain's target under the original query, with the CNAME chain
+    ///
+    /// The resulting entry is only valid as long as every link in the chain, and the target lookup are.
+    fn cache_cname_chain(&self, query: Query, chain: Vec<(Record, u32)>, lookup: Lookup) -> Lookup {
+        let now = Instant::now();
+        let lookup_ttl = u32::try_from(
+            lookup
+                .valid_until()
+                .saturating_duration_since(now)
+                .as_secs(),
+        )
+        .unwrap_or(dns_lru::MAX_TTL);
+        let ttl = chain.iter().map(|(_, ttl)| *ttl).fold(lookup_ttl, u32::min);
+
+        let records = chain
+            .into_iter()
+            .map(|(record, _)| record)
+            .filter(|_| self.preserve_intermediates)
+            .chain(lookup.records().iter().cloned())
+            .map(|record| (record, ttl))
+            .collect();
+
+        self.lru.insert(query, records, now)
+    }
+
     /// See https://tools.ietf.org/html/rfc2308
     ///
     /// For now we will regard NXDomain to strictly mean the query failed
@@ -316,6 +493,7 @@ where
         query: &Query,
         response: DnsResponse,
         mut preserved_records: Vec<(Record, u32)>,
+        mut chase: CnameChase,
     ) -> Result<Records, ProtoError> {
         // initial ttl is what CNAMES for min usage
         const INITIAL_TTL: u32 = dns_lru::MAX_TTL;
@@ -330,6 +508,8 @@ where
         //   for SRV, there could be many different targets. The search_name needs to be enhanced to
         //   be a list of names found for SRV records.
         let (search_name, cname_ttl, was_cname, preserved_records) = {
+            let mut was_srv = false;
+
             // this will only search for CNAMEs if the request was not meant to be for one of the triggers for recursion
             let (search_name, cname_ttl, was_cname) =
                 if query.query_type().is_any() || query.query_type().is_cname() {
@@ -341,7 +521,7 @@ where
                     // For SRV, the name added for the search becomes the target name.
                     //
                     // TODO: should this include the additionals?
-                    response.answers().iter().fold(
+                    response.answers().iter().try_fold(
                         (Cow::Borrowed(query.name()), INITIAL_TTL, false),
                         |(search_name, cname_ttl, was_cname), r| {
                             match r.data() {
@@ -350,7 +530,8 @@ where
                                     let ttl = cname_ttl.min(r.ttl());
                                     debug_assert_eq!(r.record_type(), RecordType::CNAME);
                                     if search_name.as_ref() == r.name() {
-                                        return (Cow::Owned(cname.clone()), ttl, true);
+                                        chase.follow(cname)?;
+                                        return Ok((Cow::Owned(cname.clone()), ttl, true));
                                     }
                                 }
                                 RData::SRV(ref srv) => {
@@ -359,16 +540,25 @@ where
                                     debug_assert_eq!(r.record_type(), RecordType::SRV);
 
                                     // the search name becomes the srv.target
-                                    return (Cow::Owned(srv.target().clone()), ttl, true);
+                                    was_srv = true;
+                                    return Ok((Cow::Owned(srv.target().clone()), ttl, true));
                                 }
                                 _ => (),
                             }
 
-                            (search_name, cname_ttl, was_cname)
+                            Ok::<_, ProtoError>((search_name, cname_ttl, was_cname))
                         },
-                    )
+                    )?
                 };
 
+            if was_srv {
+                chase.count_link(&search_name)?;
+            }
+
+            if was_cname {
+                client.cache_cname_links(query, response.answers(), Instant::now());
+            }
+
             // take all answers. // TODO: following CNAMES?
             let mut response = response.into_message();
             let answers = response.take_answers();
@@ -439,7 +629,7 @@ where
         // TODO: for SRV records we *could* do an implicit lookup, but, this requires knowing the type of IP desired
         //    for now, we'll make the API require the user to perform a follow up to the lookups.
         // It was a CNAME, but not included in the request...
-        if was_cname && client.query_depth.load(Ordering::Acquire) < MAX_QUERY_DEPTH {
+        if was_cname {
             let next_query = Query::query(search_name, query.query_type());
             Ok(Records::CnameChain {
                 next: Box::pin(Self::inner_lookup(
@@ -447,6 +637,7 @@ where
                     options,
                     client.clone(),
                     preserved_records,
+                    chase,
                 )),
                 min_ttl: cname_ttl,
             })
@@ -488,6 +679,11 @@ where
     pub fn clear_cache(&self) {
         self.lru.clear();
     }
+
+    /// The cache of the client
+    pub fn lru(&self) -> &DnsLru {
+        &self.lru
+    }
 }
 
 enum Records {
@@ -511,8 +707,10 @@ mod tests {
     use proto::op::{Message, Query};
     use proto::rr::rdata::{NS, SRV};
     use proto::rr::{Name, Record};
+    use proto::xfer::UpstreamMetadata;
 
     use super::*;
+    use crate::lookup::Provenance;
     use crate::lookup_ip::tests::*;
 
     #[test]
@@ -530,6 +728,7 @@ mod tests {
             DnsRequestOptions::default(),
             client,
             vec![],
+            CnameChase::new(DEFAULT_CNAME_CHASE_LIMIT),
         ))
         .unwrap_err()
         .kind()
@@ -566,6 +765,7 @@ mod tests {
             DnsRequestOptions::default(),
             client,
             vec![],
+            CnameChase::new(DEFAULT_CNAME_CHASE_LIMIT),
         ))
         .unwrap();
 
@@ -575,11 +775,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_prepopulated_before_upstream() {
+        let cache = DnsLru::new(1, dns_lru::TtlConfig::default());
+        let query = Query::new();
+        cache.prepopulate(
+            [Record::from_rdata(
+                query.name().clone(),
+                0,
+                RData::A(A::new(127, 0, 0, 1)),
+            )],
+            Duration::from_secs(3600),
+            Instant::now(),
+        );
+
+        // the upstream would fail
+        let client = mock(vec![error()]);
+        let client = CachingClient::with_cache(cache, client, false);
+
+        let lookup = block_on(CachingClient::inner_lookup(
+            Query::new(),
+            DnsRequestOptions::default(),
+            client,
+            vec![],
+            CnameChase::new(DEFAULT_CNAME_CHASE_LIMIT),
+        ))
+        .unwrap();
+
+        assert_eq!(lookup.provenance(), Provenance::Prepopulated);
+        assert_eq!(
+            lookup.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(A::new(127, 0, 0, 1))]
+        );
+    }
+
     #[test]
     fn test_no_cache_insert() {
         let cache = DnsLru::new(1, dns_lru::TtlConfig::default());
         // first should come from client...
-        let client = mock(vec![v4_message()]);
+        let upstream = UpstreamMetadata::new(SocketAddr::from(([192, 0, 2, 53], 53)), "udp");
+        let mut response = v4_message().unwrap();
+        response.set_upstream(upstream);
+        let client = mock(vec![Ok(response)]);
         let client = CachingClient::with_cache(cache.clone(), client, false);
 
         let ips = block_on(CachingClient::inner_lookup(
@@ -587,6 +824,7 @@ mod tests {
             DnsRequestOptions::default(),
             client,
             vec![],
+            CnameChase::new(DEFAULT_CNAME_CHASE_LIMIT),
         ))
         .unwrap();
 
@@ -594,6 +832,7 @@ mod tests {
             ips.iter().cloned().collect::<Vec<_>>(),
             vec![RData::A(A::new(127, 0, 0, 1))]
         );
+        assert_eq!(ips.upstream(), Some(&upstream));
 
         // next should come from cache...
         let client = mock(vec![empty()]);
@@ -604,6 +843,7 @@ mod tests {
             DnsRequestOptions::default(),
             client,
             vec![],
+            CnameChase::new(DEFAULT_CNAME_CHASE_LIMIT),
         ))
         .unwrap();
 
@@ -611,6 +851,7 @@ mod tests {
             ips.iter().cloned().collect::<Vec<_>>(),
             vec![RData::A(A::new(127, 0, 0, 1))]
         );
+        assert_eq!(ips.upstream(), None);
     }
 
     #[allow(clippy::unnecessary_wraps)]
@@ -675,6 +916,7 @@ mod tests {
             DnsRequestOptions::default(),
             client,
             vec![],
+            CnameChase::new(DEFAULT_CNAME_CHASE_LIMIT),
         ))
         .expect("lookup failed");
 
@@ -712,6 +954,7 @@ mod tests {
             DnsRequestOptions::default(),
             client,
             vec![],
+            CnameChase::new(DEFAULT_CNAME_CHASE_LIMIT),
         ))
         .expect("lookup failed");
 
@@ -763,6 +1006,7 @@ mod tests {
             DnsRequestOptions::default(),
             client,
             vec![],
+            CnameChase::new(DEFAULT_CNAME_CHASE_LIMIT),
         ))
         .expect("lookup failed");
 
@@ -864,6 +1108,7 @@ mod tests {
             DnsRequestOptions::default(),
             client,
             vec![],
+            CnameChase::new(DEFAULT_CNAME_CHASE_LIMIT),
         ))
         .expect("lookup failed");
 
@@ -901,6 +1146,7 @@ mod tests {
             &Query::query(Name::from_str("ttl.example.com.").unwrap(), RecordType::A),
             DnsResponse::from_message(message).unwrap(),
             vec![],
+            CnameChase::new(DEFAULT_CNAME_CHASE_LIMIT),
         );
 
         if let Ok(records) = records {
@@ -925,6 +1171,230 @@ mod tests {
         cname_ttl_test(2, 1);
     }
 
+    fn cdn_message(query_type: RecordType, rdata: RData, with_cname: bool) -> DnsResponse {
+        let www = Name::from_str("www.example.com.").unwrap();
+        let cdn = Name::from_str("cdn.example.net.").unwrap();
+
+        let mut message = Message::new();
+        if with_cname {
+            message.add_query(Query::query(www.clone(), query_type));
+            message.add_answer(Record::from_rdata(
+                www,
+                300,
+                RData::CNAME(CNAME(cdn.clone())),
+            ));
+        } else {
+            message.add_query(Query::query(cdn.clone(), query_type));
+        }
+        message.add_answer(Record::from_rdata(cdn, 60, rdata));
+        DnsResponse::from_message(message).unwrap()
+    }
+
+    #[test]
+    fn test_cname_link_cached_for_other_types() {
+        let cache = DnsLru::new(10, dns_lru::TtlConfig::default());
+        let www = Name::from_str("www.example.com.").unwrap();
+        let cdn = Name::from_str("cdn.example.net.").unwrap();
+        let v6 = RData::AAAA(AAAA::new(0, 0, 0, 0, 0, 0, 0, 1));
+
+        let client = mock(vec![
+            error(),
+            Ok(cdn_message(
+                RecordType::A,
+                RData::A(A::new(127, 0, 0, 1)),
+                true,
+            )),
+        ]);
+        let client = CachingClient::with_cache(cache.clone(), client, true);
+        block_on(CachingClient::inner_lookup(
+            Query::query(www.clone(), RecordType::A),
+            DnsRequestOptions::default(),
+            client,
+            vec![],
+            CnameChase::new(DEFAULT_CNAME_CHASE_LIMIT),
+        ))
+        .expect("lookup failed");
+
+        // the AAAA query should only be sent for the target of the cached link
+        let handle = mock(vec![
+            error(),
+            Ok(cdn_message(RecordType::AAAA, v6.clone(), false)),
+        ]);
+        let client = CachingClient::with_cache(cache, handle.clone(), true);
+        let lookup = block_on(CachingClient::inner_lookup(
+            Query::query(www.clone(), RecordType::AAAA),
+            DnsRequestOptions::default(),
+            client,
+            vec![],
+            CnameChase::new(DEFAULT_CNAME_CHASE_LIMIT),
+        ))
+        .expect("lookup failed");
+
+        assert_eq!(
+            handle.queries(),
+            vec![Query::query(cdn.clone(), RecordType::AAAA)]
+        );
+        assert_eq!(lookup.query(), &Query::query(www, RecordType::AAAA));
+        assert_eq!(
+            lookup.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::CNAME(CNAME(cdn)), v6]
+        );
+        // the chain is only valid for as long as the shortest TTL in it
+        assert!(lookup.valid_until() <= Instant::now() + Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_expired_cname_link_refetches_chain() {
+        let cache = DnsLru::new(10, dns_lru::TtlConfig::default());
+        let www = Name::from_str("www.example.com.").unwrap();
+        let cdn = Name::from_str("cdn.example.net.").unwrap();
+        let v6 = RData::AAAA(AAAA::new(0, 0, 0, 0, 0, 0, 0, 1));
+
+        // a link which has already expired
+        let then = Instant::now()
+            .checked_sub(Duration::from_secs(10))
+            .expect("system uptime too short");
+        cache.insert(
+            Query::query(www.clone(), RecordType::CNAME),
+            vec![(
+                Record::from_rdata(www.clone(), 1, RData::CNAME(CNAME(cdn))),
+                1,
+            )],
+            then,
+        );
+
+        let handle = mock(vec![
+            error(),
+            Ok(cdn_message(RecordType::AAAA, v6.clone(), true)),
+        ]);
+        let client = CachingClient::with_cache(cache, handle.clone(), true);
+        let lookup = block_on(CachingClient::inner_lookup(
+            Query::query(www.clone(), RecordType::AAAA),
+            DnsRequestOptions::default(),
+            client,
+            vec![],
+            CnameChase::new(DEFAULT_CNAME_CHASE_LIMIT),
+        ))
+        .expect("lookup failed");
+
+        assert_eq!(handle.queries(), vec![Query::query(www, RecordType::AAAA)]);
+        assert!(lookup.iter().any(|rdata| *rdata == v6));
+    }
+
+    #[test]
+    fn test_cached_cname_loop() {
+        let cache = DnsLru::new(10, dns_lru::TtlConfig::default());
+        let a = Name::from_str("a.example.com.").unwrap();
+        let b = Name::from_str("b.example.com.").unwrap();
+
+        for (from, to) in [(&a, &b), (&b, &a)] {
+            cache.insert(
+                Query::query(from.clone(), RecordType::CNAME),
+                vec![(
+                    Record::from_rdata(from.clone(), 300, RData::CNAME(CNAME(to.clone()))),
+                    300,
+                )],
+                Instant::now(),
+            );
+        }
+
+        let handle = mock(vec![error()]);
+        let client = CachingClient::with_cache(cache, handle.clone(), true);
+        let error = block_on(CachingClient::inner_lookup(
+            Query::query(a.clone(), RecordType::A),
+            DnsRequestOptions::default(),
+            client,
+            vec![],
+            CnameChase::new(DEFAULT_CNAME_CHASE_LIMIT),
+        ))
+        .unwrap_err();
+        assert!(matches!(error.kind(), ProtoErrorKind::CnameLoop(name) if *name == a));
+        assert!(handle.queries().is_empty());
+    }
+
+    fn cname_link_message(name: &Name, target: &Name) -> Result<DnsResponse, ProtoError> {
+        let mut message = Message::new();
+        message.add_query(Query::query(name.clone(), RecordType::A));
+        message.add_answer(Record::from_rdata(
+            name.clone(),
+            300,
+            RData::CNAME(CNAME(target.clone())),
+        ));
+        Ok(DnsResponse::from_message(message).unwrap())
+    }
+
+    #[test]
+    fn test_cname_loop() {
+        let cache = DnsLru::new(10, dns_lru::TtlConfig::default());
+        let a = Name::from_str("a.example.com.").unwrap();
+        let b = Name::from_str("b.example.com.").unwrap();
+
+        let handle = mock(vec![
+            error(),
+            cname_link_message(&b, &a),
+            cname_link_message(&a, &b),
+        ]);
+        let client = CachingClient::with_cache(cache, handle, true);
+        let error = block_on(CachingClient::inner_lookup(
+            Query::query(a.clone(), RecordType::A),
+            DnsRequestOptions::default(),
+            client,
+            vec![],
+            CnameChase::new(DEFAULT_CNAME_CHASE_LIMIT),
+        ))
+        .unwrap_err();
+
+        assert!(matches!(error.kind(), ProtoErrorKind::CnameLoop(name) if *name == a));
+    }
+
+    /// Looks up a chain of `links` CNAMEs, each in its own response, which ends at an A record
+    fn cname_chain_lookup(links: usize) -> Result<Lookup, ProtoError> {
+        let cache = DnsLru::new(100, dns_lru::TtlConfig::default());
+        let names = (0..=links)
+            .map(|i| Name::from_str(&format!("{i}.example.com.")).unwrap())
+            .collect::<Vec<_>>();
+
+        let mut target = Message::new();
+        target.add_query(Query::query(names[links].clone(), RecordType::A));
+        target.add_answer(Record::from_rdata(
+            names[links].clone(),
+            300,
+            RData::A(A::new(127, 0, 0, 1)),
+        ));
+
+        // the mock answers from the back
+        let mut responses = vec![error(), Ok(DnsResponse::from_message(target).unwrap())];
+        responses.extend(
+            names
+                .windows(2)
+                .rev()
+                .map(|link| cname_link_message(&link[0], &link[1])),
+        );
+
+        let client = CachingClient::with_cache(cache, mock(responses), true);
+        block_on(CachingClient::inner_lookup(
+            Query::query(names[0].clone(), RecordType::A),
+            DnsRequestOptions::default(),
+            client,
+            vec![],
+            CnameChase::new(DEFAULT_CNAME_CHASE_LIMIT),
+        ))
+    }
+
+    #[test]
+    fn test_cname_too_deep() {
+        let lookup = cname_chain_lookup(DEFAULT_CNAME_CHASE_LIMIT).expect("lookup failed");
+        assert!(lookup
+            .iter()
+            .any(|rdata| *rdata == RData::A(A::new(127, 0, 0, 1))));
+
+        let error = cname_chain_lookup(DEFAULT_CNAME_CHASE_LIMIT + 1).unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ProtoErrorKind::CnameTooDeep { limit: 10, .. }
+        ));
+    }
+
     #[test]
     fn test_early_return_localhost() {
         let cache = DnsLru::new(0, dns_lru::TtlConfig::default());
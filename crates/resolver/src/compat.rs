@@ -0,0 +1,412 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Lookups of `host:port` strings into socket addresses, like `std::net::ToSocketAddrs`
+//!
+//! See [`AsyncResolver::resolve_host_port`] and, with the `tokio-runtime` feature, the blocking
+//!  `Resolver::resolve_host_port`. With the `tower` feature, [`ResolverService`] implements
+//!  `tower::Service` over the same lookup, and with the `hyper-util` feature it can be used as
+//!  the resolver of the `HttpConnector` of hyper-util.
+
+use std::{
+    io,
+    net::{IpAddr, SocketAddr},
+    vec,
+};
+
+use proto::error::ProtoErrorKind;
+
+use crate::{error::ResolveError, name_server::ConnectionProvider, AsyncResolver};
+
+/// The socket addresses of a host, see [`AsyncResolver::resolve_host_port`]
+#[derive(Debug, Clone)]
+pub struct SocketAddrs {
+    iter: vec::IntoIter<SocketAddr>,
+}
+
+impl Iterator for SocketAddrs {
+    type Item = SocketAddr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<P: ConnectionProvider> AsyncResolver<P> {
+    /// Looks up the socket addresses of a `host:port` string, like `std::net::ToSocketAddrs`
+    ///
+    /// The port is required. IPv6 addresses must be in brackets, e.g. `[2001:db8::1]:443`. IP
+    ///  addresses are returned without a DNS lookup.
+    ///
+    /// # Errors
+    ///
+    /// An error of kind [`io::ErrorKind::InvalidInput`] if the string is not a `host:port`,
+    ///  [`io::ErrorKind::NotFound`] if the host has no addresses, other kinds for failed lookups.
+    pub async fn resolve_host_port(&self, host_port: &str) -> io::Result<SocketAddrs> {
+        let (host, port) = split_host_port(host_port)?;
+        lookup_socket_addrs(self, host, port).await
+    }
+}
+
+/// Splits `host:port`, the host of an IPv6 address in brackets is returned without them
+pub(crate) fn split_host_port(host_port: &str) -> io::Result<(&str, u16)> {
+    let (host, port) = match host_port.strip_prefix('[') {
+        Some(rest) => {
+            let (host, port) = rest
+                .split_once("]:")
+                .ok_or_else(|| invalid_input("expected [ipv6]:port"))?;
+            if host.parse::<IpAddr>().is_err() {
+                return Err(invalid_input("expected an IPv6 address in brackets"));
+            }
+            (host, port)
+        }
+        None => {
+            let (host, port) = host_port
+                .rsplit_once(':')
+                .ok_or_else(|| invalid_input("expected host:port"))?;
+            if host.contains(':') {
+                return Err(invalid_input(
+                    "IPv6 addresses with a port must be in brackets",
+                ));
+            }
+            (host, port)
+        }
+    };
+
+    if host.is_empty() {
+        return Err(invalid_input("the host is empty"));
+    }
+    let port = port
+        .parse::<u16>()
+        .map_err(|_| invalid_input("invalid port"))?;
+
+    Ok((host, port))
+}
+
+/// Looks up the addresses of the host, IP addresses are returned as is
+pub(crate) async fn lookup_socket_addrs<P: ConnectionProvider>(
+    resolver: &AsyncResolver<P>,
+    host: &str,
+    port: u16,
+) -> io::Result<SocketAddrs> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(SocketAddrs {
+            iter: vec![SocketAddr::new(ip, port)].into_iter(),
+        });
+    }
+
+    let lookup = resolver.lookup_ip(host).await.map_err(io_error)?;
+    let addrs = lookup
+        .iter()
+        .map(|ip| SocketAddr::new(ip, port))
+        .collect::<Vec<_>>();
+    if addrs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no addresses found for {host}"),
+        ));
+    }
+
+    Ok(SocketAddrs {
+        iter: addrs.into_iter(),
+    })
+}
+
+/// Converts the error of a lookup, a name without records is [`io::ErrorKind::NotFound`]
+pub(crate) fn io_error(error: ResolveError) -> io::Error {
+    match error.proto() {
+        Some(proto) if matches!(proto.kind(), ProtoErrorKind::NoRecordsFound { .. }) => {
+            io::Error::new(io::ErrorKind::NotFound, error)
+        }
+        Some(proto) => io::Error::from(proto.clone()),
+        None => io::Error::new(io::ErrorKind::Other, error),
+    }
+}
+
+fn invalid_input(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+#[cfg(feature = "tower")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+pub use service::ResolverService;
+
+#[cfg(feature = "tower")]
+mod service {
+    use std::{
+        future::Future,
+        io,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use tower_service::Service;
+
+    use super::{lookup_socket_addrs, split_host_port, SocketAddrs};
+    use crate::{name_server::ConnectionProvider, AsyncResolver};
+
+    /// A `tower::Service` looking up `host:port` strings, see [`AsyncResolver::resolve_host_port`]
+    ///
+    /// The service is always ready, every call looks up the host on a clone of the resolver.
+    ///  With the `hyper-util` feature, it is also a resolver for the `HttpConnector` of
+    ///  hyper-util, e.g. `HttpConnector::new_with_resolver(ResolverService::new(resolver))`.
+    #[derive(Clone, Debug)]
+    pub struct ResolverService<P: ConnectionProvider> {
+        resolver: AsyncResolver<P>,
+    }
+
+    impl<P: ConnectionProvider> ResolverService<P> {
+        /// Creates a service looking up hosts with the resolver
+        pub fn new(resolver: AsyncResolver<P>) -> Self {
+            Self { resolver }
+        }
+
+        /// The resolver of the lookups
+        pub fn resolver(&self) -> &AsyncResolver<P> {
+            &self.resolver
+        }
+    }
+
+    impl<P: ConnectionProvider> Service<String> for ResolverService<P> {
+        type Response = SocketAddrs;
+        type Error = io::Error;
+        type Future = Pin<Box<dyn Future<Output = io::Result<SocketAddrs>> + Send>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, host_port: String) -> Self::Future {
+            let resolver = self.resolver.clone();
+            Box::pin(async move {
+                let (host, port) = split_host_port(&host_port)?;
+                lookup_socket_addrs(&resolver, host, port).await
+            })
+        }
+    }
+
+    /// The `HttpConnector` of hyper-util sets the port of the returned addresses
+    #[cfg(feature = "hyper-util")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "hyper-util")))]
+    impl<P: ConnectionProvider> Service<hyper_util::client::legacy::connect::dns::Name>
+        for ResolverService<P>
+    {
+        type Response = SocketAddrs;
+        type Error = io::Error;
+        type Future = Pin<Box<dyn Future<Output = io::Result<SocketAddrs>> + Send>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, name: hyper_util::client::legacy::connect::dns::Name) -> Self::Future {
+            let resolver = self.resolver.clone();
+            Box::pin(async move {
+                let host = name.as_str();
+                let host = host
+                    .strip_prefix('[')
+                    .and_then(|host| host.strip_suffix(']'))
+                    .unwrap_or(host);
+                lookup_socket_addrs(&resolver, host, 0).await
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "tokio-runtime")]
+mod tests {
+    use std::{net::Ipv4Addr, sync::Arc};
+
+    use proto::{
+        error::ProtoError,
+        op::{Query, ResponseCode},
+        rr::{rdata::A, Name, RData, Record, RecordType},
+    };
+
+    use super::*;
+    use crate::{
+        config::{ResolverConfig, ResolverOpts},
+        lookup::Lookup,
+        Hosts, TokioAsyncResolver,
+    };
+
+    /// A resolver without name servers, answering from its hosts only
+    fn mock_resolver() -> TokioAsyncResolver {
+        let mut hosts = Hosts::default();
+
+        let name = Name::from_ascii("mock.test.").unwrap();
+        let record = Record::from_rdata(name.clone(), 86400, RData::A(A(Ipv4Addr::LOCALHOST)));
+        let query = Query::query(name.clone(), RecordType::A);
+        hosts.insert(
+            name,
+            RecordType::A,
+            Lookup::new_with_max_ttl(query, Arc::from([record])),
+        );
+
+        let empty = Name::from_ascii("empty.test.").unwrap();
+        for record_type in [RecordType::A, RecordType::AAAA] {
+            let query = Query::query(empty.clone(), record_type);
+            hosts.insert(
+                empty.clone(),
+                record_type,
+                Lookup::new_with_max_ttl(query, Arc::from([])),
+            );
+        }
+
+        let mut resolver =
+            TokioAsyncResolver::tokio(ResolverConfig::new(), ResolverOpts::default());
+        resolver.set_hosts(Some(hosts));
+        resolver
+    }
+
+    #[test]
+    fn test_split_host_port() {
+        assert_eq!(
+            split_host_port("example.com:443").unwrap(),
+            ("example.com", 443)
+        );
+        assert_eq!(split_host_port("192.0.2.1:53").unwrap(), ("192.0.2.1", 53));
+        assert_eq!(
+            split_host_port("[2001:db8::1]:8080").unwrap(),
+            ("2001:db8::1", 8080)
+        );
+
+        for invalid in [
+            "example.com",
+            "example.com:",
+            "example.com:65536",
+            ":80",
+            "2001:db8::1",
+            "2001:db8::1:80",
+            "[2001:db8::1]",
+            "[2001:db8::1]80",
+            "[example.com]:80",
+        ] {
+            let error = split_host_port(invalid).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_io_error() {
+        let no_records =
+            ProtoError::nx_error(Query::new(), None, None, ResponseCode::NXDomain, true);
+        assert_eq!(
+            io_error(ResolveError::from(no_records)).kind(),
+            io::ErrorKind::NotFound
+        );
+
+        let timeout = ProtoError::from(ProtoErrorKind::Timeout);
+        assert_eq!(
+            io_error(ResolveError::from(timeout)).kind(),
+            io::ErrorKind::TimedOut
+        );
+        assert_eq!(
+            io_error(ResolveError::from("failed")).kind(),
+            io::ErrorKind::Other
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_host_port() {
+        let resolver = mock_resolver();
+
+        // there are no name servers, literal addresses are not looked up
+        let addrs = resolver.resolve_host_port("192.0.2.1:53").await.unwrap();
+        assert_eq!(addrs.collect::<Vec<_>>(), ["192.0.2.1:53".parse().unwrap()]);
+        let addrs = resolver
+            .resolve_host_port("[2001:db8::1]:443")
+            .await
+            .unwrap();
+        assert_eq!(
+            addrs.collect::<Vec<_>>(),
+            ["[2001:db8::1]:443".parse().unwrap()]
+        );
+
+        let addrs = resolver.resolve_host_port("mock.test.:8080").await.unwrap();
+        assert_eq!(
+            addrs.collect::<Vec<_>>(),
+            ["127.0.0.1:8080".parse().unwrap()]
+        );
+
+        let error = resolver
+            .resolve_host_port("empty.test.:80")
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        let error = resolver.resolve_host_port("mock.test.").await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[cfg(feature = "tower")]
+    #[tokio::test]
+    async fn test_service() {
+        use futures_util::future::poll_fn;
+        use tower_service::Service;
+
+        let mut service = ResolverService::new(mock_resolver());
+
+        poll_fn(|cx| Service::<String>::poll_ready(&mut service, cx))
+            .await
+            .unwrap();
+        let addrs = service.call("[::1]:53".to_string()).await.unwrap();
+        assert_eq!(addrs.collect::<Vec<_>>(), ["[::1]:53".parse().unwrap()]);
+
+        let addrs = service.call("mock.test.:53".to_string()).await.unwrap();
+        assert_eq!(addrs.collect::<Vec<_>>(), ["127.0.0.1:53".parse().unwrap()]);
+
+        let error = service
+            .call("empty.test.:53".to_string())
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[cfg(feature = "hyper-util")]
+    #[tokio::test]
+    async fn test_hyper_client() {
+        use hyper_util::{
+            client::legacy::{connect::HttpConnector, Client},
+            rt::TokioExecutor,
+        };
+        use tokio::{
+            io::{AsyncReadExt, AsyncWriteExt},
+            net::TcpListener,
+        };
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 1024];
+            let len = stream.read(&mut request).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\nconnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8(request[..len].to_vec()).unwrap()
+        });
+
+        let connector = HttpConnector::new_with_resolver(ResolverService::new(mock_resolver()));
+        let client = Client::builder(TokioExecutor::new()).build::<_, String>(connector);
+
+        let uri = format!("http://mock.test.:{port}/").parse().unwrap();
+        let response = client.get(uri).await.unwrap();
+        assert_eq!(response.status(), 204);
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("GET / HTTP/1.1\r\n"), "{request}");
+
+        let uri = format!("http://empty.test.:{port}/").parse().unwrap();
+        assert!(client.get(uri).await.is_err());
+    }
+}
@@ -352,7 +352,14 @@ pub enum Protocol {
 
 impl fmt::Display for Protocol {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let protocol = match self {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Protocol {
+    /// The lowercase name of the protocol, e.g. `udp`
+    pub fn as_str(self) -> &'static str {
+        match self {
             Self::Udp => "udp",
             Self::Tcp => "tcp",
             #[cfg(feature = "dns-over-tls")]
@@ -365,13 +372,9 @@ impl fmt::Display for Protocol {
             Self::H3 => "h3",
             #[cfg(feature = "mdns")]
             Self::Mdns => "mdns",
-        };
-
-        f.write_str(protocol)
+        }
     }
-}
 
-impl Protocol {
     /// Returns true if this is a datagram oriented protocol, e.g. UDP
     pub fn is_datagram(self) -> bool {
         match self {
@@ -887,6 +890,33 @@ impl Default for LookupIpStrategy {
     }
 }
 
+/// The address family used to reach the upstream name servers
+///
+/// Unlike [`LookupIpStrategy`], which decides the record types queried, this selects which of the
+///  configured name server addresses are used, e.g. to avoid IPv6 on hosts where it is assigned
+///  but not routed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+pub enum UpstreamAddressFamily {
+    /// Name servers are used regardless of their address family (default)
+    Any,
+    /// Only name servers with an IPv4 address are used
+    V4Only,
+    /// Only name servers with an IPv6 address are used
+    V6Only,
+    /// Name servers with an IPv4 address are tried first
+    PreferV4,
+    /// Name servers with an IPv6 address are tried first
+    PreferV6,
+}
+
+impl Default for UpstreamAddressFamily {
+    /// Returns [`UpstreamAddressFamily::Any`] as the default.
+    fn default() -> Self {
+        Self::Any
+    }
+}
+
 /// The strategy for establishing the query order of name servers in a pool.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
@@ -906,6 +936,60 @@ impl Default for ServerOrderingStrategy {
     }
 }
 
+/// The handling of error responses, e.g. SERVFAIL, from a name server of a pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+pub enum ServerFailurePolicy {
+    /// SERVFAIL, FORMERR, NOTIMP and other error responses are retried on the remaining name
+    /// servers of the pool, the error is only returned once all of them failed.
+    TryNextServer,
+    /// The first response of any name server is returned, only name servers which did not
+    /// respond at all are retried. This favors latency over resilience to failing servers.
+    FirstAnswer,
+}
+
+impl Default for ServerFailurePolicy {
+    /// Returns [`ServerFailurePolicy::TryNextServer`] as the default.
+    fn default() -> Self {
+        Self::TryNextServer
+    }
+}
+
+/// Duplicates a sample of the queries to a candidate upstream, to compare it with the configured
+///  name servers before migrating to it
+///
+/// The shadow queries are sent once the answer of the name servers was returned, their answers are
+///  never used. The outcomes are recorded in the [`crate::name_server::ShadowLog`] of the resolver.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+pub struct ShadowConfig {
+    /// The candidate upstream
+    pub upstream: NameServerConfigGroup,
+    /// The fraction of the queries which is duplicated, from 0.0 to 1.0
+    pub sample_rate: f64,
+    /// Compare the answers of the shadow upstream, otherwise only its latency and errors are
+    ///  recorded
+    pub compare: bool,
+    /// Sample by a hash of the query name instead of randomly, so that all queries for a name are
+    ///  either duplicated or not
+    pub deterministic: bool,
+}
+
+impl ShadowConfig {
+    /// Duplicates the `sample_rate` of the queries to `upstream`, comparing the answers
+    pub fn new(upstream: NameServerConfigGroup, sample_rate: f64) -> Self {
+        Self {
+            upstream,
+            sample_rate,
+            compare: true,
+            deterministic: false,
+        }
+    }
+}
+
+// the sample rate is only compared with the random or hashed sample, NaN never samples
+impl Eq for ShadowConfig {}
+
 /// Configuration for the Resolver
 #[derive(Debug, Clone, Eq, PartialEq)]
 #[cfg_attr(
@@ -933,6 +1017,9 @@ pub struct ResolverOpts {
     pub edns0: bool,
     /// Use DNSSEC to validate the request
     pub validate: bool,
+    /// Record the DNSSEC algorithms, key sizes and denial mechanisms of the validated zones, see
+    ///  [`crate::AsyncResolver::dnssec_observations`]. Only used with `validate`, defaults to false
+    pub dnssec_observations: bool,
     /// The ip_strategy for the Resolver to use when lookup Ipv4 or Ipv6 addresses
     pub ip_strategy: LookupIpStrategy,
     /// Cache size is in number of records (some records can be large)
@@ -959,10 +1046,40 @@ pub struct ResolverOpts {
     /// Optional maximum TTL for negative (`NXDOMAIN`) responses.
     ///
     /// If this is set, any negative responses with a TTL higher than this value will have a TTL of
-    /// `negative_max_ttl` instead. Otherwise, this will default to [`MAX_TTL`] seconds.
+    /// `negative_max_ttl` instead. Otherwise, this will default to [`DEFAULT_NEGATIVE_MAX_TTL`]
+    /// seconds, i.e. 3 hours.
     ///
-    /// [`MAX_TTL`]: ../dns_lru/const.MAX_TTL.html
+    /// [`DEFAULT_NEGATIVE_MAX_TTL`]: crate::dns_lru::DEFAULT_NEGATIVE_MAX_TTL
     pub negative_max_ttl: Option<Duration>,
+    /// Optional TTL for negative responses without an SOA record in the authority section.
+    ///
+    /// The negative TTL is taken from the SOA record, RFC 2308 recommends to not cache negative
+    /// responses without one. If this is set, such responses are cached for this duration, it
+    /// should be kept short. Defaults to `None`, i.e. not cached.
+    pub negative_ttl_without_soa: Option<Duration>,
+    /// Optional minimum TTL for delegation data, i.e. NS records, DS records and glue.
+    ///
+    /// Defaults to [`DEFAULT_INFRASTRUCTURE_MIN_TTL`] seconds, so that the delegations of popular
+    /// zones aren't refetched constantly.
+    ///
+    /// [`DEFAULT_INFRASTRUCTURE_MIN_TTL`]: crate::dns_lru::DEFAULT_INFRASTRUCTURE_MIN_TTL
+    pub infrastructure_min_ttl: Option<Duration>,
+    /// Optional maximum TTL for delegation data, i.e. NS records, DS records and glue.
+    ///
+    /// This is used instead of `positive_max_ttl` for delegation data. Defaults to
+    /// [`DEFAULT_INFRASTRUCTURE_MAX_TTL`] seconds, i.e. 6 hours, so that changes to a delegation
+    /// are picked up even if the zone publishes much longer TTLs.
+    ///
+    /// [`DEFAULT_INFRASTRUCTURE_MAX_TTL`]: crate::dns_lru::DEFAULT_INFRASTRUCTURE_MAX_TTL
+    pub infrastructure_max_ttl: Option<Duration>,
+    /// Optional jitter for the TTL of delegation data.
+    ///
+    /// A random duration of up to this value, and at most a tenth of the TTL, is subtracted from
+    /// the TTL of delegation data when it is cached, so that the records of one response don't
+    /// all expire at the same time. Defaults to [`DEFAULT_INFRASTRUCTURE_TTL_JITTER`] seconds.
+    ///
+    /// [`DEFAULT_INFRASTRUCTURE_TTL_JITTER`]: crate::dns_lru::DEFAULT_INFRASTRUCTURE_TTL_JITTER
+    pub infrastructure_ttl_jitter: Option<Duration>,
     /// Number of concurrent requests per query
     ///
     /// Where more than one nameserver is configured, this configures the resolver to send queries
@@ -970,6 +1087,11 @@ pub struct ResolverOpts {
     pub num_concurrent_reqs: usize,
     /// Preserve all intermediate records in the lookup response, such as CNAME records
     pub preserve_intermediates: bool,
+    /// The maximum number of CNAME links followed for a lookup, defaults to 10
+    ///
+    /// A longer chain fails with `ProtoErrorKind::CnameTooDeep`, a chain which leads back to one of
+    ///  its names fails with `ProtoErrorKind::CnameLoop`.
+    pub cname_chase_limit: usize,
     /// Try queries over TCP if they fail over UDP.
     pub try_tcp_on_error: bool,
     /// The server ordering strategy that the resolver should use.
@@ -982,6 +1104,15 @@ pub struct ResolverOpts {
     pub authentic_data: bool,
     /// Shuffle DNS servers before each query.
     pub shuffle_dns_servers: bool,
+    /// The address family used to reach the upstream name servers.
+    ///
+    /// This can be changed at runtime, e.g. when the network interfaces change, with
+    ///  [`crate::AsyncResolver::set_upstream_address_family`].
+    pub upstream_address_family: UpstreamAddressFamily,
+    /// The handling of error responses from a name server, e.g. SERVFAIL.
+    pub server_failure_policy: ServerFailurePolicy,
+    /// Duplicates a sample of the queries to a shadow upstream, see [`ShadowConfig`].
+    pub shadow: Option<ShadowConfig>,
 }
 
 impl Default for ResolverOpts {
@@ -997,6 +1128,7 @@ impl Default for ResolverOpts {
             check_names: true,
             edns0: false,
             validate: false,
+            dnssec_observations: false,
             ip_strategy: LookupIpStrategy::default(),
             cache_size: 32,
             use_hosts_file: true,
@@ -1004,16 +1136,25 @@ impl Default for ResolverOpts {
             negative_min_ttl: None,
             positive_max_ttl: None,
             negative_max_ttl: None,
+            negative_ttl_without_soa: None,
+            infrastructure_min_ttl: None,
+            infrastructure_max_ttl: None,
+            infrastructure_ttl_jitter: None,
             num_concurrent_reqs: 2,
 
             // Defaults to `true` to match the behavior of dig and nslookup.
             preserve_intermediates: true,
 
+            cname_chase_limit: 10,
+
             try_tcp_on_error: false,
             server_ordering_strategy: ServerOrderingStrategy::default(),
             recursion_desired: true,
             authentic_data: false,
             shuffle_dns_servers: false,
+            upstream_address_family: UpstreamAddressFamily::default(),
+            server_failure_policy: ServerFailurePolicy::default(),
+            shadow: None,
         }
     }
 }
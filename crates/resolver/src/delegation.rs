@@ -0,0 +1,596 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Consistency checks of a delegation, between the parent zone and the apex of the child zone
+//!
+//! The NS records of a zone exist twice, at the zone cut in the parent zone and at the apex of the
+//!  child zone, along with the glue addresses of in-bailiwick name servers in the parent zone.
+//!  [`DelegationChecker`] queries the name servers of both zones directly, without recursion,
+//!  and reports where they disagree, and which name servers don't answer authoritatively.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+
+use tracing::debug;
+
+use crate::config::{NameServerConfig, Protocol, ResolverOpts};
+use crate::error::{ResolveError, ResolveErrorKind, ResolveResult};
+use crate::name_server::ConnectionProvider;
+use crate::proto::error::{ProtoError, ProtoErrorKind};
+use crate::proto::op::{Query, ResponseCode};
+use crate::proto::rr::{Name, RData, Record, RecordType};
+use crate::proto::xfer::{DnsHandle, DnsRequestOptions, DnsResponse, FirstAnswer};
+use crate::AsyncResolver;
+
+/// A difference between the delegation in the parent zone and the child zone, or a lame server
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DelegationFinding {
+    /// A name server at the apex of the child zone which the parent does not delegate to
+    MissingAtParent(Name),
+    /// A name server the parent delegates to which is missing at the apex of the child zone
+    MissingAtChild(Name),
+    /// The glue of an in-bailiwick name server differs from its addresses in the child zone
+    GlueMismatch {
+        /// The name of the name server
+        name: Name,
+        /// The glue addresses in the parent zone
+        glue: Vec<IpAddr>,
+        /// The addresses in the child zone
+        child: Vec<IpAddr>,
+    },
+    /// A name server the parent delegates to which does not answer authoritatively for the zone
+    LameServer {
+        /// The name of the name server
+        name: Name,
+        /// The address which was queried, None if the name server has no known address
+        addr: Option<IpAddr>,
+        /// What was observed
+        reason: LameReason,
+    },
+}
+
+impl fmt::Display for DelegationFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingAtParent(name) => write!(f, "missing at parent: {name}"),
+            Self::MissingAtChild(name) => write!(f, "missing at child: {name}"),
+            Self::GlueMismatch { name, glue, child } => {
+                write!(f, "glue mismatch: {name} parent {glue:?} child {child:?}")
+            }
+            Self::LameServer {
+                name,
+                addr: Some(addr),
+                reason,
+            } => write!(f, "lame server: {name} at {addr}: {reason}"),
+            Self::LameServer {
+                name,
+                addr: None,
+                reason,
+            } => write!(f, "lame server: {name}: {reason}"),
+        }
+    }
+}
+
+/// Why a name server is lame for a zone
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LameReason {
+    /// The name server answered with this response code
+    ResponseCode(ResponseCode),
+    /// The name server answered without the AA bit
+    NotAuthoritative,
+    /// The name server did not answer in time
+    Timeout,
+    /// No address of the name server is known
+    NoAddress,
+    /// The query failed otherwise, e.g. the connection was refused
+    Error(String),
+}
+
+impl fmt::Display for LameReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ResponseCode(code) => write!(f, "response code {code}"),
+            Self::NotAuthoritative => f.write_str("not authoritative"),
+            Self::Timeout => f.write_str("timeout"),
+            Self::NoAddress => f.write_str("no address"),
+            Self::Error(error) => write!(f, "error: {error}"),
+        }
+    }
+}
+
+/// The result of a delegation check
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DelegationReport {
+    zone: Name,
+    parent_name_servers: Vec<Name>,
+    child_name_servers: Vec<Name>,
+    findings: Vec<DelegationFinding>,
+}
+
+impl DelegationReport {
+    /// The zone which was checked
+    pub fn zone(&self) -> &Name {
+        &self.zone
+    }
+
+    /// The name servers the parent delegates to
+    pub fn parent_name_servers(&self) -> &[Name] {
+        &self.parent_name_servers
+    }
+
+    /// The name servers at the apex of the child zone, empty if no name server answered
+    ///  authoritatively
+    pub fn child_name_servers(&self) -> &[Name] {
+        &self.child_name_servers
+    }
+
+    /// The differences and lame servers which were found
+    pub fn findings(&self) -> &[DelegationFinding] {
+        &self.findings
+    }
+
+    /// Returns true if nothing was found
+    pub fn is_consistent(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+/// Checks the delegation of zones by querying the name servers directly
+///
+/// The name servers of the parent zone are taken from [`Self::with_parent_servers`], or looked up
+///  with the resolver of [`Self::with_resolver`]. The resolver also looks up the addresses of
+///  name servers which have no glue. All other queries are sent to the name servers of the
+///  parent and the child zone, without recursion.
+pub struct DelegationChecker<P: ConnectionProvider> {
+    options: ResolverOpts,
+    conn_provider: P,
+    port: u16,
+    parent_servers: Vec<IpAddr>,
+    resolver: Option<AsyncResolver<P>>,
+}
+
+impl<P: ConnectionProvider> DelegationChecker<P> {
+    /// Creates a new checker, connecting to the name servers with the provider
+    ///
+    /// The timeout of the options applies to every query.
+    pub fn new(options: ResolverOpts, conn_provider: P) -> Self {
+        Self {
+            options,
+            conn_provider,
+            port: 53,
+            parent_servers: vec![],
+            resolver: None,
+        }
+    }
+
+    /// Queries these name servers of the parent zone
+    pub fn with_parent_servers(mut self, parent_servers: Vec<IpAddr>) -> Self {
+        self.parent_servers = parent_servers;
+        self
+    }
+
+    /// Looks up the name servers of the parent zone, and addresses without glue, with the resolver
+    pub fn with_resolver(mut self, resolver: AsyncResolver<P>) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
+    /// Sends the queries to this port instead of 53
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Checks the delegation of the zone
+    ///
+    /// An error is returned if the delegation can't be read from the parent, lame servers and
+    ///  inconsistencies of the child zone are reported as findings.
+    pub async fn check(&self, zone: &Name) -> ResolveResult<DelegationReport> {
+        let delegation = self.query_parent(zone).await?;
+        debug!("delegation of {zone}: {:?}", delegation);
+
+        let mut lame = Vec::new();
+        let mut child = ChildView::default();
+        for (name, glue) in &delegation {
+            let addrs = if glue.is_empty() {
+                self.lookup_addrs(name).await
+            } else {
+                glue.clone()
+            };
+
+            if addrs.is_empty() {
+                lame.push(DelegationFinding::LameServer {
+                    name: name.clone(),
+                    addr: None,
+                    reason: LameReason::NoAddress,
+                });
+            }
+
+            for addr in addrs {
+                match self.query_child(addr, zone).await {
+                    Ok(name_servers) => {
+                        child.name_servers.extend(name_servers);
+                        child.authoritative.get_or_insert(addr);
+                    }
+                    Err(reason) => lame.push(DelegationFinding::LameServer {
+                        name: name.clone(),
+                        addr: Some(addr),
+                        reason,
+                    }),
+                }
+            }
+        }
+
+        // the addresses of the in-bailiwick name servers in the child zone
+        if let Some(addr) = child.authoritative {
+            for name in delegation.keys().filter(|name| zone.zone_of(name)) {
+                match self.query_addrs(addr, name).await {
+                    Ok(addrs) => {
+                        child.addrs.insert(name.clone(), addrs);
+                    }
+                    Err(error) => debug!("failed to query the addresses of {name}: {error}"),
+                }
+            }
+        }
+
+        let mut findings = compare(zone, &delegation, &child);
+        findings.extend(lame);
+
+        Ok(DelegationReport {
+            zone: zone.clone(),
+            parent_name_servers: delegation.into_keys().collect(),
+            child_name_servers: child.name_servers.into_iter().collect(),
+            findings,
+        })
+    }
+
+    /// Returns the name servers of the delegation with their glue addresses
+    async fn query_parent(&self, zone: &Name) -> ResolveResult<Delegation> {
+        let parent_servers = match (&self.parent_servers, &self.resolver) {
+            (servers, _) if !servers.is_empty() => servers.clone(),
+            (_, Some(resolver)) => {
+                let parent = zone.base_name();
+                let mut servers = vec![];
+                for name_server in resolver.ns_lookup(parent).await?.iter() {
+                    servers.extend(self.lookup_addrs(&name_server.0).await);
+                }
+                servers
+            }
+            (_, None) => {
+                return Err(ResolveErrorKind::Message("no name servers of the parent zone").into())
+            }
+        };
+
+        let mut last_error = ResolveError::from("no name server of the parent zone answered");
+        for addr in parent_servers {
+            let response = match self.query(addr, zone, RecordType::NS).await {
+                Ok(response) => response,
+                Err(error) => {
+                    debug!("parent name server {addr} failed: {error}");
+                    last_error = error.into();
+                    continue;
+                }
+            };
+
+            if response.response_code() != ResponseCode::NoError {
+                last_error = ResolveErrorKind::Msg(format!(
+                    "parent name server {addr} answered {}",
+                    response.response_code()
+                ))
+                .into();
+                continue;
+            }
+
+            let delegation = delegation_of(zone, &response);
+            if delegation.is_empty() {
+                last_error =
+                    ResolveErrorKind::Msg(format!("{zone} is not delegated by {addr}")).into();
+                continue;
+            }
+
+            return Ok(delegation);
+        }
+
+        Err(last_error)
+    }
+
+    /// Returns the name servers at the apex of the zone, if the answer is authoritative
+    async fn query_child(&self, addr: IpAddr, zone: &Name) -> Result<Vec<Name>, LameReason> {
+        let response = self
+            .query(addr, zone, RecordType::NS)
+            .await
+            .map_err(|error| match error.kind() {
+                ProtoErrorKind::Timeout => LameReason::Timeout,
+                _ => LameReason::Error(error.to_string()),
+            })?;
+
+        if response.response_code() != ResponseCode::NoError {
+            return Err(LameReason::ResponseCode(response.response_code()));
+        }
+
+        if !response.authoritative() {
+            return Err(LameReason::NotAuthoritative);
+        }
+
+        Ok(name_servers(zone, response.answers()).collect())
+    }
+
+    /// Returns the A and AAAA addresses of the name in the answers of the name server
+    async fn query_addrs(&self, addr: IpAddr, name: &Name) -> Result<Vec<IpAddr>, ProtoError> {
+        let mut addrs = vec![];
+        for record_type in [RecordType::A, RecordType::AAAA] {
+            let response = self.query(addr, name, record_type).await?;
+            addrs.extend(addresses(name, response.answers()));
+        }
+
+        addrs.sort();
+        addrs.dedup();
+        Ok(addrs)
+    }
+
+    /// Looks up the addresses of the name with the resolver
+    async fn lookup_addrs(&self, name: &Name) -> Vec<IpAddr> {
+        let Some(resolver) = &self.resolver else {
+            return vec![];
+        };
+
+        match resolver.lookup_ip(name.clone()).await {
+            Ok(lookup) => lookup.iter().collect(),
+            Err(error) => {
+                debug!("failed to look up the addresses of {name}: {error}");
+                vec![]
+            }
+        }
+    }
+
+    /// Sends a query without recursion to the name server
+    async fn query(
+        &self,
+        addr: IpAddr,
+        name: &Name,
+        record_type: RecordType,
+    ) -> Result<DnsResponse, ProtoError> {
+        let config = NameServerConfig::new(SocketAddr::new(addr, self.port), Protocol::Udp);
+        let conn = self
+            .conn_provider
+            .new_connection(&config, &self.options)
+            .await?;
+
+        let mut options = DnsRequestOptions::default();
+        options.recursion_desired = false;
+        options.use_edns = self.options.edns0;
+
+        conn.lookup(Query::query(name.clone(), record_type), options)
+            .first_answer()
+            .await
+    }
+}
+
+/// The name servers of a delegation, with their glue addresses
+type Delegation = BTreeMap<Name, Vec<IpAddr>>;
+
+/// What the name servers of the child zone answered
+#[derive(Debug, Default)]
+struct ChildView {
+    /// The name servers at the apex
+    name_servers: BTreeSet<Name>,
+    /// The addresses of the in-bailiwick name servers
+    addrs: BTreeMap<Name, Vec<IpAddr>>,
+    /// The first name server address which answered authoritatively
+    authoritative: Option<IpAddr>,
+}
+
+/// Reads the delegation of the zone from the response of a name server of the parent zone
+///
+/// The NS records are in the authority section of a referral, or in the answers if the name
+///  server is authoritative for the child zone as well.
+fn delegation_of(zone: &Name, response: &DnsResponse) -> Delegation {
+    let records = response.answers().iter().chain(response.name_servers());
+    let mut delegation = name_servers(zone, records)
+        .map(|name| (name, vec![]))
+        .collect::<Delegation>();
+
+    for (name, glue) in &mut delegation {
+        glue.extend(addresses(name, response.additionals()));
+        glue.sort();
+        glue.dedup();
+    }
+
+    delegation
+}
+
+/// The names of the NS records of the zone
+fn name_servers<'a>(
+    zone: &'a Name,
+    records: impl IntoIterator<Item = &'a Record> + 'a,
+) -> impl Iterator<Item = Name> + 'a {
+    records
+        .into_iter()
+        .filter(move |record| record.name() == zone)
+        .filter_map(|record| match record.data() {
+            RData::NS(ns) => Some(ns.0.clone()),
+            _ => None,
+        })
+}
+
+/// The addresses of the A and AAAA records of the name
+fn addresses<'a>(
+    name: &'a Name,
+    records: impl IntoIterator<Item = &'a Record> + 'a,
+) -> impl Iterator<Item = IpAddr> + 'a {
+    records
+        .into_iter()
+        .filter(move |record| record.name() == name)
+        .filter_map(|record| record.data().ip_addr())
+}
+
+/// Compares the delegation with the answers of the child zone
+fn compare(zone: &Name, delegation: &Delegation, child: &ChildView) -> Vec<DelegationFinding> {
+    let mut findings = vec![];
+
+    // without an authoritative answer the name servers of the child are unknown
+    if child.authoritative.is_none() {
+        return findings;
+    }
+
+    for name in child.name_servers.iter() {
+        if !delegation.contains_key(name) {
+            findings.push(DelegationFinding::MissingAtParent(name.clone()));
+        }
+    }
+
+    for (name, glue) in delegation {
+        if !child.name_servers.contains(name) {
+            findings.push(DelegationFinding::MissingAtChild(name.clone()));
+        }
+
+        if !zone.zone_of(name) {
+            continue;
+        }
+
+        if let Some(addrs) = child.addrs.get(name) {
+            if glue != addrs {
+                findings.push(DelegationFinding::GlueMismatch {
+                    name: name.clone(),
+                    glue: glue.clone(),
+                    child: addrs.clone(),
+                });
+            }
+        }
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::proto::op::{Message, MessageType};
+    use crate::proto::rr::rdata::{A, NS};
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).unwrap()
+    }
+
+    fn ip(last: u8) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(192, 0, 2, last))
+    }
+
+    fn ns(zone: &str, ns: &str) -> Record {
+        Record::from_rdata(name(zone), 86400, RData::NS(NS(name(ns))))
+    }
+
+    fn a(owner: &str, last: u8) -> Record {
+        Record::from_rdata(name(owner), 86400, RData::A(A::new(192, 0, 2, last)))
+    }
+
+    fn child(name_servers: &[&str], addrs: &[(&str, &[u8])]) -> ChildView {
+        ChildView {
+            name_servers: name_servers.iter().map(|ns| name(ns)).collect(),
+            addrs: addrs
+                .iter()
+                .map(|(ns, addrs)| (name(ns), addrs.iter().map(|last| ip(*last)).collect()))
+                .collect(),
+            authoritative: Some(ip(1)),
+        }
+    }
+
+    #[test]
+    fn test_delegation_of_referral() {
+        let mut message = Message::new();
+        message
+            .set_message_type(MessageType::Response)
+            .add_name_server(ns("example.com.", "ns1.example.com."))
+            .add_name_server(ns("example.com.", "ns.example.net."))
+            // not part of the delegation
+            .add_name_server(ns("other.com.", "ns1.other.com."))
+            .add_additional(a("ns1.example.com.", 1))
+            .add_additional(a("ns1.other.com.", 3));
+        let response = DnsResponse::from_message(message).unwrap();
+
+        let delegation = delegation_of(&name("example.com."), &response);
+        assert_eq!(
+            delegation,
+            Delegation::from([
+                (name("ns.example.net."), vec![]),
+                (name("ns1.example.com."), vec![ip(1)]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_consistent() {
+        let zone = name("example.com.");
+        let delegation = Delegation::from([
+            (name("ns1.example.com."), vec![ip(1)]),
+            (name("ns.example.net."), vec![]),
+        ]);
+        let child = child(
+            &["ns1.example.com.", "ns.example.net."],
+            &[("ns1.example.com.", &[1])],
+        );
+
+        assert!(compare(&zone, &delegation, &child).is_empty());
+    }
+
+    #[test]
+    fn test_inconsistent() {
+        let zone = name("example.com.");
+        let delegation = Delegation::from([
+            (name("ns1.example.com."), vec![ip(1), ip(9)]),
+            (name("ns2.example.com."), vec![ip(2)]),
+        ]);
+        let child = child(
+            &["ns1.example.com.", "ns3.example.com."],
+            &[("ns1.example.com.", &[1]), ("ns2.example.com.", &[2])],
+        );
+
+        assert_eq!(
+            compare(&zone, &delegation, &child),
+            vec![
+                DelegationFinding::MissingAtParent(name("ns3.example.com.")),
+                DelegationFinding::GlueMismatch {
+                    name: name("ns1.example.com."),
+                    glue: vec![ip(1), ip(9)],
+                    child: vec![ip(1)],
+                },
+                DelegationFinding::MissingAtChild(name("ns2.example.com.")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_authoritative_answer() {
+        let zone = name("example.com.");
+        let delegation = Delegation::from([(name("ns1.example.com."), vec![ip(1)])]);
+
+        assert!(compare(&zone, &delegation, &ChildView::default()).is_empty());
+    }
+
+    #[test]
+    fn test_display() {
+        let finding = DelegationFinding::LameServer {
+            name: name("ns1.example.com."),
+            addr: Some(ip(1)),
+            reason: LameReason::ResponseCode(ResponseCode::Refused),
+        };
+        assert_eq!(
+            finding.to_string(),
+            "lame server: ns1.example.com. at 192.0.2.1: response code Query Refused"
+        );
+        assert_eq!(
+            DelegationFinding::MissingAtChild(name("ns1.example.com.")).to_string(),
+            "missing at child: ns1.example.com."
+        );
+    }
+}
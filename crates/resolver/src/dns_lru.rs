@@ -8,6 +8,7 @@
 //! An LRU cache designed for work with DNS lookups
 
 use std::collections::HashMap;
+use std::io::{self, Write};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -16,24 +17,113 @@ use hickory_proto::error::{ProtoError, ProtoErrorKind};
 use hickory_proto::rr::dnssec::rdata::RRSIG;
 use lru_cache::LruCache;
 use parking_lot::Mutex;
+use rand::Rng;
+use tracing::debug;
 
-use proto::op::Query;
-use proto::rr::Record;
+use proto::op::{Query, ResponseCode};
 #[cfg(feature = "dnssec")]
 use proto::rr::RecordData;
+use proto::rr::{DNSClass, Name, Record, RecordType};
 
 use crate::config;
-use crate::lookup::Lookup;
+use crate::lookup::{Lookup, Provenance};
 
 /// Maximum TTL as defined in https://tools.ietf.org/html/rfc2181, 2147483647
 ///   Setting this to a value of 1 day, in seconds
 pub(crate) const MAX_TTL: u32 = 86400_u32;
 
+/// Default maximum TTL of negative responses, 3 hours
+///
+/// [RFC 2308 section 5](https://tools.ietf.org/html/rfc2308#section-5) recommends one to three
+///  hours, values exceeding one day have been found to be problematic.
+pub const DEFAULT_NEGATIVE_MAX_TTL: u32 = 10800_u32;
+
+/// Default maximum TTL of delegation data, 6 hours
+///
+/// NS records, DS records and glue addresses are capped well below [`MAX_TTL`], so that changes
+///  to the delegation of a zone are picked up within hours, even if the zone publishes TTLs of weeks.
+pub const DEFAULT_INFRASTRUCTURE_MAX_TTL: u32 = 21600_u32;
+
+/// Default minimum TTL of delegation data, 1 minute
+pub const DEFAULT_INFRASTRUCTURE_MIN_TTL: u32 = 60_u32;
+
+/// Default jitter of the TTL of delegation data, 5 minutes, see [`TtlConfig`]
+pub const DEFAULT_INFRASTRUCTURE_TTL_JITTER: u32 = 300_u32;
+
+/// Maximum number of pinned cache entries, see [`DnsLru::pin`]
+pub const MAX_PINNED_ENTRIES: usize = 1024;
+
+/// Number of entries copied from the cache at a time, see [`DnsLru::dump`]
+const DUMP_BATCH_SIZE: usize = 256;
+
+/// The trustworthiness of cached records, see [RFC 2181 section 5.4.1](https://datatracker.ietf.org/doc/html/rfc2181#section-5.4.1)
+///
+/// A current cache entry is only replaced, or has its TTL refreshed, by records of an equal or
+///  higher level. This keeps e.g. the glue of a referral from overwriting an answer for the same name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TrustLevel {
+    /// Records from the additional section of a response, e.g. the glue of a referral
+    Additional,
+    /// Records from the authority section of a response
+    Authority,
+    /// Records from the answer section of a response, this is the level of the records cached
+    ///  for the responses of recursive resolvers
+    Answer,
+    /// Records which were validated as secure with DNSSEC
+    Secure,
+}
+
+impl TrustLevel {
+    /// Secure if all records were validated with DNSSEC, otherwise the level of the response section
+    #[cfg_attr(not(feature = "dnssec"), allow(unused_variables))]
+    fn of_records(section: Self, records_and_ttl: &[(Record, u32)]) -> Self {
+        #[cfg(feature = "dnssec")]
+        {
+            let mut records = records_and_ttl.iter().map(|(record, _)| record);
+            if !records_and_ttl.is_empty() && records.all(|record| record.proof().is_secure()) {
+                return Self::Secure;
+            }
+        }
+
+        section
+    }
+}
+
+/// Returns true if the records describe a delegation, i.e. NS records, DS records or glue
+///
+/// Glue are the addresses of name servers from the authority or additional sections, the same
+///  addresses received as an answer are answer data.
+fn is_infrastructure(query: &Query, trust_level: TrustLevel) -> bool {
+    match query.query_type() {
+        RecordType::NS | RecordType::DS => true,
+        RecordType::A | RecordType::AAAA => trust_level < TrustLevel::Answer,
+        _ => false,
+    }
+}
+
+/// The key of a cache entry
+///
+/// An NXDOMAIN applies to all types at the name, so it is cached per name and class, while all
+///  other entries, including NODATA, are cached per query, see
+///  [RFC 2308 section 5](https://tools.ietf.org/html/rfc2308#section-5).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum CacheKey {
+    Query(Query),
+    NxDomain(Name, DNSClass),
+}
+
+impl CacheKey {
+    fn nx_domain(query: &Query) -> Self {
+        Self::NxDomain(query.name().clone(), query.query_class())
+    }
+}
+
 #[derive(Debug)]
 struct LruValue {
     // In the None case, this represents an NXDomain
     lookup: Result<Lookup, ProtoError>,
     valid_until: Instant,
+    trust_level: TrustLevel,
 }
 
 impl LruValue {
@@ -55,7 +145,12 @@ impl LruValue {
                     .iter()
                     .map(|record| {
                         let mut record = record.clone();
-                        record.set_ttl(self.ttl(now).as_secs() as u32);
+                        // the records of the entry expire with it, see `insert_with_trust_level`
+                        let ttl = match record.expires_at() {
+                            Some(_) => record.remaining_ttl(now).unwrap_or_default(),
+                            None => self.ttl(now),
+                        };
+                        record.set_ttl(ttl.as_secs() as u32);
                         record
                     })
                     .collect::<Vec<Record>>();
@@ -63,21 +158,137 @@ impl LruValue {
                     lookup.query().clone(),
                     Arc::from(records),
                     self.valid_until,
-                ))
+                )
+                .with_provenance(lookup.provenance()))
             }
             Err(ref e) => Err(e.clone()),
         };
         Self {
             lookup,
             valid_until: self.valid_until,
+            trust_level: self.trust_level,
         }
     }
+
+    /// The JSON object describing this entry, see [`DnsLru::dump`]
+    fn dump(&self, key: &CacheKey, pinned: bool, now: Instant) -> serde_json::Value {
+        let (name, record_type, class) = match key {
+            CacheKey::Query(query) => (
+                query.name(),
+                Some(query.query_type().to_string()),
+                query.query_class(),
+            ),
+            CacheKey::NxDomain(name, class) => (name, None, *class),
+        };
+
+        let (response, records, provenance) = match &self.lookup {
+            Ok(lookup) => ("records", lookup.records(), lookup.provenance()),
+            Err(e) => {
+                let response = match e.kind() {
+                    ProtoErrorKind::NoRecordsFound {
+                        response_code: ResponseCode::NXDomain,
+                        ..
+                    } => "nxdomain",
+                    ProtoErrorKind::NoRecordsFound { .. } => "nodata",
+                    _ => "error",
+                };
+                (response, &[][..], Provenance::Received)
+            }
+        };
+
+        // the weakest proof of the records
+        #[cfg(feature = "dnssec")]
+        let security = records
+            .iter()
+            .map(Record::proof)
+            .min()
+            .unwrap_or_default()
+            .to_string();
+        #[cfg(not(feature = "dnssec"))]
+        let security = "indeterminate";
+
+        serde_json::json!({
+            "name": name.to_string(),
+            "type": record_type,
+            "class": class.to_string(),
+            "ttl": self.ttl(now).as_secs(),
+            "response": response,
+            "records": records.len(),
+            "security": security.to_lowercase(),
+            "trust_level": format!("{:?}", self.trust_level).to_lowercase(),
+            "provenance": format!("{provenance:?}").to_lowercase(),
+            "pinned": pinned,
+        })
+    }
+
+    /// Returns true if this value is still valid and should not be replaced by a value of `trust_level`
+    fn outranks(&self, trust_level: TrustLevel, now: Instant) -> bool {
+        self.is_current(now) && self.trust_level > trust_level
+    }
+}
+
+/// The entries of the cache
+///
+/// The entries of pinned queries are kept apart from the LRU, so they are never evicted. They
+///  still expire with their TTL.
+#[derive(Debug)]
+struct Entries {
+    lru: LruCache<CacheKey, LruValue>,
+    // the pinned queries, with their entry if one is cached
+    pinned: HashMap<Query, Option<LruValue>>,
+}
+
+impl Entries {
+    fn new(capacity: usize) -> Self {
+        Self {
+            lru: LruCache::new(capacity),
+            pinned: HashMap::new(),
+        }
+    }
+
+    fn get_mut(&mut self, key: &CacheKey) -> Option<&mut LruValue> {
+        if let CacheKey::Query(query) = key {
+            if let Some(value) = self.pinned.get_mut(query) {
+                return value.as_mut();
+            }
+        }
+
+        self.lru.get_mut(key)
+    }
+
+    fn insert(&mut self, key: CacheKey, value: LruValue) {
+        if let CacheKey::Query(query) = &key {
+            if let Some(pinned) = self.pinned.get_mut(query) {
+                *pinned = Some(value);
+                return;
+            }
+        }
+
+        self.lru.insert(key, value);
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if let CacheKey::Query(query) = key {
+            if let Some(pinned) = self.pinned.get_mut(query) {
+                *pinned = None;
+                return;
+            }
+        }
+
+        self.lru.remove(key);
+    }
+
+    /// Removes all entries, the queries stay pinned
+    fn clear(&mut self) {
+        self.lru.clear();
+        self.pinned.values_mut().for_each(|value| *value = None);
+    }
 }
 
 /// And LRU eviction cache specifically for storing DNS records
 #[derive(Clone, Debug)]
 pub struct DnsLru {
-    cache: Arc<Mutex<LruCache<Query, LruValue>>>,
+    cache: Arc<Mutex<Entries>>,
     /// A minimum TTL value for positive responses.
     ///
     /// Positive responses with TTLs under `positive_max_ttl` will use
@@ -110,10 +321,22 @@ pub struct DnsLru {
     /// `negative_max_ttl` instead.
     ///
     ///  If this value is not set on the `TtlConfig` used to construct this
-    /// `DnsLru`, it will default to [`MAX_TTL`] seconds.
-    ///
-    /// [`MAX_TTL`]: const.MAX_TTL.html
+    /// `DnsLru`, it will default to [`DEFAULT_NEGATIVE_MAX_TTL`] seconds.
     negative_max_ttl: Duration,
+    /// The TTL of negative responses without an SOA record, these are not cached if `None`.
+    negative_ttl_without_soa: Option<Duration>,
+    /// The minimum TTL of delegation data, NS records, DS records and glue.
+    ///
+    /// Defaults to [`DEFAULT_INFRASTRUCTURE_MIN_TTL`] seconds.
+    infrastructure_min_ttl: Duration,
+    /// The maximum TTL of delegation data, used instead of `positive_max_ttl`.
+    ///
+    /// Defaults to [`DEFAULT_INFRASTRUCTURE_MAX_TTL`] seconds.
+    infrastructure_max_ttl: Duration,
+    /// The maximum random reduction of the TTL of delegation data.
+    ///
+    /// Defaults to [`DEFAULT_INFRASTRUCTURE_TTL_JITTER`] seconds.
+    infrastructure_ttl_jitter: Duration,
 }
 
 /// The time-to-live, TTL, configuration for use by the cache.
@@ -145,6 +368,27 @@ pub struct TtlConfig {
     /// `NXDOMAIN` responses with TTLs over `negative_max_ttl` will use
     /// `negative_max_ttl` instead.
     pub(crate) negative_max_ttl: Option<Duration>,
+    /// An optional TTL for negative responses without an SOA record.
+    ///
+    /// Such responses are not cached if this is `None`, as recommended by RFC 2308.
+    pub(crate) negative_ttl_without_soa: Option<Duration>,
+    /// An optional minimum TTL value for delegation data, NS records, DS records and glue.
+    ///
+    /// Delegation data with TTLs under `infrastructure_min_ttl` will use
+    /// `infrastructure_min_ttl` instead.
+    pub(crate) infrastructure_min_ttl: Option<Duration>,
+    /// An optional maximum TTL value for delegation data, NS records, DS records and glue.
+    ///
+    /// Delegation data with TTLs over `infrastructure_max_ttl` will use
+    /// `infrastructure_max_ttl` instead, `positive_max_ttl` does not apply to it.
+    pub(crate) infrastructure_max_ttl: Option<Duration>,
+    /// An optional jitter for the TTL of delegation data.
+    ///
+    /// A random duration of up to `infrastructure_ttl_jitter`, and at most a tenth of the TTL, is
+    /// subtracted from the TTL of delegation data when it is cached, so that the records of one
+    /// response don't all expire at the same time. The TTL is not reduced below
+    /// `infrastructure_min_ttl`.
+    pub(crate) infrastructure_ttl_jitter: Option<Duration>,
 }
 
 impl TtlConfig {
@@ -155,6 +399,10 @@ impl TtlConfig {
             negative_min_ttl: opts.negative_min_ttl,
             positive_max_ttl: opts.positive_max_ttl,
             negative_max_ttl: opts.negative_max_ttl,
+            negative_ttl_without_soa: opts.negative_ttl_without_soa,
+            infrastructure_min_ttl: opts.infrastructure_min_ttl,
+            infrastructure_max_ttl: opts.infrastructure_max_ttl,
+            infrastructure_ttl_jitter: opts.infrastructure_ttl_jitter,
         }
     }
 }
@@ -172,8 +420,12 @@ impl DnsLru {
             negative_min_ttl,
             positive_max_ttl,
             negative_max_ttl,
+            negative_ttl_without_soa,
+            infrastructure_min_ttl,
+            infrastructure_max_ttl,
+            infrastructure_ttl_jitter,
         } = ttl_cfg;
-        let cache = Arc::new(Mutex::new(LruCache::new(capacity)));
+        let cache = Arc::new(Mutex::new(Entries::new(capacity)));
         Self {
             cache,
             positive_min_ttl: positive_min_ttl.unwrap_or_else(|| Duration::from_secs(0)),
@@ -181,7 +433,15 @@ impl DnsLru {
             positive_max_ttl: positive_max_ttl
                 .unwrap_or_else(|| Duration::from_secs(u64::from(MAX_TTL))),
             negative_max_ttl: negative_max_ttl
-                .unwrap_or_else(|| Duration::from_secs(u64::from(MAX_TTL))),
+                .unwrap_or_else(|| Duration::from_secs(u64::from(DEFAULT_NEGATIVE_MAX_TTL))),
+            negative_ttl_without_soa,
+            infrastructure_min_ttl: infrastructure_min_ttl
+                .unwrap_or_else(|| Duration::from_secs(u64::from(DEFAULT_INFRASTRUCTURE_MIN_TTL))),
+            infrastructure_max_ttl: infrastructure_max_ttl
+                .unwrap_or_else(|| Duration::from_secs(u64::from(DEFAULT_INFRASTRUCTURE_MAX_TTL))),
+            infrastructure_ttl_jitter: infrastructure_ttl_jitter.unwrap_or_else(|| {
+                Duration::from_secs(u64::from(DEFAULT_INFRASTRUCTURE_TTL_JITTER))
+            }),
         }
     }
 
@@ -195,10 +455,52 @@ impl DnsLru {
         records_and_ttl: Vec<(Record, u32)>,
         now: Instant,
     ) -> Lookup {
+        self.insert_with_trust_level(
+            query,
+            records_and_ttl,
+            TrustLevel::Answer,
+            Provenance::Received,
+            now,
+        )
+    }
+
+    /// Subtracts a random duration from the TTL of delegation data, see [`TtlConfig`]
+    fn jitter(&self, ttl: Duration) -> Duration {
+        let spread = self
+            .infrastructure_ttl_jitter
+            .min(ttl / 10)
+            .min(ttl.saturating_sub(self.infrastructure_min_ttl));
+        if spread.is_zero() {
+            return ttl;
+        }
+
+        ttl - rand::thread_rng().gen_range(Duration::ZERO..=spread)
+    }
+
+    /// Inserts the records, unless a current entry with a higher trust level exists for the query
+    ///
+    /// Returns the records now cached for the query, or the new records if the entry which was
+    ///  kept is negative.
+    fn insert_with_trust_level(
+        &self,
+        query: Query,
+        records_and_ttl: Vec<(Record, u32)>,
+        trust_level: TrustLevel,
+        provenance: Provenance,
+        now: Instant,
+    ) -> Lookup {
+        let infrastructure = is_infrastructure(&query, trust_level);
+        let (min_ttl, max_ttl) = if infrastructure {
+            (self.infrastructure_min_ttl, self.infrastructure_max_ttl)
+        } else {
+            (self.positive_min_ttl, self.positive_max_ttl)
+        };
+
+        let trust_level = TrustLevel::of_records(trust_level, &records_and_ttl);
         let len = records_and_ttl.len();
         // collapse the values, we're going to take the Minimum TTL as the correct one
-        let (records, ttl): (Vec<Record>, Duration) = records_and_ttl.into_iter().fold(
-            (Vec::with_capacity(len), self.positive_max_ttl),
+        let (mut records, ttl): (Vec<Record>, Duration) = records_and_ttl.into_iter().fold(
+            (Vec::with_capacity(len), max_ttl),
             |(mut records, mut min_ttl), (record, ttl)| {
                 records.push(record);
                 let ttl = Duration::from_secs(u64::from(ttl));
@@ -209,16 +511,48 @@ impl DnsLru {
 
         // If the cache was configured with a minimum TTL, and that value is higher
         // than the minimum TTL in the values, use it instead.
-        let ttl = self.positive_min_ttl.max(ttl);
+        let mut ttl = min_ttl.max(ttl);
+        if infrastructure {
+            ttl = self.jitter(ttl);
+        }
         let valid_until = now + ttl;
+        for record in &mut records {
+            record.set_expires_at(valid_until);
+        }
+
+        let lookup = Lookup::new_with_deadline(query.clone(), Arc::from(records), valid_until)
+            .with_provenance(provenance);
+
+        let mut cache = self.cache.lock();
+        if let Some(value) = cache.get_mut(&CacheKey::Query(query.clone())) {
+            if value.outranks(trust_level, now) {
+                debug!(
+                    "not replacing {:?} records of {query} with {:?} records",
+                    value.trust_level, trust_level
+                );
+                return match value.with_updated_ttl(now).lookup {
+                    Ok(cached) => cached,
+                    Err(_) => lookup,
+                };
+            }
+        }
+
+        // the name exists, any NXDOMAIN cached for it is stale
+        let nx_domain = CacheKey::nx_domain(&query);
+        if cache
+            .get_mut(&nx_domain)
+            .map_or(false, |value| !value.outranks(trust_level, now))
+        {
+            cache.remove(&nx_domain);
+        }
 
         // insert into the LRU
-        let lookup = Lookup::new_with_deadline(query.clone(), Arc::from(records), valid_until);
-        self.cache.lock().insert(
-            query,
+        cache.insert(
+            CacheKey::Query(query),
             LruValue {
                 lookup: Ok(lookup.clone()),
                 valid_until,
+                trust_level,
             },
         );
 
@@ -241,11 +575,49 @@ impl DnsLru {
         original_query: Query,
         records: impl Iterator<Item = Record>,
         now: Instant,
+    ) -> Option<Lookup> {
+        self.insert_records_with_trust_level(
+            original_query,
+            records.map(|record| (record, TrustLevel::Answer)),
+            now,
+        )
+    }
+
+    /// inserts records based on the name and type, with the trust level of the response section
+    ///  they were taken from
+    ///
+    /// The records of each name and type are cached with the highest trust level among them, a
+    ///  current cache entry of a higher trust level is kept, see [`TrustLevel`].
+    ///
+    /// # Return
+    ///
+    /// The records cached for the original query, None if the original query matches no records
+    pub fn insert_records_with_trust_level(
+        &self,
+        original_query: Query,
+        records: impl Iterator<Item = (Record, TrustLevel)>,
+        now: Instant,
+    ) -> Option<Lookup> {
+        self.insert_records_with_provenance(
+            Some(&original_query),
+            records,
+            Provenance::Received,
+            now,
+        )
+    }
+
+    /// Inserts the records by name and type, marked with their provenance
+    fn insert_records_with_provenance(
+        &self,
+        original_query: Option<&Query>,
+        records: impl Iterator<Item = (Record, TrustLevel)>,
+        provenance: Provenance,
+        now: Instant,
     ) -> Option<Lookup> {
         // collect all records by name
         let records = records.fold(
-            HashMap::<Query, Vec<(Record, u32)>>::new(),
-            |mut map, record| {
+            HashMap::<Query, (Vec<(Record, u32)>, TrustLevel)>::new(),
+            |mut map, (record, trust_level)| {
                 // it's not useful to cache RRSIGs on their own using `name()` as a key because
                 // there can be multiple RRSIG associated to the same domain name where each
                 // RRSIG is *covering* a different record type
@@ -288,7 +660,9 @@ impl DnsLru {
 
                 let ttl = record.ttl();
 
-                map.entry(query).or_default().push((record, ttl));
+                let (records, level) = map.entry(query).or_insert((vec![], trust_level));
+                records.push((record, ttl));
+                *level = (*level).max(trust_level);
 
                 map
             },
@@ -296,9 +670,10 @@ impl DnsLru {
 
         // now insert by record type and name
         let mut lookup = None;
-        for (query, records_and_ttl) in records {
-            let is_query = original_query == query;
-            let inserted = self.insert(query, records_and_ttl, now);
+        for (query, (records_and_ttl, trust_level)) in records {
+            let is_query = original_query == Some(&query);
+            let inserted =
+                self.insert_with_trust_level(query, records_and_ttl, trust_level, provenance, now);
 
             if is_query {
                 lookup = Some(inserted)
@@ -308,16 +683,74 @@ impl DnsLru {
         lookup
     }
 
+    /// Inserts the records as if they were received in an answer, before any query for them
+    ///
+    /// The TTL of all records is set to `ttl`, which is clamped like the TTL of received records.
+    ///  Lookups of the records have the provenance [`Provenance::Prepopulated`] until they are
+    ///  replaced by received records. Pin the records, see [`Self::pin`], to keep them from being
+    ///  evicted.
+    pub fn prepopulate(
+        &self,
+        records: impl IntoIterator<Item = Record>,
+        ttl: Duration,
+        now: Instant,
+    ) {
+        let ttl = u32::try_from(ttl.as_secs()).unwrap_or(u32::MAX);
+        let records = records.into_iter().map(|mut record| {
+            record.set_ttl(ttl);
+            (record, TrustLevel::Answer)
+        });
+
+        self.insert_records_with_provenance(None, records, Provenance::Prepopulated, now);
+    }
+
+    /// Pins the entry of the name and type, it is never evicted to make room for other entries
+    ///
+    /// The entry still expires with its TTL. An entry which is already cached is kept. At most
+    ///  [`MAX_PINNED_ENTRIES`] entries can be pinned, an error is returned beyond that.
+    pub fn pin(&self, name: Name, record_type: RecordType) -> Result<(), ProtoError> {
+        let query = Query::query(name, record_type);
+        let mut cache = self.cache.lock();
+        if cache.pinned.contains_key(&query) {
+            return Ok(());
+        }
+
+        if cache.pinned.len() >= MAX_PINNED_ENTRIES {
+            return Err(ProtoErrorKind::Message("too many pinned cache entries").into());
+        }
+
+        let value = cache.lru.remove(&CacheKey::Query(query.clone()));
+        cache.pinned.insert(query, value);
+        Ok(())
+    }
+
+    /// Unpins the entry of the name and type, returns false if it was not pinned
+    ///
+    /// A cached entry is moved back to the LRU.
+    pub fn unpin(&self, name: &Name, record_type: RecordType) -> bool {
+        let query = Query::query(name.clone(), record_type);
+        let mut cache = self.cache.lock();
+        match cache.pinned.remove(&query) {
+            Some(Some(value)) => {
+                cache.lru.insert(CacheKey::Query(query), value);
+                true
+            }
+            Some(None) => true,
+            None => false,
+        }
+    }
+
     /// Generally for inserting a set of records that have already been cached, but with a different Query.
     pub(crate) fn duplicate(&self, query: Query, lookup: Lookup, ttl: u32, now: Instant) -> Lookup {
         let ttl = Duration::from_secs(u64::from(ttl));
         let valid_until = now + ttl;
 
         self.cache.lock().insert(
-            query,
+            CacheKey::Query(query),
             LruValue {
                 lookup: Ok(lookup.clone()),
                 valid_until,
+                trust_level: TrustLevel::Answer,
             },
         );
 
@@ -338,66 +771,200 @@ impl DnsLru {
         }
     }
 
+    /// This sets the query of the error to the one it is returned for, an NXDOMAIN cached for
+    ///  a query of one type is also returned for queries of other types.
+    fn nx_error_for_query(error: &mut ProtoError, for_query: &Query) {
+        let ProtoError { kind, .. } = error;
+
+        if let ProtoErrorKind::NoRecordsFound { ref mut query, .. } = kind.as_mut() {
+            if query.as_ref() != for_query {
+                **query = for_query.clone();
+            }
+        }
+    }
+
+    /// Caches a negative response per [RFC 2308](https://tools.ietf.org/html/rfc2308#section-5)
+    ///
+    /// The negative TTL, taken from the SOA record in the authority section, is clamped to the
+    ///  configured minimum and maximum. An NXDOMAIN is cached for all types at the name of the
+    ///  query, a NODATA only for the type of the query. Responses without an SOA record are only
+    ///  cached if a TTL was configured for them.
     pub(crate) fn negative(&self, query: Query, mut error: ProtoError, now: Instant) -> ProtoError {
         let ProtoError { ref kind, .. } = error;
 
         // TODO: if we are getting a negative response, should we instead fallback to cache?
         //   this would cache indefinitely, probably not correct
-        if let ProtoErrorKind::NoRecordsFound {
-            negative_ttl: Some(ttl),
-            ..
-        } = kind.as_ref()
+        let (ttl_duration, response_code) = match kind.as_ref() {
+            ProtoErrorKind::NoRecordsFound {
+                negative_ttl: Some(ttl),
+                response_code,
+                ..
+            } => (
+                Duration::from_secs(u64::from(*ttl))
+                    // Clamp the TTL so that it's between the cache's configured
+                    // minimum and maximum TTLs for negative responses.
+                    .clamp(self.negative_min_ttl, self.negative_max_ttl),
+                *response_code,
+            ),
+            ProtoErrorKind::NoRecordsFound {
+                negative_ttl: None,
+                soa: None,
+                response_code: response_code @ (ResponseCode::NXDomain | ResponseCode::NoError),
+                trusted: true,
+                ..
+            } => match self.negative_ttl_without_soa {
+                Some(ttl) => (ttl, *response_code),
+                None => return error,
+            },
+            _ => return error,
+        };
+        let valid_until = now + ttl_duration;
+
+        let key = if response_code == ResponseCode::NXDomain {
+            CacheKey::nx_domain(&query)
+        } else {
+            CacheKey::Query(query.clone())
+        };
+
+        let mut cache = self.cache.lock();
+        if !cache
+            .get_mut(&key)
+            .map_or(false, |value| value.outranks(TrustLevel::Answer, now))
         {
-            let ttl_duration = Duration::from_secs(u64::from(*ttl))
-                // Clamp the TTL so that it's between the cache's configured
-                // minimum and maximum TTLs for negative responses.
-                .clamp(self.negative_min_ttl, self.negative_max_ttl);
-            let valid_until = now + ttl_duration;
-
-            {
-                let error = error.clone();
-
-                self.cache.lock().insert(
-                    query,
-                    LruValue {
-                        lookup: Err(error),
-                        valid_until,
-                    },
-                );
+            // the records previously cached for the query no longer exist
+            if let CacheKey::NxDomain(..) = key {
+                let query = CacheKey::Query(query);
+                if !cache
+                    .get_mut(&query)
+                    .map_or(false, |value| value.outranks(TrustLevel::Answer, now))
+                {
+                    cache.remove(&query);
+                }
             }
 
-            Self::nx_error_with_ttl(&mut error, ttl_duration);
+            let error = error.clone();
+
+            cache.insert(
+                key,
+                LruValue {
+                    lookup: Err(error),
+                    valid_until,
+                    trust_level: TrustLevel::Answer,
+                },
+            );
         }
 
+        Self::nx_error_with_ttl(&mut error, ttl_duration);
         error
     }
 
     /// Based on the query, see if there are any records available
+    ///
+    /// An NXDOMAIN cached for the name of the query is returned if there is no entry for the query.
     pub fn get(&self, query: &Query, now: Instant) -> Option<Result<Lookup, ProtoError>> {
-        let mut out_of_date = false;
         let mut cache = self.cache.lock();
-        let lookup = cache.get_mut(query).and_then(|value| {
-            if value.is_current(now) {
-                out_of_date = false;
-                let mut result = value.with_updated_ttl(now).lookup;
-                if let Err(ref mut err) = result {
-                    Self::nx_error_with_ttl(err, value.ttl(now));
-                }
-                Some(result)
-            } else {
-                out_of_date = true;
-                None
+        if let Some(lookup) = Self::get_current(&mut cache, &CacheKey::Query(query.clone()), now) {
+            return Some(lookup);
+        }
+
+        Self::get_current(&mut cache, &CacheKey::nx_domain(query), now).map(|lookup| {
+            lookup.map_err(|mut error| {
+                Self::nx_error_for_query(&mut error, query);
+                error
+            })
+        })
+    }
+
+    /// Returns true if [`Self::get`] would return an entry for the query
+    ///
+    /// This is cheaper than `get`, the cached lookup is neither cloned nor updated, and entries
+    ///  which are out of date are left for `get` to remove.
+    pub fn contains(&self, query: &Query, now: Instant) -> bool {
+        let mut cache = self.cache.lock();
+        [CacheKey::Query(query.clone()), CacheKey::nx_domain(query)]
+            .iter()
+            .any(|key| {
+                cache
+                    .get_mut(key)
+                    .map_or(false, |value| value.is_current(now))
+            })
+    }
+
+    /// Writes the current entries as newline delimited JSON, returns the number of entries written
+    ///
+    /// Each line is an object with the `name`, `type` and `class` of the entry, the remaining `ttl`
+    ///  in seconds, the kind of `response` (`records`, `nodata`, `nxdomain` or `error`), the number
+    ///  of `records`, their DNSSEC `security` status, the `trust_level`, the `provenance`, and
+    ///  whether the entry is `pinned`. The type of an NXDOMAIN is `null`, it applies to all types.
+    ///
+    /// The cache is not locked while writing. The keys are collected first, and the entries are
+    ///  copied in small batches, so lookups proceed during the dump of a large cache. The entries are
+    ///  visited from the least to the most recently used, which keeps their order in the LRU.
+    pub fn dump<W: Write + ?Sized>(&self, writer: &mut W, now: Instant) -> io::Result<usize> {
+        let keys = {
+            let cache = self.cache.lock();
+            cache
+                .pinned
+                .keys()
+                .cloned()
+                .map(CacheKey::Query)
+                .chain(cache.lru.iter().map(|(key, _)| key.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        let mut written = 0;
+        for batch in keys.chunks(DUMP_BATCH_SIZE) {
+            let entries = {
+                let mut cache = self.cache.lock();
+                batch
+                    .iter()
+                    .filter_map(|key| {
+                        let pinned = match key {
+                            CacheKey::Query(query) => cache.pinned.contains_key(query),
+                            CacheKey::NxDomain(..) => false,
+                        };
+                        let value = cache.get_mut(key).filter(|value| value.is_current(now))?;
+                        let value = LruValue {
+                            lookup: value.lookup.clone(),
+                            valid_until: value.valid_until,
+                            trust_level: value.trust_level,
+                        };
+                        Some((key, value, pinned))
+                    })
+                    .collect::<Vec<_>>()
+            };
+
+            for (key, value, pinned) in entries {
+                let line = value.dump(key, pinned, now);
+                serde_json::to_writer(&mut *writer, &line)?;
+                writer.write_all(b"\n")?;
+                written += 1;
             }
-        });
+        }
+
+        Ok(written)
+    }
 
-        // in this case, we can preemptively remove out of data elements
-        // this assumes time is always moving forward, this would only not be true in contrived situations where now
-        //  is not current time, like tests...
-        if out_of_date {
-            cache.remove(query);
+    /// Returns the entry of the key if it is current, an entry which is out of date is removed
+    fn get_current(
+        cache: &mut Entries,
+        key: &CacheKey,
+        now: Instant,
+    ) -> Option<Result<Lookup, ProtoError>> {
+        if !cache.get_mut(key)?.is_current(now) {
+            // in this case, we can preemptively remove out of data elements
+            // this assumes time is always moving forward, this would only not be true in contrived situations where now
+            //  is not current time, like tests...
+            cache.remove(key);
+            return None;
         }
 
-        lookup
+        let value = cache.get_mut(key)?;
+        let mut result = value.with_updated_ttl(now).lookup;
+        if let Err(ref mut err) = result {
+            Self::nx_error_with_ttl(err, value.ttl(now));
+        }
+        Some(result)
     }
 }
 
@@ -407,9 +974,10 @@ mod tests {
     use std::str::FromStr;
     use std::time::*;
 
-    use proto::op::{Query, ResponseCode};
-    use proto::rr::rdata::A;
+    use proto::op::{Message, MessageType, Query, ResponseCode};
+    use proto::rr::rdata::{A, NS, SOA};
     use proto::rr::{Name, RData, RecordType};
+    use proto::xfer::DnsResponse;
 
     use super::*;
 
@@ -423,6 +991,7 @@ mod tests {
         let value = LruValue {
             lookup: Err(ProtoErrorKind::Message("test error").into()),
             valid_until: future,
+            trust_level: TrustLevel::Answer,
         };
 
         assert!(value.is_current(now));
@@ -658,6 +1227,43 @@ mod tests {
         assert!(ttl <= 8);
     }
 
+    #[test]
+    fn test_records_expire_with_entry() {
+        let now = Instant::now();
+
+        let name = Name::from_str("www.example.com.").unwrap();
+        let query = Query::query(name.clone(), RecordType::A);
+        let ips_ttl = vec![
+            (
+                Record::from_rdata(name.clone(), 10, RData::A(A::new(127, 0, 0, 1))),
+                10,
+            ),
+            (
+                Record::from_rdata(name, 20, RData::A(A::new(127, 0, 0, 2))),
+                20,
+            ),
+        ];
+        let lru = DnsLru::new(1, TtlConfig::default());
+
+        // all records expire with the minimum TTL
+        let lookup = lru.insert(query.clone(), ips_ttl, now);
+        for record in lookup.record_iter() {
+            assert_eq!(record.expires_at(), Some(now + Duration::from_secs(10)));
+        }
+
+        let later = now + Duration::from_secs(4);
+        let lookup = lru
+            .get(&query, later)
+            .unwrap()
+            .expect("records should exist");
+        for record in lookup.record_iter() {
+            assert!(!record.is_expired(later));
+            assert_eq!(record.remaining_ttl(later), Some(Duration::from_secs(6)));
+            assert_eq!(record.ttl(), 6);
+            assert!(record.is_expired(now + Duration::from_secs(11)));
+        }
+    }
+
     #[test]
     fn test_insert_ttl() {
         let now = Instant::now();
@@ -805,4 +1411,559 @@ mod tests {
         let rc_ips = lru.get(&query, now + Duration::from_secs(3));
         assert!(rc_ips.is_none());
     }
+
+    fn referral_with_glue(name: &Name, glue: A) -> Vec<(Record, TrustLevel)> {
+        let zone = Name::from_str("example.com.").unwrap();
+        let ns = Record::from_rdata(zone, 86400, RData::NS(NS(name.clone())));
+        let glue = Record::from_rdata(name.clone(), 86400, RData::A(glue));
+        vec![(ns, TrustLevel::Authority), (glue, TrustLevel::Additional)]
+    }
+
+    #[test]
+    fn test_glue_does_not_replace_answer() {
+        let now = Instant::now();
+        let name = Name::from_str("ns.example.com.").unwrap();
+        let query = Query::query(name.clone(), RecordType::A);
+        let answer = Record::from_rdata(name.clone(), 300, RData::A(A::new(127, 0, 0, 1)));
+        let lru = DnsLru::new(2, TtlConfig::default());
+
+        lru.insert_records_with_trust_level(
+            query.clone(),
+            [(answer, TrustLevel::Answer)].into_iter(),
+            now,
+        )
+        .expect("records should exist");
+
+        // a later referral with different glue for the same name
+        let ns_query = Query::query(Name::from_str("example.com.").unwrap(), RecordType::NS);
+        let later = now + Duration::from_secs(10);
+        lru.insert_records_with_trust_level(
+            ns_query,
+            referral_with_glue(&name, A::new(127, 0, 0, 2)).into_iter(),
+            later,
+        )
+        .expect("records should exist");
+
+        let lookup = lru
+            .get(&query, later)
+            .unwrap()
+            .expect("records should exist");
+        assert_eq!(
+            lookup.iter().cloned().collect::<Vec<_>>(),
+            [RData::A(A::new(127, 0, 0, 1))]
+        );
+        // the TTL was not refreshed by the glue
+        assert!(lookup.record_iter().next().unwrap().ttl() <= 290);
+    }
+
+    #[test]
+    fn test_answer_replaces_glue() {
+        let now = Instant::now();
+        let name = Name::from_str("ns.example.com.").unwrap();
+        let query = Query::query(name.clone(), RecordType::A);
+        let ns_query = Query::query(Name::from_str("example.com.").unwrap(), RecordType::NS);
+        let lru = DnsLru::new(2, TtlConfig::default());
+
+        lru.insert_records_with_trust_level(
+            ns_query,
+            referral_with_glue(&name, A::new(127, 0, 0, 2)).into_iter(),
+            now,
+        )
+        .expect("records should exist");
+
+        let answer = Record::from_rdata(name, 300, RData::A(A::new(127, 0, 0, 1)));
+        let lookup = lru
+            .insert_records_with_trust_level(
+                query.clone(),
+                [(answer, TrustLevel::Answer)].into_iter(),
+                now,
+            )
+            .expect("records should exist");
+        assert_eq!(
+            lookup.iter().cloned().collect::<Vec<_>>(),
+            [RData::A(A::new(127, 0, 0, 1))]
+        );
+
+        let lookup = lru.get(&query, now).unwrap().expect("records should exist");
+        assert_eq!(
+            lookup.iter().cloned().collect::<Vec<_>>(),
+            [RData::A(A::new(127, 0, 0, 1))]
+        );
+    }
+
+    #[test]
+    fn test_infrastructure_max_ttl_with_jitter() {
+        const TWO_WEEKS: u32 = 14 * 86400;
+
+        let now = Instant::now();
+        let zone = Name::from_str("example.com.").unwrap();
+        let name = Name::from_str("ns.example.com.").unwrap();
+        let ns_query = Query::query(zone.clone(), RecordType::NS);
+        let glue_query = Query::query(name.clone(), RecordType::A);
+        let ttls = TtlConfig {
+            infrastructure_max_ttl: Some(Duration::from_secs(3600)),
+            infrastructure_ttl_jitter: Some(Duration::from_secs(60)),
+            ..TtlConfig::default()
+        };
+        let lru = DnsLru::new(2, ttls);
+
+        let ns = Record::from_rdata(zone, TWO_WEEKS, RData::NS(NS(name.clone())));
+        let glue = Record::from_rdata(name, TWO_WEEKS, RData::A(A::new(127, 0, 0, 1)));
+        lru.insert_records_with_trust_level(
+            ns_query.clone(),
+            [(ns, TrustLevel::Authority), (glue, TrustLevel::Additional)].into_iter(),
+            now,
+        )
+        .expect("records should exist");
+
+        for query in [&ns_query, &glue_query] {
+            let lookup = lru.get(query, now).unwrap().expect("records should exist");
+            let ttl = lookup.record_iter().next().unwrap().ttl();
+            assert!((3540..=3600).contains(&ttl), "{query}: {ttl}");
+        }
+    }
+
+    #[test]
+    fn test_infrastructure_min_ttl() {
+        let now = Instant::now();
+        let zone = Name::from_str("example.com.").unwrap();
+        let query = Query::query(zone.clone(), RecordType::NS);
+        let ttls = TtlConfig {
+            infrastructure_min_ttl: Some(Duration::from_secs(120)),
+            ..TtlConfig::default()
+        };
+        let lru = DnsLru::new(1, ttls);
+
+        let ns = Record::from_rdata(
+            zone,
+            5,
+            RData::NS(NS(Name::from_str("ns.example.com.").unwrap())),
+        );
+        lru.insert_records(query.clone(), [ns].into_iter(), now)
+            .expect("records should exist");
+
+        // the floor is not reduced by the jitter
+        let lookup = lru.get(&query, now).unwrap().expect("records should exist");
+        assert_eq!(lookup.record_iter().next().unwrap().ttl(), 120);
+    }
+
+    #[test]
+    fn test_answer_ttl_without_infrastructure_policy() {
+        let now = Instant::now();
+        let name = Name::from_str("ns.example.com.").unwrap();
+        let query = Query::query(name.clone(), RecordType::A);
+        let lru = DnsLru::new(1, TtlConfig::default());
+
+        // the address of a name server received as an answer is answer data
+        let answer = Record::from_rdata(name, 14 * 86400, RData::A(A::new(127, 0, 0, 1)));
+        lru.insert_records(query.clone(), [answer].into_iter(), now)
+            .expect("records should exist");
+
+        let lookup = lru.get(&query, now).unwrap().expect("records should exist");
+        assert_eq!(lookup.record_iter().next().unwrap().ttl(), MAX_TTL);
+    }
+
+    /// A negative response from the zone example.com., with an SOA record of `soa_ttl` and `minimum`
+    fn negative_response(
+        query: &Query,
+        response_code: ResponseCode,
+        soa: Option<(u32, u32)>,
+    ) -> ProtoError {
+        let Some((soa_ttl, minimum)) = soa else {
+            // as returned by the caching client without DNSSEC, which trusts all negative responses
+            return ProtoError::nx_error(query.clone(), None, None, response_code, true);
+        };
+
+        let zone = Name::from_str("example.com.").unwrap();
+        let soa = SOA::new(
+            Name::from_str("ns.example.com.").unwrap(),
+            Name::from_str("hostmaster.example.com.").unwrap(),
+            1,
+            3600,
+            600,
+            86400,
+            minimum,
+        );
+
+        let mut message = Message::new();
+        message
+            .set_message_type(MessageType::Response)
+            .set_response_code(response_code)
+            .add_query(query.clone())
+            .add_name_server(Record::from_rdata(zone, soa_ttl, RData::SOA(soa)));
+
+        let response = DnsResponse::from_message(message).unwrap();
+        ProtoError::from_response(response, true).expect_err("response should be negative")
+    }
+
+    fn negative_ttl(result: Option<Result<Lookup, ProtoError>>) -> Option<u32> {
+        match result?
+            .expect_err("negative response should be cached")
+            .kind()
+        {
+            ProtoErrorKind::NoRecordsFound { negative_ttl, .. } => *negative_ttl,
+            other => panic!("expected ProtoErrorKind::NoRecordsFound, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_negative_ttl_from_soa() {
+        let now = Instant::now();
+        let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+        let lru = DnsLru::new(2, TtlConfig::default());
+
+        // the SOA MINIMUM is lower than the TTL of the SOA record
+        let error = negative_response(&query, ResponseCode::NoError, Some((300, 60)));
+        lru.negative(query.clone(), error, now);
+        assert_eq!(negative_ttl(lru.get(&query, now)), Some(60));
+        assert!(lru.get(&query, now + Duration::from_secs(61)).is_none());
+
+        // the TTL of the SOA record is lower than the SOA MINIMUM
+        let error = negative_response(&query, ResponseCode::NoError, Some((30, 600)));
+        lru.negative(query.clone(), error, now);
+        assert_eq!(negative_ttl(lru.get(&query, now)), Some(30));
+    }
+
+    #[test]
+    fn test_negative_ttl_default_ceiling() {
+        let now = Instant::now();
+        let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+        let lru = DnsLru::new(2, TtlConfig::default());
+
+        let error = negative_response(&query, ResponseCode::NXDomain, Some((86400, 86400)));
+        let error = lru.negative(query.clone(), error, now);
+        assert!(matches!(
+            error.kind(),
+            ProtoErrorKind::NoRecordsFound {
+                negative_ttl: Some(DEFAULT_NEGATIVE_MAX_TTL),
+                ..
+            }
+        ));
+        assert_eq!(
+            negative_ttl(lru.get(&query, now)),
+            Some(DEFAULT_NEGATIVE_MAX_TTL)
+        );
+    }
+
+    #[test]
+    fn test_nxdomain_applies_to_all_types() {
+        let now = Instant::now();
+        let name = Name::from_str("www.example.com.").unwrap();
+        let query = Query::query(name.clone(), RecordType::A);
+        let txt_query = Query::query(name.clone(), RecordType::TXT);
+        let lru = DnsLru::new(4, TtlConfig::default());
+
+        let error = negative_response(&query, ResponseCode::NXDomain, Some((300, 300)));
+        lru.negative(query.clone(), error, now);
+
+        let error = lru
+            .get(&txt_query, now)
+            .expect("NXDOMAIN should be cached for all types")
+            .expect_err("NXDOMAIN should be negative");
+        match error.kind() {
+            ProtoErrorKind::NoRecordsFound {
+                query,
+                response_code,
+                ..
+            } => {
+                assert_eq!(**query, txt_query);
+                assert_eq!(*response_code, ResponseCode::NXDomain);
+            }
+            other => panic!("expected ProtoErrorKind::NoRecordsFound, got {other:?}"),
+        }
+
+        // other names are not affected
+        let other = Query::query(Name::from_str("mail.example.com.").unwrap(), RecordType::A);
+        assert!(lru.get(&other, now).is_none());
+
+        // the name exists after all, the NXDOMAIN no longer applies
+        let ips = vec![(
+            Record::from_rdata(name, 300, RData::A(A::new(127, 0, 0, 1))),
+            300,
+        )];
+        lru.insert(query.clone(), ips, now);
+        assert!(lru.get(&query, now).unwrap().is_ok());
+        assert!(lru.get(&txt_query, now).is_none());
+    }
+
+    #[test]
+    fn test_nodata_does_not_suppress_other_types() {
+        let now = Instant::now();
+        let name = Name::from_str("www.example.com.").unwrap();
+        let query = Query::query(name.clone(), RecordType::AAAA);
+        let lru = DnsLru::new(4, TtlConfig::default());
+
+        let error = negative_response(&query, ResponseCode::NoError, Some((300, 300)));
+        lru.negative(query.clone(), error, now);
+        assert_eq!(negative_ttl(lru.get(&query, now)), Some(300));
+
+        for record_type in [RecordType::A, RecordType::TXT] {
+            let other = Query::query(name.clone(), record_type);
+            assert!(
+                lru.get(&other, now).is_none(),
+                "{record_type} is suppressed"
+            );
+        }
+    }
+
+    #[test]
+    fn test_negative_without_soa() {
+        let now = Instant::now();
+        let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+
+        // not cached by default
+        let lru = DnsLru::new(2, TtlConfig::default());
+        let error = negative_response(&query, ResponseCode::NXDomain, None);
+        let error = lru.negative(query.clone(), error, now);
+        assert!(matches!(
+            error.kind(),
+            ProtoErrorKind::NoRecordsFound {
+                negative_ttl: None,
+                ..
+            }
+        ));
+        assert!(lru.get(&query, now).is_none());
+
+        // cached briefly if configured
+        let ttls = TtlConfig {
+            negative_ttl_without_soa: Some(Duration::from_secs(5)),
+            ..TtlConfig::default()
+        };
+        let lru = DnsLru::new(2, ttls);
+        let error = negative_response(&query, ResponseCode::NoError, None);
+        lru.negative(query.clone(), error, now);
+        assert_eq!(negative_ttl(lru.get(&query, now)), Some(5));
+        assert!(lru.get(&query, now + Duration::from_secs(6)).is_none());
+
+        // errors which are not negative responses are never cached
+        let error = negative_response(&query, ResponseCode::ServFail, None);
+        let other = Query::query(Name::from_str("mail.example.com.").unwrap(), RecordType::A);
+        lru.negative(other.clone(), error, now);
+        assert!(lru.get(&other, now).is_none());
+    }
+
+    fn a_record(name: &str, ip: u8) -> Record {
+        Record::from_rdata(
+            Name::from_str(name).unwrap(),
+            300,
+            RData::A(A::new(192, 0, 2, ip)),
+        )
+    }
+
+    #[test]
+    fn test_pinned_survives_eviction() {
+        let now = Instant::now();
+        let lru = DnsLru::new(4, TtlConfig::default());
+
+        let pinned = a_record("seed.example.com.", 1);
+        let pinned_query = Query::query(pinned.name().clone(), RecordType::A);
+        let unpinned = a_record("www.example.com.", 2);
+        let unpinned_query = Query::query(unpinned.name().clone(), RecordType::A);
+
+        // pinned after it was cached
+        lru.insert(pinned_query.clone(), vec![(pinned.clone(), 300)], now);
+        lru.pin(pinned.name().clone(), RecordType::A).unwrap();
+        lru.insert(unpinned_query.clone(), vec![(unpinned, 300)], now);
+
+        // pinned before it was cached
+        let prepinned = a_record("db.example.com.", 3);
+        let prepinned_query = Query::query(prepinned.name().clone(), RecordType::A);
+        lru.pin(prepinned.name().clone(), RecordType::A).unwrap();
+        lru.insert(prepinned_query.clone(), vec![(prepinned, 300)], now);
+
+        for i in 0..100 {
+            let record = a_record(&format!("host{i}.example.com."), 4);
+            let query = Query::query(record.name().clone(), RecordType::A);
+            lru.insert(query, vec![(record, 300)], now);
+        }
+
+        assert!(lru.get(&unpinned_query, now).is_none());
+        assert!(lru.get(&pinned_query, now).unwrap().is_ok());
+        assert!(lru.get(&prepinned_query, now).unwrap().is_ok());
+
+        // TTL expiry still applies
+        assert!(lru
+            .get(&pinned_query, now + Duration::from_secs(301))
+            .is_none());
+
+        // unpinned entries are evicted again
+        lru.insert(pinned_query.clone(), vec![(pinned.clone(), 300)], now);
+        assert!(lru.unpin(pinned.name(), RecordType::A));
+        assert!(!lru.unpin(pinned.name(), RecordType::A));
+        for i in 0..100 {
+            let record = a_record(&format!("host{i}.example.com."), 4);
+            let query = Query::query(record.name().clone(), RecordType::A);
+            lru.insert(query, vec![(record, 300)], now);
+        }
+        assert!(lru.get(&pinned_query, now).is_none());
+    }
+
+    #[test]
+    fn test_pinned_entries_capped() {
+        let lru = DnsLru::new(4, TtlConfig::default());
+        for i in 0..MAX_PINNED_ENTRIES {
+            let name = Name::from_str(&format!("host{i}.example.com.")).unwrap();
+            lru.pin(name, RecordType::A).unwrap();
+        }
+
+        // pinning again is a no-op
+        let name = Name::from_str("host0.example.com.").unwrap();
+        lru.pin(name, RecordType::A).unwrap();
+
+        let name = Name::from_str("www.example.com.").unwrap();
+        assert!(lru.pin(name.clone(), RecordType::A).is_err());
+        assert!(lru.pin(name, RecordType::AAAA).is_err());
+    }
+
+    #[test]
+    fn test_prepopulate() {
+        let now = Instant::now();
+        let lru = DnsLru::new(4, TtlConfig::default());
+
+        let record = a_record("seed.example.com.", 1);
+        let query = Query::query(record.name().clone(), RecordType::A);
+        lru.prepopulate([record.clone()], Duration::from_secs(3600), now);
+
+        let lookup = lru.get(&query, now).unwrap().unwrap();
+        assert_eq!(lookup.provenance(), Provenance::Prepopulated);
+        assert_eq!(lookup.valid_until(), now + Duration::from_secs(3600));
+        assert_eq!(lookup.records()[0].ttl(), 3600);
+
+        // replaced by received records
+        lru.insert(query.clone(), vec![(record, 300)], now);
+        let lookup = lru.get(&query, now).unwrap().unwrap();
+        assert_eq!(lookup.provenance(), Provenance::Received);
+    }
+    #[test]
+    fn test_contains() {
+        let now = Instant::now();
+        let lru = DnsLru::new(4, TtlConfig::default());
+
+        let record = a_record("www.example.com.", 1);
+        let query = Query::query(record.name().clone(), RecordType::A);
+        assert!(!lru.contains(&query, now));
+
+        lru.insert(query.clone(), vec![(record, 1)], now);
+        assert!(lru.contains(&query, now));
+        assert!(!lru.contains(&Query::query(query.name().clone(), RecordType::AAAA), now));
+        assert!(!lru.contains(&query, now + Duration::from_secs(2)));
+
+        // an NXDOMAIN applies to all types of the name
+        let nx_query = Query::query(Name::from_str("nx.example.com.").unwrap(), RecordType::A);
+        let error = negative_response(&nx_query, ResponseCode::NXDomain, Some((60, 60)));
+        lru.negative(nx_query.clone(), error, now);
+        assert!(lru.contains(
+            &Query::query(nx_query.name().clone(), RecordType::AAAA),
+            now
+        ));
+    }
+    #[test]
+    fn test_dump() {
+        let now = Instant::now();
+        let lru = DnsLru::new(8, TtlConfig::default());
+
+        let record = a_record("www.example.com.", 1);
+        let query = Query::query(record.name().clone(), RecordType::A);
+        lru.insert(query, vec![(record.clone(), 300), (record, 300)], now);
+
+        lru.pin(
+            Name::from_str("pinned.example.com.").unwrap(),
+            RecordType::A,
+        )
+        .unwrap();
+        lru.prepopulate(
+            [a_record("pinned.example.com.", 2)],
+            Duration::from_secs(600),
+            now,
+        );
+
+        let nx_query = Query::query(Name::from_str("nx.example.com.").unwrap(), RecordType::A);
+        let error = negative_response(&nx_query, ResponseCode::NXDomain, Some((60, 60)));
+        lru.negative(nx_query, error, now);
+
+        // expired entries are not written
+        lru.insert(
+            Query::query(Name::from_str("old.example.com.").unwrap(), RecordType::A),
+            vec![(a_record("old.example.com.", 3), 1)],
+            now,
+        );
+
+        let later = now + Duration::from_secs(10);
+        let mut dump = Vec::new();
+        assert_eq!(lru.dump(&mut dump, later).unwrap(), 3);
+
+        let entries = String::from_utf8(dump)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(entries.len(), 3);
+
+        let entry = |name: &str| {
+            entries
+                .iter()
+                .find(|entry| entry["name"] == name)
+                .unwrap_or_else(|| panic!("{name} not in the dump"))
+        };
+
+        let www = entry("www.example.com.");
+        assert_eq!(www["type"], "A");
+        assert_eq!(www["class"], "IN");
+        assert_eq!(www["ttl"], 290);
+        assert_eq!(www["response"], "records");
+        assert_eq!(www["records"], 2);
+        assert_eq!(www["trust_level"], "answer");
+        assert_eq!(www["provenance"], "received");
+        assert_eq!(www["pinned"], false);
+
+        let pinned = entry("pinned.example.com.");
+        assert_eq!(pinned["provenance"], "prepopulated");
+        assert_eq!(pinned["pinned"], true);
+
+        let nx = entry("nx.example.com.");
+        assert!(nx["type"].is_null());
+        assert_eq!(nx["response"], "nxdomain");
+        assert_eq!(nx["records"], 0);
+        assert_eq!(nx["ttl"], 50);
+    }
+
+    /// A writer which checks that the cache is not locked while writing
+    struct UnlockedWriter<'a> {
+        lru: &'a DnsLru,
+        writes: usize,
+    }
+
+    impl Write for UnlockedWriter<'_> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            // a lookup from another task would wait for the lock
+            assert!(
+                self.lru.cache.try_lock().is_some(),
+                "cache locked during dump"
+            );
+            self.writes += 1;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_dump_does_not_lock_while_writing() {
+        let now = Instant::now();
+        let lru = DnsLru::new(2048, TtlConfig::default());
+        for i in 0..1024 {
+            let record = a_record(&format!("host{i}.example.com."), 1);
+            let query = Query::query(record.name().clone(), RecordType::A);
+            lru.insert(query, vec![(record, 300)], now);
+        }
+
+        let mut writer = UnlockedWriter {
+            lru: &lru,
+            writes: 0,
+        };
+        assert_eq!(lru.dump(&mut writer, now).unwrap(), 1024);
+        assert!(writer.writes >= 1024);
+    }
 }
@@ -7,11 +7,13 @@
 
 //! Error types for the crate
 
-use std::{fmt, io, sync};
+use std::{fmt, io, sync, time::Duration};
 
 use thiserror::Error;
 
-use crate::proto::{error::ProtoError, xfer::retry_dns_handle::RetryableError};
+use crate::proto::{
+    error::ProtoError, rr::rdata::svcb::SvcParamKey, xfer::retry_dns_handle::RetryableError,
+};
 
 #[cfg(feature = "backtrace")]
 use crate::proto::{trace, ExtBacktrace};
@@ -35,6 +37,10 @@ pub enum ResolveErrorKind {
     /// An error got returned by the hickory-proto crate
     #[error("proto error: {0}")]
     Proto(#[from] ProtoError),
+
+    /// An SVCB or HTTPS RRset has a record with a malformed parameter, the RRset must not be used
+    #[error("malformed SVCB parameter: {0}")]
+    MalformedSvcb(SvcParamKey),
 }
 
 impl Clone for ResolveErrorKind {
@@ -43,6 +49,7 @@ impl Clone for ResolveErrorKind {
         match self {
             Message(msg) => Message(msg),
             Msg(ref msg) => Msg(msg.clone()),
+            MalformedSvcb(key) => MalformedSvcb(*key),
             // foreign
             Proto(proto) => Self::from(proto.clone()),
         }
@@ -75,7 +82,9 @@ impl ResolveError {
 impl RetryableError for ResolveError {
     fn should_retry(&self) -> bool {
         match self.kind() {
-            ResolveErrorKind::Message(_) | ResolveErrorKind::Msg(_) => false,
+            ResolveErrorKind::Message(_)
+            | ResolveErrorKind::Msg(_)
+            | ResolveErrorKind::MalformedSvcb(_) => false,
             ResolveErrorKind::Proto(proto) => proto.should_retry(),
         }
     }
@@ -121,6 +130,41 @@ impl From<&'static str> for ResolveError {
     }
 }
 
+/// A conflict between the options of a resolver, see [`crate::ResolverBuilder`]
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// No name servers are configured to send queries to
+    #[error("no name servers are configured")]
+    NoNameServers,
+
+    /// DNSSEC validation was requested, but there is no support for it
+    #[error("validate is set, but DNSSEC support was not enabled during compilation")]
+    DnssecUnavailable,
+
+    /// The minimum TTL of positive responses is greater than the maximum
+    #[error("positive_min_ttl {min:?} is greater than positive_max_ttl {max:?}")]
+    PositiveTtl {
+        /// The configured minimum TTL
+        min: Duration,
+        /// The configured maximum TTL
+        max: Duration,
+    },
+
+    /// The minimum TTL of negative responses is greater than the maximum
+    #[error("negative_min_ttl {min:?} is greater than negative_max_ttl {max:?}")]
+    NegativeTtl {
+        /// The configured minimum TTL
+        min: Duration,
+        /// The configured maximum TTL
+        max: Duration,
+    },
+
+    /// The runtime of the resolver could not be created
+    #[error("failed to create the runtime: {0}")]
+    Runtime(#[from] io::Error),
+}
+
 #[cfg(target_os = "windows")]
 #[cfg(feature = "system-config")]
 #[cfg_attr(docsrs, doc(cfg(all(feature = "system-config", windows))))]
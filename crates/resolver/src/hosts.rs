@@ -2,6 +2,7 @@
 
 use std::collections::HashMap;
 use std::io;
+use std::net::Ipv6Addr;
 use std::path::Path;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -27,6 +28,8 @@ struct LookupType {
 pub struct Hosts {
     /// Name -> RDatas map
     by_name: HashMap<Name, LookupType>,
+    /// The scope of link-local IPv6 addresses, e.g. `eth0` of `fe80::1%eth0`
+    scopes: HashMap<Ipv6Addr, String>,
 }
 
 impl Hosts {
@@ -60,6 +63,14 @@ impl Hosts {
         None
     }
 
+    /// Returns the scope of an IPv6 address from the hosts file, e.g. `eth0` of `fe80::1%eth0`
+    ///
+    /// The scope is not part of the `IpAddr` returned in lookups, it has to be passed separately
+    ///  when connecting to a link-local address, e.g. as the scope id of a `SocketAddrV6`.
+    pub fn scope(&self, addr: &Ipv6Addr) -> Option<&str> {
+        self.scopes.get(addr).map(String::as_str)
+    }
+
     /// Insert a new Lookup for the associated `Name` and `RecordType`
     pub fn insert(&mut self, name: Name, record_type: RecordType, lookup: Lookup) {
         assert!(record_type == RecordType::A || record_type == RecordType::AAAA);
@@ -117,13 +128,30 @@ impl Hosts {
             if fields.len() < 2 {
                 continue;
             }
-            let addr = if let Some(a) = fields[0].try_parse_ip() {
+
+            // IPv6 addresses may have a scope, e.g. `fe80::1%eth0`
+            let (addr, scope) = match fields[0].split_once('%') {
+                Some((addr, scope)) => (addr, Some(scope)),
+                None => (fields[0], None),
+            };
+            let addr = if let Some(a) = addr.try_parse_ip() {
                 a
             } else {
                 warn!("could not parse an IP from hosts file");
                 continue;
             };
 
+            match (&addr, scope) {
+                (_, None) => (),
+                (RData::AAAA(aaaa), Some(scope)) if !scope.is_empty() => {
+                    self.scopes.insert(aaaa.0, scope.to_string());
+                }
+                _ => {
+                    warn!("invalid scope of IP in hosts file: {}", fields[0]);
+                    continue;
+                }
+            }
+
             for domain in fields.iter().skip(1).map(|domain| domain.to_lowercase()) {
                 if let Ok(name) = Name::from_str(&domain) {
                     let record = Record::from_rdata(name.clone(), dns_lru::MAX_TTL, addr.clone());
@@ -213,8 +241,16 @@ mod tests {
 
         assert_eq!(
             rdatas,
-            vec![RData::AAAA(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1).into())]
+            vec![
+                RData::AAAA(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1).into()),
+                RData::AAAA(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1).into()),
+            ]
+        );
+        assert_eq!(
+            hosts.scope(&Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1)),
+            Some("lo0")
         );
+        assert_eq!(hosts.scope(&Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)), None);
 
         let name = Name::from_str("broadcasthost").unwrap();
         let rdatas = hosts
@@ -255,4 +291,31 @@ mod tests {
             .collect::<Vec<RData>>();
         assert_eq!(rdatas, vec![RData::A(Ipv4Addr::new(10, 0, 1, 111).into())]);
     }
+
+    #[test]
+    fn test_read_hosts_conf_scope() {
+        let src = "fe80::1%lo localhost\nfe80::2% broken\n127.0.0.1%lo broken\n";
+        let hosts = Hosts::default().read_hosts_conf(src.as_bytes()).unwrap();
+
+        let name = Name::from_str("localhost").unwrap();
+        let rdatas = hosts
+            .lookup_static_host(&Query::query(name, RecordType::AAAA))
+            .unwrap()
+            .iter()
+            .map(ToOwned::to_owned)
+            .collect::<Vec<RData>>();
+
+        let addr = Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+        assert_eq!(rdatas, vec![RData::AAAA(addr.into())]);
+        assert_eq!(hosts.scope(&addr), Some("lo"));
+
+        // an empty scope or a scope of an IPv4 address is invalid
+        let name = Name::from_str("broken").unwrap();
+        assert!(hosts
+            .lookup_static_host(&Query::query(name.clone(), RecordType::A))
+            .is_none());
+        assert!(hosts
+            .lookup_static_host(&Query::query(name, RecordType::AAAA))
+            .is_none());
+    }
 }
@@ -253,9 +253,13 @@ extern crate cfg_if;
 extern crate serde;
 pub extern crate hickory_proto as proto;
 
+pub mod address_reputation;
 mod async_resolver;
+mod builder;
 pub mod caching_client;
+pub mod compat;
 pub mod config;
+pub mod delegation;
 pub mod dns_lru;
 pub mod dns_sd;
 pub mod error;
@@ -272,6 +276,7 @@ pub mod name_server;
 mod quic;
 #[cfg(feature = "tokio-runtime")]
 mod resolver;
+pub mod svcb;
 pub mod system_conf;
 #[cfg(feature = "dns-over-tls")]
 mod tls;
@@ -286,6 +291,7 @@ pub use async_resolver::AsyncResolver;
 #[cfg(feature = "tokio-runtime")]
 #[cfg_attr(docsrs, doc(cfg(feature = "tokio-runtime")))]
 pub use async_resolver::TokioAsyncResolver;
+pub use builder::ResolverBuilder;
 pub use hosts::Hosts;
 #[cfg(feature = "tokio-runtime")]
 #[cfg_attr(docsrs, doc(cfg(feature = "tokio-runtime")))]
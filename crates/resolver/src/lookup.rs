@@ -9,6 +9,7 @@
 
 use std::{
     cmp::min,
+    net::IpAddr,
     pin::Pin,
     slice::Iter,
     sync::Arc,
@@ -32,14 +33,19 @@ use crate::{
         error::ProtoError,
         op::Query,
         rr::{
+            canonical_name,
             rdata::{self, A, AAAA, NS, PTR},
-            Name, RData, Record, RecordType,
+            AddressIter, Name, RData, Record, RecordData, RecordDataIter, RecordType,
+            TxtStringIter,
         },
-        xfer::{DnsRequest, DnsRequestOptions, DnsResponse},
+        xfer::{DnsRequest, DnsRequestOptions, DnsResponse, UpstreamMetadata},
         DnsHandle, RetryDnsHandle,
     },
+    svcb::UsableSvcbRecords,
 };
 
+#[cfg(feature = "ech")]
+use proto::{error::ProtoResult, rr::rdata::svcb::ech::EchConfig};
 #[cfg(feature = "dnssec")]
 use proto::{rr::dnssec::Proven, DnssecDnsHandle};
 
@@ -51,6 +57,19 @@ pub struct Lookup {
     query: Query,
     records: Arc<[Record]>,
     valid_until: Instant,
+    provenance: Provenance,
+    upstream: Option<UpstreamMetadata>,
+}
+
+/// Where the records of a [`Lookup`] came from
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Provenance {
+    /// The records were received from a name server, or are otherwise known to the resolver
+    #[default]
+    Received,
+    /// The records were inserted into the cache ahead of any query, see
+    ///  [`DnsLru::prepopulate`](crate::dns_lru::DnsLru::prepopulate)
+    Prepopulated,
 }
 
 impl Lookup {
@@ -67,6 +86,8 @@ impl Lookup {
             query,
             records,
             valid_until,
+            provenance: Provenance::Received,
+            upstream: None,
         }
     }
 
@@ -76,14 +97,39 @@ impl Lookup {
             query,
             records,
             valid_until,
+            provenance: Provenance::Received,
+            upstream: None,
         }
     }
 
+    /// Returns this lookup with the provenance of its records
+    pub(crate) fn with_provenance(mut self, provenance: Provenance) -> Self {
+        self.provenance = provenance;
+        self
+    }
+
     /// Returns a reference to the `Query` that was used to produce this result.
     pub fn query(&self) -> &Query {
         &self.query
     }
 
+    /// Where the records came from, e.g. whether they were prepopulated in the cache
+    pub fn provenance(&self) -> Provenance {
+        self.provenance
+    }
+
+    /// Returns this lookup with the metadata of the upstream response it was built from
+    pub fn with_upstream(mut self, upstream: Option<UpstreamMetadata>) -> Self {
+        self.upstream = upstream;
+        self
+    }
+
+    /// How the records were received from the upstream name server, `None` if the lookup was
+    ///  answered from the cache
+    pub fn upstream(&self) -> Option<&UpstreamMetadata> {
+        self.upstream.as_ref()
+    }
+
     /// Returns an iterator over the matching the queried record type.
     pub fn iter(&self) -> LookupIter<'_> {
         LookupIter(self.records.iter())
@@ -128,6 +174,43 @@ impl Lookup {
         self.records.as_ref()
     }
 
+    /// Returns an iterator over the records of the given type, e.g. only the CNAMEs of the lookup
+    pub fn records_by_type(&self, record_type: RecordType) -> impl Iterator<Item = &Record> + '_ {
+        self.records
+            .iter()
+            .filter(move |record| record.record_type() == record_type)
+    }
+
+    /// Returns an iterator over the record data of type `T`, all other records are skipped
+    ///
+    /// ```
+    /// # use hickory_resolver::lookup::Lookup;
+    /// # use hickory_resolver::proto::rr::rdata::MX;
+    /// fn exchanges(lookup: &Lookup) -> Vec<String> {
+    ///     lookup.iter_as::<MX>().map(|mx| mx.exchange().to_string()).collect()
+    /// }
+    /// ```
+    pub fn iter_as<'a, T: RecordData + 'a>(&'a self) -> RecordDataIter<'a, T> {
+        RecordDataIter::new(&self.records)
+    }
+
+    /// Returns an iterator over the addresses of all A and AAAA records
+    pub fn addresses(&self) -> AddressIter<'_> {
+        AddressIter::new(&self.records)
+    }
+
+    /// Returns the name the queried name resolved to by following the CNAME records of the lookup
+    ///
+    /// If the lookup contains no CNAME for the queried name, the queried name is returned.
+    pub fn canonical_name(&self) -> &Name {
+        canonical_name(&self.records, self.query.name())
+    }
+
+    /// Returns an iterator over the TXT records, with the strings of each record joined
+    pub fn txt_strings(&self) -> TxtStringIter<'_> {
+        TxtStringIter::new(&self.records)
+    }
+
     /// Clones the inner vec, appends the other vec
     pub(crate) fn append(&self, other: Self) -> Self {
         let mut records = Vec::with_capacity(self.len() + other.len());
@@ -564,10 +647,72 @@ lookup_type!(
     rdata::SOA
 );
 lookup_type!(NsLookup, NsLookupIter, NsLookupIntoIter, RData::NS, NS);
+lookup_type!(
+    HttpsLookup,
+    HttpsLookupIter,
+    HttpsLookupIntoIter,
+    RData::HTTPS,
+    rdata::HTTPS
+);
+lookup_type!(
+    SvcbLookup,
+    SvcbLookupIter,
+    SvcbLookupIntoIter,
+    RData::SVCB,
+    rdata::SVCB
+);
+
+impl HttpsLookup {
+    /// Returns the records a client can use to connect to the service, see [`UsableSvcbRecords`]
+    ///
+    /// All records remain available from [`Self::iter`]. An error is returned if any record is
+    ///  malformed, the client should then connect without the HTTPS records.
+    pub fn usable_records(&self) -> ResolveResult<UsableSvcbRecords<'_>> {
+        UsableSvcbRecords::new(self.iter().map(|https| &https.0))
+    }
+}
+
+impl SvcbLookup {
+    /// Returns the records a client can use to connect to the service, see [`UsableSvcbRecords`]
+    ///
+    /// All records remain available from [`Self::iter`]. An error is returned if any record is
+    ///  malformed, the client should then connect without the SVCB records.
+    pub fn usable_records(&self) -> ResolveResult<UsableSvcbRecords<'_>> {
+        UsableSvcbRecords::new(self.iter())
+    }
+}
+
+/// A mail exchanger of an MX lookup with its addresses, see [`AsyncResolver::lookup_mx_resolved`]
+///
+/// [`AsyncResolver::lookup_mx_resolved`]: crate::AsyncResolver::lookup_mx_resolved
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MxResolved {
+    /// The preference of the exchanger, lower values are preferred
+    pub preference: u16,
+    /// The name of the exchanger
+    pub exchange: Name,
+    /// The A and AAAA addresses of the exchanger, empty if they could not be resolved
+    pub addresses: Vec<IpAddr>,
+}
+
+#[cfg(feature = "ech")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ech")))]
+impl HttpsLookup {
+    /// Returns the parsed ECHConfigList of each HTTPS record with an "ech" parameter
+    ///
+    /// The configs belong to the endpoint of their record, an error is returned for a record whose
+    ///  ECHConfigList is malformed.
+    pub fn ech_configs(
+        &self,
+    ) -> impl Iterator<Item = (&rdata::HTTPS, ProtoResult<Vec<EchConfig>>)> + '_ {
+        self.iter()
+            .filter_map(|https| Some((https, https.ech_config_list()?.configs())))
+    }
+}
 
 #[cfg(test)]
 pub mod tests {
-    use std::net::{IpAddr, Ipv4Addr};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
     use std::str::FromStr;
     use std::sync::{Arc, Mutex};
 
@@ -716,6 +861,70 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_https_usable_records() {
+        use proto::rr::rdata::svcb::{Alpn, Mandatory, SvcParamKey, SvcParamValue, Unknown, SVCB};
+
+        let name = Name::from_str("www.example.com.").unwrap();
+        let https = |priority, params| {
+            Record::from_rdata(
+                name.clone(),
+                80,
+                RData::HTTPS(rdata::HTTPS(SVCB::new(priority, Name::root(), params))),
+            )
+        };
+        let alpn = (
+            SvcParamKey::Alpn,
+            SvcParamValue::Alpn(Alpn(vec!["h3".to_string()])),
+        );
+
+        let mut message = Message::new();
+        message.add_query(Query::query(name.clone(), RecordType::HTTPS));
+        message.insert_answers(vec![
+            https(1, vec![alpn.clone()]),
+            https(
+                2,
+                vec![
+                    (
+                        SvcParamKey::Mandatory,
+                        SvcParamValue::Mandatory(Mandatory(vec![SvcParamKey::Key(65280)])),
+                    ),
+                    (
+                        SvcParamKey::Key(65280),
+                        SvcParamValue::Unknown(Unknown(vec![1])),
+                    ),
+                ],
+            ),
+        ]);
+        let response = DnsResponse::from_message(message).unwrap();
+
+        let lookup = HttpsLookup::from(
+            block_on(LookupFuture::lookup(
+                vec![name.clone()],
+                RecordType::HTTPS,
+                DnsRequestOptions::default(),
+                CachingClient::new(0, mock(vec![Ok(response)]), false),
+            ))
+            .unwrap(),
+        );
+
+        // the raw view has every record, the usable view only the compatible one
+        assert_eq!(lookup.iter().count(), 2);
+        let usable = lookup.usable_records().unwrap();
+        assert_eq!(
+            usable.iter().map(SVCB::svc_priority).collect::<Vec<_>>(),
+            [1]
+        );
+        let [(filtered, reason)] = usable.filtered() else {
+            panic!("expected one filtered record");
+        };
+        assert_eq!(filtered.svc_priority(), 2);
+        assert_eq!(
+            *reason,
+            crate::svcb::SvcbIncompatibility::UnknownMandatoryKey(SvcParamKey::Key(65280))
+        );
+    }
+
     #[test]
     fn test_lookup_into_iter_arc() {
         let mut lookup = LookupIntoIter {
@@ -762,6 +971,8 @@ pub mod tests {
             query: Query::default(),
             records: Arc::from([a1.clone(), a2.clone()]),
             valid_until: Instant::now(),
+            provenance: Provenance::Received,
+            upstream: None,
         };
 
         let mut lookup = lookup.dnssec_iter();
@@ -776,4 +987,120 @@ pub mod tests {
         );
         assert_eq!(lookup.next(), None);
     }
+
+    #[test]
+    fn test_typed_accessors() {
+        let name = |name| Name::from_str(name).unwrap();
+        let lookup = Lookup::new_with_max_ttl(
+            Query::query(name("www.example.com."), RecordType::A),
+            Arc::from([
+                Record::from_rdata(
+                    name("www.example.com."),
+                    80,
+                    RData::CNAME(rdata::CNAME(name("cdn.example.net."))),
+                ),
+                Record::from_rdata(
+                    name("cdn.example.net."),
+                    80,
+                    RData::CNAME(rdata::CNAME(name("edge.example.org."))),
+                ),
+                Record::from_rdata(
+                    name("edge.example.org."),
+                    80,
+                    RData::A(A::new(127, 0, 0, 1)),
+                ),
+                Record::from_rdata(
+                    name("edge.example.org."),
+                    80,
+                    RData::AAAA(AAAA::new(0, 0, 0, 0, 0, 0, 0, 1)),
+                ),
+                Record::from_rdata(
+                    name("edge.example.org."),
+                    80,
+                    RData::TXT(rdata::TXT::new(vec![
+                        "hello ".to_string(),
+                        "world".to_string(),
+                    ])),
+                ),
+            ]),
+        );
+
+        assert_eq!(lookup.canonical_name(), &name("edge.example.org."));
+        assert_eq!(lookup.records_by_type(RecordType::CNAME).count(), 2);
+        assert_eq!(
+            lookup.iter_as::<A>().collect::<Vec<_>>(),
+            [&A::new(127, 0, 0, 1)]
+        );
+        assert_eq!(
+            lookup.addresses().collect::<Vec<_>>(),
+            [
+                IpAddr::from(Ipv4Addr::new(127, 0, 0, 1)),
+                IpAddr::from(Ipv6Addr::LOCALHOST)
+            ]
+        );
+        assert_eq!(lookup.txt_strings().collect::<Vec<_>>(), ["hello world"]);
+
+        let empty = Lookup::new_with_max_ttl(
+            Query::query(name("www.example.com."), RecordType::A),
+            Arc::from([]),
+        );
+        assert_eq!(empty.canonical_name(), &name("www.example.com."));
+        assert_eq!(empty.records_by_type(RecordType::A).next(), None);
+        assert_eq!(empty.iter_as::<A>().next(), None);
+        assert_eq!(empty.addresses().next(), None);
+        assert_eq!(empty.txt_strings().next(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "ech")]
+    fn test_https_ech_configs() {
+        use proto::rr::rdata::svcb::{EchConfigList, SvcParamKey, SvcParamValue, SVCB};
+
+        let name = Name::from_str("www.example.com.").unwrap();
+        let https = |priority, params| {
+            Record::from_rdata(
+                name.clone(),
+                80,
+                RData::HTTPS(rdata::HTTPS(SVCB::new(priority, Name::root(), params))),
+            )
+        };
+        let ech = |list: &[u8]| {
+            vec![(
+                SvcParamKey::EchConfigList,
+                SvcParamValue::EchConfigList(EchConfigList(list.to_vec())),
+            )]
+        };
+
+        #[rustfmt::skip]
+        let valid = [
+            0x00, 0x15, // list length
+            0xfe, 0x0d, 0x00, 0x11, // version and length
+            0x01, 0x00, 0x20, // config id and KEM
+            0x00, 0x01, 0xaa, // public key
+            0x00, 0x04, 0x00, 0x01, 0x00, 0x01, // cipher suites
+            0x00, 0x01, b'a', // maximum name length and public name
+            0x00, 0x00, // extensions
+        ];
+        let lookup = HttpsLookup::from(Lookup::new_with_max_ttl(
+            Query::query(name.clone(), RecordType::HTTPS),
+            Arc::from([
+                https(1, ech(&valid)),
+                https(2, vec![]),
+                https(3, ech(&[0x00, 0x01, 0x00])),
+            ]),
+        ));
+
+        let configs = lookup.ech_configs().collect::<Vec<_>>();
+        assert_eq!(configs.len(), 2);
+
+        let (record, valid) = &configs[0];
+        assert_eq!(record.svc_priority(), 1);
+        let valid = valid.as_ref().unwrap();
+        assert_eq!(valid.len(), 1);
+        assert_eq!(valid[0].contents().unwrap().public_name(), "a");
+
+        let (record, malformed) = &configs[1];
+        assert_eq!(record.svc_priority(), 3);
+        assert!(malformed.is_err());
+    }
 }
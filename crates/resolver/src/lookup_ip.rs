@@ -59,6 +59,49 @@ impl LookupIp {
     pub fn as_lookup(&self) -> &Lookup {
         &self.0
     }
+
+    /// Reorders the addresses so that the IPv6 addresses are returned before the IPv4 addresses
+    ///
+    /// The order within each family is kept.
+    pub fn prefer_ipv6(self) -> Self {
+        self.sorted_by_key(|record| record.record_type() == RecordType::A)
+    }
+
+    /// Reorders the addresses so that the IPv4 addresses are returned before the IPv6 addresses
+    ///
+    /// The order within each family is kept.
+    pub fn prefer_ipv4(self) -> Self {
+        self.sorted_by_key(|record| record.record_type() == RecordType::AAAA)
+    }
+
+    /// Removes all IPv6 addresses
+    pub fn ipv4_only(self) -> Self {
+        self.filtered(|record| record.record_type() != RecordType::AAAA)
+    }
+
+    /// Removes all IPv4 addresses
+    pub fn ipv6_only(self) -> Self {
+        self.filtered(|record| record.record_type() != RecordType::A)
+    }
+
+    pub(crate) fn sorted_by_key<K: Ord>(self, key: impl FnMut(&Record) -> K) -> Self {
+        let mut records = self.0.records().to_vec();
+        records.sort_by_key(key);
+        self.with_records(records)
+    }
+
+    fn filtered(self, filter: impl FnMut(&Record) -> bool) -> Self {
+        let records = self.0.records().iter().cloned().filter(filter).collect();
+        self.with_records(records)
+    }
+
+    fn with_records(self, records: Vec<Record>) -> Self {
+        Self(Lookup::new_with_deadline(
+            self.0.query().clone(),
+            Arc::from(records),
+            self.0.valid_until(),
+        ))
+    }
 }
 
 impl From<Lookup> for LookupIp {
@@ -454,12 +497,25 @@ pub mod tests {
     #[derive(Clone)]
     pub struct MockDnsHandle {
         messages: Arc<Mutex<Vec<Result<DnsResponse, ProtoError>>>>,
+        queries: Arc<Mutex<Vec<Query>>>,
+    }
+
+    impl MockDnsHandle {
+        /// All the queries sent through this handle, in order
+        pub fn queries(&self) -> Vec<Query> {
+            self.queries.lock().unwrap().clone()
+        }
     }
 
     impl DnsHandle for MockDnsHandle {
         type Response = Pin<Box<dyn Stream<Item = Result<DnsResponse, ProtoError>> + Send + Unpin>>;
 
-        fn send<R: Into<DnsRequest>>(&self, _: R) -> Self::Response {
+        fn send<R: Into<DnsRequest>>(&self, request: R) -> Self::Response {
+            self.queries
+                .lock()
+                .unwrap()
+                .extend(request.into().queries().iter().cloned());
+
             Box::pin(once(future::ready(
                 self.messages.lock().unwrap().pop().unwrap_or_else(empty),
             )))
@@ -505,6 +561,7 @@ pub mod tests {
     pub fn mock(messages: Vec<Result<DnsResponse, ProtoError>>) -> MockDnsHandle {
         MockDnsHandle {
             messages: Arc::new(Mutex::new(messages)),
+            queries: Arc::new(Mutex::new(vec![])),
         }
     }
 
@@ -719,4 +776,237 @@ pub mod tests {
             vec![Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)]
         );
     }
+
+    #[cfg(feature = "tokio-runtime")]
+    mod truncation {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        use futures_util::stream::once;
+        use tokio::time::{sleep, Instant};
+
+        use super::*;
+        use crate::config::{
+            LookupIpStrategy, NameServerConfig, NameServerConfigGroup, Protocol, ResolverConfig,
+            ResolverOpts,
+        };
+        use crate::name_server::{ConnectionProvider, TokioRuntimeProvider};
+        use crate::AsyncResolver;
+        use proto::rr::rdata::{A, AAAA};
+
+        const UDP_RTT: Duration = Duration::from_millis(50);
+        const TCP_RTT: Duration = Duration::from_millis(100);
+
+        /// Answers all queries over UDP with a truncated response, and over TCP with the complete
+        ///  one, except for the `hanging` type which is never answered over TCP
+        #[derive(Clone)]
+        struct ScriptedConnector {
+            tcp_connections: Arc<AtomicUsize>,
+            hanging: Option<RecordType>,
+        }
+
+        #[derive(Clone)]
+        struct ScriptedConn {
+            protocol: Protocol,
+            hanging: Option<RecordType>,
+        }
+
+        impl ConnectionProvider for ScriptedConnector {
+            type Conn = ScriptedConn;
+            type FutureConn = future::Ready<Result<ScriptedConn, ProtoError>>;
+            type RuntimeProvider = TokioRuntimeProvider;
+
+            fn new_connection(
+                &self,
+                config: &NameServerConfig,
+                _options: &ResolverOpts,
+            ) -> Self::FutureConn {
+                if config.protocol == Protocol::Tcp {
+                    self.tcp_connections.fetch_add(1, Ordering::SeqCst);
+                }
+
+                future::ok(ScriptedConn {
+                    protocol: config.protocol,
+                    hanging: self.hanging,
+                })
+            }
+        }
+
+        impl DnsHandle for ScriptedConn {
+            type Response =
+                Pin<Box<dyn Stream<Item = Result<DnsResponse, ProtoError>> + Send + Unpin>>;
+
+            fn send<R: Into<DnsRequest>>(&self, request: R) -> Self::Response {
+                let request = request.into();
+                let query = request.queries()[0].clone();
+                let (protocol, hanging) = (self.protocol, self.hanging);
+
+                Box::pin(once(Box::pin(async move {
+                    let name = query.name().clone();
+                    let records = match query.query_type() {
+                        RecordType::A => vec![
+                            Record::from_rdata(name.clone(), 300, RData::A(A::new(1, 1, 1, 1))),
+                            Record::from_rdata(name, 300, RData::A(A::new(2, 2, 2, 2))),
+                        ],
+                        RecordType::AAAA => vec![
+                            Record::from_rdata(
+                                name.clone(),
+                                300,
+                                RData::AAAA(AAAA::new(1, 0, 0, 0, 0, 0, 0, 1)),
+                            ),
+                            Record::from_rdata(
+                                name,
+                                300,
+                                RData::AAAA(AAAA::new(2, 0, 0, 0, 0, 0, 0, 2)),
+                            ),
+                        ],
+                        _ => vec![],
+                    };
+
+                    let mut message = Message::new();
+                    message.set_id(request.id()).add_query(query.clone());
+                    if protocol == Protocol::Udp {
+                        sleep(UDP_RTT).await;
+                        message.set_truncated(true);
+                        message.add_answer(records[0].clone());
+                    } else if hanging == Some(query.query_type()) {
+                        future::pending::<()>().await;
+                    } else {
+                        sleep(TCP_RTT).await;
+                        message.insert_answers(records);
+                    }
+
+                    DnsResponse::from_message(message)
+                })))
+            }
+        }
+
+        fn resolver(
+            hanging: Option<RecordType>,
+        ) -> (AsyncResolver<ScriptedConnector>, Arc<AtomicUsize>) {
+            let tcp_connections = Arc::new(AtomicUsize::new(0));
+            let connector = ScriptedConnector {
+                tcp_connections: tcp_connections.clone(),
+                hanging,
+            };
+
+            let config = ResolverConfig::from_parts(
+                None,
+                vec![],
+                NameServerConfigGroup::from_ips_clear(&[IpAddr::from([127, 0, 0, 1])], 53, true),
+            );
+            let options = ResolverOpts {
+                ip_strategy: LookupIpStrategy::Ipv4AndIpv6,
+                use_hosts_file: false,
+                // no retries, so that the deadline of a single request is observed
+                attempts: 0,
+                ..ResolverOpts::default()
+            };
+
+            (
+                AsyncResolver::new_with_conn(config, options, connector),
+                tcp_connections,
+            )
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_ipv4_and_ipv6_truncated() {
+            let (resolver, tcp_connections) = resolver(None);
+
+            let started = Instant::now();
+            let lookup = resolver.lookup_ip("www.example.com.").await.unwrap();
+            let elapsed = started.elapsed();
+
+            assert_eq!(lookup.iter().count(), 4);
+            // both families are exchanged concurrently, not one after the other
+            assert!(elapsed < UDP_RTT + TCP_RTT + UDP_RTT, "took {elapsed:?}");
+            assert_eq!(tcp_connections.load(Ordering::SeqCst), 1);
+
+            // both families are cached
+            let started = Instant::now();
+            assert_eq!(
+                resolver
+                    .lookup_ip("www.example.com.")
+                    .await
+                    .unwrap()
+                    .iter()
+                    .count(),
+                4
+            );
+            assert_eq!(started.elapsed(), Duration::ZERO);
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_ipv4_and_ipv6_truncated_timeout() {
+            let (resolver, tcp_connections) = resolver(Some(RecordType::AAAA));
+
+            let started = Instant::now();
+            let lookup = resolver.lookup_ip("www.example.com.").await.unwrap();
+            let elapsed = started.elapsed();
+
+            assert_eq!(lookup.iter().count(), 2);
+            assert!(lookup.iter().all(|ip| ip.is_ipv4()));
+            // the TCP fallback is bounded by the deadline of the request, the UDP round trip isn't
+            //  deducted here only because the paused clock doesn't advance `std::time::Instant`
+            assert!(
+                elapsed <= ResolverOpts::default().timeout + UDP_RTT,
+                "took {elapsed:?}"
+            );
+            assert_eq!(tcp_connections.load(Ordering::SeqCst), 1);
+
+            // the family which completed was cached
+            let started = Instant::now();
+            assert_eq!(
+                resolver
+                    .ipv4_lookup("www.example.com.")
+                    .await
+                    .unwrap()
+                    .iter()
+                    .count(),
+                2
+            );
+            assert_eq!(started.elapsed(), Duration::ZERO);
+        }
+    }
+
+    #[test]
+    fn test_address_family_order() {
+        let v4 = |last| IpAddr::from(Ipv4Addr::new(127, 0, 0, last));
+        let v6 = |last| IpAddr::from(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, last));
+        let record = |ip: IpAddr| {
+            let rdata = match ip {
+                IpAddr::V4(ip) => RData::A(ip.into()),
+                IpAddr::V6(ip) => RData::AAAA(ip.into()),
+            };
+            Record::from_rdata(Name::root(), 86400, rdata)
+        };
+
+        let addrs = [v4(1), v6(1), v4(2), v6(2)];
+        let lookup = LookupIp::from(Lookup::new_with_max_ttl(
+            Query::query(Name::root(), RecordType::A),
+            addrs.iter().copied().map(record).collect(),
+        ));
+
+        assert_eq!(
+            lookup.clone().prefer_ipv6().iter().collect::<Vec<_>>(),
+            vec![v6(1), v6(2), v4(1), v4(2)]
+        );
+        assert_eq!(
+            lookup
+                .clone()
+                .prefer_ipv6()
+                .prefer_ipv4()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![v4(1), v4(2), v6(1), v6(2)]
+        );
+        assert_eq!(
+            lookup.clone().ipv4_only().iter().collect::<Vec<_>>(),
+            vec![v4(1), v4(2)]
+        );
+        assert_eq!(
+            lookup.ipv6_only().iter().collect::<Vec<_>>(),
+            vec![v6(1), v6(2)]
+        );
+    }
 }
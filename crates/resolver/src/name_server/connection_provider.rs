@@ -109,6 +109,16 @@ pub trait ConnectionProvider: 'static + Clone + Send + Sync + Unpin {
     /// Create a new connection.
     fn new_connection(&self, config: &NameServerConfig, options: &ResolverOpts)
         -> Self::FutureConn;
+
+    /// Spawns a future in the background, for work which must not delay the requests, e.g. the
+    ///  queries to a shadow upstream, see [`crate::config::ShadowConfig`]
+    ///
+    /// Returns false if the provider can't spawn futures, which is the default, the future is
+    ///  dropped then.
+    fn spawn_bg(&self, future: Pin<Box<dyn Future<Output = ()> + Send>>) -> bool {
+        drop(future);
+        false
+    }
 }
 
 /// A type defines the Handle which can spawn future.
@@ -427,6 +437,13 @@ impl<P: RuntimeProvider> ConnectionProvider for GenericConnector<P> {
             spawner: self.runtime_provider.create_handle(),
         }
     }
+
+    fn spawn_bg(&self, future: Pin<Box<dyn Future<Output = ()> + Send>>) -> bool {
+        self.runtime_provider
+            .create_handle()
+            .spawn_bg(future.map(Ok));
+        true
+    }
 }
 
 /// A stream of response to a DNS request.
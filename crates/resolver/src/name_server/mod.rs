@@ -13,6 +13,7 @@ mod name_server;
 mod name_server_pool;
 mod name_server_state;
 mod name_server_stats;
+pub mod shadow;
 
 pub use self::connection_provider::{ConnectionProvider, RuntimeProvider, Spawn};
 pub use self::connection_provider::{GenericConnection, GenericConnector};
@@ -20,9 +21,11 @@ pub use self::connection_provider::{GenericConnection, GenericConnector};
 #[cfg_attr(docsrs, doc(cfg(feature = "mdns")))]
 pub(crate) use self::name_server::mdns_nameserver;
 pub use self::name_server::{GenericNameServer, NameServer};
+pub(crate) use self::name_server_pool::SharedAddressFamily;
 pub use self::name_server_pool::{GenericNameServerPool, NameServerPool};
 use self::name_server_state::NameServerState;
 use self::name_server_stats::NameServerStats;
+pub use self::shadow::ShadowLog;
 
 #[cfg(feature = "tokio-runtime")]
 #[cfg_attr(docsrs, doc(cfg(feature = "tokio-runtime")))]
@@ -7,6 +7,7 @@
 
 use std::cmp::Ordering;
 use std::fmt::{self, Debug, Formatter};
+use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Instant;
@@ -17,12 +18,13 @@ use futures_util::stream::{once, Stream};
 #[cfg(feature = "mdns")]
 use proto::multicast::MDNS_IPV4;
 use proto::{
-    error::ProtoError,
+    error::{ProtoError, ProtoErrorKind},
+    op::ResponseCode,
     xfer::{DnsHandle, DnsRequest, DnsResponse, FirstAnswer},
 };
 use tracing::debug;
 
-use crate::config::{NameServerConfig, ResolverOpts};
+use crate::config::{NameServerConfig, Protocol, ResolverOpts};
 use crate::name_server::connection_provider::{ConnectionProvider, GenericConnector};
 use crate::name_server::{NameServerState, NameServerStats};
 #[cfg(feature = "mdns")]
@@ -84,6 +86,16 @@ where
         }
     }
 
+    /// The address of the remote name server
+    pub(crate) fn socket_addr(&self) -> SocketAddr {
+        self.config.socket_addr
+    }
+
+    /// The protocol used to reach the name server
+    pub(crate) fn protocol(&self) -> Protocol {
+        self.config.protocol
+    }
+
     #[cfg(test)]
     #[allow(dead_code)]
     pub(crate) fn is_connected(&self) -> bool {
@@ -142,7 +154,26 @@ where
 
                 // First evaluate if the message succeeded.
                 let response =
-                    ProtoError::from_response(response, self.config.trust_negative_responses)?;
+                    match ProtoError::from_response(response, self.config.trust_negative_responses)
+                    {
+                        Ok(response) => response,
+                        Err(error) => {
+                            // a server failing to answer is penalized like a connection failure,
+                            //  so that the other servers of the pool are preferred
+                            if let ProtoErrorKind::NoRecordsFound {
+                                response_code:
+                                    ResponseCode::ServFail
+                                    | ResponseCode::FormErr
+                                    | ResponseCode::NotImp,
+                                ..
+                            } = error.kind()
+                            {
+                                self.stats.record_connection_failure();
+                            }
+
+                            return Err(error);
+                        }
+                    };
 
                 // TODO: consider making message::take_edns...
                 let remote_edns = response.extensions().clone();
@@ -152,6 +183,11 @@ where
 
                 Ok(response)
             }
+            // the query is too big for the local socket, the name server didn't fail
+            Err(error) if error.is_message_too_big() => {
+                debug!("query too big for the socket: {}", error);
+                Err(error)
+            }
             Err(error) => {
                 debug!("name_server connection failure: {}", error);
 
@@ -7,27 +7,32 @@
 
 use std::cmp::Ordering;
 use std::pin::Pin;
+use std::sync::atomic::{self, AtomicU8};
 use std::sync::Arc;
 use std::task::{Context, Poll};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use futures_util::future::FutureExt;
 use futures_util::stream::{once, FuturesUnordered, Stream, StreamExt};
 use hickory_proto::error::ProtoErrorKind;
 use smallvec::SmallVec;
 
-use proto::xfer::{DnsHandle, DnsRequest, DnsResponse, FirstAnswer};
+use proto::xfer::{DnsHandle, DnsRequest, DnsResponse, FirstAnswer, UpstreamMetadata};
 use proto::Time;
 use tracing::debug;
 
 use rand::thread_rng as rng;
 use rand::Rng;
 
-use crate::config::{NameServerConfigGroup, ResolverConfig, ResolverOpts, ServerOrderingStrategy};
+use crate::config::{
+    NameServerConfigGroup, ResolverConfig, ResolverOpts, ServerFailurePolicy,
+    ServerOrderingStrategy, UpstreamAddressFamily,
+};
 #[cfg(feature = "mdns")]
 use crate::name_server;
 use crate::name_server::connection_provider::{ConnectionProvider, GenericConnector};
 use crate::name_server::name_server::NameServer;
+use crate::name_server::shadow::{Shadow, ShadowLog};
 use crate::name_server::RuntimeProvider;
 #[cfg(test)]
 #[cfg(feature = "tokio-runtime")]
@@ -43,6 +48,43 @@ pub struct NameServerPool<P: ConnectionProvider + Send + 'static> {
     #[cfg(feature = "mdns")]
     mdns_conns: NameServer<P>, /* All NameServers must be the same type */
     options: ResolverOpts,
+    upstream_address_family: SharedAddressFamily,
+    shadow: Option<Arc<Shadow<P>>>,
+}
+
+/// The address family used to reach the name servers, shared by all clones of a pool so that it
+///  can be changed at runtime
+#[derive(Clone, Debug)]
+pub(crate) struct SharedAddressFamily(Arc<AtomicU8>);
+
+impl SharedAddressFamily {
+    fn new(family: UpstreamAddressFamily) -> Self {
+        Self(Arc::new(AtomicU8::new(Self::to_u8(family))))
+    }
+
+    pub(crate) fn load(&self) -> UpstreamAddressFamily {
+        match self.0.load(atomic::Ordering::Acquire) {
+            1 => UpstreamAddressFamily::V4Only,
+            2 => UpstreamAddressFamily::V6Only,
+            3 => UpstreamAddressFamily::PreferV4,
+            4 => UpstreamAddressFamily::PreferV6,
+            _ => UpstreamAddressFamily::Any,
+        }
+    }
+
+    pub(crate) fn store(&self, family: UpstreamAddressFamily) {
+        self.0.store(Self::to_u8(family), atomic::Ordering::Release);
+    }
+
+    fn to_u8(family: UpstreamAddressFamily) -> u8 {
+        match family {
+            UpstreamAddressFamily::Any => 0,
+            UpstreamAddressFamily::V4Only => 1,
+            UpstreamAddressFamily::V6Only => 2,
+            UpstreamAddressFamily::PreferV4 => 3,
+            UpstreamAddressFamily::PreferV6 => 4,
+        }
+    }
 }
 
 /// A pool of NameServers
@@ -112,6 +154,8 @@ where
             stream_conns: Arc::from(stream_conns),
             #[cfg(feature = "mdns")]
             mdns_conns: name_server::mdns_nameserver(options, conn_provider.clone(), false),
+            upstream_address_family: SharedAddressFamily::new(options.upstream_address_family),
+            shadow: Shadow::from_options(&options, conn_provider),
             options,
         }
     }
@@ -138,6 +182,8 @@ where
             stream_conns: Arc::from(stream_conns),
             #[cfg(feature = "mdns")]
             mdns_conns: name_server::mdns_nameserver(*options, conn_provider.clone(), false),
+            upstream_address_family: SharedAddressFamily::new(options.upstream_address_family),
+            shadow: Shadow::from_options(&options, conn_provider),
             options,
         }
     }
@@ -152,6 +198,8 @@ where
         Self {
             datagram_conns: Arc::from(datagram_conns),
             stream_conns: Arc::from(stream_conns),
+            upstream_address_family: SharedAddressFamily::new(options.upstream_address_family),
+            shadow: None,
             options,
         }
     }
@@ -168,6 +216,8 @@ where
             datagram_conns: Arc::from(datagram_conns),
             stream_conns: Arc::from(stream_conns),
             mdns_conns,
+            upstream_address_family: SharedAddressFamily::new(options.upstream_address_family),
+            shadow: None,
             options,
         }
     }
@@ -183,6 +233,8 @@ where
         Self {
             datagram_conns,
             stream_conns,
+            upstream_address_family: SharedAddressFamily::new(options.upstream_address_family),
+            shadow: None,
             options,
         }
     }
@@ -199,12 +251,36 @@ where
             datagram_conns,
             stream_conns,
             mdns_conns,
+            upstream_address_family: SharedAddressFamily::new(options.upstream_address_family),
+            shadow: None,
             options: *options,
         }
     }
 
+    /// Sets the address family used to reach the name servers, this applies to all clones of the
+    ///  pool, starting with the next request
+    pub fn set_upstream_address_family(&self, family: UpstreamAddressFamily) {
+        self.upstream_address_family.store(family);
+    }
+
+    /// The address family currently used to reach the name servers
+    pub fn upstream_address_family(&self) -> UpstreamAddressFamily {
+        self.upstream_address_family.load()
+    }
+
+    pub(crate) fn shared_address_family(&self) -> SharedAddressFamily {
+        self.upstream_address_family.clone()
+    }
+
+    /// The outcomes of the queries duplicated to the shadow upstream, if one is configured in
+    ///  [`ResolverOpts::shadow`]
+    pub fn shadow_log(&self) -> Option<&ShadowLog> {
+        self.shadow.as_deref().map(Shadow::log)
+    }
+
     async fn try_send(
         opts: ResolverOpts,
+        family: UpstreamAddressFamily,
         conns: Arc<[NameServer<P>]>,
         request: DnsRequest,
     ) -> Result<DnsResponse, ProtoError> {
@@ -217,10 +293,91 @@ where
             ServerOrderingStrategy::QueryStatistics => conns.sort_unstable(),
             ServerOrderingStrategy::UserProvidedOrder => {}
         }
+
+        // the sorts are stable, the order within each family is kept
+        match family {
+            UpstreamAddressFamily::Any => {}
+            UpstreamAddressFamily::V4Only => conns.retain(|c| c.socket_addr().is_ipv4()),
+            UpstreamAddressFamily::V6Only => conns.retain(|c| c.socket_addr().is_ipv6()),
+            UpstreamAddressFamily::PreferV4 => conns.sort_by_key(|c| c.socket_addr().is_ipv6()),
+            UpstreamAddressFamily::PreferV6 => conns.sort_by_key(|c| c.socket_addr().is_ipv4()),
+        }
         let request_loop = request.clone();
 
         parallel_conn_loop(conns, request_loop, opts).await
     }
+
+    async fn send_upstream(
+        opts: ResolverOpts,
+        family: UpstreamAddressFamily,
+        datagram_conns: Arc<[NameServer<P>]>,
+        stream_conns: Arc<[NameServer<P>]>,
+        request: DnsRequest,
+    ) -> Result<DnsResponse, ProtoError> {
+        // TODO: remove this clone, return the Message in the error?
+        let tcp_message = request.clone();
+        debug!("sending request: {:?}", request.queries());
+        let started = Instant::now();
+
+        // First try the UDP connections
+        let udp_res: Result<DnsResponse, ProtoError> =
+            match Self::try_send(opts.clone(), family, datagram_conns, request).await {
+                Ok(response) if response.truncated() => {
+                    debug!("truncated response received, retrying over TCP");
+                    Ok(response)
+                }
+                Err(e) if e.is_message_too_big() => {
+                    debug!("request too big for UDP, retrying over TCP: {}", e);
+                    Err(e)
+                }
+                Err(e) if opts.try_tcp_on_error || e.is_no_connections() || e.is_io() => {
+                    debug!("error from UDP, retrying over TCP: {}", e);
+                    Err(e)
+                }
+                result => return result.map_err(ProtoError::from),
+            };
+
+        if stream_conns.is_empty() {
+            debug!("no TCP connections available");
+            return udp_res.map_err(ProtoError::from);
+        }
+
+        let udp_err = match udp_res {
+            // The records of the truncated response are incomplete, it's not retained while
+            //  waiting for TCP. The server did answer, so TCP only gets the rest of the time
+            //  budget of the request, which e.g. the A and AAAA lookups of `LookupIp` share.
+            Ok(truncated) => {
+                drop(truncated);
+
+                let remaining = opts.timeout.saturating_sub(started.elapsed());
+                let tcp_res = <P::RuntimeProvider as RuntimeProvider>::Timer::timeout(
+                    remaining,
+                    Self::try_send(opts, family, stream_conns, tcp_message),
+                )
+                .await;
+
+                return match tcp_res {
+                    Ok(res) => res.map(with_tcp_fallback),
+                    Err(_) => {
+                        debug!("TCP request timed out after truncated response");
+                        Err(ProtoError::from(ProtoErrorKind::Timeout))
+                    }
+                };
+            }
+            Err(e) => e,
+        };
+
+        // Try query over TCP, as response to query over UDP was an error.
+        let tcp_err = match Self::try_send(opts, family, stream_conns, tcp_message).await {
+            Ok(response) => return Ok(with_tcp_fallback(response)),
+            Err(e) => e,
+        };
+
+        match udp_err.cmp_specificity(&tcp_err) {
+            Ordering::Greater => Err(udp_err),
+            _ => Err(tcp_err),
+        }
+    }
 }
 
 impl<P> DnsHandle for NameServerPool<P>
@@ -231,11 +388,10 @@ where
 
     fn send<R: Into<DnsRequest>>(&self, request: R) -> Self::Response {
         let opts = self.options.clone();
+        let family = self.upstream_address_family.load();
         let request = request.into();
         let datagram_conns = Arc::clone(&self.datagram_conns);
         let stream_conns = Arc::clone(&self.stream_conns);
-        // TODO: remove this clone, return the Message in the error?
-        let tcp_message = request.clone();
 
         // if it's a .local. query, then we *only* query mDNS, these should never be sent on to upstream resolvers
         #[cfg(feature = "mdns")]
@@ -254,51 +410,35 @@ where
 
         // it wasn't a local query, continue with standard lookup path
         let request = mdns.take_request();
+        let shadow = self
+            .shadow
+            .as_ref()
+            .filter(|shadow| shadow.sample(&request))
+            .map(|shadow| (Arc::clone(shadow), request.clone()));
+
         Box::pin(once(async move {
-            debug!("sending request: {:?}", request.queries());
-
-            // First try the UDP connections
-            let udp_res: Result<DnsResponse, ProtoError> =
-                match Self::try_send(opts.clone(), datagram_conns, request).await {
-                    Ok(response) if response.truncated() => {
-                        debug!("truncated response received, retrying over TCP");
-                        Ok(response)
-                    }
-                    Err(e) if opts.try_tcp_on_error || e.is_no_connections() || e.is_io() => {
-                        debug!("error from UDP, retrying over TCP: {}", e);
-                        Err(e)
-                    }
-                    result => return result.map_err(ProtoError::from),
-                };
+            let started = Instant::now();
+            let result =
+                Self::send_upstream(opts, family, datagram_conns, stream_conns, request).await;
 
-            if stream_conns.is_empty() {
-                debug!("no TCP connections available");
-                return udp_res.map_err(ProtoError::from);
+            // the shadow query is never awaited, it doesn't delay the answer
+            if let Some((shadow, shadow_request)) = shadow {
+                shadow.spawn(shadow_request, &result, started.elapsed());
             }
 
-            // Try query over TCP, as response to query over UDP was either truncated or was an
-            // error.
-            let tcp_res = Self::try_send(opts, stream_conns, tcp_message).await;
-
-            let tcp_err = match tcp_res {
-                res @ Ok(..) => return res.map_err(ProtoError::from),
-                Err(e) => e,
-            };
-
-            // Even if the UDP result was truncated, return that
-            let udp_err = match udp_res {
-                Ok(response) => return Ok(response),
-                Err(e) => e,
-            };
-
-            match udp_err.cmp_specificity(&tcp_err) {
-                Ordering::Greater => Err(udp_err),
-                _ => Err(tcp_err),
-            }
+            result
         }))
     }
 }
 
+/// Marks the response as received over TCP, after the request was sent over UDP first
+fn with_tcp_fallback(mut response: DnsResponse) -> DnsResponse {
+    if let Some(upstream) = response.upstream_mut() {
+        upstream.tcp_fallback = true;
+    }
+    response
+}
+
 // TODO: we should be able to have a self-referential future here with Pin and not require cloned conns
 /// An async function that will loop over all the conns with a max parallel request count of ops.num_concurrent_req
 async fn parallel_conn_loop<P>(
@@ -323,6 +463,8 @@ where
     // to fire than the timeout configured in `ResolverOpts`.
     let mut backoff = Duration::from_millis(20);
     let mut busy = SmallVec::<[NameServer<P>; 2]>::new();
+    // the failed attempts, which are reported with the response
+    let mut failures = 0;
 
     loop {
         let request_cont = request.clone();
@@ -365,20 +507,45 @@ where
             .map(move |conn| {
                 conn.send(request_cont.clone())
                     .first_answer()
-                    .map(|result| result.map_err(|e| (conn, e)))
+                    .map(|result| match result {
+                        Ok(mut response) => {
+                            let upstream =
+                                UpstreamMetadata::new(conn.socket_addr(), conn.protocol().as_str());
+                            response.set_upstream(upstream);
+                            Ok(response)
+                        }
+                        Err(e) => Err((conn, e)),
+                    })
             })
             .collect::<FuturesUnordered<_>>();
 
         while let Some(result) = requests.next().await {
             let (conn, e) = match result {
-                Ok(sent) => return Ok(sent),
+                Ok(mut sent) => {
+                    if let Some(upstream) = sent.upstream_mut() {
+                        upstream.retries = failures;
+                    }
+                    return Ok(sent);
+                }
                 Err((conn, e)) => (conn, e),
             };
+            failures += 1;
+            debug!("name server {} failed: {}", conn.socket_addr(), e);
 
             match e.kind() {
                 ProtoErrorKind::NoRecordsFound { trusted, .. } if *trusted => {
                     return Err(e);
                 }
+                // the request is too big for the local socket, so for every other connection
+                _ if e.is_message_too_big() => {
+                    return Err(e);
+                }
+                // the name server responded, which is final unless failures are retried
+                ProtoErrorKind::NoRecordsFound { .. }
+                    if opts.server_failure_policy == ServerFailurePolicy::FirstAnswer =>
+                {
+                    return Err(e);
+                }
                 _ if e.is_busy() => {
                     busy.push(conn);
                 }
@@ -483,12 +650,14 @@ mod tests {
 
     use tokio::runtime::Runtime;
 
-    use proto::op::Query;
-    use proto::rr::{Name, RecordType};
+    use proto::op::{Edns, Message, MessageType, Query};
+    use proto::rr::rdata::{opt::EdnsOption, A};
+    use proto::rr::{Name, RData, Record, RecordType};
     use proto::xfer::{DnsHandle, DnsRequestOptions};
 
     use super::*;
     use crate::config::NameServerConfig;
+    use crate::config::NameServerConfigGroup;
     use crate::config::Protocol;
     use crate::name_server::TokioRuntimeProvider;
     use crate::name_server::{GenericNameServer, TokioConnectionProvider};
@@ -652,4 +821,124 @@ mod tests {
             "if this is failing then the NameServers aren't being properly shared."
         );
     }
+
+    /// The UDP connections send the request with a real socket, so that an oversized request
+    ///  fails like on the wire, and answer with 192.0.2.1; the TCP connections answer with
+    ///  192.0.2.2
+    #[derive(Clone, Default)]
+    struct OversizeConnector {
+        udp_sends: Arc<atomic::AtomicUsize>,
+    }
+
+    #[derive(Clone)]
+    struct OversizeConn {
+        udp_sends: Option<Arc<atomic::AtomicUsize>>,
+    }
+
+    impl ConnectionProvider for OversizeConnector {
+        type Conn = OversizeConn;
+        type FutureConn = futures_util::future::Ready<Result<OversizeConn, ProtoError>>;
+        type RuntimeProvider = TokioRuntimeProvider;
+
+        fn new_connection(
+            &self,
+            config: &NameServerConfig,
+            _options: &ResolverOpts,
+        ) -> Self::FutureConn {
+            let udp_sends = (config.protocol == Protocol::Udp).then(|| self.udp_sends.clone());
+            futures_util::future::ok(OversizeConn { udp_sends })
+        }
+    }
+
+    impl DnsHandle for OversizeConn {
+        type Response = Pin<Box<dyn Stream<Item = Result<DnsResponse, ProtoError>> + Send + Unpin>>;
+
+        fn send<R: Into<DnsRequest>>(&self, request: R) -> Self::Response {
+            let request = request.into();
+            let udp_sends = self.udp_sends.clone();
+
+            Box::pin(once(Box::pin(async move {
+                let ip = match udp_sends {
+                    Some(udp_sends) => {
+                        udp_sends.fetch_add(1, atomic::Ordering::Relaxed);
+                        let socket = tokio::net::UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+                        // the discard port, the answer is scripted
+                        socket
+                            .send_to(&request.to_vec()?, (Ipv4Addr::LOCALHOST, 9))
+                            .await?;
+                        A::new(192, 0, 2, 1)
+                    }
+                    None => A::new(192, 0, 2, 2),
+                };
+
+                let query = request.queries()[0].clone();
+                let mut message = Message::new();
+                message
+                    .set_id(request.id())
+                    .set_message_type(MessageType::Response)
+                    .add_query(query.clone())
+                    .add_answer(Record::from_rdata(query.name().clone(), 300, RData::A(ip)));
+                DnsResponse::from_message(message)
+            })))
+        }
+    }
+
+    fn oversize_request(padding: u16) -> DnsRequest {
+        let name = Name::from_str("www.example.com.").unwrap();
+        let mut edns = Edns::new();
+        edns.options_mut().insert(EdnsOption::Padding(padding));
+
+        let mut message = Message::new();
+        message
+            .add_query(Query::query(name, RecordType::A))
+            .set_edns(edns);
+        DnsRequest::new(message, DnsRequestOptions::default())
+    }
+
+    #[tokio::test]
+    async fn test_oversized_request_falls_back_to_tcp() {
+        let connector = OversizeConnector::default();
+        let group = NameServerConfigGroup::from_ips_clear(
+            &[
+                IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+                IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)),
+            ],
+            53,
+            true,
+        );
+        let options = ResolverOpts {
+            num_concurrent_reqs: 1,
+            ..ResolverOpts::default()
+        };
+        let pool = NameServerPool::from_config(group, options, connector.clone());
+
+        // a regular request is answered over UDP
+        let response = pool.send(oversize_request(0)).first_answer().await.unwrap();
+        assert_eq!(
+            response.answers()[0].data(),
+            &RData::A(A::new(192, 0, 2, 1))
+        );
+        assert_eq!(connector.udp_sends.load(atomic::Ordering::Relaxed), 1);
+        let upstream = response.upstream().unwrap();
+        assert_eq!(upstream.server, SocketAddr::from(([127, 0, 0, 1], 53)));
+        assert_eq!(upstream.protocol, "udp");
+        assert!(!upstream.tcp_fallback);
+
+        // larger than any UDP datagram, the socket rejects it
+        let response = pool
+            .send(oversize_request(65_480))
+            .first_answer()
+            .await
+            .unwrap();
+        assert_eq!(
+            response.answers()[0].data(),
+            &RData::A(A::new(192, 0, 2, 2))
+        );
+        let upstream = response.upstream().unwrap();
+        assert_eq!(upstream.protocol, "tcp");
+        assert!(upstream.tcp_fallback);
+
+        // the other UDP name server isn't tried, as it would reject the request too
+        assert_eq!(connector.udp_sends.load(atomic::Ordering::Relaxed), 2);
+    }
 }
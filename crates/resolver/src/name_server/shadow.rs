@@ -0,0 +1,551 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Duplication of a sample of the queries to a shadow upstream, see [`ShadowConfig`]
+
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures_util::StreamExt;
+use rand::Rng;
+use tracing::debug;
+
+use crate::config::{ResolverOpts, ShadowConfig};
+use crate::name_server::{ConnectionProvider, NameServerPool};
+use crate::proto::error::{ProtoError, ProtoErrorKind};
+use crate::proto::op::{Query, ResponseCode};
+use crate::proto::rr::{Name, RData};
+use crate::proto::xfer::{DnsHandle, DnsRequest, DnsResponse};
+
+/// The number of events retained by a [`ShadowLog`], the oldest are dropped first
+pub const MAX_SHADOW_EVENTS: usize = 1024;
+
+/// The upper bounds of the latency buckets of [`ShadowStats::latency_buckets`], the last bucket
+///  counts the larger differences
+pub const LATENCY_BUCKETS: [Duration; 5] = [
+    Duration::from_millis(1),
+    Duration::from_millis(10),
+    Duration::from_millis(50),
+    Duration::from_millis(200),
+    Duration::from_secs(1),
+];
+
+/// The result of the comparison of a shadow query with the primary answer
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ShadowOutcome {
+    /// Both upstreams returned the same response code and answers
+    Match,
+    /// The response codes are equal, but the answers differ
+    AnswerMismatch,
+    /// The response codes differ
+    ResponseCodeMismatch {
+        /// The response code of the configured name servers
+        primary: ResponseCode,
+        /// The response code of the shadow upstream
+        shadow: ResponseCode,
+    },
+    /// The shadow upstream didn't answer in time
+    Timeout,
+    /// The shadow query failed, e.g. it couldn't connect
+    Error(String),
+}
+
+/// A shadow query, with its outcome
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShadowEvent {
+    /// The query which was duplicated
+    pub query: Query,
+    /// The result of the comparison
+    pub outcome: ShadowOutcome,
+    /// The time taken by the configured name servers
+    pub primary_latency: Duration,
+    /// The time taken by the shadow upstream
+    pub shadow_latency: Duration,
+}
+
+/// Counters of the shadow queries by outcome
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ShadowStats {
+    /// The number of queries which were duplicated
+    pub sampled: u64,
+    /// The number of matching answers
+    pub matches: u64,
+    /// The number of answers with different records
+    pub answer_mismatches: u64,
+    /// The number of answers with different response codes
+    pub response_code_mismatches: u64,
+    /// The number of shadow queries which timed out
+    pub timeouts: u64,
+    /// The number of shadow queries which failed
+    pub errors: u64,
+    /// The number of shadow queries by how much slower the shadow upstream was, bounded by
+    ///  [`LATENCY_BUCKETS`], queries where the shadow was faster are in the first bucket
+    pub latency_buckets: [u64; LATENCY_BUCKETS.len() + 1],
+}
+
+type Callback = Arc<dyn Fn(&ShadowEvent) + Send + Sync>;
+
+#[derive(Default)]
+struct Inner {
+    stats: ShadowStats,
+    events: VecDeque<ShadowEvent>,
+    callback: Option<Callback>,
+}
+
+/// The outcomes of the shadow queries, shared by all clones of a resolver
+#[derive(Clone, Default)]
+pub struct ShadowLog(Arc<Mutex<Inner>>);
+
+impl ShadowLog {
+    /// The counters of the shadow queries since the resolver was created
+    pub fn stats(&self) -> ShadowStats {
+        self.lock().stats.clone()
+    }
+
+    /// Removes and returns the retained events, oldest first, at most [`MAX_SHADOW_EVENTS`]
+    pub fn drain_events(&self) -> Vec<ShadowEvent> {
+        self.lock().events.drain(..).collect()
+    }
+
+    /// Sets a function called with each event as it is recorded, on the task of the shadow query
+    pub fn set_callback<F>(&self, callback: F)
+    where
+        F: Fn(&ShadowEvent) + Send + Sync + 'static,
+    {
+        self.lock().callback = Some(Arc::new(callback));
+    }
+
+    fn sampled(&self) {
+        self.lock().stats.sampled += 1;
+    }
+
+    fn record(&self, event: ShadowEvent) {
+        let callback = {
+            let mut inner = self.lock();
+            let stats = &mut inner.stats;
+            match event.outcome {
+                ShadowOutcome::Match => stats.matches += 1,
+                ShadowOutcome::AnswerMismatch => stats.answer_mismatches += 1,
+                ShadowOutcome::ResponseCodeMismatch { .. } => stats.response_code_mismatches += 1,
+                ShadowOutcome::Timeout => stats.timeouts += 1,
+                ShadowOutcome::Error(_) => stats.errors += 1,
+            }
+
+            let delta = event.shadow_latency.saturating_sub(event.primary_latency);
+            let bucket = LATENCY_BUCKETS
+                .iter()
+                .position(|bound| delta <= *bound)
+                .unwrap_or(LATENCY_BUCKETS.len());
+            stats.latency_buckets[bucket] += 1;
+
+            if inner.events.len() == MAX_SHADOW_EVENTS {
+                inner.events.pop_front();
+            }
+            inner.events.push_back(event.clone());
+            inner.callback.clone()
+        };
+
+        // outside of the lock, the callback may read the log
+        if let Some(callback) = callback {
+            callback(&event);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        // the counters stay consistent even if a callback panicked
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fmt::Debug for ShadowLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShadowLog")
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+/// The shadow upstream of a [`NameServerPool`]
+pub(crate) struct Shadow<P: ConnectionProvider + Send + 'static> {
+    config: ShadowConfig,
+    pool: NameServerPool<P>,
+    conn_provider: P,
+    log: ShadowLog,
+}
+
+impl<P> Shadow<P>
+where
+    P: ConnectionProvider + 'static,
+{
+    /// Returns the shadow configured in the options, with the other options of the pool
+    pub(crate) fn from_options(options: &ResolverOpts, conn_provider: P) -> Option<Arc<Self>> {
+        let config = options.shadow.clone()?;
+        let options = ResolverOpts {
+            shadow: None,
+            ..options.clone()
+        };
+        let pool =
+            NameServerPool::from_config(config.upstream.clone(), options, conn_provider.clone());
+
+        Some(Arc::new(Self {
+            config,
+            pool,
+            conn_provider,
+            log: ShadowLog::default(),
+        }))
+    }
+
+    pub(crate) fn log(&self) -> &ShadowLog {
+        &self.log
+    }
+
+    /// Returns true if the request should be duplicated
+    pub(crate) fn sample(&self, request: &DnsRequest) -> bool {
+        let rate = self.config.sample_rate;
+        if rate.is_nan() || rate <= 0.0 {
+            return false;
+        }
+
+        let sample = match (self.config.deterministic, request.queries().first()) {
+            (true, Some(query)) => name_sample(query.name()),
+            _ => rand::thread_rng().gen::<f64>(),
+        };
+
+        sample < rate
+    }
+
+    /// Sends the request to the shadow upstream in the background, and records the outcome
+    ///  compared to the `primary` result
+    pub(crate) fn spawn(
+        &self,
+        request: DnsRequest,
+        primary: &Result<DnsResponse, ProtoError>,
+        primary_latency: Duration,
+    ) {
+        let Some(query) = request.queries().first().cloned() else {
+            return;
+        };
+        let Some(primary) = Answer::from_result(primary) else {
+            debug!("primary failed, not sending shadow query for {query}");
+            return;
+        };
+
+        let compare = self.config.compare;
+        let pool = self.pool.clone();
+        let log = self.log.clone();
+        log.sampled();
+
+        let spawned = self.conn_provider.spawn_bg(Box::pin(async move {
+            let started = Instant::now();
+            let shadow = pool
+                .send(request)
+                .next()
+                .await
+                .unwrap_or_else(|| Err(ProtoErrorKind::Timeout.into()));
+            let shadow_latency = started.elapsed();
+
+            let outcome = match Answer::from_result(&shadow) {
+                Some(shadow) => primary.compare(&shadow, compare),
+                None => match shadow {
+                    Err(e) if matches!(e.kind(), ProtoErrorKind::Timeout) => ShadowOutcome::Timeout,
+                    Err(e) => ShadowOutcome::Error(e.to_string()),
+                    Ok(_) => unreachable!("responses are answers"),
+                },
+            };
+            debug!("shadow query for {query}: {outcome:?}");
+
+            log.record(ShadowEvent {
+                query,
+                outcome,
+                primary_latency,
+                shadow_latency,
+            });
+        }));
+
+        if !spawned {
+            debug!("the connection provider can't spawn, shadow queries are disabled");
+        }
+    }
+}
+
+/// The comparable parts of a response
+struct Answer {
+    response_code: ResponseCode,
+    answers: HashSet<RData>,
+}
+
+impl Answer {
+    /// Returns None for errors without a response from the upstream
+    fn from_result(result: &Result<DnsResponse, ProtoError>) -> Option<Self> {
+        match result {
+            Ok(response) => Some(Self {
+                response_code: response.response_code(),
+                answers: response
+                    .answers()
+                    .iter()
+                    .map(|r| r.data().clone())
+                    .collect(),
+            }),
+            Err(e) => match e.kind() {
+                ProtoErrorKind::NoRecordsFound { response_code, .. } => Some(Self {
+                    response_code: *response_code,
+                    answers: HashSet::new(),
+                }),
+                _ => None,
+            },
+        }
+    }
+
+    fn compare(&self, shadow: &Self, compare_answers: bool) -> ShadowOutcome {
+        if self.response_code != shadow.response_code {
+            ShadowOutcome::ResponseCodeMismatch {
+                primary: self.response_code,
+                shadow: shadow.response_code,
+            }
+        } else if compare_answers && self.answers != shadow.answers {
+            ShadowOutcome::AnswerMismatch
+        } else {
+            ShadowOutcome::Match
+        }
+    }
+}
+
+/// Maps a name to [0, 1) by its FNV-1a hash, ignoring the case
+fn name_sample(name: &Name) -> f64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for label in name.iter() {
+        for b in label.iter().chain(b".") {
+            hash ^= u64::from(b.to_ascii_lowercase());
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    // the 53 high bits fit the mantissa
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+#[cfg(feature = "tokio-runtime")]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::pin::Pin;
+
+    use futures_util::future::{self, Future};
+    use futures_util::stream::{once, Stream};
+    use tokio::time::{sleep, Instant as TokioInstant};
+
+    use super::*;
+    use crate::config::{NameServerConfig, NameServerConfigGroup};
+    use crate::name_server::TokioRuntimeProvider;
+    use crate::proto::op::Message;
+    use crate::proto::rr::rdata::A;
+    use crate::proto::rr::{Record, RecordType};
+
+    const PRIMARY: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 1);
+    const SHADOW: Ipv4Addr = Ipv4Addr::new(127, 0, 0, 2);
+    const PRIMARY_RTT: Duration = Duration::from_millis(10);
+    const SHADOW_RTT: Duration = Duration::from_millis(500);
+
+    /// The primary and the shadow answer with the same address, except for `moved.example.com.`,
+    ///  and the shadow answers `gone.example.com.` with NXDOMAIN
+    #[derive(Clone)]
+    struct ScriptedConnector;
+
+    #[derive(Clone)]
+    struct ScriptedConn {
+        shadow: bool,
+    }
+
+    impl ConnectionProvider for ScriptedConnector {
+        type Conn = ScriptedConn;
+        type FutureConn = future::Ready<Result<ScriptedConn, ProtoError>>;
+        type RuntimeProvider = TokioRuntimeProvider;
+
+        fn new_connection(
+            &self,
+            config: &NameServerConfig,
+            _options: &ResolverOpts,
+        ) -> Self::FutureConn {
+            future::ok(ScriptedConn {
+                shadow: config.socket_addr.ip() == IpAddr::V4(SHADOW),
+            })
+        }
+
+        fn spawn_bg(&self, future: Pin<Box<dyn Future<Output = ()> + Send>>) -> bool {
+            tokio::spawn(future);
+            true
+        }
+    }
+
+    impl DnsHandle for ScriptedConn {
+        type Response = Pin<Box<dyn Stream<Item = Result<DnsResponse, ProtoError>> + Send + Unpin>>;
+
+        fn send<R: Into<DnsRequest>>(&self, request: R) -> Self::Response {
+            let request = request.into();
+            let query = request.queries()[0].clone();
+            let shadow = self.shadow;
+
+            Box::pin(once(Box::pin(async move {
+                let mut message = Message::new();
+                message.set_id(request.id()).add_query(query.clone());
+
+                let name = query.name().to_ascii();
+                if shadow && name == "gone.example.com." {
+                    message.set_response_code(ResponseCode::NXDomain);
+                } else {
+                    let ip = match (shadow, name.as_str()) {
+                        (true, "moved.example.com.") => A::new(192, 0, 2, 2),
+                        _ => A::new(192, 0, 2, 1),
+                    };
+                    message.add_answer(Record::from_rdata(query.name().clone(), 300, RData::A(ip)));
+                }
+
+                sleep(if shadow { SHADOW_RTT } else { PRIMARY_RTT }).await;
+                DnsResponse::from_message(message)
+            })))
+        }
+    }
+
+    fn pool(sample_rate: f64, deterministic: bool) -> NameServerPool<ScriptedConnector> {
+        let group = |ip: Ipv4Addr| NameServerConfigGroup::from_ips_clear(&[ip.into()], 53, true);
+        let options = ResolverOpts {
+            shadow: Some(ShadowConfig {
+                deterministic,
+                ..ShadowConfig::new(group(SHADOW), sample_rate)
+            }),
+            ..ResolverOpts::default()
+        };
+
+        NameServerPool::from_config(group(PRIMARY), options, ScriptedConnector)
+    }
+
+    async fn query(pool: &NameServerPool<ScriptedConnector>, name: &str) -> DnsResponse {
+        let query = Query::query(Name::from_ascii(name).unwrap(), RecordType::A);
+        let mut message = Message::new();
+        message.add_query(query);
+        pool.send(DnsRequest::new(message, Default::default()))
+            .next()
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_discrepancies() {
+        let pool = pool(1.0, false);
+        let log = pool.shadow_log().unwrap().clone();
+
+        for name in [
+            "same.example.com.",
+            "moved.example.com.",
+            "gone.example.com.",
+        ] {
+            let started = TokioInstant::now();
+            let response = query(&pool, name).await;
+
+            // the caller gets the primary answer without waiting for the shadow
+            assert_eq!(started.elapsed(), PRIMARY_RTT);
+            assert_eq!(response.response_code(), ResponseCode::NoError);
+        }
+        assert_eq!(log.stats().sampled, 3);
+        assert!(log.drain_events().is_empty());
+
+        sleep(2 * SHADOW_RTT).await;
+
+        let stats = log.stats();
+        assert_eq!(stats.matches, 1);
+        assert_eq!(stats.answer_mismatches, 1);
+        assert_eq!(stats.response_code_mismatches, 1);
+        assert_eq!(stats.timeouts + stats.errors, 0);
+
+        let mut events = log.drain_events();
+        events.sort_by_key(|e| e.query.name().to_ascii());
+        let outcomes = events.into_iter().map(|e| e.outcome).collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            vec![
+                ShadowOutcome::ResponseCodeMismatch {
+                    primary: ResponseCode::NoError,
+                    shadow: ResponseCode::NXDomain,
+                },
+                ShadowOutcome::AnswerMismatch,
+                ShadowOutcome::Match,
+            ]
+        );
+        assert!(log.drain_events().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_callback_without_compare() {
+        let group = |ip: Ipv4Addr| NameServerConfigGroup::from_ips_clear(&[ip.into()], 53, true);
+        let options = ResolverOpts {
+            shadow: Some(ShadowConfig {
+                compare: false,
+                ..ShadowConfig::new(group(SHADOW), 1.0)
+            }),
+            ..ResolverOpts::default()
+        };
+        let pool = NameServerPool::from_config(group(PRIMARY), options, ScriptedConnector);
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_clone = Arc::clone(&seen);
+        pool.shadow_log()
+            .unwrap()
+            .set_callback(move |event| seen_clone.lock().unwrap().push(event.outcome.clone()));
+
+        query(&pool, "moved.example.com.").await;
+        sleep(2 * SHADOW_RTT).await;
+
+        // only the response codes are compared
+        assert_eq!(*seen.lock().unwrap(), vec![ShadowOutcome::Match]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_deterministic_sample_rate() {
+        let pool = pool(0.25, true);
+        let log = pool.shadow_log().unwrap().clone();
+
+        let names = (0..400)
+            .map(|i| format!("host{i}.example.com."))
+            .collect::<Vec<_>>();
+        for name in &names {
+            query(&pool, name).await;
+        }
+        let sampled = log.stats().sampled;
+        assert!((60..=140).contains(&sampled), "sampled {sampled}");
+
+        // the same names are sampled again, regardless of the case
+        for name in &names {
+            query(&pool, &name.to_uppercase()).await;
+        }
+        assert_eq!(log.stats().sampled, 2 * sampled);
+
+        sleep(2 * SHADOW_RTT).await;
+        assert_eq!(log.stats().matches, 2 * sampled);
+    }
+
+    #[test]
+    fn test_sample_rate_bounds() {
+        let shadow = |sample_rate| {
+            let options = ResolverOpts {
+                shadow: Some(ShadowConfig::new(NameServerConfigGroup::new(), sample_rate)),
+                ..ResolverOpts::default()
+            };
+            Shadow::from_options(&options, ScriptedConnector).unwrap()
+        };
+        let mut message = Message::new();
+        message.add_query(Query::query(Name::root(), RecordType::A));
+        let request = DnsRequest::new(message, Default::default());
+
+        assert!((0..100).all(|_| !shadow(0.0).sample(&request)));
+        assert!((0..100).all(|_| !shadow(f64::NAN).sample(&request)));
+        assert!((0..100).all(|_| shadow(1.0).sample(&request)));
+    }
+}
@@ -7,7 +7,7 @@
 
 //! Structs for creating and using a Resolver
 use std::io;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Mutex;
 
 use proto::rr::domain::TryParseIp;
@@ -15,7 +15,9 @@ use proto::rr::IntoName;
 use proto::rr::RecordType;
 use tokio::runtime::{self, Runtime};
 
-use crate::config::{ResolverConfig, ResolverOpts};
+use crate::address_reputation::Outcome;
+use crate::compat;
+use crate::config::{ResolverConfig, ResolverOpts, UpstreamAddressFamily};
 use crate::error::*;
 use crate::lookup;
 use crate::lookup::Lookup;
@@ -115,11 +117,33 @@ impl Resolver {
         Self::new(config, options)
     }
 
+    /// Constructs a new Resolver with the system configuration, or the fallback `policy` if it
+    ///  has no usable name servers.
+    ///
+    /// This will use `/etc/resolv.conf` on Unix OSes and the registry on Windows.
+    #[cfg(any(unix, target_os = "windows"))]
+    #[cfg(feature = "system-config")]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(all(feature = "system-config", any(unix, target_os = "windows"))))
+    )]
+    pub fn from_system_conf_with_fallback(
+        policy: &crate::system_conf::FallbackPolicy,
+    ) -> io::Result<Self> {
+        let (config, options) = super::system_conf::read_system_conf_with_fallback(policy)?;
+        Self::new(config, options)
+    }
+
     /// Flushes/Removes all entries from the cache
     pub fn clear_cache(&self) {
         self.async_resolver.clear_cache();
     }
 
+    /// The cache of the resolver, e.g. to pin or prepopulate the entries of critical names
+    pub fn cache(&self) -> &crate::dns_lru::DnsLru {
+        self.async_resolver.cache()
+    }
+
     /// Read the config for this resolver.
     pub fn config(&self) -> &ResolverConfig {
         self.async_resolver.config()
@@ -130,6 +154,23 @@ impl Resolver {
         self.async_resolver.options()
     }
 
+    /// Sets the address family used to reach the name servers, see
+    ///  [`AsyncResolver::set_upstream_address_family`]
+    pub fn set_upstream_address_family(&self, family: UpstreamAddressFamily) {
+        self.async_resolver.set_upstream_address_family(family);
+    }
+
+    /// The address family currently used to reach the name servers
+    pub fn upstream_address_family(&self) -> UpstreamAddressFamily {
+        self.async_resolver.upstream_address_family()
+    }
+
+    /// Reports the outcome of connecting to an address returned by [`Self::lookup_ip`], see
+    ///  [`AsyncResolver::report_outcome`]
+    pub fn report_outcome(&self, ip: IpAddr, outcome: Outcome) {
+        self.async_resolver.report_outcome(ip, outcome);
+    }
+
     /// Generic lookup for any RecordType
     ///
     /// *WARNING* This interface may change in the future, please use [`Self::lookup_ip`] or another variant for more stable interfaces.
@@ -155,6 +196,33 @@ impl Resolver {
         self.runtime.lock()?.block_on(lookup)
     }
 
+    /// Looks up the MX records of the domain, and the addresses of each mail exchanger
+    ///
+    /// See [`crate::AsyncResolver::lookup_mx_resolved`].
+    ///
+    /// # Arguments
+    ///
+    /// * `domain` - the domain receiving the mail
+    pub fn lookup_mx_resolved<N: IntoName>(
+        &self,
+        domain: N,
+    ) -> ResolveResult<Vec<lookup::MxResolved>> {
+        let lookup = self.async_resolver.lookup_mx_resolved(domain);
+        self.runtime.lock()?.block_on(lookup)
+    }
+
+    /// Looks up the socket addresses of a `host:port` string, like `std::net::ToSocketAddrs`
+    ///
+    /// See [`AsyncResolver::resolve_host_port`] for the format and the kinds of the errors.
+    pub fn resolve_host_port(&self, host_port: &str) -> io::Result<Vec<SocketAddr>> {
+        let lookup = self.async_resolver.resolve_host_port(host_port);
+        let runtime = self
+            .runtime
+            .lock()
+            .map_err(|e| compat::io_error(ResolveError::from(e)))?;
+        Ok(runtime.block_on(lookup)?.collect())
+    }
+
     lookup_fn!(reverse_lookup, lookup::ReverseLookup, IpAddr);
     lookup_fn!(ipv4_lookup, lookup::Ipv4Lookup);
     lookup_fn!(ipv6_lookup, lookup::Ipv6Lookup);
@@ -164,6 +232,8 @@ impl Resolver {
     lookup_fn!(srv_lookup, lookup::SrvLookup);
     lookup_fn!(tlsa_lookup, lookup::TlsaLookup);
     lookup_fn!(txt_lookup, lookup::TxtLookup);
+    lookup_fn!(https_lookup, lookup::HttpsLookup);
+    lookup_fn!(svcb_lookup, lookup::SvcbLookup);
 }
 
 #[cfg(test)]
@@ -181,6 +251,20 @@ mod tests {
         require_send_sync::<Resolver>();
     }
 
+    #[test]
+    fn test_resolve_host_port() {
+        // there are no name servers, literal addresses are not looked up
+        let resolver = Resolver::new(ResolverConfig::new(), ResolverOpts::default()).unwrap();
+
+        let addrs = resolver.resolve_host_port("[2001:db8::1]:443").unwrap();
+        assert_eq!(addrs, ["[2001:db8::1]:443".parse().unwrap()]);
+        let addrs = resolver.resolve_host_port("192.0.2.1:80").unwrap();
+        assert_eq!(addrs, ["192.0.2.1:80".parse().unwrap()]);
+
+        let error = resolver.resolve_host_port("2001:db8::1:443").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
     #[test]
     fn test_lookup() {
         let resolver = Resolver::new(ResolverConfig::default(), ResolverOpts::default()).unwrap();
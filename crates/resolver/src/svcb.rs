@@ -0,0 +1,319 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Client side processing of SVCB and HTTPS RRsets, [RFC 9460 section 8](https://www.rfc-editor.org/rfc/rfc9460#section-8)
+//!
+//! The records of a lookup are left as they were received, [`UsableSvcbRecords`] is a view of the
+//!  records a client can use to connect to the service.
+
+use std::fmt;
+
+use crate::error::{ResolveError, ResolveErrorKind};
+use crate::proto::rr::rdata::svcb::{SvcParamKey, SvcParamValue, SVCB};
+
+/// The reason a record of an SVCB or HTTPS RRset is not usable
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SvcbIncompatibility {
+    /// The mandatory list of the ServiceMode record contains a key which isn't supported
+    UnknownMandatoryKey(SvcParamKey),
+    /// The value of the parameter is malformed, this makes the whole RRset unusable
+    MalformedParam(SvcParamKey),
+    /// The ServiceMode record is ignored because the RRset has an AliasMode record
+    AliasModePresent,
+}
+
+impl fmt::Display for SvcbIncompatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownMandatoryKey(key) => write!(f, "unknown mandatory key: {key}"),
+            Self::MalformedParam(key) => write!(f, "malformed parameter: {key}"),
+            Self::AliasModePresent => f.write_str("ignored for an AliasMode record"),
+        }
+    }
+}
+
+/// The records of an SVCB or HTTPS RRset which a client can use
+///
+/// If the RRset has an AliasMode record only the AliasMode records are usable, otherwise the
+///  ServiceMode records which don't require an unsupported parameter are usable. The records keep
+///  the order in which they were received.
+#[derive(Clone, Debug)]
+pub struct UsableSvcbRecords<'a> {
+    usable: Vec<&'a SVCB>,
+    filtered: Vec<(&'a SVCB, SvcbIncompatibility)>,
+}
+
+impl<'a> UsableSvcbRecords<'a> {
+    /// Processes the records of an SVCB or HTTPS RRset
+    ///
+    /// # Returns
+    ///
+    /// An error of the kind [`ResolveErrorKind::MalformedSvcb`] if any record is malformed, in
+    ///  which case a client must not use the RRset and should fall back to connecting without it
+    pub fn new(records: impl IntoIterator<Item = &'a SVCB>) -> Result<Self, ResolveError> {
+        let records = records.into_iter().collect::<Vec<_>>();
+        for record in &records {
+            if let Err(key) = check_malformed(record) {
+                return Err(ResolveErrorKind::MalformedSvcb(key).into());
+            }
+        }
+
+        let alias_mode = records.iter().any(|record| record.svc_priority() == 0);
+        let mut usable = Vec::new();
+        let mut filtered = Vec::new();
+        for record in records {
+            match incompatibility(record, alias_mode) {
+                None => usable.push(record),
+                Some(reason) => filtered.push((record, reason)),
+            }
+        }
+
+        Ok(Self { usable, filtered })
+    }
+
+    /// Returns an iterator over the usable records
+    pub fn iter(&self) -> impl Iterator<Item = &'a SVCB> + '_ {
+        self.usable.iter().copied()
+    }
+
+    /// Returns the records which are not usable, with the reason for each
+    pub fn filtered(&self) -> &[(&'a SVCB, SvcbIncompatibility)] {
+        &self.filtered
+    }
+
+    /// Returns `true` if no record is usable
+    pub fn is_empty(&self) -> bool {
+        self.usable.is_empty()
+    }
+}
+
+/// Returns `true` for the keys whose parameters are understood by this library
+fn is_supported(key: SvcParamKey) -> bool {
+    matches!(
+        key,
+        SvcParamKey::Mandatory
+            | SvcParamKey::Alpn
+            | SvcParamKey::NoDefaultAlpn
+            | SvcParamKey::Port
+            | SvcParamKey::Ipv4Hint
+            | SvcParamKey::EchConfigList
+            | SvcParamKey::Ipv6Hint
+    )
+}
+
+/// Checks the parameters of a ServiceMode record, see RFC 9460 section 2.4.3 and 7
+///
+/// The parameters of AliasMode records are ignored by clients, so they are never malformed.
+fn check_malformed(record: &SVCB) -> Result<(), SvcParamKey> {
+    if record.svc_priority() == 0 {
+        return Ok(());
+    }
+
+    let params = record.svc_params();
+    let has_key = |key: SvcParamKey| params.iter().any(|(k, _)| *k == key);
+
+    for (i, (key, value)) in params.iter().enumerate() {
+        if params[..i].iter().any(|(k, _)| k == key) {
+            return Err(*key);
+        }
+
+        let well_formed = match (key, value) {
+            (SvcParamKey::Mandatory, SvcParamValue::Mandatory(mandatory)) => {
+                !mandatory.0.is_empty()
+                    && mandatory.0.iter().enumerate().all(|(j, k)| {
+                        *k != SvcParamKey::Mandatory && !mandatory.0[..j].contains(k) && has_key(*k)
+                    })
+            }
+            (SvcParamKey::Alpn, SvcParamValue::Alpn(alpn)) => {
+                !alpn.0.is_empty() && alpn.0.iter().all(|id| !id.is_empty())
+            }
+            // "no-default-alpn" is only meaningful with an "alpn" list
+            (SvcParamKey::NoDefaultAlpn, SvcParamValue::NoDefaultAlpn) => {
+                has_key(SvcParamKey::Alpn)
+            }
+            (SvcParamKey::Port, SvcParamValue::Port(_)) => true,
+            (SvcParamKey::Ipv4Hint, SvcParamValue::Ipv4Hint(hint)) => !hint.0.is_empty(),
+            (SvcParamKey::EchConfigList, SvcParamValue::EchConfigList(list)) => !list.0.is_empty(),
+            (SvcParamKey::Ipv6Hint, SvcParamValue::Ipv6Hint(hint)) => !hint.0.is_empty(),
+            (key, SvcParamValue::Unknown(_)) => !is_supported(*key),
+            _ => false,
+        };
+
+        if !well_formed {
+            return Err(*key);
+        }
+    }
+
+    Ok(())
+}
+
+fn incompatibility(record: &SVCB, alias_mode: bool) -> Option<SvcbIncompatibility> {
+    if record.svc_priority() == 0 {
+        return None;
+    }
+
+    if alias_mode {
+        return Some(SvcbIncompatibility::AliasModePresent);
+    }
+
+    record
+        .svc_params()
+        .iter()
+        .find_map(|(_, value)| match value {
+            SvcParamValue::Mandatory(mandatory) => mandatory
+                .0
+                .iter()
+                .find(|key| !is_supported(**key))
+                .map(|key| SvcbIncompatibility::UnknownMandatoryKey(*key)),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+    use crate::proto::rr::rdata::svcb::{Alpn, IpHint, Mandatory, Unknown};
+    use crate::proto::rr::rdata::A;
+    use crate::proto::rr::Name;
+
+    fn svcb(priority: u16, params: Vec<(SvcParamKey, SvcParamValue)>) -> SVCB {
+        SVCB::new(
+            priority,
+            Name::from_ascii("svc.example.com.").unwrap(),
+            params,
+        )
+    }
+
+    fn alpn() -> (SvcParamKey, SvcParamValue) {
+        (
+            SvcParamKey::Alpn,
+            SvcParamValue::Alpn(Alpn(vec!["h2".to_string()])),
+        )
+    }
+
+    fn mandatory(keys: Vec<SvcParamKey>) -> (SvcParamKey, SvcParamValue) {
+        (
+            SvcParamKey::Mandatory,
+            SvcParamValue::Mandatory(Mandatory(keys)),
+        )
+    }
+
+    fn unknown(key: u16) -> (SvcParamKey, SvcParamValue) {
+        (
+            SvcParamKey::Key(key),
+            SvcParamValue::Unknown(Unknown(vec![1])),
+        )
+    }
+
+    fn priorities<'a>(records: impl Iterator<Item = &'a SVCB>) -> Vec<u16> {
+        records.map(SVCB::svc_priority).collect()
+    }
+
+    #[test]
+    fn test_usable() {
+        let records = [
+            svcb(1, vec![mandatory(vec![SvcParamKey::Alpn]), alpn()]),
+            svcb(2, vec![alpn(), unknown(65280)]),
+        ];
+
+        let usable = UsableSvcbRecords::new(&records).unwrap();
+        assert_eq!(priorities(usable.iter()), [1, 2]);
+        assert!(usable.filtered().is_empty());
+    }
+
+    #[test]
+    fn test_unknown_mandatory_key() {
+        let records = [
+            svcb(
+                1,
+                vec![mandatory(vec![SvcParamKey::Key(65280)]), unknown(65280)],
+            ),
+            svcb(2, vec![alpn()]),
+        ];
+
+        let usable = UsableSvcbRecords::new(&records).unwrap();
+        assert_eq!(priorities(usable.iter()), [2]);
+        assert_eq!(
+            usable.filtered(),
+            [(
+                &records[0],
+                SvcbIncompatibility::UnknownMandatoryKey(SvcParamKey::Key(65280))
+            )]
+        );
+    }
+
+    #[test]
+    fn test_alias_mode_overrides_service_mode() {
+        let records = [svcb(1, vec![alpn()]), svcb(0, vec![])];
+
+        let usable = UsableSvcbRecords::new(&records).unwrap();
+        assert_eq!(priorities(usable.iter()), [0]);
+        assert_eq!(
+            usable.filtered(),
+            [(&records[0], SvcbIncompatibility::AliasModePresent)]
+        );
+    }
+
+    #[test]
+    fn test_malformed_rejects_rrset() {
+        let malformed = [
+            // a mandatory key which is not in the record
+            svcb(2, vec![mandatory(vec![SvcParamKey::Port])]),
+            // the mandatory list must not contain "mandatory"
+            svcb(2, vec![mandatory(vec![SvcParamKey::Mandatory])]),
+            // "no-default-alpn" without "alpn"
+            svcb(
+                2,
+                vec![(SvcParamKey::NoDefaultAlpn, SvcParamValue::NoDefaultAlpn)],
+            ),
+            // an empty hint
+            svcb(
+                2,
+                vec![(
+                    SvcParamKey::Ipv4Hint,
+                    SvcParamValue::Ipv4Hint(IpHint(vec![])),
+                )],
+            ),
+            // a value which doesn't match its key
+            svcb(
+                2,
+                vec![(
+                    SvcParamKey::Port,
+                    SvcParamValue::Ipv4Hint(IpHint(vec![A(Ipv4Addr::LOCALHOST)])),
+                )],
+            ),
+        ];
+        let expected = [
+            SvcParamKey::Mandatory,
+            SvcParamKey::Mandatory,
+            SvcParamKey::NoDefaultAlpn,
+            SvcParamKey::Ipv4Hint,
+            SvcParamKey::Port,
+        ];
+
+        for (record, key) in malformed.into_iter().zip(expected) {
+            // a single malformed record makes the whole RRset unusable
+            let records = [svcb(1, vec![alpn()]), record];
+            let error = UsableSvcbRecords::new(&records).unwrap_err();
+            assert!(
+                matches!(error.kind(), ResolveErrorKind::MalformedSvcb(k) if *k == key),
+                "{error}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_alias_mode_params_ignored() {
+        let records = [svcb(0, vec![mandatory(vec![SvcParamKey::Port])])];
+
+        let usable = UsableSvcbRecords::new(&records).unwrap();
+        assert_eq!(priorities(usable.iter()), [0]);
+    }
+}
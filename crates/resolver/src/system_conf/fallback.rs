@@ -0,0 +1,120 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Fallback for system configurations without usable name servers
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use tracing::warn;
+
+use crate::config::{NameServerConfigGroup, ResolverConfig, ResolverOpts, ServerOrderingStrategy};
+use crate::error::{ResolveErrorKind, ResolveResult};
+
+/// The address of the local stub resolver of systemd-resolved
+const LOOPBACK_STUB: IpAddr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 53));
+
+/// The name servers to use when the system configuration has none that are usable
+///
+/// The system configuration has no usable name servers if it can't be read, lists none, or only
+///  lists IPv6 link-local addresses without the scope, i.e. the interface, needed to reach them.
+///  This happens on misconfigured hosts, or in containers before DNS is set up. Falling back to
+///  public resolvers sends the queries outside of the local network, so this is up to the caller.
+#[derive(Clone, Debug, Default)]
+pub enum FallbackPolicy {
+    /// Fail with an error, this is the default
+    #[default]
+    Error,
+    /// Use the name servers of this configuration, e.g. [`ResolverConfig::cloudflare`]
+    PublicResolvers(ResolverConfig),
+    /// Try the local stub resolver on `127.0.0.53` first, then the name servers of this configuration
+    LoopbackThenPublic(ResolverConfig),
+}
+
+impl FallbackPolicy {
+    /// Applies the policy to the result of reading the system configuration
+    ///
+    /// A configuration with usable name servers is returned unchanged. Otherwise the name servers
+    ///  are replaced according to the policy, the domain, search list and options of the system
+    ///  are kept if they could be read.
+    pub fn apply(
+        &self,
+        system_conf: ResolveResult<(ResolverConfig, ResolverOpts)>,
+    ) -> ResolveResult<(ResolverConfig, ResolverOpts)> {
+        let (public, system_conf) = match (self, system_conf) {
+            (_, Ok((config, options))) if has_usable_name_servers(&config) => {
+                return Ok((config, options))
+            }
+            (Self::Error, Ok(_)) => {
+                return Err(ResolveErrorKind::Message(
+                    "no usable name servers in the system configuration",
+                )
+                .into())
+            }
+            (Self::Error, Err(e)) => return Err(e),
+            (Self::PublicResolvers(public) | Self::LoopbackThenPublic(public), system_conf) => {
+                (public, system_conf)
+            }
+        };
+
+        let (domain, search, mut options) = match system_conf {
+            Ok((config, options)) => {
+                warn!("no usable name servers in the system configuration");
+                (config.domain().cloned(), config.search().to_vec(), options)
+            }
+            Err(e) => {
+                warn!("failed to read the system configuration: {e}");
+                (None, vec![], ResolverOpts::default())
+            }
+        };
+
+        let mut name_servers = NameServerConfigGroup::new();
+        if let Self::LoopbackThenPublic(_) = self {
+            warn!("falling back to the local stub resolver on {LOOPBACK_STUB}");
+
+            // the stub must be tried first
+            options.server_ordering_strategy = ServerOrderingStrategy::UserProvidedOrder;
+            name_servers = NameServerConfigGroup::from_ips_clear(&[LOOPBACK_STUB], 53, true);
+        }
+
+        warn!(
+            "falling back to the name servers: {:?}",
+            public.name_servers()
+        );
+        name_servers.merge(NameServerConfigGroup::from(public.name_servers().to_vec()));
+
+        Ok((
+            ResolverConfig::from_parts(domain, search, name_servers),
+            options,
+        ))
+    }
+}
+
+/// Reads the system configuration, and applies the fallback `policy` if it has no usable name servers
+#[cfg(any(unix, target_os = "windows"))]
+pub fn read_system_conf_with_fallback(
+    policy: &FallbackPolicy,
+) -> ResolveResult<(ResolverConfig, ResolverOpts)> {
+    policy.apply(super::read_system_conf())
+}
+
+fn has_usable_name_servers(config: &ResolverConfig) -> bool {
+    config
+        .name_servers()
+        .iter()
+        .any(|name_server| is_reachable(name_server.socket_addr))
+}
+
+/// IPv6 link-local addresses can only be reached with the scope of their interface
+fn is_reachable(addr: SocketAddr) -> bool {
+    match addr {
+        SocketAddr::V4(_) => true,
+        SocketAddr::V6(addr) => {
+            let is_link_local = addr.ip().segments()[0] & 0xffc0 == 0xfe80;
+            !is_link_local || addr.scope_id() != 0
+        }
+    }
+}
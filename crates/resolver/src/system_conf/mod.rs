@@ -12,6 +12,21 @@
 //!  system, e.g. most Unixes have this written to `/etc/resolv.conf`
 #![allow(missing_docs, unused_extern_crates)]
 
+#[cfg(feature = "system-config")]
+mod fallback;
+
+#[cfg(feature = "system-config")]
+#[cfg_attr(docsrs, doc(cfg(feature = "system-config")))]
+pub use self::fallback::FallbackPolicy;
+
+#[cfg(any(unix, target_os = "windows"))]
+#[cfg(feature = "system-config")]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "system-config", any(unix, target_os = "windows"))))
+)]
+pub use self::fallback::read_system_conf_with_fallback;
+
 #[cfg(unix)]
 #[cfg(feature = "system-config")]
 mod unix;
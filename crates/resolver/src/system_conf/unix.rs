@@ -14,12 +14,13 @@
 use std::fs::File;
 use std::io;
 use std::io::Read;
-use std::net::SocketAddr;
+use std::net::{SocketAddr, SocketAddrV6};
 use std::path::Path;
 use std::str::FromStr;
 use std::time::Duration;
 
-use resolv_conf;
+use resolv_conf::{self, ScopedIp};
+use tracing::warn;
 
 use crate::config::{NameServerConfig, Protocol, ResolverConfig, ResolverOpts};
 use crate::error::ResolveResult;
@@ -65,8 +66,9 @@ fn into_resolver_config(
     // nameservers
     let mut nameservers = Vec::<NameServerConfig>::with_capacity(parsed_config.nameservers.len());
     for ip in &parsed_config.nameservers {
+        let socket_addr = name_server_addr(ip);
         nameservers.push(NameServerConfig {
-            socket_addr: SocketAddr::new(ip.into(), DEFAULT_PORT),
+            socket_addr,
             protocol: Protocol::Udp,
             tls_dns_name: None,
             trust_negative_responses: false,
@@ -75,7 +77,7 @@ fn into_resolver_config(
             bind_addr: None,
         });
         nameservers.push(NameServerConfig {
-            socket_addr: SocketAddr::new(ip.into(), DEFAULT_PORT),
+            socket_addr,
             protocol: Protocol::Tcp,
             tls_dns_name: None,
             trust_negative_responses: false,
@@ -85,7 +87,7 @@ fn into_resolver_config(
         });
     }
     if nameservers.is_empty() {
-        tracing::warn!("no nameservers found in config");
+        warn!("no nameservers found in config");
     }
 
     // search
@@ -116,9 +118,38 @@ fn into_resolver_config(
     Ok((config, options))
 }
 
+/// The address of a name server, IPv6 addresses keep their scope, e.g. `fe80::1%eth0`
+fn name_server_addr(ip: &ScopedIp) -> SocketAddr {
+    match ip {
+        ScopedIp::V4(ip) => SocketAddr::new((*ip).into(), DEFAULT_PORT),
+        ScopedIp::V6(ip, scope) => {
+            let scope_id = scope.as_deref().map_or(0, |scope| {
+                scope_id(scope).unwrap_or_else(|| {
+                    warn!("unknown interface {scope} of nameserver {ip}");
+                    0
+                })
+            });
+            SocketAddr::V6(SocketAddrV6::new(*ip, DEFAULT_PORT, 0, scope_id))
+        }
+    }
+}
+
+/// Returns the index of the interface of a scope, which is either the index or the interface name
+fn scope_id(scope: &str) -> Option<u32> {
+    if let Ok(index) = scope.parse() {
+        return Some(index);
+    }
+
+    // interface names can only be resolved on Linux, the scope only contains alphanumerics
+    let index = std::fs::read_to_string(Path::new("/sys/class/net").join(scope).join("ifindex"));
+    index.ok()?.trim().parse().ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::ServerOrderingStrategy;
+    use crate::system_conf::FallbackPolicy;
     use proto::rr::Name;
     use std::env;
     use std::net::*;
@@ -225,4 +256,85 @@ mod tests {
         read_resolv_conf(format!("{}/resolv.conf-macos", tests_dir())).expect("macos failed");
         read_resolv_conf(format!("{}/resolv.conf-linux", tests_dir())).expect("linux failed");
     }
+
+    #[test]
+    fn test_scoped_link_local() {
+        let parsed = parse_resolv_conf("nameserver fe80::1%2").expect("failed");
+        let addr = SocketAddrV6::new(Ipv6Addr::from_str("fe80::1").unwrap(), 53, 0, 2);
+        assert_eq!(parsed.0.name_servers()[0].socket_addr, SocketAddr::V6(addr));
+
+        // without a known interface the address has no scope, and can't be reached
+        let parsed = parse_resolv_conf("nameserver fe80::1%nosuchif0").expect("failed");
+        assert_eq!(
+            parsed.0.name_servers()[0].socket_addr.to_string(),
+            "[fe80::1]:53"
+        );
+    }
+
+    fn read_with_fallback(
+        file: &str,
+        policy: &FallbackPolicy,
+    ) -> ResolveResult<(ResolverConfig, ResolverOpts)> {
+        policy.apply(read_resolv_conf(format!("{}/{file}", tests_dir())))
+    }
+
+    fn fallback_policies() -> [FallbackPolicy; 3] {
+        [
+            FallbackPolicy::Error,
+            FallbackPolicy::PublicResolvers(ResolverConfig::cloudflare()),
+            FallbackPolicy::LoopbackThenPublic(ResolverConfig::cloudflare()),
+        ]
+    }
+
+    #[test]
+    fn test_fallback_usable_name_servers() {
+        for file in ["resolv.conf-simple", "resolv.conf-scoped-link-local"] {
+            let system = read_resolv_conf(format!("{}/{file}", tests_dir())).expect("failed");
+            for policy in fallback_policies() {
+                let (config, options) = read_with_fallback(file, &policy).expect("failed");
+                assert_eq!(config, system.0, "{file} with {policy:?}");
+                assert_eq!(options, system.1, "{file} with {policy:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_fallback_no_usable_name_servers() {
+        let cloudflare = ResolverConfig::cloudflare();
+        let stub = SocketAddr::from(([127, 0, 0, 53], 53));
+
+        for file in [
+            "resolv.conf-empty",
+            "resolv.conf-link-local",
+            "does-not-exist",
+        ] {
+            let [error, public, loopback] = fallback_policies();
+
+            assert!(read_with_fallback(file, &error).is_err(), "{file}");
+
+            let (config, options) = read_with_fallback(file, &public).expect("failed");
+            assert_eq!(config.name_servers(), cloudflare.name_servers(), "{file}");
+            assert_eq!(
+                options.server_ordering_strategy,
+                ServerOrderingStrategy::default()
+            );
+
+            let (config, options) = read_with_fallback(file, &loopback).expect("failed");
+            assert_eq!(config.name_servers()[0].socket_addr, stub, "{file}");
+            assert_eq!(config.name_servers()[1].socket_addr, stub, "{file}");
+            assert_eq!(&config.name_servers()[2..], cloudflare.name_servers());
+            assert_eq!(
+                options.server_ordering_strategy,
+                ServerOrderingStrategy::UserProvidedOrder
+            );
+        }
+
+        // the rest of the system configuration is kept
+        let (config, _) = read_with_fallback(
+            "resolv.conf-link-local",
+            &FallbackPolicy::PublicResolvers(cloudflare),
+        )
+        .expect("failed");
+        assert_eq!(config.search(), &[Name::from_str("example.com").unwrap()]);
+    }
 }
@@ -0,0 +1,283 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Two tier admission of queries, shedding expensive work before cheap answers under overload
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// The cost of answering a query, decided before the query is looked up
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WorkClass {
+    /// The answer is local, from the records of a zone or from a cache
+    Cheap,
+    /// The answer needs queries to other name servers, e.g. recursion or forwarding on a cache
+    ///  miss, and possibly validation
+    Expensive,
+}
+
+/// The response to a query which is shed under overload
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShedResponse {
+    /// SERVFAIL with the Extended DNS Error "Not Ready", clients are expected to retry
+    #[default]
+    ServFail,
+    /// REFUSED, clients are expected to try another server
+    Refused,
+}
+
+/// The limits of one tier of the admission of queries, see [`AdmissionConfig`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TierLimits {
+    /// The maximum number of queries of the tier looked up at the same time
+    pub max_concurrent: usize,
+    /// The maximum number of queries of the tier waiting to be looked up, further queries are shed
+    pub max_queued: usize,
+}
+
+/// The configuration of the two tier admission of queries, see [`Catalog::with_admission`]
+///
+/// [`Catalog::with_admission`]: crate::authority::Catalog::with_admission
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdmissionConfig {
+    /// The limits of the queries answered locally
+    pub cheap: TierLimits,
+    /// The limits of the queries which need other name servers
+    pub expensive: TierLimits,
+    /// The response to shed queries
+    pub shed_response: ShedResponse,
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        Self {
+            cheap: TierLimits {
+                max_concurrent: 1024,
+                max_queued: 4096,
+            },
+            expensive: TierLimits {
+                max_concurrent: 256,
+                max_queued: 512,
+            },
+            shed_response: ShedResponse::default(),
+        }
+    }
+}
+
+/// The counters of one tier of the admission, see [`AdmissionStats`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TierStats {
+    /// Number of queries currently looked up
+    pub running: usize,
+    /// Number of queries currently waiting to be looked up
+    pub queued: usize,
+    /// Number of queries admitted since the start
+    pub admitted: u64,
+    /// Number of queries shed since the start
+    pub shed: u64,
+}
+
+/// The counters of the two tier admission of queries
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AdmissionStats {
+    /// The queries answered locally
+    pub cheap: TierStats,
+    /// The queries which need other name servers
+    pub expensive: TierStats,
+}
+
+/// Admits queries on two tiers with separate concurrency limits and bounded queues
+///
+/// Expensive queries are shed first: once cheap queries have to wait, no more expensive queries
+///  are queued, so that the lookups of cheap queries are never stuck behind slow upstreams.
+pub(crate) struct Admission {
+    cheap: Tier,
+    expensive: Tier,
+    shed_response: ShedResponse,
+}
+
+impl Admission {
+    pub(crate) fn new(config: AdmissionConfig) -> Self {
+        Self {
+            cheap: Tier::new(config.cheap),
+            expensive: Tier::new(config.expensive),
+            shed_response: config.shed_response,
+        }
+    }
+
+    pub(crate) fn shed_response(&self) -> ShedResponse {
+        self.shed_response
+    }
+
+    /// Waits for a slot to look up a query of `class`, returns None if the query must be shed
+    ///
+    /// The slot is released when the returned permit is dropped.
+    pub(crate) async fn admit(&self, class: WorkClass) -> Option<SemaphorePermit<'_>> {
+        let tier = match class {
+            WorkClass::Cheap => &self.cheap,
+            WorkClass::Expensive => &self.expensive,
+        };
+
+        if let Ok(permit) = tier.permits.try_acquire() {
+            tier.admitted.fetch_add(1, Ordering::Relaxed);
+            return Some(permit);
+        }
+
+        let cheap_waiting = self.cheap.queued.load(Ordering::Relaxed) > 0;
+        if class == WorkClass::Expensive && cheap_waiting {
+            tier.shed.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+
+        let queued = tier.queued.fetch_add(1, Ordering::Relaxed);
+        let _queued = Queued(&tier.queued);
+        if queued >= tier.limits.max_queued {
+            tier.shed.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+
+        // the semaphore is never closed
+        let permit = tier.permits.acquire().await.ok()?;
+        tier.admitted.fetch_add(1, Ordering::Relaxed);
+        Some(permit)
+    }
+
+    pub(crate) fn stats(&self) -> AdmissionStats {
+        AdmissionStats {
+            cheap: self.cheap.stats(),
+            expensive: self.expensive.stats(),
+        }
+    }
+}
+
+struct Tier {
+    limits: TierLimits,
+    permits: Semaphore,
+    queued: AtomicUsize,
+    admitted: AtomicU64,
+    shed: AtomicU64,
+}
+
+impl Tier {
+    fn new(limits: TierLimits) -> Self {
+        Self {
+            limits,
+            permits: Semaphore::new(limits.max_concurrent),
+            queued: AtomicUsize::new(0),
+            admitted: AtomicU64::new(0),
+            shed: AtomicU64::new(0),
+        }
+    }
+
+    fn stats(&self) -> TierStats {
+        TierStats {
+            running: self
+                .limits
+                .max_concurrent
+                .saturating_sub(self.permits.available_permits()),
+            queued: self.queued.load(Ordering::Relaxed),
+            admitted: self.admitted.load(Ordering::Relaxed),
+            shed: self.shed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Counts a waiting query until it is dropped, also when the request is cancelled
+struct Queued<'a>(&'a AtomicUsize);
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures_util::FutureExt;
+
+    use super::*;
+
+    fn admission(max_concurrent: usize, max_queued: usize) -> Admission {
+        let limits = TierLimits {
+            max_concurrent,
+            max_queued,
+        };
+        Admission::new(AdmissionConfig {
+            cheap: limits,
+            expensive: limits,
+            shed_response: ShedResponse::ServFail,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_sheds_above_queue_limit() {
+        let admission = Arc::new(admission(1, 1));
+
+        let running = admission.admit(WorkClass::Expensive).await.unwrap();
+        let waiting = {
+            let admission = admission.clone();
+            tokio::spawn(async move { admission.admit(WorkClass::Expensive).await.is_some() })
+        };
+        while admission.stats().expensive.queued == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        assert!(admission.admit(WorkClass::Expensive).await.is_none());
+        // the tiers are independent
+        assert!(admission.admit(WorkClass::Cheap).await.is_some());
+
+        drop(running);
+        assert!(waiting.await.unwrap());
+
+        let stats = admission.stats();
+        assert_eq!(stats.expensive.admitted, 2);
+        assert_eq!(stats.expensive.shed, 1);
+        assert_eq!(stats.expensive.queued, 0);
+        assert_eq!(stats.cheap.admitted, 1);
+        assert_eq!(stats.cheap.shed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_sheds_expensive_while_cheap_waits() {
+        let admission = admission(1, 8);
+
+        let cheap = admission.admit(WorkClass::Cheap).await.unwrap();
+        let mut waiting = Box::pin(admission.admit(WorkClass::Cheap));
+        assert!((&mut waiting).now_or_never().is_none());
+        assert_eq!(admission.stats().cheap.queued, 1);
+
+        // an expensive query is still admitted while it doesn't have to wait
+        let expensive = admission.admit(WorkClass::Expensive).await.unwrap();
+        assert!(admission.admit(WorkClass::Expensive).await.is_none());
+
+        drop(cheap);
+        assert!(waiting.await.is_some());
+        drop(expensive);
+
+        let stats = admission.stats();
+        assert_eq!(stats.cheap.shed, 0);
+        assert_eq!(stats.expensive.shed, 1);
+        assert_eq!(stats.expensive.running, 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_query_leaves_queue() {
+        let admission = admission(1, 1);
+
+        let running = admission.admit(WorkClass::Cheap).await.unwrap();
+        let mut waiting = Box::pin(admission.admit(WorkClass::Cheap));
+        assert!((&mut waiting).now_or_never().is_none());
+        drop(waiting);
+
+        assert_eq!(admission.stats().cheap.queued, 0);
+        drop(running);
+        assert!(admission.admit(WorkClass::Cheap).await.is_some());
+    }
+}
@@ -7,6 +7,8 @@
 
 //! All authority related types
 
+use std::{io, time::Duration};
+
 use cfg_if::cfg_if;
 
 #[cfg(feature = "dnssec")]
@@ -14,9 +16,14 @@ use crate::proto::rr::{
     dnssec::{rdata::key::KEY, DnsSecResult, SigSigner, SupportedAlgorithms},
     Name,
 };
+#[cfg(feature = "dnssec")]
+use crate::store::in_memory::SignatureTiming;
 use crate::{
-    authority::{LookupError, MessageRequest, UpdateResult, ZoneType},
-    proto::rr::{LowerName, RecordSet, RecordType, RrsetRecords},
+    authority::{LookupError, LookupObject, MessageRequest, UpdateResult, WorkClass, ZoneType},
+    proto::{
+        error::{ProtoError, ProtoResult},
+        rr::{LowerName, Record, RecordSet, RecordType, RrsetRecords},
+    },
     server::RequestInfo,
 };
 
@@ -148,6 +155,19 @@ pub trait Authority: Send + Sync {
         lookup_options: LookupOptions,
     ) -> Result<Self::Lookup, LookupError>;
 
+    /// The cost of answering the query with [`Self::search`], see [`WorkClass`]
+    ///
+    /// This is called for every query before it is admitted, so it must be fast and must not look
+    ///  up the query. The default is [`WorkClass::Cheap`], for zones answered from local records.
+    fn work_class(
+        &self,
+        request_info: &RequestInfo<'_>,
+        lookup_options: LookupOptions,
+    ) -> WorkClass {
+        let _ = (request_info, lookup_options);
+        WorkClass::Cheap
+    }
+
     /// Get the NS, NameServer, record for the zone
     async fn ns(&self, lookup_options: LookupOptions) -> Result<Self::Lookup, LookupError> {
         self.lookup(self.origin(), RecordType::NS, lookup_options)
@@ -182,6 +202,55 @@ pub trait Authority: Send + Sync {
         self.lookup(self.origin(), RecordType::SOA, lookup_options)
             .await
     }
+
+    /// Returns the SOA record at the apex of the zone, without its RRSIGs
+    ///
+    /// This is for code operating on the zone, which needs e.g. the serial or the timing
+    ///  parameters. The default looks up the SOA with [`Self::soa`], authorities holding the
+    ///  records themselves can return it directly.
+    async fn zone_apex(&self) -> ProtoResult<Record>
+    where
+        Self::Lookup: LookupObject,
+    {
+        let lookup = self
+            .soa()
+            .await
+            .map_err(|e| ProtoError::from(format!("no SOA for {}: {e}", self.origin())))?;
+        let soa = lookup
+            .iter()
+            .find(|record| record.record_type() == RecordType::SOA)
+            .cloned();
+        soa.ok_or_else(|| format!("no SOA for {}", self.origin()).into())
+    }
+
+    /// Reload the zone data from its source, e.g. the zone file on disk
+    ///
+    /// If the new data can not be loaded, the existing zone data must be left untouched.
+    ///
+    /// # Return value
+    ///
+    /// `Ok(true)` if the zone was reloaded, `Ok(false)` if this zone has no source to reload from.
+    async fn reload_zone(&self) -> Result<bool, String> {
+        Ok(false)
+    }
+
+    /// Writes the entries of the record cache of this zone as newline delimited JSON
+    ///
+    /// Returns the number of entries written. Zones answered from local records have no cache,
+    ///  forwarded and recursive zones write the entries of their resolver cache.
+    fn dump_cache(&self, writer: &mut (dyn io::Write + Send)) -> io::Result<usize> {
+        let _ = writer;
+        Ok(0)
+    }
+
+    /// The time remaining until the zone expires, reported in the EXPIRE EDNS option of
+    ///  [RFC 7314](https://tools.ietf.org/html/rfc7314)
+    ///
+    /// `None` reports the expire time of the SOA, which is what a primary does. A secondary
+    ///  returns the time remaining since its last successful refresh instead.
+    async fn remaining_expire(&self) -> Option<Duration> {
+        None
+    }
 }
 
 /// Extension to Authority to allow for DNSSEC features
@@ -197,4 +266,15 @@ pub trait DnssecAuthority: Authority {
 
     /// Sign the zone for DNSSEC
     async fn secure_zone(&self) -> DnsSecResult<()>;
+
+    /// Sets the timing of the signatures created from now on
+    async fn set_signature_timing(&self, timing: SignatureTiming) -> DnsSecResult<()>;
+
+    /// Signs again all RRsets with a signature expiring within the refresh window of the
+    ///  signature timing
+    ///
+    /// # Return value
+    ///
+    /// The number of RRsets signed again
+    async fn resign_expiring(&self) -> DnsSecResult<usize>;
 }
@@ -7,13 +7,19 @@
 
 //! All authority related types
 
-use std::sync::Arc;
+use std::{io, sync::Arc, time::Duration};
 
 use tracing::debug;
 
 use crate::{
-    authority::{Authority, LookupError, LookupOptions, MessageRequest, UpdateResult, ZoneType},
-    proto::rr::{LowerName, Record, RecordType},
+    authority::{
+        Authority, LookupError, LookupOptions, MessageRequest, UpdateResult, WorkClass, ZoneType,
+    },
+    proto::{
+        error::ProtoResult,
+        rr::{rdata::opt::ExtendedError, LowerName, Record, RecordType},
+        xfer::UpstreamMetadata,
+    },
     server::RequestInfo,
 };
 
@@ -73,6 +79,13 @@ pub trait AuthorityObject: Send + Sync {
         lookup_options: LookupOptions,
     ) -> Result<Box<dyn LookupObject>, LookupError>;
 
+    /// The cost of answering the query, see [`Authority::work_class`]
+    fn work_class(
+        &self,
+        request_info: &RequestInfo<'_>,
+        lookup_options: LookupOptions,
+    ) -> WorkClass;
+
     /// Get the NS, NameServer, record for the zone
     async fn ns(
         &self,
@@ -113,6 +126,41 @@ pub trait AuthorityObject: Send + Sync {
         self.lookup(self.origin(), RecordType::SOA, lookup_options)
             .await
     }
+
+    /// Returns the SOA record at the apex of the zone, see [`Authority::zone_apex`]
+    async fn zone_apex(&self) -> ProtoResult<Record>;
+
+    /// Reload the zone data from its source, e.g. the zone file on disk
+    ///
+    /// If the new data can not be loaded, the existing zone data must be left untouched.
+    ///
+    /// # Return value
+    ///
+    /// `Ok(true)` if the zone was reloaded, `Ok(false)` if this zone has no source to reload from.
+    async fn reload_zone(&self) -> Result<bool, String>;
+
+    /// The time remaining until the zone expires, see [`Authority::remaining_expire`]
+    async fn remaining_expire(&self) -> Option<Duration>;
+
+    /// Writes the entries of the record cache of this zone, see [`Authority::dump_cache`]
+    fn dump_cache(&self, writer: &mut (dyn io::Write + Send)) -> io::Result<usize>;
+
+    /// The value of the EXPIRE EDNS option in responses from this zone, in seconds
+    ///
+    /// This is the remaining expire time of the zone if the authority tracks it, otherwise the
+    ///  expire time of the SOA.
+    async fn expire_option(&self) -> Option<u32> {
+        if let Some(remaining) = self.remaining_expire().await {
+            return Some(u32::try_from(remaining.as_secs()).unwrap_or(u32::MAX));
+        }
+
+        let soa = self.soa().await.ok()?;
+        let expire = soa
+            .iter()
+            .find_map(|record| record.data().as_soa())?
+            .expire();
+        Some(expire.unsigned_abs())
+    }
 }
 
 #[async_trait::async_trait]
@@ -192,6 +240,15 @@ where
         lookup.map(|l| Box::new(l) as Box<dyn LookupObject>)
     }
 
+    /// The cost of answering the query
+    fn work_class(
+        &self,
+        request_info: &RequestInfo<'_>,
+        lookup_options: LookupOptions,
+    ) -> WorkClass {
+        Authority::work_class(self.as_ref(), request_info, lookup_options)
+    }
+
     /// Return the NSEC records based on the given name
     ///
     /// # Arguments
@@ -207,6 +264,26 @@ where
         let lookup = Authority::get_nsec_records(self.as_ref(), name, lookup_options).await;
         lookup.map(|l| Box::new(l) as Box<dyn LookupObject>)
     }
+
+    /// Returns the SOA record at the apex of the zone
+    async fn zone_apex(&self) -> ProtoResult<Record> {
+        Authority::zone_apex(self.as_ref()).await
+    }
+
+    /// Reload the zone data from its source, e.g. the zone file on disk
+    async fn reload_zone(&self) -> Result<bool, String> {
+        Authority::reload_zone(self.as_ref()).await
+    }
+
+    /// The time remaining until the zone expires
+    async fn remaining_expire(&self) -> Option<Duration> {
+        Authority::remaining_expire(self.as_ref()).await
+    }
+
+    /// Writes the entries of the record cache of this zone
+    fn dump_cache(&self, writer: &mut (dyn io::Write + Send)) -> io::Result<usize> {
+        Authority::dump_cache(self.as_ref(), writer)
+    }
 }
 
 /// An Object Safe Lookup for Authority
@@ -221,6 +298,34 @@ pub trait LookupObject: Send {
     ///
     /// it is acceptable for this to return None after the first call.
     fn take_additionals(&mut self) -> Option<Box<dyn LookupObject>>;
+
+    /// What happened upstream while answering a forwarded query, `None` for local data
+    fn upstream(&self) -> Option<&UpstreamInfo> {
+        None
+    }
+}
+
+/// What happened upstream while answering a forwarded query
+///
+/// The extended errors are added to the response sent to the client, the metadata is logged with
+///  the request, see [`ResponseInfo::upstream`](crate::server::ResponseInfo::upstream).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct UpstreamInfo {
+    /// How the upstream response was received, `None` if the answer came from a cache
+    pub metadata: Option<UpstreamMetadata>,
+    /// The extended DNS errors to pass on to the client
+    pub extended_errors: Vec<ExtendedError>,
+}
+
+impl UpstreamInfo {
+    /// Information about an upstream exchange, see the fields of the same name
+    pub fn new(metadata: Option<UpstreamMetadata>, extended_errors: Vec<ExtendedError>) -> Self {
+        Self {
+            metadata,
+            extended_errors,
+        }
+    }
 }
 
 /// A lookup that returns no records
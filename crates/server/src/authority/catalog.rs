@@ -8,23 +8,29 @@
 // TODO, I've implemented this as a separate entity from the cache, but I wonder if the cache
 //  should be the only "front-end" for lookups, where if that misses, then we go to the catalog
 //  then, if requested, do a recursive lookup... i.e. the catalog would only point to files.
-use std::{borrow::Borrow, collections::HashMap, future::Future, io};
+use std::{
+    borrow::Borrow, collections::HashMap, future::Future, io, iter, net::SocketAddr, sync::Arc,
+};
 
 use cfg_if::cfg_if;
+use tokio::sync::{broadcast, SemaphorePermit};
 use tracing::{debug, error, info, trace, warn};
 
 #[cfg(feature = "dnssec")]
-use crate::proto::rr::{
-    dnssec::{Algorithm, SupportedAlgorithms},
-    rdata::opt::{EdnsCode, EdnsOption},
-};
+use crate::proto::rr::dnssec::{Algorithm, SupportedAlgorithms};
 use crate::{
     authority::{
-        AuthLookup, AuthorityObject, EmptyLookup, LookupError, LookupObject, LookupOptions,
-        MessageResponse, MessageResponseBuilder, ZoneType,
+        admission::Admission, load_shedding::LoadShedding, AdmissionConfig, AdmissionStats,
+        AuthLookup, AuthorityObject, Block, DnssecRewritePolicy, EmptyLookup, LookupError,
+        LookupObject, LookupOptions, MessageResponse, MessageResponseBuilder, PolicyFilter,
+        PushSubscriptionManager, RewriteRule, RewriteRules, ShedResponse, UpdateRequest,
+        UpstreamInfo, Verdict, ZoneType,
+    },
+    proto::op::{DsoMessage, DsoTlv, Edns, Header, LowerQuery, MessageType, OpCode, ResponseCode},
+    proto::rr::{
+        rdata::opt::{EdnsCode, EdnsOption, ExtendedError},
+        LowerName, Name, Record, RecordType,
     },
-    proto::op::{Edns, Header, LowerQuery, MessageType, OpCode, ResponseCode},
-    proto::rr::{LowerName, Record, RecordType},
     server::{Request, RequestHandler, RequestInfo, ResponseHandler, ResponseInfo},
 };
 
@@ -32,8 +38,21 @@ use crate::{
 #[derive(Default)]
 pub struct Catalog {
     authorities: HashMap<LowerName, Box<dyn AuthorityObject>>,
+    push: Arc<PushSubscriptionManager>,
+    rewrite: RewriteRules,
+    policy: Option<PolicyFilter>,
+    load_shedding: Option<LoadShedding>,
+    admission: Option<Admission>,
+    ttl_scale: HashMap<LowerName, f32>,
 }
 
+/// The Extended DNS Error info code "Other Error", [RFC 8914](https://tools.ietf.org/html/rfc8914)
+const EDE_OTHER: u16 = 0;
+/// The Extended DNS Error info code "Not Ready", [RFC 8914](https://tools.ietf.org/html/rfc8914)
+const EDE_NOT_READY: u16 = 14;
+/// The largest factor the TTLs of a zone can be scaled by
+const MAX_TTL_SCALE_FACTOR: f32 = 10.0;
+
 #[allow(unused_mut, unused_variables)]
 async fn send_response<'a, R: ResponseHandler>(
     response_edns: Option<Edns>,
@@ -70,6 +89,57 @@ async fn send_response<'a, R: ResponseHandler>(
     response_handle.send_response(response).await
 }
 
+/// Builds the EDNS of the response to the request, or answers requests with an unsupported
+///  EDNS version with BADVERS
+async fn response_edns<R: ResponseHandler>(
+    request: &Request,
+    response_handle: &mut R,
+) -> Result<Option<Edns>, ResponseInfo> {
+    // check if it's edns
+    let Some(req_edns) = request.edns() else {
+        return Ok(None);
+    };
+
+    let mut response = MessageResponseBuilder::new(Some(request.raw_query()));
+    let mut response_header = Header::response_from_request(request.header());
+
+    let mut resp_edns: Edns = Edns::new();
+
+    // check our version against the request
+    // TODO: what version are we?
+    let our_version = 0;
+    resp_edns.set_dnssec_ok(true);
+    resp_edns.set_max_payload(req_edns.max_payload().max(512));
+    resp_edns.set_version(our_version);
+
+    if req_edns.version() > our_version {
+        warn!(
+            "request edns version greater than {}: {}",
+            our_version,
+            req_edns.version()
+        );
+        response_header.set_response_code(ResponseCode::BADVERS);
+        resp_edns.set_rcode_high(ResponseCode::BADVERS.high());
+        response.edns(resp_edns);
+
+        // TODO: should ResponseHandle consume self?
+        let result = response_handle
+            .send_response(response.build_no_records(response_header))
+            .await;
+
+        // couldn't handle the request
+        return Err(match result {
+            Err(e) => {
+                error!("request error: {}", e);
+                ResponseInfo::serve_failed()
+            }
+            Ok(info) => info,
+        });
+    }
+
+    Ok(Some(resp_edns))
+}
+
 #[async_trait::async_trait]
 impl RequestHandler for Catalog {
     /// Determines what needs to happen given the type of request, i.e. Query or Update.
@@ -85,51 +155,10 @@ impl RequestHandler for Catalog {
     ) -> ResponseInfo {
         trace!("request: {:?}", request);
 
-        let response_edns: Option<Edns>;
-
-        // check if it's edns
-        if let Some(req_edns) = request.edns() {
-            let mut response = MessageResponseBuilder::new(Some(request.raw_query()));
-            let mut response_header = Header::response_from_request(request.header());
-
-            let mut resp_edns: Edns = Edns::new();
-
-            // check our version against the request
-            // TODO: what version are we?
-            let our_version = 0;
-            resp_edns.set_dnssec_ok(true);
-            resp_edns.set_max_payload(req_edns.max_payload().max(512));
-            resp_edns.set_version(our_version);
-
-            if req_edns.version() > our_version {
-                warn!(
-                    "request edns version greater than {}: {}",
-                    our_version,
-                    req_edns.version()
-                );
-                response_header.set_response_code(ResponseCode::BADVERS);
-                resp_edns.set_rcode_high(ResponseCode::BADVERS.high());
-                response.edns(resp_edns);
-
-                // TODO: should ResponseHandle consume self?
-                let result = response_handle
-                    .send_response(response.build_no_records(response_header))
-                    .await;
-
-                // couldn't handle the request
-                return match result {
-                    Err(e) => {
-                        error!("request error: {}", e);
-                        ResponseInfo::serve_failed()
-                    }
-                    Ok(info) => info,
-                };
-            }
-
-            response_edns = Some(resp_edns);
-        } else {
-            response_edns = None;
-        }
+        let response_edns = match response_edns(request, &mut response_handle).await {
+            Ok(response_edns) => response_edns,
+            Err(info) => return info,
+        };
 
         let result = match request.message_type() {
             // TODO think about threading query lookups for multiple lookups, this could be a huge improvement
@@ -172,6 +201,115 @@ impl RequestHandler for Catalog {
             Ok(info) => info,
         }
     }
+
+    /// Applies the dynamic update to the authority of the zone, if it accepts updates
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - the update to perform
+    /// * `response_handle` - sink for the response message to be sent
+    async fn handle_update<R: ResponseHandler>(
+        &self,
+        request: &Request,
+        mut response_handle: R,
+    ) -> ResponseInfo {
+        trace!("update: {:?}", request);
+
+        let response_edns = match response_edns(request, &mut response_handle).await {
+            Ok(response_edns) => response_edns,
+            Err(info) => return info,
+        };
+
+        debug!("update received: {}", request.id());
+        match self.update(request, response_edns, response_handle).await {
+            Err(e) => {
+                error!("update failed: {}", e);
+                ResponseInfo::serve_failed()
+            }
+            Ok(info) => info,
+        }
+    }
+
+    /// Handles the DNS Push Notifications SUBSCRIBE and UNSUBSCRIBE requests
+    ///
+    /// Subscriptions are only accepted for zones in the catalog, the matching changes are then
+    ///  sent as PUSH messages until the client unsubscribes or the connection is closed.
+    async fn handle_dso<R: ResponseHandler>(
+        &self,
+        request: DsoMessage,
+        src: SocketAddr,
+        mut response_handle: R,
+    ) -> ResponseInfo {
+        let mut response = DsoMessage::response_from_request(request.header());
+        let mut subscription = None;
+
+        let response_code = match request.tlvs().first() {
+            Some(DsoTlv::Subscribe(query)) => {
+                let query = LowerQuery::from(query.clone());
+                if let Some(authority) = self.find(query.name()) {
+                    // subscribe before the lookup, so that no change is missed
+                    match self
+                        .push
+                        .subscribe(src, request.header().id(), query.clone())
+                    {
+                        Some(receiver) => {
+                            let records = match authority
+                                .lookup(query.name(), query.query_type(), LookupOptions::default())
+                                .await
+                            {
+                                Ok(lookup) => lookup.iter().cloned().collect(),
+                                Err(_) => Vec::new(),
+                            };
+
+                            subscription = Some((receiver, records));
+                            ResponseCode::NoError
+                        }
+                        None => ResponseCode::FormErr,
+                    }
+                } else {
+                    ResponseCode::NotAuth
+                }
+            }
+            // UNSUBSCRIBE is a unidirectional message, it is never responded to
+            Some(DsoTlv::Unsubscribe(id)) => {
+                self.push.unsubscribe(src, *id);
+                return ResponseInfo::from(*request.header());
+            }
+            // DSOTYPENI, the primary TLV is not implemented
+            _ => ResponseCode::Unknown(11),
+        };
+
+        response.header_mut().set_response_code(response_code);
+        let info = match response_handle.send_dso(&response).await {
+            Ok(info) => info,
+            Err(e) => {
+                error!("request error: {}", e);
+                self.push.remove_client(src);
+                return ResponseInfo::serve_failed();
+            }
+        };
+
+        if let Some((receiver, records)) = subscription {
+            // the current records are pushed right after the response to the SUBSCRIBE
+            if !records.is_empty() {
+                let push = DsoMessage::new(0, MessageType::Query, vec![DsoTlv::Push(records)]);
+                if let Err(e) = response_handle.send_dso(&push).await {
+                    debug!("push subscriber {} is gone: {}", src, e);
+                    self.push.remove_client(src);
+                    return info;
+                }
+            }
+
+            tokio::spawn(push_changes(
+                self.push.clone(),
+                src,
+                receiver,
+                response_handle,
+            ));
+        }
+
+        info
+    }
 }
 
 impl Catalog {
@@ -179,9 +317,123 @@ impl Catalog {
     pub fn new() -> Self {
         Self {
             authorities: HashMap::new(),
+            push: Arc::new(PushSubscriptionManager::new()),
+            rewrite: RewriteRules::default(),
+            policy: None,
+            load_shedding: None,
+            admission: None,
+            ttl_scale: HashMap::new(),
         }
     }
 
+    /// Sheds the queries to each zone above `max_qps` queries per second
+    ///
+    /// The rate is tracked per zone with a token bucket that holds up to one second worth of
+    ///  queries. Queries exceeding it are answered with SERVFAIL and an Extended DNS Error of
+    ///  "Other". This protects individual zones from becoming DDoS targets, independent of the
+    ///  rate limits per client.
+    pub fn with_load_shedding(mut self, max_qps: u32) -> Self {
+        self.load_shedding = Some(LoadShedding::new(max_qps));
+        self
+    }
+
+    /// Counts a query to the zone, returns false if the query must be shed
+    fn admit(&self, request: &Request, authority: &dyn AuthorityObject) -> bool {
+        let load_shedding = match &self.load_shedding {
+            Some(load_shedding) => load_shedding,
+            None => return true,
+        };
+
+        if load_shedding.admit(authority.origin()) {
+            return true;
+        }
+
+        warn!(
+            "request: {} shed, zone {} exceeds {} queries per second",
+            request.id(),
+            authority.origin(),
+            load_shedding.max_qps()
+        );
+        false
+    }
+
+    /// Admits queries on two tiers, so that cheap answers are not starved by expensive lookups
+    ///
+    /// Each query is classified with [`AuthorityObject::work_class`] before it is looked up:
+    ///  answers from the records of a zone or from a cache are cheap, queries which need other
+    ///  name servers are expensive. Each class has its own concurrency limit and bounded queue.
+    ///  Under overload expensive queries are shed first, they are not queued while cheap queries
+    ///  wait. Shed queries are answered with the [`ShedResponse`] of the configuration.
+    pub fn with_admission(mut self, config: AdmissionConfig) -> Self {
+        self.admission = Some(Admission::new(config));
+        self
+    }
+
+    /// The queue depths and shed counts of the two tier admission, if configured
+    pub fn admission_stats(&self) -> Option<AdmissionStats> {
+        self.admission.as_ref().map(Admission::stats)
+    }
+
+    /// Waits for a slot to look up the query in the authority, see [`Self::with_admission`]
+    ///
+    /// The slot is held until the returned permit is dropped. Returns the response for the query
+    ///  if it must be shed.
+    async fn admit_work(
+        &self,
+        request: &Request,
+        request_info: &RequestInfo<'_>,
+        lookup_options: LookupOptions,
+        authority: &dyn AuthorityObject,
+    ) -> Result<Option<SemaphorePermit<'_>>, ShedResponse> {
+        let admission = match &self.admission {
+            Some(admission) => admission,
+            None => return Ok(None),
+        };
+
+        let class = authority.work_class(request_info, lookup_options);
+        match admission.admit(class).await {
+            Some(permit) => Ok(Some(permit)),
+            None => {
+                warn!(
+                    "request: {} shed, {:?} work for zone {} exceeds the admission limits",
+                    request.id(),
+                    class,
+                    authority.origin()
+                );
+                Err(admission.shed_response())
+            }
+        }
+    }
+
+    /// Set the DNS firewall, which decides on each query before it is looked up in the catalog
+    pub fn set_policy_filter(&mut self, policy: PolicyFilter) {
+        self.policy = Some(policy);
+    }
+
+    /// The DNS firewall, if configured
+    pub fn policy_filter(&self) -> Option<&PolicyFilter> {
+        self.policy.as_ref()
+    }
+
+    /// Set the rules for rewriting the names of queries before they are looked up in the catalog
+    ///
+    /// The owner names of the answer and authority records are rewritten back to the requested
+    ///  names before the response is sent.
+    pub fn set_rewrite_rules(&mut self, rewrite: RewriteRules) {
+        self.rewrite = rewrite;
+    }
+
+    /// The rules for rewriting the names of queries
+    pub fn rewrite_rules(&self) -> &RewriteRules {
+        &self.rewrite
+    }
+
+    /// The DNS Push Notifications subscriptions of the clients, these are notified of all
+    ///  successful updates
+    pub fn push_subscriptions(&self) -> &PushSubscriptionManager {
+        &self.push
+    }
+
     /// Insert or update a zone authority
     ///
     /// # Arguments
@@ -194,9 +446,50 @@ impl Catalog {
 
     /// Remove a zone from the catalog
     pub fn remove(&mut self, name: &LowerName) -> Option<Box<dyn AuthorityObject>> {
+        self.ttl_scale.remove(name);
         self.authorities.remove(name)
     }
 
+    /// Write a summary of all zones as newline-delimited JSON, see [`ZoneSummary`]
+    ///
+    /// Returns the number of zones written.
+    ///
+    /// [`ZoneSummary`]: crate::support::ZoneSummary
+    pub async fn dump_summary(&self, writer: &mut (dyn io::Write + Send)) -> io::Result<usize> {
+        let authorities = self
+            .authorities
+            .values()
+            .map(|authority| &**authority)
+            .collect::<Vec<_>>();
+
+        crate::support::write_zone_summaries(&authorities, writer).await
+    }
+
+    /// Scales the TTLs of all records returned from the zone by `factor`
+    ///
+    /// This allows operators to shorten or lengthen the TTLs of a zone served from multiple
+    ///  anycast sites, without modifying the zone files. The factor is clamped to `[0.0, 10.0]`,
+    ///  e.g. a factor of 0.1 reduces a TTL of 3600 seconds to 360 seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - zone name, e.g. example.com.
+    /// * `factor` - the factor the TTLs are multiplied with, `NaN` is ignored
+    pub fn set_ttl_scale_factor(&mut self, name: LowerName, factor: f32) {
+        if factor.is_nan() {
+            warn!("ignoring TTL scale factor of NaN for zone: {}", name);
+            return;
+        }
+
+        self.ttl_scale
+            .insert(name, factor.clamp(0.0, MAX_TTL_SCALE_FACTOR));
+    }
+
+    /// The factor the TTLs of the zone are scaled by, if configured
+    pub fn ttl_scale_factor(&self, name: &LowerName) -> Option<f32> {
+        self.ttl_scale.get(name).copied()
+    }
+
     /// Update the zone given the Update request.
     ///
     /// [RFC 2136](https://tools.ietf.org/html/rfc2136), DNS Update, April 1997
@@ -295,7 +588,13 @@ impl Catalog {
                         let update_result = authority.update(update).await;
                         match update_result {
                             // successful update
-                            Ok(..) => ResponseCode::NoError,
+                            Ok(updated) => {
+                                if updated {
+                                    self.push
+                                        .notify(update.zone().query_class(), update.updates());
+                                }
+                                ResponseCode::NoError
+                            }
                             Err(response_code) => response_code,
                         }
                     }
@@ -346,12 +645,65 @@ impl Catalog {
         response_handle: R,
     ) -> ResponseInfo {
         let request_info = request.request_info();
+        let verdict = match &self.policy {
+            Some(policy) => {
+                policy
+                    .verdict(
+                        request_info.src.ip(),
+                        request_info.query.name(),
+                        request_info.query.query_type(),
+                    )
+                    .await
+            }
+            None => Verdict::Defer,
+        };
+
+        match verdict {
+            Verdict::Allow => (),
+            Verdict::Block(block) => {
+                return send_blocked_response(block, request, response_edns, response_handle).await
+            }
+            Verdict::Rewrite(name) => {
+                let rule = RewriteRule::exact(request_info.query.original().name().clone(), name);
+                let name = rule.to().clone();
+                return self
+                    .lookup_rewritten(&rule, name, request, response_edns, response_handle)
+                    .await;
+            }
+            Verdict::Defer => {
+                if let Some((rule, name)) =
+                    self.rewrite.rewrite(request_info.query.original().name())
+                {
+                    return self
+                        .lookup_rewritten(rule, name, request, response_edns, response_handle)
+                        .await;
+                }
+            }
+        }
+
         let authority = self.find(request_info.query.name());
 
         if let Some(authority) = authority {
+            if !self.admit(request, authority) {
+                return send_shed_response(request, response_edns, response_handle).await;
+            }
+
+            let lookup_options = lookup_options_for_edns(request.edns());
+            let _permit = match self
+                .admit_work(request, &request_info, lookup_options, authority)
+                .await
+            {
+                Ok(permit) => permit,
+                Err(shed) => {
+                    return send_overload_response(shed, request, response_edns, response_handle)
+                        .await
+                }
+            };
+
             lookup(
                 request_info,
                 authority,
+                self.ttl_scale_factor(authority.origin()),
                 request,
                 response_edns
                     .as_ref()
@@ -380,6 +732,144 @@ impl Catalog {
         }
     }
 
+    /// Looks up the rewritten name of the query, and rewrites the names in the response back
+    async fn lookup_rewritten<R: ResponseHandler>(
+        &self,
+        rule: &RewriteRule,
+        name: Name,
+        request: &Request,
+        mut response_edns: Option<Edns>,
+        response_handle: R,
+    ) -> ResponseInfo {
+        let request_info = request.request_info();
+        debug!(
+            "request: {} rewrote {} to {}",
+            request.id(),
+            request_info.query.name(),
+            name
+        );
+
+        let mut query = request_info.query.original().clone();
+        query.set_name(name);
+        let query = LowerQuery::from(query);
+        let request_info = RequestInfo::new(
+            request_info.src,
+            request_info.protocol,
+            request_info.header,
+            &query,
+        );
+
+        let dnssec_policy = self.rewrite.dnssec_policy();
+        let mut edns = request.edns().cloned();
+        if dnssec_policy == DnssecRewritePolicy::StripDnssec {
+            if let Some(edns) = edns.as_mut() {
+                edns.set_dnssec_ok(false);
+            }
+            if let Some(response_edns) = response_edns.as_mut() {
+                response_edns.set_dnssec_ok(false);
+            }
+        }
+
+        let response = MessageResponseBuilder::new(Some(request.raw_query()));
+        let result = match self.find(query.name()) {
+            Some(authority) if !self.admit(request, authority) => {
+                return send_shed_response(request, response_edns, response_handle).await;
+            }
+            Some(authority) => {
+                let lookup_options = lookup_options_for_edns(edns.as_ref());
+                let _permit = match self
+                    .admit_work(request, &request_info, lookup_options, authority)
+                    .await
+                {
+                    Ok(permit) => permit,
+                    Err(shed) => {
+                        return send_overload_response(
+                            shed,
+                            request,
+                            response_edns,
+                            response_handle,
+                        )
+                        .await
+                    }
+                };
+
+                let (response_header, mut sections) = build_response(
+                    authority,
+                    request_info,
+                    request.id(),
+                    request.header(),
+                    &query,
+                    edns.as_ref(),
+                )
+                .await;
+                if let Some(factor) = self.ttl_scale_factor(authority.origin()) {
+                    sections.scale_ttls(factor);
+                }
+
+                let is_signed = sections
+                    .answers
+                    .iter()
+                    .chain(sections.ns.iter())
+                    .chain(sections.soa.iter())
+                    .any(|record| record.record_type().is_dnssec());
+
+                // the signatures would not validate for the rewritten names
+                if is_signed && dnssec_policy == DnssecRewritePolicy::Refuse {
+                    warn!(
+                        "request: {} refusing to rewrite signed response for {}",
+                        request.id(),
+                        query.name()
+                    );
+                    send_response(
+                        response_edns,
+                        response.error_msg(request.header(), ResponseCode::Refused),
+                        response_handle,
+                    )
+                    .await
+                } else {
+                    let reverse = |lookup: &dyn LookupObject| {
+                        lookup
+                            .iter()
+                            .map(|record| rule.reverse_record(record))
+                            .collect::<Vec<_>>()
+                    };
+                    let answers = reverse(&*sections.answers);
+                    let ns = reverse(&*sections.ns);
+                    let soa = reverse(&*sections.soa);
+
+                    send_response(
+                        response_edns,
+                        response.build(
+                            response_header,
+                            answers.iter(),
+                            ns.iter(),
+                            soa.iter(),
+                            sections.additionals.iter(),
+                        ),
+                        response_handle,
+                    )
+                    .await
+                }
+            }
+            None => {
+                send_response(
+                    response_edns,
+                    response.error_msg(request.header(), ResponseCode::Refused),
+                    response_handle,
+                )
+                .await
+            }
+        };
+
+        match result {
+            Err(e) => {
+                error!("error sending response: {}", e);
+                ResponseInfo::serve_failed()
+            }
+            Ok(i) => i,
+        }
+    }
+
     /// Recursively searches the catalog for a matching authority
     pub fn find(&self, name: &LowerName) -> Option<&(dyn AuthorityObject + 'static)> {
         debug!("searching authorities for: {}", name);
@@ -397,9 +887,149 @@ impl Catalog {
     }
 }
 
+/// Answers a query blocked by the policy engine, without looking it up
+async fn send_blocked_response<R: ResponseHandler>(
+    block: Block,
+    request: &Request,
+    response_edns: Option<Edns>,
+    response_handle: R,
+) -> ResponseInfo {
+    let response = MessageResponseBuilder::new(Some(request.raw_query()));
+    let result = match block {
+        Block::ResponseCode(response_code) => {
+            send_response(
+                response_edns,
+                response.error_msg(request.header(), response_code),
+                response_handle,
+            )
+            .await
+        }
+        Block::Answer(mut answers) => {
+            let name = request.request_info().query.original().name().clone();
+            for answer in &mut answers {
+                answer.set_name(name.clone());
+            }
+
+            send_response(
+                response_edns,
+                response.build(
+                    Header::response_from_request(request.header()),
+                    answers.iter(),
+                    iter::empty(),
+                    iter::empty(),
+                    iter::empty(),
+                ),
+                response_handle,
+            )
+            .await
+        }
+    };
+
+    match result {
+        Err(e) => {
+            error!("error sending response: {}", e);
+            ResponseInfo::serve_failed()
+        }
+        Ok(i) => i,
+    }
+}
+
+/// Answers a query to an overloaded zone with SERVFAIL, without looking it up
+async fn send_shed_response<R: ResponseHandler>(
+    request: &Request,
+    mut response_edns: Option<Edns>,
+    response_handle: R,
+) -> ResponseInfo {
+    if let Some(edns) = response_edns.as_mut() {
+        let ede = ExtendedError::new(EDE_OTHER, "zone overloaded");
+        edns.options_mut().insert(EdnsOption::EDE(ede));
+    }
+
+    let response = MessageResponseBuilder::new(Some(request.raw_query()));
+    let result = send_response(
+        response_edns,
+        response.error_msg(request.header(), ResponseCode::ServFail),
+        response_handle,
+    )
+    .await;
+
+    match result {
+        Err(e) => {
+            error!("error sending response: {}", e);
+            ResponseInfo::serve_failed()
+        }
+        Ok(i) => i,
+    }
+}
+
+/// Answers a query shed by the admission of the catalog, without looking it up
+async fn send_overload_response<R: ResponseHandler>(
+    shed: ShedResponse,
+    request: &Request,
+    mut response_edns: Option<Edns>,
+    response_handle: R,
+) -> ResponseInfo {
+    let response_code = match shed {
+        ShedResponse::ServFail => {
+            if let Some(edns) = response_edns.as_mut() {
+                let ede = ExtendedError::new(EDE_NOT_READY, "server overloaded");
+                edns.options_mut().insert(EdnsOption::EDE(ede));
+            }
+            ResponseCode::ServFail
+        }
+        ShedResponse::Refused => ResponseCode::Refused,
+    };
+
+    let response = MessageResponseBuilder::new(Some(request.raw_query()));
+    let result = send_response(
+        response_edns,
+        response.error_msg(request.header(), response_code),
+        response_handle,
+    )
+    .await;
+
+    match result {
+        Err(e) => {
+            error!("error sending response: {}", e);
+            ResponseInfo::serve_failed()
+        }
+        Ok(i) => i,
+    }
+}
+
+/// Sends the changes of a subscription to the client, until it is removed or the client is gone
+async fn push_changes<R: ResponseHandler>(
+    push: Arc<PushSubscriptionManager>,
+    src: SocketAddr,
+    mut receiver: broadcast::Receiver<Record>,
+    mut response_handle: R,
+) {
+    loop {
+        let record = match receiver.recv().await {
+            Ok(record) => record,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!(
+                    "push subscriber {} lagged, skipped {} changes",
+                    src, skipped
+                );
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        let message = DsoMessage::new(0, MessageType::Query, vec![DsoTlv::Push(vec![record])]);
+        if let Err(e) = response_handle.send_dso(&message).await {
+            debug!("push subscriber {} is gone: {}", src, e);
+            push.remove_client(src);
+            break;
+        }
+    }
+}
+
 async fn lookup<'a, R: ResponseHandler + Unpin>(
     request_info: RequestInfo<'_>,
     authority: &dyn AuthorityObject,
+    ttl_scale_factor: Option<f32>,
     request: &Request,
     response_edns: Option<Edns>,
     response_handle: R,
@@ -411,7 +1041,7 @@ async fn lookup<'a, R: ResponseHandler + Unpin>(
         authority.origin()
     );
 
-    let (response_header, sections) = build_response(
+    let (response_header, mut sections) = build_response(
         authority,
         request_info,
         request.id(),
@@ -420,6 +1050,30 @@ async fn lookup<'a, R: ResponseHandler + Unpin>(
         request.edns(),
     )
     .await;
+    if let Some(factor) = ttl_scale_factor {
+        sections.scale_ttls(factor);
+    }
+
+    // RFC 7314, the EXPIRE option is answered with the remaining expire time of the zone
+    let mut response_edns = response_edns;
+    if let Some(edns) = response_edns.as_mut() {
+        let expire_requested = request
+            .edns()
+            .map_or(false, |edns| edns.option(EdnsCode::Expire).is_some());
+        if expire_requested {
+            if let Some(expire) = authority.expire_option().await {
+                edns.options_mut().insert(EdnsOption::Expire(Some(expire)));
+            }
+        }
+    }
+
+    // RFC 8914, the extended errors of the upstream response are passed on to the client
+    let upstream = sections.upstream.take().unwrap_or_default();
+    if let Some(edns) = response_edns.as_mut() {
+        for extended_error in upstream.extended_errors {
+            edns.options_mut().insert(EdnsOption::EDE(extended_error));
+        }
+    }
 
     let response = MessageResponseBuilder::new(Some(request.raw_query())).build(
         response_header,
@@ -429,14 +1083,14 @@ async fn lookup<'a, R: ResponseHandler + Unpin>(
         sections.additionals.iter(),
     );
 
-    let result = send_response(response_edns.clone(), response, response_handle.clone()).await;
+    let result = send_response(response_edns, response, response_handle.clone()).await;
 
     match result {
         Err(e) => {
             error!("error sending response: {}", e);
             ResponseInfo::serve_failed()
         }
-        Ok(i) => i,
+        Ok(i) => i.with_upstream(upstream.metadata),
     }
 }
 
@@ -502,7 +1156,8 @@ async fn build_response(
             .await
         }
         ZoneType::Forward | ZoneType::Hint => {
-            send_forwarded_response(future, request_header, &mut response_header).await
+            send_forwarded_response(future, request_header, &mut response_header, lookup_options)
+                .await
         }
     };
 
@@ -536,6 +1191,7 @@ async fn send_authoritative_response(
                 ns: Box::<AuthLookup>::default(),
                 soa: Box::<AuthLookup>::default(),
                 additionals: Box::<AuthLookup>::default(),
+                upstream: None,
             };
         }
         Err(e) => {
@@ -610,17 +1266,22 @@ async fn send_authoritative_response(
         ns: ns.unwrap_or_else(|| Box::<AuthLookup>::default()),
         soa: soa.unwrap_or_else(|| Box::<AuthLookup>::default()),
         additionals,
+        upstream: None,
     }
 }
 
+#[cfg_attr(not(feature = "dnssec"), allow(unused_variables))]
 async fn send_forwarded_response(
     future: impl Future<Output = Result<Box<dyn LookupObject>, LookupError>>,
     request_header: &Header,
     response_header: &mut Header,
+    lookup_options: LookupOptions,
 ) -> LookupSections {
     response_header.set_recursion_available(true);
     response_header.set_authoritative(false);
 
+    let mut upstream = None;
+
     // Don't perform the recursive query if this is disabled...
     let answers = if !request_header.recursion_desired() {
         // cancel the future??
@@ -636,13 +1297,32 @@ async fn send_forwarded_response(
     } else {
         match future.await {
             Err(e) => {
-                if e.is_nx_domain() {
-                    response_header.set_response_code(ResponseCode::NXDomain);
+                if let Some(response_code) = e.response_code() {
+                    response_header.set_response_code(response_code);
                 }
                 debug!("error resolving: {}", e);
+                if let LookupError::Upstream(_, info) = e {
+                    upstream = Some(*info);
+                }
                 Box::new(EmptyLookup)
             }
-            Ok(rsp) => rsp,
+            Ok(rsp) => {
+                upstream = rsp.upstream().cloned();
+
+                // RFC 6840, AD is only set for requests with DO or AD, if all answers are secure
+                #[cfg(feature = "dnssec")]
+                if lookup_options.is_dnssec() || request_header.authentic_data() {
+                    let mut answers = rsp
+                        .iter()
+                        .filter(|record| record.record_type() != RecordType::RRSIG)
+                        .peekable();
+                    let is_secure = answers.peek().is_some()
+                        && answers.all(|record| record.proof().is_secure());
+                    response_header.set_authentic_data(is_secure);
+                }
+
+                rsp
+            }
         }
     };
 
@@ -651,6 +1331,7 @@ async fn send_forwarded_response(
         ns: Box::<AuthLookup>::default(),
         soa: Box::<AuthLookup>::default(),
         additionals: Box::<AuthLookup>::default(),
+        upstream,
     }
 }
 
@@ -659,4 +1340,51 @@ struct LookupSections {
     ns: Box<dyn LookupObject>,
     soa: Box<dyn LookupObject>,
     additionals: Box<dyn LookupObject>,
+    /// What happened upstream, only for forwarded queries
+    upstream: Option<UpstreamInfo>,
+}
+
+impl LookupSections {
+    /// Multiplies the TTLs of the records in all sections by `factor`
+    fn scale_ttls(&mut self, factor: f32) {
+        for section in [
+            &mut self.answers,
+            &mut self.ns,
+            &mut self.soa,
+            &mut self.additionals,
+        ] {
+            let records = section
+                .iter()
+                .map(|record| {
+                    let mut record = record.clone();
+                    record.set_ttl(scale_ttl(record.ttl(), factor));
+                    record
+                })
+                .collect();
+            *section = Box::new(ScaledLookup(records));
+        }
+    }
+}
+
+/// Multiplies the TTL by `factor`, rounded to the nearest second
+fn scale_ttl(ttl: u32, factor: f32) -> u32 {
+    // the cast saturates at u32::MAX
+    (f64::from(ttl) * f64::from(factor)).round() as u32
+}
+
+/// The records of a section with scaled TTLs
+struct ScaledLookup(Vec<Record>);
+
+impl LookupObject for ScaledLookup {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Record> + Send + 'a> {
+        Box::new(self.0.iter())
+    }
+
+    fn take_additionals(&mut self) -> Option<Box<dyn LookupObject>> {
+        None
+    }
 }
@@ -10,6 +10,7 @@ use std::io;
 use enum_as_inner::EnumAsInner;
 use thiserror::Error;
 
+use crate::authority::UpstreamInfo;
 use crate::proto::op::ResponseCode;
 #[cfg(feature = "hickory-resolver")]
 use crate::resolver::error::ResolveError;
@@ -39,6 +40,9 @@ pub enum LookupError {
     /// An underlying IO error occurred
     #[error("io error: {0}")]
     Io(io::Error),
+    /// The upstream name server of a forwarded query answered with an error
+    #[error("upstream error: {0}")]
+    Upstream(ResponseCode, Box<UpstreamInfo>),
 }
 
 impl LookupError {
@@ -49,12 +53,22 @@ impl LookupError {
 
     /// This is a non-existent domain name
     pub fn is_nx_domain(&self) -> bool {
-        matches!(*self, Self::ResponseCode(ResponseCode::NXDomain))
+        self.response_code() == Some(ResponseCode::NXDomain)
     }
 
     /// This is a non-existent domain name
     pub fn is_refused(&self) -> bool {
-        matches!(*self, Self::ResponseCode(ResponseCode::Refused))
+        self.response_code() == Some(ResponseCode::Refused)
+    }
+
+    /// The response code to answer with, if the error carries one
+    pub fn response_code(&self) -> Option<ResponseCode> {
+        match *self {
+            Self::ResponseCode(response_code) | Self::Upstream(response_code, _) => {
+                Some(response_code)
+            }
+            _ => None,
+        }
     }
 }
 
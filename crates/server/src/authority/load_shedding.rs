@@ -0,0 +1,114 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Per zone load shedding, protecting individual zones from becoming DDoS targets
+
+use std::{collections::HashMap, sync::Mutex, time::Instant};
+
+use crate::proto::rr::LowerName;
+
+/// Limits the query rate of each zone with a token bucket per zone name
+///
+/// Each bucket holds up to one second worth of queries, and is refilled at `max_qps` queries per
+///  second. Unlike the per source rate limit, all clients share the budget of a zone.
+pub(crate) struct LoadShedding {
+    max_qps: u32,
+    zones: Mutex<HashMap<LowerName, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    last_update: Instant,
+}
+
+impl LoadShedding {
+    pub(crate) fn new(max_qps: u32) -> Self {
+        Self {
+            max_qps,
+            zones: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The maximum rate of queries per zone
+    pub(crate) fn max_qps(&self) -> u32 {
+        self.max_qps
+    }
+
+    /// Counts a query to `zone`, returns false if it exceeds the rate and must be shed
+    pub(crate) fn admit(&self, zone: &LowerName) -> bool {
+        self.admit_at(zone, Instant::now())
+    }
+
+    fn admit_at(&self, zone: &LowerName, now: Instant) -> bool {
+        let max_qps = f64::from(self.max_qps);
+        let mut zones = self.zones.lock().expect("load shedding lock poisoned");
+
+        let bucket = zones.entry(zone.clone()).or_insert(Bucket {
+            tokens: max_qps,
+            last_update: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.last_update);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * max_qps).min(max_qps);
+        bucket.last_update = now;
+
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+
+        bucket.tokens -= 1.0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use super::*;
+    use crate::proto::rr::Name;
+
+    fn zone(name: &str) -> LowerName {
+        LowerName::from(Name::from_str(name).unwrap())
+    }
+
+    #[test]
+    fn test_sheds_above_max_qps() {
+        let shedding = LoadShedding::new(10);
+        let example = zone("example.com.");
+        let now = Instant::now();
+
+        assert_eq!(
+            (0..20).filter(|_| shedding.admit_at(&example, now)).count(),
+            10
+        );
+
+        // half a second refills half of the bucket
+        let now = now + Duration::from_millis(500);
+        assert_eq!(
+            (0..20).filter(|_| shedding.admit_at(&example, now)).count(),
+            5
+        );
+
+        // the bucket never holds more than one second worth of queries
+        let now = now + Duration::from_secs(60);
+        assert_eq!(
+            (0..20).filter(|_| shedding.admit_at(&example, now)).count(),
+            10
+        );
+    }
+
+    #[test]
+    fn test_zones_are_independent() {
+        let shedding = LoadShedding::new(1);
+        let now = Instant::now();
+
+        assert!(shedding.admit_at(&zone("example.com."), now));
+        assert!(!shedding.admit_at(&zone("example.com."), now));
+        assert!(shedding.admit_at(&zone("example.org."), now));
+    }
+}
@@ -18,7 +18,7 @@ use crate::proto::{
 };
 
 /// A Message which captures the data from an inbound request
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct MessageRequest {
     header: Header,
     query: WireQuery,
@@ -152,6 +152,11 @@ impl MessageRequest {
         self.edns.as_ref()
     }
 
+    /// Returns the mutable EDNS record, see `edns`
+    pub(crate) fn edns_mut(&mut self) -> Option<&mut Edns> {
+        self.edns.as_mut()
+    }
+
     /// Any SIG0 records for signed messages
     pub fn sig0(&self) -> &[Record] {
         &self.sig0
@@ -299,7 +304,7 @@ impl Queries {
 }
 
 /// A query with the original bytes stored from the query
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub(crate) struct WireQuery {
     query: LowerQuery,
     original: Box<[u8]>,
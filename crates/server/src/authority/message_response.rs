@@ -102,11 +102,15 @@ where
     }
 
     /// Consumes self, and emits to the encoder.
+    ///
+    /// The returned [`ResponseInfo`] describes the message as it was emitted, e.g. with the record
+    ///  counts after truncation.
     pub fn destructive_emit(mut self, encoder: &mut BinEncoder<'_>) -> ProtoResult<ResponseInfo> {
         // soa records are part of the nameserver section
         let mut name_servers = self.name_servers.chain(self.soa);
+        let start = encoder.offset();
 
-        message::emit_message_parts(
+        let header = message::emit_message_parts(
             &self.header,
             &mut EmptyOrQueries::from(self.query),
             &mut self.answers,
@@ -115,8 +119,13 @@ where
             self.edns.as_ref(),
             &self.sig0,
             encoder,
-        )
-        .map(Into::into)
+        )?;
+
+        Ok(ResponseInfo::new(
+            header,
+            encoder.offset() - start,
+            self.edns.as_ref().map(Edns::max_payload),
+        ))
     }
 }
 
@@ -257,6 +266,8 @@ mod tests {
     use std::str::FromStr;
 
     use crate::proto::op::{Header, Message};
+    #[cfg(feature = "dnssec")]
+    use crate::proto::rr::RecordType;
     use crate::proto::rr::{DNSClass, Name, RData, Record};
     use crate::proto::serialize::binary::BinEncoder;
 
@@ -295,7 +306,8 @@ mod tests {
 
         let response = Message::from_vec(&buf).expect("failed to decode");
         assert!(response.header().truncated());
-        assert!(response.answer_count() > 1);
+        // the answers are a single RRset, which is never split
+        assert_eq!(response.answer_count(), 0);
         // should never have written the name server field...
         assert_eq!(response.name_server_count(), 0);
     }
@@ -334,6 +346,153 @@ mod tests {
         let response = Message::from_vec(&buf).expect("failed to decode");
         assert!(response.header().truncated());
         assert_eq!(response.answer_count(), 0);
-        assert!(response.name_server_count() > 1);
+        // without answers, the authority section is emitted completely or not at all
+        assert_eq!(response.name_server_count(), 0);
+    }
+
+    fn emit_with_max_size(
+        max_size: u16,
+        header: Header,
+        answers: &[Record],
+        name_servers: &[Record],
+        additionals: &[Record],
+    ) -> Message {
+        let mut buf = Vec::with_capacity(512);
+        {
+            let mut encoder = BinEncoder::new(&mut buf);
+            encoder.set_max_size(max_size);
+
+            let message = MessageResponse {
+                header,
+                query: None,
+                answers: answers.iter(),
+                name_servers: name_servers.iter(),
+                soa: iter::empty(),
+                additionals: additionals.iter(),
+                sig0: vec![],
+                edns: None,
+            };
+
+            message
+                .destructive_emit(&mut encoder)
+                .expect("failed to encode");
+        }
+
+        Message::from_vec(&buf).expect("failed to decode")
+    }
+
+    fn a_records(name: &str, count: u8) -> Vec<Record> {
+        (0..count)
+            .map(|i| {
+                Record::from_rdata(
+                    Name::from_str(name).unwrap(),
+                    300,
+                    RData::A(Ipv4Addr::new(192, 0, 2, i).into()),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_additionals_elided_without_truncation() {
+        let answers = a_records("www.example.com.", 2);
+        let additionals = a_records("ns.example.com.", 100);
+
+        let response = emit_with_max_size(512, Header::new(), &answers, &[], &additionals);
+        assert!(!response.header().truncated());
+        assert_eq!(response.answer_count(), 2);
+        // the additionals are a single RRset, which is not split
+        assert_eq!(response.additional_count(), 0);
+    }
+
+    #[cfg(feature = "dnssec")]
+    fn rrsig(name: &Name, type_covered: RecordType) -> Record {
+        use crate::proto::rr::dnssec::{
+            rdata::{DNSSECRData, RRSIG},
+            Algorithm,
+        };
+
+        Record::from_rdata(
+            name.clone(),
+            300,
+            RData::DNSSEC(DNSSECRData::RRSIG(RRSIG::new(
+                type_covered,
+                Algorithm::ECDSAP256SHA256,
+                name.num_labels(),
+                300,
+                0,
+                0,
+                0,
+                Name::from_str("example.com.").unwrap(),
+                vec![0; 400],
+            ))),
+        )
+    }
+
+    #[cfg(feature = "dnssec")]
+    #[test]
+    fn test_signed_answer_not_split() {
+        let mut answers = a_records("www.example.com.", 10);
+        answers.push(rrsig(answers[0].name(), RecordType::A));
+
+        // over UDP the RRset only fits without its signature
+        let response = emit_with_max_size(512, Header::new(), &answers, &[], &[]);
+        assert!(response.header().truncated());
+        assert_eq!(response.answer_count(), 0);
+
+        // over TCP everything fits
+        let response = emit_with_max_size(u16::MAX, Header::new(), &answers, &[], &[]);
+        assert!(!response.header().truncated());
+        assert_eq!(response.answer_count(), 11);
+    }
+
+    #[cfg(feature = "dnssec")]
+    #[test]
+    fn test_negative_proof_not_split() {
+        use crate::proto::rr::{
+            dnssec::rdata::{DNSSECRData, NSEC},
+            rdata::SOA,
+        };
+
+        let origin = Name::from_str("example.com.").unwrap();
+        let soa = Record::from_rdata(
+            origin.clone(),
+            300,
+            RData::SOA(SOA::new(
+                Name::from_str("ns.example.com.").unwrap(),
+                Name::from_str("admin.example.com.").unwrap(),
+                1,
+                3600,
+                600,
+                86400,
+                300,
+            )),
+        );
+        let nsec = Record::from_rdata(
+            origin.clone(),
+            300,
+            RData::DNSSEC(DNSSECRData::NSEC(NSEC::new(
+                Name::from_str("www.example.com.").unwrap(),
+                vec![RecordType::SOA, RecordType::NS],
+            ))),
+        );
+        let name_servers = vec![
+            soa.clone(),
+            rrsig(&origin, RecordType::SOA),
+            nsec,
+            rrsig(&origin, RecordType::NSEC),
+        ];
+
+        let mut header = Header::new();
+        header.set_response_code(ResponseCode::NXDomain);
+
+        let response = emit_with_max_size(512, header, &[], &name_servers, &[]);
+        assert!(response.header().truncated());
+        assert_eq!(response.response_code(), ResponseCode::NXDomain);
+        assert_eq!(response.name_server_count(), 0);
+
+        let response = emit_with_max_size(u16::MAX, header, &[], &name_servers, &[]);
+        assert!(!response.header().truncated());
+        assert_eq!(response.name_server_count(), 4);
     }
 }
@@ -12,25 +12,38 @@ use crate::proto::op::ResponseCode;
 /// Result of an Update operation
 pub type UpdateResult<T> = Result<T, ResponseCode>;
 
+mod admission;
 mod auth_lookup;
 #[allow(clippy::module_inception)]
 mod authority;
 pub(crate) mod authority_object;
 mod catalog;
 mod error;
+mod load_shedding;
 pub(crate) mod message_request;
 mod message_response;
+mod policy;
+mod push;
+mod rewrite;
 mod zone_type;
 
+pub use self::admission::{
+    AdmissionConfig, AdmissionStats, ShedResponse, TierLimits, TierStats, WorkClass,
+};
 pub use self::auth_lookup::{
     AnyRecords, AuthLookup, AuthLookupIter, LookupRecords, LookupRecordsIter,
 };
 pub use self::authority::{Authority, LookupOptions};
-pub use self::authority_object::{AuthorityObject, EmptyLookup, LookupObject};
+pub use self::authority_object::{AuthorityObject, EmptyLookup, LookupObject, UpstreamInfo};
 pub use self::catalog::Catalog;
 pub use self::error::{LookupError, LookupResult};
 pub use self::message_request::{MessageRequest, Queries, UpdateRequest};
 pub use self::message_response::{MessageResponse, MessageResponseBuilder};
+pub use self::policy::{
+    Block, FailMode, PolicyCounters, PolicyEngine, PolicyFilter, PolicyRequest, Verdict,
+};
+pub use self::push::PushSubscriptionManager;
+pub use self::rewrite::{DnssecRewritePolicy, RewriteMatch, RewriteRule, RewriteRules};
 pub use self::zone_type::ZoneType;
 
 #[cfg(feature = "dnssec")]
@@ -0,0 +1,438 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! A DNS firewall, which asks a policy engine for a verdict on each query before it is looked up
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use ipnet::IpNet;
+use tracing::{debug, warn};
+
+use crate::proto::{
+    op::ResponseCode,
+    rr::{LowerName, Name, Record, RecordType},
+};
+
+/// Default time to wait for a verdict of the engine
+const DEFAULT_DECISION_TIMEOUT: Duration = Duration::from_millis(250);
+/// Default time for which a verdict is reused for repeated queries
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(60);
+/// Default maximum number of cached verdicts
+const DEFAULT_CACHE_SIZE: usize = 8192;
+/// Default prefix length of the IPv4 client addresses sharing cached verdicts
+const DEFAULT_IPV4_PREFIX_LEN: u8 = 24;
+/// Default prefix length of the IPv6 client addresses sharing cached verdicts
+const DEFAULT_IPV6_PREFIX_LEN: u8 = 56;
+
+/// The query a verdict is requested for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolicyRequest {
+    /// The address of the client
+    pub src: IpAddr,
+    /// The name of the query
+    pub name: LowerName,
+    /// The type of the query
+    pub query_type: RecordType,
+}
+
+/// How a blocked query is answered
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Block {
+    /// Respond with the response code, e.g. NXDomain or Refused, and no records
+    ResponseCode(ResponseCode),
+    /// Respond with these records, their names are replaced with the name of the query
+    Answer(Vec<Record>),
+}
+
+/// The decision of a policy engine for a query
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// Look up the query as requested, without applying the rewrite rules of the catalog
+    Allow,
+    /// Do not look up the query
+    Block(Block),
+    /// Look up this name instead, the records are sent with the name of the query
+    Rewrite(Name),
+    /// No decision, the query is handled by the rest of the catalog, e.g. the rewrite rules
+    Defer,
+}
+
+/// Decides whether, and how, a query is answered
+///
+/// The engine could be a local model, or a client of an external service. Verdicts are cached by
+///  the [`PolicyFilter`], so the engine is not consulted for repeated queries.
+#[async_trait::async_trait]
+pub trait PolicyEngine: Send + Sync + 'static {
+    /// Returns the verdict for the query, an error is handled according to the [`FailMode`]
+    async fn decide(&self, request: &PolicyRequest) -> Result<Verdict, String>;
+}
+
+/// The verdict used when the engine did not decide in time, or failed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum FailMode {
+    /// The query is allowed
+    #[default]
+    Open,
+    /// The query is blocked with ServFail
+    Closed,
+}
+
+/// Counters of the verdicts of a [`PolicyFilter`]
+#[derive(Debug, Default)]
+pub struct PolicyCounters {
+    allowed: AtomicU64,
+    blocked: AtomicU64,
+    rewritten: AtomicU64,
+    deferred: AtomicU64,
+    cache_hits: AtomicU64,
+    timeouts: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl PolicyCounters {
+    /// Number of allowed queries
+    pub fn allowed(&self) -> u64 {
+        self.allowed.load(Ordering::Relaxed)
+    }
+
+    /// Number of blocked queries
+    pub fn blocked(&self) -> u64 {
+        self.blocked.load(Ordering::Relaxed)
+    }
+
+    /// Number of rewritten queries
+    pub fn rewritten(&self) -> u64 {
+        self.rewritten.load(Ordering::Relaxed)
+    }
+
+    /// Number of queries without a decision
+    pub fn deferred(&self) -> u64 {
+        self.deferred.load(Ordering::Relaxed)
+    }
+
+    /// Number of verdicts taken from the cache, without consulting the engine
+    pub fn cache_hits(&self) -> u64 {
+        self.cache_hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of times the engine did not decide in time
+    pub fn timeouts(&self) -> u64 {
+        self.timeouts.load(Ordering::Relaxed)
+    }
+
+    /// Number of times the engine failed
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    fn count(&self, verdict: &Verdict) {
+        let counter = match verdict {
+            Verdict::Allow => &self.allowed,
+            Verdict::Block(_) => &self.blocked,
+            Verdict::Rewrite(_) => &self.rewritten,
+            Verdict::Defer => &self.deferred,
+        };
+
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Key of a cached verdict, clients in the same network share the verdicts
+type CacheKey = (LowerName, RecordType, IpNet);
+
+/// The decision point of the DNS firewall, consulted by the `Catalog` ahead of any lookup
+pub struct PolicyFilter {
+    engine: Arc<dyn PolicyEngine>,
+    timeout: Duration,
+    fail_mode: FailMode,
+    cache_ttl: Duration,
+    cache_size: usize,
+    ipv4_prefix_len: u8,
+    ipv6_prefix_len: u8,
+    cache: Mutex<HashMap<CacheKey, (Verdict, Instant)>>,
+    counters: PolicyCounters,
+}
+
+impl PolicyFilter {
+    /// Construct a new filter, asking the engine for the verdicts
+    pub fn new(engine: Arc<dyn PolicyEngine>) -> Self {
+        Self {
+            engine,
+            timeout: DEFAULT_DECISION_TIMEOUT,
+            fail_mode: FailMode::default(),
+            cache_ttl: DEFAULT_CACHE_TTL,
+            cache_size: DEFAULT_CACHE_SIZE,
+            ipv4_prefix_len: DEFAULT_IPV4_PREFIX_LEN,
+            ipv6_prefix_len: DEFAULT_IPV6_PREFIX_LEN,
+            cache: Mutex::new(HashMap::new()),
+            counters: PolicyCounters::default(),
+        }
+    }
+
+    /// Set the time to wait for a verdict of the engine, defaults to 250ms
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Set the verdict used when the engine times out or fails, defaults to `FailMode::Open`
+    pub fn with_fail_mode(self, fail_mode: FailMode) -> Self {
+        Self { fail_mode, ..self }
+    }
+
+    /// Set how long, and how many, verdicts are cached, a `cache_size` of 0 disables the cache
+    pub fn with_cache(self, cache_ttl: Duration, cache_size: usize) -> Self {
+        Self {
+            cache_ttl,
+            cache_size,
+            ..self
+        }
+    }
+
+    /// Set the prefix lengths of the client networks sharing cached verdicts, defaults to /24 and /56
+    pub fn with_client_prefix_len(self, ipv4_prefix_len: u8, ipv6_prefix_len: u8) -> Self {
+        Self {
+            ipv4_prefix_len: ipv4_prefix_len.min(32),
+            ipv6_prefix_len: ipv6_prefix_len.min(128),
+            ..self
+        }
+    }
+
+    /// The counters of the verdicts
+    pub fn counters(&self) -> &PolicyCounters {
+        &self.counters
+    }
+
+    /// Returns the verdict for the query, from the cache or the engine
+    pub async fn verdict(&self, src: IpAddr, name: &LowerName, query_type: RecordType) -> Verdict {
+        let key = (name.clone(), query_type, self.client_network(src));
+        if let Some(verdict) = self.cached(&key) {
+            self.counters.cache_hits.fetch_add(1, Ordering::Relaxed);
+            self.counters.count(&verdict);
+            return verdict;
+        }
+
+        let request = PolicyRequest {
+            src,
+            name: name.clone(),
+            query_type,
+        };
+
+        let verdict = match tokio::time::timeout(self.timeout, self.engine.decide(&request)).await {
+            Ok(Ok(verdict)) => {
+                self.insert(key, verdict.clone());
+                verdict
+            }
+            Ok(Err(e)) => {
+                warn!("policy engine failed for {name} {query_type}: {e}");
+                self.counters.errors.fetch_add(1, Ordering::Relaxed);
+                self.fail_verdict()
+            }
+            Err(_) => {
+                warn!("policy engine timed out for {name} {query_type}");
+                self.counters.timeouts.fetch_add(1, Ordering::Relaxed);
+                self.fail_verdict()
+            }
+        };
+
+        debug!("policy verdict for {name} {query_type} from {src}: {verdict:?}");
+        self.counters.count(&verdict);
+        verdict
+    }
+
+    fn fail_verdict(&self) -> Verdict {
+        match self.fail_mode {
+            FailMode::Open => Verdict::Allow,
+            FailMode::Closed => Verdict::Block(Block::ResponseCode(ResponseCode::ServFail)),
+        }
+    }
+
+    fn client_network(&self, src: IpAddr) -> IpNet {
+        let prefix_len = match src {
+            IpAddr::V4(_) => self.ipv4_prefix_len,
+            IpAddr::V6(_) => self.ipv6_prefix_len,
+        };
+
+        IpNet::new(src, prefix_len)
+            .expect("prefix length is bounded")
+            .trunc()
+    }
+
+    fn cached(&self, key: &CacheKey) -> Option<Verdict> {
+        let cache = self.cache.lock().expect("verdict cache poisoned");
+        cache
+            .get(key)
+            .filter(|(_, valid_until)| Instant::now() < *valid_until)
+            .map(|(verdict, _)| verdict.clone())
+    }
+
+    fn insert(&self, key: CacheKey, verdict: Verdict) {
+        if self.cache_size == 0 {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut cache = self.cache.lock().expect("verdict cache poisoned");
+        if cache.len() >= self.cache_size && !cache.contains_key(&key) {
+            cache.retain(|_, (_, valid_until)| now < *valid_until);
+            if cache.len() >= self.cache_size {
+                debug!("verdict cache is full, not caching {}", key.0);
+                return;
+            }
+        }
+
+        cache.insert(key, (verdict, now + self.cache_ttl));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        str::FromStr,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+
+    struct MockEngine {
+        verdict: Verdict,
+        delay: Option<Duration>,
+        calls: AtomicUsize,
+    }
+
+    impl MockEngine {
+        fn new(verdict: Verdict) -> Arc<Self> {
+            Arc::new(Self {
+                verdict,
+                delay: None,
+                calls: AtomicUsize::new(0),
+            })
+        }
+
+        fn slow() -> Arc<Self> {
+            Arc::new(Self {
+                verdict: Verdict::Allow,
+                delay: Some(Duration::from_secs(10)),
+                calls: AtomicUsize::new(0),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::Relaxed)
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl PolicyEngine for MockEngine {
+        async fn decide(&self, _request: &PolicyRequest) -> Result<Verdict, String> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            if let Some(delay) = self.delay {
+                tokio::time::sleep(delay).await;
+            }
+
+            Ok(self.verdict.clone())
+        }
+    }
+
+    fn name() -> LowerName {
+        LowerName::from_str("www.example.com.").unwrap()
+    }
+
+    const SRC: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 1));
+
+    #[tokio::test]
+    async fn test_allow() {
+        let engine = MockEngine::new(Verdict::Allow);
+        let filter = PolicyFilter::new(engine.clone());
+
+        assert_eq!(
+            filter.verdict(SRC, &name(), RecordType::A).await,
+            Verdict::Allow
+        );
+        assert_eq!(engine.calls(), 1);
+        assert_eq!(filter.counters().allowed(), 1);
+        assert_eq!(filter.counters().blocked(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_block_nxdomain() {
+        let block = Verdict::Block(Block::ResponseCode(ResponseCode::NXDomain));
+        let filter = PolicyFilter::new(MockEngine::new(block.clone()));
+
+        assert_eq!(filter.verdict(SRC, &name(), RecordType::A).await, block);
+        assert_eq!(filter.counters().blocked(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_fail_open() {
+        let filter = PolicyFilter::new(MockEngine::slow()).with_timeout(Duration::from_millis(10));
+
+        assert_eq!(
+            filter.verdict(SRC, &name(), RecordType::A).await,
+            Verdict::Allow
+        );
+        assert_eq!(filter.counters().timeouts(), 1);
+        assert_eq!(filter.counters().allowed(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_fail_closed() {
+        let engine = MockEngine::slow();
+        let filter = PolicyFilter::new(engine.clone())
+            .with_timeout(Duration::from_millis(10))
+            .with_fail_mode(FailMode::Closed);
+
+        assert_eq!(
+            filter.verdict(SRC, &name(), RecordType::A).await,
+            Verdict::Block(Block::ResponseCode(ResponseCode::ServFail))
+        );
+        assert_eq!(filter.counters().timeouts(), 1);
+        assert_eq!(filter.counters().blocked(), 1);
+
+        // verdicts of a failed engine are not cached
+        filter.verdict(SRC, &name(), RecordType::A).await;
+        assert_eq!(engine.calls(), 2);
+        assert_eq!(filter.counters().cache_hits(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cache_hits() {
+        let engine = MockEngine::new(Verdict::Defer);
+        let filter = PolicyFilter::new(engine.clone());
+
+        filter.verdict(SRC, &name(), RecordType::A).await;
+        // same client network
+        let neighbour = IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 200));
+        assert_eq!(
+            filter.verdict(neighbour, &name(), RecordType::A).await,
+            Verdict::Defer
+        );
+        assert_eq!(engine.calls(), 1);
+        assert_eq!(filter.counters().cache_hits(), 1);
+        assert_eq!(filter.counters().deferred(), 2);
+
+        // other type and other network are different keys
+        filter.verdict(SRC, &name(), RecordType::AAAA).await;
+        let other = IpAddr::V4(std::net::Ipv4Addr::new(198, 51, 100, 1));
+        filter.verdict(other, &name(), RecordType::A).await;
+        assert_eq!(engine.calls(), 3);
+
+        // an expired verdict is not used
+        let filter = PolicyFilter::new(engine.clone()).with_cache(Duration::ZERO, 16);
+        filter.verdict(SRC, &name(), RecordType::A).await;
+        filter.verdict(SRC, &name(), RecordType::A).await;
+        assert_eq!(engine.calls(), 5);
+        assert_eq!(filter.counters().cache_hits(), 0);
+    }
+}
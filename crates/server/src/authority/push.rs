@@ -0,0 +1,260 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DNS Push Notifications, [RFC 8765](https://tools.ietf.org/html/rfc8765)
+
+use std::{collections::HashMap, net::SocketAddr, sync::Mutex};
+
+use tokio::sync::broadcast;
+use tracing::debug;
+
+use crate::proto::{
+    op::LowerQuery,
+    rr::{DNSClass, LowerName, Record, RecordType},
+};
+
+/// Number of changes buffered for each subscription before the subscriber starts lagging
+const SUBSCRIPTION_BUFFER_SIZE: usize = 64;
+/// TTL of a PUSH record removing a single record
+const DELETE_RECORD_TTL: u32 = 0xFFFF_FFFF;
+/// TTL of a PUSH record removing an RRset, or all RRsets of the name
+const DELETE_RRSET_TTL: u32 = 0xFFFF_FFFE;
+
+/// Tracks the DNS Push Notifications subscriptions of the clients
+///
+/// Each subscription is identified by the client's address, which is unique per connection, and
+///  the subscribed query. Changes to the zones are sent to all subscriptions matching them.
+#[derive(Default)]
+pub struct PushSubscriptionManager {
+    inner: Mutex<Subscriptions>,
+}
+
+#[derive(Default)]
+struct Subscriptions {
+    senders: HashMap<(SocketAddr, LowerQuery), broadcast::Sender<Record>>,
+    /// The SUBSCRIBE message id of each subscription, used by UNSUBSCRIBE
+    ids: HashMap<(SocketAddr, u16), LowerQuery>,
+}
+
+impl PushSubscriptionManager {
+    /// Constructs a new manager without any subscriptions
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribes the client to changes of the records matching the query
+    ///
+    /// Returns `None` if the client already subscribed to the query, or already used the id.
+    pub fn subscribe(
+        &self,
+        client: SocketAddr,
+        id: u16,
+        query: LowerQuery,
+    ) -> Option<broadcast::Receiver<Record>> {
+        let mut inner = self.inner.lock().expect("push subscriptions poisoned");
+        if inner.senders.contains_key(&(client, query.clone()))
+            || inner.ids.contains_key(&(client, id))
+        {
+            return None;
+        }
+
+        debug!("push subscription from {client}: {query}");
+        let (sender, receiver) = broadcast::channel(SUBSCRIPTION_BUFFER_SIZE);
+        inner.senders.insert((client, query.clone()), sender);
+        inner.ids.insert((client, id), query);
+
+        Some(receiver)
+    }
+
+    /// Removes the subscription created by the SUBSCRIBE message with the id
+    ///
+    /// Returns `false` if there was no such subscription.
+    pub fn unsubscribe(&self, client: SocketAddr, id: u16) -> bool {
+        let mut inner = self.inner.lock().expect("push subscriptions poisoned");
+        let Some(query) = inner.ids.remove(&(client, id)) else {
+            return false;
+        };
+
+        debug!("push subscription removed for {client}: {query}");
+        inner.senders.remove(&(client, query)).is_some()
+    }
+
+    /// Removes all subscriptions of the client, e.g. once its connection is closed
+    pub fn remove_client(&self, client: SocketAddr) {
+        let mut inner = self.inner.lock().expect("push subscriptions poisoned");
+        inner.senders.retain(|(addr, _), _| *addr != client);
+        inner.ids.retain(|(addr, _), _| *addr != client);
+    }
+
+    /// Returns the number of active subscriptions
+    pub fn len(&self) -> usize {
+        self.inner
+            .lock()
+            .expect("push subscriptions poisoned")
+            .senders
+            .len()
+    }
+
+    /// Returns `true` if there are no active subscriptions
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sends the changes to all subscriptions matching them
+    ///
+    /// The `updates` are in the format of the update section of a DNS Update, [RFC 2136](https://tools.ietf.org/html/rfc2136),
+    ///  and are converted into the PUSH format of [RFC 8765](https://tools.ietf.org/html/rfc8765#section-6.3.1).
+    ///
+    /// # Arguments
+    ///
+    /// * `dns_class` - the class of the updated zone
+    /// * `updates` - the records which were added or deleted
+    ///
+    /// Returns the number of notifications that were sent.
+    pub fn notify<'r>(
+        &self,
+        dns_class: DNSClass,
+        updates: impl IntoIterator<Item = &'r Record>,
+    ) -> usize {
+        let inner = self.inner.lock().expect("push subscriptions poisoned");
+        if inner.senders.is_empty() {
+            return 0;
+        }
+
+        let mut sent = 0;
+        for update in updates {
+            let record = push_record(dns_class, update);
+            let record = &record;
+            let name = LowerName::from(record.name());
+            for ((_, query), sender) in &inner.senders {
+                if !matches(query, &name, record) {
+                    continue;
+                }
+
+                // an error only means that the subscriber is gone, it is cleaned up by its owner
+                if sender.send(record.clone()).is_ok() {
+                    sent += 1;
+                }
+            }
+        }
+
+        sent
+    }
+}
+
+/// Converts a record of an update section to the PUSH format
+fn push_record(dns_class: DNSClass, update: &Record) -> Record {
+    let mut record = update.clone();
+    match update.dns_class() {
+        // delete an RR from an RRset
+        DNSClass::NONE => {
+            record.set_dns_class(dns_class).set_ttl(DELETE_RECORD_TTL);
+        }
+        // delete an RRset, or all RRsets if the type is ANY
+        DNSClass::ANY => {
+            record.set_ttl(DELETE_RRSET_TTL);
+        }
+        // add to an RRset
+        _ => (),
+    }
+
+    record
+}
+
+/// Changes are matched by name, type and class, ANY matches all types or classes
+fn matches(query: &LowerQuery, name: &LowerName, record: &Record) -> bool {
+    fn type_matches(left: RecordType, right: RecordType) -> bool {
+        left == RecordType::ANY || right == RecordType::ANY || left == right
+    }
+
+    fn class_matches(left: DNSClass, right: DNSClass) -> bool {
+        left == DNSClass::ANY || right == DNSClass::ANY || left == right
+    }
+
+    query.name() == name
+        && type_matches(query.query_type(), record.record_type())
+        && class_matches(query.query_class(), record.dns_class())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::proto::{
+        op::Query,
+        rr::{rdata::A, Name, RData},
+    };
+
+    fn query(record_type: RecordType) -> LowerQuery {
+        LowerQuery::query(Query::query(
+            Name::from_str("www.example.com.").unwrap(),
+            record_type,
+        ))
+    }
+
+    #[test]
+    fn test_subscriptions() {
+        let manager = PushSubscriptionManager::new();
+        let client = SocketAddr::from(([127, 0, 0, 1], 5353));
+        let other = SocketAddr::from(([127, 0, 0, 2], 5353));
+
+        let mut a = manager.subscribe(client, 1, query(RecordType::A)).unwrap();
+        let mut any = manager.subscribe(other, 1, query(RecordType::ANY)).unwrap();
+        assert!(manager
+            .subscribe(client, 1, query(RecordType::AAAA))
+            .is_none());
+        assert!(manager.subscribe(client, 2, query(RecordType::A)).is_none());
+        assert_eq!(manager.len(), 2);
+
+        let record = Record::from_rdata(
+            Name::from_str("WWW.example.com.").unwrap(),
+            300,
+            RData::A(A::new(127, 0, 0, 1)),
+        );
+        let other_name = Record::from_rdata(
+            Name::from_str("ftp.example.com.").unwrap(),
+            300,
+            RData::A(A::new(127, 0, 0, 1)),
+        );
+        assert_eq!(manager.notify(DNSClass::IN, [&record, &other_name]), 2);
+        assert_eq!(a.try_recv().unwrap(), record);
+        assert!(a.try_recv().is_err());
+        assert_eq!(any.try_recv().unwrap(), record);
+
+        assert!(manager.unsubscribe(client, 1));
+        assert!(!manager.unsubscribe(client, 1));
+        assert_eq!(
+            a.try_recv(),
+            Err(broadcast::error::TryRecvError::Closed),
+            "the sender should be dropped"
+        );
+        assert_eq!(manager.notify(DNSClass::IN, [&record]), 1);
+        assert_eq!(any.try_recv().unwrap(), record);
+
+        let mut delete = record.clone();
+        delete.set_dns_class(DNSClass::NONE).set_ttl(0);
+        assert_eq!(manager.notify(DNSClass::IN, [&delete]), 1);
+        let pushed = any.try_recv().unwrap();
+        assert_eq!(pushed.dns_class(), DNSClass::IN);
+        assert_eq!(pushed.ttl(), DELETE_RECORD_TTL);
+
+        let mut delete_all = Record::update0(record.name().clone(), 0, RecordType::ANY);
+        delete_all.set_dns_class(DNSClass::ANY);
+        let mut aaaa = manager
+            .subscribe(client, 2, query(RecordType::AAAA))
+            .unwrap();
+        assert_eq!(manager.notify(DNSClass::IN, [&delete_all]), 2);
+        assert_eq!(aaaa.try_recv().unwrap().ttl(), DELETE_RRSET_TTL);
+        assert_eq!(any.try_recv().unwrap().ttl(), DELETE_RRSET_TTL);
+
+        manager.remove_client(client);
+        manager.remove_client(other);
+        assert!(manager.is_empty());
+        assert_eq!(manager.notify(DNSClass::IN, [&record]), 0);
+    }
+}
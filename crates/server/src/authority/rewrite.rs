@@ -0,0 +1,280 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Rules for rewriting the names of queries, and the names of the records sent in response
+
+use serde::Deserialize;
+
+use crate::proto::rr::{rdata::CNAME, Name, RData, Record};
+
+/// How a rewrite rule is matched against the name of a query
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RewriteMatch {
+    /// Only the name itself is rewritten
+    Exact,
+    /// The name and all names below it are rewritten, e.g. `www.old.example.` for `old.example.`
+    Suffix,
+}
+
+/// What to do when a rewritten query is answered with DNSSEC records
+///
+/// Signatures are made over the original owner names, after the names are rewritten these would
+///  fail validation on the client.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum DnssecRewritePolicy {
+    /// Respond with REFUSED if the response would contain DNSSEC records
+    #[default]
+    Refuse,
+    /// Ignore the DO bit of the request, so that no DNSSEC records are looked up
+    StripDnssec,
+}
+
+/// A single rule, rewriting names from one domain to another
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct RewriteRule {
+    match_type: RewriteMatch,
+    from: Name,
+    to: Name,
+    rewrite_cname_targets: bool,
+}
+
+impl RewriteRule {
+    /// Rewrite queries for exactly `from` to `to`
+    pub fn exact(from: Name, to: Name) -> Self {
+        Self::new(RewriteMatch::Exact, from, to)
+    }
+
+    /// Rewrite queries for `from`, and all names below it, to the same names below `to`
+    pub fn suffix(from: Name, to: Name) -> Self {
+        Self::new(RewriteMatch::Suffix, from, to)
+    }
+
+    /// Construct a new rule
+    ///
+    /// # Arguments
+    ///
+    /// * `match_type` - how the name of the query is matched against `from`
+    /// * `from` - the name as requested by the client
+    /// * `to` - the name to lookup in the catalog instead
+    pub fn new(match_type: RewriteMatch, from: Name, to: Name) -> Self {
+        Self {
+            match_type,
+            from,
+            to,
+            rewrite_cname_targets: false,
+        }
+    }
+
+    /// Also rewrite the targets of CNAME records in the response back to the requested domain
+    pub fn with_cname_targets(self, rewrite_cname_targets: bool) -> Self {
+        Self {
+            rewrite_cname_targets,
+            ..self
+        }
+    }
+
+    /// How the name of the query is matched
+    pub fn match_type(&self) -> RewriteMatch {
+        self.match_type
+    }
+
+    /// The name as requested by the client
+    pub fn from(&self) -> &Name {
+        &self.from
+    }
+
+    /// The name to lookup in the catalog instead
+    pub fn to(&self) -> &Name {
+        &self.to
+    }
+
+    /// Returns true if the targets of CNAME records are rewritten as well
+    pub fn rewrite_cname_targets(&self) -> bool {
+        self.rewrite_cname_targets
+    }
+
+    /// Rewrites the name of a query, returns `None` if this rule does not match
+    pub fn rewrite(&self, name: &Name) -> Option<Name> {
+        replace(self.match_type, name, &self.from, &self.to)
+    }
+
+    /// Rewrites a name in the response back to the requested domain, returns `None` if this rule
+    ///  does not match
+    pub fn reverse(&self, name: &Name) -> Option<Name> {
+        replace(self.match_type, name, &self.to, &self.from)
+    }
+
+    /// Returns a copy of the record with the owner name, and optionally the CNAME target, rewritten
+    ///  back to the requested domain
+    pub fn reverse_record(&self, record: &Record) -> Record {
+        let mut record = record.clone();
+        if let Some(name) = self.reverse(record.name()) {
+            record.set_name(name);
+        }
+
+        if self.rewrite_cname_targets {
+            if let RData::CNAME(CNAME(target)) = record.data() {
+                if let Some(target) = self.reverse(target) {
+                    record.set_data(RData::CNAME(CNAME(target)));
+                }
+            }
+        }
+
+        record
+    }
+}
+
+fn replace(match_type: RewriteMatch, name: &Name, from: &Name, to: &Name) -> Option<Name> {
+    match match_type {
+        RewriteMatch::Exact if name == from => Some(to.clone()),
+        RewriteMatch::Suffix if from.zone_of(name) => {
+            let prefix = name.iter().count() - from.iter().count();
+            Name::from_labels(name.iter().take(prefix))
+                .and_then(|prefix| prefix.append_domain(to))
+                .ok()
+        }
+        _ => None,
+    }
+}
+
+/// Ordered set of rewrite rules, the first matching rule is applied to a query
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct RewriteRules {
+    rules: Vec<RewriteRule>,
+    dnssec_policy: DnssecRewritePolicy,
+}
+
+impl RewriteRules {
+    /// Construct an empty set of rules, which do not rewrite any query
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a rule, it is only applied if none of the previous rules match
+    pub fn with_rule(mut self, rule: RewriteRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Set what to do with rewritten queries that are answered with DNSSEC records
+    pub fn with_dnssec_policy(self, dnssec_policy: DnssecRewritePolicy) -> Self {
+        Self {
+            dnssec_policy,
+            ..self
+        }
+    }
+
+    /// The rules in the order they are evaluated
+    pub fn rules(&self) -> &[RewriteRule] {
+        &self.rules
+    }
+
+    /// What to do with rewritten queries that are answered with DNSSEC records
+    pub fn dnssec_policy(&self) -> DnssecRewritePolicy {
+        self.dnssec_policy
+    }
+
+    /// Returns true if there are no rules
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Finds the first matching rule and returns it with the rewritten name
+    pub fn rewrite(&self, name: &Name) -> Option<(&RewriteRule, Name)> {
+        self.rules
+            .iter()
+            .find_map(|rule| rule.rewrite(name).map(|rewritten| (rule, rewritten)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).unwrap()
+    }
+
+    #[test]
+    fn test_exact() {
+        let rule = RewriteRule::exact(name("old.example."), name("new.example."));
+
+        assert_eq!(
+            rule.rewrite(&name("Old.Example.")),
+            Some(name("new.example."))
+        );
+        assert_eq!(rule.rewrite(&name("www.old.example.")), None);
+        assert_eq!(
+            rule.reverse(&name("new.example.")),
+            Some(name("old.example."))
+        );
+    }
+
+    #[test]
+    fn test_suffix() {
+        let rule = RewriteRule::suffix(name("old.example."), name("new.example.com."));
+
+        assert_eq!(
+            rule.rewrite(&name("www.old.example.")),
+            Some(name("www.new.example.com."))
+        );
+        assert_eq!(
+            rule.rewrite(&name("old.example.")),
+            Some(name("new.example.com."))
+        );
+        assert_eq!(rule.rewrite(&name("www.bold.example.")), None);
+        assert_eq!(
+            rule.reverse(&name("a.b.new.example.com.")),
+            Some(name("a.b.old.example."))
+        );
+    }
+
+    #[test]
+    fn test_cname_targets() {
+        let rule = RewriteRule::suffix(name("old.example."), name("new.example."));
+        let record = Record::from_rdata(
+            name("www.new.example."),
+            86400,
+            RData::CNAME(CNAME(name("host.new.example."))),
+        );
+
+        let rewritten = rule.reverse_record(&record);
+        assert_eq!(rewritten.name(), &name("www.old.example."));
+        assert_eq!(
+            rewritten.data(),
+            &RData::CNAME(CNAME(name("host.new.example.")))
+        );
+
+        let rewritten = rule.with_cname_targets(true).reverse_record(&record);
+        assert_eq!(
+            rewritten.data(),
+            &RData::CNAME(CNAME(name("host.old.example.")))
+        );
+    }
+
+    #[test]
+    fn test_first_match() {
+        let rules = RewriteRules::new()
+            .with_rule(RewriteRule::exact(
+                name("www.old.example."),
+                name("web.new.example."),
+            ))
+            .with_rule(RewriteRule::suffix(
+                name("old.example."),
+                name("new.example."),
+            ));
+
+        let (_, rewritten) = rules.rewrite(&name("www.old.example.")).unwrap();
+        assert_eq!(rewritten, name("web.new.example."));
+        let (_, rewritten) = rules.rewrite(&name("mail.old.example.")).unwrap();
+        assert_eq!(rewritten, name("mail.new.example."));
+        assert!(rules.rewrite(&name("www.other.example.")).is_none());
+        assert_eq!(rules.dnssec_policy(), DnssecRewritePolicy::Refuse);
+    }
+}
@@ -25,9 +25,11 @@ use serde::{self, Deserialize};
 use crate::proto::error::ProtoResult;
 use crate::proto::rr::Name;
 
-use crate::authority::ZoneType;
+use crate::authority::{DnssecRewritePolicy, RewriteMatch, RewriteRule, RewriteRules, ZoneType};
 #[cfg(feature = "toml")]
 use crate::error::ConfigResult;
+#[cfg(feature = "dnssec")]
+use crate::store::in_memory::SignatureTiming;
 use crate::store::StoreConfig;
 
 static DEFAULT_PATH: &str = "/var/named"; // TODO what about windows (do I care? ;)
@@ -75,6 +77,9 @@ pub struct Config {
     /// Networks allowed to access the server
     #[serde(default)]
     allow_networks: Vec<IpNet>,
+    /// Rules for rewriting the names of queries
+    #[serde(default)]
+    rewrite: RewriteConfig,
 }
 
 impl Config {
@@ -177,6 +182,56 @@ impl Config {
     pub fn get_allow_networks(&self) -> &[IpNet] {
         &self.allow_networks
     }
+
+    /// the rules for rewriting the names of queries
+    pub fn get_rewrite(&self) -> &RewriteConfig {
+        &self.rewrite
+    }
+}
+
+/// Configuration for rewriting the names of queries, see [`RewriteRules`]
+#[derive(Deserialize, PartialEq, Eq, Debug, Default)]
+pub struct RewriteConfig {
+    /// What to do with rewritten queries that are answered with DNSSEC records, defaults to Refuse
+    #[serde(default)]
+    pub dnssec_policy: DnssecRewritePolicy,
+    /// Rules in the order they are evaluated
+    #[serde(default)]
+    pub rules: Vec<RewriteRuleConfig>,
+}
+
+impl RewriteConfig {
+    /// Construct the rewrite rules for the catalog
+    pub fn to_rules(&self) -> ProtoResult<RewriteRules> {
+        self.rules.iter().try_fold(
+            RewriteRules::new().with_dnssec_policy(self.dnssec_policy),
+            |rules, rule| Ok(rules.with_rule(rule.to_rule()?)),
+        )
+    }
+}
+
+/// Configuration for a single rewrite rule, see [`RewriteRule`]
+#[derive(Deserialize, PartialEq, Eq, Debug)]
+pub struct RewriteRuleConfig {
+    /// How the name of the query is matched, Exact or Suffix
+    pub match_type: RewriteMatch,
+    /// The name as requested by the client
+    pub from: String,
+    /// The name to lookup in the catalog instead
+    pub to: String,
+    /// Also rewrite the targets of CNAME records in the response, defaults to false
+    pub rewrite_cname_targets: Option<bool>,
+}
+
+impl RewriteRuleConfig {
+    /// Construct the rewrite rule
+    pub fn to_rule(&self) -> ProtoResult<RewriteRule> {
+        let from = Name::parse(&self.from, Some(&Name::new()))?;
+        let to = Name::parse(&self.to, Some(&Name::new()))?;
+
+        Ok(RewriteRule::new(self.match_type, from, to)
+            .with_cname_targets(self.rewrite_cname_targets.unwrap_or(false)))
+    }
 }
 
 /// Configuration for a zone
@@ -197,9 +252,17 @@ pub struct ZoneConfig {
     /// Keys for use by the zone
     #[serde(default)]
     pub keys: Vec<dnssec::KeyConfig>,
+    /// Timing of the signatures of the zone
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    #[serde(default)]
+    pub signature_timing: SignatureTiming,
     /// Store configurations, TODO: allow chained Stores
     #[serde(default)]
     pub stores: Option<StoreConfig>,
+    /// Factor to scale the TTLs of all records returned from the zone by, clamped to `[0.0, 10.0]`
+    #[serde(default)]
+    pub ttl_scale_factor: Option<f32>,
 }
 
 impl ZoneConfig {
@@ -231,7 +294,10 @@ impl ZoneConfig {
             allow_axfr,
             enable_dnssec,
             keys,
+            #[cfg(feature = "dnssec")]
+            signature_timing: SignatureTiming::default(),
             stores: None,
+            ttl_scale_factor: None,
         }
     }
 
@@ -260,6 +326,11 @@ impl ZoneConfig {
         self.allow_update.unwrap_or(false)
     }
 
+    /// the factor to scale the TTLs of the zone by, see `Catalog::set_ttl_scale_factor`
+    pub fn get_ttl_scale_factor(&self) -> Option<f32> {
+        self.ttl_scale_factor
+    }
+
     /// enable AXFR transfers
     pub fn is_axfr_allowed(&self) -> bool {
         self.allow_axfr.unwrap_or(false)
@@ -282,4 +353,11 @@ impl ZoneConfig {
     pub fn get_keys(&self) -> &[dnssec::KeyConfig] {
         &self.keys
     }
+
+    /// the timing of the signatures of the zone
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub fn signature_timing(&self) -> SignatureTiming {
+        self.signature_timing
+    }
 }
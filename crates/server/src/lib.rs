@@ -60,6 +60,7 @@ pub mod config;
 pub mod error;
 pub mod server;
 pub mod store;
+pub mod support;
 
 pub use self::server::ServerFuture;
 
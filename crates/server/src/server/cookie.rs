@@ -0,0 +1,353 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DNS Cookies, [RFC 7873](https://tools.ietf.org/html/rfc7873)
+//!
+//! The server cookies follow the interoperable format of
+//!  [RFC 9018](https://tools.ietf.org/html/rfc9018), with HMAC-SHA-256 as the hash.
+
+use std::net::IpAddr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::proto::op::Edns;
+use crate::proto::rr::dnssec::rdata::tsig::TsigAlgorithm;
+use crate::proto::rr::rdata::opt::{EdnsCode, EdnsOption};
+
+/// The length of a client cookie
+pub const CLIENT_COOKIE_LEN: usize = 8;
+/// The length of the server cookies generated by [`CookieServer`]
+pub const SERVER_COOKIE_LEN: usize = 16;
+
+/// The version of the server cookie format of RFC 9018
+const VERSION: u8 = 1;
+/// The length of the version, reserved and timestamp fields preceding the hash
+const HEADER_LEN: usize = 8;
+/// Server cookies are accepted for up to one hour after they were generated, RFC 9018 section 4.3
+const LIFETIME: Duration = Duration::from_secs(60 * 60);
+/// The default tolerance for server cookies generated in the future
+const DEFAULT_CLOCK_SKEW: Duration = Duration::from_secs(5 * 60);
+
+/// The outcome of validating the cookie of a request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CookieStatus {
+    /// The server cookie was generated by this server for the client
+    Valid,
+    /// Only a client cookie was sent, the client has no server cookie yet
+    ClientOnly,
+    /// The cookie is malformed, or the server cookie was not generated by this server for the client
+    Invalid,
+    /// The server cookie was generated by this server, but is older than its lifetime or too far
+    ///  in the future
+    Expired,
+}
+
+/// Generates and validates the server cookies of DNS Cookies
+///
+/// A server cookie is bound to the client cookie and the addresses of the client and the server,
+///  so a cookie replayed from another address, or with another client cookie, is invalid. It
+///  embeds the time it was generated and expires after an hour, clients receive a fresh cookie
+///  with every response. All servers of an anycast or load balanced setup must share the secret.
+#[derive(Clone)]
+pub struct CookieServer {
+    secret: [u8; 16],
+    clock_skew: Duration,
+}
+
+impl CookieServer {
+    /// Creates a new cookie server with the secret, the clock skew defaults to 5 minutes
+    pub fn new(secret: [u8; 16]) -> Self {
+        Self {
+            secret,
+            clock_skew: DEFAULT_CLOCK_SKEW,
+        }
+    }
+
+    /// Sets how far in the future a server cookie may have been generated
+    ///
+    /// This tolerates clocks which are out of sync between servers sharing the secret.
+    pub fn with_clock_skew(mut self, clock_skew: Duration) -> Self {
+        self.clock_skew = clock_skew;
+        self
+    }
+
+    /// How far in the future a server cookie may have been generated
+    pub fn clock_skew(&self) -> Duration {
+        self.clock_skew
+    }
+
+    /// Generates the server cookie for the client cookie, sent from `client` to `server`
+    pub fn generate(
+        &self,
+        client_cookie: &[u8; CLIENT_COOKIE_LEN],
+        client: IpAddr,
+        server: IpAddr,
+    ) -> [u8; SERVER_COOKIE_LEN] {
+        self.generate_at(client_cookie, client, server, now())
+    }
+
+    fn generate_at(
+        &self,
+        client_cookie: &[u8; CLIENT_COOKIE_LEN],
+        client: IpAddr,
+        server: IpAddr,
+        timestamp: u32,
+    ) -> [u8; SERVER_COOKIE_LEN] {
+        let mut cookie = [0; SERVER_COOKIE_LEN];
+        cookie[0] = VERSION;
+        cookie[4..HEADER_LEN].copy_from_slice(&timestamp.to_be_bytes());
+
+        let hash = self.hash(client_cookie, &cookie[..HEADER_LEN], client, server);
+        cookie[HEADER_LEN..].copy_from_slice(&hash);
+        cookie
+    }
+
+    /// Validates the cookie option, the client cookie followed by the server cookie, sent from
+    ///  `client` to `server`
+    pub fn validate(&self, cookie: &[u8], client: IpAddr, server: IpAddr) -> CookieStatus {
+        self.validate_at(cookie, client, server, now())
+    }
+
+    fn validate_at(&self, cookie: &[u8], client: IpAddr, server: IpAddr, now: u32) -> CookieStatus {
+        if cookie.len() == CLIENT_COOKIE_LEN {
+            return CookieStatus::ClientOnly;
+        }
+
+        // server cookies of other formats, e.g. from a server with another configuration, are
+        //  simply replaced in the response
+        if cookie.len() != CLIENT_COOKIE_LEN + SERVER_COOKIE_LEN {
+            return CookieStatus::Invalid;
+        }
+
+        let (client_cookie, server_cookie) = cookie.split_at(CLIENT_COOKIE_LEN);
+        if server_cookie[0] != VERSION {
+            return CookieStatus::Invalid;
+        }
+
+        let expected = self.hash(client_cookie, &server_cookie[..HEADER_LEN], client, server);
+        if !constant_time_eq(&expected, &server_cookie[HEADER_LEN..]) {
+            return CookieStatus::Invalid;
+        }
+
+        let mut timestamp = [0; 4];
+        timestamp.copy_from_slice(&server_cookie[4..HEADER_LEN]);
+        let timestamp = u32::from_be_bytes(timestamp);
+
+        // serial number arithmetic, RFC 1982, the timestamp wraps around in 2106
+        let age = now.wrapping_sub(timestamp) as i32;
+        let expired = if age >= 0 {
+            u64::from(age.unsigned_abs()) > LIFETIME.as_secs()
+        } else {
+            u64::from(age.unsigned_abs()) > self.clock_skew.as_secs()
+        };
+
+        if expired {
+            CookieStatus::Expired
+        } else {
+            CookieStatus::Valid
+        }
+    }
+
+    /// HMAC-SHA-256 over the client cookie, the header of the server cookie, and the addresses,
+    ///  truncated to 8 bytes
+    fn hash(
+        &self,
+        client_cookie: &[u8],
+        header: &[u8],
+        client: IpAddr,
+        server: IpAddr,
+    ) -> [u8; SERVER_COOKIE_LEN - HEADER_LEN] {
+        let mut data = Vec::with_capacity(CLIENT_COOKIE_LEN + HEADER_LEN + 32);
+        data.extend_from_slice(client_cookie);
+        data.extend_from_slice(header);
+        extend_ip(&mut data, client);
+        extend_ip(&mut data, server);
+
+        let mac = TsigAlgorithm::HmacSha256
+            .mac_data(&self.secret, &data)
+            .expect("HMAC-SHA-256 is always supported");
+
+        let mut hash = [0; SERVER_COOKIE_LEN - HEADER_LEN];
+        hash.copy_from_slice(&mac[..SERVER_COOKIE_LEN - HEADER_LEN]);
+        hash
+    }
+}
+
+/// Returns the cookie option of the EDNS record of a request
+pub fn request_cookie(edns: &Edns) -> Option<&[u8]> {
+    match edns.option(EdnsCode::Cookie)? {
+        // a cookie of an invalid length is kept unparsed, and rejected by the validation
+        EdnsOption::Cookie(cookie) | EdnsOption::Unknown(_, cookie) => Some(cookie),
+        _ => None,
+    }
+}
+
+fn extend_ip(data: &mut Vec<u8>, ip: IpAddr) {
+    match ip {
+        IpAddr::V4(ip) => data.extend_from_slice(&ip.octets()),
+        IpAddr::V6(ip) => data.extend_from_slice(&ip.octets()),
+    }
+}
+
+/// Compares the hashes without revealing the position of the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// The current time as the seconds since the UNIX epoch, truncated to 32 bits
+fn now() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    const CLIENT: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+    const SERVER: IpAddr = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x53));
+    const CLIENT_COOKIE: [u8; CLIENT_COOKIE_LEN] = [1, 2, 3, 4, 5, 6, 7, 8];
+    const NOW: u32 = 1_700_000_000;
+
+    fn cookie(server_cookie: &[u8]) -> Vec<u8> {
+        let mut cookie = CLIENT_COOKIE.to_vec();
+        cookie.extend_from_slice(server_cookie);
+        cookie
+    }
+
+    #[test]
+    fn test_generate() {
+        let cookies = CookieServer::new([7; 16]);
+        let server_cookie = cookies.generate_at(&CLIENT_COOKIE, CLIENT, SERVER, NOW);
+
+        assert_eq!(server_cookie[0], VERSION);
+        assert_eq!(server_cookie[1..4], [0, 0, 0]);
+        assert_eq!(server_cookie[4..8], NOW.to_be_bytes());
+
+        // deterministic for the same inputs
+        assert_eq!(
+            server_cookie,
+            cookies.generate_at(&CLIENT_COOKIE, CLIENT, SERVER, NOW)
+        );
+
+        // bound to the client cookie, the addresses and the secret
+        assert_ne!(
+            server_cookie,
+            cookies.generate_at(&[8; CLIENT_COOKIE_LEN], CLIENT, SERVER, NOW)
+        );
+        assert_ne!(
+            server_cookie,
+            cookies.generate_at(&CLIENT_COOKIE, SERVER, CLIENT, NOW)
+        );
+        assert_ne!(
+            server_cookie,
+            CookieServer::new([8; 16]).generate_at(&CLIENT_COOKIE, CLIENT, SERVER, NOW)
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let cookies = CookieServer::new([7; 16]);
+        let server_cookie = cookies.generate_at(&CLIENT_COOKIE, CLIENT, SERVER, NOW);
+
+        assert_eq!(
+            cookies.validate_at(&cookie(&server_cookie), CLIENT, SERVER, NOW),
+            CookieStatus::Valid
+        );
+        assert_eq!(
+            cookies.validate_at(&CLIENT_COOKIE, CLIENT, SERVER, NOW),
+            CookieStatus::ClientOnly
+        );
+        assert_eq!(
+            cookies.validate_at(&cookie(&server_cookie[..8]), CLIENT, SERVER, NOW),
+            CookieStatus::Invalid
+        );
+        assert_eq!(
+            cookies.validate(
+                &cookie(&cookies.generate(&CLIENT_COOKIE, CLIENT, SERVER)),
+                CLIENT,
+                SERVER
+            ),
+            CookieStatus::Valid
+        );
+
+        // tampered
+        let mut tampered = server_cookie;
+        tampered[15] ^= 1;
+        assert_eq!(
+            cookies.validate_at(&cookie(&tampered), CLIENT, SERVER, NOW),
+            CookieStatus::Invalid
+        );
+
+        // another secret
+        assert_eq!(
+            CookieServer::new([8; 16]).validate_at(&cookie(&server_cookie), CLIENT, SERVER, NOW),
+            CookieStatus::Invalid
+        );
+    }
+
+    #[test]
+    fn test_replayed_cookie() {
+        let cookies = CookieServer::new([7; 16]);
+        let server_cookie = cookies.generate_at(&CLIENT_COOKIE, CLIENT, SERVER, NOW);
+
+        // from another client
+        let other = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2));
+        assert_eq!(
+            cookies.validate_at(&cookie(&server_cookie), other, SERVER, NOW),
+            CookieStatus::Invalid
+        );
+
+        // with another client cookie
+        let mut replayed = vec![8; CLIENT_COOKIE_LEN];
+        replayed.extend_from_slice(&server_cookie);
+        assert_eq!(
+            cookies.validate_at(&replayed, CLIENT, SERVER, NOW),
+            CookieStatus::Invalid
+        );
+
+        // after the lifetime
+        let lifetime = LIFETIME.as_secs() as u32;
+        assert_eq!(
+            cookies.validate_at(&cookie(&server_cookie), CLIENT, SERVER, NOW + lifetime),
+            CookieStatus::Valid
+        );
+        assert_eq!(
+            cookies.validate_at(&cookie(&server_cookie), CLIENT, SERVER, NOW + lifetime + 1),
+            CookieStatus::Expired
+        );
+    }
+
+    #[test]
+    fn test_clock_skew() {
+        assert_eq!(
+            CookieServer::new([7; 16]).clock_skew(),
+            Duration::from_secs(5 * 60)
+        );
+
+        let cookies = CookieServer::new([7; 16]).with_clock_skew(Duration::from_secs(60));
+        let server_cookie = cookies.generate_at(&CLIENT_COOKIE, CLIENT, SERVER, NOW);
+
+        assert_eq!(
+            cookies.validate_at(&cookie(&server_cookie), CLIENT, SERVER, NOW - 60),
+            CookieStatus::Valid
+        );
+        assert_eq!(
+            cookies.validate_at(&cookie(&server_cookie), CLIENT, SERVER, NOW - 61),
+            CookieStatus::Expired
+        );
+
+        // the timestamp wraps around
+        let server_cookie = cookies.generate_at(&CLIENT_COOKIE, CLIENT, SERVER, u32::MAX);
+        assert_eq!(
+            cookies.validate_at(&cookie(&server_cookie), CLIENT, SERVER, 10),
+            CookieStatus::Valid
+        );
+    }
+}
@@ -10,7 +10,7 @@ use std::{io, net::SocketAddr, sync::Arc};
 use bytes::{Bytes, BytesMut};
 use futures_util::lock::Mutex;
 use h2::server;
-use hickory_proto::{http::Version, rr::Record};
+use hickory_proto::{http::Version, rr::Record, xfer::DnsResponse};
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, warn};
@@ -120,8 +120,6 @@ impl ResponseHandler for HttpsResponseHandle {
             impl Iterator<Item = &'a Record> + Send + 'a,
         >,
     ) -> io::Result<ResponseInfo> {
-        use crate::proto::h2::HttpsError;
-        use crate::proto::http::response;
         use crate::proto::serialize::binary::BinEncoder;
 
         let mut bytes = Vec::with_capacity(512);
@@ -130,6 +128,29 @@ impl ResponseHandler for HttpsResponseHandle {
             let mut encoder = BinEncoder::new(&mut bytes);
             response.destructive_emit(&mut encoder)?
         };
+        self.send_bytes(bytes).await?;
+
+        Ok(info)
+    }
+
+    async fn send_raw_response(
+        &mut self,
+        id: u16,
+        response: &DnsResponse,
+    ) -> io::Result<ResponseInfo> {
+        self.send_bytes(response.bytes_with_id(id)).await?;
+
+        Ok(ResponseInfo::from_raw_response(id, response))
+    }
+}
+
+impl HttpsResponseHandle {
+    async fn send_bytes(&self, bytes: Vec<u8>) -> io::Result<()> {
+        use crate::proto::h2::HttpsError;
+        use crate::proto::http::response;
+
+        #[cfg(feature = "opentelemetry")]
+        super::telemetry::record_response_size(bytes.len());
         let bytes = Bytes::from(bytes);
         let response = response::new(Version::Http2, bytes.len())?;
 
@@ -142,6 +163,6 @@ impl ResponseHandler for HttpsResponseHandle {
             .map_err(HttpsError::from)?;
         stream.send_data(bytes, true).map_err(HttpsError::from)?;
 
-        Ok(info)
+        Ok(())
     }
 }
@@ -13,6 +13,7 @@ use h3::server::RequestStream;
 use h3_quinn::BidiStream;
 use hickory_proto::{
     error::ProtoError, h3::h3_server::H3Connection, h3::H3Error, http::Version, rr::Record,
+    xfer::DnsResponse,
 };
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, warn};
@@ -121,7 +122,6 @@ impl ResponseHandler for H3ResponseHandle {
             impl Iterator<Item = &'a Record> + Send + 'a,
         >,
     ) -> io::Result<ResponseInfo> {
-        use crate::proto::http::response;
         use crate::proto::serialize::binary::BinEncoder;
 
         let mut bytes = Vec::with_capacity(512);
@@ -130,6 +130,28 @@ impl ResponseHandler for H3ResponseHandle {
             let mut encoder = BinEncoder::new(&mut bytes);
             response.destructive_emit(&mut encoder)?
         };
+        self.send_bytes(bytes).await?;
+
+        Ok(info)
+    }
+
+    async fn send_raw_response(
+        &mut self,
+        id: u16,
+        response: &DnsResponse,
+    ) -> io::Result<ResponseInfo> {
+        self.send_bytes(response.bytes_with_id(id)).await?;
+
+        Ok(ResponseInfo::from_raw_response(id, response))
+    }
+}
+
+impl H3ResponseHandle {
+    async fn send_bytes(&self, bytes: Vec<u8>) -> io::Result<()> {
+        use crate::proto::http::response;
+
+        #[cfg(feature = "opentelemetry")]
+        super::telemetry::record_response_size(bytes.len());
         let bytes = Bytes::from(bytes);
         let response = response::new(Version::Http3, bytes.len())?;
 
@@ -142,6 +164,6 @@ impl ResponseHandler for H3ResponseHandle {
         stream.send_data(bytes).await.map_err(H3Error::from)?;
         stream.finish().await.map_err(H3Error::from)?;
 
-        Ok(info)
+        Ok(())
     }
 }
@@ -0,0 +1,497 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Middleware intercepting requests before they reach the RequestHandler
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use futures_util::future::BoxFuture;
+use ipnet::IpNet;
+use tracing::{error, info};
+
+#[cfg(any(feature = "dnssec-ring", feature = "dnssec-openssl"))]
+use crate::server::cookie::{request_cookie, CookieServer, CookieStatus};
+use crate::{
+    access::AccessControl,
+    authority::MessageResponseBuilder,
+    proto::{
+        op::{DsoMessage, LowerQuery, OpCode, ResponseCode},
+        xfer::UpstreamMetadata,
+    },
+    server::{Protocol, Request, RequestHandler, ResponseHandler, ResponseInfo},
+};
+
+/// Intercepts requests on their way to the RequestHandler
+///
+/// A middleware either passes the request on with [`Next::run`], possibly after inspecting or
+///  replacing it, or answers it itself with [`Next::respond`], in which case the rest of the
+///  chain is never called.
+pub trait RequestMiddleware: Send + Sync + 'static {
+    /// Handles the request, `next` is the remainder of the chain
+    fn intercept<'a>(&'a self, request: &'a Request, next: Next<'a>)
+        -> BoxFuture<'a, ResponseInfo>;
+}
+
+/// The remainder of a middleware chain, ending with the RequestHandler
+pub struct Next<'a> {
+    middlewares: &'a [Box<dyn RequestMiddleware>],
+    endpoint: &'a dyn Endpoint,
+}
+
+impl<'a> Next<'a> {
+    /// Passes the request on to the next middleware, or the RequestHandler at the end of the chain
+    pub fn run<'r>(self, request: &'r Request) -> BoxFuture<'r, ResponseInfo>
+    where
+        'a: 'r,
+    {
+        match self.middlewares.split_first() {
+            Some((middleware, middlewares)) => middleware.intercept(
+                request,
+                Next {
+                    middlewares,
+                    endpoint: self.endpoint,
+                },
+            ),
+            None => self.endpoint.handle_request(request),
+        }
+    }
+
+    /// Answers the request with an empty response with `response_code`, skipping the rest of the chain
+    pub fn respond<'r>(
+        self,
+        request: &'r Request,
+        response_code: ResponseCode,
+    ) -> BoxFuture<'r, ResponseInfo>
+    where
+        'a: 'r,
+    {
+        self.endpoint.respond(request, response_code)
+    }
+}
+
+/// The RequestHandler with the ResponseHandler of the request, with the type of both erased
+trait Endpoint: Send + Sync {
+    fn handle_request<'a>(&'a self, request: &'a Request) -> BoxFuture<'a, ResponseInfo>;
+
+    fn respond<'a>(
+        &'a self,
+        request: &'a Request,
+        response_code: ResponseCode,
+    ) -> BoxFuture<'a, ResponseInfo>;
+}
+
+struct HandlerEndpoint<'h, T, R> {
+    handler: &'h T,
+    response_handle: R,
+}
+
+impl<T: RequestHandler, R: ResponseHandler> Endpoint for HandlerEndpoint<'_, T, R> {
+    fn handle_request<'a>(&'a self, request: &'a Request) -> BoxFuture<'a, ResponseInfo> {
+        let response_handle = self.response_handle.clone();
+        match request.is_update() {
+            true => self.handler.handle_update(request, response_handle),
+            false => self.handler.handle_request(request, response_handle),
+        }
+    }
+
+    fn respond<'a>(
+        &'a self,
+        request: &'a Request,
+        response_code: ResponseCode,
+    ) -> BoxFuture<'a, ResponseInfo> {
+        let mut response_handle = self.response_handle.clone();
+        Box::pin(async move {
+            let response = MessageResponseBuilder::from_message_request(request);
+            let result = response_handle
+                .send_response(response.error_msg(request.header(), response_code))
+                .await;
+
+            match result {
+                Ok(info) => info,
+                Err(e) => {
+                    error!("failed to send response: {}", e);
+                    ResponseInfo::serve_failed()
+                }
+            }
+        })
+    }
+}
+
+/// A RequestHandler passing each request through a chain of middlewares before the wrapped handler
+///
+/// The middlewares are called in the order they were given, the first one sees the request
+///  first and the response info last.
+pub struct MiddlewareChain<T: RequestHandler> {
+    handler: T,
+    middlewares: Vec<Box<dyn RequestMiddleware>>,
+}
+
+impl<T: RequestHandler> MiddlewareChain<T> {
+    /// Wraps `handler` with the `middlewares`
+    pub fn new(handler: T, middlewares: Vec<Box<dyn RequestMiddleware>>) -> Self {
+        Self {
+            handler,
+            middlewares,
+        }
+    }
+
+    /// Returns the wrapped handler
+    pub fn handler(&self) -> &T {
+        &self.handler
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: RequestHandler> RequestHandler for MiddlewareChain<T> {
+    async fn handle_request<R: ResponseHandler>(
+        &self,
+        request: &Request,
+        response_handle: R,
+    ) -> ResponseInfo {
+        let endpoint = HandlerEndpoint {
+            handler: &self.handler,
+            response_handle,
+        };
+
+        Next {
+            middlewares: &self.middlewares,
+            endpoint: &endpoint,
+        }
+        .run(request)
+        .await
+    }
+
+    /// Updates pass through the same middlewares, the end of the chain calls `handle_update`
+    async fn handle_update<R: ResponseHandler>(
+        &self,
+        request: &Request,
+        response_handle: R,
+    ) -> ResponseInfo {
+        self.handle_request(request, response_handle).await
+    }
+
+    async fn handle_dso<R: ResponseHandler>(
+        &self,
+        request: DsoMessage,
+        src: SocketAddr,
+        response_handle: R,
+    ) -> ResponseInfo {
+        self.handler.handle_dso(request, src, response_handle).await
+    }
+}
+
+/// Logs every request with the response code and the time it took to handle it
+///
+/// Each request is logged as a [`QueryLogEntry`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LoggingMiddleware;
+
+impl RequestMiddleware for LoggingMiddleware {
+    fn intercept<'a>(
+        &'a self,
+        request: &'a Request,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, ResponseInfo> {
+        Box::pin(async move {
+            let start = Instant::now();
+            let response_info = next.run(request).await;
+
+            info!(
+                "{}",
+                QueryLogEntry::new(request, &response_info, start.elapsed())
+            );
+
+            response_info
+        })
+    }
+}
+
+/// The metadata of a request logged by the [`LoggingMiddleware`]
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct QueryLogEntry {
+    /// The id of the request message
+    pub id: u16,
+    /// The identifier correlating the request across services, if the request has one
+    pub correlation_id: Option<u64>,
+    /// The protocol used for the request
+    pub protocol: Protocol,
+    /// The source address from which the request came
+    pub src: SocketAddr,
+    /// The op code of the request
+    pub op_code: OpCode,
+    /// The query of the request
+    pub query: LowerQuery,
+    /// The response code of the response sent
+    pub response_code: ResponseCode,
+    /// The time it took to handle the request
+    pub elapsed: Duration,
+    /// The upstream name server which answered a forwarded query
+    pub upstream: Option<UpstreamMetadata>,
+}
+
+impl QueryLogEntry {
+    /// Collects the metadata of `request` and of the response it got
+    pub fn new(request: &Request, response_info: &ResponseInfo, elapsed: Duration) -> Self {
+        Self {
+            id: request.id(),
+            correlation_id: request.correlation_id(),
+            protocol: request.protocol(),
+            src: request.src(),
+            op_code: request.op_code(),
+            query: request.query().clone(),
+            response_code: response_info.response_code(),
+            elapsed,
+            upstream: response_info.upstream().copied(),
+        }
+    }
+}
+
+impl fmt::Display for QueryLogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "request:{id} src:{proto}://{addr}#{port} {op}:{query}:{qtype}:{class} response:{code:?} elapsed:{elapsed:?}",
+            id = self.id,
+            proto = self.protocol,
+            addr = self.src.ip(),
+            port = self.src.port(),
+            op = self.op_code,
+            query = self.query.name(),
+            qtype = self.query.query_type(),
+            class = self.query.query_class(),
+            code = self.response_code,
+            elapsed = self.elapsed,
+        )?;
+
+        if let Some(correlation_id) = self.correlation_id {
+            write!(f, " correlation:{correlation_id}")?;
+        }
+
+        if let Some(upstream) = &self.upstream {
+            write!(f, " upstream:{upstream}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Answers requests from denied networks with REFUSED
+///
+/// The networks are evaluated like the access control of the `ServerFuture`, see
+///  `ServerFuture::with_access`.
+pub struct AclMiddleware {
+    access: AccessControl,
+}
+
+impl AclMiddleware {
+    /// Creates a new ACL of the denied and allowed networks
+    pub fn new(denied_networks: &[IpNet], allowed_networks: &[IpNet]) -> Self {
+        let mut access = AccessControl::default();
+        access.insert_deny(denied_networks);
+        access.insert_allow(allowed_networks);
+
+        Self { access }
+    }
+}
+
+impl RequestMiddleware for AclMiddleware {
+    fn intercept<'a>(
+        &'a self,
+        request: &'a Request,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, ResponseInfo> {
+        if self.access.allow(request.src().ip()) {
+            next.run(request)
+        } else {
+            info!(
+                "request:{id} src:{addr} denied by acl",
+                id = request.id(),
+                addr = request.src().ip(),
+            );
+            next.respond(request, ResponseCode::Refused)
+        }
+    }
+}
+
+/// The number of tracked sources above which expired entries are dropped
+const RATE_LIMIT_MAX_SOURCES: usize = 4096;
+
+/// Answers requests with REFUSED once their source address exceeds the allowed request rate
+///
+/// Every source address may send `max_requests` requests in each `period`.
+pub struct RateLimitMiddleware {
+    period: Duration,
+    anonymous: RateLimit,
+    #[cfg(any(feature = "dnssec-ring", feature = "dnssec-openssl"))]
+    cookies: Option<CookieRateLimit>,
+}
+
+impl RateLimitMiddleware {
+    /// Allows `max_requests` requests per source address in every `period`
+    pub fn new(max_requests: u32, period: Duration) -> Self {
+        Self {
+            period,
+            anonymous: RateLimit::new(max_requests),
+            #[cfg(any(feature = "dnssec-ring", feature = "dnssec-openssl"))]
+            cookies: None,
+        }
+    }
+
+    /// Allows `max_requests` requests per source address in every period to requests with a valid
+    ///  DNS cookie
+    ///
+    /// Their source address can't be spoofed, so they are counted separately from requests
+    ///  without a valid cookie, usually with a higher limit. The cookies are validated for the
+    ///  `server_ip` the server is listening on, see [`CookieServer`].
+    #[cfg(any(feature = "dnssec-ring", feature = "dnssec-openssl"))]
+    pub fn with_cookies(
+        mut self,
+        cookies: CookieServer,
+        server_ip: IpAddr,
+        max_requests: u32,
+    ) -> Self {
+        self.cookies = Some(CookieRateLimit {
+            cookies,
+            server_ip,
+            limit: RateLimit::new(max_requests),
+        });
+        self
+    }
+
+    /// Counts the request, returns false if it exceeds the rate
+    fn admit(&self, request: &Request) -> bool {
+        let src = request.src().ip();
+
+        #[cfg(any(feature = "dnssec-ring", feature = "dnssec-openssl"))]
+        if let Some(cookies) = &self.cookies {
+            let cookie = request.edns().and_then(request_cookie);
+            if cookie.map_or(false, |cookie| {
+                cookies.cookies.validate(cookie, src, cookies.server_ip) == CookieStatus::Valid
+            }) {
+                return cookies.limit.admit(src, self.period);
+            }
+        }
+
+        self.anonymous.admit(src, self.period)
+    }
+}
+
+impl RequestMiddleware for RateLimitMiddleware {
+    fn intercept<'a>(
+        &'a self,
+        request: &'a Request,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, ResponseInfo> {
+        if self.admit(request) {
+            next.run(request)
+        } else {
+            info!(
+                "request:{id} src:{addr} rate limited",
+                id = request.id(),
+                addr = request.src().ip(),
+            );
+            next.respond(request, ResponseCode::Refused)
+        }
+    }
+}
+
+/// The requests per source address in the current period
+struct RateLimit {
+    max_requests: u32,
+    // the start of the current period and the requests in it, per source address
+    sources: Mutex<HashMap<IpAddr, (Instant, u32)>>,
+}
+
+impl RateLimit {
+    fn new(max_requests: u32) -> Self {
+        Self {
+            max_requests,
+            sources: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts a request from `src`, returns false if it exceeds the rate
+    fn admit(&self, src: IpAddr, period: Duration) -> bool {
+        let now = Instant::now();
+        let mut sources = self.sources.lock().expect("rate limit lock poisoned");
+
+        if sources.len() >= RATE_LIMIT_MAX_SOURCES {
+            sources.retain(|_, (start, _)| now.duration_since(*start) < period);
+        }
+
+        let (start, count) = sources.entry(src).or_insert((now, 0));
+        if now.duration_since(*start) >= period {
+            *start = now;
+            *count = 0;
+        }
+
+        *count = count.saturating_add(1);
+        *count <= self.max_requests
+    }
+}
+
+/// The separate rate limit of requests with a valid cookie
+#[cfg(any(feature = "dnssec-ring", feature = "dnssec-openssl"))]
+struct CookieRateLimit {
+    cookies: CookieServer,
+    server_ip: IpAddr,
+    limit: RateLimit,
+}
+
+/// Caps the payload size requested in the EDNS record of requests
+///
+/// Responses are sized to fit the payload size of the request, capping it avoids fragmented UDP
+///  responses, e.g. with the 1232 bytes recommended by the DNS Flag Day 2020.
+#[derive(Clone, Copy, Debug)]
+pub struct EdnsMiddleware {
+    max_payload: u16,
+}
+
+impl EdnsMiddleware {
+    /// Caps the payload size of requests to `max_payload`, values below 512 are treated as 512
+    pub fn new(max_payload: u16) -> Self {
+        Self {
+            max_payload: max_payload.max(512),
+        }
+    }
+
+    /// The maximum payload size passed on to the next handler
+    pub fn max_payload(&self) -> u16 {
+        self.max_payload
+    }
+}
+
+impl RequestMiddleware for EdnsMiddleware {
+    fn intercept<'a>(
+        &'a self,
+        request: &'a Request,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, ResponseInfo> {
+        let max_payload = self.max_payload;
+        if !request
+            .edns()
+            .map_or(false, |edns| edns.max_payload() > max_payload)
+        {
+            return next.run(request);
+        }
+
+        Box::pin(async move {
+            let mut message = (**request).clone();
+            if let Some(edns) = message.edns_mut() {
+                edns.set_max_payload(max_payload);
+            }
+
+            let capped = Request::new(message, request.src(), request.protocol());
+            next.run(&capped).await
+        })
+    }
+}
@@ -7,20 +7,35 @@
 
 //! `Server` component for hosting a domain name servers operations.
 
+#[cfg(any(feature = "dnssec-ring", feature = "dnssec-openssl"))]
+mod cookie;
 #[cfg(feature = "dns-over-https")]
 mod h2_handler;
 #[cfg(feature = "dns-over-h3")]
 mod h3_handler;
+mod middleware;
 mod protocol;
 #[cfg(feature = "dns-over-quic")]
 mod quic_handler;
 mod request_handler;
+mod response_buffer;
 mod response_handler;
 mod server_future;
+#[cfg(feature = "opentelemetry")]
+mod telemetry;
 mod timeout_stream;
 
+#[cfg(any(feature = "dnssec-ring", feature = "dnssec-openssl"))]
+pub use self::cookie::{
+    request_cookie, CookieServer, CookieStatus, CLIENT_COOKIE_LEN, SERVER_COOKIE_LEN,
+};
+pub use self::middleware::{
+    AclMiddleware, EdnsMiddleware, LoggingMiddleware, MiddlewareChain, Next, QueryLogEntry,
+    RateLimitMiddleware, RequestMiddleware,
+};
 pub use self::protocol::Protocol;
 pub use self::request_handler::{Request, RequestHandler, RequestInfo, ResponseInfo};
+pub use self::response_buffer::{ResponseBufferCounters, ResponseBufferLimits};
 pub use self::response_handler::{ResponseHandle, ResponseHandler};
 pub use self::server_future::ServerFuture;
 pub use self::timeout_stream::TimeoutStream;
@@ -13,6 +13,7 @@ use hickory_proto::{
     error::ProtoError,
     quic::{DoqErrorCode, QuicStream},
     rr::Record,
+    xfer::DnsResponse,
 };
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, warn};
@@ -126,13 +127,32 @@ impl ResponseHandler for QuicResponseHandle {
             let mut encoder = BinEncoder::new(&mut bytes);
             response.destructive_emit(&mut encoder)?
         };
+        self.send_bytes(bytes).await?;
+
+        Ok(info)
+    }
+
+    async fn send_raw_response(
+        &mut self,
+        _id: u16,
+        response: &DnsResponse,
+    ) -> io::Result<ResponseInfo> {
+        // The id should always be 0 in DoQ
+        self.send_bytes(response.bytes_with_id(0)).await?;
+
+        Ok(ResponseInfo::from_raw_response(0, response))
+    }
+}
+
+impl QuicResponseHandle {
+    async fn send_bytes(&self, bytes: Vec<u8>) -> Result<(), ProtoError> {
+        #[cfg(feature = "opentelemetry")]
+        super::telemetry::record_response_size(bytes.len());
         let bytes = Bytes::from(bytes);
 
         debug!("sending quic response: {}", bytes.len());
         let mut lock = self.0.lock().await;
         lock.send_bytes(bytes).await?;
-        lock.finish().await?;
-
-        Ok(info)
+        lock.finish().await
     }
 }
@@ -9,9 +9,16 @@
 
 use std::net::SocketAddr;
 
+use tracing::error;
+
 use crate::{
-    authority::MessageRequest,
-    proto::op::{Header, LowerQuery, ResponseCode},
+    authority::{MessageRequest, MessageResponseBuilder},
+    proto::{
+        error::ProtoResult,
+        op::{DsoMessage, Edns, Header, LowerQuery, MessageType, OpCode, ResponseCode},
+        serialize::binary::{BinDecodable, BinEncodable},
+        xfer::{DnsRequest, DnsResponse, UpstreamMetadata},
+    },
     server::{Protocol, ResponseHandler},
 };
 
@@ -24,6 +31,8 @@ pub struct Request {
     src: SocketAddr,
     /// Protocol of the request
     protocol: Protocol,
+    /// Identifier correlating the request across services, never sent on the wire
+    correlation_id: Option<u64>,
 }
 
 impl Request {
@@ -35,9 +44,32 @@ impl Request {
             message,
             src,
             protocol,
+            correlation_id: None,
         }
     }
 
+    /// Builds a request from a `DnsRequest` handed over in process, keeping its correlation id
+    pub fn from_dns_request(
+        request: &DnsRequest,
+        src: SocketAddr,
+        protocol: Protocol,
+    ) -> ProtoResult<Self> {
+        let message = MessageRequest::from_bytes(&request.to_bytes()?)?;
+        Ok(Self {
+            correlation_id: request.correlation_id(),
+            ..Self::new(message, src, protocol)
+        })
+    }
+
+    /// Associates an identifier with the request to correlate it in the logs of several services
+    ///
+    /// The identifier is not part of the message, it is logged with the request by the
+    ///  `LoggingMiddleware`.
+    pub fn with_correlation_id(mut self, correlation_id: u64) -> Self {
+        self.correlation_id = Some(correlation_id);
+        self
+    }
+
     /// Return just the header and request information from the Request Message
     pub fn request_info(&self) -> RequestInfo<'_> {
         RequestInfo {
@@ -57,6 +89,17 @@ impl Request {
     pub fn protocol(&self) -> Protocol {
         self.protocol
     }
+
+    /// The identifier correlating the request across services, see [`Self::with_correlation_id`]
+    pub fn correlation_id(&self) -> Option<u64> {
+        self.correlation_id
+    }
+
+    /// True for dynamic update requests, [RFC 2136](https://tools.ietf.org/html/rfc2136), which
+    ///  the server passes to [`RequestHandler::handle_update`]
+    pub fn is_update(&self) -> bool {
+        self.message_type() == MessageType::Query && self.op_code() == OpCode::Update
+    }
 }
 
 impl std::ops::Deref for Request {
@@ -107,21 +150,73 @@ impl<'a> RequestInfo<'a> {
 }
 
 /// Information about the response sent for a request
-#[derive(Clone, Copy)]
-#[repr(transparent)]
-pub struct ResponseInfo(Header);
+///
+/// Dereferences to the [`Header`] of the response as it was sent, which carries the response code,
+///  the flags, e.g. TC if the response was truncated or AD if its data was validated, and the record
+///  counts of each section.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResponseInfo {
+    header: Header,
+    encoded_len: usize,
+    max_payload: Option<u16>,
+    upstream: Option<UpstreamMetadata>,
+}
 
 impl ResponseInfo {
+    pub(crate) fn new(header: Header, encoded_len: usize, max_payload: Option<u16>) -> Self {
+        Self {
+            header,
+            encoded_len,
+            max_payload,
+            upstream: None,
+        }
+    }
+
+    /// Records the upstream exchange the response was built from
+    pub(crate) fn with_upstream(mut self, upstream: Option<UpstreamMetadata>) -> Self {
+        self.upstream = upstream;
+        self
+    }
+
+    /// Information about the upstream `response` sent as it was received, only with the id `id`
+    pub(crate) fn from_raw_response(id: u16, response: &DnsResponse) -> Self {
+        let mut header = *response.header();
+        header.set_id(id);
+        let max_payload = response.extensions().as_ref().map(Edns::max_payload);
+        Self::new(header, response.as_buffer().len(), max_payload)
+    }
+
     pub(crate) fn serve_failed() -> Self {
         let mut header = Header::new();
         header.set_response_code(ResponseCode::ServFail);
         header.into()
     }
+
+    /// The header of the response as it was sent
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// The number of bytes of the encoded response, 0 if it's unknown, e.g. if nothing was sent
+    pub fn encoded_len(&self) -> usize {
+        self.encoded_len
+    }
+
+    /// The EDNS payload size advertised in the response, `None` if it had no EDNS
+    pub fn max_payload(&self) -> Option<u16> {
+        self.max_payload
+    }
+
+    /// How the answer of a forwarded query was received from upstream, `None` if it was answered
+    ///  locally or from a cache
+    pub fn upstream(&self) -> Option<&UpstreamMetadata> {
+        self.upstream.as_ref()
+    }
 }
 
 impl From<Header> for ResponseInfo {
     fn from(header: Header) -> Self {
-        Self(header)
+        Self::new(header, 0, None)
     }
 }
 
@@ -129,7 +224,7 @@ impl std::ops::Deref for ResponseInfo {
     type Target = Header;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.header
     }
 }
 
@@ -147,6 +242,65 @@ pub trait RequestHandler: Send + Sync + Unpin + 'static {
         request: &Request,
         response_handle: R,
     ) -> ResponseInfo;
+
+    /// Handles a dynamic update request, [RFC 2136](https://tools.ietf.org/html/rfc2136)
+    ///
+    /// The server calls this instead of `handle_request` for requests with the UPDATE op code.
+    ///  By default updates are not implemented.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - the update to perform
+    /// * `response_handle` - handle to which a return message should be sent
+    async fn handle_update<R: ResponseHandler>(
+        &self,
+        request: &Request,
+        mut response_handle: R,
+    ) -> ResponseInfo {
+        let response = MessageResponseBuilder::from_message_request(request);
+        let result = response_handle
+            .send_response(response.error_msg(request.header(), ResponseCode::NotImp))
+            .await;
+
+        match result {
+            Ok(info) => info,
+            Err(e) => {
+                error!("request error: {}", e);
+                ResponseInfo::serve_failed()
+            }
+        }
+    }
+
+    /// Handles a DNS Stateful Operations request, [RFC 8490](https://tools.ietf.org/html/rfc8490),
+    ///  e.g. a DNS Push Notifications SUBSCRIBE
+    ///
+    /// These are only received on connection oriented protocols, the `response_handle` remains
+    ///  usable for as long as the connection is open. By default these are not implemented.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - the DSO request
+    /// * `_src` - the address of the client, unique for each connection
+    /// * `response_handle` - handle to which a return message should be sent
+    async fn handle_dso<R: ResponseHandler>(
+        &self,
+        request: DsoMessage,
+        _src: SocketAddr,
+        mut response_handle: R,
+    ) -> ResponseInfo {
+        let mut response = DsoMessage::response_from_request(request.header());
+        response
+            .header_mut()
+            .set_response_code(ResponseCode::NotImp);
+
+        match response_handle.send_dso(&response).await {
+            Ok(info) => info,
+            Err(e) => {
+                error!("request error: {}", e);
+                ResponseInfo::serve_failed()
+            }
+        }
+    }
 }
 
 #[cfg(test)]
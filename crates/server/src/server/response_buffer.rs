@@ -0,0 +1,388 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Accounting of the responses queued on TCP and TLS connections, which were not yet written to
+//!  the socket
+//!
+//! A client which pipelines queries without reading the responses would otherwise make the server
+//!  buffer the responses without bounds.
+
+use std::{
+    collections::HashMap,
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, Weak,
+    },
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use futures_util::{task::AtomicWaker, FutureExt};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    time::Sleep,
+};
+use tracing::{debug, warn};
+
+/// Default limit of the queued response bytes of a single connection
+const DEFAULT_PER_CONNECTION: usize = 4 * 1024 * 1024;
+/// Default limit of the queued response bytes of all connections
+const DEFAULT_GLOBAL: usize = 256 * 1024 * 1024;
+/// Default time a connection over its limit has to drain its responses
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Limits of the response bytes queued on TCP and TLS connections, which were not yet written to
+///  the socket
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResponseBufferLimits {
+    /// Once a connection has more bytes queued, no further requests are read from it until the
+    ///  responses were written
+    pub per_connection: usize,
+    /// Once all connections together have more bytes queued, the connections with the most bytes
+    ///  queued are closed
+    pub global: usize,
+    /// A connection is closed if it stays over its limit for this long
+    pub drain_timeout: Duration,
+}
+
+impl Default for ResponseBufferLimits {
+    fn default() -> Self {
+        Self {
+            per_connection: DEFAULT_PER_CONNECTION,
+            global: DEFAULT_GLOBAL,
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+        }
+    }
+}
+
+/// Counters of the response buffers of the TCP and TLS connections
+#[derive(Debug, Default)]
+pub struct ResponseBufferCounters {
+    queued_bytes: AtomicUsize,
+    throttled: AtomicU64,
+    drain_timeouts: AtomicU64,
+    shed: AtomicU64,
+}
+
+impl ResponseBufferCounters {
+    /// Number of response bytes currently queued on all connections
+    pub fn queued_bytes(&self) -> usize {
+        self.queued_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Number of times a connection exceeded its limit, and reading requests from it was paused
+    pub fn throttled(&self) -> u64 {
+        self.throttled.load(Ordering::Relaxed)
+    }
+
+    /// Number of connections closed because they didn't drain their responses in time
+    pub fn drain_timeouts(&self) -> u64 {
+        self.drain_timeouts.load(Ordering::Relaxed)
+    }
+
+    /// Number of connections closed to get back under the global limit
+    pub fn shed(&self) -> u64 {
+        self.shed.load(Ordering::Relaxed)
+    }
+}
+
+/// The response buffers of all TCP and TLS connections of a server
+pub(crate) struct ResponseBuffers {
+    limits: ResponseBufferLimits,
+    counters: Arc<ResponseBufferCounters>,
+    connections: Mutex<HashMap<u64, Weak<ConnectionBuffer>>>,
+    next_id: AtomicU64,
+}
+
+impl ResponseBuffers {
+    pub(crate) fn new(limits: ResponseBufferLimits) -> Self {
+        Self {
+            limits,
+            counters: Arc::new(ResponseBufferCounters::default()),
+            connections: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn limits(&self) -> ResponseBufferLimits {
+        self.limits
+    }
+
+    pub(crate) fn counters(&self) -> &Arc<ResponseBufferCounters> {
+        &self.counters
+    }
+
+    /// Starts the accounting of a new connection, which ends when the returned buffer is dropped
+    pub(crate) fn register(self: &Arc<Self>) -> Arc<ConnectionBuffer> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let buffer = Arc::new(ConnectionBuffer {
+            id,
+            queued: AtomicUsize::new(0),
+            throttled: AtomicBool::new(false),
+            shed: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+            buffers: self.clone(),
+        });
+
+        self.connections
+            .lock()
+            .expect("response buffers poisoned")
+            .insert(id, Arc::downgrade(&buffer));
+        buffer
+    }
+
+    /// Closes the connections with the most bytes queued, until the rest is under the global limit
+    fn shed(&self) {
+        let mut connections = self
+            .connections
+            .lock()
+            .expect("response buffers poisoned")
+            .values()
+            .filter_map(Weak::upgrade)
+            .filter(|connection| !connection.is_shed())
+            .collect::<Vec<_>>();
+        connections.sort_by_key(|connection| std::cmp::Reverse(connection.queued()));
+
+        let mut queued = self.counters.queued_bytes();
+        for connection in connections {
+            if queued <= self.limits.global {
+                break;
+            }
+
+            warn!(
+                "closing connection with {} bytes queued, {} bytes queued on all connections",
+                connection.queued(),
+                queued
+            );
+            queued = queued.saturating_sub(connection.queued());
+            connection.shed.store(true, Ordering::Relaxed);
+            connection.waker.wake();
+            self.counters.shed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// The response buffer of a single connection
+pub(crate) struct ConnectionBuffer {
+    id: u64,
+    queued: AtomicUsize,
+    throttled: AtomicBool,
+    shed: AtomicBool,
+    waker: AtomicWaker,
+    buffers: Arc<ResponseBuffers>,
+}
+
+impl ConnectionBuffer {
+    /// Accounts for a response of `len` bytes queued on the connection
+    pub(crate) fn queue(&self, len: usize) {
+        self.queued.fetch_add(len, Ordering::Relaxed);
+        let counters = &self.buffers.counters;
+        let total = counters.queued_bytes.fetch_add(len, Ordering::Relaxed) + len;
+        if total > self.buffers.limits.global {
+            self.buffers.shed();
+        }
+    }
+
+    /// Accounts for `len` bytes written to the socket
+    fn written(&self, len: usize) {
+        // the length prefix and multiple messages of a response aren't necessarily accounted for
+        let len = self
+            .queued
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |queued| {
+                Some(queued.saturating_sub(len))
+            })
+            .map_or(0, |queued| queued.min(len));
+        self.buffers
+            .counters
+            .queued_bytes
+            .fetch_sub(len, Ordering::Relaxed);
+    }
+
+    fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    fn is_shed(&self) -> bool {
+        self.shed.load(Ordering::Relaxed)
+    }
+
+    fn is_over_limit(&self) -> bool {
+        self.queued() > self.buffers.limits.per_connection
+    }
+}
+
+impl Drop for ConnectionBuffer {
+    fn drop(&mut self) {
+        self.buffers
+            .counters
+            .queued_bytes
+            .fetch_sub(self.queued(), Ordering::Relaxed);
+        self.buffers
+            .connections
+            .lock()
+            .expect("response buffers poisoned")
+            .remove(&self.id);
+    }
+}
+
+/// Wraps the stream of a connection, reading stops while the connection is over its limit
+pub(crate) struct ResponseBufferStream<S> {
+    stream: S,
+    buffer: Arc<ConnectionBuffer>,
+    deadline: Option<Pin<Box<Sleep>>>,
+}
+
+impl<S> ResponseBufferStream<S> {
+    pub(crate) fn new(stream: S, buffer: Arc<ConnectionBuffer>) -> Self {
+        Self {
+            stream,
+            buffer,
+            deadline: None,
+        }
+    }
+
+    /// Ready once the connection is under its limit, fails if it was shed or didn't drain in time
+    fn poll_drained(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.buffer.waker.register(cx.waker());
+        if self.buffer.is_shed() {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "too many responses queued on all connections",
+            )));
+        }
+
+        if !self.buffer.is_over_limit() {
+            self.buffer.throttled.store(false, Ordering::Relaxed);
+            self.deadline = None;
+            return Poll::Ready(Ok(()));
+        }
+
+        let counters = &self.buffer.buffers.counters;
+        if !self.buffer.throttled.swap(true, Ordering::Relaxed) {
+            debug!(
+                "pausing reads, {} response bytes queued on connection",
+                self.buffer.queued()
+            );
+            counters.throttled.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let drain_timeout = self.buffer.buffers.limits.drain_timeout;
+        let deadline = self
+            .deadline
+            .get_or_insert_with(|| Box::pin(tokio::time::sleep(drain_timeout)));
+        ready!(deadline.poll_unpin(cx));
+
+        counters.drain_timeouts.fetch_add(1, Ordering::Relaxed);
+        Poll::Ready(Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "queued responses were not drained in time",
+        )))
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for ResponseBufferStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        ready!(self.poll_drained(cx))?;
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for ResponseBufferStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match Pin::new(&mut self.stream).poll_write(cx, buf) {
+            Poll::Ready(Ok(len)) => {
+                self.buffer.written(len);
+                Poll::Ready(Ok(len))
+            }
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            // the peer isn't reading, this fails once the drain timeout expires
+            Poll::Pending => match self.poll_drained(cx) {
+                Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+                Poll::Ready(Ok(())) | Poll::Pending => Poll::Pending,
+            },
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffers(per_connection: usize, global: usize) -> Arc<ResponseBuffers> {
+        Arc::new(ResponseBuffers::new(ResponseBufferLimits {
+            per_connection,
+            global,
+            drain_timeout: Duration::from_secs(1),
+        }))
+    }
+
+    #[test]
+    fn test_accounting() {
+        let buffers = buffers(100, 1000);
+        let first = buffers.register();
+        let second = buffers.register();
+
+        first.queue(50);
+        second.queue(70);
+        assert_eq!(buffers.counters().queued_bytes(), 120);
+
+        first.written(20);
+        assert_eq!(first.queued(), 30);
+        assert_eq!(buffers.counters().queued_bytes(), 100);
+
+        // more bytes written than accounted for
+        first.written(40);
+        assert_eq!(first.queued(), 0);
+        assert_eq!(buffers.counters().queued_bytes(), 70);
+
+        drop(second);
+        assert_eq!(buffers.counters().queued_bytes(), 0);
+        assert!(buffers.connections.lock().unwrap().len() == 1);
+    }
+
+    #[test]
+    fn test_shed_most_backlogged() {
+        let buffers = buffers(1000, 1000);
+        let small = buffers.register();
+        let large = buffers.register();
+        let medium = buffers.register();
+
+        small.queue(100);
+        large.queue(600);
+        medium.queue(300);
+        assert_eq!(buffers.counters().shed(), 0);
+
+        // 1100 bytes queued, closing the largest connection is enough
+        small.queue(100);
+        assert!(large.is_shed());
+        assert!(!medium.is_shed());
+        assert!(!small.is_shed());
+        assert_eq!(buffers.counters().shed(), 1);
+
+        // the bytes of a closed connection are released once it is dropped
+        drop(large);
+        assert_eq!(buffers.counters().queued_bytes(), 500);
+    }
+}
@@ -5,18 +5,23 @@
 // https://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use std::{io, net::SocketAddr};
+use std::{io, net::SocketAddr, sync::Arc};
 
-use hickory_proto::rr::Record;
+use hickory_proto::{
+    op::{DsoMessage, Message},
+    rr::Record,
+};
 use tracing::{debug, trace};
 
 use crate::server::Protocol;
 use crate::{
     authority::MessageResponse,
     proto::{
-        serialize::binary::BinEncoder, xfer::SerialMessage, BufDnsStreamHandle, DnsStreamHandle,
+        serialize::binary::BinEncoder,
+        xfer::{DnsResponse, SerialMessage},
+        BufDnsStreamHandle,
     },
-    server::ResponseInfo,
+    server::{response_buffer::ConnectionBuffer, ResponseInfo},
 };
 
 /// A handler for send a response to a client
@@ -39,6 +44,27 @@ pub trait ResponseHandler: Clone + Send + Sync + Unpin + 'static {
             impl Iterator<Item = &'a Record> + Send + 'a,
         >,
     ) -> io::Result<ResponseInfo>;
+
+    /// Sends an upstream response as it was received, only replacing its id with `id`
+    ///
+    /// This allows forwarding a response that does not need to be modified without re-encoding it,
+    ///  which would not necessarily produce the same bytes and so break e.g. TSIG signatures. No
+    ///  truncation is performed, the caller must make sure the response fits the request's transport.
+    async fn send_raw_response(
+        &mut self,
+        id: u16,
+        response: &DnsResponse,
+    ) -> io::Result<ResponseInfo>;
+
+    /// Serializes and sends a DNS Stateful Operations message, e.g. a DNS Push Notifications PUSH
+    async fn send_dso(&mut self, message: &DsoMessage) -> io::Result<ResponseInfo> {
+        let mut header_only = Message::new();
+        header_only.set_header(*message.header());
+        let response = DnsResponse::new(header_only, message.to_vec()?);
+
+        self.send_raw_response(message.header().id(), &response)
+            .await
+    }
 }
 
 /// A handler for wrapping a BufStreamHandle, which will properly serialize the message and add the
@@ -48,6 +74,7 @@ pub struct ResponseHandle {
     dst: SocketAddr,
     stream_handle: BufDnsStreamHandle,
     protocol: Protocol,
+    buffer: Option<Arc<ConnectionBuffer>>,
 }
 
 impl ResponseHandle {
@@ -57,9 +84,29 @@ impl ResponseHandle {
             dst,
             stream_handle,
             protocol,
+            buffer: None,
         }
     }
 
+    /// Accounts for the responses in the response buffer of the connection
+    pub(crate) fn with_buffer(mut self, buffer: Option<Arc<ConnectionBuffer>>) -> Self {
+        self.buffer = buffer;
+        self
+    }
+
+    /// Queues the message on the stream
+    async fn send(&mut self, buffer: Vec<u8>) -> io::Result<()> {
+        // accounted before it is queued, it may be written right away; plus the length prefix
+        if let Some(connection) = &self.buffer {
+            connection.queue(buffer.len() + 2);
+        }
+
+        self.stream_handle
+            .send_async(SerialMessage::new(buffer, self.dst))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "unknown"))
+    }
+
     /// Selects an appropriate maximum serialized size for the given response.
     fn max_size_for_response<'a>(
         &self,
@@ -127,9 +174,30 @@ impl ResponseHandler for ResponseHandle {
             io::Error::new(io::ErrorKind::Other, format!("error encoding message: {e}"))
         })?;
 
-        self.stream_handle
-            .send(SerialMessage::new(buffer, self.dst))
-            .map_err(|_| io::Error::new(io::ErrorKind::Other, "unknown"))?;
+        #[cfg(feature = "opentelemetry")]
+        super::telemetry::record_response_size(buffer.len());
+
+        self.send(buffer).await?;
+
+        Ok(info)
+    }
+
+    async fn send_raw_response(
+        &mut self,
+        id: u16,
+        response: &DnsResponse,
+    ) -> io::Result<ResponseInfo> {
+        let info = ResponseInfo::from_raw_response(id, response);
+        debug!(
+            "raw response: {} response_code: {}",
+            id,
+            info.response_code(),
+        );
+
+        #[cfg(feature = "opentelemetry")]
+        super::telemetry::record_response_size(info.encoded_len());
+
+        self.send(response.bytes_with_id(id)).await?;
 
         Ok(info)
     }
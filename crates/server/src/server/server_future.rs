@@ -16,7 +16,11 @@ use hickory_proto::{op::MessageType, rr::Record};
 use ipnet::IpNet;
 #[cfg(feature = "dns-over-rustls")]
 use rustls::{Certificate, PrivateKey, ServerConfig};
-use tokio::{net, task::JoinSet};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net,
+    task::JoinSet,
+};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
@@ -28,14 +32,19 @@ use crate::{
     proto::{
         error::ProtoError,
         iocompat::AsyncIoTokioAsStd,
-        op::{Edns, Header, LowerQuery, Query, ResponseCode},
+        op::{DsoMessage, Edns, Header, LowerQuery, OpCode, Query, ResponseCode},
         serialize::binary::{BinDecodable, BinDecoder},
         tcp::TcpStream,
-        udp::UdpStream,
-        xfer::SerialMessage,
+        udp::{UdpSendCounters, UdpStream},
+        xfer::{DnsResponse, SerialMessage},
         BufDnsStreamHandle,
     },
-    server::{Protocol, Request, RequestHandler, ResponseHandle, ResponseHandler, TimeoutStream},
+    server::{
+        response_buffer::{ConnectionBuffer, ResponseBufferStream, ResponseBuffers},
+        MiddlewareChain, Protocol, Request, RequestHandler, RequestMiddleware,
+        ResponseBufferCounters, ResponseBufferLimits, ResponseHandle, ResponseHandler,
+        ResponseInfo, TimeoutStream,
+    },
 };
 
 // TODO, would be nice to have a Slab for buffers here...
@@ -45,6 +54,17 @@ pub struct ServerFuture<T: RequestHandler> {
     join_set: JoinSet<Result<(), ProtoError>>,
     shutdown_token: CancellationToken,
     access: Arc<AccessControl>,
+    response_buffers: Arc<ResponseBuffers>,
+    udp_send_counters: Arc<UdpSendCounters>,
+}
+
+impl<T: RequestHandler> ServerFuture<MiddlewareChain<T>> {
+    /// Creates a new ServerFuture passing each request through the `middlewares` before the Handler
+    ///
+    /// The middlewares are called in order, see [`MiddlewareChain`].
+    pub fn with_middleware(handler: T, middlewares: Vec<Box<dyn RequestMiddleware>>) -> Self {
+        Self::new(MiddlewareChain::new(handler, middlewares))
+    }
 }
 
 impl<T: RequestHandler> ServerFuture<T> {
@@ -64,17 +84,43 @@ impl<T: RequestHandler> ServerFuture<T> {
             join_set: JoinSet::new(),
             shutdown_token: CancellationToken::new(),
             access: Arc::new(access),
+            response_buffers: Arc::new(ResponseBuffers::new(ResponseBufferLimits::default())),
+            udp_send_counters: Arc::new(UdpSendCounters::default()),
         }
     }
 
+    /// Sets the limits of the responses queued on TCP and TLS connections
+    ///
+    /// A client which pipelines requests without reading the responses is throttled, and closed
+    ///  if it doesn't drain the responses in time. The limits apply to the listeners registered
+    ///  afterwards.
+    pub fn with_response_buffer_limits(mut self, limits: ResponseBufferLimits) -> Self {
+        self.response_buffers = Arc::new(ResponseBuffers::new(limits));
+        self
+    }
+
+    /// The counters of the responses queued on TCP and TLS connections
+    pub fn response_buffer_counters(&self) -> Arc<ResponseBufferCounters> {
+        self.response_buffers.counters().clone()
+    }
+
+    /// The counters of the UDP responses the sockets rejected as too big
+    ///
+    /// A response exceeding e.g. the MTU of a VPN link is sent again as an empty truncated response,
+    ///  so that the client retries over TCP.
+    pub fn udp_send_counters(&self) -> Arc<UdpSendCounters> {
+        self.udp_send_counters.clone()
+    }
+
     /// Register a UDP socket. Should be bound before calling this function.
     pub fn register_socket(&mut self, socket: net::UdpSocket) {
         debug!("registering udp: {:?}", socket);
 
         // create the new UdpStream, the IP address isn't relevant, and ideally goes essentially no where.
         //   the address used is acquired from the inbound queries
-        let (mut stream, stream_handle) =
+        let (stream, stream_handle) =
             UdpStream::with_bound(socket, ([127, 255, 255, 254], 0).into());
+        let mut stream = stream.with_send_counters(self.udp_send_counters.clone());
         let shutdown = self.shutdown_token.clone();
         let handler = self.handler.clone();
         let access = self.access.clone();
@@ -121,8 +167,15 @@ impl<T: RequestHandler> ServerFuture<T> {
                     let stream_handle = stream_handle.with_remote_addr(src_addr);
 
                     inner_join_set.spawn(async move {
-                        handle_raw_request(message, Protocol::Udp, access, handler, stream_handle)
-                            .await;
+                        handle_raw_request(
+                            message,
+                            Protocol::Udp,
+                            access,
+                            handler,
+                            stream_handle,
+                            None,
+                        )
+                        .await;
                     });
 
                     reap_tasks(&mut inner_join_set);
@@ -161,6 +214,7 @@ impl<T: RequestHandler> ServerFuture<T> {
 
         let handler = self.handler.clone();
         let access = self.access.clone();
+        let response_buffers = self.response_buffers.clone();
 
         // for each incoming request...
         let shutdown = self.shutdown_token.clone();
@@ -196,38 +250,21 @@ impl<T: RequestHandler> ServerFuture<T> {
 
                 let handler = handler.clone();
                 let access = access.clone();
+                let response_buffers = response_buffers.clone();
 
                 // and spawn to the io_loop
                 inner_join_set.spawn(async move {
                     debug!("accepted request from: {}", src_addr);
-                    // take the created stream...
-                    let (buf_stream, stream_handle) =
-                        TcpStream::from_stream(AsyncIoTokioAsStd(tcp_stream), src_addr);
-                    let mut timeout_stream = TimeoutStream::new(buf_stream, timeout);
-
-                    while let Some(message) = timeout_stream.next().await {
-                        let message = match message {
-                            Ok(message) => message,
-                            Err(e) => {
-                                debug!(
-                                    "error in TCP request_stream src: {} error: {}",
-                                    src_addr, e
-                                );
-                                // we're going to bail on this connection...
-                                return;
-                            }
-                        };
-
-                        // we don't spawn here to limit clients from getting too many resources
-                        handle_raw_request(
-                            message,
-                            Protocol::Tcp,
-                            access.clone(),
-                            handler.clone(),
-                            stream_handle.clone(),
-                        )
-                        .await;
-                    }
+                    handle_stream(
+                        tcp_stream,
+                        src_addr,
+                        Protocol::Tcp,
+                        timeout,
+                        access,
+                        handler,
+                        &response_buffers,
+                    )
+                    .await;
                 });
 
                 reap_tasks(&mut inner_join_set);
@@ -286,7 +323,7 @@ impl<T: RequestHandler> ServerFuture<T> {
         timeout: Duration,
         certificate_and_key: ((X509, Option<Stack<X509>>), PKey<Private>),
     ) -> io::Result<()> {
-        use crate::proto::openssl::{tls_server, TlsStream};
+        use crate::proto::openssl::tls_server;
         use openssl::ssl::Ssl;
         use std::pin::Pin;
         use tokio_openssl::SslStream as TokioSslStream;
@@ -294,6 +331,8 @@ impl<T: RequestHandler> ServerFuture<T> {
         let ((cert, chain), key) = certificate_and_key;
 
         let handler = self.handler.clone();
+        let access = self.access.clone();
+        let response_buffers = self.response_buffers.clone();
         debug!("registered tcp: {:?}", listener);
 
         let tls_acceptor = Box::pin(tls_server::new_acceptor(cert, chain, key)?);
@@ -331,6 +370,8 @@ impl<T: RequestHandler> ServerFuture<T> {
                 }
 
                 let handler = handler.clone();
+                let access = access.clone();
+                let response_buffers = response_buffers.clone();
                 let tls_acceptor = tls_acceptor.clone();
 
                 // kick out to a different task immediately, let them do the TLS handshake
@@ -355,32 +396,16 @@ impl<T: RequestHandler> ServerFuture<T> {
                         }
                     };
                     debug!("accepted TLS request from: {}", src_addr);
-                    let (buf_stream, stream_handle) =
-                        TlsStream::from_stream(AsyncIoTokioAsStd(tls_stream), src_addr);
-                    let mut timeout_stream = TimeoutStream::new(buf_stream, timeout);
-                    while let Some(message) = timeout_stream.next().await {
-                        let message = match message {
-                            Ok(message) => message,
-                            Err(e) => {
-                                debug!(
-                                    "error in TLS request_stream src: {:?} error: {}",
-                                    src_addr, e
-                                );
-
-                                // kill this connection
-                                return ();
-                            }
-                        };
-
-                        self::handle_raw_request(
-                            message,
-                            Protocol::Tls,
-                            access.clone(),
-                            handler.clone(),
-                            stream_handle.clone(),
-                        )
-                        .await;
-                    }
+                    handle_stream(
+                        tls_stream,
+                        src_addr,
+                        Protocol::Tls,
+                        timeout,
+                        access,
+                        handler,
+                        &response_buffers,
+                    )
+                    .await;
                 });
 
                 reap_tasks(&mut inner_join_set);
@@ -448,11 +473,11 @@ impl<T: RequestHandler> ServerFuture<T> {
         timeout: Duration,
         tls_config: Arc<ServerConfig>,
     ) -> io::Result<()> {
-        use crate::proto::rustls::tls_from_stream;
         use tokio_rustls::TlsAcceptor;
 
         let handler = self.handler.clone();
         let access = self.access.clone();
+        let response_buffers = self.response_buffers.clone();
 
         debug!("registered tcp: {:?}", listener);
 
@@ -492,6 +517,7 @@ impl<T: RequestHandler> ServerFuture<T> {
 
                 let handler = handler.clone();
                 let access = access.clone();
+                let response_buffers = response_buffers.clone();
                 let tls_acceptor = tls_acceptor.clone();
 
                 // kick out to a different task immediately, let them do the TLS handshake
@@ -502,38 +528,23 @@ impl<T: RequestHandler> ServerFuture<T> {
                     let tls_stream = tls_acceptor.accept(tcp_stream).await;
 
                     let tls_stream = match tls_stream {
-                        Ok(tls_stream) => AsyncIoTokioAsStd(tls_stream),
+                        Ok(tls_stream) => tls_stream,
                         Err(e) => {
                             debug!("tls handshake src: {} error: {}", src_addr, e);
                             return;
                         }
                     };
                     debug!("accepted TLS request from: {}", src_addr);
-                    let (buf_stream, stream_handle) = tls_from_stream(tls_stream, src_addr);
-                    let mut timeout_stream = TimeoutStream::new(buf_stream, timeout);
-                    while let Some(message) = timeout_stream.next().await {
-                        let message = match message {
-                            Ok(message) => message,
-                            Err(e) => {
-                                debug!(
-                                    "error in TLS request_stream src: {:?} error: {}",
-                                    src_addr, e
-                                );
-
-                                // kill this connection
-                                return;
-                            }
-                        };
-
-                        handle_raw_request(
-                            message,
-                            Protocol::Tls,
-                            access.clone(),
-                            handler.clone(),
-                            stream_handle.clone(),
-                        )
-                        .await;
-                    }
+                    handle_stream(
+                        tls_stream,
+                        src_addr,
+                        Protocol::Tls,
+                        timeout,
+                        access,
+                        handler,
+                        &response_buffers,
+                    )
+                    .await;
                 });
 
                 reap_tasks(&mut inner_join_set);
@@ -982,15 +993,66 @@ fn reap_tasks(join_set: &mut JoinSet<()>) {
     {}
 }
 
+/// Reads the requests of a TCP or TLS connection and answers them, until the connection is closed
+///
+/// Reading pauses while the connection has more responses queued than its limit in the
+///  `response_buffers`.
+async fn handle_stream<S, T>(
+    stream: S,
+    src_addr: SocketAddr,
+    protocol: Protocol,
+    timeout: Duration,
+    access: Arc<AccessControl>,
+    handler: Arc<T>,
+    response_buffers: &Arc<ResponseBuffers>,
+) where
+    S: AsyncRead + AsyncWrite + Unpin + Send + Sync + 'static,
+    T: RequestHandler,
+{
+    let buffer = response_buffers.register();
+    let stream = ResponseBufferStream::new(stream, buffer.clone());
+
+    // take the created stream...
+    let (buf_stream, stream_handle) = TcpStream::from_stream(AsyncIoTokioAsStd(stream), src_addr);
+    let mut timeout_stream = TimeoutStream::new(buf_stream, timeout);
+
+    while let Some(message) = timeout_stream.next().await {
+        let message = match message {
+            Ok(message) => message,
+            Err(e) => {
+                debug!(
+                    "error in {} request_stream src: {} error: {}",
+                    protocol, src_addr, e
+                );
+                // we're going to bail on this connection...
+                return;
+            }
+        };
+
+        // we don't spawn here to limit clients from getting too many resources
+        handle_raw_request(
+            message,
+            protocol,
+            access.clone(),
+            handler.clone(),
+            stream_handle.clone(),
+            Some(buffer.clone()),
+        )
+        .await;
+    }
+}
+
 pub(crate) async fn handle_raw_request<T: RequestHandler>(
     message: SerialMessage,
     protocol: Protocol,
     access: Arc<AccessControl>,
     request_handler: Arc<T>,
     response_handler: BufDnsStreamHandle,
+    buffer: Option<Arc<ConnectionBuffer>>,
 ) {
     let src_addr = message.addr();
-    let response_handler = ResponseHandle::new(message.addr(), response_handler, protocol);
+    let response_handler =
+        ResponseHandle::new(message.addr(), response_handler, protocol).with_buffer(buffer);
 
     handle_request(
         message.bytes(),
@@ -1012,22 +1074,9 @@ struct ReportingResponseHandler<R: ResponseHandler> {
     handler: R,
 }
 
-#[async_trait::async_trait]
-#[allow(clippy::uninlined_format_args)]
-impl<R: ResponseHandler> ResponseHandler for ReportingResponseHandler<R> {
-    async fn send_response<'a>(
-        &mut self,
-        response: crate::authority::MessageResponse<
-            '_,
-            'a,
-            impl Iterator<Item = &'a Record> + Send + 'a,
-            impl Iterator<Item = &'a Record> + Send + 'a,
-            impl Iterator<Item = &'a Record> + Send + 'a,
-            impl Iterator<Item = &'a Record> + Send + 'a,
-        >,
-    ) -> io::Result<super::ResponseInfo> {
-        let response_info = self.handler.send_response(response).await?;
-
+impl<R: ResponseHandler> ReportingResponseHandler<R> {
+    #[allow(clippy::uninlined_format_args)]
+    fn report(&self, response_info: &super::ResponseInfo) {
         let id = self.request_header.id();
         let rid = response_info.id();
         if id != rid {
@@ -1040,8 +1089,9 @@ impl<R: ResponseHandler> ResponseHandler for ReportingResponseHandler<R> {
         let authority_count = response_info.name_server_count();
         let additional_count = response_info.additional_count();
         let response_code = response_info.response_code();
+        let size = response_info.encoded_len();
 
-        info!("request:{id} src:{proto}://{addr}#{port} {op}:{query}:{qtype}:{class} qflags:{qflags} response:{code:?} rr:{answers}/{authorities}/{additionals} rflags:{rflags}",
+        info!("request:{id} src:{proto}://{addr}#{port} {op}:{query}:{qtype}:{class} qflags:{qflags} response:{code:?} rr:{answers}/{authorities}/{additionals} rflags:{rflags} size:{size}",
             id = rid,
             proto = self.protocol,
             addr = self.src_addr.ip(),
@@ -1055,13 +1105,46 @@ impl<R: ResponseHandler> ResponseHandler for ReportingResponseHandler<R> {
             answers = answer_count,
             authorities = authority_count,
             additionals = additional_count,
-            rflags = rflags
+            rflags = rflags,
+            size = size
         );
+    }
+}
+
+#[async_trait::async_trait]
+impl<R: ResponseHandler> ResponseHandler for ReportingResponseHandler<R> {
+    async fn send_response<'a>(
+        &mut self,
+        response: crate::authority::MessageResponse<
+            '_,
+            'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+        >,
+    ) -> io::Result<super::ResponseInfo> {
+        let response_info = self.handler.send_response(response).await?;
+        self.report(&response_info);
+
+        Ok(response_info)
+    }
+
+    async fn send_raw_response(
+        &mut self,
+        id: u16,
+        response: &DnsResponse,
+    ) -> io::Result<super::ResponseInfo> {
+        let response_info = self.handler.send_raw_response(id, response).await?;
+        self.report(&response_info);
 
         Ok(response_info)
     }
 }
 
+/// The highest EDNS version supported by the server
+const EDNS_VERSION: u8 = 0;
+
 pub(crate) async fn handle_request<R: ResponseHandler, T: RequestHandler>(
     // TODO: allow Message here...
     message_bytes: &[u8],
@@ -1073,54 +1156,6 @@ pub(crate) async fn handle_request<R: ResponseHandler, T: RequestHandler>(
 ) {
     let mut decoder = BinDecoder::new(message_bytes);
 
-    // method to handle the request
-    let inner_handle_request = |message: MessageRequest, response_handler: R| async move {
-        if message.message_type() == MessageType::Response {
-            // Don't process response messages to avoid DoS attacks from reflection.
-            return;
-        }
-
-        let id = message.id();
-        let qflags = message.header().flags();
-        let qop_code = message.op_code();
-        let message_type = message.message_type();
-        let is_dnssec = message.edns().map_or(false, Edns::dnssec_ok);
-
-        let request = Request::new(message, src_addr, protocol);
-
-        let info = request.request_info();
-        let query = info.query.clone();
-        let query_name = info.query.name();
-        let query_type = info.query.query_type();
-        let query_class = info.query.query_class();
-
-        debug!(
-            "request:{id} src:{proto}://{addr}#{port} type:{message_type} dnssec:{is_dnssec} {op}:{query}:{qtype}:{class} qflags:{qflags}",
-            id = id,
-            proto = protocol,
-            addr = src_addr.ip(),
-            port = src_addr.port(),
-            message_type= message_type,
-            is_dnssec = is_dnssec,
-            op = qop_code,
-            query = query_name,
-            qtype = query_type,
-            class = query_class,
-            qflags = qflags,
-        );
-
-        // The reporter will handle making sure to log the result of the request
-        let reporter = ReportingResponseHandler {
-            request_header: *request.header(),
-            query,
-            protocol,
-            src_addr,
-            handler: response_handler,
-        };
-
-        request_handler.handle_request(&request, reporter).await;
-    };
-
     // method to return an error to the client
     let error_response_handler = |protocol: Protocol,
                                   src_addr: SocketAddr,
@@ -1171,6 +1206,117 @@ pub(crate) async fn handle_request<R: ResponseHandler, T: RequestHandler>(
         return;
     }
 
+    // DSO messages don't have a question, they are handled separately from the other requests
+    if let Ok(header) = Header::read(&mut BinDecoder::new(message_bytes)) {
+        if header.op_code() == OpCode::Dso {
+            if header.message_type() == MessageType::Response {
+                return;
+            }
+
+            if !matches!(protocol, Protocol::Tcp | Protocol::Tls) {
+                debug!(
+                    "request:{id} src:{proto}://{addr}#{port} DSO is only supported on TCP and TLS",
+                    id = header.id(),
+                    proto = protocol,
+                    addr = src_addr.ip(),
+                    port = src_addr.port(),
+                );
+                return;
+            }
+
+            match DsoMessage::from_vec(message_bytes) {
+                Ok(message) => {
+                    debug!(
+                        "request:{id} src:{proto}://{addr}#{port} {op}",
+                        id = header.id(),
+                        proto = protocol,
+                        addr = src_addr.ip(),
+                        port = src_addr.port(),
+                        op = header.op_code(),
+                    );
+                    request_handler
+                        .handle_dso(message, src_addr, response_handler)
+                        .await;
+                }
+                Err(ProtoError { kind, .. }) if kind.as_form_error().is_some() => {
+                    let (header, error) = kind
+                        .into_form_error()
+                        .expect("as form_error already confirmed this is a FormError");
+
+                    error_response_handler(
+                        protocol,
+                        src_addr,
+                        header,
+                        LowerQuery::query(Query::default()),
+                        ResponseCode::FormErr,
+                        error,
+                        response_handler,
+                    )
+                    .await;
+                }
+                Err(error) => info!(
+                    "request:Failed src:{proto}://{addr}#{port} error:{error}",
+                    proto = protocol,
+                    addr = src_addr.ip(),
+                    port = src_addr.port(),
+                ),
+            }
+
+            return;
+        }
+    }
+
+    // method to handle the request
+    let inner_handle_request = |message: MessageRequest, response_handler: R| async move {
+        if message.message_type() == MessageType::Response {
+            // Don't process response messages to avoid DoS attacks from reflection.
+            return;
+        }
+
+        let id = message.id();
+        let qflags = message.header().flags();
+        let qop_code = message.op_code();
+        let message_type = message.message_type();
+        let is_dnssec = message.edns().map_or(false, Edns::dnssec_ok);
+
+        let request = Request::new(message, src_addr, protocol);
+
+        let info = request.request_info();
+        let query = info.query.clone();
+        let query_name = info.query.name();
+        let query_type = info.query.query_type();
+        let query_class = info.query.query_class();
+
+        debug!(
+            "request:{id} src:{proto}://{addr}#{port} type:{message_type} dnssec:{is_dnssec} {op}:{query}:{qtype}:{class} qflags:{qflags}",
+            id = id,
+            proto = protocol,
+            addr = src_addr.ip(),
+            port = src_addr.port(),
+            message_type= message_type,
+            is_dnssec = is_dnssec,
+            op = qop_code,
+            query = query_name,
+            qtype = query_type,
+            class = query_class,
+            qflags = qflags,
+        );
+
+        // The reporter will handle making sure to log the result of the request
+        let reporter = ReportingResponseHandler {
+            request_header: *request.header(),
+            query,
+            protocol,
+            src_addr,
+            handler: response_handler,
+        };
+
+        let response = respond(&request, &*request_handler, reporter);
+        #[cfg(feature = "opentelemetry")]
+        let response = super::telemetry::trace_request(&request, response);
+        response.await;
+    };
+
     // Attempt to decode the message
     match MessageRequest::read(&mut decoder) {
         Ok(message) => {
@@ -1203,6 +1349,48 @@ pub(crate) async fn handle_request<R: ResponseHandler, T: RequestHandler>(
     }
 }
 
+/// Answers the request with the handler, or with BADVERS for an unsupported EDNS version
+async fn respond<R: ResponseHandler, T: RequestHandler>(
+    request: &Request,
+    request_handler: &T,
+    mut reporter: ReportingResponseHandler<R>,
+) -> ResponseInfo {
+    // RFC 6891, requests with an unsupported EDNS version are answered with BADVERS
+    let Some(req_edns) = request.edns().filter(|edns| edns.version() > EDNS_VERSION) else {
+        return match request.is_update() {
+            true => request_handler.handle_update(request, reporter).await,
+            false => request_handler.handle_request(request, reporter).await,
+        };
+    };
+
+    warn!(
+        "request:{id} edns version greater than {EDNS_VERSION}: {version}",
+        id = request.id(),
+        version = req_edns.version(),
+    );
+
+    let mut response_header = Header::response_from_request(request.header());
+    response_header.set_response_code(ResponseCode::BADVERS);
+
+    let mut resp_edns = Edns::new();
+    resp_edns.set_version(EDNS_VERSION);
+    resp_edns.set_max_payload(req_edns.max_payload().max(512));
+    resp_edns.set_rcode_high(ResponseCode::BADVERS.high());
+
+    let mut response = MessageResponseBuilder::new(Some(request.raw_query()));
+    response.edns(resp_edns);
+    match reporter
+        .send_response(response.build_no_records(response_header))
+        .await
+    {
+        Ok(info) => info,
+        Err(e) => {
+            warn!("failed to return BADVERS to client: {}", e);
+            ResponseInfo::serve_failed()
+        }
+    }
+}
+
 /// Checks if the IP address is safe for returning messages
 ///
 /// Examples of unsafe addresses are any with a port of `0`
@@ -1256,6 +1444,8 @@ fn is_unrecoverable_socket_error(err: &io::Error) -> bool {
 mod tests {
     use super::*;
     use crate::authority::Catalog;
+    use crate::proto::op::Message;
+    use crate::proto::rr::{Name, RecordType};
     use futures_util::future;
     #[cfg(feature = "dns-over-rustls")]
     use rustls::{Certificate, PrivateKey};
@@ -1495,4 +1685,373 @@ mod tests {
             reap_tasks(&mut joinset);
         });
     }
+
+    #[derive(Clone)]
+    struct RecordingResponseHandler {
+        inner: ResponseHandle,
+        infos: Arc<std::sync::Mutex<Vec<ResponseInfo>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ResponseHandler for RecordingResponseHandler {
+        async fn send_response<'a>(
+            &mut self,
+            response: crate::authority::MessageResponse<
+                '_,
+                'a,
+                impl Iterator<Item = &'a Record> + Send + 'a,
+                impl Iterator<Item = &'a Record> + Send + 'a,
+                impl Iterator<Item = &'a Record> + Send + 'a,
+                impl Iterator<Item = &'a Record> + Send + 'a,
+            >,
+        ) -> io::Result<ResponseInfo> {
+            let info = self.inner.send_response(response).await?;
+            self.infos.lock().unwrap().push(info);
+            Ok(info)
+        }
+
+        async fn send_raw_response(
+            &mut self,
+            id: u16,
+            response: &DnsResponse,
+        ) -> io::Result<ResponseInfo> {
+            let info = self.inner.send_raw_response(id, response).await?;
+            self.infos.lock().unwrap().push(info);
+            Ok(info)
+        }
+    }
+
+    fn example_catalog() -> Catalog {
+        use crate::authority::ZoneType;
+        use crate::proto::rr::{
+            rdata::{A, SOA, TXT},
+            Name, RData,
+        };
+        use crate::store::in_memory::InMemoryAuthority;
+
+        let origin = Name::from_ascii("example.com.").unwrap();
+        let mut authority = InMemoryAuthority::empty(origin.clone(), ZoneType::Primary, false);
+        authority.upsert_mut(
+            Record::from_rdata(
+                origin.clone(),
+                3600,
+                RData::SOA(SOA::new(
+                    Name::from_ascii("ns.example.com.").unwrap(),
+                    Name::from_ascii("hostmaster.example.com.").unwrap(),
+                    1,
+                    3600,
+                    600,
+                    86400,
+                    300,
+                )),
+            ),
+            1,
+        );
+        authority.upsert_mut(
+            Record::from_rdata(
+                Name::from_ascii("www.example.com.").unwrap(),
+                300,
+                RData::A(A::new(192, 0, 2, 1)),
+            ),
+            1,
+        );
+        for i in 0..20 {
+            authority.upsert_mut(
+                Record::from_rdata(
+                    Name::from_ascii("big.example.com.").unwrap(),
+                    300,
+                    RData::TXT(TXT::new(vec![format!("{i:02}{}", "x".repeat(48))])),
+                ),
+                1,
+            );
+        }
+
+        let mut catalog = Catalog::new();
+        catalog.upsert(origin.into(), Box::new(Arc::new(authority)));
+        catalog
+    }
+
+    /// Handles `request` over UDP, returns the info of the response and the bytes sent
+    async fn response_info(request: &[u8]) -> (ResponseInfo, Vec<u8>) {
+        handle_with(request, example_catalog()).await
+    }
+
+    /// Handles `request` over UDP with `handler`, see [`response_info`]
+    async fn handle_with<T: RequestHandler>(request: &[u8], handler: T) -> (ResponseInfo, Vec<u8>) {
+        let src = SocketAddr::from(([192, 0, 2, 100], 53535));
+        let (stream_handle, mut receiver) = BufDnsStreamHandle::new(src);
+        let infos = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let response_handler = RecordingResponseHandler {
+            inner: ResponseHandle::new(src, stream_handle, Protocol::Udp),
+            infos: infos.clone(),
+        };
+
+        handle_request(
+            request,
+            src,
+            Protocol::Udp,
+            Arc::new(AccessControl::default()),
+            Arc::new(handler),
+            response_handler,
+        )
+        .await;
+
+        let sent = receiver.next().await.expect("no response was sent");
+        let [info] = infos.lock().unwrap().as_slice().try_into().unwrap();
+        (info, sent.into_parts().0)
+    }
+
+    fn query(name: &str, record_type: RecordType, max_payload: u16) -> Vec<u8> {
+        let mut message = Message::new();
+        message
+            .set_id(1234)
+            .add_query(Query::query(Name::from_ascii(name).unwrap(), record_type));
+        let mut edns = Edns::new();
+        edns.set_max_payload(max_payload);
+        message.set_edns(edns);
+        message.to_vec().unwrap()
+    }
+
+    #[tokio::test]
+    async fn response_info_answer() {
+        let (info, sent) = response_info(&query("www.example.com.", RecordType::A, 1232)).await;
+
+        assert_eq!(info.id(), 1234);
+        assert_eq!(info.response_code(), ResponseCode::NoError);
+        assert!(info.authoritative());
+        assert!(!info.truncated());
+        assert_eq!(info.answer_count(), 1);
+        assert_eq!(info.encoded_len(), sent.len());
+        assert_eq!(info.max_payload(), Some(1232));
+    }
+
+    #[tokio::test]
+    async fn response_info_truncated() {
+        let (info, sent) = response_info(&query("big.example.com.", RecordType::TXT, 512)).await;
+
+        assert_eq!(info.response_code(), ResponseCode::NoError);
+        assert!(info.truncated());
+        assert_eq!(info.answer_count(), 0);
+        assert_eq!(info.encoded_len(), sent.len());
+        assert!(info.encoded_len() <= 512);
+        assert_eq!(info.max_payload(), Some(512));
+    }
+
+    #[tokio::test]
+    async fn response_info_refused() {
+        let (info, sent) = response_info(&query("www.example.net.", RecordType::A, 1232)).await;
+
+        assert_eq!(info.response_code(), ResponseCode::Refused);
+        assert!(!info.authoritative());
+        assert_eq!(info.answer_count(), 0);
+        assert_eq!(info.name_server_count(), 0);
+        assert_eq!(info.encoded_len(), sent.len());
+    }
+
+    #[tokio::test]
+    async fn response_info_form_error() {
+        // the header announces a query which is cut off
+        let mut request = query("www.example.com.", RecordType::A, 1232);
+        request.truncate(Header::len() + 3);
+
+        let (info, sent) = response_info(&request).await;
+
+        assert_eq!(info.id(), 1234);
+        assert_eq!(info.response_code(), ResponseCode::FormErr);
+        assert_eq!(info.query_count(), 0);
+        assert_eq!(info.answer_count(), 0);
+        assert_eq!(info.encoded_len(), sent.len());
+        assert_eq!(info.encoded_len(), Header::len());
+        assert_eq!(info.max_payload(), None);
+    }
+
+    /// Records which of its methods was called, answers with NOERROR
+    #[derive(Clone, Default)]
+    struct RoutingHandler {
+        calls: Arc<std::sync::Mutex<Vec<&'static str>>>,
+    }
+
+    impl RoutingHandler {
+        async fn respond<R: ResponseHandler>(
+            &self,
+            call: &'static str,
+            request: &Request,
+            mut response_handle: R,
+        ) -> ResponseInfo {
+            self.calls.lock().unwrap().push(call);
+            let response = MessageResponseBuilder::from_message_request(request);
+            response_handle
+                .send_response(response.error_msg(request.header(), ResponseCode::NoError))
+                .await
+                .unwrap()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl RequestHandler for RoutingHandler {
+        async fn handle_request<R: ResponseHandler>(
+            &self,
+            request: &Request,
+            response_handle: R,
+        ) -> ResponseInfo {
+            self.respond("request", request, response_handle).await
+        }
+
+        async fn handle_update<R: ResponseHandler>(
+            &self,
+            request: &Request,
+            response_handle: R,
+        ) -> ResponseInfo {
+            self.respond("update", request, response_handle).await
+        }
+    }
+
+    /// Only implements `handle_request`
+    struct QueryHandler(RoutingHandler);
+
+    #[async_trait::async_trait]
+    impl RequestHandler for QueryHandler {
+        async fn handle_request<R: ResponseHandler>(
+            &self,
+            request: &Request,
+            response_handle: R,
+        ) -> ResponseInfo {
+            self.0.handle_request(request, response_handle).await
+        }
+    }
+
+    fn update(zone: &str) -> Vec<u8> {
+        let mut message = Message::new();
+        message
+            .set_id(4321)
+            .set_op_code(OpCode::Update)
+            .add_query(Query::query(
+                Name::from_ascii(zone).unwrap(),
+                RecordType::SOA,
+            ));
+        message.to_vec().unwrap()
+    }
+
+    #[tokio::test]
+    async fn update_routed_to_handle_update() {
+        let handler = RoutingHandler::default();
+
+        let (info, _) = handle_with(&update("example.com."), handler.clone()).await;
+        assert_eq!(info.id(), 4321);
+        assert_eq!(info.response_code(), ResponseCode::NoError);
+
+        let (info, _) = handle_with(
+            &query("www.example.com.", RecordType::A, 1232),
+            handler.clone(),
+        )
+        .await;
+        assert_eq!(info.id(), 1234);
+        assert_eq!(info.response_code(), ResponseCode::NoError);
+
+        assert_eq!(*handler.calls.lock().unwrap(), ["update", "request"]);
+    }
+
+    #[tokio::test]
+    async fn update_not_implemented_by_default() {
+        let handler = RoutingHandler::default();
+
+        let (info, _) = handle_with(&update("example.com."), QueryHandler(handler.clone())).await;
+        assert_eq!(info.id(), 4321);
+        assert_eq!(info.response_code(), ResponseCode::NotImp);
+        assert!(handler.calls.lock().unwrap().is_empty());
+    }
+
+    /// Frames `message` for TCP, with the length prefix
+    fn tcp_request(message: &[u8]) -> Vec<u8> {
+        let mut framed = (message.len() as u16).to_be_bytes().to_vec();
+        framed.extend_from_slice(message);
+        framed
+    }
+
+    #[tokio::test]
+    async fn response_buffer_pipelining_client() {
+        use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+        let response_buffers = Arc::new(ResponseBuffers::new(ResponseBufferLimits {
+            per_connection: 256,
+            global: 1024 * 1024,
+            drain_timeout: Duration::from_millis(200),
+        }));
+        let access = Arc::new(AccessControl::default());
+        let handler = Arc::new(example_catalog());
+        let session = |stream: DuplexStream, src: SocketAddr| {
+            let access = access.clone();
+            let handler = handler.clone();
+            let response_buffers = response_buffers.clone();
+            tokio::spawn(async move {
+                let timeout = Duration::from_secs(5);
+                handle_stream(
+                    stream,
+                    src,
+                    Protocol::Tcp,
+                    timeout,
+                    access,
+                    handler,
+                    &response_buffers,
+                )
+                .await
+            })
+        };
+
+        // pipelines requests for large responses, without reading any of them
+        let (mut pipelining, server) = duplex(1024);
+        let pipelining_session = session(server, SocketAddr::from(([192, 0, 2, 1], 53535)));
+        let requests = tcp_request(&query("big.example.com.", RecordType::TXT, 4096)).repeat(200);
+        let writer = tokio::spawn(async move {
+            let _ = pipelining.write_all(&requests).await;
+            pipelining
+        });
+
+        let (mut normal, server) = duplex(1024);
+        let normal_session = session(server, SocketAddr::from(([192, 0, 2, 2], 53535)));
+
+        timeout(Duration::from_secs(2), async {
+            while response_buffers.counters().throttled() == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the pipelining client was not throttled");
+
+        // the normal client is answered while the other one is throttled, and after it was closed
+        async fn exchange(stream: &mut DuplexStream, request: &[u8]) -> Message {
+            stream.write_all(&tcp_request(request)).await.unwrap();
+            let len = stream.read_u16().await.unwrap();
+            let mut response = vec![0; len as usize];
+            stream.read_exact(&mut response).await.unwrap();
+            Message::from_vec(&response).unwrap()
+        }
+
+        let request = query("www.example.com.", RecordType::A, 1232);
+        let response = exchange(&mut normal, &request).await;
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(response.answers().len(), 1);
+
+        // the connection of the pipelining client is closed once the drain timeout expired
+        timeout(Duration::from_secs(2), pipelining_session)
+            .await
+            .expect("the pipelining session did not end")
+            .unwrap();
+
+        let response = exchange(&mut normal, &request).await;
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(response.answers().len(), 1);
+
+        drop(writer.await.unwrap());
+        assert_eq!(response_buffers.counters().throttled(), 1);
+        assert_eq!(response_buffers.counters().drain_timeouts(), 1);
+        assert_eq!(response_buffers.counters().shed(), 0);
+
+        drop(normal);
+        timeout(Duration::from_secs(2), normal_session)
+            .await
+            .expect("the normal session did not end")
+            .unwrap();
+        assert_eq!(response_buffers.counters().queued_bytes(), 0);
+    }
 }
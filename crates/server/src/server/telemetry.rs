@@ -0,0 +1,75 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! OpenTelemetry spans for the requests handled by the server
+//!
+//! The spans are created with the global tracer, see `opentelemetry::global::set_tracer_provider`,
+//!  nothing is exported unless a provider is installed.
+
+use std::future::Future;
+
+use opentelemetry::{
+    global,
+    trace::{FutureExt, SpanKind, TraceContextExt, Tracer},
+    Context, KeyValue,
+};
+
+use crate::server::{Protocol, Request, ResponseInfo};
+
+/// The name of the tracer of the server
+const TRACER_NAME: &str = "hickory-server";
+
+/// Runs `response` in a span covering the request, ended once the response was sent
+pub(crate) async fn trace_request<F>(request: &Request, response: F) -> ResponseInfo
+where
+    F: Future<Output = ResponseInfo>,
+{
+    let query = request.query();
+    let tracer = global::tracer(TRACER_NAME);
+    let span = tracer
+        .span_builder("dns.request")
+        .with_kind(SpanKind::Server)
+        .with_attributes([
+            KeyValue::new("dns.query.name", query.name().to_string()),
+            KeyValue::new("dns.query.type", query.query_type().to_string()),
+            KeyValue::new("dns.transport", transport(request.protocol())),
+            KeyValue::new("net.peer.ip", request.src().ip().to_string()),
+        ])
+        .start(&tracer);
+
+    let cx = Context::current_with_span(span);
+    let response_info = response.with_context(cx.clone()).await;
+
+    let span = cx.span();
+    span.set_attribute(KeyValue::new(
+        "dns.response.code",
+        response_info.response_code().to_string(),
+    ));
+    span.end();
+
+    response_info
+}
+
+/// Records the size of the response on the span of the current request
+pub(crate) fn record_response_size(len: usize) {
+    Context::map_current(|cx| {
+        cx.span()
+            .set_attribute(KeyValue::new("dns.response.size", len as i64))
+    });
+}
+
+fn transport(protocol: Protocol) -> &'static str {
+    match protocol {
+        Protocol::Udp => "udp",
+        Protocol::Tcp => "tcp",
+        Protocol::Tls => "tls",
+        Protocol::Dtls => "dtls",
+        Protocol::Https => "doh",
+        Protocol::Quic => "doq",
+        Protocol::H3 => "doh3",
+    }
+}
@@ -33,8 +33,9 @@ pub enum StoreConfig {
     #[cfg(feature = "hickory-resolver")]
     #[cfg_attr(docsrs, doc(cfg(feature = "resolver")))]
     Forward(ForwardConfig),
-    /// Recursive Resolver
+    /// Recursive Resolver, `type = "recursive"` is accepted as well
     #[cfg(feature = "hickory-recursor")]
     #[cfg_attr(docsrs, doc(cfg(feature = "recursor")))]
+    #[serde(alias = "recursive")]
     Recursor(RecursiveConfig),
 }
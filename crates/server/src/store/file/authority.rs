@@ -20,10 +20,12 @@ use tracing::{debug, info};
 use crate::{
     authority::DnssecAuthority,
     proto::rr::dnssec::{rdata::key::KEY, DnsSecResult, SigSigner},
+    store::in_memory::SignatureTiming,
 };
 use crate::{
     authority::{Authority, LookupError, LookupOptions, MessageRequest, UpdateResult, ZoneType},
-    proto::rr::{LowerName, Name, RecordSet, RecordType, RrKey},
+    proto::error::ProtoResult,
+    proto::rr::{LowerName, Name, Record, RecordSet, RecordType, RrKey},
     proto::serialize::txt::Parser,
     server::RequestInfo,
     store::{file::FileConfig, in_memory::InMemoryAuthority},
@@ -33,7 +35,10 @@ use crate::{
 ///
 /// Authorities default to DNSClass IN. The ZoneType specifies if this should be treated as the
 /// start of authority for the zone, is a Secondary, or a cached zone.
-pub struct FileAuthority(InMemoryAuthority);
+pub struct FileAuthority {
+    authority: InMemoryAuthority,
+    zone_path: Option<PathBuf>,
+}
 
 impl FileAuthority {
     /// Creates a new Authority.
@@ -57,7 +62,10 @@ impl FileAuthority {
         zone_type: ZoneType,
         allow_axfr: bool,
     ) -> Result<Self, String> {
-        InMemoryAuthority::new(origin, records, zone_type, allow_axfr).map(Self)
+        InMemoryAuthority::new(origin, records, zone_type, allow_axfr).map(|authority| Self {
+            authority,
+            zone_path: None,
+        })
     }
 
     /// Read the Authority for the origin from the specified configuration
@@ -71,16 +79,28 @@ impl FileAuthority {
         let root_dir_path = root_dir.map(PathBuf::from).unwrap_or_default();
         let zone_path = root_dir_path.join(&config.zone_file_path);
 
+        let (origin, records) = Self::read_zone_file(origin, &zone_path)?;
+
+        let mut authority = Self::new(origin, records, zone_type, allow_axfr)?;
+        authority.zone_path = Some(zone_path);
+        Ok(authority)
+    }
+
+    /// Reads and parses the zone file at `zone_path`
+    fn read_zone_file(
+        origin: Name,
+        zone_path: &Path,
+    ) -> Result<(Name, BTreeMap<RrKey, RecordSet>), String> {
         info!("loading zone file: {:?}", zone_path);
 
         // TODO: this should really use something to read line by line or some other method to
         //  keep the usage down. and be a custom lexer...
-        let buf = fs::read_to_string(&zone_path)
-            .map_err(|e| format!("failed to read {}: {:?}", &config.zone_file_path, e))?;
+        let buf = fs::read_to_string(zone_path)
+            .map_err(|e| format!("failed to read {}: {:?}", zone_path.display(), e))?;
 
-        let (origin, records) = Parser::new(buf, Some(zone_path), Some(origin))
+        let (origin, records) = Parser::new(buf, Some(zone_path.to_owned()), Some(origin))
             .parse()
-            .map_err(|e| format!("failed to parse {}: {:?}", config.zone_file_path, e))?;
+            .map_err(|e| format!("failed to parse {}: {:?}", zone_path.display(), e))?;
 
         info!(
             "zone file loaded: {} with {} records",
@@ -89,12 +109,12 @@ impl FileAuthority {
         );
         debug!("zone: {:#?}", records);
 
-        Self::new(origin, records, zone_type, allow_axfr)
+        Ok((origin, records))
     }
 
     /// Unwrap the InMemoryAuthority
     pub fn unwrap(self) -> InMemoryAuthority {
-        self.0
+        self.authority
     }
 }
 
@@ -102,13 +122,13 @@ impl Deref for FileAuthority {
     type Target = InMemoryAuthority;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.authority
     }
 }
 
 impl DerefMut for FileAuthority {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.authority
     }
 }
 
@@ -118,12 +138,12 @@ impl Authority for FileAuthority {
 
     /// What type is this zone
     fn zone_type(&self) -> ZoneType {
-        self.0.zone_type()
+        self.authority.zone_type()
     }
 
     /// Return true if AXFR is allowed
     fn is_axfr_allowed(&self) -> bool {
-        self.0.is_axfr_allowed()
+        self.authority.is_axfr_allowed()
     }
 
     /// Perform a dynamic update of a zone
@@ -134,7 +154,7 @@ impl Authority for FileAuthority {
 
     /// Get the origin of this zone, i.e. example.com is the origin for www.example.com
     fn origin(&self) -> &LowerName {
-        self.0.origin()
+        self.authority.origin()
     }
 
     /// Looks up all Resource Records matching the giving `Name` and `RecordType`.
@@ -157,7 +177,7 @@ impl Authority for FileAuthority {
         rtype: RecordType,
         lookup_options: LookupOptions,
     ) -> Result<Self::Lookup, LookupError> {
-        self.0.lookup(name, rtype, lookup_options).await
+        self.authority.lookup(name, rtype, lookup_options).await
     }
 
     /// Using the specified query, perform a lookup against this zone.
@@ -176,12 +196,12 @@ impl Authority for FileAuthority {
         request_info: RequestInfo<'_>,
         lookup_options: LookupOptions,
     ) -> Result<Self::Lookup, LookupError> {
-        self.0.search(request_info, lookup_options).await
+        self.authority.search(request_info, lookup_options).await
     }
 
     /// Get the NS, NameServer, record for the zone
     async fn ns(&self, lookup_options: LookupOptions) -> Result<Self::Lookup, LookupError> {
-        self.0.ns(lookup_options).await
+        self.authority.ns(lookup_options).await
     }
 
     /// Return the NSEC records based on the given name
@@ -196,7 +216,7 @@ impl Authority for FileAuthority {
         name: &LowerName,
         lookup_options: LookupOptions,
     ) -> Result<Self::Lookup, LookupError> {
-        self.0.get_nsec_records(name, lookup_options).await
+        self.authority.get_nsec_records(name, lookup_options).await
     }
 
     /// Returns the SOA of the authority.
@@ -204,12 +224,28 @@ impl Authority for FileAuthority {
     /// *Note*: This will only return the SOA, if this is fulfilling a request, a standard lookup
     ///  should be used, see `soa_secure()`, which will optionally return RRSIGs.
     async fn soa(&self) -> Result<Self::Lookup, LookupError> {
-        self.0.soa().await
+        self.authority.soa().await
     }
 
     /// Returns the SOA record for the zone
     async fn soa_secure(&self, lookup_options: LookupOptions) -> Result<Self::Lookup, LookupError> {
-        self.0.soa_secure(lookup_options).await
+        self.authority.soa_secure(lookup_options).await
+    }
+
+    /// Returns the SOA record at the apex of the zone
+    async fn zone_apex(&self) -> ProtoResult<Record> {
+        self.authority.zone_apex().await
+    }
+
+    /// Reread the zone file from disk, keeping the current records if it fails to load
+    async fn reload_zone(&self) -> Result<bool, String> {
+        let Some(zone_path) = &self.zone_path else {
+            return Ok(false);
+        };
+
+        let (_, records) = Self::read_zone_file(self.origin().into(), zone_path)?;
+        self.authority.replace_records(records).await?;
+        Ok(true)
     }
 }
 
@@ -219,17 +255,27 @@ impl Authority for FileAuthority {
 impl DnssecAuthority for FileAuthority {
     /// Add a (Sig0) key that is authorized to perform updates against this authority
     async fn add_update_auth_key(&self, name: Name, key: KEY) -> DnsSecResult<()> {
-        self.0.add_update_auth_key(name, key).await
+        self.authority.add_update_auth_key(name, key).await
     }
 
     /// Add Signer
     async fn add_zone_signing_key(&self, signer: SigSigner) -> DnsSecResult<()> {
-        self.0.add_zone_signing_key(signer).await
+        self.authority.add_zone_signing_key(signer).await
     }
 
     /// Sign the zone for DNSSEC
     async fn secure_zone(&self) -> DnsSecResult<()> {
-        DnssecAuthority::secure_zone(&self.0).await
+        DnssecAuthority::secure_zone(&self.authority).await
+    }
+
+    /// Sets the timing of the signatures created from now on
+    async fn set_signature_timing(&self, timing: SignatureTiming) -> DnsSecResult<()> {
+        self.authority.set_signature_timing(timing).await
+    }
+
+    /// Signs again all RRsets with a signature expiring within the refresh window
+    async fn resign_expiring(&self) -> DnsSecResult<usize> {
+        self.authority.resign_expiring().await
     }
 }
 
@@ -5,30 +5,63 @@
 // https://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use std::io;
+use std::{
+    io,
+    net::IpAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use hickory_resolver::name_server::TokioConnectionProvider;
-use tracing::{debug, info};
+use hickory_resolver::name_server::{NameServerPool, TokioConnectionProvider};
+use tracing::{debug, info, warn};
 
+#[cfg(feature = "dnssec")]
+use crate::proto::rr::dnssec::Proof;
 use crate::{
     authority::{
-        Authority, LookupError, LookupObject, LookupOptions, MessageRequest, UpdateResult, ZoneType,
+        Authority, LookupError, LookupObject, LookupOptions, MessageRequest, UpdateResult,
+        UpstreamInfo, WorkClass, ZoneType,
     },
     proto::{
-        op::ResponseCode,
-        rr::{LowerName, Name, Record, RecordType},
+        op::{Edns, Message, MessageType, OpCode, Query, ResponseCode},
+        rr::{
+            rdata::{
+                opt::{EdnsCode, EdnsOption, ExtendedError},
+                A, AAAA,
+            },
+            LowerName, Name, RData, Record, RecordType,
+        },
+        xfer::{DnsRequest, DnsRequestOptions, FirstAnswer, UpstreamMetadata},
+        DnsHandle, RetryDnsHandle,
+    },
+    resolver::{
+        config::{NameServerConfigGroup, ResolverConfig, ResolverOpts},
+        error::ResolveError,
+        lookup::Lookup as ResolverLookup,
+        TokioAsyncResolver,
     },
-    resolver::{config::ResolverConfig, lookup::Lookup as ResolverLookup, TokioAsyncResolver},
     server::RequestInfo,
-    store::forwarder::ForwardConfig,
+    store::forwarder::{ForwardCacheConfig, ForwardConfig, ForwardValidation},
 };
 
+/// The payload size advertised in forwarded requests
+const MAX_PAYLOAD_LEN: u16 = 1232;
+
+/// The Extended DNS Error info code "Other Error", [RFC 8914](https://tools.ietf.org/html/rfc8914)
+const EDE_OTHER: u16 = 0;
+
 /// An authority that will forward resolutions to upstream resolvers.
 ///
-/// This uses the hickory-resolver for resolving requests.
+/// This uses the hickory-resolver for resolving requests. Requests which need the DO or CD bits
+///  passed upstream, see [`ForwardValidation`], are forwarded directly to the name servers of the
+///  resolver, bypassing its cache.
 pub struct ForwardAuthority {
     origin: LowerName,
     resolver: TokioAsyncResolver,
+    upstream: RetryDnsHandle<NameServerPool<TokioConnectionProvider>>,
+    validation: ForwardValidation,
+    trust_ad: bool,
+    debug_upstream: bool,
 }
 
 impl ForwardAuthority {
@@ -38,10 +71,18 @@ impl ForwardAuthority {
     pub fn new(runtime: TokioConnectionProvider) -> Result<Self, String> {
         let resolver = TokioAsyncResolver::from_system_conf(runtime)
             .map_err(|e| format!("error constructing new Resolver: {e}"))?;
+        let upstream = upstream(
+            resolver.config().name_servers().to_vec().into(),
+            resolver.options().clone(),
+        );
 
         Ok(Self {
             origin: Name::root().into(),
             resolver,
+            upstream,
+            validation: ForwardValidation::default(),
+            trust_ad: false,
+            debug_upstream: false,
         })
     }
 
@@ -75,9 +116,29 @@ impl ForwardAuthority {
             options.preserve_intermediates = true;
         }
 
+        if config.validation == ForwardValidation::Local {
+            if !cfg!(feature = "dnssec") {
+                return Err("local validation requires the dnssec feature".to_string());
+            }
+
+            options.validate = true;
+        } else if options.validate {
+            warn!(
+                "validate set in the forwarder options, but validation is {:?}; disabling it",
+                config.validation
+            );
+            options.validate = false;
+        }
+
+        let upstream = upstream(name_servers.clone(), options.clone());
+        let validation = config.validation;
+        let trust_ad = config.trust_ad;
+        let debug_upstream = config.debug_upstream;
+        let cache = &config.cache;
         let config = ResolverConfig::from_parts(None, vec![], name_servers);
 
         let resolver = TokioAsyncResolver::new(config, options, TokioConnectionProvider::default());
+        populate_cache(&resolver, cache)?;
 
         info!("forward resolver configured: {}: ", origin);
 
@@ -85,8 +146,196 @@ impl ForwardAuthority {
         Ok(Self {
             origin: origin.into(),
             resolver,
+            upstream,
+            validation,
+            trust_ad,
+            debug_upstream,
         })
     }
+
+    /// Where the DNSSEC records of forwarded responses are validated
+    pub fn validation(&self) -> ForwardValidation {
+        self.validation
+    }
+
+    /// Whether the AD bit of upstream responses is returned, see [`ForwardConfig::trust_ad`]
+    pub fn trust_ad(&self) -> bool {
+        self.trust_ad
+    }
+
+    /// Whether the upstream path is added to responses, see [`ForwardConfig::debug_upstream`]
+    pub fn debug_upstream(&self) -> bool {
+        self.debug_upstream
+    }
+
+    /// Collects what is passed on to the client about the upstream exchange
+    ///
+    /// With [`ForwardConfig::debug_upstream`], an extended DNS error with the upstream path is
+    ///  added to the extended errors of the upstream response.
+    fn upstream_info(
+        &self,
+        metadata: Option<UpstreamMetadata>,
+        mut extended_errors: Vec<ExtendedError>,
+    ) -> UpstreamInfo {
+        if self.debug_upstream {
+            let text = match &metadata {
+                Some(metadata) => format!("upstream {metadata}"),
+                None => "answered from cache".to_string(),
+            };
+            extended_errors.push(ExtendedError::new(EDE_OTHER, text));
+        }
+
+        UpstreamInfo::new(metadata, extended_errors)
+    }
+
+    /// Whether a request with the DO and CD bits is forwarded upstream directly, see
+    ///  [`ForwardValidation`], instead of resolved through the cache of the resolver
+    fn bypasses_cache(&self, dnssec_ok: bool, checking_disabled: bool) -> bool {
+        match self.validation {
+            ForwardValidation::Local => checking_disabled,
+            ForwardValidation::Upstream => true,
+            ForwardValidation::None => dnssec_ok,
+        }
+    }
+
+    /// Forwards the query to the upstream name servers with the DO and CD bits of the request,
+    ///  bypassing the cache of the resolver
+    ///
+    /// DNSSEC records are only returned if `dnssec_ok` is set, even if they were requested
+    ///  upstream for local validation.
+    async fn forward(
+        &self,
+        name: &LowerName,
+        rtype: RecordType,
+        dnssec_ok: bool,
+        checking_disabled: bool,
+    ) -> Result<ForwardLookup, LookupError> {
+        let upstream_dnssec_ok = dnssec_ok || self.validation == ForwardValidation::Local;
+        debug!(
+            "forwarding query: {} {} do:{} cd:{}",
+            name, rtype, upstream_dnssec_ok, checking_disabled
+        );
+
+        let query = Query::query(name.into(), rtype);
+        let mut message = Message::new();
+        message
+            .add_query(query.clone())
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(true)
+            .set_checking_disabled(checking_disabled);
+        message
+            .extensions_mut()
+            .get_or_insert_with(Edns::new)
+            .set_max_payload(MAX_PAYLOAD_LEN)
+            .set_version(0)
+            .set_dnssec_ok(upstream_dnssec_ok);
+
+        let mut options = DnsRequestOptions::default();
+        options.use_edns = true;
+        options.edns_set_dnssec_ok = upstream_dnssec_ok;
+
+        let mut response = self
+            .upstream
+            .send(DnsRequest::new(message, options))
+            .first_answer()
+            .await
+            .map_err(ResolveError::from)?;
+
+        let extended_errors = response
+            .extensions()
+            .as_ref()
+            .map(|edns| {
+                edns.options()
+                    .get_all(EdnsCode::EDE)
+                    .into_iter()
+                    .filter_map(|option| match option {
+                        EdnsOption::EDE(ede) => Some(ede.clone()),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let info = self.upstream_info(response.upstream().copied(), extended_errors);
+
+        let response_code = response.response_code();
+        if response_code != ResponseCode::NoError {
+            return Err(LookupError::Upstream(response_code, Box::new(info)));
+        }
+
+        let mut answers = response.take_answers();
+        if !dnssec_ok {
+            answers.retain(|record| !record.record_type().is_dnssec());
+        }
+
+        // the catalog sets the AD bit of the response from the proofs of the records
+        #[cfg(feature = "dnssec")]
+        if response.authentic_data()
+            && self.trust_ad
+            && self.validation == ForwardValidation::Upstream
+        {
+            for record in &mut answers {
+                record.set_proof(Proof::Secure);
+            }
+        }
+
+        Ok(ForwardLookup(
+            ResolverLookup::new_with_max_ttl(query, Arc::from(answers)),
+            info,
+        ))
+    }
+}
+
+/// Pins the configured cache entries, and inserts the prepopulated records
+fn populate_cache(
+    resolver: &TokioAsyncResolver,
+    config: &ForwardCacheConfig,
+) -> Result<(), String> {
+    let cache = resolver.cache();
+    let pin = |name: &Name, record_type| {
+        cache
+            .pin(name.clone(), record_type)
+            .map_err(|e| format!("failed to pin {name} {record_type}: {e}"))
+    };
+
+    for entry in &config.pinned {
+        pin(&entry.name, entry.record_type)?;
+    }
+
+    let mut records = vec![];
+    for entry in &config.prepopulate {
+        for addr in &entry.addrs {
+            let (rdata, record_type) = match *addr {
+                IpAddr::V4(addr) => (RData::A(A::from(addr)), RecordType::A),
+                IpAddr::V6(addr) => (RData::AAAA(AAAA::from(addr)), RecordType::AAAA),
+            };
+
+            pin(&entry.name, record_type)?;
+            records.push(Record::from_rdata(entry.name.clone(), 0, rdata));
+        }
+    }
+
+    if !records.is_empty() {
+        info!("prepopulating the cache with {} records", records.len());
+        cache.prepopulate(
+            records,
+            Duration::from_secs(u64::from(config.prepopulate_ttl)),
+            Instant::now(),
+        );
+    }
+
+    Ok(())
+}
+
+/// The handle for forwarding requests directly to the name servers
+fn upstream(
+    name_servers: NameServerConfigGroup,
+    options: ResolverOpts,
+) -> RetryDnsHandle<NameServerPool<TokioConnectionProvider>> {
+    let attempts = options.attempts;
+    let pool =
+        NameServerPool::from_config(name_servers, options, TokioConnectionProvider::default());
+    RetryDnsHandle::new(pool, attempts)
 }
 
 #[async_trait::async_trait]
@@ -128,9 +377,20 @@ impl Authority for ForwardAuthority {
 
         debug!("forwarding lookup: {} {}", name, rtype);
         let name: LowerName = name.clone();
-        let resolve = self.resolver.lookup(name, rtype).await;
+        let resolve = self.resolver.lookup(name, rtype).await?;
 
-        resolve.map(ForwardLookup).map_err(LookupError::from)
+        // the resolver only validates for local validation
+        #[cfg(feature = "dnssec")]
+        if resolve
+            .record_iter()
+            .any(|record| record.proof().is_bogus())
+        {
+            warn!("validation of {} {} failed", resolve.query().name(), rtype);
+            return Err(LookupError::from(ResponseCode::ServFail));
+        }
+
+        let info = self.upstream_info(resolve.upstream().copied(), vec![]);
+        Ok(ForwardLookup(resolve, info))
     }
 
     async fn search(
@@ -138,12 +398,39 @@ impl Authority for ForwardAuthority {
         request_info: RequestInfo<'_>,
         lookup_options: LookupOptions,
     ) -> Result<Self::Lookup, LookupError> {
-        self.lookup(
-            request_info.query.name(),
+        let name = request_info.query.name();
+        let rtype = request_info.query.query_type();
+        let dnssec_ok = lookup_options.is_dnssec();
+        let checking_disabled = request_info.header.checking_disabled();
+
+        if self.bypasses_cache(dnssec_ok, checking_disabled) {
+            self.forward(name, rtype, dnssec_ok, checking_disabled)
+                .await
+        } else {
+            self.lookup(name, rtype, lookup_options).await
+        }
+    }
+
+    /// Cheap if the query is answered from the cache of the resolver
+    fn work_class(
+        &self,
+        request_info: &RequestInfo<'_>,
+        lookup_options: LookupOptions,
+    ) -> WorkClass {
+        let checking_disabled = request_info.header.checking_disabled();
+        if self.bypasses_cache(lookup_options.is_dnssec(), checking_disabled) {
+            return WorkClass::Expensive;
+        }
+
+        let query = Query::query(
+            request_info.query.name().into(),
             request_info.query.query_type(),
-            lookup_options,
-        )
-        .await
+        );
+        if self.resolver.cache().contains(&query, Instant::now()) {
+            WorkClass::Cheap
+        } else {
+            WorkClass::Expensive
+        }
     }
 
     async fn get_nsec_records(
@@ -156,12 +443,16 @@ impl Authority for ForwardAuthority {
             "Getting NSEC records is unimplemented for the forwarder",
         )))
     }
+
+    fn dump_cache(&self, writer: &mut (dyn io::Write + Send)) -> io::Result<usize> {
+        self.resolver.cache().dump(writer, Instant::now())
+    }
 }
 
 /// A structure that holds the results of a forwarding lookup.
 ///
 /// This exposes an iterator interface for consumption downstream.
-pub struct ForwardLookup(pub ResolverLookup);
+pub struct ForwardLookup(pub ResolverLookup, UpstreamInfo);
 
 impl LookupObject for ForwardLookup {
     fn is_empty(&self) -> bool {
@@ -175,4 +466,8 @@ impl LookupObject for ForwardLookup {
     fn take_additionals(&mut self) -> Option<Box<dyn LookupObject>> {
         None
     }
+
+    fn upstream(&self) -> Option<&UpstreamInfo> {
+        Some(&self.1)
+    }
 }
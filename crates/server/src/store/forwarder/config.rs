@@ -5,8 +5,11 @@
 // https://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use std::net::IpAddr;
+
 use serde::Deserialize;
 
+use crate::proto::rr::{Name, RecordType};
 use crate::resolver::config::{NameServerConfigGroup, ResolverOpts};
 
 /// Configuration for file based zones
@@ -16,4 +19,84 @@ pub struct ForwardConfig {
     pub name_servers: NameServerConfigGroup,
     /// Resolver options
     pub options: Option<ResolverOpts>,
+    /// Where the DNSSEC records of forwarded responses are validated
+    #[serde(default)]
+    pub validation: ForwardValidation,
+    /// Returns the AD bit of upstream responses with [`ForwardValidation::Upstream`]
+    ///
+    /// Only enable this if the upstream name servers validate and the path to them is trusted.
+    #[serde(default)]
+    pub trust_ad: bool,
+    /// Cache entries of critical names, which must resolve even while the upstream is down
+    #[serde(default)]
+    pub cache: ForwardCacheConfig,
+    /// Adds an extended DNS error (code 0, Other) to forwarded responses, its extra text tells
+    ///  which upstream name server answered, over which protocol and after how many retries
+    ///
+    /// Meant for debugging, it discloses the upstream configuration to clients.
+    #[serde(default)]
+    pub debug_upstream: bool,
+}
+
+/// Cache entries which are pinned, or inserted at startup
+///
+/// See [`DnsLru::pin`](crate::resolver::dns_lru::DnsLru::pin) and
+///  [`DnsLru::prepopulate`](crate::resolver::dns_lru::DnsLru::prepopulate).
+#[derive(Clone, Deserialize, PartialEq, Eq, Debug)]
+#[serde(default)]
+pub struct ForwardCacheConfig {
+    /// Entries which are never evicted from the cache, they still expire with their TTL
+    pub pinned: Vec<PinnedEntry>,
+    /// Addresses inserted into the cache at startup, their entries are pinned
+    pub prepopulate: Vec<PrepopulatedAddresses>,
+    /// The TTL of the prepopulated records in seconds, defaults to one hour
+    pub prepopulate_ttl: u32,
+}
+
+impl Default for ForwardCacheConfig {
+    fn default() -> Self {
+        Self {
+            pinned: vec![],
+            prepopulate: vec![],
+            prepopulate_ttl: 3600,
+        }
+    }
+}
+
+/// The name and type of a pinned cache entry
+#[derive(Clone, Deserialize, PartialEq, Eq, Debug)]
+pub struct PinnedEntry {
+    /// The name of the entry
+    pub name: Name,
+    /// The record type of the entry
+    pub record_type: RecordType,
+}
+
+/// The addresses of a name, cached as A and AAAA records
+#[derive(Clone, Deserialize, PartialEq, Eq, Debug)]
+pub struct PrepopulatedAddresses {
+    /// The name of the records
+    pub name: Name,
+    /// The addresses of the name
+    pub addrs: Vec<IpAddr>,
+}
+
+/// Where the DNSSEC records of forwarded responses are validated
+///
+/// The DO bit of the request is only known with the `dnssec` feature, without it requests are
+///  always forwarded as if DO was not set.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum ForwardValidation {
+    /// Validate locally, DO is always set upstream and AD is set from the local result
+    ///
+    /// Bogus responses are answered with SERVFAIL. Requests with CD are forwarded without
+    ///  validation and never get AD. Requires the `dnssec` feature.
+    Local,
+    /// Delegate validation to the upstream name servers, DO and CD of the request are passed on
+    ///
+    /// AD of the upstream response is returned if `trust_ad` is set. Responses are not cached.
+    Upstream,
+    /// No validation, DO is only set upstream if the request has it, AD is never set
+    #[default]
+    None,
 }
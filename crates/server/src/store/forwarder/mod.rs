@@ -14,4 +14,6 @@ mod config;
 
 pub use self::authority::ForwardAuthority;
 pub use self::authority::ForwardLookup;
-pub use self::config::ForwardConfig;
+pub use self::config::{
+    ForwardCacheConfig, ForwardConfig, ForwardValidation, PinnedEntry, PrepopulatedAddresses,
+};
@@ -13,25 +13,36 @@ use std::borrow::Borrow;
 use std::ops::Deref;
 use std::{
     collections::{BTreeMap, HashSet},
+    fs,
     ops::DerefMut,
-    sync::Arc,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
 
 use cfg_if::cfg_if;
 use futures_util::future::{self, TryFutureExt};
 #[cfg(feature = "dnssec")]
+use futures_util::stream::{Stream, StreamExt};
+#[cfg(feature = "dnssec")]
+use serde::Deserialize;
+#[cfg(feature = "dnssec")]
 use time::OffsetDateTime;
 use tracing::{debug, error, warn};
 
-use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tokio::sync::{Notify, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 #[cfg(feature = "dnssec")]
 use crate::{
     authority::DnssecAuthority,
     proto::rr::dnssec::{
-        rdata::{key::KEY, DNSSECRData, NSEC},
-        {tbs, DnsSecResult, SigSigner, SupportedAlgorithms},
+        rdata::{key::KEY, DNSSECRData, DNSKEY, DS, NSEC},
+        {tbs, DigestType, DnsSecResult, SigSigner, SupportedAlgorithms},
     },
+    proto::rr::{rdata::Serial, RecordData},
 };
 
 use crate::{
@@ -40,15 +51,175 @@ use crate::{
         MessageRequest, UpdateResult, ZoneType,
     },
     proto::{
+        error::{ProtoError, ProtoResult},
         op::ResponseCode,
         rr::{
             rdata::SOA,
             {DNSClass, LowerName, Name, RData, Record, RecordSet, RecordType, RrKey},
         },
+        serialize::txt::Parser,
     },
     server::RequestInfo,
 };
 
+/// How long a query waits for the records of a zone file which is loaded in the background
+const LAZY_LOAD_WAIT: Duration = Duration::from_secs(1);
+
+/// How the serial of a signed zone is derived from the serial of the zone it was transferred from
+#[cfg(feature = "dnssec")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum SerialPolicy {
+    /// Start at the serial of the first transfer, then increment by one for every change
+    #[default]
+    Increment,
+    /// Use the current time in seconds since the unix epoch, or the previous serial plus one if
+    ///  that is larger
+    UnixTime,
+}
+
+#[cfg(feature = "dnssec")]
+impl SerialPolicy {
+    /// Returns the next serial of the signed zone
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - the serial of the signed zone, if any records were loaded before
+    /// * `source` - the serial of the transferred zone
+    pub fn next_serial(self, current: Option<u32>, source: u32) -> u32 {
+        match (self, current) {
+            (Self::Increment, Some(current)) => current.wrapping_add(1),
+            (Self::Increment, None) => source,
+            (Self::UnixTime, current) => {
+                let now = OffsetDateTime::now_utc().unix_timestamp() as u32;
+                // the serial must increase in sequence space arithmetic, RFC 1982
+                current.map_or(now, |current| {
+                    let next = Serial(current) + 1;
+                    if Serial(now) > next {
+                        now
+                    } else {
+                        next.0
+                    }
+                })
+            }
+        }
+    }
+}
+
+/// The timing of the signatures of a zone, all durations are in seconds
+///
+/// Signatures are valid from `now - inception_offset` until `now + validity`, the expiration of
+///  each RRset is brought forward by up to `jitter_percent` of the validity, so that the
+///  signatures of a zone do not all expire, and need to be refreshed, at the same time.
+#[cfg(feature = "dnssec")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+#[serde(default)]
+pub struct SignatureTiming {
+    /// How long signatures are valid, defaults to the signature duration of the key
+    pub validity: Option<u32>,
+    /// How far the inception of signatures is backdated, to allow for clock skew of validators
+    pub inception_offset: u32,
+    /// How long before their expiration signatures are refreshed, defaults to a quarter of the
+    ///  validity
+    pub refresh: Option<u32>,
+    /// The largest share of the validity, in percent, by which the expiration of an RRset is
+    ///  brought forward, values above 100 are treated as 100
+    pub jitter_percent: u8,
+}
+
+#[cfg(feature = "dnssec")]
+impl SignatureTiming {
+    /// Returns how long the signatures created by `signer` are valid
+    pub fn validity(&self, signer: &SigSigner) -> Duration {
+        self.validity.map_or_else(
+            || signer.sig_duration(),
+            |validity| Duration::from_secs(validity.into()),
+        )
+    }
+
+    /// Returns how long before their expiration signatures with `validity` are refreshed
+    pub fn refresh(&self, validity: Duration) -> Duration {
+        self.refresh
+            .map_or(validity / 4, |refresh| Duration::from_secs(refresh.into()))
+    }
+
+    /// Returns the largest amount by which the expiration of signatures with `validity` is
+    ///  brought forward
+    pub fn max_jitter(&self, validity: Duration) -> Duration {
+        Duration::from_secs(validity.as_secs() * u64::from(self.jitter_percent.min(100)) / 100)
+    }
+
+    /// Returns the inception and expiration of the signatures of an RRset created at `now`
+    ///
+    /// The jitter is derived from the name and type of the RRset, signing it again moves its
+    ///  expiration by the same amount.
+    pub fn signature_period(
+        &self,
+        validity: Duration,
+        name: &Name,
+        record_type: RecordType,
+        now: OffsetDateTime,
+    ) -> (OffsetDateTime, OffsetDateTime) {
+        let max_jitter = self.max_jitter(validity).as_secs();
+        let jitter = max_jitter * (rrset_hash(name, record_type) % 1024) / 1024;
+
+        let inception = now - Duration::from_secs(self.inception_offset.into());
+        let expiration = now + validity - Duration::from_secs(jitter);
+        (inception, expiration)
+    }
+
+    /// Returns the problems of this timing for a zone with the `soa` and the largest TTL `max_ttl`
+    ///
+    /// Signatures must outlive the records in caches, and the zone on secondaries which can not
+    ///  reach the primary.
+    pub fn check(&self, validity: Duration, max_ttl: u32, soa: &SOA) -> Vec<String> {
+        let shortest = (validity - self.max_jitter(validity)).as_secs();
+        let refresh = self.refresh(validity).as_secs();
+        let max_ttl = u64::from(max_ttl);
+
+        let mut problems = Vec::new();
+        if shortest <= max_ttl {
+            problems.push(format!(
+                "signature validity of {shortest}s is shorter than the max TTL of {max_ttl}s"
+            ));
+        }
+        if refresh < max_ttl {
+            problems.push(format!(
+                "signature refresh of {refresh}s is shorter than the max TTL of {max_ttl}s"
+            ));
+        }
+        if refresh >= shortest {
+            problems.push(format!(
+                "signature refresh of {refresh}s is not shorter than the validity of {shortest}s"
+            ));
+        }
+        if shortest <= u64::from(soa.expire().unsigned_abs()) {
+            problems.push(format!(
+                "signature validity of {shortest}s is shorter than the SOA expire of {}s",
+                soa.expire()
+            ));
+        }
+
+        problems
+    }
+}
+
+/// FNV-1a hash of the lowercase name and the type of an RRset, stable across restarts
+#[cfg(feature = "dnssec")]
+fn rrset_hash(name: &Name, record_type: RecordType) -> u64 {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+    name.to_lowercase()
+        .to_ascii()
+        .bytes()
+        .chain(u16::from(record_type).to_be_bytes())
+        .fold(FNV_OFFSET, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        })
+}
+
 /// InMemoryAuthority is responsible for storing the resource records for a particular zone.
 ///
 /// Authorities default to DNSClass IN. The ZoneType specifies if this should be treated as the
@@ -59,6 +230,8 @@ pub struct InMemoryAuthority {
     zone_type: ZoneType,
     allow_axfr: bool,
     inner: RwLock<InnerInMemory>,
+    records_loaded: AtomicBool,
+    records_notify: Notify,
 }
 
 impl InMemoryAuthority {
@@ -127,6 +300,8 @@ impl InMemoryAuthority {
             zone_type,
             allow_axfr,
             inner: RwLock::new(InnerInMemory::default()),
+            records_loaded: AtomicBool::new(true),
+            records_notify: Notify::new(),
         }
     }
 
@@ -171,6 +346,367 @@ impl InMemoryAuthority {
         &mut self.inner.get_mut().records
     }
 
+    /// Replaces all records in the zone, e.g. after the zone file was reloaded
+    ///
+    /// The new records are validated before anything is replaced, on error the existing records
+    /// are left untouched. If the zone has signing keys, the DNSKEYs are added to the new records
+    /// and the zone is signed again.
+    pub async fn replace_records(&self, records: BTreeMap<RrKey, RecordSet>) -> Result<(), String> {
+        let replacement = Self::new(
+            self.origin.clone().into(),
+            records,
+            self.zone_type,
+            self.allow_axfr,
+        )?;
+        #[allow(unused_mut)]
+        let mut replacement = replacement.inner.into_inner();
+
+        let mut inner = self.inner.write().await;
+
+        #[cfg(feature = "dnssec")]
+        if !inner.secure_keys.is_empty() {
+            let zone_ttl = replacement.minimum_ttl(&self.origin);
+            let serial = replacement.serial(&self.origin);
+            for signer in &inner.secure_keys {
+                let dnskey = signer
+                    .key()
+                    .to_dnskey(signer.algorithm())
+                    .map_err(|e| format!("failed to generate DNSKEY: {e}"))?;
+                let dnskey = Record::from_rdata(
+                    self.origin.clone().into(),
+                    zone_ttl,
+                    RData::DNSSEC(DNSSECRData::DNSKEY(dnskey)),
+                );
+                replacement.upsert(dnskey, serial, self.class);
+            }
+
+            replacement.secure_keys = std::mem::take(&mut inner.secure_keys);
+            replacement.signature_timing = inner.signature_timing;
+            if let Err(e) = replacement.secure_zone_mut(&self.origin, self.class) {
+                inner.secure_keys = replacement.secure_keys;
+                return Err(format!("failed to sign zone {}: {e}", self.origin));
+            }
+        }
+
+        *inner = replacement;
+        Ok(())
+    }
+
+    /// Loads the records of the zone file at `path` in the background, replacing all records of
+    /// the zone once it's parsed
+    ///
+    /// The file is read before this returns, so that it failing to open is reported. Parsing it
+    /// and inserting the records happens on a blocking task, until the records are loaded queries
+    /// wait for them briefly, and are answered with SERVFAIL if loading takes longer. A zone file
+    /// which fails to load is logged, and leaves the existing records in place.
+    ///
+    /// This must be called from within a Tokio runtime.
+    pub fn import_zone_file_lazy(self: &Arc<Self>, path: &Path) -> ProtoResult<()> {
+        let buf = fs::read_to_string(path)?;
+        self.records_loaded.store(false, Ordering::Release);
+
+        let this = Arc::clone(self);
+        let path = path.to_owned();
+        tokio::spawn(async move {
+            let origin = Name::from(this.origin.clone());
+            let zone_path = path.clone();
+            let parsed = tokio::task::spawn_blocking(move || {
+                Parser::new(buf, Some(zone_path), Some(origin)).parse()
+            })
+            .await;
+
+            let loaded = match parsed {
+                Ok(Ok((_, records))) => this.replace_records(records).await,
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+
+            match loaded {
+                Ok(()) => debug!("zone file loaded: {}", path.display()),
+                Err(e) => error!("failed to load zone file {}: {e}", path.display()),
+            }
+
+            this.records_loaded.store(true, Ordering::Release);
+            this.records_notify.notify_waiters();
+        });
+
+        Ok(())
+    }
+
+    /// Waits briefly for the records of a zone file that is loaded in the background, see
+    /// `import_zone_file_lazy`
+    async fn wait_for_records(&self) -> Result<(), LookupError> {
+        if self.records_loaded.load(Ordering::Acquire) {
+            return Ok(());
+        }
+
+        // the future receives `notify_waiters` from its creation, so the notification can't be
+        //  missed between checking the flag and waiting
+        let notified = self.records_notify.notified();
+        if self.records_loaded.load(Ordering::Acquire) {
+            return Ok(());
+        }
+
+        if tokio::time::timeout(LAZY_LOAD_WAIT, notified)
+            .await
+            .is_err()
+        {
+            warn!("records of {} are still loading", self.origin);
+            return Err(LookupError::from(ResponseCode::ServFail));
+        }
+
+        Ok(())
+    }
+
+    /// Loads and signs the records of a zone transfer, replacing all records of the zone
+    ///
+    /// The records are expected in the order of an AXFR, starting with the SOA record. Records of
+    /// the same RRset should follow each other, each RRset is signed as soon as it is complete,
+    /// so that no unsigned copy of the zone is kept. The NSEC chain, DNSKEY and SOA are generated
+    /// and signed once the stream ends, after which the new records replace the existing ones.
+    ///
+    /// Any RRSIG, NSEC or NSEC3 records in the transfer are dropped, these are generated for the
+    /// keys of this zone.
+    ///
+    /// # Arguments
+    ///
+    /// * `records` - the records as received in the zone transfer
+    /// * `serial_policy` - how the serial of the signed zone is derived
+    ///
+    /// # Return value
+    ///
+    /// The serial of the signed zone
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub async fn load_signed_transfer<S>(
+        &self,
+        mut records: S,
+        serial_policy: SerialPolicy,
+    ) -> Result<u32, String>
+    where
+        S: Stream<Item = Record> + Unpin,
+    {
+        let inner = self.inner.read().await;
+        if inner.secure_keys.is_empty() {
+            return Err(format!("no signing keys for zone: {}", self.origin));
+        }
+
+        let soa = match records.next().await {
+            Some(record) if record.record_type() == RecordType::SOA => record,
+            _ => return Err(format!("transfer must start with the SOA: {}", self.origin)),
+        };
+        self.check_transferred(&soa)?;
+
+        let current = inner.inner_soa(&self.origin).map(SOA::serial);
+        let source = soa
+            .data()
+            .as_soa()
+            .map(SOA::serial)
+            .ok_or_else(|| format!("invalid SOA in transfer: {}", self.origin))?;
+        let serial = serial_policy.next_serial(current, source);
+        let zone_ttl = soa.data().as_soa().map(SOA::minimum).unwrap_or_default();
+        let now = OffsetDateTime::now_utc();
+
+        let mut signed = BTreeMap::<RrKey, Arc<RecordSet>>::new();
+        let mut sign = |mut rr_set: RecordSet| -> Result<(), String> {
+            let rr_key = RrKey::new(rr_set.name().into(), rr_set.record_type());
+
+            // the RRset was split in the transfer, merge it with the already signed records
+            if let Some(existing) = signed.remove(&rr_key) {
+                let mut existing =
+                    Arc::try_unwrap(existing).unwrap_or_else(|existing| (*existing).clone());
+                for record in rr_set.records_without_rrsigs() {
+                    existing.insert(record.clone(), serial);
+                }
+                rr_set = existing;
+            }
+
+            InnerInMemory::sign_rrset(
+                &mut rr_set,
+                &inner.secure_keys,
+                &inner.signature_timing,
+                now,
+                zone_ttl,
+                self.class,
+            )
+            .map_err(|e| format!("failed to sign {rr_key:?}: {e}"))?;
+            signed.insert(rr_key, Arc::new(rr_set));
+            Ok(())
+        };
+
+        let mut rr_set: Option<RecordSet> = None;
+        while let Some(record) = records.next().await {
+            match record.record_type() {
+                // the closing SOA of the transfer
+                RecordType::SOA => continue,
+                RecordType::RRSIG
+                | RecordType::NSEC
+                | RecordType::NSEC3
+                | RecordType::NSEC3PARAM => {
+                    debug!("dropping {} from transfer", record.record_type());
+                    continue;
+                }
+                _ => self.check_transferred(&record)?,
+            }
+
+            match rr_set {
+                Some(ref mut rr_set)
+                    if rr_set.name() == record.name()
+                        && rr_set.record_type() == record.record_type() =>
+                {
+                    rr_set.insert(record, serial);
+                }
+                _ => {
+                    let mut next = RecordSet::new(record.name(), record.record_type(), serial);
+                    next.insert(record, serial);
+                    if let Some(complete) = rr_set.replace(next) {
+                        sign(complete)?;
+                    }
+                }
+            }
+        }
+        if let Some(complete) = rr_set {
+            sign(complete)?;
+        }
+        drop(sign);
+        drop(inner);
+
+        let mut soa = soa;
+        if let RData::SOA(soa) = soa.data_mut() {
+            *soa = SOA::new(
+                soa.mname().clone(),
+                soa.rname().clone(),
+                serial,
+                soa.refresh(),
+                soa.retry(),
+                soa.expire(),
+                soa.minimum(),
+            );
+        }
+        let mut soa_rrset = RecordSet::new(soa.name(), RecordType::SOA, serial);
+        soa_rrset.insert(soa, serial);
+        signed.insert(
+            RrKey::new(self.origin.clone(), RecordType::SOA),
+            Arc::new(soa_rrset),
+        );
+
+        let mut inner = self.inner.write().await;
+        let previous = std::mem::replace(&mut inner.records, signed);
+        if let Err(e) = inner.sign_generated(&self.origin, self.class) {
+            inner.records = previous;
+            return Err(format!("failed to sign zone {}: {e}", self.origin));
+        }
+
+        Ok(serial)
+    }
+
+    /// Applies the difference between two versions of a transferred zone, e.g. from an IXFR, and
+    /// signs the changed RRsets
+    ///
+    /// Only the changed RRsets, the NSEC records which changed with them and the SOA are signed
+    /// again, the signatures of all other RRsets are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `deleted` - the records removed from the zone
+    /// * `added` - the records added to the zone
+    /// * `serial_policy` - how the serial of the signed zone is derived
+    ///
+    /// # Return value
+    ///
+    /// The serial of the signed zone
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub async fn apply_signed_diff(
+        &self,
+        deleted: Vec<Record>,
+        added: Vec<Record>,
+        serial_policy: SerialPolicy,
+    ) -> Result<u32, String> {
+        let is_transferred = |record: &Record| {
+            !matches!(
+                record.record_type(),
+                RecordType::SOA
+                    | RecordType::RRSIG
+                    | RecordType::NSEC
+                    | RecordType::NSEC3
+                    | RecordType::NSEC3PARAM
+            )
+        };
+        for record in deleted
+            .iter()
+            .chain(added.iter())
+            .filter(|r| is_transferred(*r))
+        {
+            self.check_transferred(record)?;
+        }
+
+        let mut inner = self.inner.write().await;
+        let source = added
+            .iter()
+            .filter_map(|r| r.data().as_soa())
+            .map(SOA::serial)
+            .next();
+        let current = inner
+            .inner_soa(&self.origin)
+            .map(SOA::serial)
+            .ok_or_else(|| format!("SOA record must be present: {}", self.origin))?;
+        let serial = serial_policy.next_serial(Some(current), source.unwrap_or(current));
+        let previous = inner.records.clone();
+
+        let mut changed = HashSet::new();
+        for record in deleted.into_iter().filter(is_transferred) {
+            let rr_key = RrKey::new(record.name().into(), record.record_type());
+            let Some(rr_set) = inner.records.get_mut(&rr_key) else {
+                continue;
+            };
+
+            if Arc::make_mut(rr_set).remove(&record, serial) {
+                if rr_set.is_empty() {
+                    inner.records.remove(&rr_key);
+                }
+                changed.insert(rr_key);
+            }
+        }
+        for record in added.into_iter().filter(is_transferred) {
+            let rr_key = RrKey::new(record.name().into(), record.record_type());
+            let rr_set = inner.records.entry(rr_key.clone()).or_insert_with(|| {
+                Arc::new(RecordSet::new(record.name(), record.record_type(), serial))
+            });
+
+            if Arc::make_mut(rr_set).insert(record, serial) {
+                changed.insert(rr_key);
+            }
+        }
+
+        if let Err(e) = inner.resign_changed(&self.origin, self.class, serial, changed) {
+            inner.records = previous;
+            return Err(format!("failed to sign zone {}: {e}", self.origin));
+        }
+
+        Ok(serial)
+    }
+
+    /// Verifies that a transferred record belongs to this zone
+    #[cfg(feature = "dnssec")]
+    fn check_transferred(&self, record: &Record) -> Result<(), String> {
+        if record.dns_class() != self.class {
+            return Err(format!(
+                "{} {} has class {}, expected {} for zone: {}",
+                record.name(),
+                record.record_type(),
+                record.dns_class(),
+                self.class,
+                self.origin,
+            ));
+        }
+        if !self.origin.zone_of(&record.name().into()) {
+            return Err(format!("{} is not in zone: {}", record.name(), self.origin));
+        }
+
+        Ok(())
+    }
+
     /// Returns the minimum ttl (as used in the SOA record)
     pub async fn minimum_ttl(&self) -> u32 {
         self.inner.read().await.minimum_ttl(self.origin())
@@ -301,6 +837,130 @@ impl InMemoryAuthority {
         inner.get_mut().secure_zone_mut(origin, self.class)
     }
 
+    /// Signs the zone with `signer`, replacing the records with the signed ones
+    ///
+    /// The zone is signed on a copy of the records, on error the existing records are left
+    /// untouched. Signing again with a key that was already added only refreshes the signatures,
+    /// and does not increment the serial number of the zone.
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub fn sign(&mut self, signer: SigSigner) -> DnsSecResult<()> {
+        let Self {
+            ref origin,
+            ref mut inner,
+            class,
+            ..
+        } = self;
+        let inner = inner.get_mut();
+
+        let mut signed = InnerInMemory {
+            records: inner.records.clone(),
+            secure_keys: std::mem::take(&mut inner.secure_keys),
+            signature_timing: inner.signature_timing,
+        };
+        let key_count = signed.secure_keys.len();
+
+        match Self::inner_sign(&mut signed, signer, origin, *class) {
+            Ok(()) => {
+                *inner = signed;
+                Ok(())
+            }
+            Err(e) => {
+                signed.secure_keys.truncate(key_count);
+                inner.secure_keys = signed.secure_keys;
+                Err(e)
+            }
+        }
+    }
+
+    /// Adds `signer` unless its key is already known and signs all records, see `sign`
+    #[cfg(feature = "dnssec")]
+    fn inner_sign(
+        inner: &mut InnerInMemory,
+        signer: SigSigner,
+        origin: &LowerName,
+        dns_class: DNSClass,
+    ) -> DnsSecResult<()> {
+        let dnskey = signer.key().to_dnskey(signer.algorithm())?;
+        let mut known = false;
+        for secure_key in &inner.secure_keys {
+            if secure_key.key().to_dnskey(secure_key.algorithm())? == dnskey {
+                known = true;
+                break;
+            }
+        }
+        if !known {
+            Self::inner_add_zone_signing_key(inner, signer, origin, dns_class)?;
+        }
+
+        // the serial is left as is, signing the same zone again must not change it
+        inner.cds_zone(origin, dns_class)?;
+        inner.nsec_zone(origin, dns_class);
+        inner.sign_zone(origin, dns_class)
+    }
+
+    /// Returns true if the zone is signed, i.e. its SOA record has signatures
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub async fn is_signed(&self) -> bool {
+        let inner = self.inner.read().await;
+        let soa = RrKey::new(self.origin.clone(), RecordType::SOA);
+        inner
+            .records
+            .get(&soa)
+            .map_or(false, |rr_set| !rr_set.rrsigs().is_empty())
+    }
+
+    /// Sets the timing of the signatures created from now on
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub fn set_signature_timing_mut(&mut self, timing: SignatureTiming) {
+        self.inner.get_mut().signature_timing = timing;
+    }
+
+    /// Returns the problems of the signature timing for the records and signing keys of the zone
+    ///
+    /// The validity, without the jitter, must exceed the largest TTL and the SOA expire, and
+    ///  signatures must be refreshed while they are still valid for longer than the largest TTL.
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub async fn check_signature_timing(&self) -> Vec<String> {
+        let inner = self.inner.read().await;
+        let timing = &inner.signature_timing;
+
+        let validity = inner
+            .secure_keys
+            .iter()
+            .map(|signer| timing.validity(signer))
+            .min();
+        let (Some(validity), Some(soa)) = (validity, inner.inner_soa(&self.origin)) else {
+            return Vec::new();
+        };
+        let max_ttl = inner
+            .records
+            .values()
+            .map(|rr_set| rr_set.ttl())
+            .max()
+            .unwrap_or_default();
+
+        timing.check(validity, max_ttl, soa)
+    }
+
+    /// Signs again all RRsets with a signature expiring within the refresh window at `now`
+    ///
+    /// If any RRset was signed again, the serial is incremented and the SOA is signed as well.
+    ///
+    /// # Return value
+    ///
+    /// The number of RRsets signed again, not counting the SOA unless its signature was expiring
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub async fn resign_expiring_at(&self, now: OffsetDateTime) -> DnsSecResult<usize> {
+        let mut inner = self.inner.write().await;
+
+        inner.resign_expiring(&self.origin, self.class, now)
+    }
+
     /// (Re)generates the nsec records, increments the serial number and signs the zone
     #[cfg(not(feature = "dnssec"))]
     #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
@@ -319,6 +979,8 @@ struct InnerInMemory {
     //   for this, in some form, perhaps alternate root zones...
     #[cfg(feature = "dnssec")]
     secure_keys: Vec<SigSigner>,
+    #[cfg(feature = "dnssec")]
+    signature_timing: SignatureTiming,
 }
 
 impl InnerInMemory {
@@ -339,14 +1001,19 @@ impl InnerInMemory {
     // }
 
     fn inner_soa(&self, origin: &LowerName) -> Option<&SOA> {
+        self.soa_record(origin)
+            .map(Record::data)
+            .and_then(RData::as_soa)
+    }
+
+    /// Returns the SOA record of the zone, without its RRSIGs
+    fn soa_record(&self, origin: &LowerName) -> Option<&Record> {
         // TODO: can't there be an RrKeyRef?
         let rr_key = RrKey::new(origin.clone(), RecordType::SOA);
 
         self.records
             .get(&rr_key)
             .and_then(|rrset| rrset.records_without_rrsigs().next())
-            .map(Record::data)
-            .and_then(RData::as_soa)
     }
 
     /// Returns the minimum ttl (as used in the SOA record)
@@ -486,6 +1153,11 @@ impl InnerInMemory {
                 query_types_arr = [RecordType::A, RecordType::AAAA];
                 &query_types_arr[..]
             }
+            #[cfg(feature = "unstable-deleg")]
+            RecordType::DELEG => {
+                query_types_arr = [RecordType::A, RecordType::AAAA];
+                &query_types_arr[..]
+            }
             _ => &query_types_arr[..1],
         };
 
@@ -648,6 +1320,9 @@ impl InnerInMemory {
     #[cfg(feature = "dnssec")]
     #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
     fn secure_zone_mut(&mut self, origin: &LowerName, dns_class: DNSClass) -> DnsSecResult<()> {
+        // the CDS and CDNSKEY records are signed like all other records, and need to be in the NSEC type bitmaps
+        self.cds_zone(origin, dns_class)?;
+
         // TODO: only call nsec_zone after adds/deletes
         // needs to be called before incrementing the soa serial, to make sure IXFR works properly
         self.nsec_zone(origin, dns_class);
@@ -660,6 +1335,67 @@ impl InnerInMemory {
         self.sign_zone(origin, dns_class)
     }
 
+    /// (Re)generates the CDS and CDNSKEY records from the secure entry points in the DNSKEY RRset
+    ///
+    /// The DNSKEY RRset includes any public only keys of other signers, in a multi-signer setup,
+    ///  [RFC 8901](https://datatracker.ietf.org/doc/html/rfc8901#section-8), all providers then
+    ///  publish the same CDS and CDNSKEY RRsets to the parent.
+    #[cfg(feature = "dnssec")]
+    fn cds_zone(&mut self, origin: &LowerName, dns_class: DNSClass) -> DnsSecResult<()> {
+        // only publish CDS and CDNSKEY records for secure zones
+        if self.secure_keys.is_empty() {
+            return Ok(());
+        }
+        debug!("generating cds and cdnskey records: {}", origin);
+
+        self.records
+            .remove(&RrKey::new(origin.clone(), RecordType::CDS));
+        self.records
+            .remove(&RrKey::new(origin.clone(), RecordType::CDNSKEY));
+
+        let dnskeys = self
+            .records
+            .get(&RrKey::new(origin.clone(), RecordType::DNSKEY))
+            .map(|rrset| {
+                rrset
+                    .records_without_rrsigs()
+                    .filter_map(|record| DNSKEY::try_borrow(record.data()))
+                    .filter(|dnskey| dnskey.secure_entry_point() && !dnskey.revoke())
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let name = Name::from(origin);
+        let ttl = self.minimum_ttl(origin);
+        let serial = self.serial(origin);
+        for dnskey in dnskeys {
+            let digest = dnskey.to_digest(&name, DigestType::SHA256)?;
+            let cds = DS::new(
+                dnskey.calculate_key_tag()?,
+                dnskey.algorithm(),
+                DigestType::SHA256,
+                digest.as_ref().to_owned(),
+            );
+
+            let cds = Record::from_rdata(
+                name.clone(),
+                ttl,
+                RData::DNSSEC(DNSSECRData::CDS(cds.into())),
+            );
+            let cdnskey = Record::from_rdata(
+                name.clone(),
+                ttl,
+                RData::DNSSEC(DNSSECRData::CDNSKEY(dnskey.into())),
+            );
+
+            self.upsert(cds, serial, dns_class);
+            self.upsert(cdnskey, serial, dns_class);
+        }
+
+        Ok(())
+    }
+
     /// Dummy implementation for when DNSSEC is disabled.
     #[cfg(feature = "dnssec")]
     fn nsec_zone(&mut self, origin: &LowerName, dns_class: DNSClass) {
@@ -730,19 +1466,21 @@ impl InnerInMemory {
     ///
     /// * `rr_set` - RecordSet to sign
     /// * `secure_keys` - Set of keys to use to sign the RecordSet, see `self.signers()`
+    /// * `timing` - the timing of the signatures, see `self.signature_timing`
+    /// * `now` - the time of signing
     /// * `zone_ttl` - the zone TTL, see `self.minimum_ttl()`
     /// * `zone_class` - DNSClass of the zone, see `self.zone_class()`
     #[cfg(feature = "dnssec")]
     fn sign_rrset(
         rr_set: &mut RecordSet,
         secure_keys: &[SigSigner],
+        timing: &SignatureTiming,
+        now: OffsetDateTime,
         zone_ttl: u32,
         zone_class: DNSClass,
     ) -> DnsSecResult<()> {
         use crate::proto::rr::dnssec::rdata::RRSIG;
 
-        let inception = OffsetDateTime::now_utc();
-
         rr_set.clear_rrsigs();
 
         let rrsig_temp = Record::update0(rr_set.name().clone(), zone_ttl, RecordType::RRSIG);
@@ -755,7 +1493,12 @@ impl InnerInMemory {
                 signer.algorithm(),
             );
 
-            let expiration = inception + signer.sig_duration();
+            let (inception, expiration) = timing.signature_period(
+                timing.validity(signer),
+                rr_set.name(),
+                rr_set.record_type(),
+                now,
+            );
 
             let tbs = tbs::rrset_tbs(
                 rr_set.name(),
@@ -822,12 +1565,194 @@ impl InnerInMemory {
         Ok(())
     }
 
+    /// Adds the DNSKEYs, CDS, CDNSKEY and NSEC records to an otherwise signed zone, and signs them
+    ///  together with the SOA
+    #[cfg(feature = "dnssec")]
+    fn sign_generated(&mut self, origin: &LowerName, dns_class: DNSClass) -> DnsSecResult<()> {
+        let zone_ttl = self.minimum_ttl(origin);
+        let serial = self.serial(origin);
+        let now = OffsetDateTime::now_utc();
+        let mut dnskeys = Vec::with_capacity(self.secure_keys.len());
+        for signer in &self.secure_keys {
+            let dnskey = signer.key().to_dnskey(signer.algorithm())?;
+            dnskeys.push(Record::from_rdata(
+                origin.clone().into(),
+                zone_ttl,
+                RData::DNSSEC(DNSSECRData::DNSKEY(dnskey)),
+            ));
+        }
+        for dnskey in dnskeys {
+            self.upsert(dnskey, serial, dns_class);
+        }
+
+        self.cds_zone(origin, dns_class)?;
+        self.nsec_zone(origin, dns_class);
+
+        for (rr_key, rr_set) in self.records.iter_mut() {
+            if matches!(
+                rr_key.record_type,
+                RecordType::SOA
+                    | RecordType::DNSKEY
+                    | RecordType::CDS
+                    | RecordType::CDNSKEY
+                    | RecordType::NSEC
+            ) {
+                let rr_set = Arc::make_mut(rr_set);
+                Self::sign_rrset(
+                    rr_set,
+                    &self.secure_keys,
+                    &self.signature_timing,
+                    now,
+                    zone_ttl,
+                    dns_class,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets the serial of the SOA, regenerates the NSEC chain, and signs the SOA, the `changed`
+    ///  RRsets and any NSEC records which differ from before
+    #[cfg(feature = "dnssec")]
+    fn resign_changed(
+        &mut self,
+        origin: &LowerName,
+        dns_class: DNSClass,
+        serial: u32,
+        mut changed: HashSet<RrKey>,
+    ) -> DnsSecResult<()> {
+        let soa_key = RrKey::new(origin.clone(), RecordType::SOA);
+        let mut soa = self
+            .records
+            .get(&soa_key)
+            .and_then(|rr_set| rr_set.records_without_rrsigs().next().cloned())
+            .ok_or_else(|| format!("SOA record must be present: {origin}"))?;
+        if let RData::SOA(soa) = soa.data_mut() {
+            *soa = SOA::new(
+                soa.mname().clone(),
+                soa.rname().clone(),
+                serial,
+                soa.refresh(),
+                soa.retry(),
+                soa.expire(),
+                soa.minimum(),
+            );
+        }
+        let mut soa_rrset = RecordSet::new(soa.name(), RecordType::SOA, serial);
+        soa_rrset.insert(soa, serial);
+        self.records.insert(soa_key.clone(), Arc::new(soa_rrset));
+        changed.insert(soa_key);
+
+        // the NSEC records of unchanged names keep their signatures
+        let previous_nsec = self
+            .records
+            .iter()
+            .filter(|(rr_key, _)| rr_key.record_type == RecordType::NSEC)
+            .map(|(rr_key, rr_set)| (rr_key.clone(), rr_set.clone()))
+            .collect::<BTreeMap<_, _>>();
+        self.nsec_zone(origin, dns_class);
+        for (rr_key, rr_set) in self.records.iter_mut() {
+            if rr_key.record_type != RecordType::NSEC {
+                continue;
+            }
+
+            match previous_nsec.get(rr_key) {
+                Some(previous)
+                    if previous
+                        .records_without_rrsigs()
+                        .eq(rr_set.records_without_rrsigs()) =>
+                {
+                    *rr_set = previous.clone()
+                }
+                _ => {
+                    changed.insert(rr_key.clone());
+                }
+            }
+        }
+
+        let zone_ttl = self.minimum_ttl(origin);
+        let now = OffsetDateTime::now_utc();
+        for rr_key in changed {
+            if let Some(rr_set) = self.records.get_mut(&rr_key) {
+                let rr_set = Arc::make_mut(rr_set);
+                Self::sign_rrset(
+                    rr_set,
+                    &self.secure_keys,
+                    &self.signature_timing,
+                    now,
+                    zone_ttl,
+                    dns_class,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Signs again the RRsets with a signature expiring within the refresh window at `now`, see
+    ///  `InMemoryAuthority::resign_expiring_at`
+    #[cfg(feature = "dnssec")]
+    fn resign_expiring(
+        &mut self,
+        origin: &LowerName,
+        dns_class: DNSClass,
+        now: OffsetDateTime,
+    ) -> DnsSecResult<usize> {
+        use crate::proto::rr::dnssec::rdata::RRSIG;
+
+        let timing = self.signature_timing;
+        let Some(refresh) = self
+            .secure_keys
+            .iter()
+            .map(|signer| timing.refresh(timing.validity(signer)))
+            .max()
+        else {
+            return Ok(0);
+        };
+        let refresh_until = Serial(now.unix_timestamp() as u32) + refresh.as_secs() as u32;
+
+        let mut expiring = self
+            .records
+            .iter()
+            .filter(|(_, rr_set)| {
+                rr_set.rrsigs().iter().any(|rrsig| {
+                    RRSIG::try_borrow(rrsig.data()).map_or(false, |rrsig| {
+                        Serial(rrsig.sig_expiration()) <= refresh_until
+                    })
+                })
+            })
+            .map(|(rr_key, _)| rr_key.clone())
+            .collect::<HashSet<_>>();
+        if expiring.is_empty() {
+            return Ok(0);
+        }
+
+        debug!("signing {} expiring RRsets of {}", expiring.len(), origin);
+        let count = expiring.len();
+
+        // secondaries only transfer the new signatures with a new serial
+        self.increment_soa_serial(origin, dns_class);
+        expiring.insert(RrKey::new(origin.clone(), RecordType::SOA));
+
+        let zone_ttl = self.minimum_ttl(origin);
+        for rr_key in expiring {
+            if let Some(rr_set) = self.records.get_mut(&rr_key) {
+                let rr_set = Arc::make_mut(rr_set);
+                Self::sign_rrset(rr_set, &self.secure_keys, &timing, now, zone_ttl, dns_class)?;
+            }
+        }
+
+        Ok(count)
+    }
+
     /// Signs any records in the zone that have serial numbers greater than or equal to `serial`
     #[cfg(feature = "dnssec")]
     fn sign_zone(&mut self, origin: &LowerName, dns_class: DNSClass) -> DnsSecResult<()> {
         debug!("signing zone: {}", origin);
 
         let minimum_ttl = self.minimum_ttl(origin);
+        let now = OffsetDateTime::now_utc();
         let secure_keys = &self.secure_keys;
         let records = &mut self.records;
 
@@ -843,7 +1768,14 @@ impl InnerInMemory {
         for rr_set_orig in records.values_mut() {
             // because the rrset is an Arc, it must be cloned before mutated
             let rr_set = Arc::make_mut(rr_set_orig);
-            Self::sign_rrset(rr_set, secure_keys, minimum_ttl, dns_class)?;
+            Self::sign_rrset(
+                rr_set,
+                secure_keys,
+                &self.signature_timing,
+                now,
+                minimum_ttl,
+                dns_class,
+            )?;
         }
 
         Ok(())
@@ -899,6 +1831,18 @@ fn maybe_next_name(
             .map(|srv| srv.target().clone())
             .map(LowerName::from)
             .map(|name| (name, t)),
+        // DELEG is served by the parent at the delegation point, like NS the addresses of the
+        //  target are added unless they are in the parameters
+        #[cfg(feature = "unstable-deleg")]
+        (t @ RecordType::DELEG, RecordType::DELEG) => record_set
+            .records_without_rrsigs()
+            .next()
+            .map(Record::data)
+            .and_then(RData::as_deleg)
+            .filter(|deleg| deleg.svc_priority() != 0 && deleg.ip_hints().next().is_none())
+            .map(|deleg| deleg.target_name().clone())
+            .map(LowerName::from)
+            .map(|name| (name, t)),
         // other additional collectors can be added here can be added here
         _ => None,
     }
@@ -1004,6 +1948,7 @@ impl Authority for InMemoryAuthority {
         query_type: RecordType,
         lookup_options: LookupOptions,
     ) -> Result<Self::Lookup, LookupError> {
+        self.wait_for_records().await?;
         let inner = self.inner.read().await;
 
         // Collect the records from each rr_set
@@ -1096,6 +2041,8 @@ impl Authority for InMemoryAuthority {
                                         InnerInMemory::sign_rrset(
                                             &mut new_answer,
                                             inner.secure_keys(),
+                                            &inner.signature_timing,
+                                            OffsetDateTime::now_utc(),
                                             inner.minimum_ttl(self.origin()),
                                             self.class(),
                                         )
@@ -1312,6 +2259,19 @@ impl Authority for InMemoryAuthority {
     ) -> Result<Self::Lookup, LookupError> {
         Ok(AuthLookup::default())
     }
+
+    /// Returns the SOA record at the apex of the zone, taken directly from the records
+    async fn zone_apex(&self) -> ProtoResult<Record> {
+        self.wait_for_records()
+            .await
+            .map_err(|e| ProtoError::from(format!("no SOA for {}: {e}", self.origin)))?;
+        let inner = self.inner.read().await;
+
+        inner
+            .soa_record(&self.origin)
+            .cloned()
+            .ok_or_else(|| format!("SOA record must be present: {}", self.origin).into())
+    }
 }
 
 #[cfg(feature = "dnssec")]
@@ -1342,4 +2302,20 @@ impl DnssecAuthority for InMemoryAuthority {
 
         inner.secure_zone_mut(self.origin(), self.class)
     }
+
+    /// Sets the timing of the signatures created from now on, and warns about any problems of the
+    ///  timing for the zone, see `check_signature_timing`
+    async fn set_signature_timing(&self, timing: SignatureTiming) -> DnsSecResult<()> {
+        self.inner.write().await.signature_timing = timing;
+
+        for problem in self.check_signature_timing().await {
+            warn!("zone {}: {}", self.origin, problem);
+        }
+        Ok(())
+    }
+
+    /// Signs again all RRsets with a signature expiring within the refresh window
+    async fn resign_expiring(&self) -> DnsSecResult<usize> {
+        self.resign_expiring_at(OffsetDateTime::now_utc()).await
+    }
 }
@@ -10,3 +10,6 @@
 mod authority;
 
 pub use self::authority::InMemoryAuthority;
+#[cfg(feature = "dnssec")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+pub use self::authority::{SerialPolicy, SignatureTiming};
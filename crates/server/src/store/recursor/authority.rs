@@ -11,7 +11,8 @@ use tracing::{debug, info};
 
 use crate::{
     authority::{
-        Authority, LookupError, LookupObject, LookupOptions, MessageRequest, UpdateResult, ZoneType,
+        Authority, LookupError, LookupObject, LookupOptions, MessageRequest, UpdateResult,
+        WorkClass, ZoneType,
     },
     proto::{
         op::{Query, ResponseCode},
@@ -19,7 +20,8 @@ use crate::{
     },
     recursor::Recursor,
     resolver::{
-        config::{NameServerConfig, NameServerConfigGroup, Protocol},
+        config::{NameServerConfig, NameServerConfigGroup, Protocol, ResolverConfig, ResolverOpts},
+        dns_lru::TtlConfig,
         lookup::Lookup,
     },
     server::RequestInfo,
@@ -35,6 +37,30 @@ pub struct RecursiveAuthority {
 }
 
 impl RecursiveAuthority {
+    /// Construct a recursive authority for the root zone
+    ///
+    /// The name servers of the resolver configuration are used as the root hints. The size and the
+    ///  TTLs of the record cache are taken from the options, the recursor is security-aware if
+    ///  `validate` is set.
+    pub fn new(config: ResolverConfig, opts: ResolverOpts) -> Result<Self, String> {
+        let roots = NameServerConfigGroup::from(config.name_servers().to_vec());
+
+        let mut recursor = Recursor::builder();
+        recursor
+            .record_cache_size(opts.cache_size)
+            .ttl_config(TtlConfig::from_opts(&opts));
+        #[cfg(feature = "dnssec")]
+        recursor.security_aware(opts.validate);
+        let recursor = recursor
+            .build(roots)
+            .map_err(|e| format!("failed to initialize recursor: {e}"))?;
+
+        Ok(Self {
+            origin: Name::root().into(),
+            recursor,
+        })
+    }
+
     /// Read the Authority for the origin from the specified configuration
     pub async fn try_from_config(
         origin: Name,
@@ -76,7 +102,9 @@ impl RecursiveAuthority {
         let mut recursor = Recursor::builder();
         recursor
             .ns_cache_size(config.ns_cache_size)
-            .record_cache_size(config.record_cache_size);
+            .record_cache_size(config.record_cache_size)
+            .ttl_config(config.ttl_config())
+            .server_failure_policy(config.server_failure_policy);
         #[cfg(feature = "dnssec")]
         recursor.security_aware(config.security_aware);
         let recursor = recursor
@@ -149,6 +177,23 @@ impl Authority for RecursiveAuthority {
         .await
     }
 
+    /// Cheap if the query is answered from the cache of the recursor
+    fn work_class(
+        &self,
+        request_info: &RequestInfo<'_>,
+        _lookup_options: LookupOptions,
+    ) -> WorkClass {
+        let query = Query::query(
+            request_info.query.name().into(),
+            request_info.query.query_type(),
+        );
+        if self.recursor.is_cached(&query, Instant::now()) {
+            WorkClass::Cheap
+        } else {
+            WorkClass::Expensive
+        }
+    }
+
     async fn get_nsec_records(
         &self,
         _name: &LowerName,
@@ -159,6 +204,10 @@ impl Authority for RecursiveAuthority {
             "Getting NSEC records is unimplemented for the recursor",
         )))
     }
+
+    fn dump_cache(&self, writer: &mut (dyn io::Write + Send)) -> io::Result<usize> {
+        self.recursor.cache().dump(writer, Instant::now())
+    }
 }
 
 pub struct RecursiveLookup(Lookup);
@@ -11,6 +11,7 @@ use std::{
     io::Read,
     net::SocketAddr,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use serde::Deserialize;
@@ -20,7 +21,11 @@ use crate::proto::{
     rr::{RData, Record, RecordSet},
     serialize::txt::Parser,
 };
-use crate::resolver::Name;
+use crate::resolver::{
+    config::{ResolverOpts, ServerFailurePolicy},
+    dns_lru::TtlConfig,
+    Name,
+};
 
 /// Configuration for file based zones
 #[derive(Clone, Deserialize, Eq, PartialEq, Debug)]
@@ -37,6 +42,24 @@ pub struct RecursiveConfig {
     #[serde(default = "record_cache_size_default")]
     pub record_cache_size: usize,
 
+    /// Handling of SERVFAIL and other error responses from the nameservers of a zone, by default
+    ///  the remaining nameservers are tried, `FirstAnswer` returns the first response instead
+    #[serde(default)]
+    pub server_failure_policy: ServerFailurePolicy,
+
+    /// Minimum TTL in seconds of cached delegation data, i.e. NS records, DS records and glue
+    #[serde(default)]
+    pub infrastructure_min_ttl: Option<u64>,
+
+    /// Maximum TTL in seconds of cached delegation data, by default 6 hours
+    #[serde(default)]
+    pub infrastructure_max_ttl: Option<u64>,
+
+    /// Maximum random reduction in seconds of the TTL of cached delegation data, so that the
+    ///  delegations of one response don't expire at the same time
+    #[serde(default)]
+    pub infrastructure_ttl_jitter: Option<u64>,
+
     /// Whether the recursor is security-aware (RFC4035 section 3.2)
     #[cfg(feature = "dnssec")]
     #[serde(default)]
@@ -44,6 +67,15 @@ pub struct RecursiveConfig {
 }
 
 impl RecursiveConfig {
+    /// The TTL configuration of the record cache
+    pub(crate) fn ttl_config(&self) -> TtlConfig {
+        let mut opts = ResolverOpts::default();
+        opts.infrastructure_min_ttl = self.infrastructure_min_ttl.map(Duration::from_secs);
+        opts.infrastructure_max_ttl = self.infrastructure_max_ttl.map(Duration::from_secs);
+        opts.infrastructure_ttl_jitter = self.infrastructure_ttl_jitter.map(Duration::from_secs);
+        TtlConfig::from_opts(&opts)
+    }
+
     pub(crate) fn read_roots(
         &self,
         root_dir: Option<&Path>,
@@ -20,6 +20,7 @@ use crate::{
     authority::{Authority, LookupError, LookupOptions, MessageRequest, UpdateResult, ZoneType},
     error::{PersistenceErrorKind, PersistenceResult},
     proto::{
+        error::ProtoResult,
         op::ResponseCode,
         rr::{DNSClass, LowerName, Name, RData, Record, RecordSet, RecordType, RrKey},
     },
@@ -36,6 +37,7 @@ use crate::{
         rdata::{key::KEY, DNSSECRData},
         DnsSecResult, SigSigner, Verifier,
     },
+    store::in_memory::SignatureTiming,
 };
 
 /// SqliteAuthority is responsible for storing the resource records for a particular zone.
@@ -980,6 +982,11 @@ impl Authority for SqliteAuthority {
     ) -> Result<Self::Lookup, LookupError> {
         self.in_memory.get_nsec_records(name, lookup_options).await
     }
+
+    /// Returns the SOA record at the apex of the zone
+    async fn zone_apex(&self) -> ProtoResult<Record> {
+        self.in_memory.zone_apex().await
+    }
 }
 
 #[cfg(feature = "dnssec")]
@@ -1003,6 +1010,16 @@ impl DnssecAuthority for SqliteAuthority {
     async fn secure_zone(&self) -> DnsSecResult<()> {
         self.in_memory.secure_zone().await
     }
+
+    /// Sets the timing of the signatures created from now on
+    async fn set_signature_timing(&self, timing: SignatureTiming) -> DnsSecResult<()> {
+        self.in_memory.set_signature_timing(timing).await
+    }
+
+    /// Signs again all RRsets with a signature expiring within the refresh window
+    async fn resign_expiring(&self) -> DnsSecResult<usize> {
+        self.in_memory.resign_expiring().await
+    }
 }
 
 #[cfg(test)]
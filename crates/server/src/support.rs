@@ -0,0 +1,275 @@
+// Copyright 2015-2024 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Support bundles, a snapshot of the server state to attach to bug reports
+//!
+//! A bundle is plain text split into sections, each introduced by a `# <section>` line. The
+//!  configuration is included with all secrets redacted, zones and cache entries are written as
+//!  newline-delimited JSON.
+
+use std::{collections::BTreeMap, io};
+
+use serde::Serialize;
+
+#[cfg(feature = "dnssec")]
+use crate::proto::rr::dnssec::SupportedAlgorithms;
+use crate::{
+    authority::{AuthorityObject, LookupOptions, ZoneType},
+    proto::rr::{RData, RecordType},
+};
+
+/// Value written in place of redacted configuration values
+pub const REDACTED: &str = "<redacted>";
+
+/// Summary of a single zone of the catalog
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ZoneSummary {
+    /// Origin of the zone
+    pub origin: String,
+    /// Where the zone data comes from
+    pub zone_type: ZoneType,
+    /// Serial of the SOA record, if the zone has one
+    pub serial: Option<u32>,
+    /// True if the zone contains DNSSEC signatures or keys
+    pub signed: bool,
+    /// Number of records in the zone by record type
+    pub records: BTreeMap<String, usize>,
+}
+
+impl ZoneSummary {
+    /// Summarize the zone of the authority
+    ///
+    /// Records are only counted for the zones held by this server, i.e. primary and secondary
+    ///  zones, forwarded and recursive zones have none.
+    pub async fn of(authority: &dyn AuthorityObject) -> Self {
+        let zone_type = authority.zone_type();
+        let apex = authority.zone_apex().await.ok();
+        let serial = apex.as_ref().and_then(|apex| match apex.data() {
+            RData::SOA(soa) => Some(soa.serial()),
+            _ => None,
+        });
+
+        let mut records = BTreeMap::new();
+        if matches!(zone_type, ZoneType::Primary | ZoneType::Secondary) {
+            #[cfg(feature = "dnssec")]
+            let lookup_options = LookupOptions::for_dnssec(true, SupportedAlgorithms::all());
+            #[cfg(not(feature = "dnssec"))]
+            let lookup_options = LookupOptions::default();
+
+            if let Ok(lookup) = authority
+                .lookup(authority.origin(), RecordType::AXFR, lookup_options)
+                .await
+            {
+                for record in lookup.iter() {
+                    *records.entry(record.record_type().to_string()).or_default() += 1;
+                }
+            }
+
+            // the SOA is only sent around a zone transfer, it is not part of it
+            if apex.is_some() {
+                *records.entry(RecordType::SOA.to_string()).or_default() += 1;
+            }
+        }
+
+        let signed = records.contains_key(&RecordType::RRSIG.to_string())
+            || records.contains_key(&RecordType::DNSKEY.to_string());
+
+        Self {
+            origin: authority.origin().to_string(),
+            zone_type,
+            serial,
+            signed,
+            records,
+        }
+    }
+}
+
+/// Write a summary of each authority as newline-delimited JSON, ordered by origin
+///
+/// Returns the number of zones written.
+pub async fn write_zone_summaries(
+    authorities: &[&dyn AuthorityObject],
+    writer: &mut (dyn io::Write + Send),
+) -> io::Result<usize> {
+    let mut summaries = Vec::with_capacity(authorities.len());
+    for authority in authorities {
+        summaries.push(ZoneSummary::of(*authority).await);
+    }
+    summaries.sort_by(|a, b| a.origin.cmp(&b.origin));
+
+    for summary in &summaries {
+        serde_json::to_writer(&mut *writer, summary)?;
+        writer.write_all(b"\n")?;
+    }
+
+    Ok(summaries.len())
+}
+
+/// Parse the TOML configuration and redact all secrets from it
+///
+/// The values of keys containing `password`, `secret` or `token`, and of keys named `key`, are
+///  replaced by `"<redacted>"`, wherever they are nested. Paths to key files are kept, the files
+///  themselves are never read. Comments are not part of the result.
+#[cfg(feature = "toml")]
+pub fn redact_config(toml: &str) -> crate::error::ConfigResult<serde_json::Value> {
+    let mut config: serde_json::Value = basic_toml::from_str(toml)?;
+    redact(&mut config);
+    Ok(config)
+}
+
+#[cfg(feature = "toml")]
+fn redact(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(table) => {
+            for (key, value) in table.iter_mut() {
+                if is_secret(key) {
+                    *value = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => (),
+    }
+}
+
+#[cfg(feature = "toml")]
+fn is_secret(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key == "key"
+        || ["password", "secret", "token"]
+            .iter()
+            .any(|secret| key.contains(secret))
+}
+
+/// Write a support bundle
+///
+/// # Arguments
+///
+/// * `writer` - destination of the bundle
+/// * `config` - the TOML configuration the server was started with, secrets are redacted
+/// * `counters` - named error and drop counters of the server
+/// * `authorities` - all the zones of the catalog, caches of forwarded and recursive zones are
+///                   dumped as well
+#[cfg(feature = "toml")]
+pub async fn write_support_bundle(
+    writer: &mut (dyn io::Write + Send),
+    config: Option<&str>,
+    counters: &[(&str, u64)],
+    authorities: &[&dyn AuthorityObject],
+) -> io::Result<()> {
+    writeln!(writer, "# version")?;
+    writeln!(writer, "{}", crate::version())?;
+
+    writeln!(writer, "# config")?;
+    match config.map(redact_config) {
+        Some(Ok(config)) => {
+            serde_json::to_writer_pretty(&mut *writer, &config)?;
+            writeln!(writer)?;
+        }
+        Some(Err(error)) => writeln!(writer, "unreadable: {error}")?,
+        None => writeln!(writer, "unavailable")?,
+    }
+
+    writeln!(writer, "# counters")?;
+    let counters = counters
+        .iter()
+        .map(|(name, count)| (*name, *count))
+        .collect::<BTreeMap<_, _>>();
+    serde_json::to_writer(&mut *writer, &counters)?;
+    writeln!(writer)?;
+
+    writeln!(writer, "# zones")?;
+    write_zone_summaries(authorities, writer).await?;
+
+    for authority in authorities {
+        writeln!(writer, "# cache {}", authority.origin())?;
+        authority.dump_cache(writer)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "toml"))]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+listen_port = 53
+# secret = "commented out"
+
+[[zones]]
+zone = "example.com"
+zone_type = "Primary"
+
+[[zones.keys]]
+key_path = "/etc/hickory/example.com.pk8"
+password = "hunter2"
+algorithm = "RSASHA256"
+
+[zones.stores]
+type = "sqlite"
+tsig = { name = "update-key", algorithm = "hmac-sha256", secret = "c2VjcmV0" }
+allow_update = true
+
+[tls_cert]
+path = "/etc/hickory/cert.p12"
+endpoint_name = "ns.example.com"
+private_key = "/etc/hickory/cert.key"
+"#;
+
+    #[test]
+    fn test_redact_secrets() {
+        let config = redact_config(CONFIG).unwrap();
+        let zone = &config["zones"][0];
+
+        assert_eq!(zone["keys"][0]["password"], REDACTED);
+        assert_eq!(zone["stores"]["tsig"]["secret"], REDACTED);
+        assert_eq!(zone["stores"]["tsig"]["name"], "update-key");
+
+        let text = config.to_string();
+        assert!(!text.contains("hunter2"));
+        assert!(!text.contains("c2VjcmV0"));
+        assert!(!text.contains("commented out"));
+    }
+
+    #[test]
+    fn test_redact_keeps_key_paths() {
+        let config = redact_config(CONFIG).unwrap();
+
+        assert_eq!(
+            config["zones"][0]["keys"][0]["key_path"],
+            "/etc/hickory/example.com.pk8"
+        );
+        assert_eq!(config["tls_cert"]["private_key"], "/etc/hickory/cert.key");
+        assert_eq!(config["zones"][0]["zone"], "example.com");
+        assert_eq!(config["listen_port"], 53);
+    }
+
+    #[test]
+    fn test_redact_nested_keys() {
+        let config = redact_config(
+            r#"
+[[zones]]
+zone = "example.com"
+[zones.stores]
+type = "forward"
+[[zones.stores.name_servers]]
+socket_addr = "8.8.8.8:853"
+key = "inline key"
+auth_token = "bearer"
+"#,
+        )
+        .unwrap();
+
+        let server = &config["zones"][0]["stores"]["name_servers"][0];
+        assert_eq!(server["key"], REDACTED);
+        assert_eq!(server["auth_token"], REDACTED);
+        assert_eq!(server["socket_addr"], "8.8.8.8:853");
+    }
+}
@@ -70,6 +70,20 @@ pub fn test_soa<A: Authority<Lookup = AuthLookup>>(authority: A) {
     }
 }
 
+pub fn test_zone_apex<A: Authority<Lookup = AuthLookup>>(authority: A) {
+    let apex = block_on(authority.zone_apex()).expect("SOA record not found in authority");
+    let lookup = block_on(authority.soa()).unwrap();
+
+    assert_eq!(Some(&apex), lookup.iter().next());
+    assert_eq!(
+        199609203,
+        apex.data()
+            .as_soa()
+            .expect("wrong rdata type returned")
+            .serial()
+    );
+}
+
 pub fn test_ns<A: Authority<Lookup = AuthLookup>>(authority: A) {
     let lookup = block_on(authority.ns(LookupOptions::default())).unwrap();
 
@@ -751,6 +765,7 @@ macro_rules! basic_battery {
                 define_basic_test!($new;
                     test_a_lookup,
                     test_soa,
+                    test_zone_apex,
                     test_ns,
                     test_ns_lookup,
                     test_mx,
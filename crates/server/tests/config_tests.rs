@@ -148,6 +148,92 @@ fn test_parse_toml() {
     assert_eq!(config.get_directory(), Path::new("/dev/null"));
 }
 
+#[test]
+fn test_parse_rewrite() {
+    use hickory_proto::rr::Name;
+    use hickory_server::authority::{DnssecRewritePolicy, RewriteMatch, RewriteRule};
+
+    let config = Config::from_toml(
+        "
+[rewrite]
+dnssec_policy = \"StripDnssec\"
+
+[[rewrite.rules]]
+match_type = \"Suffix\"
+from = \"old.example\"
+to = \"new.example\"
+rewrite_cname_targets = true
+",
+    )
+    .unwrap();
+
+    let rules = config.get_rewrite().to_rules().unwrap();
+    assert_eq!(rules.dnssec_policy(), DnssecRewritePolicy::StripDnssec);
+    assert_eq!(
+        rules.rules(),
+        &[RewriteRule::new(
+            RewriteMatch::Suffix,
+            Name::from_ascii("old.example.").unwrap(),
+            Name::from_ascii("new.example.").unwrap(),
+        )
+        .with_cname_targets(true)]
+    );
+
+    let config = Config::from_toml("").unwrap();
+    let rules = config.get_rewrite().to_rules().unwrap();
+    assert!(rules.is_empty());
+    assert_eq!(rules.dnssec_policy(), DnssecRewritePolicy::Refuse);
+}
+
+#[test]
+fn test_parse_ttl_scale_factor() {
+    let config = Config::from_toml(
+        "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Primary\"
+file = \"example.com.zone\"
+ttl_scale_factor = 0.1
+",
+    )
+    .unwrap();
+    assert_eq!(config.get_zones()[0].get_ttl_scale_factor(), Some(0.1));
+
+    let config = Config::from_toml(
+        "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Primary\"
+file = \"example.com.zone\"
+",
+    )
+    .unwrap();
+    assert_eq!(config.get_zones()[0].get_ttl_scale_factor(), None);
+}
+
+#[cfg(feature = "recursor")]
+#[test]
+fn test_parse_recursive_store() {
+    use hickory_server::store::StoreConfig;
+
+    for store_type in ["recursor", "recursive"] {
+        let config = Config::from_toml(&format!(
+            "
+[[zones]]
+zone = \".\"
+zone_type = \"Hint\"
+stores = {{ type = \"{store_type}\", roots = \"default/root.zone\" }}
+"
+        ))
+        .unwrap();
+
+        let Some(StoreConfig::Recursor(recursor)) = &config.get_zones()[0].stores else {
+            panic!("expected a recursor store for type {store_type}");
+        };
+        assert_eq!(recursor.roots, Path::new("default/root.zone"));
+    }
+}
+
 #[cfg(feature = "dnssec")]
 #[test]
 fn test_parse_zone_keys() {
@@ -215,6 +301,46 @@ signer_name = \"ns.example.com.\"
     assert!(!config.get_zones()[0].get_keys()[1].is_zone_update_auth(),);
 }
 
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_parse_signature_timing() {
+    use hickory_server::store::in_memory::SignatureTiming;
+
+    let config = Config::from_toml(
+        "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Primary\"
+file = \"example.com.zone\"
+
+[zones.signature_timing]
+validity = 1209600
+inception_offset = 3600
+jitter_percent = 10
+
+[[zones]]
+zone = \"example.net\"
+zone_type = \"Primary\"
+file = \"example.net.zone\"
+",
+    )
+    .unwrap();
+
+    assert_eq!(
+        config.get_zones()[0].signature_timing(),
+        SignatureTiming {
+            validity: Some(1209600),
+            inception_offset: 3600,
+            refresh: None,
+            jitter_percent: 10,
+        }
+    );
+    assert_eq!(
+        config.get_zones()[1].signature_timing(),
+        SignatureTiming::default()
+    );
+}
+
 #[test]
 #[cfg(feature = "dnssec")]
 fn test_parse_tls() {
@@ -266,6 +392,7 @@ define_test_config!(dns_over_tls);
 define_test_config!(dnssec_with_update);
 define_test_config!(dnssec_with_update_deprecated);
 define_test_config!(example);
+define_test_config!(example_rewrite);
 define_test_config!(ipv4_and_ipv6);
 define_test_config!(ipv4_only);
 define_test_config!(ipv6_only);
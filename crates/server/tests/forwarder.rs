@@ -1,18 +1,37 @@
 #![recursion_limit = "128"]
 #![cfg(feature = "hickory-resolver")]
 
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, SocketAddr};
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use futures_util::future::BoxFuture;
+use tokio::net::UdpSocket;
 use tokio::runtime::Runtime;
 
-use hickory_proto::rr::{Name, RecordType};
+use hickory_proto::op::{Edns, Message, MessageType, OpCode, Query, ResponseCode};
+use hickory_proto::rr::{
+    rdata::{
+        opt::{EdnsCode, EdnsOption, ExtendedError},
+        A,
+    },
+    Name, RData, Record, RecordType,
+};
+use hickory_resolver::config::{NameServerConfigGroup, ResolverOpts};
 use hickory_resolver::name_server::TokioConnectionProvider;
 use hickory_server::{
-    authority::{Authority, LookupObject},
-    store::forwarder::ForwardAuthority,
+    authority::{Authority, Catalog, LookupObject, ZoneType},
+    server::{MiddlewareChain, Next, QueryLogEntry, Request, RequestMiddleware, ResponseInfo},
+    store::forwarder::{ForwardAuthority, ForwardCacheConfig, ForwardConfig, ForwardValidation},
+    ServerFuture,
 };
 
+/// The extended error attached by the mock upstream to answers for `bogus.example.com.`
+fn dnssec_bogus() -> ExtendedError {
+    ExtendedError::new(6, "signature expired")
+}
+
 #[ignore]
 #[test]
 fn test_lookup() {
@@ -32,3 +51,373 @@ fn test_lookup() {
     let address = address.data().as_a().expect("not an A record");
     assert_eq!(*address, Ipv4Addr::new(93, 184, 215, 14).into());
 }
+
+/// The bits of a query received by the mock upstream
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct UpstreamQuery {
+    query_type: RecordType,
+    dnssec_ok: bool,
+    checking_disabled: bool,
+}
+
+/// Answers A queries with 127.0.0.1 and everything else with an empty response
+///
+/// A validating upstream sets AD on answers to queries without CD, as if they were validated.
+///  Answers for `bogus.example.com.` carry the DNSSEC Bogus extended error, as returned by a
+///  validating upstream to queries with CD.
+async fn mock_upstream(validating: bool) -> (SocketAddr, Arc<Mutex<Vec<UpstreamQuery>>>) {
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr = socket.local_addr().unwrap();
+    let queries = Arc::new(Mutex::new(Vec::new()));
+
+    let received = queries.clone();
+    tokio::spawn(async move {
+        let mut buf = [0u8; 4096];
+        loop {
+            let (len, src) = socket.recv_from(&mut buf).await.unwrap();
+            let request = Message::from_vec(&buf[..len]).unwrap();
+            let query = request.queries()[0].clone();
+            let dnssec_ok = request.extensions().as_ref().map_or(false, Edns::dnssec_ok);
+            received.lock().unwrap().push(UpstreamQuery {
+                query_type: query.query_type(),
+                dnssec_ok,
+                checking_disabled: request.checking_disabled(),
+            });
+
+            let mut response = Message::new();
+            response
+                .set_id(request.id())
+                .set_message_type(MessageType::Response)
+                .set_op_code(OpCode::Query)
+                .set_recursion_desired(true)
+                .set_recursion_available(true)
+                .set_checking_disabled(request.checking_disabled());
+            if query.query_type() == RecordType::A {
+                response.set_authentic_data(validating && !request.checking_disabled());
+                response.add_answer(Record::from_rdata(
+                    query.name().clone(),
+                    300,
+                    RData::A(A::new(127, 0, 0, 1)),
+                ));
+            }
+            if query.name() == &Name::from_ascii("bogus.example.com.").unwrap() {
+                let mut edns = Edns::new();
+                edns.options_mut().insert(EdnsOption::EDE(dnssec_bogus()));
+                response.set_edns(edns);
+            }
+            response.add_query(query);
+
+            socket
+                .send_to(&response.to_vec().unwrap(), src)
+                .await
+                .unwrap();
+        }
+    });
+
+    (addr, queries)
+}
+
+/// Records the query log entry of each request
+#[derive(Clone, Default)]
+struct QueryLog(Arc<Mutex<Vec<QueryLogEntry>>>);
+
+impl RequestMiddleware for QueryLog {
+    fn intercept<'a>(
+        &'a self,
+        request: &'a Request,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, ResponseInfo> {
+        Box::pin(async move {
+            let response_info = next.run(request).await;
+            self.0.lock().unwrap().push(QueryLogEntry::new(
+                request,
+                &response_info,
+                Duration::ZERO,
+            ));
+            response_info
+        })
+    }
+}
+
+/// The configuration of a forwarder to the mock upstream at `upstream`
+fn config(upstream: SocketAddr, validation: ForwardValidation, trust_ad: bool) -> ForwardConfig {
+    let mut options = ResolverOpts::default();
+    options.timeout = Duration::from_secs(1);
+    ForwardConfig {
+        name_servers: NameServerConfigGroup::from_ips_clear(
+            &[upstream.ip()],
+            upstream.port(),
+            true,
+        ),
+        options: Some(options),
+        validation,
+        trust_ad,
+        cache: ForwardCacheConfig::default(),
+        debug_upstream: false,
+    }
+}
+
+/// A query for `name A` with the DO and CD bits
+fn request(name: &str, dnssec_ok: bool, checking_disabled: bool) -> Message {
+    let mut request = Message::new();
+    request
+        .add_query(Query::query(Name::from_ascii(name).unwrap(), RecordType::A))
+        .set_id(0x1234)
+        .set_recursion_desired(true)
+        .set_checking_disabled(checking_disabled);
+    let mut edns = Edns::new();
+    edns.set_dnssec_ok(dnssec_ok);
+    request.set_edns(edns);
+    request
+}
+
+/// Sends the requests one after the other through a forwarder configured with `config`
+///
+/// Returns the responses of the forwarder and the query log entries of the requests.
+async fn serve(config: &ForwardConfig, requests: &[Message]) -> (Vec<Message>, Vec<QueryLogEntry>) {
+    let forwarder = ForwardAuthority::try_from_config(Name::root(), ZoneType::Forward, config)
+        .expect("failed to create forwarder");
+
+    let mut catalog = Catalog::new();
+    catalog.upsert(Name::root().into(), Box::new(Arc::new(forwarder)));
+    let query_log = QueryLog::default();
+    let handler = MiddlewareChain::new(catalog, vec![Box::new(query_log.clone())]);
+    let server_socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let server_addr = server_socket.local_addr().unwrap();
+    let mut server = ServerFuture::new(handler);
+    server.register_socket(server_socket);
+
+    let client = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let mut responses = Vec::with_capacity(requests.len());
+    for request in requests {
+        client
+            .send_to(&request.to_vec().unwrap(), server_addr)
+            .await
+            .unwrap();
+        let mut buf = [0u8; 4096];
+        let len = client.recv(&mut buf).await.unwrap();
+        responses.push(Message::from_vec(&buf[..len]).unwrap());
+    }
+
+    server.shutdown_gracefully().await.unwrap();
+
+    let entries = query_log.0.lock().unwrap().clone();
+    (responses, entries)
+}
+
+/// Sends a query for `www.example.com. A` through a forwarder to the mock upstream
+///
+/// Returns the bits of the A queries received upstream and the response of the forwarder.
+async fn forward(
+    validation: ForwardValidation,
+    trust_ad: bool,
+    validating_upstream: bool,
+    dnssec_ok: bool,
+    checking_disabled: bool,
+) -> (Vec<UpstreamQuery>, Message) {
+    let (upstream, queries) = mock_upstream(validating_upstream).await;
+
+    let config = config(upstream, validation, trust_ad);
+    let (mut responses, _) = serve(
+        &config,
+        &[request("www.example.com.", dnssec_ok, checking_disabled)],
+    )
+    .await;
+
+    let queries = queries
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|query| query.query_type == RecordType::A)
+        .copied()
+        .collect();
+    (queries, responses.remove(0))
+}
+
+/// The extended errors of the response
+fn extended_errors(response: &Message) -> Vec<ExtendedError> {
+    let Some(edns) = response.extensions() else {
+        return vec![];
+    };
+
+    edns.options()
+        .get_all(EdnsCode::EDE)
+        .into_iter()
+        .filter_map(|option| match option {
+            EdnsOption::EDE(ede) => Some(ede.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn upstream_query(dnssec_ok: bool, checking_disabled: bool) -> UpstreamQuery {
+    UpstreamQuery {
+        query_type: RecordType::A,
+        dnssec_ok,
+        checking_disabled,
+    }
+}
+
+#[tokio::test]
+async fn test_validation_none() {
+    let (queries, response) = forward(ForwardValidation::None, true, true, false, false).await;
+    assert_eq!(queries, [upstream_query(false, false)]);
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert_eq!(response.answers().len(), 1);
+    assert!(!response.authentic_data());
+
+    let (queries, response) = forward(ForwardValidation::None, true, true, false, true).await;
+    assert_eq!(queries, [upstream_query(false, false)]);
+    assert!(!response.authentic_data());
+}
+
+#[tokio::test]
+#[cfg(feature = "dnssec")]
+async fn test_validation_none_dnssec_ok() {
+    // DO is passed on, but AD of the validating upstream is never returned
+    let (queries, response) = forward(ForwardValidation::None, true, true, true, false).await;
+    assert_eq!(queries, [upstream_query(true, false)]);
+    assert_eq!(response.answers().len(), 1);
+    assert!(!response.authentic_data());
+
+    let (queries, response) = forward(ForwardValidation::None, true, false, true, true).await;
+    assert_eq!(queries, [upstream_query(true, true)]);
+    assert!(response.checking_disabled());
+    assert!(!response.authentic_data());
+}
+
+#[tokio::test]
+#[cfg(feature = "dnssec")]
+async fn test_validation_upstream() {
+    let (queries, response) = forward(ForwardValidation::Upstream, true, true, true, false).await;
+    assert_eq!(queries, [upstream_query(true, false)]);
+    assert_eq!(response.answers().len(), 1);
+    assert!(response.authentic_data());
+
+    // AD is only trusted with trust_ad
+    let (queries, response) = forward(ForwardValidation::Upstream, false, true, true, false).await;
+    assert_eq!(queries, [upstream_query(true, false)]);
+    assert!(!response.authentic_data());
+
+    let (queries, response) = forward(ForwardValidation::Upstream, true, false, true, false).await;
+    assert_eq!(queries, [upstream_query(true, false)]);
+    assert!(!response.authentic_data());
+
+    // CD is passed on, and the upstream does not validate
+    let (queries, response) = forward(ForwardValidation::Upstream, true, true, true, true).await;
+    assert_eq!(queries, [upstream_query(true, true)]);
+    assert_eq!(response.answers().len(), 1);
+    assert!(!response.authentic_data());
+}
+
+#[tokio::test]
+#[cfg(feature = "dnssec")]
+async fn test_validation_local() {
+    // DO is always set upstream, the unsigned answer fails local validation despite the AD of
+    //  the upstream
+    for validating_upstream in [true, false] {
+        let (queries, response) = forward(
+            ForwardValidation::Local,
+            true,
+            validating_upstream,
+            false,
+            false,
+        )
+        .await;
+        assert_eq!(queries, [upstream_query(true, false)]);
+        assert!(!response.authentic_data());
+    }
+
+    // CD skips the local validation
+    for validating_upstream in [true, false] {
+        let (queries, response) = forward(
+            ForwardValidation::Local,
+            true,
+            validating_upstream,
+            false,
+            true,
+        )
+        .await;
+        assert_eq!(queries, [upstream_query(true, true)]);
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(response.answers().len(), 1);
+        assert!(!response.authentic_data());
+    }
+}
+
+#[tokio::test]
+async fn test_upstream_extended_error() {
+    let (upstream, _) = mock_upstream(true).await;
+
+    // DO and CD are forwarded directly, the extended error of the upstream is passed on
+    let config = config(upstream, ForwardValidation::Upstream, false);
+    let (responses, _) = serve(&config, &[request("bogus.example.com.", true, true)]).await;
+    assert_eq!(responses[0].response_code(), ResponseCode::NoError);
+    assert_eq!(responses[0].answers().len(), 1);
+    assert_eq!(extended_errors(&responses[0]), [dnssec_bogus()]);
+
+    let (responses, _) = serve(&config, &[request("www.example.com.", true, true)]).await;
+    assert_eq!(extended_errors(&responses[0]), []);
+}
+
+#[tokio::test]
+async fn test_debug_upstream() {
+    let (upstream, _) = mock_upstream(false).await;
+    let requests = [
+        request("www.example.com.", false, false),
+        request("www.example.com.", false, false),
+    ];
+
+    // off by default
+    let mut config = config(upstream, ForwardValidation::None, false);
+    let (responses, _) = serve(&config, &requests).await;
+    for response in &responses {
+        assert_eq!(response.answers().len(), 1);
+        assert_eq!(extended_errors(response), []);
+    }
+
+    // the second response is answered from the cache of the resolver
+    config.debug_upstream = true;
+    let (responses, _) = serve(&config, &requests).await;
+    assert_eq!(
+        extended_errors(&responses[0]),
+        [ExtendedError::new(
+            0,
+            format!("upstream udp://{upstream} retries:0")
+        )]
+    );
+    assert_eq!(
+        extended_errors(&responses[1]),
+        [ExtendedError::new(0, "answered from cache")]
+    );
+}
+
+#[tokio::test]
+async fn test_query_log_upstream() {
+    let (upstream, _) = mock_upstream(false).await;
+    let config = config(upstream, ForwardValidation::None, false);
+    let requests = [
+        request("www.example.com.", false, false),
+        request("www.example.com.", false, false),
+    ];
+
+    let (_, entries) = serve(&config, &requests).await;
+    assert_eq!(entries.len(), 2);
+
+    let metadata = entries[0].upstream.expect("no upstream logged");
+    assert_eq!(metadata.server, upstream);
+    assert_eq!(metadata.protocol, "udp");
+    assert_eq!(metadata.retries, 0);
+    assert!(!metadata.tcp_fallback);
+    assert!(
+        entries[0]
+            .to_string()
+            .ends_with(&format!(" upstream:udp://{upstream} retries:0")),
+        "{}",
+        entries[0]
+    );
+
+    // the cached answer did not come from upstream
+    assert_eq!(entries[1].upstream, None);
+    assert!(!entries[1].to_string().contains("upstream"));
+}
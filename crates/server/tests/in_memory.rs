@@ -148,3 +148,408 @@ fn test_cname_loop() {
         &RData::CNAME(CNAME(Name::from_str("baz.example.com.").unwrap()))
     );
 }
+
+#[tokio::test]
+async fn test_import_zone_file_lazy() {
+    use std::{
+        net::Ipv4Addr,
+        path::Path,
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use hickory_proto::rr::rdata::A;
+
+    let started = Instant::now();
+    let auth = Arc::new(InMemoryAuthority::empty(
+        Name::from_str("example.com.").unwrap(),
+        ZoneType::Primary,
+        false,
+    ));
+
+    auth.import_zone_file_lazy(Path::new(
+        "../../tests/test-data/test_configs/example.com.zone",
+    ))
+    .expect("failed to read zone file");
+
+    // the query is issued while the records may still be loading
+    let lookup = auth
+        .lookup(
+            &Name::from_str("www.example.com.").unwrap().into(),
+            RecordType::A,
+            Default::default(),
+        )
+        .await
+        .expect("records should be loaded");
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    let records: Vec<&Record> = lookup.iter().collect();
+    assert_eq!(records.len(), 1);
+    assert_eq!(
+        records[0].data(),
+        &RData::A(A::from(Ipv4Addr::new(127, 0, 0, 1)))
+    );
+
+    // a missing file is reported right away
+    assert!(auth
+        .import_zone_file_lazy(Path::new("does-not-exist.zone"))
+        .is_err());
+}
+
+#[cfg(feature = "dnssec-ring")]
+#[tokio::test]
+async fn test_signed_transfer() {
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        net::Ipv4Addr,
+        sync::Arc,
+    };
+
+    use futures_util::stream;
+    use hickory_proto::rr::{
+        dnssec::{
+            rdata::{DNSSECRData, DNSKEY},
+            Algorithm, SupportedAlgorithms, Verifier,
+        },
+        rdata::{A, NS, SOA},
+        DNSClass, LowerName, RecordData, RecordSet, RrKey,
+    };
+    use hickory_server::{
+        authority::{DnssecAuthority, LookupOptions},
+        config::dnssec::KeyConfig,
+        store::in_memory::SerialPolicy,
+    };
+
+    fn inceptions(records: &BTreeMap<RrKey, Arc<RecordSet>>) -> BTreeMap<RrKey, u32> {
+        records
+            .iter()
+            .filter_map(|(rr_key, rr_set)| {
+                let inception = rr_set
+                    .rrsigs()
+                    .iter()
+                    .find_map(|rrsig| match rrsig.data() {
+                        RData::DNSSEC(DNSSECRData::RRSIG(rrsig)) => Some(rrsig.sig_inception()),
+                        _ => None,
+                    })?;
+                Some((rr_key.clone(), inception))
+            })
+            .collect()
+    }
+
+    let origin = Name::from_str("example.com.").unwrap();
+    let name = |name: &str| {
+        Name::from_str(name)
+            .unwrap()
+            .append_domain(&origin)
+            .unwrap()
+    };
+    let soa = |serial| {
+        Record::from_rdata(
+            origin.clone(),
+            3600,
+            RData::SOA(SOA::new(
+                name("ns"),
+                name("hostmaster"),
+                serial,
+                7200,
+                600,
+                86400,
+                300,
+            )),
+        )
+    };
+
+    let authority = InMemoryAuthority::empty(origin.clone(), ZoneType::Secondary, false);
+    let key_config = KeyConfig {
+        key_path: "../../tests/test-data/test_configs/dnssec/ed25519.pk8".to_string(),
+        password: None,
+        algorithm: Algorithm::ED25519.to_string(),
+        signer_name: Some(origin.to_string()),
+        is_zone_signing_key: Some(true),
+        is_zone_update_auth: Some(false),
+    };
+    let signer = key_config
+        .try_into_signer(origin.clone())
+        .expect("failed to read key_config");
+    authority
+        .add_zone_signing_key(signer)
+        .await
+        .expect("failed to add signer to zone");
+
+    // an AXFR starts and ends with the SOA, the www RRset is split to check that it is merged
+    let hosts = 50_000;
+    let mut transfer = vec![
+        soa(2023),
+        Record::from_rdata(origin.clone(), 3600, RData::NS(NS(name("ns")))),
+        Record::from_rdata(name("www"), 300, RData::A(A::new(127, 0, 0, 1))),
+    ];
+    transfer.extend((0..hosts).map(|i| {
+        Record::from_rdata(
+            name(&format!("host{i}")),
+            300,
+            RData::A(A::from(Ipv4Addr::from(i as u32))),
+        )
+    }));
+    transfer.push(Record::from_rdata(
+        name("www"),
+        300,
+        RData::A(A::new(127, 0, 0, 2)),
+    ));
+    transfer.push(soa(2023));
+
+    let serial = authority
+        .load_signed_transfer(stream::iter(transfer), SerialPolicy::Increment)
+        .await
+        .expect("failed to load transfer");
+    assert_eq!(serial, 2023);
+    assert_eq!(authority.serial().await, 2023);
+
+    let records = authority.records().await;
+    // SOA, NS, DNSKEY, CDS and CDNSKEY at the apex, A for each host and www, and NSEC for all names
+    assert_eq!(records.len(), 5 + (hosts + 1) * 2 + 1);
+    assert!(records.values().all(|rr_set| !rr_set.rrsigs().is_empty()));
+
+    let dnskey = records
+        .get(&RrKey::new(origin.clone().into(), RecordType::DNSKEY))
+        .and_then(|rr_set| rr_set.records_without_rrsigs().next())
+        .and_then(|record| DNSKEY::try_borrow(record.data()))
+        .expect("DNSKEY missing")
+        .clone();
+    for rr_key in [
+        RrKey::new(origin.clone().into(), RecordType::SOA),
+        RrKey::new(origin.clone().into(), RecordType::NSEC),
+        RrKey::new(name("www").into(), RecordType::A),
+        RrKey::new(name("host42").into(), RecordType::A),
+    ] {
+        let rr_set = &records[&rr_key];
+        let rrset_records = rr_set.records_without_rrsigs().collect::<Vec<_>>();
+        for rrsig in rr_set.rrsigs() {
+            let RData::DNSSEC(DNSSECRData::RRSIG(rrsig)) = rrsig.data() else {
+                panic!("not an RRSIG: {rrsig:?}");
+            };
+            dnskey
+                .verify_rrsig(rr_set.name(), DNSClass::IN, rrsig, &rrset_records)
+                .unwrap_or_else(|e| panic!("{rr_key:?} failed to validate: {e}"));
+        }
+    }
+    assert_eq!(
+        records[&RrKey::new(name("www").into(), RecordType::A)]
+            .records_without_rrsigs()
+            .count(),
+        2
+    );
+
+    let lookup = authority
+        .lookup(
+            &LowerName::from(name("www")),
+            RecordType::A,
+            LookupOptions::for_dnssec(true, SupportedAlgorithms::all()),
+        )
+        .await
+        .expect("lookup failed");
+    assert!(lookup.iter().any(|r| r.record_type() == RecordType::RRSIG));
+
+    // signatures have a resolution of seconds
+    let before = inceptions(&records);
+    drop(records);
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+
+    let serial = authority
+        .apply_signed_diff(
+            vec![
+                soa(2023),
+                Record::from_rdata(name("host1"), 300, RData::A(A::from(Ipv4Addr::from(1u32)))),
+            ],
+            vec![
+                soa(2024),
+                Record::from_rdata(name("host1"), 300, RData::A(A::new(127, 0, 0, 3))),
+                Record::from_rdata(name("added"), 300, RData::A(A::new(127, 0, 0, 4))),
+            ],
+            SerialPolicy::Increment,
+        )
+        .await
+        .expect("failed to apply diff");
+    assert_eq!(serial, 2024);
+
+    let after = inceptions(&authority.records().await);
+    let resigned = after
+        .iter()
+        .filter(|(rr_key, inception)| before.get(rr_key) != Some(inception))
+        .map(|(rr_key, _)| rr_key.clone())
+        .collect::<BTreeSet<_>>();
+    assert_eq!(
+        resigned,
+        BTreeSet::from([
+            RrKey::new(origin.clone().into(), RecordType::SOA),
+            // the next name of the apex NSEC is now added.example.com.
+            RrKey::new(origin.clone().into(), RecordType::NSEC),
+            RrKey::new(name("host1").into(), RecordType::A),
+            RrKey::new(name("added").into(), RecordType::A),
+            RrKey::new(name("added").into(), RecordType::NSEC),
+        ])
+    );
+}
+
+#[cfg(feature = "dnssec-ring")]
+#[tokio::test]
+async fn test_signature_timing() {
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        net::Ipv4Addr,
+    };
+
+    use hickory_proto::rr::{
+        dnssec::{rdata::DNSSECRData, Algorithm},
+        rdata::{A, NS, SOA},
+        RrKey,
+    };
+    use hickory_server::{
+        authority::DnssecAuthority, config::dnssec::KeyConfig, store::in_memory::SignatureTiming,
+    };
+    use time::OffsetDateTime;
+
+    const DAY: u32 = 86400;
+
+    async fn expirations(authority: &InMemoryAuthority) -> BTreeMap<RrKey, u32> {
+        authority
+            .records()
+            .await
+            .iter()
+            .filter_map(|(rr_key, rr_set)| {
+                let expiration = rr_set
+                    .rrsigs()
+                    .iter()
+                    .find_map(|rrsig| match rrsig.data() {
+                        RData::DNSSEC(DNSSECRData::RRSIG(rrsig)) => Some(rrsig.sig_expiration()),
+                        _ => None,
+                    })?;
+                Some((rr_key.clone(), expiration))
+            })
+            .collect()
+    }
+
+    let origin = Name::from_str("example.com.").unwrap();
+    let name = |name: &str| {
+        Name::from_str(name)
+            .unwrap()
+            .append_domain(&origin)
+            .unwrap()
+    };
+
+    let mut authority = InMemoryAuthority::empty(origin.clone(), ZoneType::Primary, false);
+    authority.upsert_mut(
+        Record::from_rdata(
+            origin.clone(),
+            3600,
+            RData::SOA(SOA::new(
+                name("ns"),
+                name("hostmaster"),
+                2023,
+                7200,
+                600,
+                DAY as i32,
+                300,
+            )),
+        ),
+        2023,
+    );
+    authority.upsert_mut(
+        Record::from_rdata(origin.clone(), 3600, RData::NS(NS(name("ns")))),
+        2023,
+    );
+    for i in 0..100 {
+        authority.upsert_mut(
+            Record::from_rdata(
+                name(&format!("host{i}")),
+                300,
+                RData::A(A::from(Ipv4Addr::from(i as u32))),
+            ),
+            2023,
+        );
+    }
+
+    let timing = SignatureTiming {
+        validity: Some(30 * DAY),
+        inception_offset: 3600,
+        refresh: Some(3 * DAY),
+        jitter_percent: 20,
+    };
+    authority.set_signature_timing_mut(timing);
+
+    let key_config = KeyConfig {
+        key_path: "../../tests/test-data/test_configs/dnssec/ed25519.pk8".to_string(),
+        password: None,
+        algorithm: Algorithm::ED25519.to_string(),
+        signer_name: Some(origin.to_string()),
+        is_zone_signing_key: Some(true),
+        is_zone_update_auth: Some(false),
+    };
+    let signer = key_config
+        .try_into_signer(origin.clone())
+        .expect("failed to read key_config");
+    authority
+        .add_zone_signing_key(signer)
+        .await
+        .expect("failed to add signer to zone");
+
+    let signed_from = OffsetDateTime::now_utc().unix_timestamp() as u32;
+    authority.secure_zone().await.expect("failed to sign zone");
+    let signed_until = OffsetDateTime::now_utc().unix_timestamp() as u32;
+    assert!(authority.check_signature_timing().await.is_empty());
+
+    // the expirations are spread over the last 20% of the validity
+    let before = expirations(&authority).await;
+    assert!(before.values().all(|expiration| {
+        (signed_from + 24 * DAY..=signed_until + 30 * DAY).contains(expiration)
+    }));
+    let distinct = before.values().collect::<BTreeSet<_>>();
+    assert!(distinct.len() > before.len() / 2, "{distinct:?}");
+
+    // nothing expires within the refresh window a day after signing
+    let now = OffsetDateTime::from_unix_timestamp(i64::from(signed_until + DAY)).unwrap();
+    assert_eq!(authority.resign_expiring_at(now).await.unwrap(), 0);
+    assert_eq!(expirations(&authority).await, before);
+
+    // half of the RRsets enter the refresh window
+    let mut sorted = before.values().copied().collect::<Vec<_>>();
+    sorted.sort_unstable();
+    let median = sorted[sorted.len() / 2];
+    let expiring = before
+        .iter()
+        .filter(|(_, expiration)| **expiration <= median)
+        .map(|(rr_key, _)| rr_key.clone())
+        .collect::<BTreeSet<_>>();
+
+    let now = OffsetDateTime::from_unix_timestamp(i64::from(median - 3 * DAY)).unwrap();
+    assert_eq!(
+        authority.resign_expiring_at(now).await.unwrap(),
+        expiring.len()
+    );
+
+    let soa_key = RrKey::new(origin.clone().into(), RecordType::SOA);
+    let after = expirations(&authority).await;
+    for (rr_key, expiration) in &after {
+        if expiring.contains(rr_key) || *rr_key == soa_key {
+            assert!(*expiration >= median - 3 * DAY + 24 * DAY, "{rr_key:?}");
+        } else {
+            assert_eq!(before[rr_key], *expiration, "{rr_key:?}");
+        }
+    }
+    assert_eq!(authority.serial().await, 2025);
+
+    // a second pass at the same time has nothing left to sign
+    assert_eq!(authority.resign_expiring_at(now).await.unwrap(), 0);
+
+    // signatures expiring before the records in caches
+    authority.set_signature_timing_mut(SignatureTiming {
+        validity: Some(1800),
+        ..timing
+    });
+    let problems = authority.check_signature_timing().await;
+    assert!(
+        problems
+            .iter()
+            .any(|problem| problem.contains("shorter than the max TTL of 3600s")),
+        "{problems:?}"
+    );
+}
@@ -0,0 +1,116 @@
+#![recursion_limit = "128"]
+#![cfg(feature = "hickory-recursor")]
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+
+use hickory_proto::op::{Message, MessageType, OpCode, Query, ResponseCode};
+use hickory_proto::rr::{
+    rdata::{A, NS},
+    Name, RData, Record, RecordType,
+};
+use hickory_resolver::config::{NameServerConfig, Protocol, ResolverConfig, ResolverOpts};
+use hickory_server::{authority::Catalog, store::recursor::RecursiveAuthority, ServerFuture};
+
+fn root_server() -> Name {
+    Name::from_ascii("a.root-servers.test.").unwrap()
+}
+
+/// A root server, answering the NS query for the root zone with a glued name server
+///
+/// The recursor only uses the configured port for the roots, so all lookups under test must be
+///  answered by the roots.
+async fn mock_root() -> (SocketAddr, Arc<Mutex<Vec<Query>>>) {
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr = socket.local_addr().unwrap();
+    let queries = Arc::new(Mutex::new(Vec::new()));
+
+    let received = queries.clone();
+    tokio::spawn(async move {
+        let mut buf = [0u8; 4096];
+        loop {
+            let (len, src) = socket.recv_from(&mut buf).await.unwrap();
+            let request = Message::from_vec(&buf[..len]).unwrap();
+            let query = request.queries()[0].clone();
+            received.lock().unwrap().push(query.clone());
+
+            let mut response = Message::new();
+            response
+                .set_id(request.id())
+                .set_message_type(MessageType::Response)
+                .set_op_code(OpCode::Query)
+                .set_authoritative(true);
+            if query.name().is_root() && query.query_type() == RecordType::NS {
+                response.add_answer(Record::from_rdata(
+                    Name::root(),
+                    86400,
+                    RData::NS(NS(root_server())),
+                ));
+                response.add_additional(Record::from_rdata(
+                    root_server(),
+                    86400,
+                    RData::A(A::new(127, 0, 0, 1)),
+                ));
+            }
+            response.add_query(query);
+
+            socket
+                .send_to(&response.to_vec().unwrap(), src)
+                .await
+                .unwrap();
+        }
+    });
+
+    (addr, queries)
+}
+
+#[tokio::test]
+async fn test_recursive_resolution() {
+    let (root, queries) = mock_root().await;
+
+    let mut config = ResolverConfig::new();
+    config.add_name_server(NameServerConfig::new(root, Protocol::Udp));
+    let mut options = ResolverOpts::default();
+    options.timeout = Duration::from_secs(1);
+    let recursor = RecursiveAuthority::new(config, options).expect("failed to create recursor");
+
+    let mut catalog = Catalog::new();
+    catalog.upsert(Name::root().into(), Box::new(Arc::new(recursor)));
+    let server_socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let server_addr = server_socket.local_addr().unwrap();
+    let mut server = ServerFuture::new(catalog);
+    server.register_socket(server_socket);
+
+    let mut request = Message::new();
+    request
+        .add_query(Query::query(Name::root(), RecordType::NS))
+        .set_id(0x1234)
+        .set_recursion_desired(true);
+
+    let client = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    for _ in 0..2 {
+        client
+            .send_to(&request.to_vec().unwrap(), server_addr)
+            .await
+            .unwrap();
+        let mut buf = [0u8; 4096];
+        let len = client.recv(&mut buf).await.unwrap();
+        let response = Message::from_vec(&buf[..len]).unwrap();
+
+        assert_eq!(response.id(), 0x1234);
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(response.answers().len(), 1);
+        assert_eq!(response.answers()[0].data(), &RData::NS(NS(root_server())));
+    }
+
+    server.shutdown_gracefully().await.unwrap();
+
+    // the second response is served from the cache of the recursor
+    assert_eq!(
+        *queries.lock().unwrap(),
+        [Query::query(Name::root(), RecordType::NS)]
+    );
+}
@@ -1,7 +1,7 @@
-use std::str::FromStr;
+use std::{collections::BTreeMap, env, fs, process, str::FromStr, sync::Arc};
 
-use hickory_proto::rr::{LowerName, Name, RecordType, RrKey};
-use hickory_server::authority::{Authority, LookupOptions, ZoneType};
+use hickory_proto::rr::{rdata::A, LowerName, Name, RData, RecordType, RrKey};
+use hickory_server::authority::{Authority, AuthorityObject, LookupOptions, ZoneType};
 use hickory_server::store::file::{FileAuthority, FileConfig};
 
 #[macro_use]
@@ -99,3 +99,236 @@ async fn test_ttl_wilcard() {
     assert_eq!(data.record_type(), RecordType::A);
     assert_eq!(data.ttl(), 120);
 }
+
+fn reload_zone_file(address: &str) -> String {
+    format!(
+        "$TTL 3600
+@               SOA     ns.example.com. root.example.com. (
+                                1       ; Serial
+                                28800   ; Refresh
+                                7200    ; Retry
+                                604800  ; Expire
+                                86400)  ; Minimum TTL
+                NS      ns.example.com.
+www             A       {address}
+"
+    )
+}
+
+async fn lookup_www(authority: &dyn AuthorityObject) -> Vec<RData> {
+    authority
+        .lookup(
+            &LowerName::from_str("www.example.com.").unwrap(),
+            RecordType::A,
+            LookupOptions::default(),
+        )
+        .await
+        .expect("lookup failed")
+        .iter()
+        .map(|record| record.data().clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_reload_zone() {
+    let zone_path = env::temp_dir().join(format!("hickory-reload-{}.zone", process::id()));
+    fs::write(&zone_path, reload_zone_file("127.0.0.1")).unwrap();
+
+    let config = FileConfig {
+        zone_file_path: zone_path.to_string_lossy().into_owned(),
+    };
+    let authority = FileAuthority::try_from_config(
+        Name::from_str("example.com.").unwrap(),
+        ZoneType::Primary,
+        false,
+        None,
+        &config,
+    )
+    .expect("failed to load");
+    let authority: Box<dyn AuthorityObject> = Box::new(Arc::new(authority));
+
+    assert_eq!(
+        lookup_www(&*authority).await,
+        vec![RData::A(A::new(127, 0, 0, 1))]
+    );
+
+    // the zone file changes on disk
+    fs::write(&zone_path, reload_zone_file("127.0.0.2")).unwrap();
+    assert!(authority.reload_zone().await.expect("reload failed"));
+    assert_eq!(
+        lookup_www(&*authority).await,
+        vec![RData::A(A::new(127, 0, 0, 2))]
+    );
+
+    // a broken zone file must not replace the current data
+    fs::write(&zone_path, "www A not-an-address\n").unwrap();
+    assert!(authority.reload_zone().await.is_err());
+    assert_eq!(
+        lookup_www(&*authority).await,
+        vec![RData::A(A::new(127, 0, 0, 2))]
+    );
+
+    fs::remove_file(&zone_path).unwrap();
+    assert!(authority.reload_zone().await.is_err());
+    assert_eq!(
+        lookup_www(&*authority).await,
+        vec![RData::A(A::new(127, 0, 0, 2))]
+    );
+}
+
+#[cfg(feature = "dnssec-ring")]
+#[tokio::test]
+async fn test_reload_zone_resigns() {
+    use hickory_proto::rr::dnssec::{Algorithm, SupportedAlgorithms};
+    use hickory_server::authority::DnssecAuthority;
+    use hickory_server::config::dnssec::KeyConfig;
+
+    let zone_path = env::temp_dir().join(format!("hickory-resign-{}.zone", process::id()));
+    fs::write(&zone_path, reload_zone_file("127.0.0.1")).unwrap();
+
+    let config = FileConfig {
+        zone_file_path: zone_path.to_string_lossy().into_owned(),
+    };
+    let origin = Name::from_str("example.com.").unwrap();
+    let authority =
+        FileAuthority::try_from_config(origin.clone(), ZoneType::Primary, false, None, &config)
+            .expect("failed to load");
+
+    let key_config = KeyConfig {
+        key_path: "../../tests/test-data/test_configs/dnssec/ed25519.pk8".to_string(),
+        password: None,
+        algorithm: Algorithm::ED25519.to_string(),
+        signer_name: Some(origin.to_string()),
+        is_zone_signing_key: Some(true),
+        is_zone_update_auth: Some(false),
+    };
+    let signer = key_config
+        .try_into_signer(origin.clone())
+        .expect("failed to read key_config");
+    authority
+        .add_zone_signing_key(signer)
+        .await
+        .expect("failed to add signer to zone");
+    authority.secure_zone().await.expect("failed to sign zone");
+
+    fs::write(&zone_path, reload_zone_file("127.0.0.2")).unwrap();
+    assert!(Authority::reload_zone(&authority)
+        .await
+        .expect("reload failed"));
+    fs::remove_file(&zone_path).unwrap();
+
+    let lookup_options = LookupOptions::for_dnssec(true, SupportedAlgorithms::all());
+    let www = authority
+        .lookup(
+            &LowerName::from_str("www.example.com.").unwrap(),
+            RecordType::A,
+            lookup_options,
+        )
+        .await
+        .expect("lookup failed");
+    let www = www.iter().collect::<Vec<_>>();
+    assert!(www
+        .iter()
+        .any(|r| r.data() == &RData::A(A::new(127, 0, 0, 2))));
+    assert!(www.iter().any(|r| r.record_type() == RecordType::RRSIG));
+
+    let dnskeys = authority
+        .lookup(
+            &LowerName::from(origin),
+            RecordType::DNSKEY,
+            LookupOptions::default(),
+        )
+        .await
+        .expect("lookup failed");
+    assert!(dnskeys.iter().next().is_some());
+}
+
+#[cfg(feature = "dnssec-ring")]
+#[tokio::test]
+async fn test_multi_signer_zone() {
+    use hickory_proto::rr::dnssec::{Algorithm, SupportedAlgorithms};
+    use hickory_server::authority::DnssecAuthority;
+    use hickory_server::config::dnssec::KeyConfig;
+
+    // the key of the other provider is only published, it can not be used for signing
+    let zone_path = env::temp_dir().join(format!("hickory-multi-signer-{}.zone", process::id()));
+    let zone = format!(
+        "{}@ DNSKEY 257 3 15 l02Woi0iS8Aa25FQkUd9RMzZHJpBoRQwAQEX1SxZJA4=\n",
+        reload_zone_file("127.0.0.1")
+    );
+    fs::write(&zone_path, zone).unwrap();
+
+    let config = FileConfig {
+        zone_file_path: zone_path.to_string_lossy().into_owned(),
+    };
+    let origin = Name::from_str("example.com.").unwrap();
+    let authority =
+        FileAuthority::try_from_config(origin.clone(), ZoneType::Primary, false, None, &config)
+            .expect("failed to load");
+    fs::remove_file(&zone_path).unwrap();
+
+    let key_config = KeyConfig {
+        key_path: "../../tests/test-data/test_configs/dnssec/ed25519.pk8".to_string(),
+        password: None,
+        algorithm: Algorithm::ED25519.to_string(),
+        signer_name: Some(origin.to_string()),
+        is_zone_signing_key: Some(true),
+        is_zone_update_auth: Some(false),
+    };
+    let signer = key_config
+        .try_into_signer(origin.clone())
+        .expect("failed to read key_config");
+    authority
+        .add_zone_signing_key(signer)
+        .await
+        .expect("failed to add signer to zone");
+    authority.secure_zone().await.expect("failed to sign zone");
+
+    let lookup_options = LookupOptions::for_dnssec(true, SupportedAlgorithms::all());
+    let origin = LowerName::from(origin);
+    for (record_type, count) in [
+        (RecordType::DNSKEY, 2),
+        (RecordType::CDNSKEY, 2),
+        (RecordType::CDS, 2),
+    ] {
+        let lookup = authority
+            .lookup(&origin, record_type, lookup_options)
+            .await
+            .expect("lookup failed");
+        let records = lookup.iter().collect::<Vec<_>>();
+        assert_eq!(
+            records
+                .iter()
+                .filter(|r| r.record_type() == record_type)
+                .count(),
+            count,
+            "{record_type}"
+        );
+        assert!(records.iter().any(|r| r.record_type() == RecordType::RRSIG));
+    }
+}
+
+#[tokio::test]
+async fn test_reload_zone_without_file() {
+    let records = file(
+        "../../tests/test-data/test_configs/default/implicitclass.zone",
+        "",
+        "",
+    )
+    .records()
+    .await
+    .into_iter()
+    .map(|(key, rrset)| (key, (*rrset).clone()))
+    .collect::<BTreeMap<_, _>>();
+
+    let authority = FileAuthority::new(
+        Name::from_str("example.com.").unwrap(),
+        records,
+        ZoneType::Primary,
+        false,
+    )
+    .expect("failed to create authority");
+
+    // there is no file to read the zone from
+    assert!(!Authority::reload_zone(&authority).await.unwrap());
+}
@@ -0,0 +1,48 @@
+#![no_main]
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+use hickory_proto::{
+    op::Message,
+    rr::{RData, RecordType},
+    serialize::binary::{BinDecoder, BinEncodable, BinEncoder, Restrict},
+};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    record_type: u16,
+    data: Vec<u8>,
+}
+
+fuzz_target!(|input: Input| {
+    let record_type = RecordType::from(input.record_type);
+    if let Ok(length) = u16::try_from(input.data.len()) {
+        let mut decoder = BinDecoder::new(&input.data);
+        if let Ok(rdata) = RData::read(&mut decoder, record_type, Restrict::new(length)) {
+            round_trip(record_type, &rdata);
+        }
+    }
+
+    // the same bytes through the whole pipeline, any result is fine as long as it doesn't panic
+    _ = Message::from_vec(&input.data);
+});
+
+fn round_trip(record_type: RecordType, original: &RData) {
+    // FIXME: evaluate why these don't work, see the message target
+    if record_type == RecordType::CSYNC {
+        return;
+    }
+
+    let mut encoded = Vec::new();
+    let mut encoder = BinEncoder::new(&mut encoded);
+    if let Err(e) = original.emit(&mut encoder) {
+        panic!("RData failed to serialize: {:?}: {}", original, e);
+    }
+
+    let length = u16::try_from(encoded.len()).expect("RData longer than u16::MAX");
+    let mut decoder = BinDecoder::new(&encoded);
+    match RData::read(&mut decoder, record_type, Restrict::new(length)) {
+        Ok(reparsed) => assert_eq!(original, &reparsed),
+        Err(e) => panic!("RData failed to deserialize: {:?}: {}", original, e),
+    }
+}
@@ -0,0 +1,35 @@
+#!/bin/bash
+#
+# Generates a golden file for crates/proto/tests/rdata_golden_tests.rs from a live server with dig
+#
+# usage: rdata_fixture.sh NAME TYPE [@SERVER] > crates/proto/tests/test-data/rdata/TYPE.golden
+#
+# The first record of TYPE in the answer is used. dig and hickory-dns differ in some details of
+#  the presentation format, e.g. the case of hex strings, always review the generated file.
+
+set -e
+
+if [ $# -lt 2 ]; then
+    echo "usage: $0 NAME TYPE [@SERVER]" >&2
+    exit 1
+fi
+
+name=$1
+type=$(echo "$2" | tr '[:lower:]' '[:upper:]')
+server=$3
+
+# the record in presentation format, and again with the RDATA in the RFC 3597 generic format
+record=$(dig +noall +answer +norecurse ${server} "${name}" "${type}" | awk -v type="${type}" '$4 == type { print; exit }')
+generic=$(dig +noall +answer +norecurse +unknownformat ${server} "${name}" "${type}" | awk -v type="${type}" '$4 == type { print; exit }')
+
+if [ -z "${record}" ] || [ -z "${generic}" ]; then
+    echo "no ${type} record found for ${name}" >&2
+    exit 1
+fi
+
+# NAME TTL CLASS TYPE \# LENGTH HEX...
+hex=$(echo "${generic}" | awk '{ for (i = 7; i <= NF; i++) printf "%s", tolower($i) }')
+
+echo "; ${type} record of ${name}, generated with dig from ${server:-the default server}"
+echo "${record}" | tr -s ' \t' ' '
+echo "${hex}" | fold -w 64
@@ -1,17 +1,34 @@
-use std::{str::FromStr, sync::Arc};
+use std::{
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use hickory_client::{
+    client::ZoneExpiry,
     op::*,
     rr::{rdata::*, *},
     serialize::binary::{BinDecodable, BinEncodable},
 };
 
+use hickory_proto::{
+    op::{DsoMessage, DsoTlv},
+    rr::rdata::opt::{EdnsCode, EdnsOption, ExtendedError},
+};
+#[cfg(feature = "dnssec")]
+use hickory_server::authority::DnssecRewritePolicy;
 use hickory_server::{
-    authority::{Authority, Catalog, MessageRequest, ZoneType},
-    server::{Protocol, Request},
+    authority::{
+        AdmissionConfig, AuthLookup, Authority, Block, Catalog, LookupError, LookupOptions,
+        MessageRequest, PolicyEngine, PolicyFilter, PolicyRequest, RewriteRule, RewriteRules,
+        ShedResponse, TierLimits, UpdateResult, Verdict, WorkClass, ZoneType,
+    },
+    server::{Protocol, Request, RequestHandler, RequestInfo},
     store::in_memory::InMemoryAuthority,
 };
 
+#[cfg(feature = "dnssec")]
+use hickory_integration::example_authority::create_secure_example;
 use hickory_integration::{example_authority::create_example, *};
 
 #[allow(clippy::unreadable_literal)]
@@ -179,6 +196,41 @@ async fn test_catalog_lookup() {
     );
 }
 
+#[tokio::test]
+async fn test_catalog_dump_summary() {
+    let mut catalog = Catalog::new();
+    catalog.upsert(
+        Name::parse("test.com.", None).unwrap().into(),
+        Box::new(Arc::new(create_test())),
+    );
+    catalog.upsert(
+        Name::parse("example.com.", None).unwrap().into(),
+        Box::new(Arc::new(create_example())),
+    );
+
+    let mut dump = Vec::new();
+    let zones = catalog.dump_summary(&mut dump).await.unwrap();
+    assert_eq!(zones, 2);
+
+    let summaries = String::from_utf8(dump)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(summaries.len(), 2);
+    assert_eq!(summaries[0]["origin"], "example.com.");
+    assert_eq!(summaries[1]["origin"], "test.com.");
+
+    let test = &summaries[1];
+    assert_eq!(test["zone_type"], "Primary");
+    assert_eq!(test["serial"], 2015082403);
+    assert_eq!(test["signed"], false);
+    assert_eq!(
+        test["records"],
+        serde_json::json!({ "SOA": 1, "NS": 2, "A": 2, "AAAA": 2 })
+    );
+}
+
 #[tokio::test]
 async fn test_catalog_lookup_soa() {
     let example = create_example();
@@ -611,3 +663,765 @@ async fn test_multiple_cname_additionals() {
         &RData::A(A::new(93, 184, 215, 14))
     );
 }
+
+#[tokio::test]
+async fn test_push_subscription() {
+    let test = create_test();
+    let origin = test.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin, Box::new(Arc::new(test)));
+
+    let src = ([127, 0, 0, 1], 5553).into();
+    let name = Name::parse("new.test.com.", None).unwrap();
+
+    // not authoritative for the zone
+    let subscribe = DsoMessage::new(
+        1,
+        MessageType::Query,
+        vec![DsoTlv::Subscribe(Query::query(
+            Name::parse("www.example.com.", None).unwrap(),
+            RecordType::A,
+        ))],
+    );
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .handle_dso(subscribe, src, response_handler.clone())
+        .await;
+    let response = DsoMessage::from_vec(&response_handler.into_inner().await).unwrap();
+    assert_eq!(response.header().response_code(), ResponseCode::NotAuth);
+    assert!(catalog.push_subscriptions().is_empty());
+
+    // unknown primary TLV, DSOTYPENI
+    let keepalive = DsoMessage::new(
+        2,
+        MessageType::Query,
+        vec![DsoTlv::Unknown(0x0001, vec![0; 8])],
+    );
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .handle_dso(keepalive, src, response_handler.clone())
+        .await;
+    let response = DsoMessage::from_vec(&response_handler.into_inner().await).unwrap();
+    assert_eq!(response.header().response_code(), ResponseCode::Unknown(11));
+
+    // subscribe to a name without any records, so nothing is pushed initially
+    let subscribe = DsoMessage::new(
+        3,
+        MessageType::Query,
+        vec![DsoTlv::Subscribe(Query::query(name.clone(), RecordType::A))],
+    );
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .handle_dso(subscribe, src, response_handler.clone())
+        .await;
+    let response = DsoMessage::from_vec(&response_handler.clone().into_inner().await).unwrap();
+    assert_eq!(response.header().id(), 3);
+    assert_eq!(response.header().message_type(), MessageType::Response);
+    assert_eq!(response.header().response_code(), ResponseCode::NoError);
+    assert_eq!(catalog.push_subscriptions().len(), 1);
+
+    let record = Record::from_rdata(name, 86400, RData::A(A::new(127, 0, 0, 1)));
+    assert_eq!(
+        catalog.push_subscriptions().notify(DNSClass::IN, [&record]),
+        1
+    );
+
+    let push = DsoMessage::from_vec(&response_handler.into_inner().await).unwrap();
+    assert_eq!(push.header().id(), 0);
+    assert_eq!(push.tlvs(), &[DsoTlv::Push(vec![record])]);
+
+    // UNSUBSCRIBE is not responded to
+    let unsubscribe = DsoMessage::new(0, MessageType::Query, vec![DsoTlv::Unsubscribe(3)]);
+    catalog
+        .handle_dso(unsubscribe, src, TestResponseHandler::new())
+        .await;
+    assert!(catalog.push_subscriptions().is_empty());
+}
+
+fn rewrite_request(name: &str, query_type: RecordType, dnssec_ok: bool) -> Request {
+    let mut question: Message = Message::new();
+    question.add_query(Query::query(Name::from_str(name).unwrap(), query_type));
+    if dnssec_ok {
+        let mut edns = Edns::new();
+        edns.set_dnssec_ok(true);
+        question.set_edns(edns);
+    }
+
+    let question_bytes = question.to_bytes().unwrap();
+    let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+    Request::new(question_req, ([127, 0, 0, 1], 5553).into(), Protocol::Udp)
+}
+
+#[tokio::test]
+async fn test_rewrite_suffix() {
+    let example = create_example();
+    let origin = example.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin, Box::new(Arc::new(example)));
+    catalog.set_rewrite_rules(
+        RewriteRules::new().with_rule(
+            RewriteRule::suffix(
+                Name::from_str("example.org.").unwrap(),
+                Name::from_str("example.com.").unwrap(),
+            )
+            .with_cname_targets(true),
+        ),
+    );
+
+    let question_req = rewrite_request("www.example.org.", RecordType::A, false);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.header().authoritative());
+    assert_eq!(
+        result.queries().first().unwrap().name(),
+        &Name::from_str("www.example.org.").unwrap()
+    );
+
+    let answers: &[Record] = result.answers();
+    assert_eq!(answers.len(), 1);
+    assert_eq!(
+        answers.first().unwrap().name(),
+        &Name::from_str("www.example.org.").unwrap()
+    );
+    assert_eq!(
+        answers.first().unwrap().data(),
+        &RData::A(A::new(93, 184, 215, 14))
+    );
+
+    // the target of the CNAME is rewritten as well
+    let question_req = rewrite_request("alias.example.org.", RecordType::A, false);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    let answers: &[Record] = result.answers();
+    assert_eq!(answers.len(), 1);
+    assert_eq!(
+        answers.first().unwrap().name(),
+        &Name::from_str("alias.example.org.").unwrap()
+    );
+    assert_eq!(
+        answers.first().unwrap().data(),
+        &RData::CNAME(CNAME(Name::from_str("www.example.org.").unwrap()))
+    );
+
+    // the SOA of a negative response is rewritten
+    let question_req = rewrite_request("nx.example.org.", RecordType::A, false);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NXDomain);
+    assert!(result.answers().is_empty());
+    let soa = result
+        .name_servers()
+        .iter()
+        .find(|record| record.record_type() == RecordType::SOA)
+        .unwrap();
+    assert_eq!(soa.name(), &Name::from_str("example.org.").unwrap());
+}
+
+#[tokio::test]
+async fn test_rewrite_no_match() {
+    let example = create_example();
+    let origin = example.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin, Box::new(Arc::new(example)));
+    catalog.set_rewrite_rules(RewriteRules::new().with_rule(RewriteRule::exact(
+        Name::from_str("www.example.org.").unwrap(),
+        Name::from_str("www.example.com.").unwrap(),
+    )));
+
+    let question_req = rewrite_request("www.example.com.", RecordType::A, false);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    let answers: &[Record] = result.answers();
+    assert_eq!(answers.len(), 1);
+    assert_eq!(
+        answers.first().unwrap().name(),
+        &Name::from_str("www.example.com.").unwrap()
+    );
+
+    // exact rules don't apply to names below the rule
+    let question_req = rewrite_request("a.www.example.org.", RecordType::A, false);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+}
+
+#[cfg(feature = "dnssec")]
+#[tokio::test]
+async fn test_rewrite_signed_refused() {
+    let example = create_secure_example();
+    let origin = example.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin, Box::new(Arc::new(example)));
+    let rules = RewriteRules::new().with_rule(RewriteRule::suffix(
+        Name::from_str("example.org.").unwrap(),
+        Name::from_str("example.com.").unwrap(),
+    ));
+    catalog.set_rewrite_rules(rules.clone());
+
+    // the signed response is refused by default
+    let question_req = rewrite_request("www.example.org.", RecordType::A, true);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+    assert!(result.answers().is_empty());
+
+    // without DO the response is not signed
+    let question_req = rewrite_request("www.example.org.", RecordType::A, false);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert_eq!(result.answers().len(), 1);
+
+    // the DO bit is ignored when stripping DNSSEC
+    catalog.set_rewrite_rules(rules.with_dnssec_policy(DnssecRewritePolicy::StripDnssec));
+    let question_req = rewrite_request("www.example.org.", RecordType::A, true);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    let answers: &[Record] = result.answers();
+    assert_eq!(answers.len(), 1);
+    assert_eq!(answers.first().unwrap().record_type(), RecordType::A);
+    assert_eq!(
+        answers.first().unwrap().name(),
+        &Name::from_str("www.example.org.").unwrap()
+    );
+}
+
+struct BlockEngine;
+
+#[async_trait::async_trait]
+impl PolicyEngine for BlockEngine {
+    async fn decide(&self, request: &PolicyRequest) -> Result<Verdict, String> {
+        Ok(match request.name.to_string().as_str() {
+            "blocked.example.com." => Verdict::Block(Block::ResponseCode(ResponseCode::NXDomain)),
+            "sinkhole.example.com." => Verdict::Block(Block::Answer(vec![Record::from_rdata(
+                Name::root(),
+                60,
+                RData::A(A::new(192, 0, 2, 1)),
+            )])),
+            "alias.example.net." => Verdict::Rewrite(Name::from_str("www.example.com.").unwrap()),
+            _ => Verdict::Defer,
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_policy_filter() {
+    let example = create_example();
+    let origin = example.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin, Box::new(Arc::new(example)));
+    catalog.set_policy_filter(PolicyFilter::new(Arc::new(BlockEngine)));
+
+    // the name exists in the zone, but is blocked
+    let question_req = rewrite_request("blocked.example.com.", RecordType::A, false);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NXDomain);
+    assert!(result.answers().is_empty());
+
+    // fixed answer
+    let question_req = rewrite_request("sinkhole.example.com.", RecordType::A, false);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    let answers: &[Record] = result.answers();
+    assert_eq!(answers.len(), 1);
+    assert_eq!(
+        answers.first().unwrap().name(),
+        &Name::from_str("sinkhole.example.com.").unwrap()
+    );
+    assert_eq!(
+        answers.first().unwrap().data(),
+        &RData::A(A::new(192, 0, 2, 1))
+    );
+
+    // rewritten to a name of the zone
+    let question_req = rewrite_request("alias.example.net.", RecordType::A, false);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    let answers: &[Record] = result.answers();
+    assert_eq!(answers.len(), 1);
+    assert_eq!(
+        answers.first().unwrap().name(),
+        &Name::from_str("alias.example.net.").unwrap()
+    );
+
+    // no decision, looked up as usual
+    let question_req = rewrite_request("www.example.com.", RecordType::A, false);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert_eq!(result.answers().len(), 1);
+
+    let counters = catalog.policy_filter().unwrap().counters();
+    assert_eq!(counters.blocked(), 2);
+    assert_eq!(counters.rewritten(), 1);
+    assert_eq!(counters.deferred(), 1);
+}
+
+#[tokio::test]
+async fn test_ttl_scale_factor() {
+    let mut example = create_example();
+    let origin = example.origin().clone();
+    example.upsert_mut(
+        Record::from_rdata(
+            origin.clone().into(),
+            3600,
+            RData::MX(MX::new(10, Name::from_str("mail.example.com.").unwrap())),
+        ),
+        0,
+    );
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), Box::new(Arc::new(example)));
+    catalog.set_ttl_scale_factor(origin.clone(), 0.1);
+
+    let ttls = |result: &Message| {
+        result
+            .answers()
+            .iter()
+            .map(|record| (record.record_type(), record.ttl()))
+            .collect::<Vec<_>>()
+    };
+
+    let question_req = rewrite_request("example.com.", RecordType::A, false);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+    assert_eq!(ttls(&result), [(RecordType::A, 8640)]);
+
+    let question_req = rewrite_request("example.com.", RecordType::MX, false);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+    assert_eq!(ttls(&result), [(RecordType::MX, 360)]);
+
+    // the NS records returned with the SOA are scaled as well
+    let question_req = rewrite_request("example.com.", RecordType::SOA, false);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+    assert_eq!(ttls(&result), [(RecordType::SOA, 360)]);
+    assert!(result
+        .name_servers()
+        .iter()
+        .all(|record| record.ttl() == 8640));
+
+    // the SOA of a negative response
+    let question_req = rewrite_request("nx.example.com.", RecordType::A, false);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+    assert_eq!(result.response_code(), ResponseCode::NXDomain);
+    let soa = result
+        .name_servers()
+        .iter()
+        .find(|record| record.record_type() == RecordType::SOA)
+        .unwrap();
+    assert_eq!(soa.ttl(), 360);
+
+    // the factor is clamped
+    catalog.set_ttl_scale_factor(origin.clone(), 100.0);
+    assert_eq!(catalog.ttl_scale_factor(&origin), Some(10.0));
+    let question_req = rewrite_request("example.com.", RecordType::MX, false);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+    assert_eq!(ttls(&result), [(RecordType::MX, 36000)]);
+}
+
+/// A secondary zone, which reports the remaining time of its expire timer at a fixed `now`
+struct SecondaryAuthority {
+    zone: InMemoryAuthority,
+    expiry: ZoneExpiry,
+    now: Instant,
+}
+
+#[async_trait::async_trait]
+impl Authority for SecondaryAuthority {
+    type Lookup = AuthLookup;
+
+    fn zone_type(&self) -> ZoneType {
+        ZoneType::Secondary
+    }
+
+    fn is_axfr_allowed(&self) -> bool {
+        self.zone.is_axfr_allowed()
+    }
+
+    async fn update(&self, update: &MessageRequest) -> UpdateResult<bool> {
+        self.zone.update(update).await
+    }
+
+    fn origin(&self) -> &LowerName {
+        self.zone.origin()
+    }
+
+    async fn lookup(
+        &self,
+        name: &LowerName,
+        rtype: RecordType,
+        lookup_options: LookupOptions,
+    ) -> Result<AuthLookup, LookupError> {
+        self.zone.lookup(name, rtype, lookup_options).await
+    }
+
+    async fn search(
+        &self,
+        request: RequestInfo<'_>,
+        lookup_options: LookupOptions,
+    ) -> Result<AuthLookup, LookupError> {
+        self.zone.search(request, lookup_options).await
+    }
+
+    async fn get_nsec_records(
+        &self,
+        name: &LowerName,
+        lookup_options: LookupOptions,
+    ) -> Result<AuthLookup, LookupError> {
+        self.zone.get_nsec_records(name, lookup_options).await
+    }
+
+    async fn remaining_expire(&self) -> Option<Duration> {
+        Some(self.expiry.remaining(self.now))
+    }
+}
+
+/// Queries the SOA of test.com. with the EXPIRE option, returns the SOA and the option value
+async fn query_expire(catalog: &Catalog, expire_option: bool) -> (SOA, Option<u32>) {
+    let mut question = Message::new();
+    question.add_query(Query::query(
+        Name::from_str("test.com.").unwrap(),
+        RecordType::SOA,
+    ));
+    let edns = question.extensions_mut().get_or_insert_with(Edns::new);
+    if expire_option {
+        edns.options_mut().insert(EdnsOption::Expire(None));
+    }
+
+    let question_bytes = question.to_bytes().unwrap();
+    let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+    let question_req = Request::new(question_req, ([127, 0, 0, 1], 5553).into(), Protocol::Udp);
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .handle_request(&question_req, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+
+    let soa = result.answers()[0].data().as_soa().unwrap().clone();
+    (soa, ZoneExpiry::expire_option(&result))
+}
+
+#[tokio::test]
+async fn test_expire_option() {
+    let test = create_test();
+    let origin = test.origin().clone();
+
+    let mut primary = Catalog::new();
+    primary.upsert(origin.clone(), Box::new(Arc::new(test)));
+
+    // the primary answers with the expire time of the SOA, only if asked
+    let (soa, expire) = query_expire(&primary, true).await;
+    assert_eq!(expire, Some(1209600));
+    let (_, no_expire) = query_expire(&primary, false).await;
+    assert_eq!(no_expire, None);
+
+    // a secondary transferred the zone from the primary, and lost contact with it for a day
+    let transferred = Instant::now();
+    let expiry = ZoneExpiry::new(&soa, expire, transferred);
+    let now = transferred + Duration::from_secs(86400);
+
+    let mut secondary = Catalog::new();
+    secondary.upsert(
+        origin,
+        Box::new(Arc::new(SecondaryAuthority {
+            zone: create_test(),
+            expiry,
+            now,
+        })),
+    );
+
+    // it propagates the remaining time to the next secondary in the chain
+    let (soa, expire) = query_expire(&secondary, true).await;
+    assert_eq!(expire, Some(1209600 - 86400));
+
+    let chained = ZoneExpiry::new(&soa, expire, now);
+    assert_eq!(chained.remaining(now), expiry.remaining(now));
+    assert!(chained.remaining(now) < Duration::from_secs(soa.expire() as u64));
+}
+
+/// A forwarding zone, which answers `www` from its cache and everything else from a slow upstream
+struct SlowUpstreamAuthority {
+    zone: InMemoryAuthority,
+    cached: LowerName,
+    delay: Duration,
+}
+
+#[async_trait::async_trait]
+impl Authority for SlowUpstreamAuthority {
+    type Lookup = AuthLookup;
+
+    fn zone_type(&self) -> ZoneType {
+        ZoneType::Forward
+    }
+
+    fn is_axfr_allowed(&self) -> bool {
+        false
+    }
+
+    async fn update(&self, update: &MessageRequest) -> UpdateResult<bool> {
+        self.zone.update(update).await
+    }
+
+    fn origin(&self) -> &LowerName {
+        self.zone.origin()
+    }
+
+    async fn lookup(
+        &self,
+        name: &LowerName,
+        rtype: RecordType,
+        lookup_options: LookupOptions,
+    ) -> Result<AuthLookup, LookupError> {
+        if *name != self.cached {
+            tokio::time::sleep(self.delay).await;
+        }
+        self.zone.lookup(name, rtype, lookup_options).await
+    }
+
+    async fn search(
+        &self,
+        request: RequestInfo<'_>,
+        lookup_options: LookupOptions,
+    ) -> Result<AuthLookup, LookupError> {
+        self.lookup(
+            request.query.name(),
+            request.query.query_type(),
+            lookup_options,
+        )
+        .await
+    }
+
+    fn work_class(&self, request: &RequestInfo<'_>, _lookup_options: LookupOptions) -> WorkClass {
+        if *request.query.name() == self.cached {
+            WorkClass::Cheap
+        } else {
+            WorkClass::Expensive
+        }
+    }
+
+    async fn get_nsec_records(
+        &self,
+        name: &LowerName,
+        lookup_options: LookupOptions,
+    ) -> Result<AuthLookup, LookupError> {
+        self.zone.get_nsec_records(name, lookup_options).await
+    }
+}
+
+fn edns_request(name: &str) -> Request {
+    let mut question = Message::new();
+    question.add_query(Query::query(Name::from_str(name).unwrap(), RecordType::A));
+    question.set_edns(Edns::new());
+
+    let question_bytes = question.to_bytes().unwrap();
+    let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+    Request::new(question_req, ([127, 0, 0, 1], 5553).into(), Protocol::Udp)
+}
+
+fn admission_catalog(shed_response: ShedResponse) -> Arc<Catalog> {
+    let example = create_example();
+    let origin = example.origin().clone();
+    let cached = LowerName::from(Name::from_str("www.example.com.").unwrap());
+
+    let mut catalog = Catalog::new().with_admission(AdmissionConfig {
+        cheap: TierLimits {
+            max_concurrent: 8,
+            max_queued: 64,
+        },
+        expensive: TierLimits {
+            max_concurrent: 2,
+            max_queued: 2,
+        },
+        shed_response,
+    });
+    catalog.upsert(
+        origin,
+        Box::new(Arc::new(SlowUpstreamAuthority {
+            zone: example,
+            cached,
+            delay: Duration::from_secs(2),
+        })),
+    );
+    Arc::new(catalog)
+}
+
+#[tokio::test]
+async fn test_admission_sheds_expensive_first() {
+    let catalog = admission_catalog(ShedResponse::Refused);
+
+    // saturate the expensive tier with queries to the slow upstream
+    let expensive = (0..20)
+        .map(|_| {
+            let catalog = catalog.clone();
+            tokio::spawn(async move {
+                let response_handler = TestResponseHandler::new();
+                catalog
+                    .handle_request(
+                        &edns_request("alias.example.com."),
+                        response_handler.clone(),
+                    )
+                    .await;
+                response_handler.into_message().await.response_code()
+            })
+        })
+        .collect::<Vec<_>>();
+    while catalog.admission_stats().unwrap().expensive.shed < 16 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let stats = catalog.admission_stats().unwrap();
+    assert_eq!(stats.expensive.running, 2);
+    assert_eq!(stats.expensive.queued, 2);
+
+    // cached answers are still fast
+    for _ in 0..20 {
+        let started = Instant::now();
+        let response_handler = TestResponseHandler::new();
+        catalog
+            .handle_request(&edns_request("www.example.com."), response_handler.clone())
+            .await;
+        let result = response_handler.into_message().await;
+
+        assert_eq!(result.response_code(), ResponseCode::NoError);
+        assert_eq!(result.answers().len(), 1);
+        assert!(started.elapsed() < Duration::from_millis(500));
+    }
+
+    let mut response_codes = Vec::new();
+    for task in expensive {
+        response_codes.push(task.await.unwrap());
+    }
+    let refused = response_codes
+        .iter()
+        .filter(|code| **code == ResponseCode::Refused)
+        .count();
+    assert_eq!(refused, 16);
+    assert_eq!(response_codes.len() - refused, 4);
+
+    let stats = catalog.admission_stats().unwrap();
+    assert_eq!(stats.cheap.admitted, 20);
+    assert_eq!(stats.cheap.shed, 0);
+    assert_eq!(stats.expensive.admitted, 4);
+    assert_eq!(stats.expensive.shed, 16);
+    assert_eq!(stats.expensive.running, 0);
+    assert_eq!(stats.expensive.queued, 0);
+}
+
+#[tokio::test]
+async fn test_admission_shed_not_ready() {
+    let catalog = admission_catalog(ShedResponse::ServFail);
+
+    let _running = (0..4)
+        .map(|_| {
+            let catalog = catalog.clone();
+            tokio::spawn(async move {
+                catalog
+                    .handle_request(
+                        &edns_request("alias.example.com."),
+                        TestResponseHandler::new(),
+                    )
+                    .await;
+            })
+        })
+        .collect::<Vec<_>>();
+    while catalog.admission_stats().unwrap().expensive.queued < 2 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .handle_request(
+            &edns_request("alias.example.com."),
+            response_handler.clone(),
+        )
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::ServFail);
+    let ede = match result.extensions().as_ref().unwrap().option(EdnsCode::EDE) {
+        Some(EdnsOption::EDE(ede)) => ede,
+        other => panic!("expected an EDE option, got {other:?}"),
+    };
+    assert_eq!(ede, &ExtendedError::new(14, "server overloaded"));
+}
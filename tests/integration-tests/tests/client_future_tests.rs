@@ -1,8 +1,11 @@
-use std::{
-    net::*,
-    str::FromStr,
-    sync::{Arc, Mutex as StdMutex},
-};
+#[cfg(any(
+    feature = "dns-over-https-rustls",
+    all(feature = "dnssec", feature = "sqlite")
+))]
+use std::sync::Arc;
+#[cfg(all(feature = "dnssec", feature = "sqlite"))]
+use std::sync::Mutex as StdMutex;
+use std::{net::*, str::FromStr};
 
 use futures::{Future, FutureExt, TryFutureExt};
 #[cfg(feature = "dnssec")]
@@ -36,25 +39,29 @@ use hickory_proto::xfer::{DnsExchangeBackground, DnsMultiplexer};
 use hickory_proto::TokioTime;
 use hickory_proto::{iocompat::AsyncIoTokioAsStd, xfer::FirstAnswer, DnsHandle};
 
+#[cfg(all(feature = "dnssec", feature = "sqlite"))]
 use hickory_server::authority::{Authority, Catalog};
 
-use hickory_integration::{
-    example_authority::create_example, NeverReturnsClientStream, TestClientStream,
-};
+#[cfg(all(feature = "dnssec", feature = "sqlite"))]
+use hickory_integration::TestClientStream;
+use hickory_integration::{example_authority::create_example, NeverReturnsClientStream};
+use hickory_test_support::TestDnsNetwork;
 
 #[test]
 fn test_query_nonet() {
     // env_logger::init();
 
-    let authority = create_example();
-    let mut catalog = Catalog::new();
-    catalog.upsert(authority.origin().clone(), Box::new(Arc::new(authority)));
-
     let io_loop = Runtime::new().unwrap();
-    let (stream, sender) = TestClientStream::new(Arc::new(StdMutex::new(catalog)));
-    let client = AsyncClient::new(stream, sender, None);
-    let (mut client, bg) = io_loop.block_on(client).expect("client failed to connect");
-    hickory_proto::spawn_bg(&io_loop, bg);
+    let network = io_loop
+        .block_on(
+            TestDnsNetwork::builder()
+                .authority(create_example())
+                .build(),
+        )
+        .unwrap();
+    let mut client = io_loop
+        .block_on(network.client())
+        .expect("client failed to connect");
 
     io_loop.block_on(test_query(&mut client));
     io_loop.block_on(test_query(&mut client));
@@ -262,14 +269,16 @@ fn test_query_edns(client: &mut AsyncClient) -> impl Future<Output = ()> {
 #[test]
 fn test_notify() {
     let io_loop = Runtime::new().unwrap();
-    let authority = create_example();
-    let mut catalog = Catalog::new();
-    catalog.upsert(authority.origin().clone(), Box::new(Arc::new(authority)));
-
-    let (stream, sender) = TestClientStream::new(Arc::new(StdMutex::new(catalog)));
-    let client = AsyncClient::new(stream, sender, None);
-    let (mut client, bg) = io_loop.block_on(client).expect("client failed to connect");
-    hickory_proto::spawn_bg(&io_loop, bg);
+    let network = io_loop
+        .block_on(
+            TestDnsNetwork::builder()
+                .authority(create_example())
+                .build(),
+        )
+        .unwrap();
+    let mut client = io_loop
+        .block_on(network.client())
+        .expect("client failed to connect");
 
     let name = Name::from_str("ping.example.com").unwrap();
 
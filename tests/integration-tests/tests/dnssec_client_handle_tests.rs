@@ -2,7 +2,8 @@
 
 use std::net::*;
 use std::str::FromStr;
-use std::sync::{Arc, Mutex as StdMutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use futures::executor::block_on;
 use tokio::net::TcpStream as TokioTcpStream;
@@ -14,16 +15,24 @@ use hickory_client::tcp::TcpClientStream;
 
 use hickory_proto::iocompat::AsyncIoTokioAsStd;
 use hickory_proto::op::ResponseCode;
-use hickory_proto::rr::dnssec::{Proof, TrustAnchor};
-use hickory_proto::rr::rdata::A;
+use hickory_proto::rr::dnssec::rdata::{DNSSECRData, DNSKEY, DS};
+use hickory_proto::rr::dnssec::{
+    Algorithm, DigestType, KeyPair, KeySize, Private, Proof, SigSigner, TrustAnchor,
+};
+use hickory_proto::rr::rdata::{A, NS, SOA};
 use hickory_proto::rr::Name;
-use hickory_proto::rr::{DNSClass, RData, RecordType};
+use hickory_proto::rr::{DNSClass, RData, Record, RecordType, RrKey};
 use hickory_proto::udp::{UdpClientConnect, UdpClientStream};
+use hickory_proto::xfer::dnssec_observations::Denial;
+use hickory_proto::xfer::{DnsHandle, DnsRequest, DnssecObservations, UnprovenDsPolicy};
 use hickory_proto::DnssecDnsHandle;
-use hickory_server::authority::{Authority, Catalog};
+use hickory_server::authority::{Authority, LookupOptions, ZoneType};
+use hickory_server::store::in_memory::InMemoryAuthority;
+use openssl::rsa::Rsa;
+use time::Duration;
 
-use hickory_integration::example_authority::create_secure_example;
-use hickory_integration::TestClientStream;
+use hickory_integration::example_authority::{create_example, create_secure_example};
+use hickory_test_support::TestDnsNetwork;
 
 #[test]
 fn test_secure_query_example_nonet() {
@@ -42,6 +51,13 @@ fn test_secure_query_example_tcp() {
 }
 
 fn test_secure_query_example<H>(mut client: DnssecDnsHandle<H>, io_loop: Runtime)
+where
+    H: ClientHandle + Sync + 'static,
+{
+    secure_query_example(&mut client, &io_loop);
+}
+
+fn secure_query_example<H>(client: &mut DnssecDnsHandle<H>, io_loop: &Runtime)
 where
     H: ClientHandle + Sync + 'static,
 {
@@ -193,6 +209,555 @@ where
 //     assert!(response.answers().is_empty());
 // }
 
+/// Signs the example zone with `key`, publishing the public only `other_key` of another signer
+fn create_multi_signer_example(key: KeyPair<Private>, other_key: DNSKEY) -> InMemoryAuthority {
+    let mut authority = create_example();
+    let origin: Name = authority.origin().clone().into();
+
+    let dnskey = key.to_dnskey(Algorithm::RSASHA256).unwrap();
+    let signer = SigSigner::dnssec(
+        dnskey,
+        key,
+        origin.clone(),
+        Duration::weeks(1).try_into().unwrap(),
+    );
+
+    let serial = block_on(authority.serial());
+    authority.upsert_mut(
+        Record::from_rdata(origin, 86400, RData::DNSSEC(DNSSECRData::DNSKEY(other_key))),
+        serial,
+    );
+    authority.add_zone_signing_key_mut(signer).unwrap();
+    authority.secure_zone_mut().unwrap();
+
+    authority
+}
+
+fn rsa_key() -> KeyPair<Private> {
+    KeyPair::from_rsa(Rsa::generate(2048).unwrap()).unwrap()
+}
+
+fn client_for(authority: InMemoryAuthority, io_loop: &Runtime) -> AsyncClient {
+    let network = io_loop
+        .block_on(TestDnsNetwork::builder().authority(authority).build())
+        .unwrap();
+    io_loop
+        .block_on(network.client())
+        .expect("failed to create new client")
+}
+
+/// Sends each request to the next of the handles, like a resolver spreading its queries over
+///  the name servers of different providers.
+#[derive(Clone)]
+struct AlternatingHandle {
+    handles: Arc<[AsyncClient]>,
+    next: Arc<AtomicUsize>,
+}
+
+impl DnsHandle for AlternatingHandle {
+    type Response = <AsyncClient as DnsHandle>::Response;
+
+    fn send<R: Into<DnsRequest> + Unpin + Send + 'static>(&self, request: R) -> Self::Response {
+        let next = self.next.fetch_add(1, Ordering::Relaxed);
+        self.handles[next % self.handles.len()].send(request)
+    }
+}
+
+#[test]
+fn test_multi_signer_nonet() {
+    let io_loop = Runtime::new().unwrap();
+
+    let key_a = rsa_key();
+    let key_b = rsa_key();
+    let dnskey_a = key_a.to_dnskey(Algorithm::RSASHA256).unwrap();
+    let dnskey_b = key_b.to_dnskey(Algorithm::RSASHA256).unwrap();
+
+    // the parent only has a DS for the key of provider a
+    let mut trust_anchor = TrustAnchor::new();
+    trust_anchor.insert_trust_anchor(&key_a.to_public_key().unwrap());
+
+    let provider_a = create_multi_signer_example(key_a, dnskey_b.clone());
+    let provider_b = create_multi_signer_example(key_b, dnskey_a.clone());
+
+    // both providers serve the same DNSKEY, CDS and CDNSKEY RRsets
+    for record_type in [RecordType::DNSKEY, RecordType::CDS, RecordType::CDNSKEY] {
+        let rrset = |authority: &InMemoryAuthority| {
+            let mut rdatas = block_on(authority.lookup(
+                authority.origin(),
+                record_type,
+                LookupOptions::default(),
+            ))
+            .unwrap()
+            .iter()
+            .map(|r| r.data().clone())
+            .collect::<Vec<_>>();
+            rdatas.sort_by_key(|rdata| rdata.to_string());
+            rdatas
+        };
+
+        let rrset_a = rrset(&provider_a);
+        assert_eq!(rrset_a.len(), 2, "{record_type} of both signers");
+        assert_eq!(rrset_a, rrset(&provider_b));
+    }
+
+    let handle = AlternatingHandle {
+        handles: Arc::from([
+            client_for(provider_a, &io_loop),
+            client_for(provider_b, &io_loop),
+        ]),
+        next: Arc::new(AtomicUsize::new(0)),
+    };
+    let mut client = DnssecDnsHandle::with_trust_anchor(handle, trust_anchor);
+
+    // every combination of providers for the answer and the DNSKEY lookup must validate
+    for _ in 0..4 {
+        secure_query_example(&mut client, &io_loop);
+    }
+}
+
+#[test]
+fn test_unverifiable_rrsig_nonet() {
+    // the order of the RRSIGs must not matter
+    unverifiable_rrsig(false);
+    unverifiable_rrsig(true);
+}
+
+/// Every RRset gets two RRSIGs, one of them made with a key that isn't in the DNSKEY RRset
+fn unverifiable_rrsig(unpublished_first: bool) {
+    let io_loop = Runtime::new().unwrap();
+
+    let unpublished_key = rsa_key();
+    let key = rsa_key();
+    let mut trust_anchor = TrustAnchor::new();
+    trust_anchor.insert_trust_anchor(&key.to_public_key().unwrap());
+
+    let mut authority = create_example();
+    let origin: Name = authority.origin().clone().into();
+    let unpublished_dnskey = unpublished_key.to_dnskey(Algorithm::RSASHA256).unwrap();
+    let keys = if unpublished_first {
+        [unpublished_key, key]
+    } else {
+        [key, unpublished_key]
+    };
+    for key in keys {
+        let signer = SigSigner::dnssec(
+            key.to_dnskey(Algorithm::RSASHA256).unwrap(),
+            key,
+            origin.clone(),
+            Duration::weeks(1).try_into().unwrap(),
+        );
+        authority.add_zone_signing_key_mut(signer).unwrap();
+    }
+
+    let dnskeys = authority
+        .records_get_mut()
+        .get_mut(&RrKey::new(origin.clone().into(), RecordType::DNSKEY))
+        .unwrap();
+    let unpublished = Record::from_rdata(
+        origin,
+        0,
+        RData::DNSSEC(DNSSECRData::DNSKEY(unpublished_dnskey)),
+    );
+    assert!(Arc::make_mut(dnskeys).remove(&unpublished, 0));
+    authority.secure_zone_mut().unwrap();
+
+    let client = client_for(authority, &io_loop);
+    let mut client = DnssecDnsHandle::with_trust_anchor(client, trust_anchor);
+    secure_query_example(&mut client, &io_loop);
+}
+
+#[test]
+fn test_sign_nonet() {
+    let io_loop = Runtime::new().unwrap();
+
+    let rsa = Rsa::generate(2048).unwrap();
+    let signer = || {
+        let key = KeyPair::from_rsa(rsa.clone()).unwrap();
+        SigSigner::dnssec(
+            key.to_dnskey(Algorithm::RSASHA256).unwrap(),
+            key,
+            Name::from_str("example.com.").unwrap(),
+            Duration::weeks(1).try_into().unwrap(),
+        )
+    };
+    let mut trust_anchor = TrustAnchor::new();
+    trust_anchor.insert_trust_anchor(
+        &KeyPair::from_rsa(rsa.clone())
+            .unwrap()
+            .to_public_key()
+            .unwrap(),
+    );
+
+    let mut authority = create_example();
+    assert!(!block_on(authority.is_signed()));
+    let serial = block_on(authority.serial());
+
+    authority.sign(signer()).unwrap();
+    assert!(block_on(authority.is_signed()));
+    assert_eq!(block_on(authority.secure_keys()).len(), 1);
+
+    // signing again with the same key only refreshes the signatures
+    authority.sign(signer()).unwrap();
+    assert!(block_on(authority.is_signed()));
+    assert_eq!(block_on(authority.secure_keys()).len(), 1);
+    assert_eq!(block_on(authority.serial()), serial);
+
+    let dnskeys = block_on(authority.lookup(
+        authority.origin(),
+        RecordType::DNSKEY,
+        LookupOptions::default(),
+    ))
+    .unwrap();
+    assert_eq!(dnskeys.iter().count(), 1);
+
+    let client = client_for(authority, &io_loop);
+    let mut client = DnssecDnsHandle::with_trust_anchor(client, trust_anchor);
+    secure_query_example(&mut client, &io_loop);
+}
+
+#[test]
+fn test_observations_nonet() {
+    for (algorithm, key_size) in [
+        (Algorithm::RSASHA256, KeySize::Rsa(2048)),
+        (Algorithm::ECDSAP256SHA256, KeySize::P256),
+        (Algorithm::ECDSAP384SHA384, KeySize::P384),
+    ] {
+        observations(algorithm, key_size);
+    }
+}
+
+/// Validates the example zone signed with a key of `algorithm`, and checks the observations
+fn observations(algorithm: Algorithm, key_size: KeySize) {
+    let io_loop = Runtime::new().unwrap();
+
+    let key = KeyPair::generate(algorithm).unwrap();
+    let key_tag = key.key_tag().unwrap();
+    let mut trust_anchor = TrustAnchor::new();
+    trust_anchor.insert_trust_anchor(&key.to_public_key().unwrap());
+
+    let mut authority = create_example();
+    let origin: Name = authority.origin().clone().into();
+    let signer = SigSigner::dnssec(
+        key.to_dnskey(algorithm).unwrap(),
+        key,
+        origin.clone(),
+        Duration::weeks(1).try_into().unwrap(),
+    );
+    authority.add_zone_signing_key_mut(signer).unwrap();
+    authority.secure_zone_mut().unwrap();
+
+    let observations = DnssecObservations::new();
+    let client = client_for(authority, &io_loop);
+    let mut client = DnssecDnsHandle::with_trust_anchor(client, trust_anchor);
+    client.set_observations(observations.clone());
+
+    secure_query_example(&mut client, &io_loop);
+
+    let observation = observations.get(&origin).expect("zone not observed");
+    assert_eq!(observations.len(), 1, "{algorithm}");
+    assert_eq!(observation.keys.len(), 1, "{algorithm}");
+    let observed_key = &observation.keys[0];
+    assert_eq!(observed_key.key_tag, key_tag);
+    assert_eq!(observed_key.algorithm, algorithm);
+    assert_eq!(observed_key.key_size, Some(key_size));
+    assert_eq!(observation.signature_algorithms, [algorithm]);
+    assert_eq!(
+        observation.min_rsa_bits(),
+        key_size.is_rsa().then_some(2048)
+    );
+    assert!(!observation.uses_sha1());
+    assert_eq!(
+        observation.max_signature_validity,
+        Some(std::time::Duration::from_secs(7 * 24 * 60 * 60))
+    );
+    assert_eq!(observation.denial, None);
+
+    let response = io_loop
+        .block_on(client.query(
+            Name::from_str("none.example.com.").unwrap(),
+            DNSClass::IN,
+            RecordType::A,
+        ))
+        .expect("query failed");
+    assert_eq!(response.response_code(), ResponseCode::NXDomain);
+    assert_eq!(
+        observations.get(&origin).unwrap().denial,
+        Some(Denial::Nsec)
+    );
+
+    observations.reset();
+    assert!(observations.is_empty());
+}
+
+fn sign(authority: &mut InMemoryAuthority, key: KeyPair<Private>) {
+    let signer = SigSigner::dnssec(
+        key.to_dnskey(Algorithm::RSASHA256).unwrap(),
+        key,
+        authority.origin().clone().into(),
+        Duration::weeks(1).try_into().unwrap(),
+    );
+    authority.add_zone_signing_key_mut(signer).unwrap();
+    authority.secure_zone_mut().unwrap();
+}
+
+fn child_origin() -> Name {
+    Name::from_str("sub.example.com.").unwrap()
+}
+
+/// The example zone, with the delegation of `sub.example.com.` without DS records
+fn create_parent(key: KeyPair<Private>) -> InMemoryAuthority {
+    let mut authority = create_example();
+    let serial = block_on(authority.serial());
+    authority.upsert_mut(
+        Record::from_rdata(
+            child_origin(),
+            86400,
+            RData::NS(NS(Name::from_str("ns.sub.example.com.").unwrap())),
+        ),
+        serial,
+    );
+    sign(&mut authority, key);
+
+    authority
+}
+
+/// The zone `sub.example.com.`, if `key` is given it's signed and publishes a DS record of its
+///  own key
+fn create_child(key: Option<KeyPair<Private>>) -> InMemoryAuthority {
+    let origin = child_origin();
+    let mut authority = InMemoryAuthority::empty(origin.clone(), ZoneType::Primary, false);
+    authority.upsert_mut(
+        Record::from_rdata(
+            origin.clone(),
+            3600,
+            RData::SOA(SOA::new(
+                Name::from_str("ns.sub.example.com.").unwrap(),
+                Name::from_str("hostmaster.sub.example.com.").unwrap(),
+                1,
+                7200,
+                3600,
+                1209600,
+                3600,
+            )),
+        ),
+        1,
+    );
+    authority.upsert_mut(
+        Record::from_rdata(
+            origin.clone(),
+            3600,
+            RData::NS(NS(Name::from_str("ns.sub.example.com.").unwrap())),
+        ),
+        1,
+    );
+    authority.upsert_mut(
+        Record::from_rdata(
+            Name::from_str("www.sub.example.com.").unwrap(),
+            3600,
+            RData::A(A::new(127, 0, 0, 1)),
+        ),
+        1,
+    );
+
+    if let Some(key) = key {
+        let dnskey = key.to_dnskey(Algorithm::RSASHA256).unwrap();
+        let ds = DS::new(
+            dnskey.calculate_key_tag().unwrap(),
+            Algorithm::RSASHA256,
+            DigestType::SHA256,
+            dnskey
+                .to_digest(&origin, DigestType::SHA256)
+                .unwrap()
+                .as_ref()
+                .to_owned(),
+        );
+        authority.upsert_mut(
+            Record::from_rdata(origin, 3600, RData::DNSSEC(DNSSECRData::DS(ds))),
+            1,
+        );
+        sign(&mut authority, key);
+    }
+
+    authority
+}
+
+/// Sends the queries for names in the child zone to the child, like a resolver which knows the
+///  zone cut. The DS records of the delegation are requested from the parent, unless
+///  `child_answers_ds` is set.
+#[derive(Clone)]
+struct DelegationHandle {
+    parent: AsyncClient,
+    child: AsyncClient,
+    child_answers_ds: bool,
+}
+
+impl DnsHandle for DelegationHandle {
+    type Response = <AsyncClient as DnsHandle>::Response;
+
+    fn send<R: Into<DnsRequest> + Unpin + Send + 'static>(&self, request: R) -> Self::Response {
+        let request = request.into();
+        let to_child = request.queries().first().map_or(false, |query| {
+            let child_origin = child_origin();
+            child_origin.zone_of(query.name())
+                && (self.child_answers_ds
+                    || query.query_type() != RecordType::DS
+                    || query.name() != &child_origin)
+        });
+
+        if to_child {
+            self.child.send(request)
+        } else {
+            self.parent.send(request)
+        }
+    }
+}
+
+/// Returns the proof of `www.sub.example.com. A`, validated with the key of the parent as trust
+///  anchor
+fn delegated_proof(
+    parent: InMemoryAuthority,
+    child: InMemoryAuthority,
+    child_answers_ds: bool,
+    policy: UnprovenDsPolicy,
+) -> Proof {
+    let io_loop = Runtime::new().unwrap();
+
+    let mut trust_anchor = TrustAnchor::new();
+    for signer in block_on(parent.secure_keys()).iter() {
+        trust_anchor.insert_trust_anchor(&signer.key().to_public_key().unwrap());
+    }
+
+    let handle = DelegationHandle {
+        parent: client_for(parent, &io_loop),
+        child: client_for(child, &io_loop),
+        child_answers_ds,
+    };
+    let mut client = DnssecDnsHandle::with_trust_anchor(handle, trust_anchor);
+    client.set_unproven_ds_policy(policy);
+
+    let name = Name::from_str("www.sub.example.com.").unwrap();
+    let response = io_loop
+        .block_on(client.query(name.clone(), DNSClass::IN, RecordType::A))
+        .expect("query failed");
+
+    let record = response
+        .answers()
+        .iter()
+        .find(|r| r.name() == &name && r.record_type() == RecordType::A)
+        .expect("A record not found");
+    record.proof()
+}
+
+#[test]
+fn test_insecure_delegation_nonet() {
+    // the NSEC record of the delegation in the parent proves the absence of DS records
+    for policy in [UnprovenDsPolicy::Bogus, UnprovenDsPolicy::Insecure] {
+        let parent = create_parent(rsa_key());
+        assert_eq!(
+            delegated_proof(parent, create_child(None), false, policy),
+            Proof::Insecure
+        );
+    }
+
+    // the RRSIGs of a signed child zone can't make its records secure
+    let parent = create_parent(rsa_key());
+    let child = create_child(Some(rsa_key()));
+    assert_eq!(
+        delegated_proof(parent, child, false, UnprovenDsPolicy::Bogus),
+        Proof::Insecure
+    );
+}
+
+#[test]
+fn test_secure_delegation_nonet() {
+    let io_loop = Runtime::new().unwrap();
+    let network = io_loop
+        .block_on(
+            TestDnsNetwork::builder()
+                .authority(create_example())
+                .signed()
+                .child_zone(
+                    child_origin(),
+                    [Record::from_rdata(
+                        Name::from_str("www.sub.example.com.").unwrap(),
+                        3600,
+                        RData::A(A::new(127, 0, 0, 1)),
+                    )],
+                )
+                .signed()
+                .build(),
+        )
+        .unwrap();
+    let mut client = io_loop.block_on(network.dnssec_client()).unwrap();
+
+    let name = Name::from_str("www.sub.example.com.").unwrap();
+    let response = io_loop
+        .block_on(client.query(name.clone(), DNSClass::IN, RecordType::A))
+        .expect("query failed");
+    let record = response
+        .answers()
+        .iter()
+        .find(|r| r.name() == &name && r.record_type() == RecordType::A)
+        .expect("A record not found");
+    assert_eq!(record.proof(), Proof::Secure);
+
+    // the child zone is signed again after the change
+    let zone = network.zone(child_origin()).unwrap();
+    let other = Name::from_str("other.sub.example.com.").unwrap();
+    assert!(io_loop
+        .block_on(zone.upsert(Record::from_rdata(
+            other.clone(),
+            3600,
+            RData::A(A::new(127, 0, 0, 2)),
+        )))
+        .unwrap());
+    let response = io_loop
+        .block_on(client.query(other, DNSClass::IN, RecordType::A))
+        .expect("query failed");
+    assert_eq!(response.answers()[0].proof(), Proof::Secure);
+}
+
+#[test]
+fn test_unproven_delegation_nonet() {
+    // a broken parent, which omits the NSEC record of the delegation
+    let unproven_parent = || {
+        let mut parent = create_parent(rsa_key());
+        let nsec = RrKey::new(child_origin().into(), RecordType::NSEC);
+        assert!(parent.records_get_mut().remove(&nsec).is_some());
+        parent
+    };
+
+    assert_eq!(
+        delegated_proof(
+            unproven_parent(),
+            create_child(None),
+            false,
+            UnprovenDsPolicy::Bogus
+        ),
+        Proof::Bogus
+    );
+    assert_eq!(
+        delegated_proof(
+            unproven_parent(),
+            create_child(None),
+            false,
+            UnprovenDsPolicy::Insecure
+        ),
+        Proof::Insecure
+    );
+}
+
+#[test]
+fn test_child_signed_ds_nonet() {
+    // the child answers the DS query itself, with a DS record signed by its own key
+    let parent = create_parent(rsa_key());
+    let child = create_child(Some(rsa_key()));
+    assert_eq!(
+        delegated_proof(parent, child, true, UnprovenDsPolicy::Insecure),
+        Proof::Bogus
+    );
+}
+
 fn with_nonet<F>(test: F)
 where
     F: Fn(DnssecDnsHandle<MemoizeClientHandle<AsyncClient>>, Runtime),
@@ -215,37 +780,20 @@ where
         })
         .unwrap();
 
-    let authority = create_secure_example();
-
-    let trust_anchor = {
-        let signers = block_on(authority.secure_keys());
-        let public_key = signers
-            .first()
-            .expect("expected a key in the authority")
-            .key()
-            .to_public_key()
-            .expect("could not convert keypair to public_key");
-
-        let mut trust_anchor = TrustAnchor::new();
-        trust_anchor.insert_trust_anchor(&public_key);
-
-        trust_anchor
-    };
-
-    let mut catalog = Catalog::new();
-    catalog.upsert(authority.origin().clone(), Box::new(Arc::new(authority)));
-
     let io_loop = Runtime::new().unwrap();
-    let (stream, sender) = TestClientStream::new(Arc::new(StdMutex::new(catalog)));
-    let client = AsyncClient::new(stream, sender, None);
-
-    let (client, bg) = io_loop
-        .block_on(client)
+    let network = io_loop
+        .block_on(
+            TestDnsNetwork::builder()
+                .authority(create_secure_example())
+                .build(),
+        )
+        .unwrap();
+    let client = io_loop
+        .block_on(network.client())
         .expect("failed to create new client");
 
-    hickory_proto::spawn_bg(&io_loop, bg);
     let client = MemoizeClientHandle::new(client);
-    let secure_client = DnssecDnsHandle::with_trust_anchor(client, trust_anchor);
+    let secure_client = DnssecDnsHandle::with_trust_anchor(client, network.trust_anchor().clone());
 
     test(secure_client, io_loop);
     succeeded.store(true, std::sync::atomic::Ordering::Relaxed);
@@ -0,0 +1,113 @@
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+
+use hickory_client::client::AsyncClient;
+use hickory_client::edns_compliance::{self, Probe, ProbeOptions, ProbeStatus};
+use hickory_integration::example_authority::create_example;
+use hickory_proto::op::{Message, MessageType};
+use hickory_proto::rr::Name;
+use hickory_proto::udp::UdpClientStream;
+use hickory_server::authority::{Authority, Catalog};
+use hickory_server::ServerFuture;
+
+async fn client(nameserver: SocketAddr) -> AsyncClient {
+    let stream = UdpClientStream::<UdpSocket>::with_timeout(nameserver, Duration::from_secs(1));
+    let (client, bg) = AsyncClient::connect(stream).await.unwrap();
+    tokio::spawn(bg);
+    client
+}
+
+fn options() -> ProbeOptions {
+    ProbeOptions {
+        interval: Duration::from_millis(1),
+        ..ProbeOptions::default()
+    }
+}
+
+#[tokio::test]
+async fn test_hickory_server_compliance() {
+    let authority = create_example();
+    let name = Name::from(authority.origin().clone());
+    let mut catalog = Catalog::new();
+    catalog.upsert(authority.origin().clone(), Box::new(Arc::new(authority)));
+
+    let udp_socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let nameserver = udp_socket.local_addr().unwrap();
+    let mut server = ServerFuture::new(catalog);
+    server.register_socket(udp_socket);
+
+    let report = edns_compliance::run_probes(client(nameserver).await, name, &options()).await;
+    println!("{report}");
+
+    assert_eq!(report.results.len(), Probe::ALL.len());
+    assert!(report.is_compliant(), "{report}");
+    for probe in [
+        Probe::Dns,
+        Probe::Edns,
+        Probe::Edns1,
+        Probe::EdnsOpt,
+        Probe::EdnsFlags,
+        Probe::Do,
+    ] {
+        assert_eq!(
+            report.result(probe).unwrap().status,
+            ProbeStatus::Supported,
+            "{probe}"
+        );
+    }
+    assert_eq!(report.max_payload, Some(options().max_payload));
+
+    server.shutdown_gracefully().await.unwrap();
+}
+
+/// Answers every query with an empty NOERROR response, dropping the OPT record of the request
+async fn opt_stripping_server(socket: UdpSocket) {
+    let mut buf = [0u8; 4096];
+    loop {
+        let Ok((len, src)) = socket.recv_from(&mut buf).await else {
+            return;
+        };
+        let Ok(request) = Message::from_vec(&buf[..len]) else {
+            continue;
+        };
+
+        let mut response = Message::new();
+        response
+            .set_id(request.id())
+            .set_message_type(MessageType::Response)
+            .set_op_code(request.op_code())
+            .set_authoritative(true)
+            .add_queries(request.queries().to_vec());
+
+        let bytes = response.to_vec().unwrap();
+        socket.send_to(&bytes, src).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_opt_stripping_server() {
+    let udp_socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let nameserver = udp_socket.local_addr().unwrap();
+    let server = tokio::spawn(opt_stripping_server(udp_socket));
+
+    let name = Name::from_ascii("example.com.").unwrap();
+    let report = edns_compliance::run_probes(client(nameserver).await, name, &options()).await;
+    println!("{report}");
+
+    assert!(!report.is_compliant());
+    assert_eq!(
+        report.result(Probe::Dns).unwrap().status,
+        ProbeStatus::Supported
+    );
+    for probe in Probe::ALL.into_iter().skip(1) {
+        let result = report.result(probe).unwrap();
+        assert_eq!(result.status, ProbeStatus::Broken, "{probe}");
+        assert_eq!(result.response.as_ref().unwrap().edns, None, "{probe}");
+    }
+    assert_eq!(report.max_payload, None);
+
+    server.abort();
+}
@@ -0,0 +1,239 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use ipnet::IpNet;
+
+use hickory_client::{
+    op::{Edns, Message, Query, ResponseCode},
+    rr::{Name, RecordType},
+    serialize::binary::{BinDecodable, BinEncodable},
+};
+use hickory_integration::{example_authority::create_example, TestResponseHandler};
+use hickory_proto::xfer::{DnsRequest, DnsRequestOptions};
+use hickory_server::{
+    authority::{Authority, Catalog, MessageRequest},
+    server::{
+        AclMiddleware, EdnsMiddleware, MiddlewareChain, Next, Protocol, QueryLogEntry,
+        RateLimitMiddleware, Request, RequestHandler, RequestMiddleware, ResponseHandler,
+        ResponseInfo,
+    },
+};
+
+type Events = Arc<Mutex<Vec<String>>>;
+
+/// Records the order in which it sees requests and responses
+struct Recording {
+    name: &'static str,
+    events: Events,
+}
+
+impl RequestMiddleware for Recording {
+    fn intercept<'a>(
+        &'a self,
+        request: &'a Request,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, ResponseInfo> {
+        Box::pin(async move {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{} request", self.name));
+            let response_info = next.run(request).await;
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{} response", self.name));
+            response_info
+        })
+    }
+}
+
+/// Records the requests reaching it, before passing them to the catalog
+struct RecordingHandler {
+    catalog: Catalog,
+    events: Events,
+}
+
+#[async_trait::async_trait]
+impl RequestHandler for RecordingHandler {
+    async fn handle_request<R: ResponseHandler>(
+        &self,
+        request: &Request,
+        response_handle: R,
+    ) -> ResponseInfo {
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("handler max_payload:{}", request.max_payload()));
+        self.catalog.handle_request(request, response_handle).await
+    }
+}
+
+fn chain(
+    middlewares: Vec<Box<dyn RequestMiddleware>>,
+) -> (MiddlewareChain<RecordingHandler>, Events) {
+    let authority = create_example();
+    let mut catalog = Catalog::new();
+    catalog.upsert(authority.origin().clone(), Box::new(Arc::new(authority)));
+
+    let events = Events::default();
+    let handler = RecordingHandler {
+        catalog,
+        events: events.clone(),
+    };
+
+    (MiddlewareChain::new(handler, middlewares), events)
+}
+
+fn request(max_payload: u16) -> Request {
+    let mut message = Message::new();
+    message.add_query(Query::query(
+        Name::from_ascii("www.example.com.").unwrap(),
+        RecordType::A,
+    ));
+    let mut edns = Edns::new();
+    edns.set_max_payload(max_payload);
+    message.set_edns(edns);
+
+    let message = MessageRequest::from_bytes(&message.to_bytes().unwrap()).unwrap();
+    Request::new(message, ([127, 0, 0, 1], 5553).into(), Protocol::Udp)
+}
+
+async fn send(chain: &MiddlewareChain<RecordingHandler>, request: &Request) -> Message {
+    let response_handler = TestResponseHandler::new();
+    chain
+        .handle_request(request, response_handler.clone())
+        .await;
+    response_handler.into_message().await
+}
+
+fn recording(name: &'static str, events: &Events) -> Box<dyn RequestMiddleware> {
+    Box::new(Recording {
+        name,
+        events: events.clone(),
+    })
+}
+
+#[tokio::test]
+async fn test_middleware_order() {
+    let events = Events::default();
+    let (chain, handler_events) = chain(vec![
+        recording("first", &events),
+        recording("second", &events),
+    ]);
+
+    let response = send(&chain, &request(1232)).await;
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert!(!response.answers().is_empty());
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            "first request",
+            "second request",
+            "second response",
+            "first response"
+        ]
+    );
+    assert_eq!(
+        *handler_events.lock().unwrap(),
+        ["handler max_payload:1232"]
+    );
+}
+
+#[tokio::test]
+async fn test_acl_deny_short_circuits() {
+    let events = Events::default();
+    let denied = ["127.0.0.0/8".parse::<IpNet>().unwrap()];
+    let (chain, handler_events) = chain(vec![
+        recording("before", &events),
+        Box::new(AclMiddleware::new(&denied, &[])),
+        recording("after", &events),
+    ]);
+
+    let response = send(&chain, &request(1232)).await;
+    assert_eq!(response.response_code(), ResponseCode::Refused);
+    assert!(response.answers().is_empty());
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        ["before request", "before response"]
+    );
+    assert!(handler_events.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_acl_allow() {
+    let allowed = ["127.0.0.1/32".parse::<IpNet>().unwrap()];
+    let denied = ["127.0.0.0/8".parse::<IpNet>().unwrap()];
+    let (chain, handler_events) = chain(vec![Box::new(AclMiddleware::new(&denied, &allowed))]);
+
+    let response = send(&chain, &request(1232)).await;
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert_eq!(handler_events.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_rate_limit() {
+    let (chain, handler_events) = chain(vec![Box::new(RateLimitMiddleware::new(
+        2,
+        Duration::from_secs(3600),
+    ))]);
+
+    for _ in 0..2 {
+        let response = send(&chain, &request(1232)).await;
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+    }
+
+    let response = send(&chain, &request(1232)).await;
+    assert_eq!(response.response_code(), ResponseCode::Refused);
+    assert_eq!(handler_events.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_edns_caps_max_payload() {
+    let (chain, handler_events) = chain(vec![Box::new(EdnsMiddleware::new(1232))]);
+
+    let response = send(&chain, &request(4096)).await;
+    assert_eq!(response.max_payload(), 1232);
+
+    // smaller payload sizes are passed on as is
+    let response = send(&chain, &request(1024)).await;
+    assert_eq!(response.max_payload(), 1024);
+
+    assert_eq!(
+        *handler_events.lock().unwrap(),
+        ["handler max_payload:1232", "handler max_payload:1024"]
+    );
+}
+
+#[tokio::test]
+async fn test_query_log_correlation_id() {
+    let (chain, _) = chain(vec![]);
+
+    let mut message = Message::new();
+    message.add_query(Query::query(
+        Name::from_ascii("www.example.com.").unwrap(),
+        RecordType::A,
+    ));
+    let dns_request =
+        DnsRequest::new(message, DnsRequestOptions::default()).with_correlation_id(42);
+    let correlated =
+        Request::from_dns_request(&dns_request, ([127, 0, 0, 1], 5553).into(), Protocol::Udp)
+            .unwrap();
+    assert_eq!(correlated.correlation_id(), Some(42));
+
+    let response_handler = TestResponseHandler::new();
+    let response_info = chain.handle_request(&correlated, response_handler).await;
+
+    let entry = QueryLogEntry::new(&correlated, &response_info, Duration::from_millis(1));
+    assert_eq!(entry.correlation_id, Some(42));
+    assert_eq!(entry.response_code, ResponseCode::NoError);
+    assert!(entry.to_string().ends_with(" correlation:42"), "{entry}");
+
+    // requests without an id are logged as before
+    let entry = QueryLogEntry::new(&request(1232), &response_info, Duration::from_millis(1));
+    assert_eq!(entry.correlation_id, None);
+    assert!(!entry.to_string().contains("correlation"), "{entry}");
+}
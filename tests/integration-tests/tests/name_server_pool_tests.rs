@@ -155,8 +155,8 @@ fn test_datagram() {
     assert_eq!(response.answers()[0], udp_record);
 }
 
-#[test]
-fn test_datagram_stream_upgrades_on_truncation() {
+#[tokio::test]
+async fn test_datagram_stream_upgrades_on_truncation() {
     // Lookup to UDP should return a truncated message, then we expect lookup on TCP.
     // This should occur even though `try_tcp_on_error` is set to false.
 
@@ -189,12 +189,12 @@ fn test_datagram_stream_upgrades_on_truncation() {
     let request = message(query, vec![], vec![], vec![]);
     let future = pool.send(request).first_answer();
 
-    let response = block_on(future).unwrap();
+    let response = future.await.unwrap();
     assert_eq!(response.answers()[0], tcp_record);
 }
 
-#[test]
-fn test_datagram_stream_upgrade_on_truncation_despite_udp() {
+#[tokio::test]
+async fn test_datagram_stream_upgrade_on_truncation_despite_udp() {
     // Lookup to UDP should return a truncated message, then we expect lookup on TCP.
     // This should occur even though `try_tcp_on_error` is set to false.
 
@@ -234,7 +234,7 @@ fn test_datagram_stream_upgrade_on_truncation_despite_udp() {
     let request = message(query, vec![], vec![], vec![]);
     let future = pool.send(request).first_answer();
 
-    let response = block_on(future).unwrap();
+    let response = future.await.unwrap();
     assert_eq!(response.answers(), &[tcp_record1, tcp_record2]);
 }
 
@@ -584,6 +584,64 @@ fn test_user_provided_server_order() {
         });
 }
 
+#[test]
+fn test_upstream_address_family() {
+    use hickory_proto::rr::Record;
+
+    let mut options = ResolverOpts::default();
+
+    options.num_concurrent_reqs = 1;
+    options.server_ordering_strategy = ServerOrderingStrategy::UserProvidedOrder;
+    options.upstream_address_family = UpstreamAddressFamily::V4Only;
+
+    let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+
+    let v6_answer = v4_record(query.name().clone(), Ipv4Addr::new(127, 0, 0, 6));
+    let v4_answer = v4_record(query.name().clone(), Ipv4Addr::new(127, 0, 0, 4));
+
+    let responses = |record: &Record| -> Vec<Result<DnsResponse, ProtoError>> {
+        (0..3)
+            .map(|_| {
+                let message = message(query.clone(), vec![record.clone()], vec![], vec![]);
+                Ok(DnsResponse::from_message(message).unwrap())
+            })
+            .collect()
+    };
+
+    // the IPv6 name server is first in the user provided order
+    let v6_nameserver = mock_nameserver_with_addr(
+        responses(&v6_answer),
+        Ipv6Addr::LOCALHOST.into(),
+        Default::default(),
+    );
+    let v4_nameserver = mock_nameserver_with_addr(
+        responses(&v4_answer),
+        Ipv4Addr::LOCALHOST.into(),
+        Default::default(),
+    );
+
+    let pool = mock_nameserver_pool(vec![v6_nameserver, v4_nameserver], vec![], None, options);
+
+    let send = |expected: &Record| {
+        let request = message(query.clone(), vec![], vec![], vec![]);
+        let response = block_on(pool.send(request).first_answer()).unwrap();
+        assert_eq!(&response.answers()[0], expected);
+    };
+
+    send(&v4_answer);
+
+    // changing the family applies to the next request
+    pool.set_upstream_address_family(UpstreamAddressFamily::V6Only);
+    assert_eq!(
+        pool.upstream_address_family(),
+        UpstreamAddressFamily::V6Only
+    );
+    send(&v6_answer);
+
+    pool.set_upstream_address_family(UpstreamAddressFamily::PreferV4);
+    send(&v4_answer);
+}
+
 #[test]
 fn test_return_error_from_highest_priority_nameserver() {
     let query = Query::query(Name::from_str("www.example.").unwrap(), RecordType::A);
@@ -626,6 +684,160 @@ fn test_return_error_from_highest_priority_nameserver() {
     }
 }
 
+/// Counts the requests sent to a name server
+#[derive(Clone, Default)]
+struct OnSendCounter(Arc<AtomicIsize>);
+
+impl OnSendCounter {
+    fn count(&self) -> isize {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl OnSend for OnSendCounter {
+    fn on_send<E>(
+        &self,
+        response: Result<DnsResponse, E>,
+    ) -> Pin<Box<dyn Future<Output = Result<DnsResponse, E>> + Send>>
+    where
+        E: From<ProtoError> + Send + 'static,
+    {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        Box::pin(future::ready(response))
+    }
+}
+
+/// A pool of a name server answering with `first` and one answering with `second`, in this order
+fn server_failure_pool(
+    first: ResponseCode,
+    second: ResponseCode,
+    options: ResolverOpts,
+) -> (
+    MockedNameServerPool<OnSendCounter>,
+    OnSendCounter,
+    OnSendCounter,
+) {
+    let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+    let responses = |response_code| -> Vec<Result<DnsResponse, ProtoError>> {
+        let answers = match response_code {
+            ResponseCode::NoError => {
+                vec![v4_record(query.name().clone(), Ipv4Addr::new(127, 0, 0, 1))]
+            }
+            _ => vec![],
+        };
+        let mut message = message(query.clone(), answers, vec![], vec![]);
+        message.set_response_code(response_code);
+        vec![Ok(DnsResponse::from_message(message).unwrap()); 10]
+    };
+
+    let first_counter = OnSendCounter::default();
+    let second_counter = OnSendCounter::default();
+    let first = mock_nameserver_on_send_nx(
+        responses(first),
+        options.clone(),
+        first_counter.clone(),
+        Ipv4Addr::new(128, 0, 0, 1).into(),
+        false,
+    );
+    let second = mock_nameserver_on_send_nx(
+        responses(second),
+        options.clone(),
+        second_counter.clone(),
+        Ipv4Addr::new(129, 0, 0, 1).into(),
+        false,
+    );
+
+    let pool = mock_nameserver_pool_on_send(vec![first, second], vec![], None, options);
+    (pool, first_counter, second_counter)
+}
+
+fn send_query(pool: &MockedNameServerPool<OnSendCounter>) -> Result<DnsResponse, ProtoError> {
+    let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+    block_on(
+        pool.send(message(query, vec![], vec![], vec![]))
+            .first_answer(),
+    )
+}
+
+fn response_code(error: &ProtoError) -> ResponseCode {
+    match error.kind() {
+        ProtoErrorKind::NoRecordsFound { response_code, .. } => *response_code,
+        kind => panic!("expected an error response, got {kind:?}"),
+    }
+}
+
+#[test]
+fn test_server_failure_failover() {
+    let mut options = ResolverOpts::default();
+    options.num_concurrent_reqs = 1;
+    options.server_ordering_strategy = ServerOrderingStrategy::UserProvidedOrder;
+
+    for failure in [
+        ResponseCode::ServFail,
+        ResponseCode::FormErr,
+        ResponseCode::NotImp,
+    ] {
+        let (pool, failing, working) =
+            server_failure_pool(failure, ResponseCode::NoError, options.clone());
+
+        let response = send_query(&pool).expect("the second name server should answer");
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(response.answers().len(), 1);
+        assert_eq!(failing.count(), 1, "{failure}");
+        assert_eq!(working.count(), 1, "{failure}");
+    }
+}
+
+#[test]
+fn test_server_failure_all_failed() {
+    let mut options = ResolverOpts::default();
+    options.num_concurrent_reqs = 1;
+    options.server_ordering_strategy = ServerOrderingStrategy::UserProvidedOrder;
+
+    let (pool, first, second) =
+        server_failure_pool(ResponseCode::ServFail, ResponseCode::ServFail, options);
+
+    let error = send_query(&pool).expect_err("both name servers fail");
+    assert_eq!(response_code(&error), ResponseCode::ServFail);
+    assert_eq!(first.count(), 1);
+    assert_eq!(second.count(), 1);
+}
+
+#[test]
+fn test_server_failure_first_answer() {
+    let mut options = ResolverOpts::default();
+    options.num_concurrent_reqs = 1;
+    options.server_ordering_strategy = ServerOrderingStrategy::UserProvidedOrder;
+    options.server_failure_policy = ServerFailurePolicy::FirstAnswer;
+
+    let (pool, failing, working) =
+        server_failure_pool(ResponseCode::ServFail, ResponseCode::NoError, options);
+
+    let error = send_query(&pool).expect_err("the first answer is returned");
+    assert_eq!(response_code(&error), ResponseCode::ServFail);
+    assert_eq!(failing.count(), 1);
+    assert_eq!(working.count(), 0);
+}
+
+#[test]
+fn test_server_failure_penalized() {
+    let mut options = ResolverOpts::default();
+    options.num_concurrent_reqs = 1;
+    options.server_ordering_strategy = ServerOrderingStrategy::QueryStatistics;
+
+    let (pool, failing, working) =
+        server_failure_pool(ResponseCode::ServFail, ResponseCode::NoError, options);
+
+    for _ in 0..5 {
+        let response = send_query(&pool).expect("the working name server should answer");
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+    }
+
+    // once it failed, the failing name server is ordered after the working one
+    assert!(failing.count() <= 1, "failing: {}", failing.count());
+    assert_eq!(working.count(), 5);
+}
+
 // === Concurrent requests ===
 
 #[derive(Clone)]
@@ -0,0 +1,137 @@
+#![cfg(any(feature = "dnssec-ring", feature = "dnssec-openssl"))]
+
+use hickory_proto::{
+    op::{Message, MessageType, OpCode, Query},
+    rr::{
+        dnssec::{
+            rdata::tsig::{make_tsig_record, TsigAlgorithm, TSIG},
+            tsig::TSigner,
+        },
+        Name, RecordType,
+    },
+    serialize::binary::{BinDecodable, BinEncodable, BinEncoder},
+    xfer::DnsResponse,
+};
+use hickory_server::{
+    authority::MessageRequest,
+    server::{Protocol, Request, RequestHandler, ResponseHandler, ResponseInfo},
+};
+
+use hickory_integration::TestResponseHandler;
+
+/// Forwards every request with a response previously received from an upstream server
+struct RelayHandler(DnsResponse);
+
+#[async_trait::async_trait]
+impl RequestHandler for RelayHandler {
+    async fn handle_request<R: ResponseHandler>(
+        &self,
+        request: &Request,
+        mut response_handle: R,
+    ) -> ResponseInfo {
+        response_handle
+            .send_raw_response(request.id(), &self.0)
+            .await
+            .expect("failed to send response")
+    }
+}
+
+/// A response to `www.example.com. A` as an upstream server might encode it, with the OPT record
+///  before the last additional record
+#[rustfmt::skip]
+const UPSTREAM_RESPONSE: &[u8] = &[
+    0x12, 0x34, 0x81, 0x80, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x02,
+    // query
+    0x03, b'w', b'w', b'w', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c', b'o', b'm',
+    0x00, 0x00, 0x01, 0x00, 0x01,
+    // answer
+    0xc0, 0x0c, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x04, 127, 0, 0, 1,
+    // authority
+    0xc0, 0x10, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x05, 0x02, b'n', b's', 0xc0,
+    0x10,
+    // additional
+    0x00, 0x00, 0x29, 0x04, 0xd0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xc0, 0x3d, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c, 0x00, 0x04, 127, 0, 0, 2,
+];
+
+fn signer() -> TSigner {
+    TSigner::new(
+        b"relay-test-key".to_vec(),
+        TsigAlgorithm::HmacSha256,
+        Name::from_ascii("relay-key.").unwrap(),
+        300,
+    )
+    .unwrap()
+}
+
+/// Appends a TSIG record to the wire form of the message
+fn tsig_sign(signer: &TSigner, message: &[u8], time: u64) -> Vec<u8> {
+    let pre_tsig = TSIG::new(
+        signer.algorithm().clone(),
+        time,
+        signer.fudge(),
+        Vec::new(),
+        u16::from_be_bytes([message[0], message[1]]),
+        0,
+        Vec::new(),
+    );
+
+    let mut tsig_vars = Vec::new();
+    pre_tsig
+        .emit_tsig_for_mac(&mut BinEncoder::new(&mut tsig_vars), signer.signer_name())
+        .unwrap();
+    let mac = signer.sign(&[message, &tsig_vars].concat()).unwrap();
+
+    let mut signed = message.to_vec();
+    let additional_count = u16::from_be_bytes([signed[10], signed[11]]) + 1;
+    signed[10..12].copy_from_slice(&additional_count.to_be_bytes());
+    let tsig = make_tsig_record(signer.signer_name().clone(), pre_tsig.set_mac(mac));
+    let mut tsig_bytes = Vec::new();
+    tsig.emit(&mut BinEncoder::new(&mut tsig_bytes)).unwrap();
+    signed.extend_from_slice(&tsig_bytes);
+    signed
+}
+
+#[tokio::test]
+async fn test_relay_preserves_tsig() {
+    let signer = signer();
+    let signed = tsig_sign(&signer, UPSTREAM_RESPONSE, 1_700_000_000);
+    signer
+        .verify_message_byte(None, &signed, true)
+        .expect("upstream response should verify");
+
+    let upstream = DnsResponse::from_buffer(signed, false).unwrap();
+    assert_eq!(upstream.answers().len(), 1);
+
+    let mut query = Message::new();
+    query
+        .set_id(0x4321)
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .add_query(Query::query(
+            Name::from_ascii("www.example.com.").unwrap(),
+            RecordType::A,
+        ));
+    let request = MessageRequest::from_bytes(&query.to_bytes().unwrap()).unwrap();
+    let request = Request::new(request, ([127, 0, 0, 1], 5553).into(), Protocol::Tcp);
+
+    let response_handler = TestResponseHandler::new();
+    let info = RelayHandler(upstream.clone())
+        .handle_request(&request, response_handler.clone())
+        .await;
+    assert_eq!(info.id(), 0x4321);
+    assert_eq!(info.answer_count(), 1);
+
+    let relayed = response_handler.into_inner().await;
+    assert_eq!(&relayed[..2], &[0x43, 0x21]);
+    assert_eq!(&relayed[2..], &upstream.as_bytes()[2..]);
+    signer
+        .verify_message_byte(None, &relayed, true)
+        .expect("relayed response should verify");
+
+    // re-encoding the parsed message places the OPT record last, invalidating the MAC
+    let mut reencoded = upstream.into_message();
+    reencoded.set_id(0x4321);
+    let reencoded = reencoded.to_vec().unwrap();
+    assert!(signer.verify_message_byte(None, &reencoded, true).is_err());
+}
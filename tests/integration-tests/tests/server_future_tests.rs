@@ -181,6 +181,87 @@ fn test_server_form_error_on_multiple_queries() {
     server_thread.join().unwrap();
 }
 
+fn server_response_code(message: Message) -> ResponseCode {
+    let runtime = Runtime::new().expect("failed to create Tokio Runtime");
+    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0));
+    let udp_socket = runtime.block_on(UdpSocket::bind(&addr)).unwrap();
+
+    let ipaddr = udp_socket.local_addr().unwrap();
+    let server_continue = Arc::new(AtomicBool::new(true));
+    let server_continue2 = server_continue.clone();
+
+    let server_thread = thread::Builder::new()
+        .name("test_server:udp:server".to_string())
+        .spawn(move || server_thread_udp(runtime, udp_socket, server_continue2))
+        .unwrap();
+
+    let conn = UdpClientConnection::new(ipaddr).unwrap();
+    let client = SyncClient::new(conn);
+
+    let mut client_result = client.send(message);
+    server_continue.store(false, Ordering::Relaxed);
+    server_thread.join().unwrap();
+
+    assert_eq!(client_result.len(), 1);
+    client_result
+        .pop()
+        .expect("there should be one response")
+        .expect("should have been a successful network request")
+        .response_code()
+}
+
+fn edns_query() -> Message {
+    let mut message = Message::new();
+    message
+        .add_query(Query::query(
+            Name::from_str("www.example.com.").unwrap(),
+            RecordType::A,
+        ))
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(true)
+        .set_edns(Edns::new());
+    message
+}
+
+#[test]
+fn test_server_form_error_on_multiple_edns() {
+    let mut message = edns_query();
+    message.add_additional(Record::from(&Edns::new()));
+
+    assert_eq!(server_response_code(message), ResponseCode::FormErr);
+}
+
+#[test]
+fn test_server_form_error_on_edns_in_answers() {
+    let mut message = edns_query();
+    message.add_answer(Record::from(&Edns::new()));
+
+    assert_eq!(server_response_code(message), ResponseCode::FormErr);
+}
+
+#[test]
+fn test_server_form_error_on_edns_not_root() {
+    let mut message = edns_query();
+    let mut opt = Record::from(&Edns::new());
+    opt.set_name(Name::from_str("example.com.").unwrap());
+    message.extensions_mut().take();
+    message.add_additional(opt);
+
+    assert_eq!(server_response_code(message), ResponseCode::FormErr);
+}
+
+#[test]
+fn test_server_badvers() {
+    let mut message = edns_query();
+    message
+        .extensions_mut()
+        .get_or_insert_with(Edns::new)
+        .set_version(1);
+
+    assert_eq!(server_response_code(message), ResponseCode::BADVERS);
+}
+
 #[test]
 fn test_server_no_response_on_response() {
     let runtime = Runtime::new().expect("failed to create Tokio Runtime");
@@ -0,0 +1,130 @@
+use std::net::{Ipv4Addr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::net::{TcpListener, TcpStream};
+
+use hickory_client::client::AsyncClient;
+use hickory_client::zone_diff::{
+    self, DiffOptions, DifferenceKind, Severity, Side, SourceKind, ZoneSource,
+};
+use hickory_integration::example_authority::create_example;
+use hickory_proto::iocompat::AsyncIoTokioAsStd;
+use hickory_proto::rr::rdata::A;
+use hickory_proto::rr::{Name, RData, Record, RecordType};
+use hickory_proto::tcp::TcpClientStream;
+use hickory_server::authority::{Authority, Catalog};
+use hickory_server::store::in_memory::InMemoryAuthority;
+use hickory_server::ServerFuture;
+
+async fn server(mut authority: InMemoryAuthority) -> (ServerFuture<Catalog>, SocketAddr) {
+    authority.set_allow_axfr(true);
+    let mut catalog = Catalog::new();
+    catalog.upsert(authority.origin().clone(), Box::new(Arc::new(authority)));
+
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let nameserver = listener.local_addr().unwrap();
+    let mut server = ServerFuture::new(catalog);
+    server.register_listener(listener, Duration::from_secs(5));
+    (server, nameserver)
+}
+
+async fn client(nameserver: SocketAddr) -> AsyncClient {
+    let (stream, sender) = TcpClientStream::<AsyncIoTokioAsStd<TcpStream>>::with_timeout(
+        nameserver,
+        Duration::from_secs(5),
+    );
+    let (client, bg) = AsyncClient::new(stream, sender, None).await.unwrap();
+    tokio::spawn(bg);
+    client
+}
+
+#[tokio::test]
+async fn test_identical_zones() {
+    let (mut first, first_addr) = server(create_example()).await;
+    let (mut second, second_addr) = server(create_example()).await;
+    let zone = Name::from_str("example.com.").unwrap();
+
+    let report = zone_diff::diff_zones(
+        client(first_addr).await,
+        client(second_addr).await,
+        zone,
+        &ZoneSource::Transfer,
+        &DiffOptions::default(),
+    )
+    .await
+    .unwrap();
+    println!("{report}");
+
+    assert_eq!(report.sources, [SourceKind::Transfer; 2]);
+    assert_eq!(report.rrsets[0], report.rrsets[1]);
+    assert!(report.rrsets[0] > 0);
+    assert!(report.differences.is_empty(), "{report}");
+    assert_eq!(report.max_severity(), None);
+
+    first.shutdown_gracefully().await.unwrap();
+    second.shutdown_gracefully().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_ttl_drift_and_missing_rrset() {
+    let www = Name::from_str("www.example.com.").unwrap();
+    let extra = Name::from_str("extra.example.com.").unwrap();
+
+    let mut changed = create_example();
+    // same record data as in the first copy, with a shorter TTL
+    changed.upsert_mut(
+        Record::from_rdata(www.clone(), 3600, RData::A(A::new(93, 184, 215, 14))),
+        0,
+    );
+    changed.upsert_mut(
+        Record::from_rdata(extra.clone(), 3600, RData::A(A::new(192, 0, 2, 1))),
+        0,
+    );
+
+    let (mut first, first_addr) = server(create_example()).await;
+    let (mut second, second_addr) = server(changed).await;
+    let zone = Name::from_str("example.com.").unwrap();
+
+    let report = zone_diff::diff_zones(
+        client(first_addr).await,
+        client(second_addr).await,
+        zone,
+        &ZoneSource::Transfer,
+        &DiffOptions::default(),
+    )
+    .await
+    .unwrap();
+    println!("{report}");
+
+    assert_eq!(report.rrsets[0] + 1, report.rrsets[1]);
+    assert_eq!(report.differences.len(), 2, "{report}");
+
+    let missing = &report.differences[0];
+    assert_eq!(missing.name, extra);
+    assert_eq!(missing.record_type, RecordType::A);
+    assert_eq!(
+        missing.kind,
+        DifferenceKind::MissingRrset {
+            present_on: Side::Second
+        }
+    );
+
+    let drift = &report.differences[1];
+    assert_eq!(drift.name, www);
+    assert_eq!(drift.record_type, RecordType::A);
+    assert_eq!(
+        drift.kind,
+        DifferenceKind::TtlDrift {
+            first: 86400,
+            second: 3600
+        }
+    );
+
+    assert_eq!(report.max_severity(), Some(Severity::Error));
+    assert!(report.has_differences(Severity::Warning));
+
+    first.shutdown_gracefully().await.unwrap();
+    second.shutdown_gracefully().await.unwrap();
+}
@@ -0,0 +1,201 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! A client stream answering the requests with a `RequestHandler`, without any sockets
+
+use std::{
+    fmt, io, mem,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+};
+
+use futures::{
+    future,
+    stream::{Stream, StreamExt},
+    Future, FutureExt,
+};
+
+use hickory_proto::{
+    error::ProtoError,
+    op::Message,
+    rr::Record,
+    serialize::binary::{BinDecodable, BinDecoder, BinEncoder},
+    xfer::{DnsClientStream, DnsResponse, SerialMessage, StreamReceiver},
+    BufDnsStreamHandle, TokioTime,
+};
+use hickory_server::{
+    authority::{Catalog, MessageRequest, MessageResponse},
+    server::{Protocol, Request, RequestHandler, ResponseHandler, ResponseInfo},
+};
+
+/// A `DnsClientStream` which passes each request to a `RequestHandler`, usually a `Catalog`
+///
+/// The requests are answered in the task polling the stream, as if they were received over UDP
+///  from `127.0.0.1:1234`.
+pub struct TestClientStream<H = Catalog> {
+    catalog: Arc<Mutex<H>>,
+    outbound_messages: StreamReceiver,
+}
+
+impl<H: RequestHandler> TestClientStream<H> {
+    /// Returns the stream and the handle for sending to it, e.g. for `AsyncClient::new`
+    #[allow(clippy::type_complexity)]
+    pub fn new(
+        catalog: Arc<Mutex<H>>,
+    ) -> (
+        Pin<Box<dyn Future<Output = Result<Self, ProtoError>> + Send>>,
+        BufDnsStreamHandle,
+    ) {
+        let (message_sender, outbound_messages) = BufDnsStreamHandle::new(([0, 0, 0, 0], 0).into());
+
+        let stream = Box::pin(future::ok(Self {
+            catalog,
+            outbound_messages,
+        }));
+
+        (stream, message_sender)
+    }
+}
+
+/// A `ResponseHandler` which keeps the encoded response, for calling a `RequestHandler` directly
+#[derive(Clone, Default)]
+pub struct TestResponseHandler {
+    message_ready: Arc<AtomicBool>,
+    buf: Arc<Mutex<Vec<u8>>>,
+}
+
+impl TestResponseHandler {
+    /// Creates a handler without a response
+    pub fn new() -> Self {
+        let buf = Arc::new(Mutex::new(Vec::with_capacity(512)));
+        let message_ready = Arc::new(AtomicBool::new(false));
+        Self { message_ready, buf }
+    }
+
+    /// Waits for the response, and returns it encoded
+    pub fn into_inner(self) -> impl Future<Output = Vec<u8>> {
+        future::poll_fn(move |cx| {
+            if self
+                .message_ready
+                .compare_exchange(true, false, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+            {
+                let bytes: Vec<u8> = mem::take(&mut self.buf.lock().unwrap());
+                Poll::Ready(bytes)
+            } else {
+                // the response might be sent from another task, which doesn't know about this waker
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        })
+    }
+
+    /// Waits for the response, and returns it decoded
+    pub fn into_message(self) -> impl Future<Output = Message> {
+        let bytes = self.into_inner();
+        bytes.map(|b| {
+            let mut decoder = BinDecoder::new(&b);
+            Message::read(&mut decoder).expect("could not decode message")
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl ResponseHandler for TestResponseHandler {
+    async fn send_response<'a>(
+        &mut self,
+        response: MessageResponse<
+            '_,
+            'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+        >,
+    ) -> io::Result<ResponseInfo> {
+        let buf = &mut self.buf.lock().unwrap();
+        buf.clear();
+        let mut encoder = BinEncoder::new(buf);
+        let info = response
+            .destructive_emit(&mut encoder)
+            .expect("could not encode");
+        self.message_ready.store(true, Ordering::Release);
+        Ok(info)
+    }
+
+    async fn send_raw_response(
+        &mut self,
+        id: u16,
+        response: &DnsResponse,
+    ) -> io::Result<ResponseInfo> {
+        let mut header = *response.header();
+        header.set_id(id);
+
+        *self.buf.lock().unwrap() = response.bytes_with_id(id);
+        self.message_ready.store(true, Ordering::Release);
+        Ok(header.into())
+    }
+}
+
+impl<H> fmt::Display for TestClientStream<H> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "TestClientStream")
+    }
+}
+
+impl<H: RequestHandler> DnsClientStream for TestClientStream<H> {
+    type Time = TokioTime;
+
+    fn name_server_addr(&self) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], 1234))
+    }
+}
+
+impl<H: RequestHandler> Stream for TestClientStream<H> {
+    type Item = Result<SerialMessage, ProtoError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        use futures::executor::block_on;
+
+        match self.outbound_messages.next().poll_unpin(cx) {
+            // already handled above, here to make sure the poll() pops the next message
+            Poll::Ready(Some(bytes)) => {
+                let mut decoder = BinDecoder::new(bytes.bytes());
+                let src_addr = SocketAddr::from(([127, 0, 0, 1], 1234));
+
+                let message = MessageRequest::read(&mut decoder).expect("could not decode message");
+                let request = Request::new(message, src_addr, Protocol::Udp);
+
+                let response_handler = TestResponseHandler::new();
+                block_on(
+                    self.catalog
+                        .lock()
+                        .unwrap()
+                        .handle_request(&request, response_handler.clone()),
+                );
+
+                let buf = block_on(response_handler.into_inner());
+                Poll::Ready(Some(Ok(SerialMessage::new(buf, src_addr))))
+            }
+            // all the handles were dropped, there is nothing more to answer
+            Poll::Ready(None) => Poll::Ready(None),
+            // the receiver registered the waker, we'll be woken on the next message
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<H> fmt::Debug for TestClientStream<H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TestClientStream catalog")
+    }
+}
@@ -0,0 +1,65 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Test fixtures for Hickory DNS, usable by downstream crates
+//!
+//! [`TestDnsNetwork`] wires a set of authoritative zones together, optionally signed and with the
+//!  delegations between them, and hands out clients which reach the zones in-process without any
+//!  sockets, or over loopback UDP. [`TestClientStream`] is the underlying client stream, answering
+//!  requests with any `RequestHandler`.
+//!
+//! ```no_run
+//! # #[cfg(feature = "dnssec")]
+//! # async fn network() -> Result<(), Box<dyn std::error::Error>> {
+//! use std::str::FromStr;
+//!
+//! use hickory_client::client::ClientHandle;
+//! use hickory_proto::rr::{rdata::A, DNSClass, Name, RData, Record, RecordType};
+//! use hickory_test_support::TestDnsNetwork;
+//!
+//! let www = Name::from_str("www.sub.example.com.")?;
+//! let network = TestDnsNetwork::builder()
+//!     .zone("example.com.", vec![])
+//!     .signed()
+//!     .child_zone(
+//!         "sub.example.com.",
+//!         vec![Record::from_rdata(www.clone(), 3600, RData::A(A::new(192, 0, 2, 1)))],
+//!     )
+//!     .signed()
+//!     .build()
+//!     .await?;
+//!
+//! let mut client = network.dnssec_client().await?;
+//! let response = client.query(www, DNSClass::IN, RecordType::A).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The API of this crate follows the semver of the other Hickory DNS crates.
+
+#![warn(
+    clippy::default_trait_access,
+    clippy::dbg_macro,
+    clippy::print_stdout,
+    clippy::unimplemented,
+    clippy::use_self,
+    missing_copy_implementations,
+    missing_docs,
+    non_snake_case,
+    non_upper_case_globals,
+    rust_2018_idioms,
+    unreachable_pub
+)]
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+mod client_stream;
+mod network;
+
+pub use self::client_stream::{TestClientStream, TestResponseHandler};
+#[cfg(feature = "dnssec")]
+pub use self::network::{generate_key, ALGORITHM};
+pub use self::network::{NetworkHandler, TestDnsNetwork, TestDnsNetworkBuilder, ZoneHandle};
@@ -0,0 +1,640 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! An in-process network of authoritative zones, with delegations and DNSSEC signing
+
+use std::{
+    fmt, io,
+    net::{Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex},
+};
+
+use futures::executor::block_on;
+use tokio::net::UdpSocket;
+
+use hickory_client::client::AsyncClient;
+use hickory_proto::{
+    error::ProtoError,
+    op::LowerQuery,
+    rr::{
+        rdata::{NS, SOA},
+        IntoName, LowerName, Name, RData, Record, RecordType,
+    },
+    udp::UdpClientStream,
+};
+use hickory_server::{
+    authority::{Authority, Catalog, ZoneType},
+    server::{Request, RequestHandler, ResponseHandler, ResponseInfo},
+    store::in_memory::InMemoryAuthority,
+    ServerFuture,
+};
+
+#[cfg(feature = "dnssec")]
+use hickory_proto::{
+    rr::dnssec::{
+        rdata::{DNSSECRData, DS},
+        Algorithm, DigestType, DnsSecResult, KeyPair, Private, SigSigner, TrustAnchor,
+    },
+    DnssecDnsHandle,
+};
+#[cfg(feature = "dnssec")]
+use hickory_server::authority::DnssecAuthority;
+
+use crate::TestClientStream;
+
+/// The algorithm of the keys generated for the signed zones
+#[cfg(feature = "dnssec")]
+pub const ALGORITHM: Algorithm = Algorithm::ECDSAP256SHA256;
+
+/// The validity of the signatures of the signed zones
+#[cfg(feature = "dnssec")]
+const SIGNATURE_DURATION: std::time::Duration = std::time::Duration::from_secs(7 * 24 * 60 * 60);
+
+/// A set of authoritative zones answering in-process, see [`TestDnsNetworkBuilder`]
+///
+/// The zones are reachable without sockets through [`TestDnsNetwork::client`], and on a loopback
+///  UDP socket if it was requested. Clients and servers are spawned on the current tokio runtime.
+pub struct TestDnsNetwork {
+    zones: Vec<ZoneHandle>,
+    handler: NetworkHandler,
+    #[cfg(feature = "dnssec")]
+    trust_anchor: TrustAnchor,
+    udp: Option<(SocketAddr, ServerFuture<NetworkHandler>)>,
+    #[cfg(feature = "forwarder")]
+    forwarder: Option<(SocketAddr, ServerFuture<Catalog>)>,
+}
+
+impl TestDnsNetwork {
+    /// Returns a builder for a new network
+    pub fn builder() -> TestDnsNetworkBuilder {
+        TestDnsNetworkBuilder::default()
+    }
+
+    /// All zones of the network, in the order they were added
+    pub fn zones(&self) -> &[ZoneHandle] {
+        &self.zones
+    }
+
+    /// The zone with the origin `origin`
+    pub fn zone(&self, origin: impl IntoName) -> Option<&ZoneHandle> {
+        let origin = LowerName::from(origin.into_name().ok()?);
+        self.zones.iter().find(|zone| zone.origin() == &origin)
+    }
+
+    /// The `RequestHandler` answering for all zones, e.g. for a `ServerFuture` of its own
+    pub fn handler(&self) -> NetworkHandler {
+        self.handler.clone()
+    }
+
+    /// Returns a client sending its requests in-process to the zones of the network
+    ///
+    /// Each request is answered by the zone closest to its name, DS requests for the origin of a
+    ///  child zone are answered by the parent, as a resolver would ask them.
+    pub async fn client(&self) -> Result<AsyncClient, ProtoError> {
+        let (stream, sender) = TestClientStream::new(Arc::new(Mutex::new(self.handler())));
+        let (client, bg) = AsyncClient::new(stream, sender, None).await?;
+        tokio::spawn(bg);
+
+        Ok(client)
+    }
+
+    /// The keys of the signed zones which don't have a parent in the network
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub fn trust_anchor(&self) -> &TrustAnchor {
+        &self.trust_anchor
+    }
+
+    /// Returns a validating client, with the [`TestDnsNetwork::trust_anchor`], see
+    ///  [`TestDnsNetwork::client`]
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub async fn dnssec_client(&self) -> Result<DnssecDnsHandle<AsyncClient>, ProtoError> {
+        let client = self.client().await?;
+        Ok(DnssecDnsHandle::with_trust_anchor(
+            client,
+            self.trust_anchor.clone(),
+        ))
+    }
+
+    /// The loopback address the zones are served on, if enabled with
+    ///  [`TestDnsNetworkBuilder::loopback_udp`]
+    pub fn udp_addr(&self) -> Option<SocketAddr> {
+        self.udp.as_ref().map(|(addr, _)| *addr)
+    }
+
+    /// Returns a client sending its requests over UDP to [`TestDnsNetwork::udp_addr`]
+    pub async fn udp_client(&self) -> Result<AsyncClient, ProtoError> {
+        let addr = self
+            .udp_addr()
+            .ok_or("the network is not served on loopback UDP")?;
+        udp_client(addr).await
+    }
+
+    /// The loopback address of the forwarder, if enabled with [`TestDnsNetworkBuilder::forwarder`]
+    #[cfg(feature = "forwarder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "forwarder")))]
+    pub fn forwarder_addr(&self) -> Option<SocketAddr> {
+        self.forwarder.as_ref().map(|(addr, _)| *addr)
+    }
+
+    /// Returns a client sending its requests over UDP to [`TestDnsNetwork::forwarder_addr`]
+    #[cfg(feature = "forwarder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "forwarder")))]
+    pub async fn forwarder_client(&self) -> Result<AsyncClient, ProtoError> {
+        let addr = self
+            .forwarder_addr()
+            .ok_or("the network has no forwarder")?;
+        udp_client(addr).await
+    }
+}
+
+impl fmt::Debug for TestDnsNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TestDnsNetwork")
+            .field("zones", &self.zones)
+            .field("udp", &self.udp_addr())
+            .finish()
+    }
+}
+
+async fn udp_client(addr: SocketAddr) -> Result<AsyncClient, ProtoError> {
+    let stream = UdpClientStream::<UdpSocket>::new(addr);
+    let (client, bg) = AsyncClient::connect(stream).await?;
+    tokio::spawn(bg);
+
+    Ok(client)
+}
+
+/// Builds a [`TestDnsNetwork`]
+///
+/// ```no_run
+/// # async fn network() -> std::io::Result<()> {
+/// use std::str::FromStr;
+///
+/// use hickory_proto::rr::{rdata::A, Name, RData, Record};
+/// use hickory_test_support::TestDnsNetwork;
+///
+/// let www = Record::from_rdata(
+///     Name::from_str("www.example.com.").unwrap(),
+///     3600,
+///     RData::A(A::new(192, 0, 2, 1)),
+/// );
+///
+/// let network = TestDnsNetwork::builder()
+///     .zone("example.com.", vec![www])
+///     .child_zone("sub.example.com.", vec![])
+///     .build()
+///     .await?;
+/// let client = network.client().await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Zones without an SOA or NS record at their origin get a default one. With the `dnssec`
+///  feature, zones marked with [`TestDnsNetworkBuilder::signed`] are signed with a new key, and a
+///  DS record of it is added to the parent.
+#[derive(Default)]
+pub struct TestDnsNetworkBuilder {
+    zones: Vec<ZoneSpec>,
+    loopback_udp: bool,
+    #[cfg(feature = "forwarder")]
+    forwarder: bool,
+}
+
+struct ZoneSpec {
+    authority: InMemoryAuthority,
+    parent: Option<usize>,
+    #[cfg(feature = "dnssec")]
+    signed: bool,
+}
+
+impl TestDnsNetworkBuilder {
+    /// Adds a zone with the `records`, independent of the other zones
+    ///
+    /// # Panics
+    ///
+    /// If `origin` is not a valid name.
+    pub fn zone(self, origin: impl IntoName, records: impl IntoIterator<Item = Record>) -> Self {
+        let origin = origin.into_name().expect("invalid zone origin");
+        self.authority(zone_authority(origin, records))
+    }
+
+    /// Adds a zone delegated from the closest zone above it, which gets NS records for it
+    ///
+    /// # Panics
+    ///
+    /// If `origin` is not a valid name, or no zone added before is a parent of it.
+    pub fn child_zone(
+        mut self,
+        origin: impl IntoName,
+        records: impl IntoIterator<Item = Record>,
+    ) -> Self {
+        let origin = origin.into_name().expect("invalid zone origin");
+        let authority = zone_authority(origin.clone(), records);
+        let parent = self
+            .parent_of(&origin)
+            .expect("no parent zone for the child");
+
+        let delegation = block_on(authority.records())
+            .into_iter()
+            .filter(|(key, _)| {
+                key.record_type == RecordType::NS && key.name() == authority.origin()
+            })
+            .flat_map(|(_, rrset)| rrset.records_without_rrsigs().cloned().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let parent_authority = &mut self.zones[parent].authority;
+        let serial = block_on(parent_authority.serial());
+        for record in delegation {
+            parent_authority.upsert_mut(record, serial);
+        }
+
+        self.zones.push(ZoneSpec {
+            authority,
+            parent: Some(parent),
+            #[cfg(feature = "dnssec")]
+            signed: false,
+        });
+        self
+    }
+
+    /// Adds an existing zone, e.g. one which is signed already
+    pub fn authority(mut self, authority: InMemoryAuthority) -> Self {
+        self.zones.push(ZoneSpec {
+            authority,
+            parent: None,
+            #[cfg(feature = "dnssec")]
+            signed: false,
+        });
+        self
+    }
+
+    /// Signs the zone added last when the network is built
+    ///
+    /// # Panics
+    ///
+    /// If no zone was added yet.
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub fn signed(mut self) -> Self {
+        self.zones.last_mut().expect("no zone to sign").signed = true;
+        self
+    }
+
+    /// Serves the zones on a UDP socket bound to an ephemeral port of `127.0.0.1`
+    pub fn loopback_udp(mut self) -> Self {
+        self.loopback_udp = true;
+        self
+    }
+
+    /// Adds a forwarding server on loopback UDP, which forwards all requests to the zones
+    ///
+    /// This implies [`TestDnsNetworkBuilder::loopback_udp`], the forwarder sends its requests there.
+    #[cfg(feature = "forwarder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "forwarder")))]
+    pub fn forwarder(mut self) -> Self {
+        self.loopback_udp = true;
+        self.forwarder = true;
+        self
+    }
+
+    /// Signs the zones, and starts the servers on the current tokio runtime
+    pub async fn build(self) -> io::Result<TestDnsNetwork> {
+        #[cfg(feature = "forwarder")]
+        let forwarder = self.forwarder;
+        let Self {
+            zones,
+            loopback_udp,
+            ..
+        } = self;
+
+        if zones.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the network has no zones",
+            ));
+        }
+
+        #[cfg(feature = "dnssec")]
+        let (zones, trust_anchor) = {
+            let mut zones = zones;
+            let trust_anchor = sign_zones(&mut zones).await.map_err(other)?;
+            (zones, trust_anchor)
+        };
+
+        let zones = zones
+            .into_iter()
+            .map(|spec| {
+                (
+                    ZoneHandle {
+                        authority: Arc::new(spec.authority),
+                    },
+                    spec.parent,
+                )
+            })
+            .collect::<Vec<_>>();
+        let handler = NetworkHandler {
+            zones: zones
+                .iter()
+                .map(|(zone, parent)| {
+                    let mut catalog = Catalog::new();
+                    catalog.upsert(zone.origin().clone(), Box::new(zone.authority.clone()));
+                    RoutedZone {
+                        origin: zone.origin().clone(),
+                        parent: *parent,
+                        catalog,
+                    }
+                })
+                .collect(),
+        };
+
+        let udp = if loopback_udp {
+            let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+            let addr = socket.local_addr()?;
+            let mut server = ServerFuture::new(handler.clone());
+            server.register_socket(socket);
+            Some((addr, server))
+        } else {
+            None
+        };
+
+        #[cfg(feature = "forwarder")]
+        let forwarder = match (forwarder, &udp) {
+            (true, Some((upstream, _))) => Some(serve_forwarder(*upstream).await?),
+            _ => None,
+        };
+
+        Ok(TestDnsNetwork {
+            zones: zones.into_iter().map(|(zone, _)| zone).collect(),
+            handler,
+            #[cfg(feature = "dnssec")]
+            trust_anchor,
+            udp,
+            #[cfg(feature = "forwarder")]
+            forwarder,
+        })
+    }
+
+    /// The deepest zone which `name` is in
+    fn parent_of(&self, name: &Name) -> Option<usize> {
+        let name = LowerName::from(name);
+        self.zones
+            .iter()
+            .enumerate()
+            .filter(|(_, zone)| zone.authority.origin().zone_of(&name))
+            .max_by_key(|(_, zone)| zone.authority.origin().num_labels())
+            .map(|(idx, _)| idx)
+    }
+}
+
+/// Returns a primary zone with the records, and a default SOA and NS record if they are missing
+fn zone_authority(origin: Name, records: impl IntoIterator<Item = Record>) -> InMemoryAuthority {
+    let mut records = records.into_iter().collect::<Vec<_>>();
+    let at_origin = |records: &[Record], record_type| {
+        records
+            .iter()
+            .any(|r| r.name() == &origin && r.record_type() == record_type)
+    };
+
+    let name_server = Name::from_ascii("ns")
+        .and_then(|ns| ns.append_domain(&origin))
+        .expect("invalid name server name");
+    if !at_origin(&records, RecordType::SOA) {
+        let hostmaster = Name::from_ascii("hostmaster")
+            .and_then(|hostmaster| hostmaster.append_domain(&origin))
+            .expect("invalid hostmaster name");
+        records.push(Record::from_rdata(
+            origin.clone(),
+            3600,
+            RData::SOA(SOA::new(
+                name_server.clone(),
+                hostmaster,
+                1,
+                7200,
+                3600,
+                1209600,
+                3600,
+            )),
+        ));
+    }
+    if !at_origin(&records, RecordType::NS) {
+        records.push(Record::from_rdata(
+            origin.clone(),
+            3600,
+            RData::NS(NS(name_server)),
+        ));
+    }
+
+    let serial = records
+        .iter()
+        .find_map(|r| match r.data() {
+            RData::SOA(soa) if r.name() == &origin => Some(soa.serial()),
+            _ => None,
+        })
+        .unwrap_or(1);
+    let mut authority = InMemoryAuthority::empty(origin, ZoneType::Primary, false);
+    for record in records {
+        authority.upsert_mut(record, serial);
+    }
+
+    authority
+}
+
+/// Signs the zones marked as signed, children before their parents so that the parents sign the
+///  DS records, and returns the keys of the signed zones without a parent
+#[cfg(feature = "dnssec")]
+async fn sign_zones(zones: &mut [ZoneSpec]) -> DnsSecResult<TrustAnchor> {
+    let mut order = (0..zones.len()).collect::<Vec<_>>();
+    order.sort_by_key(|idx| std::cmp::Reverse(zones[*idx].authority.origin().num_labels()));
+
+    for idx in order {
+        if !zones[idx].signed {
+            continue;
+        }
+
+        let origin = Name::from(zones[idx].authority.origin());
+        let key = generate_key()?;
+        let dnskey = key.to_dnskey(ALGORITHM)?;
+
+        if let Some(parent) = zones[idx].parent {
+            let ds = DS::new(
+                dnskey.calculate_key_tag()?,
+                ALGORITHM,
+                DigestType::SHA256,
+                dnskey
+                    .to_digest(&origin, DigestType::SHA256)?
+                    .as_ref()
+                    .to_owned(),
+            );
+            let parent = &mut zones[parent].authority;
+            let serial = parent.serial().await;
+            parent.upsert_mut(
+                Record::from_rdata(origin.clone(), 3600, RData::DNSSEC(DNSSECRData::DS(ds))),
+                serial,
+            );
+        }
+
+        zones[idx]
+            .authority
+            .sign(SigSigner::dnssec(dnskey, key, origin, SIGNATURE_DURATION))?;
+    }
+
+    let mut trust_anchor = TrustAnchor::new();
+    for zone in zones.iter().filter(|zone| zone.parent.is_none()) {
+        for signer in zone.authority.secure_keys().await.iter() {
+            trust_anchor.insert_trust_anchor(&signer.key().to_public_key()?);
+        }
+    }
+
+    Ok(trust_anchor)
+}
+
+/// Generates a new key of [`ALGORITHM`]
+#[cfg(feature = "dnssec")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+pub fn generate_key() -> DnsSecResult<KeyPair<Private>> {
+    #[cfg(feature = "dnssec-ring")]
+    {
+        use hickory_proto::rr::dnssec::KeyFormat;
+
+        let pkcs8 = KeyPair::generate_pkcs8(ALGORITHM)?;
+        KeyFormat::Pkcs8.decode_key(&pkcs8, None, ALGORITHM)
+    }
+
+    #[cfg(not(feature = "dnssec-ring"))]
+    KeyPair::generate(ALGORITHM)
+}
+
+/// Serves a forwarder to `upstream` on loopback UDP
+#[cfg(feature = "forwarder")]
+async fn serve_forwarder(upstream: SocketAddr) -> io::Result<(SocketAddr, ServerFuture<Catalog>)> {
+    use hickory_server::{
+        resolver::config::{NameServerConfig, NameServerConfigGroup, Protocol},
+        store::forwarder::{
+            ForwardAuthority, ForwardCacheConfig, ForwardConfig, ForwardValidation,
+        },
+    };
+
+    let config = ForwardConfig {
+        name_servers: NameServerConfigGroup::from(vec![NameServerConfig::new(
+            upstream,
+            Protocol::Udp,
+        )]),
+        options: None,
+        validation: ForwardValidation::None,
+        trust_ad: false,
+        cache: ForwardCacheConfig::default(),
+        debug_upstream: false,
+    };
+    let authority = ForwardAuthority::try_from_config(Name::root(), ZoneType::Forward, &config)
+        .map_err(other)?;
+
+    let mut catalog = Catalog::new();
+    catalog.upsert(Name::root().into(), Box::new(Arc::new(authority)));
+
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+    let addr = socket.local_addr()?;
+    let mut server = ServerFuture::new(catalog);
+    server.register_socket(socket);
+
+    Ok((addr, server))
+}
+
+#[allow(dead_code)]
+fn other(error: impl fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, error.to_string())
+}
+
+/// A zone of a [`TestDnsNetwork`], for changing its records while the network is running
+#[derive(Clone)]
+pub struct ZoneHandle {
+    authority: Arc<InMemoryAuthority>,
+}
+
+impl ZoneHandle {
+    /// The origin of the zone
+    pub fn origin(&self) -> &LowerName {
+        self.authority.origin()
+    }
+
+    /// The authority serving the zone
+    pub fn authority(&self) -> &Arc<InMemoryAuthority> {
+        &self.authority
+    }
+
+    /// Inserts or replaces the record, and signs the zone again if it's signed
+    ///
+    /// Returns true if the record was inserted.
+    pub async fn upsert(&self, record: Record) -> io::Result<bool> {
+        let serial = self.authority.serial().await;
+        let inserted = self.authority.upsert(record, serial).await;
+
+        #[cfg(feature = "dnssec")]
+        if inserted && self.authority.is_signed().await {
+            self.authority.secure_zone().await.map_err(other)?;
+        }
+
+        Ok(inserted)
+    }
+}
+
+impl fmt::Debug for ZoneHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZoneHandle")
+            .field("origin", self.origin())
+            .finish()
+    }
+}
+
+/// Answers each request from the zone of a [`TestDnsNetwork`] closest to the name of its query
+///
+/// DS requests for the origin of a child zone are answered by the parent zone, as a resolver
+///  which knows the zone cut would ask them.
+#[derive(Clone)]
+pub struct NetworkHandler {
+    zones: Arc<[RoutedZone]>,
+}
+
+struct RoutedZone {
+    origin: LowerName,
+    parent: Option<usize>,
+    catalog: Catalog,
+}
+
+impl NetworkHandler {
+    fn route(&self, query: &LowerQuery) -> &RoutedZone {
+        let name = query.name();
+        let closest = self
+            .zones
+            .iter()
+            .filter(|zone| zone.origin.zone_of(name))
+            .max_by_key(|zone| zone.origin.num_labels());
+
+        match closest {
+            Some(zone) if query.query_type() == RecordType::DS && &zone.origin == name => {
+                zone.parent.map_or(zone, |parent| &self.zones[parent])
+            }
+            Some(zone) => zone,
+            // the catalog refuses names it isn't authoritative for
+            None => &self.zones[0],
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RequestHandler for NetworkHandler {
+    async fn handle_request<R: ResponseHandler>(
+        &self,
+        request: &Request,
+        response_handle: R,
+    ) -> ResponseInfo {
+        self.route(request.query())
+            .catalog
+            .handle_request(request, response_handle)
+            .await
+    }
+}
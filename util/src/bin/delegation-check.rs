@@ -0,0 +1,106 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The delegation-check program
+
+// BINARY WARNINGS
+#![warn(
+    clippy::default_trait_access,
+    clippy::dbg_macro,
+    clippy::unimplemented,
+    missing_copy_implementations,
+    missing_docs,
+    non_snake_case,
+    non_upper_case_globals,
+    rust_2018_idioms,
+    unreachable_pub
+)]
+
+use std::{net::IpAddr, process::ExitCode, time::Duration};
+
+use clap::Parser;
+use console::style;
+
+use hickory_resolver::{
+    config::{ResolverConfig, ResolverOpts},
+    delegation::DelegationChecker,
+    name_server::TokioConnectionProvider,
+    Name, TokioAsyncResolver,
+};
+
+/// Checks the delegation of a zone.
+///
+/// The NS records and glue of the delegation in the parent zone are compared with the NS records
+/// at the apex of the child zone, and with the addresses of the in-bailiwick name servers in the
+/// child zone. Name servers which don't answer authoritatively for the zone are reported as lame.
+/// The exit status is 1 if anything was found.
+#[derive(Debug, Parser)]
+#[clap(name = "delegation-check")]
+struct Opts {
+    /// The zone to check, e.g. example.com.
+    zone: Name,
+
+    /// The name servers of the parent zone, looked up with the system configuration by default,
+    /// delimited by a comma like 192.0.2.1,192.0.2.2
+    #[clap(short = 'p', long, use_value_delimiter = true, value_delimiter(','))]
+    parent: Vec<IpAddr>,
+
+    /// The port of the name servers
+    #[clap(long, default_value = "53")]
+    port: u16,
+
+    /// The timeout of every query in seconds
+    #[clap(long, default_value = "5")]
+    timeout: u64,
+
+    /// Enable debug and all logging
+    #[clap(long)]
+    debug: bool,
+}
+
+/// Run the delegation-check program
+#[tokio::main]
+pub async fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let opts: Opts = Opts::parse();
+
+    let log_level = opts.debug.then_some(tracing::Level::DEBUG);
+    hickory_util::logger(env!("CARGO_BIN_NAME"), log_level);
+
+    let mut options = ResolverOpts::default();
+    options.timeout = Duration::from_secs(opts.timeout);
+
+    let mut checker = DelegationChecker::new(options, TokioConnectionProvider::default())
+        .with_port(opts.port);
+    if opts.parent.is_empty() {
+        let (config, options) = hickory_resolver::system_conf::read_system_conf()
+            .unwrap_or_else(|_| (ResolverConfig::default(), ResolverOpts::default()));
+        checker = checker.with_resolver(TokioAsyncResolver::tokio(config, options));
+    } else {
+        checker = checker.with_parent_servers(opts.parent);
+    }
+
+    let report = checker.check(&opts.zone).await?;
+
+    println!("zone {}", style(report.zone()).blue());
+    for name in report.parent_name_servers() {
+        println!("parent NS {}", style(name).blue());
+    }
+    for name in report.child_name_servers() {
+        println!("child NS {}", style(name).blue());
+    }
+
+    if report.is_consistent() {
+        println!("{}", style("consistent").green());
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    for finding in report.findings() {
+        println!("{}", style(finding).red());
+    }
+
+    Ok(ExitCode::FAILURE)
+}
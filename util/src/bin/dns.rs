@@ -21,6 +21,7 @@
 )]
 
 use std::net::SocketAddr;
+use std::time::Duration;
 #[cfg(feature = "dns-over-rustls")]
 use std::{sync::Arc, time::SystemTime};
 
@@ -35,6 +36,7 @@ use tracing::Level;
 
 use hickory_client::{
     client::{AsyncClient, ClientHandle},
+    edns_compliance::{self, ProbeOptions},
     rr::{DNSClass, RData, RecordSet, RecordType},
     serialize::txt::RDataParser,
     tcp::TcpClientStream,
@@ -125,6 +127,7 @@ enum Command {
     Append(AppendOpt),
     // CompareAndSwap(),
     DeleteRecord(DeleteRecordOpt),
+    EdnsCompliance(EdnsComplianceOpt),
     // DeleteRecordSet,
     // DeleteAll,
     // ZoneTransfer,
@@ -212,6 +215,21 @@ struct DeleteRecordOpt {
     rdata: Vec<String>,
 }
 
+/// Probe the EDNS compliance of the name server, with queries for the SOA of a zone it serves
+#[derive(Debug, Args)]
+struct EdnsComplianceOpt {
+    /// Name of a zone the name server is authoritative for
+    name: Name,
+
+    /// Maximum number of probes in flight at once
+    #[clap(long, default_value_t = 4)]
+    concurrency: usize,
+
+    /// Minimum number of milliseconds between starting two probes
+    #[clap(long, default_value_t = 50)]
+    interval: u64,
+}
+
 /// Run the resolve program
 #[tokio::main]
 pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -485,6 +503,19 @@ async fn handle_request(
             println!("; sending delete-record: {name} {class} {ty} from {zone}");
             client.delete_by_rdata(rdata, zone).await?
         }
+        Command::EdnsCompliance(opt) => {
+            let name = opt.name;
+            let options = ProbeOptions {
+                concurrency: opt.concurrency,
+                interval: Duration::from_millis(opt.interval),
+                ..ProbeOptions::default()
+            };
+
+            println!("; probing edns compliance: {name}");
+            let report = edns_compliance::run_probes(client, name, &options).await;
+            print!("{report}");
+            return Ok(());
+        }
     };
 
     let response = response.into_message();
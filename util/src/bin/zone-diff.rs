@@ -0,0 +1,194 @@
+// Copyright 2015-2023 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// https://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// https://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! The zone-diff program
+
+// BINARY WARNINGS
+#![warn(
+    clippy::default_trait_access,
+    clippy::dbg_macro,
+    clippy::unimplemented,
+    missing_copy_implementations,
+    missing_docs,
+    non_snake_case,
+    non_upper_case_globals,
+    rust_2018_idioms,
+    unreachable_pub
+)]
+
+use std::{fs, net::SocketAddr, path::PathBuf, process::ExitCode, time::Duration};
+
+use clap::{Parser, ValueEnum};
+use console::style;
+use tokio::net::TcpStream as TokioTcpStream;
+
+use hickory_client::{
+    client::AsyncClient,
+    rr::{Name, RecordType},
+    tcp::TcpClientStream,
+    zone_diff::{self, DiffOptions, Severity, ZoneSource},
+};
+use hickory_proto::iocompat::AsyncIoTokioAsStd;
+
+/// Compares the copies of a zone served by two name servers, e.g. before moving the zone.
+///
+/// The zone is read with a zone transfer, else by walking its NSEC chain, else by querying the
+/// names of the names file. The RRsets missing on either server, with different record data or
+/// with a different TTL are reported. The exit status is 1 if a difference of at least the
+/// `--fail-on` severity was found.
+#[derive(Debug, Parser)]
+#[clap(name = "zone-diff")]
+struct Opts {
+    /// The zone to compare, e.g. example.com.
+    zone: Name,
+
+    /// The first name server, usually the current one, ip and port e.g. 192.0.2.1:53
+    first: SocketAddr,
+
+    /// The second name server, usually the one the zone is moved to
+    second: SocketAddr,
+
+    /// The way the zone is read from the name servers
+    #[clap(short = 's', long, default_value = "auto", value_enum)]
+    source: Source,
+
+    /// A file with the names to query, one per line, when the zone can't be transferred or walked
+    #[clap(long)]
+    names: Option<PathBuf>,
+
+    /// The least severity of the differences which fail the comparison
+    #[clap(long, default_value = "warning", value_enum)]
+    fail_on: FailOn,
+
+    /// Compare the RRSIG records, which differ between signers
+    #[clap(long)]
+    compare_rrsig: bool,
+
+    /// Report a different SOA serial
+    #[clap(long)]
+    compare_soa_serial: bool,
+
+    /// The timeout of every query in seconds
+    #[clap(long, default_value = "5")]
+    timeout: u64,
+
+    /// Enable debug and all logging
+    #[clap(long)]
+    debug: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Source {
+    Auto,
+    Transfer,
+    Walk,
+    Names,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum FailOn {
+    Info,
+    Warning,
+    Error,
+}
+
+impl From<FailOn> for Severity {
+    fn from(fail_on: FailOn) -> Self {
+        match fail_on {
+            FailOn::Info => Self::Info,
+            FailOn::Warning => Self::Warning,
+            FailOn::Error => Self::Error,
+        }
+    }
+}
+
+/// Run the zone-diff program
+#[tokio::main]
+pub async fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let opts: Opts = Opts::parse();
+
+    let log_level = opts.debug.then_some(tracing::Level::DEBUG);
+    hickory_util::logger(env!("CARGO_BIN_NAME"), log_level);
+
+    let names = match &opts.names {
+        Some(path) => fs::read_to_string(path)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with(';'))
+            .map(|line| Name::from_ascii(line)?.append_domain(&opts.zone))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => vec![],
+    };
+
+    let source = match opts.source {
+        Source::Auto => ZoneSource::Auto(names),
+        Source::Transfer => ZoneSource::Transfer,
+        #[cfg(any(feature = "dnssec-openssl", feature = "dnssec-ring"))]
+        Source::Walk => ZoneSource::Walk,
+        #[cfg(not(any(feature = "dnssec-openssl", feature = "dnssec-ring")))]
+        Source::Walk => return Err("walking the NSEC chain requires a dnssec feature".into()),
+        Source::Names if names.is_empty() => return Err("--names is required".into()),
+        Source::Names => ZoneSource::Names(names),
+    };
+
+    let mut options = DiffOptions {
+        ignore_soa_serial: !opts.compare_soa_serial,
+        ..DiffOptions::default()
+    };
+    if opts.compare_rrsig {
+        options.ignored_types.retain(|t| *t != RecordType::RRSIG);
+    }
+
+    let timeout = Duration::from_secs(opts.timeout);
+    let first = client(opts.first, timeout).await?;
+    let second = client(opts.second, timeout).await?;
+
+    let report = zone_diff::diff_zones(first, second, opts.zone, &source, &options).await?;
+
+    println!("zone {}", style(&report.zone).blue());
+    println!(
+        "first {} {} rrsets by {}",
+        style(opts.first).blue(),
+        report.rrsets[0],
+        report.sources[0]
+    );
+    println!(
+        "second {} {} rrsets by {}",
+        style(opts.second).blue(),
+        report.rrsets[1],
+        report.sources[1]
+    );
+
+    for difference in &report.differences {
+        let line = match difference.severity() {
+            Severity::Info => style(difference).dim(),
+            Severity::Warning => style(difference).yellow(),
+            Severity::Error => style(difference).red(),
+        };
+        println!("{line}");
+    }
+
+    if report.has_differences(opts.fail_on.into()) {
+        return Ok(ExitCode::FAILURE);
+    }
+
+    if report.differences.is_empty() {
+        println!("{}", style("no differences").green());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn client(
+    address: SocketAddr,
+    timeout: Duration,
+) -> Result<AsyncClient, Box<dyn std::error::Error>> {
+    let (stream, sender) =
+        TcpClientStream::<AsyncIoTokioAsStd<TokioTcpStream>>::with_timeout(address, timeout);
+    let (client, bg) = AsyncClient::new(stream, sender, None).await?;
+    tokio::spawn(bg);
+    Ok(client)
+}